@@ -0,0 +1,292 @@
+use anyhow::{Context, bail};
+use serde_json::Value;
+
+/// Usage of the CLI, printed for `help` and invalid arguments.
+pub const USAGE: &str = "\
+Usage: luminair [--url <url> | --direct] <command>
+
+Commands:
+  types                                        list the document types
+  find <type> [--status <s>] [--query <q>]     list documents, q as in the REST API
+  get <type> <id> [--status <s>]               read one document
+  create <type> (--data <json> | --file <f>)   create a draft
+  update <type> <id> (--data <json> | --file <f>)
+                                               update the draft
+  publish <type> <id>                          publish the draft
+  export <type> [--status <s>] [--output <f>]  write the documents as NDJSON
+  import <type> <f> [--publish]                create the documents of an NDJSON export
+  seed --fake <n> [--publish]                  create n fake documents per collection type
+
+Against the instance at --url, by default http://localhost:<server_port>,
+or with --direct against the database of the settings.";
+
+/// Where the CLI sends its requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// the REST API of a running instance, at this url
+    Remote(Option<String>),
+    /// the database of the settings, through the REST API run in-process
+    Direct,
+}
+
+/// Parsed arguments of the CLI.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Args {
+    pub target: Target,
+    pub command: Command,
+}
+
+/// Operation the CLI carries out.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Types,
+    Find {
+        api_type: String,
+        status: Option<String>,
+        query: Option<String>,
+    },
+    Get {
+        api_type: String,
+        document_id: String,
+        status: Option<String>,
+    },
+    Create {
+        api_type: String,
+        data: Value,
+    },
+    Update {
+        api_type: String,
+        document_id: String,
+        data: Value,
+    },
+    Publish {
+        api_type: String,
+        document_id: String,
+    },
+    Export {
+        api_type: String,
+        status: Option<String>,
+        output: Option<String>,
+    },
+    Import {
+        api_type: String,
+        input: String,
+        publish: bool,
+    },
+    Seed {
+        fake: usize,
+        publish: bool,
+    },
+    Help,
+}
+
+impl Args {
+    /// Parse the arguments following the name of the binary.
+    pub fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let mut options = Options::parse(args)?;
+        let target = if options.flag("--direct") {
+            if options.values.iter().any(|(name, _)| name == "--url") {
+                bail!("--url and --direct exclude each other");
+            }
+            Target::Direct
+        } else {
+            Target::Remote(options.value("--url"))
+        };
+
+        let mut positional = std::mem::take(&mut options.positional).into_iter();
+        let command = match positional.next().as_deref() {
+            None | Some("help") => Command::Help,
+            Some("types") => Command::Types,
+            Some("find") => Command::Find {
+                api_type: required(positional.next(), "type")?,
+                status: options.value("--status"),
+                query: options.value("--query"),
+            },
+            Some("get") => Command::Get {
+                api_type: required(positional.next(), "type")?,
+                document_id: required(positional.next(), "id")?,
+                status: options.value("--status"),
+            },
+            Some("create") => Command::Create {
+                api_type: required(positional.next(), "type")?,
+                data: data(&mut options)?,
+            },
+            Some("update") => Command::Update {
+                api_type: required(positional.next(), "type")?,
+                document_id: required(positional.next(), "id")?,
+                data: data(&mut options)?,
+            },
+            Some("publish") => Command::Publish {
+                api_type: required(positional.next(), "type")?,
+                document_id: required(positional.next(), "id")?,
+            },
+            Some("export") => Command::Export {
+                api_type: required(positional.next(), "type")?,
+                status: options.value("--status"),
+                output: options.value("--output"),
+            },
+            Some("import") => Command::Import {
+                api_type: required(positional.next(), "type")?,
+                input: required(positional.next(), "file")?,
+                publish: options.flag("--publish"),
+            },
+            Some("seed") => Command::Seed {
+                fake: count(options.value("--fake"))?,
+                publish: options.flag("--publish"),
+            },
+            Some(command) => bail!("unknown command '{command}'"),
+        };
+
+        if let Some(extra) = positional.next() {
+            bail!("unexpected argument '{extra}'");
+        }
+        if let Some((name, _)) = options.values.first() {
+            bail!("unexpected option '{name}'");
+        }
+        if let Some(name) = options.flags.first() {
+            bail!("unexpected option '{name}'");
+        }
+        Ok(Self { target, command })
+    }
+}
+
+/// Options without a value.
+const FLAGS: [&str; 2] = ["--direct", "--publish"];
+
+/// Arguments split into positional ones, flags and options with a value,
+/// which are taken out as the command reads them.
+struct Options {
+    positional: Vec<String>,
+    flags: Vec<String>,
+    values: Vec<(String, String)>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let mut options = Self {
+            positional: Vec::new(),
+            flags: Vec::new(),
+            values: Vec::new(),
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if FLAGS.contains(&arg.as_str()) {
+                options.flags.push(arg.clone());
+            } else if arg.starts_with("--") {
+                let value = args
+                    .next()
+                    .with_context(|| format!("option '{arg}' needs a value"))?;
+                options.values.push((arg.clone(), value.clone()));
+            } else {
+                options.positional.push(arg.clone());
+            }
+        }
+        Ok(options)
+    }
+
+    fn flag(&mut self, name: &str) -> bool {
+        let before = self.flags.len();
+        self.flags.retain(|flag| flag != name);
+        self.flags.len() != before
+    }
+
+    fn value(&mut self, name: &str) -> Option<String> {
+        let index = self.values.iter().position(|(option, _)| option == name)?;
+        Some(self.values.remove(index).1)
+    }
+}
+
+fn required(arg: Option<String>, name: &str) -> anyhow::Result<String> {
+    arg.with_context(|| format!("missing <{name}> argument"))
+}
+
+/// The number of fake documents of `seed`, given by `--fake`.
+fn count(value: Option<String>) -> anyhow::Result<usize> {
+    let value = value.context("give the number of fake documents with --fake")?;
+    value
+        .parse()
+        .with_context(|| format!("--fake needs a number, not '{value}'"))
+}
+
+/// The `data` of a create or update, from `--data` or the JSON file of `--file`.
+fn data(options: &mut Options) -> anyhow::Result<Value> {
+    let json = match (options.value("--data"), options.value("--file")) {
+        (Some(json), None) => json,
+        (None, Some(file)) => std::fs::read_to_string(&file)
+            .with_context(|| format!("failed to read data from '{file}'"))?,
+        _ => bail!("give the data with either --data or --file"),
+    };
+    serde_json::from_str(&json).context("data must be a JSON object")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> anyhow::Result<Args> {
+        let args = args
+            .split_whitespace()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        Args::parse(&args)
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let args = parse("find brands --status draft").unwrap();
+        assert_eq!(args.target, Target::Remote(None));
+        assert_eq!(
+            args.command,
+            Command::Find {
+                api_type: "brands".to_string(),
+                status: Some("draft".to_string()),
+                query: None,
+            }
+        );
+
+        let args = parse(r#"--direct create brands --data {"uid":"b-1"}"#).unwrap();
+        assert_eq!(args.target, Target::Direct);
+        assert_eq!(
+            args.command,
+            Command::Create {
+                api_type: "brands".to_string(),
+                data: serde_json::json!({"uid": "b-1"}),
+            }
+        );
+
+        let args = parse("import brands brands.ndjson --publish --url http://cms:8080").unwrap();
+        assert_eq!(
+            args.target,
+            Target::Remote(Some("http://cms:8080".to_string()))
+        );
+        assert_eq!(
+            args.command,
+            Command::Import {
+                api_type: "brands".to_string(),
+                input: "brands.ndjson".to_string(),
+                publish: true,
+            }
+        );
+        assert_eq!(
+            parse("seed --fake 25 --publish").unwrap().command,
+            Command::Seed {
+                fake: 25,
+                publish: true,
+            }
+        );
+        assert_eq!(parse("").unwrap().command, Command::Help);
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_arguments() {
+        assert!(parse("get brands").is_err());
+        assert!(parse("publish brands id extra").is_err());
+        assert!(parse("types --status draft").is_err());
+        assert!(parse("find brands --status").is_err());
+        assert!(parse("create brands").is_err());
+        assert!(parse("--direct --url http://cms types").is_err());
+        assert!(parse("remove brands").is_err());
+        assert!(parse("seed").is_err());
+        assert!(parse("seed --fake many").is_err());
+    }
+}
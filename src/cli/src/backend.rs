@@ -0,0 +1,187 @@
+use anyhow::Context;
+use axum::Router;
+use axum::body::{Body, Bytes};
+use axum::http::{HeaderMap, Method, Request, StatusCode, header};
+use luminair_common::{DocumentTypesRegistry, database};
+use serde_json::Value;
+use service::infrastructure::AppStateImpl;
+use service::infrastructure::http::routes::api_routes;
+use service::infrastructure::persistence::repository::PostgresDocumentsRepository;
+use service::infrastructure::settings::Settings;
+use tower::ServiceExt;
+
+/// The REST API the commands of the CLI are carried out with: the one of a
+/// running instance, or the one of the service run in-process against the
+/// database, so both validate alike.
+pub enum Backend {
+    Remote {
+        client: reqwest::Client,
+        base_url: String,
+    },
+    Direct {
+        router: Router,
+    },
+}
+
+/// Request to the REST API, by its path under `/api`.
+pub struct ApiRequest {
+    pub method: Method,
+    pub path: String,
+    pub accept: Option<&'static str>,
+    pub body: Option<Value>,
+}
+
+/// Response of the REST API.
+pub struct ApiResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl Backend {
+    /// The REST API of the instance at `base_url`.
+    pub fn remote(base_url: &str) -> Self {
+        Self::Remote {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// The REST API run in-process against the database of `settings`.
+    ///
+    /// Writes are recorded in the outbox when `events.enabled` is set, for the
+    /// running instances to publish.
+    pub async fn direct(
+        settings: &Settings,
+        registry: &'static dyn DocumentTypesRegistry,
+    ) -> anyhow::Result<Self> {
+        let database = database::connect(&settings.database).await?;
+        let mut repository = PostgresDocumentsRepository::new(registry, database);
+        if settings.events.enabled {
+            repository = repository.with_outbox();
+        }
+        let state = AppStateImpl::builder(registry, repository)
+            .pagination_settings(settings.pagination)
+            .response_format(settings.response_format)
+            .build();
+        let router = Router::new().nest("/api", api_routes()).with_state(state);
+        Ok(Self::Direct { router })
+    }
+
+    /// Send `request` and read the whole response.
+    pub async fn send(&self, request: ApiRequest) -> anyhow::Result<ApiResponse> {
+        match self {
+            Backend::Remote { client, base_url } => {
+                let mut builder =
+                    client.request(request.method, format!("{}/api{}", base_url, request.path));
+                if let Some(accept) = request.accept {
+                    builder = builder.header(header::ACCEPT, accept);
+                }
+                if let Some(body) = &request.body {
+                    builder = builder.json(body);
+                }
+                let response = builder
+                    .send()
+                    .await
+                    .with_context(|| format!("failed to reach {base_url}"))?;
+                Ok(ApiResponse {
+                    status: response.status(),
+                    headers: response.headers().clone(),
+                    body: response.bytes().await?,
+                })
+            }
+            Backend::Direct { router } => {
+                let mut builder = Request::builder()
+                    .method(request.method)
+                    .uri(format!("/api{}", request.path));
+                if let Some(accept) = request.accept {
+                    builder = builder.header(header::ACCEPT, accept);
+                }
+                let body = match &request.body {
+                    Some(body) => {
+                        builder = builder.header(header::CONTENT_TYPE, "application/json");
+                        Body::from(serde_json::to_vec(body)?)
+                    }
+                    None => Body::empty(),
+                };
+                let response = router.clone().oneshot(builder.body(body)?).await?;
+                let status = response.status();
+                let headers = response.headers().clone();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+                Ok(ApiResponse {
+                    status,
+                    headers,
+                    body,
+                })
+            }
+        }
+    }
+}
+
+impl ApiRequest {
+    /// `GET` request of `path`.
+    pub fn get(path: String) -> Self {
+        Self {
+            method: Method::GET,
+            path,
+            accept: None,
+            body: None,
+        }
+    }
+
+    /// `POST` request of `path`, with a JSON `body` if any.
+    pub fn post(path: String, body: Option<Value>) -> Self {
+        Self {
+            method: Method::POST,
+            path,
+            accept: None,
+            body,
+        }
+    }
+
+    /// `PUT` request of `path`, with a JSON `body`.
+    pub fn put(path: String, body: Value) -> Self {
+        Self {
+            method: Method::PUT,
+            path,
+            accept: None,
+            body: Some(body),
+        }
+    }
+
+    /// Ask for the media type `accept`.
+    pub fn accept(mut self, accept: &'static str) -> Self {
+        self.accept = Some(accept);
+        self
+    }
+}
+
+impl ApiResponse {
+    /// The response of a successful request, or an error with the `detail`
+    /// of the problem the API answered with.
+    pub fn success(self) -> anyhow::Result<Self> {
+        if self.status.is_success() {
+            return Ok(self);
+        }
+        let detail = serde_json::from_slice::<Value>(&self.body)
+            .ok()
+            .and_then(|problem| problem["detail"].as_str().map(String::from))
+            .unwrap_or_else(|| String::from_utf8_lossy(&self.body).into_owned());
+        anyhow::bail!("{}: {}", self.status, detail)
+    }
+
+    /// The body as JSON.
+    pub fn json(&self) -> anyhow::Result<Value> {
+        serde_json::from_slice(&self.body).context("the API answered with invalid JSON")
+    }
+
+    /// Id of the document a create answered with in its `Location`.
+    pub fn created_id(&self) -> anyhow::Result<String> {
+        self.headers
+            .get(header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| location.rsplit('/').next())
+            .map(String::from)
+            .context("the API answered a create without a Location")
+    }
+}
@@ -0,0 +1,85 @@
+use std::process::ExitCode;
+
+use anyhow::bail;
+use luminair_common::schema_lint::{self, Severity};
+
+/// Usage of the schema tool, printed for `help` and invalid arguments.
+const USAGE: &str = "\
+Usage: luminair-schema lint <dir> [--format text|json]
+
+Validates the schema files of <dir> without a database and prints a diagnostic
+per problem, one per line: as `file[:line:column]: severity[code]: message`, or
+as a JSON object with --format json. Exits with 1 when any error is found.";
+
+/// Format of the printed diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
+}
+
+fn main() -> anyhow::Result<ExitCode> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (dir, format) = match parse(&args) {
+        Ok(Some(parsed)) => parsed,
+        Ok(None) => {
+            println!("{USAGE}");
+            return Ok(ExitCode::SUCCESS);
+        }
+        Err(e) => {
+            eprintln!("{USAGE}");
+            return Err(e);
+        }
+    };
+
+    let diagnostics = schema_lint::lint(&dir)?;
+    for diagnostic in &diagnostics {
+        match format {
+            Format::Text => println!("{diagnostic}"),
+            Format::Json => println!("{}", serde_json::to_string(diagnostic)?),
+        }
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    eprintln!("{errors} error(s), {warnings} warning(s)");
+    Ok(if errors > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+/// The directory and format of `lint`, or none for `help`.
+fn parse(args: &[String]) -> anyhow::Result<Option<(String, Format)>> {
+    let mut args = args.iter().map(String::as_str);
+    match args.next() {
+        None | Some("help") => return Ok(None),
+        Some("lint") => {}
+        Some(command) => bail!("unknown command '{command}'"),
+    }
+
+    let mut dir = None;
+    let mut format = Format::Text;
+    while let Some(arg) = args.next() {
+        match arg {
+            "--format" => {
+                format = match args.next() {
+                    Some("text") => Format::Text,
+                    Some("json") => Format::Json,
+                    _ => bail!("--format needs 'text' or 'json'"),
+                }
+            }
+            _ if arg.starts_with("--") => bail!("unexpected option '{arg}'"),
+            _ if dir.is_none() => dir = Some(arg.to_string()),
+            _ => bail!("unexpected argument '{arg}'"),
+        }
+    }
+    let Some(dir) = dir else {
+        bail!("missing <dir> argument");
+    };
+    Ok(Some((dir, format)))
+}
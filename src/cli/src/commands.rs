@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::str::FromStr;
+
+use anyhow::Context;
+use luminair_common::{DocumentType, DocumentTypeApiId, DocumentTypesRegistry};
+use serde_json::{Map, Value, json};
+
+use crate::args::{Command, USAGE};
+use crate::backend::{ApiRequest, Backend};
+use crate::fake::{self, Faker};
+
+/// Media type of newline-delimited JSON exports.
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Carry out `command` with `backend`, writing its output to `out`.
+pub async fn run(
+    command: Command,
+    backend: &Backend,
+    registry: &dyn DocumentTypesRegistry,
+    out: &mut impl Write,
+) -> anyhow::Result<()> {
+    match command {
+        Command::Help => writeln!(out, "{USAGE}")?,
+        Command::Types => {
+            let mut document_types = registry.iterate().collect::<Vec<_>>();
+            document_types
+                .sort_by(|a, b| a.info.plural_name.as_ref().cmp(b.info.plural_name.as_ref()));
+            for document_type in document_types {
+                let kind = serde_json::to_value(document_type.kind)?;
+                writeln!(
+                    out,
+                    "{}\t{}\t{}",
+                    document_type.info.plural_name.as_ref(),
+                    kind.as_str().unwrap_or_default(),
+                    document_type.info.title.as_ref()
+                )?;
+            }
+        }
+        Command::Find {
+            api_type,
+            status,
+            query,
+        } => {
+            let path = with_query(format!("/documents/{api_type}"), query, status);
+            let response = backend.send(ApiRequest::get(path)).await?.success()?;
+            writeln!(out, "{:#}", response.json()?)?;
+        }
+        Command::Get {
+            api_type,
+            document_id,
+            status,
+        } => {
+            let path = with_query(format!("/documents/{api_type}/{document_id}"), None, status);
+            let response = backend.send(ApiRequest::get(path)).await?.success()?;
+            writeln!(out, "{:#}", response.json()?)?;
+        }
+        Command::Create { api_type, data } => {
+            let request = ApiRequest::post(
+                format!("/documents/{api_type}"),
+                Some(json!({ "data": data })),
+            );
+            let response = backend.send(request).await?.success()?;
+            writeln!(out, "{}", response.created_id()?)?;
+        }
+        Command::Update {
+            api_type,
+            document_id,
+            data,
+        } => {
+            let request = ApiRequest::put(
+                format!("/documents/{api_type}/{document_id}"),
+                json!({ "data": data }),
+            );
+            backend.send(request).await?.success()?;
+        }
+        Command::Publish {
+            api_type,
+            document_id,
+        } => {
+            let request =
+                ApiRequest::post(format!("/documents/{api_type}/{document_id}/publish"), None);
+            backend.send(request).await?.success()?;
+        }
+        Command::Export {
+            api_type,
+            status,
+            output,
+        } => {
+            let path = with_query(format!("/documents/{api_type}"), None, status);
+            let request = ApiRequest::get(path).accept(NDJSON_CONTENT_TYPE);
+            let response = backend.send(request).await?.success()?;
+            match output {
+                Some(output) => std::fs::write(&output, &response.body)
+                    .with_context(|| format!("failed to write the export to '{output}'"))?,
+                None => out.write_all(&response.body)?,
+            }
+        }
+        Command::Import {
+            api_type,
+            input,
+            publish,
+        } => {
+            let api_id = DocumentTypeApiId::from_str(&api_type)
+                .map_err(|_| anyhow::anyhow!("invalid document type '{api_type}'"))?;
+            let document_type = registry
+                .lookup(&api_id)
+                .with_context(|| format!("document type '{api_type}' not found"))?;
+            let lines = std::fs::read_to_string(&input)
+                .with_context(|| format!("failed to read '{input}'"))?;
+
+            for (number, line) in lines.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let document = serde_json::from_str::<Value>(line)
+                    .with_context(|| format!("line {} of '{input}' is not JSON", number + 1))?;
+                let data = import_data(document_type, &document);
+                let request = ApiRequest::post(
+                    format!("/documents/{api_type}"),
+                    Some(json!({ "data": data })),
+                );
+                let document_id = backend
+                    .send(request)
+                    .await?
+                    .success()
+                    .with_context(|| format!("failed to import line {}", number + 1))?
+                    .created_id()?;
+                if publish {
+                    let request = ApiRequest::post(
+                        format!("/documents/{api_type}/{document_id}/publish"),
+                        None,
+                    );
+                    backend.send(request).await?.success()?;
+                }
+                writeln!(out, "{document_id}")?;
+            }
+        }
+        Command::Seed { fake, publish } => {
+            let mut faker = Faker::from_clock();
+            let mut seeded = HashMap::new();
+            for document_type in fake::seeding_order(registry) {
+                let api_type = document_type.info.plural_name.as_ref();
+                let publish = publish
+                    && document_type
+                        .options
+                        .as_ref()
+                        .is_some_and(|options| options.draft_and_publish);
+
+                let mut document_ids = Vec::with_capacity(fake);
+                for _ in 0..fake {
+                    let mut data = faker.document(document_type)?;
+                    data.extend(faker.relations(document_type, &seeded));
+                    let request = ApiRequest::post(
+                        format!("/documents/{api_type}"),
+                        Some(json!({ "data": data })),
+                    );
+                    let document_id = backend
+                        .send(request)
+                        .await?
+                        .success()
+                        .with_context(|| format!("failed to seed a document of '{api_type}'"))?
+                        .created_id()?;
+                    if publish {
+                        let request = ApiRequest::post(
+                            format!("/documents/{api_type}/{document_id}/publish"),
+                            None,
+                        );
+                        backend.send(request).await?.success()?;
+                    }
+                    document_ids.push(document_id);
+                }
+                writeln!(out, "{api_type}\t{}", document_ids.len())?;
+                seeded.insert(document_type.id.clone(), document_ids);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The `data` of a create from a document of an export: its fields, by
+/// attribute id; system fields like `documentId` are left out.
+pub fn import_data(document_type: &DocumentType, document: &Value) -> Map<String, Value> {
+    document_type
+        .fields
+        .iter()
+        .filter_map(|field| {
+            let id = field.id.as_ref();
+            let value = document.get(api_key(id))?;
+            Some((id.to_string(), value.clone()))
+        })
+        .collect()
+}
+
+/// `path` with the query string `query` and the `status` parameter.
+fn with_query(path: String, query: Option<String>, status: Option<String>) -> String {
+    let mut parameters = query.into_iter().collect::<Vec<_>>();
+    parameters.extend(status.map(|status| format!("status={status}")));
+    if parameters.is_empty() {
+        path
+    } else {
+        format!("{}?{}", path, parameters.join("&"))
+    }
+}
+
+/// Key of an attribute in the documents of the API: "legal_entity" → "legalEntity"
+fn api_key(id: &str) -> String {
+    let mut key = String::with_capacity(id.len());
+    let mut next_upper = false;
+    for c in id.chars() {
+        if c == '_' {
+            next_upper = true;
+        } else if next_upper {
+            key.extend(c.to_uppercase());
+            next_upper = false;
+        } else {
+            key.push(c);
+        }
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_query() {
+        assert_eq!(
+            with_query("/documents/brands".into(), None, None),
+            "/documents/brands"
+        );
+        assert_eq!(
+            with_query(
+                "/documents/brands".into(),
+                Some("filters[uid][$eq]=b-1&sort=uid:asc".into()),
+                Some("draft".into())
+            ),
+            "/documents/brands?filters[uid][$eq]=b-1&sort=uid:asc&status=draft"
+        );
+    }
+
+    #[test]
+    fn test_api_key() {
+        assert_eq!(api_key("legal_entity"), "legalEntity");
+        assert_eq!(api_key("points-of-sale"), "points-of-sale");
+        assert_eq!(api_key("uid"), "uid");
+    }
+}
@@ -0,0 +1,660 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, bail};
+use luminair_common::entities::{
+    DocumentField, DocumentKind, DocumentType, FieldConstraint, FieldType, IntegerSize,
+    RelationType,
+};
+use luminair_common::{AttributeId, DocumentTypeId, DocumentTypesRegistry};
+use regex::Regex;
+use serde_json::{Map, Value, json};
+
+/// Generator of fake documents, whose field values honour the type and the
+/// constraints of their field, for demos and load testing.
+///
+/// Values of unique fields are never given twice by the same generator.
+pub struct Faker {
+    state: u64,
+    used: HashSet<(DocumentTypeId, AttributeId, String)>,
+}
+
+impl Faker {
+    /// Generator of the values following from `seed`: the same seed gives
+    /// the same values.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed,
+            used: HashSet::new(),
+        }
+    }
+
+    /// Generator seeded by the clock, giving other values on every run.
+    pub fn from_clock() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default();
+        Self::new(nanos)
+    }
+
+    /// The `data` of a create of a fake document of `document_type`: a value
+    /// of each of its fields, by attribute id.
+    pub fn document(&mut self, document_type: &DocumentType) -> anyhow::Result<Map<String, Value>> {
+        let mut fields = document_type.fields.iter().collect::<Vec<_>>();
+        fields.sort_by(|a, b| a.id.as_ref().cmp(b.id.as_ref()));
+
+        let mut data = Map::new();
+        for field in fields {
+            let value = self.value(document_type, field).with_context(|| {
+                format!(
+                    "failed to generate a value of '{}' of '{}' matching its constraints",
+                    field.id.as_ref(),
+                    document_type.id.as_ref()
+                )
+            })?;
+            data.insert(field.id.as_ref().to_string(), value);
+        }
+        Ok(data)
+    }
+
+    /// The relation operations of a create of a fake document of
+    /// `document_type`, connecting each owning relation to documents of
+    /// `seeded`, the ids of the documents created so far by document type.
+    ///
+    /// Relations to document types without documents are left out.
+    pub fn relations(
+        &mut self,
+        document_type: &DocumentType,
+        seeded: &HashMap<DocumentTypeId, Vec<String>>,
+    ) -> Map<String, Value> {
+        let mut relations = document_type
+            .relations
+            .iter()
+            .filter(|relation| relation.relation_type.is_owning())
+            .collect::<Vec<_>>();
+        relations.sort_by(|a, b| a.id.as_ref().cmp(b.id.as_ref()));
+
+        let mut data = Map::new();
+        for relation in relations {
+            let Some(targets) = seeded.get(&relation.target).filter(|ids| !ids.is_empty()) else {
+                continue;
+            };
+            let count = match relation.relation_type {
+                RelationType::HasMany => 1 + self.below(targets.len().min(3) as u64) as usize,
+                _ => 1,
+            };
+            let mut ids = targets.iter().collect::<Vec<_>>();
+            let mut connect = Vec::with_capacity(count);
+            for _ in 0..count {
+                let index = self.below(ids.len() as u64) as usize;
+                connect.push(ids.swap_remove(index).clone());
+            }
+            data.insert(
+                relation.id.as_ref().to_string(),
+                json!({ "connect": connect }),
+            );
+        }
+        data
+    }
+
+    fn value(
+        &mut self,
+        document_type: &DocumentType,
+        field: &DocumentField,
+    ) -> anyhow::Result<Value> {
+        for _ in 0..ATTEMPTS {
+            let value = match field.field_type {
+                FieldType::Uid | FieldType::Text => {
+                    return self.text(document_type, field).map(Value::from);
+                }
+                FieldType::LocalizedText => {
+                    let locales = document_type
+                        .options
+                        .iter()
+                        .flat_map(|options| options.localizations.iter())
+                        .map(|locale| locale.as_ref().to_string())
+                        .collect::<Vec<_>>();
+                    let locales = if locales.is_empty() {
+                        vec![DEFAULT_LOCALE.to_string()]
+                    } else {
+                        locales
+                    };
+                    let mut texts = Map::new();
+                    for locale in locales {
+                        texts.insert(locale, self.text(document_type, field)?.into());
+                    }
+                    Value::Object(texts)
+                }
+                FieldType::Integer(size) => self.integer(field, size).into(),
+                FieldType::Decimal { precision, scale } => self.decimal(precision, scale).into(),
+                FieldType::Date => self.date().into(),
+                FieldType::DateTime => format!("{}T{}Z", self.date(), self.time()).into(),
+                FieldType::LocalDateTime => format!("{}T{}", self.date(), self.time()).into(),
+                FieldType::Time => self.time().into(),
+                FieldType::Boolean => (self.below(2) == 1).into(),
+                FieldType::Uuid => self.uuid().into(),
+                FieldType::Json => json!({
+                    "color": self.pick(COLORS),
+                    "rank": self.below(100),
+                }),
+            };
+            if self.unused(document_type, field, &value.to_string()) {
+                return Ok(value);
+            }
+        }
+        bail!("no unused value left after {ATTEMPTS} attempts")
+    }
+
+    /// A text of `field`: first of the shape its name suggests, then of
+    /// every shape and length its constraints allow, until one matches them.
+    fn text(
+        &mut self,
+        document_type: &DocumentType,
+        field: &DocumentField,
+    ) -> anyhow::Result<String> {
+        let mut pattern = None;
+        let mut min = 1;
+        let mut max = MAX_TEXT_LENGTH;
+        for constraint in &field.constraints {
+            match constraint {
+                FieldConstraint::Pattern(regex) => {
+                    pattern = Some(
+                        Regex::new(regex).with_context(|| format!("invalid pattern '{regex}'"))?,
+                    )
+                }
+                FieldConstraint::MinimalLength(length) => min = *length,
+                FieldConstraint::MaximalLength(length) => max = *length,
+                _ => {}
+            }
+        }
+        let max = max.max(min);
+        let preferred = Shape::of(field);
+
+        let natural = (0..ATTEMPTS).map(|_| (preferred, None));
+        let exhaustive = Shape::ALL.into_iter().flat_map(|shape| {
+            let lengths: Box<dyn Iterator<Item = Option<usize>>> = match shape {
+                Shape::Email | Shape::Url => Box::new(std::iter::once(None)),
+                _ => Box::new((min..=max.min(min + MAX_TEXT_LENGTH)).map(Some)),
+            };
+            lengths.map(move |length| (shape, length))
+        });
+        for (shape, length) in natural.chain(exhaustive) {
+            let text = match length {
+                Some(length) => self.shaped(shape, length),
+                None => self.natural(shape),
+            };
+            let length = text.chars().count();
+            if length < min || length > max {
+                continue;
+            }
+            if pattern
+                .as_ref()
+                .is_some_and(|pattern| !pattern.is_match(&text))
+            {
+                continue;
+            }
+            if self.unused(document_type, field, &text) {
+                return Ok(text);
+            }
+        }
+        bail!("no text of its length and pattern found")
+    }
+
+    /// Whether `value` can be given to `field`, noting it as given when the
+    /// field is unique.
+    fn unused(&mut self, document_type: &DocumentType, field: &DocumentField, value: &str) -> bool {
+        !field.unique
+            || self.used.insert((
+                document_type.id.clone(),
+                field.id.clone(),
+                value.to_string(),
+            ))
+    }
+
+    /// A text of `shape` as people would write it.
+    fn natural(&mut self, shape: Shape) -> String {
+        match shape {
+            Shape::Words => {
+                let count = 2 + self.below(2) as usize;
+                capitalize(&self.words(count, " "))
+            }
+            Shape::Sentence => {
+                let count = 6 + self.below(7) as usize;
+                format!("{}.", capitalize(&self.words(count, " ")))
+            }
+            Shape::Slug => {
+                let count = 2 + self.below(2) as usize;
+                format!("{}-{}", self.words(count, "-"), self.below(1000))
+            }
+            Shape::Email => format!(
+                "{}.{}{}@example.com",
+                self.pick(FIRST_NAMES),
+                self.pick(LAST_NAMES),
+                self.below(100)
+            ),
+            Shape::Url => format!("https://www.example.com/{}", self.words(2, "-")),
+            Shape::Digits => self.shaped(shape, 10),
+            Shape::Lowercase | Shape::Uppercase => {
+                let length = 3 + self.below(6) as usize;
+                self.shaped(shape, length)
+            }
+            Shape::Alphanumeric => self.shaped(shape, 8),
+        }
+    }
+
+    /// A text of `shape` of exactly `length` characters.
+    fn shaped(&mut self, shape: Shape, length: usize) -> String {
+        let from = |chars: &str, faker: &mut Self| {
+            (0..length)
+                .map(|_| {
+                    let index = faker.below(chars.len() as u64) as usize;
+                    chars.as_bytes()[index] as char
+                })
+                .collect::<String>()
+        };
+        match shape {
+            Shape::Digits => from("0123456789", self),
+            Shape::Lowercase => from("abcdefghijklmnopqrstuvwxyz", self),
+            Shape::Uppercase => from("ABCDEFGHIJKLMNOPQRSTUVWXYZ", self),
+            Shape::Alphanumeric => from("abcdefghijklmnopqrstuvwxyz0123456789", self),
+            Shape::Words | Shape::Sentence | Shape::Slug => {
+                let separator = if shape == Shape::Slug { "-" } else { " " };
+                let mut text = String::new();
+                while text.len() < length {
+                    if !text.is_empty() {
+                        text.push_str(separator);
+                    }
+                    text.push_str(self.pick(WORDS));
+                }
+                text.truncate(length);
+                if text.ends_with(separator) {
+                    text.pop();
+                    text.push('s');
+                }
+                if shape == Shape::Slug {
+                    text
+                } else {
+                    capitalize(&text)
+                }
+            }
+            Shape::Email | Shape::Url => self.natural(shape),
+        }
+    }
+
+    fn words(&mut self, count: usize, separator: &str) -> String {
+        (0..count)
+            .map(|_| self.pick(WORDS))
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    /// An integer within the bounds of the constraints of `field`, else
+    /// within a small positive range.
+    fn integer(&mut self, field: &DocumentField, size: IntegerSize) -> i64 {
+        let mut min = None;
+        let mut max = None;
+        for constraint in &field.constraints {
+            match constraint {
+                FieldConstraint::MinimalIntegerValue(value) => min = Some(i64::from(*value)),
+                FieldConstraint::MaximalIntegerValue(value) => max = Some(i64::from(*value)),
+                _ => {}
+            }
+        }
+        let limit = match size {
+            IntegerSize::Int16 => i64::from(i16::MAX),
+            IntegerSize::Int32 | IntegerSize::Int64 => i64::from(i32::MAX),
+        };
+        let (min, max) = match (min, max) {
+            (Some(min), Some(max)) => (min, max.max(min)),
+            (Some(min), None) => (min, (min + INTEGER_RANGE).min(limit).max(min)),
+            (None, Some(max)) => ((max - INTEGER_RANGE).max(0).min(max), max),
+            (None, None) => (0, INTEGER_RANGE),
+        };
+        min + self.below((max - min) as u64 + 1) as i64
+    }
+
+    /// A decimal of `scale` fractional digits fitting in `precision` digits,
+    /// as a string to keep its precision.
+    fn decimal(&mut self, precision: usize, scale: u32) -> String {
+        let scale = scale as usize;
+        let whole_digits = precision.saturating_sub(scale).min(4) as u32;
+        let whole = self.below(10u64.pow(whole_digits));
+        if scale == 0 {
+            return whole.to_string();
+        }
+        let fraction = (0..scale)
+            .map(|_| char::from(b'0' + self.below(10) as u8))
+            .collect::<String>();
+        format!("{whole}.{fraction}")
+    }
+
+    fn date(&mut self) -> String {
+        format!(
+            "{}-{:02}-{:02}",
+            2020 + self.below(6),
+            1 + self.below(12),
+            1 + self.below(28)
+        )
+    }
+
+    fn time(&mut self) -> String {
+        format!(
+            "{:02}:{:02}:{:02}",
+            self.below(24),
+            self.below(60),
+            self.below(60)
+        )
+    }
+
+    /// A random (version 4) UUID.
+    fn uuid(&mut self) -> String {
+        let high = (self.next_u64() & 0xffff_ffff_ffff_0fff) | 0x4000;
+        let low = (self.next_u64() & 0x3fff_ffff_ffff_ffff) | 0x8000_0000_0000_0000;
+        format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            high >> 32,
+            (high >> 16) & 0xffff,
+            high & 0xffff,
+            low >> 48,
+            low & 0xffff_ffff_ffff
+        )
+    }
+
+    fn pick(&mut self, values: &[&'static str]) -> &'static str {
+        values[self.below(values.len() as u64) as usize]
+    }
+
+    /// A number below `bound`, which is positive.
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// Next number of the SplitMix64 sequence.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// The collection types of `registry` in the order to seed them: the targets
+/// of the owning relations of a type before the type, so that its documents
+/// can be connected to them. Types of a relation cycle come in the order of
+/// their plural names.
+pub fn seeding_order(registry: &dyn DocumentTypesRegistry) -> Vec<&DocumentType> {
+    let mut pending = registry
+        .iterate()
+        .filter(|document_type| document_type.kind == DocumentKind::Collection)
+        .collect::<Vec<_>>();
+    pending.sort_by(|a, b| a.info.plural_name.as_ref().cmp(b.info.plural_name.as_ref()));
+
+    let mut order = Vec::with_capacity(pending.len());
+    while !pending.is_empty() {
+        let ready = pending
+            .iter()
+            .position(|document_type| {
+                document_type.relations.iter().all(|relation| {
+                    !relation.relation_type.is_owning()
+                        || relation.target == document_type.id
+                        || pending.iter().all(|other| other.id != relation.target)
+                })
+            })
+            .unwrap_or(0);
+        order.push(pending.remove(ready));
+    }
+    order
+}
+
+/// Attempts at generating a value before giving up on a field.
+const ATTEMPTS: usize = 20;
+
+/// Maximal length of texts without a maximal length constraint.
+const MAX_TEXT_LENGTH: usize = 60;
+
+/// Width of the range of integers with at most one bound.
+const INTEGER_RANGE: i64 = 1000;
+
+/// Locale of the localized texts of document types without localizations.
+const DEFAULT_LOCALE: &str = "en";
+
+const WORDS: &[&str] = &[
+    "amber", "atlas", "bright", "cedar", "coastal", "copper", "crest", "delta", "ember", "field",
+    "forest", "golden", "harbor", "horizon", "iron", "lake", "maple", "meadow", "north", "oak",
+    "orchard", "pine", "prime", "river", "silver", "stone", "summit", "sun", "valley", "willow",
+];
+
+const FIRST_NAMES: &[&str] = &[
+    "ana", "ben", "clara", "david", "elena", "felix", "grace", "ivan", "julia", "leo", "maria",
+    "nina", "oscar", "paul", "sofia",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "brown", "costa", "garcia", "ivanov", "jones", "kim", "lopez", "miller", "novak", "popescu",
+    "rossi", "smith", "wagner",
+];
+
+const COLORS: &[&str] = &["blue", "green", "orange", "purple", "red", "teal", "yellow"];
+
+/// Kinds of fake texts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    Words,
+    Sentence,
+    Slug,
+    Email,
+    Url,
+    Digits,
+    Lowercase,
+    Uppercase,
+    Alphanumeric,
+}
+
+impl Shape {
+    const ALL: [Shape; 9] = [
+        Shape::Words,
+        Shape::Sentence,
+        Shape::Slug,
+        Shape::Email,
+        Shape::Url,
+        Shape::Digits,
+        Shape::Lowercase,
+        Shape::Uppercase,
+        Shape::Alphanumeric,
+    ];
+
+    /// Shape of the texts of `field`, by its type and name.
+    fn of(field: &DocumentField) -> Self {
+        if field.field_type == FieldType::Uid {
+            return Shape::Slug;
+        }
+        let name = field.id.as_ref().to_ascii_lowercase();
+        let has = |parts: &[&str]| parts.iter().any(|part| name.contains(part));
+        if has(&["email", "mail"]) {
+            Shape::Email
+        } else if has(&["url", "website", "link"]) {
+            Shape::Url
+        } else if has(&["slug"]) {
+            Shape::Slug
+        } else if has(&["phone", "number"]) {
+            Shape::Digits
+        } else if has(&["code", "sku"]) {
+            Shape::Alphanumeric
+        } else if has(&["description", "summary", "body", "content", "note"]) {
+            Shape::Sentence
+        } else {
+            Shape::Words
+        }
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use luminair_common::entities::{DocumentRelation, OnDeleteAction};
+
+    use super::*;
+
+    fn field(id: &str, field_type: FieldType, constraints: Vec<FieldConstraint>) -> DocumentField {
+        DocumentField {
+            id: AttributeId::try_new(id).unwrap(),
+            field_type,
+            unique: true,
+            required: true,
+            constraints: constraints.into_iter().collect(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        }
+    }
+
+    fn relation(id: &str, relation_type: RelationType, target: &str) -> DocumentRelation {
+        DocumentRelation {
+            id: AttributeId::try_new(id).unwrap(),
+            relation_type,
+            target: DocumentTypeId::try_new(target).unwrap(),
+            on_delete: OnDeleteAction::default(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_document_honours_constraints() {
+        let mut partner =
+            DocumentType::new_bare_collection("partner", "partner", "partners").unwrap();
+        partner.fields = HashSet::from([
+            field(
+                "idno",
+                FieldType::Text,
+                vec![FieldConstraint::Pattern("^[0-9]{13}$".into())],
+            ),
+            field(
+                "name",
+                FieldType::Text,
+                vec![
+                    FieldConstraint::MinimalLength(4),
+                    FieldConstraint::MaximalLength(10),
+                ],
+            ),
+            // five values can't be unique over fifty documents
+            DocumentField {
+                unique: false,
+                ..field(
+                    "rating",
+                    FieldType::Integer(IntegerSize::Int16),
+                    vec![
+                        FieldConstraint::MinimalIntegerValue(1),
+                        FieldConstraint::MaximalIntegerValue(5),
+                    ],
+                )
+            },
+            field(
+                "price",
+                FieldType::Decimal {
+                    precision: 5,
+                    scale: 2,
+                },
+                vec![],
+            ),
+            field("founded", FieldType::Date, vec![]),
+            field("key", FieldType::Uuid, vec![]),
+        ]);
+
+        let idno = Regex::new("^[0-9]{13}$").unwrap();
+        let mut faker = Faker::new(7);
+        let mut idnos = HashSet::new();
+        for _ in 0..50 {
+            let data = faker.document(&partner).unwrap();
+            let value = data["idno"].as_str().unwrap();
+            assert!(idno.is_match(value), "{value}");
+            assert!(idnos.insert(value.to_string()), "{value} given twice");
+
+            let name = data["name"].as_str().unwrap().chars().count();
+            assert!((4..=10).contains(&name));
+            assert!((1..=5).contains(&data["rating"].as_i64().unwrap()));
+
+            let price = data["price"].as_str().unwrap();
+            let (whole, fraction) = price.split_once('.').unwrap();
+            assert!(whole.len() <= 3 && fraction.len() == 2, "{price}");
+            assert_eq!(data["founded"].as_str().unwrap().len(), "2024-01-31".len());
+            assert_eq!(data["key"].as_str().unwrap().as_bytes()[14], b'4');
+        }
+    }
+
+    #[test]
+    fn test_document_fails_on_unmatchable_pattern() {
+        let mut brand = DocumentType::new_bare_collection("brand", "brand", "brands").unwrap();
+        brand.fields = HashSet::from([field(
+            "uid",
+            FieldType::Uid,
+            vec![FieldConstraint::Pattern("^@@[0-9]$".into())],
+        )]);
+
+        let error = Faker::new(1).document(&brand).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to generate a value of 'uid' of 'brand' matching its constraints"
+        );
+    }
+
+    #[test]
+    fn test_relations_connect_seeded_documents() {
+        let mut partner =
+            DocumentType::new_bare_collection("partner", "partner", "partners").unwrap();
+        partner.relations = HashSet::from([
+            relation("brands", RelationType::HasMany, "brand"),
+            relation("category", RelationType::HasOne, "category"),
+            relation("owner", RelationType::BelongsToOne, "brand"),
+        ]);
+        let brands = vec!["b-1".to_string(), "b-2".to_string()];
+        let seeded = HashMap::from([(DocumentTypeId::try_new("brand").unwrap(), brands.clone())]);
+
+        let data = Faker::new(3).relations(&partner, &seeded);
+        assert_eq!(data.keys().collect::<Vec<_>>(), ["brands"]);
+        let connect = data["brands"]["connect"].as_array().unwrap();
+        assert!(!connect.is_empty() && connect.len() <= 2);
+        assert!(
+            connect
+                .iter()
+                .all(|id| brands.contains(&id.as_str().unwrap().to_string()))
+        );
+    }
+
+    #[test]
+    fn test_seeding_order_puts_targets_first() {
+        let mut partner =
+            DocumentType::new_bare_collection("partner", "partner", "partners").unwrap();
+        partner.relations = HashSet::from([
+            relation("brands", RelationType::HasMany, "brand"),
+            relation("category", RelationType::HasOne, "category"),
+        ]);
+        let mut brand = DocumentType::new_bare_collection("brand", "brand", "brands").unwrap();
+        brand.relations =
+            HashSet::from([relation("partners", RelationType::BelongsToMany, "partner")]);
+        let category =
+            DocumentType::new_bare_collection("category", "category", "categories").unwrap();
+        let registry = luminair_common::InMemoryDocumentTypesRegistry::from_vec(vec![
+            partner, brand, category,
+        ]);
+
+        let order = seeding_order(&registry)
+            .into_iter()
+            .map(|document_type| document_type.id.as_ref().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(order, ["brand", "category", "partner"]);
+    }
+}
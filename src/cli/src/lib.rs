@@ -0,0 +1,7 @@
+//! The `luminair` CLI: content operations against a running instance, or
+//! directly against its database.
+
+pub mod args;
+pub mod backend;
+pub mod commands;
+pub mod fake;
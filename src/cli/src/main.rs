@@ -0,0 +1,36 @@
+use cli::args::{Args, Command, Target, USAGE};
+use cli::backend::Backend;
+use cli::commands;
+use luminair_common::load_documents;
+use service::infrastructure::settings::Settings;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args = match Args::parse(&args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{USAGE}");
+            return Err(e);
+        }
+    };
+    if args.command == Command::Help {
+        println!("{USAGE}");
+        return Ok(());
+    }
+
+    let settings = Settings::from_env()?;
+    let registry = load_documents(&settings.schema_config_path)?;
+    let backend = match &args.target {
+        Target::Remote(url) => {
+            let url = url
+                .clone()
+                .unwrap_or_else(|| format!("http://localhost:{}", settings.server_port));
+            Backend::remote(&url)
+        }
+        Target::Direct => Backend::direct(&settings, registry).await?,
+    };
+
+    let mut out = std::io::stdout().lock();
+    commands::run(args.command, &backend, registry, &mut out).await
+}
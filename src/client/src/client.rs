@@ -0,0 +1,225 @@
+use crate::error::{ClientError, Problem};
+use crate::query::{ListQuery, Status};
+use reqwest::header::{HeaderMap, HeaderValue, IF_MATCH, LOCATION};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::marker::PhantomData;
+
+/// Header with the id of the user making the request.
+const USER_ID_HEADER: &str = "x-user-id";
+
+/// Header with the comma separated roles of the user making the request.
+const USER_ROLES_HEADER: &str = "x-user-roles";
+
+/// Client of the content API of a Luminair service.
+///
+/// Requests are made on behalf of the user given with [`Client::user`],
+/// which the service trusts like the headers set by a gateway in front of it.
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    headers: HeaderMap,
+}
+
+/// The documents of one type, deserialized into `T`: a struct generated
+/// by the `codegen` feature, or a plain JSON [`Value`].
+#[derive(Debug, Clone)]
+pub struct Documents<'a, T = Value> {
+    client: &'a Client,
+    api_id: String,
+    document: PhantomData<T>,
+}
+
+/// Page of a list of documents.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub documents: Vec<T>,
+    /// page number, counted from 1
+    pub page: u16,
+    pub page_size: u16,
+    /// number of the documents matching the query, on all pages
+    pub total: u64,
+}
+
+impl Client {
+    /// Client of the service at `base_url`, e.g. `http://localhost:8080`;
+    /// the content API is under its `/api`.
+    pub fn new(base_url: &str) -> Self {
+        Self::with_http_client(reqwest::Client::new(), base_url)
+    }
+
+    /// Client making its requests with `http`, for its timeouts, proxies or
+    /// TLS settings.
+    pub fn with_http_client(http: reqwest::Client, base_url: &str) -> Self {
+        Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// Make the requests on behalf of the user `id` with `roles`.
+    pub fn user(mut self, id: &str, roles: &[&str]) -> Result<Self, ClientError> {
+        self.headers
+            .insert(USER_ID_HEADER, HeaderValue::from_str(id)?);
+        if !roles.is_empty() {
+            self.headers
+                .insert(USER_ROLES_HEADER, HeaderValue::from_str(&roles.join(","))?);
+        }
+        Ok(self)
+    }
+
+    /// The documents of the type with `api_id`: the plural name of a
+    /// collection type or the singular name of a single type.
+    pub fn documents<T: DeserializeOwned>(&self, api_id: &str) -> Documents<'_, T> {
+        Documents {
+            client: self,
+            api_id: api_id.to_string(),
+            document: PhantomData,
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http
+            .request(method, format!("{}/api{}", self.base_url, path))
+            .headers(self.headers.clone())
+    }
+}
+
+impl<T: DeserializeOwned> Documents<'_, T> {
+    /// The documents matching `query`, one page of them.
+    pub async fn list(&self, query: &ListQuery) -> Result<Page<T>, ClientError> {
+        let response = self
+            .client
+            .request(Method::GET, &self.path(None))
+            .query(&query.to_query_pairs())
+            .send()
+            .await?;
+        let body: Value = read_json(response).await?;
+        let meta = &body["meta"];
+        let number = |key: &str| {
+            meta[key].as_u64().ok_or_else(|| {
+                ClientError::UnexpectedResponse(format!("missing meta.{key} of the page"))
+            })
+        };
+        let (page, page_size, total) = (number("page")?, number("page_size")?, number("total")?);
+        Ok(Page {
+            documents: data(body)?,
+            page: u16::try_from(page).unwrap_or(u16::MAX),
+            page_size: u16::try_from(page_size).unwrap_or(u16::MAX),
+            total,
+        })
+    }
+
+    /// The document with `document_id`, its published version or its draft.
+    pub async fn get(&self, document_id: &str, status: Status) -> Result<T, ClientError> {
+        let response = self
+            .client
+            .request(Method::GET, &self.path(Some(document_id)))
+            .query(&[("status", status.as_str())])
+            .send()
+            .await?;
+        data(read_json(response).await?)
+    }
+
+    /// Create a document of the fields and relations of `data`, and answer
+    /// its id.
+    pub async fn create<D: Serialize>(&self, data: &D) -> Result<String, ClientError> {
+        let response = self
+            .client
+            .request(Method::POST, &self.path(None))
+            .json(&json!({"data": data}))
+            .send()
+            .await?;
+        let response = check(response).await?;
+        response
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| location.rsplit('/').next())
+            .map(String::from)
+            .ok_or_else(|| ClientError::UnexpectedResponse("missing Location header".into()))
+    }
+
+    /// Update the draft of the document with `document_id` by `data`; when
+    /// `version` is given, only if the draft is still at that version.
+    pub async fn update<D: Serialize>(
+        &self,
+        document_id: &str,
+        data: &D,
+        version: Option<i32>,
+    ) -> Result<(), ClientError> {
+        let request = self
+            .client
+            .request(Method::PUT, &self.path(Some(document_id)))
+            .json(&json!({"data": data}));
+        check(if_match(request, version).send().await?).await?;
+        Ok(())
+    }
+
+    /// Publish the draft of the document with `document_id`; when `version`
+    /// is given, only if the draft is still at that version.
+    pub async fn publish(
+        &self,
+        document_id: &str,
+        version: Option<i32>,
+    ) -> Result<(), ClientError> {
+        let request = self.client.request(
+            Method::POST,
+            &format!("{}/publish", self.path(Some(document_id))),
+        );
+        check(if_match(request, version).send().await?).await?;
+        Ok(())
+    }
+
+    fn path(&self, document_id: Option<&str>) -> String {
+        match document_id {
+            Some(document_id) => format!("/documents/{}/{}", self.api_id, document_id),
+            None => format!("/documents/{}", self.api_id),
+        }
+    }
+}
+
+/// `response` if its status is a success, its problem details otherwise.
+async fn check(response: Response) -> Result<Response, ClientError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let problem = response
+        .json::<Problem>()
+        .await
+        .unwrap_or_else(|_| Problem {
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            ..Problem::default()
+        });
+    Err(ClientError::Api { status, problem })
+}
+
+async fn read_json(response: Response) -> Result<Value, ClientError> {
+    let response = check(response).await?;
+    if response.status() == StatusCode::NO_CONTENT {
+        return Err(ClientError::UnexpectedResponse("empty response".into()));
+    }
+    Ok(response.json().await?)
+}
+
+/// The `data` of the envelope of a response.
+fn data<D: DeserializeOwned>(mut body: Value) -> Result<D, ClientError> {
+    let data = body
+        .get_mut("data")
+        .map(Value::take)
+        .ok_or_else(|| ClientError::UnexpectedResponse("missing data".into()))?;
+    serde_json::from_value(data)
+        .map_err(|err| ClientError::UnexpectedResponse(format!("invalid data: {err}")))
+}
+
+fn if_match(request: RequestBuilder, version: Option<i32>) -> RequestBuilder {
+    match version {
+        Some(version) => request.header(IF_MATCH, format!("\"{version}\"")),
+        None => request,
+    }
+}
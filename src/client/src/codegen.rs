@@ -0,0 +1,298 @@
+//! Generation of content structs from the JSON Schemas the service answers
+//! at `GET /api/meta/documents/{id}/json-schema`, for build scripts:
+//!
+//! ```no_run
+//! // build.rs, with the schemas saved as `schemas/<document type id>.json`
+//! let out = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("content.rs");
+//! luminair_client::codegen::generate_from_dir("schemas".as_ref(), &out).unwrap();
+//! ```
+//!
+//! and `include!(concat!(env!("OUT_DIR"), "/content.rs"));` in the crate.
+//! Each document type gets a struct named after its id, `partner-category`
+//! becoming `PartnerCategory`, with a field per property of its schema.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Keywords which are valid raw identifiers.
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "yield",
+];
+
+/// Error of the generation of the content structs.
+#[derive(Debug, thiserror::Error)]
+pub enum CodegenError {
+    #[error("Failed to access {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Invalid JSON in {path}: {source}")]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    /// A schema which isn't one of a document type.
+    #[error("Unsupported schema of '{0}': {1}")]
+    Schema(String, String),
+}
+
+/// Rust source of the structs of the documents described by `schemas`,
+/// JSON Schemas of document types keyed by their ids, and of the types
+/// their relations lead to. Structs are ordered by name.
+pub fn generate(schemas: &[(String, Value)]) -> Result<String, CodegenError> {
+    let mut structs = BTreeMap::new();
+    for (id, schema) in schemas {
+        let name = type_name(id);
+        if let Some(Value::Object(definitions)) = schema.get("$defs") {
+            for (definition_id, definition) in definitions {
+                if let Entry::Vacant(entry) = structs.entry(type_name(definition_id)) {
+                    let source = struct_source(entry.key(), definition, &name)?;
+                    entry.insert(source);
+                }
+            }
+        }
+        // the schema of the type itself wins over a definition of it
+        structs.insert(name.clone(), struct_source(&name, schema, &name)?);
+    }
+
+    let mut source = String::from("// Generated by luminair-client, do not edit.\n");
+    for struct_source in structs.into_values() {
+        source.push('\n');
+        source.push_str(&struct_source);
+    }
+    Ok(source)
+}
+
+/// Write to `out` the structs of the schemas of the `*.json` files of `dir`,
+/// each named after the id of its document type; cargo is told to rerun
+/// the build script when they change.
+pub fn generate_from_dir(dir: &Path, out: &Path) -> Result<(), CodegenError> {
+    let io = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source: std::io::Error| CodegenError::Io { path, source }
+    };
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(io(dir))? {
+        let path = entry.map_err(io(dir))?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut schemas = Vec::with_capacity(paths.len());
+    for path in paths {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let text = std::fs::read_to_string(&path).map_err(io(&path))?;
+        let schema = serde_json::from_str(&text).map_err(|source| CodegenError::Json {
+            path: path.clone(),
+            source,
+        })?;
+        let id = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        schemas.push((id, schema));
+    }
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    std::fs::write(out, generate(&schemas)?).map_err(io(out))
+}
+
+/// `partner-category` → `PartnerCategory`
+fn type_name(id: &str) -> String {
+    id.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// `documentId` → `document_id`, keywords raw: `type` → `r#type`
+fn field_name(key: &str) -> String {
+    let mut name = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            if !name.is_empty() && !name.ends_with('_') {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        } else if c.is_ascii_alphanumeric() {
+            name.push(c);
+        } else {
+            name.push('_');
+        }
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    if KEYWORDS.contains(&name.as_str()) {
+        name.insert_str(0, "r#");
+    }
+    name
+}
+
+fn struct_source(name: &str, schema: &Value, root: &str) -> Result<String, CodegenError> {
+    let unsupported = |reason: &str| CodegenError::Schema(name.to_string(), reason.to_string());
+    let properties = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .ok_or_else(|| unsupported("no properties"))?;
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|required| required.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let mut source = String::new();
+    if let Some(title) = schema.get("title").and_then(Value::as_str) {
+        let _ = writeln!(source, "/// {title}");
+    }
+    let _ = writeln!(
+        source,
+        "#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]"
+    );
+    let _ = writeln!(source, "pub struct {name} {{");
+    for (key, property) in properties {
+        let (rust_type, defaulted) =
+            property_type(property, required.contains(&key.as_str()), root)
+                .ok_or_else(|| unsupported(&format!("property '{key}'")))?;
+        if let Some(description) = property.get("description").and_then(Value::as_str) {
+            let _ = writeln!(source, "    /// {description}");
+        }
+        let field = field_name(key);
+        let mut attributes = Vec::new();
+        if field.trim_start_matches("r#") != key {
+            attributes.push(format!("rename = \"{key}\""));
+        }
+        if defaulted {
+            attributes.push("default".to_string());
+        }
+        if !attributes.is_empty() {
+            let _ = writeln!(source, "    #[serde({})]", attributes.join(", "));
+        }
+        let _ = writeln!(source, "    pub {field}: {rust_type},");
+    }
+    source.push_str("}\n");
+    Ok(source)
+}
+
+/// Rust type of the values of `property`, and whether a missing one is
+/// taken by default.
+fn property_type(property: &Value, required: bool, root: &str) -> Option<(String, bool)> {
+    let types: Vec<&str> = match property.get("type") {
+        Some(Value::String(single)) => vec![single.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    let nullable = types.contains(&"null");
+    let types: Vec<&str> = types.into_iter().filter(|t| *t != "null").collect();
+
+    let rust_type = match types.as_slice() {
+        ["string"] => "String".to_string(),
+        ["integer"] => "i64".to_string(),
+        ["boolean"] => "bool".to_string(),
+        // decimals, a number or a string holding it
+        ["number", "string"] | ["number"] => "serde_json::Value".to_string(),
+        ["object"] => match (
+            property.get("additionalProperties"),
+            property.get("properties"),
+        ) {
+            (Some(Value::Object(values)), _)
+                if values.get("type").and_then(Value::as_str) == Some("string") =>
+            {
+                "std::collections::HashMap<String, String>".to_string()
+            }
+            (_, Some(Value::Object(_))) => "std::collections::HashMap<String, u64>".to_string(),
+            _ => "serde_json::Map<String, serde_json::Value>".to_string(),
+        },
+        ["array"] => {
+            let target = property.get("items")?.get("$ref")?.as_str()?;
+            let target = match target {
+                "#" => root.to_string(),
+                other => type_name(other.strip_prefix("#/$defs/")?),
+            };
+            // relations are answered only when populated
+            return Some((format!("Vec<{target}>"), true));
+        }
+        _ => return None,
+    };
+    if nullable || !required {
+        Some((format!("Option<{rust_type}>"), true))
+    } else {
+        Some((rust_type, false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_structs_are_generated_for_types_and_related_types() {
+        let schema = json!({
+            "title": "Brands",
+            "type": "object",
+            "properties": {
+                "documentId": {"type": "string", "format": "uuid"},
+                "name": {"type": "string", "description": "name of the brand"},
+                "type": {"type": ["string", "null"]},
+                "partners": {"type": "array", "items": {"$ref": "#/$defs/partner"}},
+            },
+            "required": ["documentId", "name"],
+            "$defs": {
+                "partner": {
+                    "title": "Partners",
+                    "type": "object",
+                    "properties": {
+                        "brand": {"type": "array", "items": {"$ref": "#"}},
+                    },
+                    "required": [],
+                },
+            },
+        });
+
+        let source = generate(&[("brand".to_string(), schema)]).unwrap();
+
+        assert!(source.contains("pub struct Brand {"), "{source}");
+        assert!(
+            source.contains("    #[serde(rename = \"documentId\")]\n    pub document_id: String,"),
+            "{source}"
+        );
+        assert!(
+            source.contains("    /// name of the brand\n    pub name: String,"),
+            "{source}"
+        );
+        assert!(
+            source.contains("    #[serde(default)]\n    pub r#type: Option<String>,"),
+            "{source}"
+        );
+        assert!(source.contains("pub partners: Vec<Partner>,"), "{source}");
+        assert!(source.contains("pub brand: Vec<Brand>,"), "{source}");
+    }
+
+    #[test]
+    fn test_names_follow_rust_conventions() {
+        assert_eq!(type_name("partner-categories"), "PartnerCategories");
+        assert_eq!(field_name("relationCounts"), "relation_counts");
+        assert_eq!(field_name("match"), "r#match");
+    }
+}
@@ -0,0 +1,57 @@
+use serde::Deserialize;
+
+/// Error of a request to the content API.
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// The service couldn't be reached, or its response couldn't be read.
+    #[error("Request failed: {0}")]
+    Transport(#[from] reqwest::Error),
+
+    /// The service answered with an error status, described by its
+    /// problem details.
+    #[error("{status} {}: {}", problem.title, problem.detail)]
+    Api {
+        status: reqwest::StatusCode,
+        problem: Problem,
+    },
+
+    /// A user id or role can't be sent in a header.
+    #[error("Invalid header value: {0}")]
+    InvalidHeader(#[from] reqwest::header::InvalidHeaderValue),
+
+    /// The response wasn't the JSON the request answers, e.g. because the
+    /// service answers another response format.
+    #[error("Unexpected response: {0}")]
+    UnexpectedResponse(String),
+}
+
+/// Problem details (RFC 9457) of an error response.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Problem {
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub detail: String,
+    /// stable code of the kind of error, e.g. `NOT_FOUND` or `CONFLICT`
+    pub code: Option<String>,
+    /// fields which failed validation
+    #[serde(default)]
+    pub errors: Vec<ProblemFieldError>,
+}
+
+/// Field of a request body which failed validation.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProblemFieldError {
+    /// id of the field, as it is given in the request
+    pub field: String,
+    /// kind of the failure: `required`, `invalid`, `constraint` or `unknown`
+    pub code: String,
+    pub detail: String,
+}
+
+impl ClientError {
+    /// Whether the service answered with `status`.
+    pub fn is_status(&self, status: reqwest::StatusCode) -> bool {
+        matches!(self, ClientError::Api { status: actual, .. } if *actual == status)
+    }
+}
@@ -0,0 +1,34 @@
+//! Typed async client of the content API of a Luminair service.
+//!
+//! ```no_run
+//! use luminair_client::{Client, Filter, ListQuery, SortDirection};
+//! use serde_json::{Value, json};
+//!
+//! # async fn example() -> Result<(), luminair_client::ClientError> {
+//! let client = Client::new("http://localhost:8080").user("editor", &["editors"])?;
+//! let brands = client.documents::<Value>("brands");
+//!
+//! let query = ListQuery::new()
+//!     .filter(Filter::new().starts_with("name", "ac"))
+//!     .sort("name", SortDirection::Ascending);
+//! let page = brands.list(&query).await?;
+//!
+//! let id = brands.create(&json!({"name": "acme"})).await?;
+//! brands.publish(&id, None).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! With the `codegen` feature, a build script generates structs of the
+//! documents to read them into instead of [`serde_json::Value`], see
+//! `codegen`.
+
+mod client;
+#[cfg(feature = "codegen")]
+pub mod codegen;
+mod error;
+mod query;
+
+pub use client::{Client, Documents, Page};
+pub use error::{ClientError, Problem, ProblemFieldError};
+pub use query::{Condition, Filter, ListQuery, SortDirection, Status};
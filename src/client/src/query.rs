@@ -0,0 +1,432 @@
+use serde_json::Value;
+
+/// One condition on a field of the documents, the way the content API
+/// filters them: `filters[<field>][<operator>]=<value>`.
+///
+/// A field of a localized text is filtered by one locale, `description.en`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// Exact match: field = value
+    Equals { field: String, value: Value },
+
+    /// Not equal match: field != value
+    NotEquals { field: String, value: Value },
+
+    /// Greater than
+    GreaterThan { field: String, value: Value },
+
+    /// Greater than or equal
+    GreaterThanOrEqual { field: String, value: Value },
+
+    /// Less than
+    LessThan { field: String, value: Value },
+
+    /// Less than or equal
+    LessThanOrEqual { field: String, value: Value },
+
+    /// In: field in (values)
+    In { field: String, values: Vec<Value> },
+
+    /// Not In: field not in (values)
+    NotIn { field: String, values: Vec<Value> },
+
+    /// Range: from <= field <= to
+    Between {
+        field: String,
+        from: Value,
+        to: Value,
+    },
+
+    /// Contains (for text fields)
+    Contains { field: String, value: String },
+
+    /// Starts with (for text fields)
+    StartsWith { field: String, value: String },
+
+    /// Ends with (for text fields)
+    EndsWith { field: String, value: String },
+
+    /// Is null
+    IsNull { field: String },
+
+    /// Is not null
+    IsNotNull { field: String },
+
+    /// For relations: document has a related document matching `filter`
+    HasRelation { relation: String, filter: Filter },
+}
+
+/// Conditions the documents match all of.
+///
+/// ```
+/// use luminair_client::Filter;
+///
+/// let filter = Filter::new()
+///     .equals("active", true)
+///     .greater_than("stock", 5)
+///     .has_relation("brand", Filter::new().equals("slug", "acme"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filter {
+    conditions: Vec<Condition>,
+}
+
+/// Direction of a sort.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+/// Version of the documents to read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Status {
+    #[default]
+    Published,
+    Draft,
+}
+
+/// Query of a list of documents: its filter, sorts, page and the relations
+/// populated in the documents.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListQuery {
+    filter: Filter,
+    sorts: Vec<(String, SortDirection)>,
+    page: Option<(u16, u16)>,
+    status: Status,
+    populate: Vec<String>,
+}
+
+impl Filter {
+    /// Filter without conditions, matching all documents.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `condition` to the conditions the documents match.
+    pub fn condition(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    /// Add equality filter: field = value
+    pub fn equals(self, field: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.condition(Condition::Equals {
+            field: field.into(),
+            value: value.into(),
+        })
+    }
+
+    /// Add inequality filter: field != value
+    pub fn not_equals(self, field: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.condition(Condition::NotEquals {
+            field: field.into(),
+            value: value.into(),
+        })
+    }
+
+    /// Add filter: field > value
+    pub fn greater_than(self, field: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.condition(Condition::GreaterThan {
+            field: field.into(),
+            value: value.into(),
+        })
+    }
+
+    /// Add filter: field >= value
+    pub fn greater_than_or_equal(self, field: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.condition(Condition::GreaterThanOrEqual {
+            field: field.into(),
+            value: value.into(),
+        })
+    }
+
+    /// Add filter: field < value
+    pub fn less_than(self, field: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.condition(Condition::LessThan {
+            field: field.into(),
+            value: value.into(),
+        })
+    }
+
+    /// Add filter: field <= value
+    pub fn less_than_or_equal(self, field: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.condition(Condition::LessThanOrEqual {
+            field: field.into(),
+            value: value.into(),
+        })
+    }
+
+    /// Add filter: field in (values)
+    pub fn is_in<V: Into<Value>>(
+        self,
+        field: impl Into<String>,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        self.condition(Condition::In {
+            field: field.into(),
+            values: values.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    /// Add filter: field not in (values)
+    pub fn not_in<V: Into<Value>>(
+        self,
+        field: impl Into<String>,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        self.condition(Condition::NotIn {
+            field: field.into(),
+            values: values.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    /// Add filter: from <= field <= to
+    pub fn between(
+        self,
+        field: impl Into<String>,
+        from: impl Into<Value>,
+        to: impl Into<Value>,
+    ) -> Self {
+        self.condition(Condition::Between {
+            field: field.into(),
+            from: from.into(),
+            to: to.into(),
+        })
+    }
+
+    /// Add filter: the text field contains value
+    pub fn contains(self, field: impl Into<String>, value: impl Into<String>) -> Self {
+        self.condition(Condition::Contains {
+            field: field.into(),
+            value: value.into(),
+        })
+    }
+
+    /// Add filter: the text field starts with value
+    pub fn starts_with(self, field: impl Into<String>, value: impl Into<String>) -> Self {
+        self.condition(Condition::StartsWith {
+            field: field.into(),
+            value: value.into(),
+        })
+    }
+
+    /// Add filter: the text field ends with value
+    pub fn ends_with(self, field: impl Into<String>, value: impl Into<String>) -> Self {
+        self.condition(Condition::EndsWith {
+            field: field.into(),
+            value: value.into(),
+        })
+    }
+
+    /// Add filter: field is null
+    pub fn is_null(self, field: impl Into<String>) -> Self {
+        self.condition(Condition::IsNull {
+            field: field.into(),
+        })
+    }
+
+    /// Add filter: field is not null
+    pub fn is_not_null(self, field: impl Into<String>) -> Self {
+        self.condition(Condition::IsNotNull {
+            field: field.into(),
+        })
+    }
+
+    /// Add filter: a document related by `relation` matches `filter`
+    pub fn has_relation(self, relation: impl Into<String>, filter: Filter) -> Self {
+        self.condition(Condition::HasRelation {
+            relation: relation.into(),
+            filter,
+        })
+    }
+
+    /// The `filters[...]` query parameters of the conditions.
+    pub fn to_query_pairs(&self) -> Vec<(String, String)> {
+        let mut pairs = Vec::new();
+        self.push_pairs("filters", &mut pairs);
+        pairs
+    }
+
+    fn push_pairs(&self, prefix: &str, pairs: &mut Vec<(String, String)>) {
+        for condition in &self.conditions {
+            let key = |field: &str, operator: &str| format!("{prefix}[{field}][{operator}]");
+            match condition {
+                Condition::Equals { field, value } => {
+                    pairs.push((key(field, "$eq"), value_param(value)))
+                }
+                Condition::NotEquals { field, value } => {
+                    pairs.push((key(field, "$ne"), value_param(value)))
+                }
+                Condition::GreaterThan { field, value } => {
+                    pairs.push((key(field, "$gt"), value_param(value)))
+                }
+                Condition::GreaterThanOrEqual { field, value } => {
+                    pairs.push((key(field, "$gte"), value_param(value)))
+                }
+                Condition::LessThan { field, value } => {
+                    pairs.push((key(field, "$lt"), value_param(value)))
+                }
+                Condition::LessThanOrEqual { field, value } => {
+                    pairs.push((key(field, "$lte"), value_param(value)))
+                }
+                Condition::In { field, values } => {
+                    let key = format!("{}[]", key(field, "$in"));
+                    pairs.extend(values.iter().map(|value| (key.clone(), value_param(value))));
+                }
+                Condition::NotIn { field, values } => {
+                    let key = format!("{}[]", key(field, "$notIn"));
+                    pairs.extend(values.iter().map(|value| (key.clone(), value_param(value))));
+                }
+                Condition::Between { field, from, to } => {
+                    let key = format!("{}[]", key(field, "$between"));
+                    pairs.push((key.clone(), value_param(from)));
+                    pairs.push((key, value_param(to)));
+                }
+                Condition::Contains { field, value } => {
+                    pairs.push((key(field, "$contains"), value.clone()))
+                }
+                Condition::StartsWith { field, value } => {
+                    pairs.push((key(field, "$startsWith"), value.clone()))
+                }
+                Condition::EndsWith { field, value } => {
+                    pairs.push((key(field, "$endsWith"), value.clone()))
+                }
+                Condition::IsNull { field } => pairs.push((key(field, "$null"), "true".into())),
+                Condition::IsNotNull { field } => {
+                    pairs.push((key(field, "$notNull"), "true".into()))
+                }
+                Condition::HasRelation { relation, filter } => {
+                    filter.push_pairs(&format!("{prefix}[{relation}]"), pairs)
+                }
+            }
+        }
+    }
+}
+
+impl ListQuery {
+    /// Query of the first page of all the published documents.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Filter the documents by `filter`.
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Sort the documents by `field`, after the sorts added before.
+    pub fn sort(mut self, field: impl Into<String>, direction: SortDirection) -> Self {
+        self.sorts.push((field.into(), direction));
+        self
+    }
+
+    /// Read the page `page`, counted from 1, of `page_size` documents.
+    pub fn page(mut self, page: u16, page_size: u16) -> Self {
+        self.page = Some((page, page_size));
+        self
+    }
+
+    /// Read the drafts rather than the published documents.
+    pub fn status(mut self, status: Status) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Populate the related documents of `relation`.
+    pub fn populate(mut self, relation: impl Into<String>) -> Self {
+        self.populate.push(relation.into());
+        self
+    }
+
+    /// The query parameters of the query.
+    pub fn to_query_pairs(&self) -> Vec<(String, String)> {
+        let mut pairs = self.filter.to_query_pairs();
+        if !self.sorts.is_empty() {
+            let sorts = self
+                .sorts
+                .iter()
+                .map(|(field, direction)| match direction {
+                    SortDirection::Ascending => format!("{field}:asc"),
+                    SortDirection::Descending => format!("{field}:desc"),
+                })
+                .collect::<Vec<_>>();
+            pairs.push(("sort".into(), sorts.join(",")));
+        }
+        if let Some((page, page_size)) = self.page {
+            pairs.push(("pagination[page]".into(), page.to_string()));
+            pairs.push(("pagination[pageSize]".into(), page_size.to_string()));
+        }
+        if self.status == Status::Draft {
+            pairs.push(("status".into(), self.status.as_str().into()));
+        }
+        pairs.extend(
+            self.populate
+                .iter()
+                .map(|relation| ("populate[]".into(), relation.clone())),
+        );
+        pairs
+    }
+}
+
+impl Status {
+    /// Value of the `status` query parameter.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Published => "published",
+            Status::Draft => "draft",
+        }
+    }
+}
+
+/// The text of `value` in a query parameter.
+fn value_param(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_is_rendered_as_bracketed_parameters() {
+        let query = ListQuery::new()
+            .filter(
+                Filter::new()
+                    .equals("active", true)
+                    .between("stock", 1, 9)
+                    .is_not_null("meta")
+                    .has_relation("brand", Filter::new().starts_with("slug", "ac")),
+            )
+            .sort("name", SortDirection::Descending)
+            .page(2, 10)
+            .status(Status::Draft)
+            .populate("brand");
+
+        let pairs: Vec<String> = query
+            .to_query_pairs()
+            .into_iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                "filters[active][$eq]=true",
+                "filters[stock][$between][]=1",
+                "filters[stock][$between][]=9",
+                "filters[meta][$notNull]=true",
+                "filters[brand][slug][$startsWith]=ac",
+                "sort=name:desc",
+                "pagination[page]=2",
+                "pagination[pageSize]=10",
+                "status=draft",
+                "populate[]=brand",
+            ]
+        );
+    }
+}
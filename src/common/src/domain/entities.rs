@@ -15,6 +15,8 @@ pub struct DocumentType {
     pub options: Option<DocumentTypeOptions>,
     pub fields: HashSet<DocumentField>,
     pub relations: HashSet<DocumentRelation>,
+    /// previous id of the document type, used by migration to rename tables instead of recreating them
+    pub renamed_from: Option<DocumentTypeId>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -57,8 +59,150 @@ pub struct DocumentTitle(String);
 pub struct DocumentTypeOptions {
     pub draft_and_publish: bool,
     pub localizations: Vec<LocalizationId>,
+    /// editorial stages the documents move through, besides draft and published
+    pub workflow: Option<Workflow>,
+    /// HTTP caching of the published documents, by browsers and CDNs
+    pub cache_control: Option<CacheControl>,
+    /// fields of the documents making up the entries of the RSS feed of a collection type
+    pub feed: Option<Feed>,
+    /// operations of the content API exposed for the documents, all of them if unset
+    pub operations: Option<Vec<ApiOperation>>,
+    /// range partitioning of the main table by the creation time of the documents
+    pub partitioning: Option<Partitioning>,
+    /// materialized view the documents of a read-only type are read from,
+    /// instead of tables of their own
+    pub view: Option<MaterializedView>,
+    /// removal, or archival, of the documents older than a number of days
+    pub retention: Option<Retention>,
 }
 
+/// How long the documents of a log-like collection type are kept after
+/// their creation, and whether they are then deleted or moved to the
+/// archived table of the type: `{"deleteAfterDays": 90}` or
+/// `{"archiveAfterDays": 30}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Retention {
+    DeleteAfterDays(u32),
+    ArchiveAfterDays(u32),
+}
+
+impl Retention {
+    /// Days the documents are kept for.
+    pub fn days(&self) -> u32 {
+        match self {
+            Retention::DeleteAfterDays(days) | Retention::ArchiveAfterDays(days) => *days,
+        }
+    }
+
+    /// Whether the expired documents are archived rather than deleted.
+    pub fn archives(&self) -> bool {
+        matches!(self, Retention::ArchiveAfterDays(_))
+    }
+}
+
+/// Materialized view backing a read-only document type, declared in the
+/// `views/` folder of the schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaterializedView {
+    /// query answering a `document_id` and a column per field of the type
+    pub sql: String,
+}
+
+/// Range partitioning of the main table of a collection type by `created_at`,
+/// for event-like collections with millions of rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Partitioning {
+    /// time span of the rows of one partition
+    #[serde(default)]
+    pub interval: PartitionInterval,
+    /// partitions created ahead of the current one, so inserts never wait for them
+    #[serde(default = "default_premake")]
+    pub premake: u32,
+}
+
+/// Time span of the rows of one partition
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PartitionInterval {
+    #[default]
+    Month,
+}
+
+fn default_premake() -> u32 {
+    3
+}
+
+/// An operation of the content API on the documents of a type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ApiOperation {
+    Find,
+    FindOne,
+    Create,
+    Update,
+    Delete,
+    Publish,
+}
+
+/// `Cache-Control` directives of the responses with published documents
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheControl {
+    /// seconds a response may be reused
+    pub max_age: Option<u32>,
+    /// seconds a response may be reused by shared caches, like CDNs, instead
+    #[serde(rename = "sMaxAge")]
+    pub s_maxage: Option<u32>,
+    /// seconds a stale response may still be served while it is revalidated
+    pub stale_while_revalidate: Option<u32>,
+}
+
+/// Fields of the documents making up the entries of an RSS feed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Feed {
+    /// text field with the title of an entry
+    pub title: AttributeId,
+    /// text field with the description of an entry
+    pub description: AttributeId,
+    /// date or date-time field an entry is dated by, the newest entries make up the feed
+    pub date: AttributeId,
+}
+
+/// Editorial workflow of a document type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workflow {
+    /// stages in their editorial order, new documents start in the first one
+    pub stages: Vec<WorkflowStage>,
+}
+
+/// A stage of an editorial workflow, e.g. review or approved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowStage {
+    pub id: WorkflowStageId,
+    /// roles allowed to move documents into this stage, anyone if empty
+    pub roles: Vec<String>,
+}
+
+#[nutype(
+    sanitize(trim, lowercase),
+    validate(not_empty, len_char_max = 30),
+    derive(
+        Clone,
+        Debug,
+        Display,
+        FromStr,
+        AsRef,
+        PartialEq,
+        Eq,
+        Hash,
+        Serialize,
+        Deserialize
+    )
+)]
+pub struct WorkflowStageId(String);
+
 static VALID_LOCALIZATIONS_REGEX: LazyLock<Regex> =
     LazyLock::new(|| Regex::new("^(ru|ro|en)").unwrap());
 
@@ -90,6 +234,19 @@ pub struct DocumentField {
     pub unique: bool,
     pub required: bool,
     pub constraints: HashSet<FieldConstraint>,
+    /// previous id of the field, used by migration to rename columns instead of recreating them
+    pub renamed_from: Option<AttributeId>,
+    /// name of the field in the payloads of the API, instead of its id in camelCase
+    pub api_name: Option<String>,
+    /// what the field holds, for the documentation of the API
+    pub description: Option<String>,
+    /// whether clients filter by the field, so migration indexes it
+    pub filterable: bool,
+    /// whether clients sort by the field, so migration indexes it
+    pub sortable: bool,
+    /// whether the field holds personal data of a data subject, which is
+    /// looked up and erased on their request
+    pub personal: bool,
 }
 
 /// A uniquely identifiable document Relation.
@@ -98,13 +255,21 @@ pub struct DocumentRelation {
     pub id: AttributeId,
     pub relation_type: RelationType,
     pub target: DocumentTypeId,
+    /// action on relation links when the target document is deleted
+    pub on_delete: OnDeleteAction,
+    /// previous id of the relation, used by migration to rename relation tables instead of recreating them
+    pub renamed_from: Option<AttributeId>,
+    /// name of the relation in the payloads of the API, instead of its id in camelCase
+    pub api_name: Option<String>,
+    /// what the relation links, for the documentation of the API
+    pub description: Option<String>,
 }
 
 // TODO: support for more complex relations (e.g. with additional fields on the relation itself, like in a many-to-many with pivot table)
 // TODO: support for self-relations (e.g. a "Category" that can have a parent category, which is also of type "Category")
 // TODO: support for polymorphic relations (e.g. a "Comment" that can belong to either a "Post" or a "Product", etc.)
 // TODO: support for recursive relations (e.g. a "Category" that can have subcategories, which are also of type "Category")
-// TODO: support for more complex relation types (e.g. one-to-one, many-to-many, etc.) and relation options (e.g. cascade delete, etc.)
+// TODO: support for more complex relation types (e.g. one-to-one, many-to-many, etc.)
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -116,7 +281,9 @@ pub enum FieldType {
     Integer(#[serde(default)] IntegerSize),
     Decimal { precision: usize, scale: u32 },
     Date,
-    DateTime,
+    DateTime,      // instant, stored with its time zone
+    LocalDateTime, // wall-clock date and time, stored without a time zone
+    Time,          // time of day
     Boolean,
     Json, // arbitrary JSON data
 }
@@ -178,6 +345,76 @@ impl FieldConstraint {
             FieldConstraint::MaximalIntegerValue(_) => field_type.is_integer(),
         }
     }
+
+    /// Why a pattern can't be used, if it can't: the service checks it with
+    /// the `regex` crate and the database with the `~` operator of Postgres,
+    /// so a pattern must stay within the syntax both read the same way —
+    /// no flags, named groups, word boundaries, anchors other than `^` and
+    /// `$`, Unicode classes, code point escapes or nested classes.
+    pub fn pattern_error(&self) -> Option<String> {
+        let FieldConstraint::Pattern(pattern) = self else {
+            return None;
+        };
+        if let Err(error) = Regex::new(pattern) {
+            return Some(format!("isn't a valid regex: {error}"));
+        }
+        let mut in_class = false;
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(escaped)
+                        if !escaped.is_ascii_alphanumeric() || "dDwWsSntr".contains(escaped) => {}
+                    Some(escaped) => {
+                        return Some(format!(
+                            "uses '\\{escaped}', which Postgres reads differently"
+                        ));
+                    }
+                    None => {}
+                },
+                '(' if !in_class && chars.peek() == Some(&'?') => {
+                    chars.next();
+                    if chars.next() != Some(':') {
+                        return Some(
+                            "uses a '(?' group other than '(?:', which Postgres reads differently"
+                                .to_string(),
+                        );
+                    }
+                }
+                '[' if !in_class => {
+                    in_class = true;
+                    // a leading ']' is a member of the class
+                    if chars.peek() == Some(&'^') {
+                        chars.next();
+                    }
+                    if chars.peek() == Some(&']') {
+                        chars.next();
+                    }
+                }
+                '[' if chars.peek() == Some(&':') => {
+                    // a POSIX class like [:alpha:]
+                    for c in chars.by_ref() {
+                        if c == ']' {
+                            break;
+                        }
+                    }
+                }
+                '[' => {
+                    return Some(
+                        "nests a character class, which Postgres reads differently".to_string(),
+                    );
+                }
+                ']' if in_class => in_class = false,
+                '&' | '-' | '~' if in_class && chars.peek() == Some(&c) => {
+                    return Some(format!(
+                        "uses '{c}{c}' in a character class, which Postgres reads differently"
+                    ));
+                }
+                _ => {}
+            }
+        }
+        None
+    }
 }
 
 // TODO: support for more complex constraints (e.g. regex patterns for text, min/max for numbers, date ranges for dates, etc.)
@@ -200,10 +437,36 @@ pub enum RelationType {
     BelongsToMany,
 }
 
+/// Referential action of the foreign key to the relation target
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OnDeleteAction {
+    #[default]
+    Cascade, // links to the deleted document are deleted
+    Restrict, // documents referenced by links can't be deleted
+    Detach, // links to the deleted document are deleted, and the linking documents are marked as updated
+    #[serde(alias = "set null")]
+    SetNull, // the linking documents no longer link to the deleted document, like with detach
+}
+
 // implementations
 
 // Document
 
+impl ApiOperation {
+    /// Name of the operation, as in the schema options.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Find => "find",
+            Self::FindOne => "findOne",
+            Self::Create => "create",
+            Self::Update => "update",
+            Self::Delete => "delete",
+            Self::Publish => "publish",
+        }
+    }
+}
+
 impl DocumentType {
     /// Creates a minimal `Collection` document type with no fields or relations.
     ///
@@ -226,6 +489,7 @@ impl DocumentType {
             options: None,
             fields: HashSet::new(),
             relations: HashSet::new(),
+            renamed_from: None,
         })
     }
 
@@ -241,21 +505,71 @@ impl DocumentType {
             .is_some_and(|options| options.draft_and_publish)
     }
 
+    pub fn workflow(&self) -> Option<&Workflow> {
+        self.options
+            .as_ref()
+            .and_then(|options| options.workflow.as_ref())
+    }
+
+    pub fn partitioning(&self) -> Option<&Partitioning> {
+        self.options
+            .as_ref()
+            .and_then(|options| options.partitioning.as_ref())
+    }
+
+    pub fn view(&self) -> Option<&MaterializedView> {
+        self.options
+            .as_ref()
+            .and_then(|options| options.view.as_ref())
+    }
+
+    pub fn retention(&self) -> Option<Retention> {
+        self.options.as_ref().and_then(|options| options.retention)
+    }
+
+    /// The fields holding personal data, by name.
+    pub fn personal_fields(&self) -> Vec<&DocumentField> {
+        let mut fields: Vec<_> = self.fields.iter().filter(|f| f.personal).collect();
+        fields.sort_by_key(|f| &f.id);
+        fields
+    }
+
+    /// Whether the content API exposes `operation` for the documents.
+    pub fn exposes(&self, operation: ApiOperation) -> bool {
+        self.options
+            .as_ref()
+            .and_then(|options| options.operations.as_ref())
+            .is_none_or(|operations| operations.contains(&operation))
+    }
+
+    /// Name of `attribute` in the payloads of the API, if its schema gives one.
+    pub fn api_name(&self, attribute: &AttributeId) -> Option<&str> {
+        let field = self.fields.get(attribute).map(|field| &field.api_name);
+        let relation = || {
+            self.relations
+                .get(attribute)
+                .map(|relation| &relation.api_name)
+        };
+        field.or_else(relation).and_then(|name| name.as_deref())
+    }
+
     pub fn ordered_fields(&self) -> Vec<&DocumentField> {
         // sord fields by unique flag, FieldType & name
-        // order of types: integer, uuid, date, datetime, boolean, decimal, uid, text, localized text, json
+        // order of types: integer, uuid, date, datetime, local datetime, time, boolean, decimal, uid, text, localized text, json
         fn field_type_order(ft: &FieldType) -> u8 {
             match ft {
                 FieldType::Integer(_) => 0,
                 FieldType::Uuid => 1,
                 FieldType::Date => 2,
                 FieldType::DateTime => 3,
-                FieldType::Boolean => 4,
-                FieldType::Decimal { .. } => 5,
-                FieldType::Uid => 6,
-                FieldType::Text => 7,
-                FieldType::LocalizedText => 8,
-                FieldType::Json => 9,
+                FieldType::LocalDateTime => 4,
+                FieldType::Time => 5,
+                FieldType::Boolean => 6,
+                FieldType::Decimal { .. } => 7,
+                FieldType::Uid => 8,
+                FieldType::Text => 9,
+                FieldType::LocalizedText => 10,
+                FieldType::Json => 11,
             }
         }
         let mut fields: Vec<_> = self.fields.iter().collect();
@@ -295,6 +609,46 @@ impl Hash for DocumentType {
     }
 }
 
+// Workflow
+
+impl Workflow {
+    /// The stage of documents which were never moved to another stage.
+    pub fn initial_stage(&self) -> &WorkflowStage {
+        &self.stages[0]
+    }
+
+    pub fn stage(&self, id: &str) -> Option<&WorkflowStage> {
+        self.stages.iter().find(|stage| stage.id.as_ref() == id)
+    }
+}
+
+impl WorkflowStage {
+    /// Whether a user with `roles` may move documents into this stage.
+    pub fn admits(&self, roles: &[String]) -> bool {
+        self.roles.is_empty() || self.roles.iter().any(|role| roles.contains(role))
+    }
+}
+
+// Cache control
+
+impl CacheControl {
+    /// Value of the `Cache-Control` header, e.g. `public, max-age=60, s-maxage=300`.
+    pub fn header_value(&self) -> String {
+        let directives = [
+            ("max-age", self.max_age),
+            ("s-maxage", self.s_maxage),
+            ("stale-while-revalidate", self.stale_while_revalidate),
+        ];
+        let mut value = "public".to_string();
+        for (directive, seconds) in directives {
+            if let Some(seconds) = seconds {
+                value.push_str(&format!(", {directive}={seconds}"));
+            }
+        }
+        value
+    }
+}
+
 // Field
 
 impl PartialEq for DocumentField {
@@ -336,6 +690,24 @@ impl RelationType {
     }
 }
 
+impl OnDeleteAction {
+    pub fn to_sql_action(&self) -> &'static str {
+        match self {
+            // detached links are deleted before the document, cascade keeps
+            // links written concurrently from failing the delete; links are
+            // primary keys of the relation tables, clearing one deletes it
+            OnDeleteAction::Cascade | OnDeleteAction::Detach | OnDeleteAction::SetNull => "CASCADE",
+            OnDeleteAction::Restrict => "RESTRICT",
+        }
+    }
+
+    /// Whether the documents linking to a deleted document are marked as
+    /// updated, their links to it being removed.
+    pub fn detaches(&self) -> bool {
+        matches!(self, OnDeleteAction::Detach | OnDeleteAction::SetNull)
+    }
+}
+
 impl PartialEq for DocumentRelation {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
@@ -394,6 +766,132 @@ mod tests {
         );
     }
 
+    #[test]
+    fn pattern_errors() {
+        let error = |pattern: &str| FieldConstraint::Pattern(pattern.to_string()).pattern_error();
+        for pattern in [
+            "^[a-z']+$",
+            r"^\d{3}-\d{2}$",
+            r"^[[:alpha:]_\-]+(?:\.[a-z]+)*$",
+            "^[]a]+$",
+            "^[^]a-]*$",
+        ] {
+            assert_eq!(error(pattern), None, "{pattern}");
+        }
+        for pattern in [
+            "(",
+            r"\bword\b",
+            r"\Aabc\z",
+            r"\p{L}+",
+            r"\x{41}",
+            "(?i)abc",
+            "(?P<name>a)",
+            "[a[b]]",
+            "[a-z&&[^x]]",
+            "[a--b]",
+        ] {
+            assert!(error(pattern).is_some(), "{pattern}");
+        }
+        assert_eq!(FieldConstraint::MinimalLength(1).pattern_error(), None);
+    }
+
+    #[test]
+    fn on_delete_action_sql_and_names() {
+        assert_eq!(OnDeleteAction::default(), OnDeleteAction::Cascade);
+        assert_eq!(OnDeleteAction::Restrict.to_sql_action(), "RESTRICT");
+        assert_eq!(OnDeleteAction::SetNull.to_sql_action(), "CASCADE");
+        assert!(OnDeleteAction::SetNull.detaches());
+        assert!(!OnDeleteAction::Cascade.detaches());
+        assert_eq!(OnDeleteAction::Detach.to_sql_action(), "CASCADE");
+        let parsed: OnDeleteAction = serde_json::from_str("\"detach\"").unwrap();
+        assert_eq!(parsed, OnDeleteAction::Detach);
+        let parsed: OnDeleteAction = serde_json::from_str("\"set null\"").unwrap();
+        assert_eq!(parsed, OnDeleteAction::SetNull);
+        let parsed: OnDeleteAction = serde_json::from_str("\"setNull\"").unwrap();
+        assert_eq!(parsed, OnDeleteAction::SetNull);
+    }
+
+    #[test]
+    fn workflow_stages_and_roles() {
+        let stage = |id: &str, roles: &[&str]| WorkflowStage {
+            id: WorkflowStageId::try_new(id).unwrap(),
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+        };
+        let workflow = Workflow {
+            stages: vec![stage("draft", &[]), stage("approved", &["editor"])],
+        };
+
+        assert_eq!(workflow.initial_stage().id.as_ref(), "draft");
+        assert!(workflow.stage("review").is_none());
+
+        let approved = workflow.stage("approved").unwrap();
+        assert!(approved.admits(&["author".to_string(), "editor".to_string()]));
+        assert!(!approved.admits(&["author".to_string()]));
+        assert!(workflow.initial_stage().admits(&[]));
+    }
+
+    #[test]
+    fn exposed_api_operations() {
+        let mut document = DocumentType::new_bare_collection("log", "log", "logs").unwrap();
+        assert!(document.exposes(ApiOperation::Delete));
+
+        document.options = Some(DocumentTypeOptions {
+            draft_and_publish: false,
+            localizations: Vec::new(),
+            workflow: None,
+            cache_control: None,
+            feed: None,
+            operations: serde_json::from_str(r#"["find", "findOne"]"#).unwrap(),
+            partitioning: None,
+            view: None,
+            retention: None,
+        });
+        assert!(document.exposes(ApiOperation::FindOne));
+        assert!(!document.exposes(ApiOperation::Create));
+        assert!(!document.exposes(ApiOperation::Publish));
+    }
+
+    #[test]
+    fn api_names_of_attributes() {
+        let mut document = DocumentType::new_bare_collection("person", "person", "people").unwrap();
+        let field = |id: &str, api_name: Option<&str>| DocumentField {
+            id: AttributeId::try_new(id).unwrap(),
+            field_type: FieldType::Text,
+            unique: false,
+            required: false,
+            constraints: Default::default(),
+            renamed_from: None,
+            api_name: api_name.map(String::from),
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        };
+        document
+            .fields
+            .insert(field("first_name", Some("givenName")));
+        document.fields.insert(field("last_name", None));
+
+        let first_name = AttributeId::try_new("first_name").unwrap();
+        let last_name = AttributeId::try_new("last_name").unwrap();
+        assert_eq!(document.api_name(&first_name), Some("givenName"));
+        assert_eq!(document.api_name(&last_name), None);
+    }
+
+    #[test]
+    fn cache_control_header_value() {
+        let cache_control = CacheControl {
+            max_age: Some(60),
+            s_maxage: None,
+            stale_while_revalidate: Some(30),
+        };
+        assert_eq!(
+            cache_control.header_value(),
+            "public, max-age=60, stale-while-revalidate=30"
+        );
+        assert_eq!(CacheControl::default().header_value(), "public");
+    }
+
     #[test]
     fn relation_type_flags() {
         assert!(RelationType::HasOne.is_owning());
@@ -426,6 +924,12 @@ mod tests {
             unique: true,
             required: false,
             constraints: Default::default(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
         };
 
         let f2 = DocumentField {
@@ -434,6 +938,12 @@ mod tests {
             unique: false,
             required: false,
             constraints: Default::default(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
         };
 
         fields.insert(f1);
@@ -446,6 +956,7 @@ mod tests {
             options: None,
             fields,
             relations: Default::default(),
+            renamed_from: None,
         };
 
         // has_localization false when options None
@@ -473,6 +984,7 @@ mod tests {
             options: None,
             fields: Default::default(),
             relations: Default::default(),
+            renamed_from: None,
         };
         // inserting duplicate by id should not increase set size
         assert!(!set.insert(dup));
@@ -5,9 +5,11 @@ use nutype::nutype;
 use regex::Regex;
 
 pub use crate::domain::entities::DocumentType;
+pub use crate::domain::relations::ResolvedRelation;
 
 pub mod entities;
 pub mod persistence;
+pub mod relations;
 
 #[cfg(feature = "test-helpers")]
 pub mod test_support;
@@ -24,6 +26,46 @@ pub trait DocumentTypesRegistry: Send + Sync + Debug + 'static {
     /// Returns the document type for the given API id (plural for Collection,
     /// singular for SingleType), if it exists.
     fn lookup(&self, api_id: &DocumentTypeApiId) -> Option<&DocumentType>;
+
+    /// Returns the relation `attribute` of the document type with the given
+    /// id, resolved against its target, if both exist.
+    fn relation(
+        &self,
+        id: &DocumentTypeId,
+        attribute: &AttributeId,
+    ) -> Option<ResolvedRelation<'_>> {
+        let owner = self.get(id)?;
+        let relation = owner.relations.get(attribute)?;
+        let target = self.get(&relation.target)?;
+        Some(ResolvedRelation {
+            owner,
+            relation,
+            target,
+        })
+    }
+
+    /// Returns the relations of the document type with the given id, ordered
+    /// by attribute and resolved against their targets; relations to unknown
+    /// document types are left out.
+    fn relations_of(&self, id: &DocumentTypeId) -> Vec<ResolvedRelation<'_>> {
+        let Some(owner) = self.get(id) else {
+            return Vec::new();
+        };
+        let mut relations = owner
+            .relations
+            .iter()
+            .filter_map(|relation| {
+                let target = self.get(&relation.target)?;
+                Some(ResolvedRelation {
+                    owner,
+                    relation,
+                    target,
+                })
+            })
+            .collect::<Vec<_>>();
+        relations.sort_by(|a, b| a.id().cmp(b.id()));
+        relations
+    }
 }
 
 // A regex for IDs/names that may contain only ASCII letters, digits, and underscore.
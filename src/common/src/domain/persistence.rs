@@ -1,5 +1,59 @@
-use crate::{AttributeId, DocumentType};
-use sea_query::{IntoIden, TableName, TableRef};
+use crate::{AttributeId, DocumentType, DocumentTypeId};
+use sea_query::{Alias, IntoIden, TableName, TableRef};
+
+/// Name of the table with the content change events waiting to be published
+/// to the event bus, shared by all document types
+pub const OUTBOX_TABLE_NAME: &str = "luminair_outbox";
+
+/// Name of the table with the responses of the writes sent with an
+/// `Idempotency-Key`, replayed when the write is retried
+pub const IDEMPOTENCY_TABLE_NAME: &str = "luminair_idempotency";
+
+/// Name of the table with the background jobs, queued for the job workers
+pub const JOBS_TABLE_NAME: &str = "luminair_jobs";
+
+/// Name of the table with the audit records of the erasures of the personal
+/// data of data subjects
+pub const ERASURES_TABLE_NAME: &str = "luminair_erasures";
+
+/// Name of the main table of the document type, shared by the service and the migration tool
+pub fn main_table_name(document: &DocumentTypeId) -> String {
+    document.normalized()
+}
+
+/// Name of the table with published revisions of the document type
+pub fn snapshot_table_name(document: &DocumentTypeId) -> String {
+    format!("{}_snapshots", document.normalized())
+}
+
+/// Name of the table with the previous contents of updated documents of the document type
+pub fn revision_table_name(document: &DocumentTypeId) -> String {
+    format!("{}_revisions", document.normalized())
+}
+
+/// Name of the table the expired documents of a document type with an
+/// archiving retention are moved to
+pub fn archived_table_name(document: &DocumentTypeId) -> String {
+    format!("{}_archived", document.normalized())
+}
+
+/// Name of the table with links of the owning relation
+pub fn relation_table_name(document: &DocumentTypeId, relation: &AttributeId) -> String {
+    format!(
+        "{}_{}_relation",
+        document.normalized(),
+        relation.normalized()
+    )
+}
+
+/// Name of the table with links of the owning relation for published revisions
+pub fn relation_snapshot_table_name(document: &DocumentTypeId, relation: &AttributeId) -> String {
+    format!(
+        "{}_{}_relation_snapshots",
+        document.normalized(),
+        relation.normalized()
+    )
+}
 
 #[derive(Debug)]
 pub enum TableNameProvider<'a> {
@@ -9,6 +63,12 @@ pub enum TableNameProvider<'a> {
     SnapshotTable {
         document: &'a DocumentType,
     },
+    RevisionTable {
+        document: &'a DocumentType,
+    },
+    ArchivedTable {
+        document: &'a DocumentType,
+    },
     RelationTable {
         document: &'a DocumentType,
         relation: &'a AttributeId,
@@ -22,18 +82,16 @@ pub enum TableNameProvider<'a> {
 impl<'a> TableNameProvider<'a> {
     pub fn table_name(&self) -> String {
         match self {
-            Self::MainTable { document } => document.id.normalized().to_string(),
-            Self::SnapshotTable { document } => format!("{}_snapshots", document.id.normalized()),
-            Self::RelationTable { document, relation } => format!(
-                "{}_{}_relation",
-                document.id.normalized(),
-                relation.normalized()
-            ),
-            Self::RelationSnapshotTable { document, relation } => format!(
-                "{}_{}_relation_snapshots",
-                document.id.normalized(),
-                relation.normalized()
-            ),
+            Self::MainTable { document } => main_table_name(&document.id),
+            Self::SnapshotTable { document } => snapshot_table_name(&document.id),
+            Self::RevisionTable { document } => revision_table_name(&document.id),
+            Self::ArchivedTable { document } => archived_table_name(&document.id),
+            Self::RelationTable { document, relation } => {
+                relation_table_name(&document.id, relation)
+            }
+            Self::RelationSnapshotTable { document, relation } => {
+                relation_snapshot_table_name(&document.id, relation)
+            }
         }
     }
 
@@ -44,6 +102,8 @@ impl<'a> TableNameProvider<'a> {
         match self {
             Self::MainTable { .. } => Self::MAIN_TABLE_ALIAS,
             Self::SnapshotTable { .. } => Self::MAIN_TABLE_ALIAS,
+            Self::RevisionTable { .. } => Self::MAIN_TABLE_ALIAS,
+            Self::ArchivedTable { .. } => Self::MAIN_TABLE_ALIAS,
             Self::RelationTable { .. } => Self::RELATION_TABLE_ALIAS,
             Self::RelationSnapshotTable { .. } => Self::RELATION_TABLE_ALIAS,
         }
@@ -52,11 +112,22 @@ impl<'a> TableNameProvider<'a> {
     pub fn qualified(&self) -> String {
         format!("{} AS \"{}\"", self.table_name(), self.alias())
     }
+
+    /// Table reference with a custom alias, for a table that is joined again
+    /// in a nested subquery where the default alias is already taken.
+    pub fn with_alias(&self, alias: &str) -> TableRef {
+        TableRef::Table(
+            TableName::from(self.table_name()),
+            Some(Alias::new(alias).into_iden()),
+        )
+    }
 }
 
 pub trait TableNameProviderConstructor<'a> {
     fn main_table(&'a self) -> TableNameProvider<'a>;
     fn snapshot_table(&'a self) -> TableNameProvider<'a>;
+    fn revision_table(&'a self) -> TableNameProvider<'a>;
+    fn archived_table(&'a self) -> TableNameProvider<'a>;
     fn relation_table(&'a self, relation: &'a AttributeId) -> TableNameProvider<'a>;
     fn relation_snapshot_table(&'a self, relation: &'a AttributeId) -> TableNameProvider<'a>;
 }
@@ -70,6 +141,14 @@ impl<'a> TableNameProviderConstructor<'a> for DocumentType {
         TableNameProvider::SnapshotTable { document: self }
     }
 
+    fn revision_table(&'a self) -> TableNameProvider<'a> {
+        TableNameProvider::RevisionTable { document: self }
+    }
+
+    fn archived_table(&'a self) -> TableNameProvider<'a> {
+        TableNameProvider::ArchivedTable { document: self }
+    }
+
     fn relation_table(&'a self, relation: &'a AttributeId) -> TableNameProvider<'a> {
         TableNameProvider::RelationTable {
             document: self,
@@ -113,6 +192,7 @@ mod tests {
             options: None,
             fields: Default::default(),
             relations: Default::default(),
+            renamed_from: None,
         }
     }
 
@@ -129,5 +209,26 @@ mod tests {
         assert_eq!(rel.table_name(), "product_owner_relation");
         assert_eq!(rel.alias(), "r");
         assert_eq!(rel.qualified(), "product_owner_relation AS \"r\"");
+
+        assert_eq!(doc.snapshot_table().table_name(), "product_snapshots");
+        assert_eq!(doc.revision_table().table_name(), "product_revisions");
+        assert_eq!(doc.revision_table().alias(), "m");
+        assert_eq!(doc.archived_table().table_name(), "product_archived");
+        assert_eq!(
+            doc.relation_snapshot_table(&attr).table_name(),
+            "product_owner_relation_snapshots"
+        );
+    }
+
+    #[test]
+    fn table_names_of_renamed_types() {
+        let id = DocumentTypeId::try_new("old-product").unwrap();
+        let attr = AttributeId::try_new("old-owner").unwrap();
+        assert_eq!(main_table_name(&id), "old_product");
+        assert_eq!(snapshot_table_name(&id), "old_product_snapshots");
+        assert_eq!(
+            relation_table_name(&id, &attr),
+            "old_product_old_owner_relation"
+        );
     }
 }
@@ -0,0 +1,179 @@
+use crate::domain::AttributeId;
+use crate::entities::{DocumentRelation, DocumentType};
+use crate::persistence::{TableNameProvider, TableNameProviderConstructor};
+
+/// A relation of a document type together with the document type it targets,
+/// as looked up by [`DocumentTypesRegistry::relation`] and
+/// [`DocumentTypesRegistry::relations_of`].
+///
+/// [`DocumentTypesRegistry::relation`]: crate::DocumentTypesRegistry::relation
+/// [`DocumentTypesRegistry::relations_of`]: crate::DocumentTypesRegistry::relations_of
+#[derive(Debug, Clone, Copy)]
+pub struct ResolvedRelation<'a> {
+    /// document type declaring the relation
+    pub owner: &'a DocumentType,
+    pub relation: &'a DocumentRelation,
+    /// document type of the related documents
+    pub target: &'a DocumentType,
+}
+
+impl<'a> ResolvedRelation<'a> {
+    /// Attribute of the relation on its owner.
+    pub fn id(&self) -> &'a AttributeId {
+        &self.relation.id
+    }
+
+    /// Whether the links of the relation are stored by its owner.
+    pub fn is_owning(&self) -> bool {
+        self.relation.relation_type.is_owning()
+    }
+
+    /// Table of the links of an owning relation: of the published revisions
+    /// when `published` and the owner has draft and publish.
+    pub fn links_table(&self, published: bool) -> TableNameProvider<'a> {
+        if published && self.owner.has_draft_and_publish() {
+            self.owner.relation_snapshot_table(&self.relation.id)
+        } else {
+            self.owner.relation_table(&self.relation.id)
+        }
+    }
+
+    /// Table of the related documents: of the published revisions when
+    /// `published` and the target has draft and publish.
+    pub fn target_table(&self, published: bool) -> TableNameProvider<'a> {
+        if published && self.target.has_draft_and_publish() {
+            self.target.snapshot_table()
+        } else {
+            self.target.main_table()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::domain::{DocumentTypeApiId, DocumentTypeId, DocumentTypesRegistry};
+    use crate::entities::{DocumentTypeOptions, OnDeleteAction, RelationType};
+
+    #[derive(Debug)]
+    struct Registry(Vec<DocumentType>);
+
+    impl DocumentTypesRegistry for Registry {
+        fn iterate(&self) -> Box<dyn Iterator<Item = &DocumentType> + '_> {
+            Box::new(self.0.iter())
+        }
+        fn get(&self, id: &DocumentTypeId) -> Option<&DocumentType> {
+            self.0.iter().find(|document_type| &document_type.id == id)
+        }
+        fn lookup(&self, _api_id: &DocumentTypeApiId) -> Option<&DocumentType> {
+            None
+        }
+    }
+
+    fn relation(id: &str, relation_type: RelationType, target: &str) -> DocumentRelation {
+        DocumentRelation {
+            id: AttributeId::try_new(id).unwrap(),
+            relation_type,
+            target: DocumentTypeId::try_new(target).unwrap(),
+            on_delete: OnDeleteAction::default(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+        }
+    }
+
+    fn registry() -> Registry {
+        let mut partner =
+            DocumentType::new_bare_collection("partner", "partner", "partners").unwrap();
+        partner.options = Some(DocumentTypeOptions {
+            draft_and_publish: true,
+            localizations: Vec::new(),
+            workflow: None,
+            cache_control: None,
+            feed: None,
+            operations: None,
+            partitioning: None,
+            view: None,
+            retention: None,
+        });
+        partner.relations = HashSet::from([
+            relation("brands", RelationType::HasMany, "brand"),
+            relation("category", RelationType::HasOne, "category"),
+            relation("owner", RelationType::BelongsToOne, "user"),
+        ]);
+        let mut category =
+            DocumentType::new_bare_collection("category", "category", "categories").unwrap();
+        category.relations =
+            HashSet::from([relation("partners", RelationType::BelongsToMany, "partner")]);
+        Registry(vec![
+            partner,
+            category,
+            DocumentType::new_bare_collection("brand", "brand", "brands").unwrap(),
+        ])
+    }
+
+    #[test]
+    fn test_relation_resolves_target_and_tables() {
+        let registry = registry();
+        let partner = DocumentTypeId::try_new("partner").unwrap();
+
+        let brands = registry
+            .relation(&partner, &AttributeId::try_new("brands").unwrap())
+            .unwrap();
+        assert_eq!(brands.owner.id, partner);
+        assert_eq!(brands.target.id.as_ref(), "brand");
+        assert!(brands.is_owning());
+        assert_eq!(
+            brands.links_table(true).table_name(),
+            "partner_brands_relation_snapshots"
+        );
+        assert_eq!(
+            brands.links_table(false).table_name(),
+            "partner_brands_relation"
+        );
+        // brands have no draft and publish, so no snapshots
+        assert_eq!(brands.target_table(true).table_name(), "brand");
+
+        let category = DocumentTypeId::try_new("category").unwrap();
+        let partners = registry
+            .relation(&category, &AttributeId::try_new("partners").unwrap())
+            .unwrap();
+        assert!(!partners.is_owning());
+        assert_eq!(
+            partners.target_table(true).table_name(),
+            "partner_snapshots"
+        );
+        assert_eq!(partners.target_table(false).table_name(), "partner");
+
+        // unknown relation, unknown target, unknown owner
+        for (owner, attribute) in [
+            ("partner", "logo"),
+            ("partner", "owner"),
+            ("user", "partners"),
+        ] {
+            let owner = DocumentTypeId::try_new(owner).unwrap();
+            let attribute = AttributeId::try_new(attribute).unwrap();
+            assert!(registry.relation(&owner, &attribute).is_none());
+        }
+    }
+
+    #[test]
+    fn test_relations_of_skips_unknown_targets() {
+        let registry = registry();
+        let partner = DocumentTypeId::try_new("partner").unwrap();
+
+        let relations = registry
+            .relations_of(&partner)
+            .into_iter()
+            .map(|relation| relation.id().as_ref().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(relations, ["brands", "category"]);
+        assert!(
+            registry
+                .relations_of(&DocumentTypeId::try_new("user").unwrap())
+                .is_empty()
+        );
+    }
+}
@@ -12,11 +12,15 @@ use crate::{
     AttributeId, DocumentTypeApiId,
     domain::{DocumentType, DocumentTypeId, DocumentTypesRegistry},
     entities::{
-        DocumentField, DocumentKind, DocumentRelation, DocumentTitle, DocumentTypeInfo,
-        DocumentTypeOptions, FieldType, LocalizationId, LocalizationIdError, RelationType,
+        ApiOperation, CacheControl, DocumentField, DocumentKind, DocumentRelation, DocumentTitle,
+        DocumentTypeInfo, DocumentTypeOptions, Feed, FieldType, LocalizationId,
+        LocalizationIdError, MaterializedView, OnDeleteAction, Partitioning, RelationType,
+        Retention, Workflow, WorkflowStage, WorkflowStageId,
     },
 };
 
+pub mod lint;
+
 pub fn load(schema_config_path: &str) -> Result<&'static dyn DocumentTypesRegistry, anyhow::Error> {
     let loaded = DocumentTypesRegistryAdapter::load(schema_config_path)?;
     // store loaded documents in static variable
@@ -69,17 +73,22 @@ impl DocumentTypesRegistryAdapter {
         })?;
 
         let mut types = HashSet::new();
-        for entry_res in entries {
-            let entry =
-                entry_res.map_err(|e| anyhow!("failed to read a directory entry: {}", e))?;
-            let path = entry.path();
-            if path.is_file() && is_json(&path) {
-                let document = load_document(&path)?;
-                let static_ref: &'static DocumentType = Box::leak(Box::new(document));
-                types.insert(static_ref);
-            }
+        load_documents(entries, false, &mut types)?;
+
+        // read-only types backed by materialized views
+        let views_path = dir_path.join("views");
+        if views_path.is_dir() {
+            let entries = fs::read_dir(&views_path).with_context(|| {
+                format!(
+                    "failed to read schema views directory: {}",
+                    views_path.to_string_lossy()
+                )
+            })?;
+            load_documents(entries, true, &mut types)?;
         }
 
+        check_relation_targets(&types)?;
+
         let mut map = HashMap::new();
         for dt in types.iter() {
             let api_id = match dt.kind {
@@ -93,8 +102,25 @@ impl DocumentTypesRegistryAdapter {
     }
 }
 
+fn load_documents(
+    entries: std::fs::ReadDir,
+    views: bool,
+    types: &mut HashSet<&'static DocumentType>,
+) -> Result<(), anyhow::Error> {
+    for entry_res in entries {
+        let entry = entry_res.map_err(|e| anyhow!("failed to read a directory entry: {}", e))?;
+        let path = entry.path();
+        if path.is_file() && is_json(&path) {
+            let document = load_document(&path, views)?;
+            let static_ref: &'static DocumentType = Box::leak(Box::new(document));
+            types.insert(static_ref);
+        }
+    }
+    Ok(())
+}
+
 // Use DeserializeOwned so the deserialized value owns its data and does not borrow from `content`.
-fn load_document(path: &Path) -> Result<DocumentType, anyhow::Error> {
+fn load_document(path: &Path, view: bool) -> Result<DocumentType, anyhow::Error> {
     use std::fs;
 
     let path_str = path.to_string_lossy().into_owned();
@@ -104,6 +130,12 @@ fn load_document(path: &Path) -> Result<DocumentType, anyhow::Error> {
 
     let document_record = serde_json::from_str::<DocumentRecord>(&content)
         .with_context(|| format!("failed to parse JSON entity config '{}'", path_str))?;
+    if view != document_record.sql.is_some() {
+        return Err(anyhow!(
+            "Invalid entity config '{}': only views, and all of them, declare a 'sql' query",
+            path_str
+        ));
+    }
 
     let id = path
         .file_stem()
@@ -127,6 +159,176 @@ mod tests {
         assert!(!is_json(Path::new("/tmp/a")));
     }
 
+    #[test]
+    fn check_feed_needs_texts_and_a_date() {
+        let field = |id: &str, field_type| DocumentField {
+            id: AttributeId::try_new(id).unwrap(),
+            field_type,
+            unique: false,
+            required: false,
+            constraints: HashSet::new(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        };
+        let fields = HashSet::from([
+            field("title", FieldType::LocalizedText),
+            field("summary", FieldType::Text),
+            field("published", FieldType::Date),
+        ]);
+        let feed = |title: &str, date: &str| Feed {
+            title: AttributeId::try_new(title).unwrap(),
+            description: AttributeId::try_new("summary").unwrap(),
+            date: AttributeId::try_new(date).unwrap(),
+        };
+
+        assert!(
+            check_feed(
+                &feed("title", "published"),
+                DocumentKind::Collection,
+                &fields
+            )
+            .is_ok()
+        );
+        for (feed, kind, error) in [
+            (
+                feed("title", "published"),
+                DocumentKind::SingleType,
+                "Invalid feed: only collection types have a feed",
+            ),
+            (
+                feed("published", "published"),
+                DocumentKind::Collection,
+                "Invalid feed: field 'published' is not a text",
+            ),
+            (
+                feed("title", "summary"),
+                DocumentKind::Collection,
+                "Invalid feed: field 'summary' is not a date or date-time",
+            ),
+            (
+                feed("title", "created"),
+                DocumentKind::Collection,
+                "Invalid feed: field 'created' doesn't exist",
+            ),
+        ] {
+            let result = check_feed(&feed, kind, &fields);
+            assert_eq!(result.unwrap_err().to_string(), error);
+        }
+    }
+
+    #[test]
+    fn check_partitioning_rejects_unique_fields() {
+        let field = |unique| DocumentField {
+            id: AttributeId::try_new("code").unwrap(),
+            field_type: FieldType::Text,
+            unique,
+            required: false,
+            constraints: HashSet::new(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        };
+        let relations = HashSet::new();
+
+        let fields = HashSet::from([field(false)]);
+        assert!(check_partitioning(DocumentKind::Collection, &fields, &relations).is_ok());
+        assert_eq!(
+            check_partitioning(DocumentKind::SingleType, &fields, &relations)
+                .unwrap_err()
+                .to_string(),
+            "Invalid partitioning: only collection types are partitioned"
+        );
+
+        let fields = HashSet::from([field(true)]);
+        assert_eq!(
+            check_partitioning(DocumentKind::Collection, &fields, &relations)
+                .unwrap_err()
+                .to_string(),
+            "Invalid partitioning: field 'code' of a partitioned type can't be unique"
+        );
+    }
+
+    #[test]
+    fn check_api_names_must_be_valid_and_distinct() {
+        let field = |id: &str, api_name: Option<&str>| DocumentField {
+            id: AttributeId::try_new(id).unwrap(),
+            field_type: FieldType::Text,
+            unique: false,
+            required: false,
+            constraints: HashSet::new(),
+            renamed_from: None,
+            api_name: api_name.map(String::from),
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        };
+        let relations = HashSet::new();
+
+        let fields = HashSet::from([field("first_name", Some("givenName")), field("title", None)]);
+        assert!(check_api_names(&fields, &relations).is_ok());
+
+        for (fields, error) in [
+            (
+                HashSet::from([field("first_name", Some("given-name"))]),
+                "Invalid apiName 'given-name' of attribute 'first_name': only letters, digits and underscores are allowed, starting with a letter",
+            ),
+            (
+                HashSet::from([field("first_name", Some("title")), field("title", None)]),
+                "Invalid apiName 'title' of attribute 'first_name': it names another attribute",
+            ),
+        ] {
+            let result = check_api_names(&fields, &relations);
+            assert_eq!(result.unwrap_err().to_string(), error);
+        }
+    }
+
+    #[test]
+    fn view_options_expose_only_reads() {
+        let options = view_options(None, "SELECT 1").unwrap();
+        assert_eq!(
+            options.operations,
+            Some(vec![ApiOperation::Find, ApiOperation::FindOne])
+        );
+        assert_eq!(options.view.unwrap().sql, "SELECT 1");
+
+        let options = DocumentTypeOptions {
+            operations: Some(vec![ApiOperation::Find, ApiOperation::Create]),
+            ..view_options(None, "SELECT 1").unwrap()
+        };
+        assert_eq!(
+            view_options(Some(options), "SELECT 1")
+                .unwrap_err()
+                .to_string(),
+            "Invalid view: only the 'find' and 'findOne' operations are exposed"
+        );
+    }
+
+    #[test]
+    fn check_retention_of_collections_only() {
+        let retention = Retention::ArchiveAfterDays(30);
+        assert!(check_retention(DocumentKind::Collection, retention).is_ok());
+        assert_eq!(
+            check_retention(DocumentKind::SingleType, retention)
+                .unwrap_err()
+                .to_string(),
+            "Invalid retention: only the documents of collection types expire"
+        );
+        assert_eq!(
+            check_retention(DocumentKind::Collection, Retention::DeleteAfterDays(0))
+                .unwrap_err()
+                .to_string(),
+            "Invalid retention: documents are kept for a day at least"
+        );
+    }
+
     // The more comprehensive parsing test was moved to an integration test using
     // the `tempfile` crate to ensure safe cleanup.
 }
@@ -142,6 +344,9 @@ struct DocumentRecord<'a> {
     info: DocumentInfoRecord<'a>,
     options: Option<DocumentOptionsRecord<'a>>,
     attributes: HashMap<&'a str, AttributeRecord<'a>>,
+    renamed_from: Option<&'a str>,
+    /// query of the materialized view of a read-only type, declared in `views/`
+    sql: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -162,6 +367,43 @@ struct DocumentOptionsRecord<'a> {
     draft_and_publish: bool,
     #[serde(default)]
     localizations: Vec<&'a str>,
+    #[serde(default)]
+    workflow: Option<WorkflowRecord<'a>>,
+    #[serde(default)]
+    cache_control: Option<CacheControl>,
+    #[serde(default)]
+    feed: Option<FeedRecord<'a>>,
+    #[serde(default)]
+    operations: Option<Vec<ApiOperation>>,
+    #[serde(default)]
+    partitioning: Option<Partitioning>,
+    #[serde(default)]
+    retention: Option<Retention>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(bound = "'de: 'a")]
+#[serde(rename_all = "camelCase")]
+struct FeedRecord<'a> {
+    title: &'a str,
+    description: &'a str,
+    date: &'a str,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(bound = "'de: 'a")]
+#[serde(rename_all = "camelCase")]
+struct WorkflowRecord<'a> {
+    stages: Vec<WorkflowStageRecord<'a>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(bound = "'de: 'a")]
+#[serde(rename_all = "camelCase")]
+struct WorkflowStageRecord<'a> {
+    name: &'a str,
+    #[serde(default)]
+    roles: Vec<&'a str>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -177,11 +419,34 @@ enum AttributeRecord<'a> {
         required: bool,
         #[serde(default)]
         constraints: HashSet<FieldConstraint>,
+        /// whether a `dateTime` field is stored with its time zone
+        #[serde(default, rename = "timeZone")]
+        time_zone: Option<bool>,
+        #[serde(default, rename = "renamedFrom")]
+        renamed_from: Option<&'a str>,
+        #[serde(default, rename = "apiName")]
+        api_name: Option<&'a str>,
+        #[serde(default)]
+        description: Option<&'a str>,
+        #[serde(default)]
+        filterable: bool,
+        #[serde(default)]
+        sortable: bool,
+        #[serde(default)]
+        personal: bool,
     },
     Relation {
         #[serde(alias = "relation")]
         relation_type: RelationType,
         target: &'a str,
+        #[serde(default, rename = "onDelete")]
+        on_delete: Option<OnDeleteAction>,
+        #[serde(default, rename = "renamedFrom")]
+        renamed_from: Option<&'a str>,
+        #[serde(default, rename = "apiName")]
+        api_name: Option<&'a str>,
+        #[serde(default)]
+        description: Option<&'a str>,
     },
 }
 
@@ -195,11 +460,18 @@ impl<'a> TryFrom<(&'a str, DocumentRecord<'a>)> for DocumentType {
         let record = &value.1;
         let kind = record.kind;
         let info = DocumentTypeInfo::try_from(&record.info)?;
-        let options = record
+        let mut options = record
             .options
             .as_ref()
             .map(DocumentTypeOptions::try_from)
             .transpose()?;
+        if let Some(sql) = &record.sql {
+            options = Some(view_options(options, sql)?);
+        }
+        let renamed_from = record
+            .renamed_from
+            .map(DocumentTypeId::try_new)
+            .transpose()?;
 
         let mut fields = HashSet::new();
         let mut relations = HashSet::new();
@@ -214,8 +486,24 @@ impl<'a> TryFrom<(&'a str, DocumentRecord<'a>)> for DocumentType {
                     unique,
                     required,
                     constraints,
+                    time_zone,
+                    renamed_from,
+                    api_name,
+                    description,
+                    filterable,
+                    sortable,
+                    personal,
                 } => {
-                    let field_type = *field_type;
+                    let field_type = match (*field_type, *time_zone) {
+                        (FieldType::DateTime, Some(false)) => FieldType::LocalDateTime,
+                        (FieldType::DateTime, _) | (_, None) => *field_type,
+                        (_, Some(_)) => {
+                            return Err(anyhow!(
+                                "Invalid timeZone for field '{}': only dateTime fields have a time zone",
+                                id
+                            ));
+                        }
+                    };
 
                     let constraints_are_valid = constraints
                         .iter()
@@ -227,7 +515,18 @@ impl<'a> TryFrom<(&'a str, DocumentRecord<'a>)> for DocumentType {
                             field_type
                         ));
                     }
+                    if let Some(error) = constraints.iter().find_map(|c| c.pattern_error()) {
+                        return Err(anyhow!("Invalid pattern for field '{}': it {}", id, error));
+                    }
+                    // erasure clears the personal fields
+                    if *personal && *required {
+                        return Err(anyhow!(
+                            "Invalid personal field '{}': personal data is erased by clearing it, the field can't be required",
+                            id
+                        ));
+                    }
                     let constraints = constraints.iter().cloned().collect();
+                    let renamed_from = renamed_from.map(AttributeId::try_new).transpose()?;
 
                     let field = DocumentField {
                         id,
@@ -235,25 +534,64 @@ impl<'a> TryFrom<(&'a str, DocumentRecord<'a>)> for DocumentType {
                         unique: *unique,
                         required: *required,
                         constraints,
+                        renamed_from,
+                        api_name: api_name.map(String::from),
+                        description: description.map(String::from),
+                        filterable: *filterable,
+                        sortable: *sortable,
+                        personal: *personal,
                     };
                     fields.insert(field);
                 }
                 AttributeRecord::Relation {
                     relation_type,
                     target,
+                    on_delete,
+                    renamed_from,
+                    api_name,
+                    description,
                 } => {
+                    // the links are stored, and deleted, by the owning side
+                    if on_delete.is_some() && !relation_type.is_owning() {
+                        return Err(anyhow!(
+                            "Invalid onDelete action for relation '{}': only owning relations (hasOne, hasMany) have one",
+                            id
+                        ));
+                    }
                     let target = DocumentTypeId::try_new(target.to_owned())?;
+                    let renamed_from = renamed_from.map(AttributeId::try_new).transpose()?;
 
                     let relation = DocumentRelation {
                         id,
                         relation_type: *relation_type,
                         target,
+                        on_delete: on_delete.unwrap_or_default(),
+                        renamed_from,
+                        api_name: api_name.map(String::from),
+                        description: description.map(String::from),
                     };
                     relations.insert(relation);
                 }
             }
         }
 
+        check_api_names(&fields, &relations)?;
+        if let Some(feed) = options.as_ref().and_then(|options| options.feed.as_ref()) {
+            check_feed(feed, kind, &fields)?;
+        }
+        if options
+            .as_ref()
+            .is_some_and(|options| options.partitioning.is_some())
+        {
+            check_partitioning(kind, &fields, &relations)?;
+        }
+        if let Some(retention) = options.as_ref().and_then(|options| options.retention) {
+            check_retention(kind, retention)?;
+        }
+        if record.sql.is_some() && !relations.is_empty() {
+            return Err(anyhow!("Invalid view: a view can't have relations"));
+        }
+
         Ok(Self {
             id,
             kind,
@@ -261,6 +599,7 @@ impl<'a> TryFrom<(&'a str, DocumentRecord<'a>)> for DocumentType {
             options,
             fields,
             relations,
+            renamed_from,
         })
     }
 }
@@ -275,13 +614,242 @@ impl<'a> TryFrom<&DocumentOptionsRecord<'a>> for DocumentTypeOptions {
             .iter()
             .map(|localization| LocalizationId::try_new(localization.to_owned()))
             .collect();
+        let workflow = value
+            .workflow
+            .as_ref()
+            .map(Workflow::try_from)
+            .transpose()?;
+        let feed = value.feed.as_ref().map(Feed::try_from).transpose()?;
         Ok(Self {
             draft_and_publish,
             localizations: localizations?,
+            workflow,
+            cache_control: value.cache_control,
+            feed,
+            operations: value.operations.clone(),
+            partitioning: value.partitioning,
+            view: None,
+            retention: value.retention,
         })
     }
 }
 
+/// The API names of the attributes must be valid GraphQL names, and name no
+/// other attribute, by its id or its API name.
+fn check_api_names(
+    fields: &HashSet<DocumentField>,
+    relations: &HashSet<DocumentRelation>,
+) -> anyhow::Result<()> {
+    let attributes: Vec<(&AttributeId, Option<&str>)> = fields
+        .iter()
+        .map(|field| (&field.id, field.api_name.as_deref()))
+        .chain(
+            relations
+                .iter()
+                .map(|relation| (&relation.id, relation.api_name.as_deref())),
+        )
+        .collect();
+    for (id, api_name) in &attributes {
+        let Some(api_name) = api_name else {
+            continue;
+        };
+        let valid = api_name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && api_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(anyhow!(
+                "Invalid apiName '{}' of attribute '{}': only letters, digits and underscores are allowed, starting with a letter",
+                api_name,
+                id
+            ));
+        }
+        let taken = attributes.iter().any(|(other, other_name)| {
+            other != id && (other.as_ref() == *api_name || *other_name == Some(*api_name))
+        });
+        if taken {
+            return Err(anyhow!(
+                "Invalid apiName '{}' of attribute '{}': it names another attribute",
+                api_name,
+                id
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The feed fields of a collection type must be texts and a date.
+fn check_feed(
+    feed: &Feed,
+    kind: DocumentKind,
+    fields: &HashSet<DocumentField>,
+) -> anyhow::Result<()> {
+    if kind != DocumentKind::Collection {
+        return Err(anyhow!("Invalid feed: only collection types have a feed"));
+    }
+    let field_type = |id: &AttributeId| {
+        fields
+            .get(id)
+            .map(|field| field.field_type)
+            .ok_or_else(|| anyhow!("Invalid feed: field '{}' doesn't exist", id))
+    };
+    for id in [&feed.title, &feed.description] {
+        if !field_type(id)?.is_text() {
+            return Err(anyhow!("Invalid feed: field '{}' is not a text", id));
+        }
+    }
+    if !matches!(
+        field_type(&feed.date)?,
+        FieldType::Date | FieldType::DateTime
+    ) {
+        return Err(anyhow!(
+            "Invalid feed: field '{}' is not a date or date-time",
+            feed.date
+        ));
+    }
+    Ok(())
+}
+
+/// Unique constraints of a partitioned table must include its partition key,
+/// and foreign keys can't reference it, so a partitioned type is a collection
+/// without unique fields or relations.
+fn check_partitioning(
+    kind: DocumentKind,
+    fields: &HashSet<DocumentField>,
+    relations: &HashSet<DocumentRelation>,
+) -> anyhow::Result<()> {
+    if kind != DocumentKind::Collection {
+        return Err(anyhow!(
+            "Invalid partitioning: only collection types are partitioned"
+        ));
+    }
+    if let Some(field) = fields.iter().find(|field| field.unique) {
+        return Err(anyhow!(
+            "Invalid partitioning: field '{}' of a partitioned type can't be unique",
+            field.id
+        ));
+    }
+    if let Some(relation) = relations.iter().next() {
+        return Err(anyhow!(
+            "Invalid partitioning: a partitioned type can't have relations, like '{}'",
+            relation.id
+        ));
+    }
+    Ok(())
+}
+
+/// Documents are kept for a day at least, and only those of collection
+/// types expire: a single type has one document, which is never recreated.
+fn check_retention(kind: DocumentKind, retention: Retention) -> anyhow::Result<()> {
+    if kind != DocumentKind::Collection {
+        return Err(anyhow!(
+            "Invalid retention: only the documents of collection types expire"
+        ));
+    }
+    if retention.days() == 0 {
+        return Err(anyhow!(
+            "Invalid retention: documents are kept for a day at least"
+        ));
+    }
+    Ok(())
+}
+
+/// Options of a read-only type backed by the materialized view of `sql`:
+/// published documents only, read by `find` and `findOne`.
+fn view_options(
+    options: Option<DocumentTypeOptions>,
+    sql: &str,
+) -> anyhow::Result<DocumentTypeOptions> {
+    let mut options = options.unwrap_or(DocumentTypeOptions {
+        draft_and_publish: false,
+        localizations: Vec::new(),
+        workflow: None,
+        cache_control: None,
+        feed: None,
+        operations: None,
+        partitioning: None,
+        view: None,
+        retention: None,
+    });
+    if options.draft_and_publish
+        || options.workflow.is_some()
+        || options.partitioning.is_some()
+        || options.retention.is_some()
+    {
+        return Err(anyhow!(
+            "Invalid view: a view has no drafts, workflow, partitions or retention"
+        ));
+    }
+    let read_only = [ApiOperation::Find, ApiOperation::FindOne];
+    match &options.operations {
+        Some(operations) if operations.iter().any(|op| !read_only.contains(op)) => {
+            return Err(anyhow!(
+                "Invalid view: only the 'find' and 'findOne' operations are exposed"
+            ));
+        }
+        Some(_) => {}
+        None => options.operations = Some(read_only.to_vec()),
+    }
+    options.view = Some(MaterializedView {
+        sql: sql.to_string(),
+    });
+    Ok(options)
+}
+
+/// Relations can't target a partitioned type or a view, the links couldn't
+/// reference their documents by a foreign key.
+fn check_relation_targets(types: &HashSet<&'static DocumentType>) -> anyhow::Result<()> {
+    for document in types {
+        for relation in &document.relations {
+            let Some(target) = types.get(&relation.target) else {
+                continue;
+            };
+            if target.partitioning().is_some() || target.view().is_some() {
+                return Err(anyhow!(
+                    "Invalid relation '{}' of '{}': its target '{}' is partitioned or a view",
+                    relation.id,
+                    document.id,
+                    relation.target
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+impl<'a> TryFrom<&FeedRecord<'a>> for Feed {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &FeedRecord<'a>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            title: AttributeId::try_new(value.title)?,
+            description: AttributeId::try_new(value.description)?,
+            date: AttributeId::try_new(value.date)?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&WorkflowRecord<'a>> for Workflow {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &WorkflowRecord<'a>) -> Result<Self, Self::Error> {
+        if value.stages.is_empty() {
+            return Err(anyhow!("Invalid workflow: at least one stage is required"));
+        }
+
+        let mut stages: Vec<WorkflowStage> = Vec::with_capacity(value.stages.len());
+        for record in value.stages.iter() {
+            let id = WorkflowStageId::try_new(record.name)?;
+            if stages.iter().any(|stage| stage.id == id) {
+                return Err(anyhow!("Invalid workflow: stage '{}' is repeated", id));
+            }
+            let roles = record.roles.iter().map(|role| role.to_string()).collect();
+            stages.push(WorkflowStage { id, roles });
+        }
+        Ok(Self { stages })
+    }
+}
+
 impl<'a> TryFrom<&DocumentInfoRecord<'a>> for DocumentTypeInfo {
     type Error = anyhow::Error;
 
@@ -0,0 +1,618 @@
+//! Validation of a directory of schema files without a database, reporting
+//! every problem found instead of failing on the first one.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Serialize;
+
+use super::{AttributeRecord, DocumentRecord, is_json};
+use crate::entities::{
+    DocumentKind, DocumentTitle, DocumentType, FieldConstraint, FieldType, LocalizationId, Workflow,
+};
+use crate::persistence::{
+    main_table_name, relation_snapshot_table_name, relation_table_name, revision_table_name,
+    snapshot_table_name,
+};
+use crate::{AttributeId, DocumentTypeId};
+
+/// Severity of a diagnostic: errors keep the schema from loading, warnings
+/// point at likely mistakes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem found in a schema file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// path of the schema file
+    pub file: String,
+    /// line of the problem, for files which aren't valid JSON
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// column of the problem, for files which aren't valid JSON
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    pub severity: Severity,
+    /// kind of the problem, e.g. `unknown-target`
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    /// The diagnostic as compilers print them:
+    /// `brands.json:3:7: error[invalid-json]: expected value`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, ":{line}:{column}")?;
+        }
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, ": {}[{}]: {}", severity, self.code, self.message)
+    }
+}
+
+/// Lint the schema files of the `schema_config_path` directory and of its
+/// `views/` subfolder: their ids,
+/// locale codes, constraints and relations, and the names their document
+/// types and attributes would collide on in the API and the database.
+///
+/// Fails only when the directory or a file of it can't be read.
+pub fn lint(schema_config_path: &str) -> anyhow::Result<Vec<Diagnostic>> {
+    let dir_path = Path::new(schema_config_path);
+    let entries = std::fs::read_dir(dir_path).with_context(|| {
+        format!(
+            "failed to read schema config directory: {}",
+            dir_path.to_string_lossy()
+        )
+    })?;
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry.context("failed to read a directory entry")?.path();
+        if path.is_file() && is_json(&path) {
+            paths.push(path);
+        }
+    }
+    let views_path = dir_path.join("views");
+    if views_path.is_dir() {
+        let entries = std::fs::read_dir(&views_path).with_context(|| {
+            format!(
+                "failed to read schema views directory: {}",
+                views_path.to_string_lossy()
+            )
+        })?;
+        for entry in entries {
+            let path = entry.context("failed to read a directory entry")?.path();
+            if path.is_file() && is_json(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read '{}'", path.to_string_lossy()))?;
+        let id = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        files.push((path.to_string_lossy().into_owned(), id, content));
+    }
+
+    let mut linter = Linter::default();
+    let mut schemas = Vec::with_capacity(files.len());
+    for (file, id, content) in &files {
+        match serde_json::from_str::<DocumentRecord>(content) {
+            Ok(record) => schemas.push(Schema { file, id, record }),
+            Err(error) => {
+                let code = if error.is_data() {
+                    "invalid-schema"
+                } else {
+                    "invalid-json"
+                };
+                let message = error.to_string();
+                let message = message.split(" at line ").next().unwrap_or_default();
+                linter.diagnostics.push(Diagnostic {
+                    file: file.clone(),
+                    line: Some(error.line()),
+                    column: Some(error.column()),
+                    severity: Severity::Error,
+                    code,
+                    message: message.to_string(),
+                });
+            }
+        }
+    }
+
+    for schema in &schemas {
+        linter.check_document_type(schema);
+        // only the files of `views/` declare the query of a view
+        let in_views = Path::new(schema.file).parent() == Some(views_path.as_path());
+        if in_views != schema.record.sql.is_some() {
+            linter.error(
+                schema.file,
+                "misplaced-view",
+                "only the schema files in 'views/', and all of them, have a 'sql' query"
+                    .to_string(),
+            );
+        }
+    }
+    linter.check_relations(&schemas);
+    linter.check_collisions(&schemas);
+
+    // whatever the checks above missed, loading the file would report
+    for schema in &schemas {
+        if linter.has_errors(schema.file) {
+            continue;
+        }
+        let loaded: Result<DocumentType, _> = (schema.id, schema.record.clone()).try_into();
+        if let Err(error) = loaded {
+            linter.error(schema.file, "invalid-schema", error.to_string());
+        }
+    }
+
+    let mut diagnostics = linter.diagnostics;
+    diagnostics.sort_by(|a, b| a.file.cmp(&b.file));
+    Ok(diagnostics)
+}
+
+/// Names of the columns every main table has, which attributes can't take.
+const RESERVED_COLUMNS: [&str; 14] = [
+    crate::ID_FIELD_NAME,
+    crate::DOCUMENT_ID_FIELD_NAME,
+    crate::STATUS_FIELD_NAME,
+    crate::CREATED_FIELD_NAME,
+    crate::UPDATED_FIELD_NAME,
+    crate::PUBLISHED_FIELD_NAME,
+    crate::CREATED_BY_FIELD_NAME,
+    crate::UPDATED_BY_FIELD_NAME,
+    crate::PUBLISHED_BY_FIELD_NAME,
+    crate::VERSION_FIELD_NAME,
+    crate::REVISION_FIELD_NAME,
+    crate::PUBLISH_AT_FIELD_NAME,
+    crate::UNPUBLISH_AT_FIELD_NAME,
+    crate::WORKFLOW_STAGE_FIELD_NAME,
+];
+
+/// A schema file which is valid JSON of a document type.
+struct Schema<'a> {
+    file: &'a String,
+    /// id of the document type, the name of the file
+    id: &'a str,
+    record: DocumentRecord<'a>,
+}
+
+impl Schema<'_> {
+    fn attributes(&self) -> Vec<(&str, &AttributeRecord<'_>)> {
+        let mut attributes = self
+            .record
+            .attributes
+            .iter()
+            .map(|(name, record)| (*name, record))
+            .collect::<Vec<_>>();
+        attributes.sort_by_key(|(name, _)| *name);
+        attributes
+    }
+
+    /// Whether the document type has an owning relation to `target`.
+    fn owns_relation_to(&self, target: &str) -> bool {
+        self.record.attributes.values().any(|record| {
+            matches!(record, AttributeRecord::Relation { relation_type, target: other, .. }
+                if relation_type.is_owning() && same_id(other, target))
+        })
+    }
+}
+
+#[derive(Default)]
+struct Linter {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter {
+    fn error(&mut self, file: &str, code: &'static str, message: String) {
+        self.push(file, Severity::Error, code, message);
+    }
+
+    fn warning(&mut self, file: &str, code: &'static str, message: String) {
+        self.push(file, Severity::Warning, code, message);
+    }
+
+    fn push(&mut self, file: &str, severity: Severity, code: &'static str, message: String) {
+        self.diagnostics.push(Diagnostic {
+            file: file.to_string(),
+            line: None,
+            column: None,
+            severity,
+            code,
+            message,
+        });
+    }
+
+    fn has_errors(&self, file: &str) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.file == file && diagnostic.severity == Severity::Error)
+    }
+
+    /// Check the ids, options and attributes of one document type.
+    fn check_document_type(&mut self, schema: &Schema) {
+        let file = schema.file;
+        let record = &schema.record;
+        if let Err(error) = DocumentTypeId::try_new(schema.id) {
+            self.error(
+                file,
+                "invalid-id",
+                format!("the file name '{}' isn't a valid id: {error}", schema.id),
+            );
+        }
+        if let Err(error) = DocumentTitle::try_new(record.info.title) {
+            self.error(file, "invalid-title", format!("invalid title: {error}"));
+        }
+        for (key, name) in [
+            ("singularName", record.info.singular_name),
+            ("pluralName", record.info.plural_name),
+        ] {
+            if let Err(error) = DocumentTypeId::try_new(name) {
+                self.error(
+                    file,
+                    "invalid-id",
+                    format!("{key} '{name}' isn't a valid id: {error}"),
+                );
+            }
+        }
+
+        if let Some(options) = &record.options {
+            let mut locales = HashSet::new();
+            for locale in &options.localizations {
+                if LocalizationId::try_new(*locale).is_err() {
+                    self.error(
+                        file,
+                        "invalid-locale",
+                        format!("'{locale}' isn't a supported locale code"),
+                    );
+                } else if !locales.insert(locale.trim().to_lowercase()) {
+                    self.warning(
+                        file,
+                        "duplicate-locale",
+                        format!("locale '{locale}' is listed more than once"),
+                    );
+                }
+            }
+            if let Some(Err(error)) = options.workflow.as_ref().map(Workflow::try_from) {
+                self.error(file, "invalid-workflow", error.to_string());
+            }
+        }
+
+        let mut columns: HashMap<String, &str> = HashMap::new();
+        for (name, attribute) in schema.attributes() {
+            let id = match AttributeId::try_new(name) {
+                Ok(id) => id,
+                Err(error) => {
+                    self.error(
+                        file,
+                        "invalid-id",
+                        format!("attribute '{name}' isn't a valid id: {error}"),
+                    );
+                    continue;
+                }
+            };
+            let column = id.normalized();
+            if RESERVED_COLUMNS.contains(&column.as_str()) {
+                self.error(
+                    file,
+                    "reserved-attribute",
+                    format!("attribute '{name}' takes the name of the system column '{column}'"),
+                );
+            }
+            if let Some(other) = columns.insert(column.clone(), name) {
+                self.error(
+                    file,
+                    "attribute-collision",
+                    format!("attributes '{other}' and '{name}' are both stored as '{column}'"),
+                );
+            }
+
+            match attribute {
+                AttributeRecord::Field {
+                    field_type,
+                    constraints,
+                    renamed_from,
+                    ..
+                } => {
+                    self.check_constraints(file, name, *field_type, constraints);
+                    self.check_renamed_from(file, name, *renamed_from);
+                }
+                AttributeRecord::Relation {
+                    relation_type,
+                    target,
+                    on_delete,
+                    renamed_from,
+                    ..
+                } => {
+                    if on_delete.is_some() && !relation_type.is_owning() {
+                        self.error(
+                            file,
+                            "invalid-on-delete",
+                            format!(
+                                "relation '{name}' is an inverse one, only owning relations have an onDelete action"
+                            ),
+                        );
+                    }
+                    if let Err(error) = DocumentTypeId::try_new(*target) {
+                        self.error(
+                            file,
+                            "invalid-id",
+                            format!(
+                                "target '{target}' of relation '{name}' isn't a valid id: {error}"
+                            ),
+                        );
+                    }
+                    self.check_renamed_from(file, name, *renamed_from);
+                }
+            }
+        }
+    }
+
+    fn check_constraints(
+        &mut self,
+        file: &str,
+        name: &str,
+        field_type: FieldType,
+        constraints: &HashSet<FieldConstraint>,
+    ) {
+        let mut length = (None, None);
+        let mut value = (None, None);
+        let mut constraints = constraints.iter().collect::<Vec<_>>();
+        constraints.sort_by_key(|constraint| format!("{constraint:?}"));
+        for constraint in constraints {
+            if !constraint.is_applicable_for(field_type) {
+                self.error(
+                    file,
+                    "inapplicable-constraint",
+                    format!(
+                        "constraint {constraint:?} of field '{name}' doesn't apply to {field_type:?} fields"
+                    ),
+                );
+                continue;
+            }
+            match constraint {
+                FieldConstraint::Pattern(_) => {
+                    if let Some(error) = constraint.pattern_error() {
+                        self.error(
+                            file,
+                            "invalid-pattern",
+                            format!("pattern of field '{name}' {error}"),
+                        );
+                    }
+                }
+                FieldConstraint::MinimalLength(min) => length.0 = Some(*min as i64),
+                FieldConstraint::MaximalLength(max) => length.1 = Some(*max as i64),
+                FieldConstraint::MinimalIntegerValue(min) => value.0 = Some(i64::from(*min)),
+                FieldConstraint::MaximalIntegerValue(max) => value.1 = Some(i64::from(*max)),
+            }
+        }
+        for (bounds, what) in [(length, "length"), (value, "value")] {
+            match bounds {
+                (Some(min), Some(max)) if min > max => self.error(
+                    file,
+                    "contradictory-constraints",
+                    format!(
+                        "minimal {what} {min} of field '{name}' exceeds its maximal {what} {max}"
+                    ),
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    fn check_renamed_from(&mut self, file: &str, name: &str, renamed_from: Option<&str>) {
+        let Some(renamed_from) = renamed_from else {
+            return;
+        };
+        if let Err(error) = AttributeId::try_new(renamed_from) {
+            self.error(
+                file,
+                "invalid-id",
+                format!(
+                    "renamedFrom '{renamed_from}' of attribute '{name}' isn't a valid id: {error}"
+                ),
+            );
+        }
+    }
+
+    /// Check that relations target document types of the schema, and that
+    /// inverse relations have an owning relation on their target.
+    fn check_relations(&mut self, schemas: &[Schema]) {
+        for schema in schemas {
+            for (name, attribute) in schema.attributes() {
+                let AttributeRecord::Relation {
+                    relation_type,
+                    target,
+                    ..
+                } = attribute
+                else {
+                    continue;
+                };
+                let Some(target_schema) = schemas.iter().find(|other| same_id(other.id, target))
+                else {
+                    self.error(
+                        schema.file,
+                        "unknown-target",
+                        format!("relation '{name}' targets the unknown document type '{target}'"),
+                    );
+                    continue;
+                };
+                if relation_type.is_inverse() && !target_schema.owns_relation_to(schema.id) {
+                    self.warning(
+                        schema.file,
+                        "unpaired-inverse",
+                        format!(
+                            "inverse relation '{name}' has no owning relation of '{target}' to '{}'",
+                            schema.id
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Check that no two document types share an API id or a table.
+    fn check_collisions(&mut self, schemas: &[Schema]) {
+        let mut api_ids: HashMap<String, &str> = HashMap::new();
+        let mut tables: HashMap<String, &str> = HashMap::new();
+        for schema in schemas {
+            let Ok(id) = DocumentTypeId::try_new(schema.id) else {
+                continue;
+            };
+            let api_id = match schema.record.kind {
+                DocumentKind::Collection => schema.record.info.plural_name,
+                DocumentKind::SingleType => schema.record.info.singular_name,
+            };
+            let api_id = api_id.trim().to_lowercase();
+            if let Some(other) = api_ids.get(&api_id) {
+                self.error(
+                    schema.file,
+                    "api-id-collision",
+                    format!("API id '{api_id}' is already the one of '{other}'"),
+                );
+            } else {
+                api_ids.insert(api_id, schema.file);
+            }
+
+            let mut names = vec![
+                main_table_name(&id),
+                snapshot_table_name(&id),
+                revision_table_name(&id),
+            ];
+            for (name, attribute) in schema.attributes() {
+                let AttributeRecord::Relation { relation_type, .. } = attribute else {
+                    continue;
+                };
+                if !relation_type.is_owning() {
+                    continue;
+                }
+                if let Ok(relation) = AttributeId::try_new(name) {
+                    names.push(relation_table_name(&id, &relation));
+                    names.push(relation_snapshot_table_name(&id, &relation));
+                }
+            }
+            for table in names {
+                match tables.get(&table) {
+                    Some(other) if other != schema.file => self.error(
+                        schema.file,
+                        "table-collision",
+                        format!("table '{table}' is already the one of '{other}'"),
+                    ),
+                    Some(_) => {}
+                    None => {
+                        tables.insert(table, schema.file);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether two document type ids are the same once sanitized.
+fn same_id(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(diagnostics: &[Diagnostic], file: &str) -> Vec<&'static str> {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.file.ends_with(file))
+            .map(|diagnostic| diagnostic.code)
+            .collect()
+    }
+
+    #[test]
+    fn test_lint_reports_every_problem() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, content: &str| std::fs::write(dir.path().join(name), content);
+        write(
+            "brands.json",
+            r#"{
+              "type": "collection",
+              "info": { "title": "Brands", "singularName": "brand", "pluralName": "brands" },
+              "options": { "localizations": ["en", "xx"] },
+              "attributes": {
+                "name": { "type": "text", "constraints": [{ "minimalLength": 9 }, { "maximalLength": 3 }] },
+                "code": { "type": "text", "constraints": [{ "pattern": "[a-" }] },
+                "rank": { "type": "boolean", "constraints": [{ "minimalIntegerValue": 1 }] },
+                "version": { "type": { "integer": "int32" } },
+                "partners": { "relation": "belongsToMany", "target": "partners", "onDelete": "restrict" },
+                "owner": { "relation": "hasOne", "target": "users", "onDelete": "setNull" }
+              }
+            }"#,
+        )
+        .unwrap();
+        write(
+            "partners.json",
+            r#"{
+              "type": "collection",
+              "info": { "title": "Partners", "singularName": "partner", "pluralName": "brands" },
+              "attributes": {
+                "legal_entity": { "type": "text" },
+                "legal-entity": { "type": "text" }
+              }
+            }"#,
+        )
+        .unwrap();
+        write("broken.json", "{\n  \"type\": \"collection\",\n}").unwrap();
+        write("notes.txt", "not a schema").unwrap();
+
+        let diagnostics = lint(dir.path().to_str().unwrap()).unwrap();
+
+        assert_eq!(
+            codes(&diagnostics, "brands.json"),
+            [
+                "invalid-locale",
+                "invalid-pattern",
+                "contradictory-constraints",
+                "invalid-on-delete",
+                "inapplicable-constraint",
+                "reserved-attribute",
+                "unknown-target",
+                "unpaired-inverse",
+            ]
+        );
+        assert_eq!(
+            codes(&diagnostics, "partners.json"),
+            ["attribute-collision", "api-id-collision"]
+        );
+
+        let broken = diagnostics
+            .iter()
+            .find(|diagnostic| diagnostic.file.ends_with("broken.json"))
+            .unwrap();
+        assert_eq!((broken.line, broken.column), (Some(3), Some(1)));
+        assert_eq!(broken.code, "invalid-json");
+        assert!(
+            broken
+                .to_string()
+                .ends_with("broken.json:3:1: error[invalid-json]: trailing comma")
+        );
+    }
+
+    #[test]
+    fn test_lint_accepts_the_example_schema() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config/schema");
+        let diagnostics = lint(path).unwrap();
+        assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+    }
+}
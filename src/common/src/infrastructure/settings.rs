@@ -0,0 +1,192 @@
+use std::env;
+
+use anyhow::Context;
+use config::builder::DefaultState;
+use config::{Config, ConfigBuilder, ConfigError, Environment, File, Map};
+use dotenvy::dotenv;
+use serde::de::DeserializeOwned;
+
+/// Prefix of the environment variables overriding settings: `APP_SERVER_PORT`
+/// sets `server_port`, `APP_DATABASE_HOST` sets `database.host`.
+pub const ENV_PREFIX: &str = "APP";
+
+/// Suffix of the environment variables naming a file which holds the value
+/// of a setting, like a Docker or Kubernetes secret:
+/// `APP_DATABASE_CREDENTIALS_PASSWORD_FILE=/run/secrets/db-password`.
+pub const FILE_SUFFIX: &str = "_FILE";
+
+/// Environment variable naming a configuration file to use instead of
+/// `config.toml` or `config.yaml` of the working directory.
+pub const CONFIG_FILE_ENV: &str = "CONFIG_FILE";
+
+/// Load the settings of type `T` from layers of sources, each one overriding
+/// the former ones:
+///
+/// 1. `config/default`, the defaults;
+/// 2. `config/{RUN_MODE}`, `development` by default, when present;
+/// 3. the file of `CONFIG_FILE`, or else `config.toml` or `config.yaml` of
+///    the working directory, when present;
+/// 4. the `APP_` environment variables, those of `.env` included;
+/// 5. the `APP_.._FILE` environment variables, read from the file they name.
+///
+/// `configure` adds the defaults and overrides of the caller, given the run
+/// mode. Settings which are missing or invalid are reported by their key.
+pub fn load<T: DeserializeOwned>(
+    configure: impl FnOnce(
+        ConfigBuilder<DefaultState>,
+        &str,
+    ) -> Result<ConfigBuilder<DefaultState>, ConfigError>,
+) -> anyhow::Result<T> {
+    dotenv().ok();
+    let run_mode = env::var("RUN_MODE").unwrap_or_else(|_| "development".into());
+
+    let local = match env::var(CONFIG_FILE_ENV) {
+        Ok(path) => File::with_name(&path),
+        Err(_) => File::with_name("./config").required(false),
+    };
+    let builder = Config::builder()
+        .add_source(File::with_name("./config/default"))
+        .add_source(File::with_name(&format!("./config/{run_mode}")).required(false))
+        .add_source(local)
+        .add_source(
+            Environment::with_prefix(ENV_PREFIX)
+                .separator("_")
+                .source(Some(environment(env::vars())?)),
+        );
+    let config = configure(builder, &run_mode)?
+        .build()
+        .context("failed to read config")?;
+    deserialize(config)
+}
+
+/// The `APP_` variables of `vars`, with those of a `_FILE` suffix replaced by
+/// the contents of their file, under their name without the suffix.
+fn environment(
+    vars: impl Iterator<Item = (String, String)>,
+) -> anyhow::Result<Map<String, String>> {
+    let prefix = format!("{ENV_PREFIX}_");
+    let (files, values): (Vec<_>, Vec<_>) = vars
+        .filter(|(name, _)| name.to_ascii_uppercase().starts_with(&prefix))
+        .partition(|(name, _)| name.to_ascii_uppercase().ends_with(FILE_SUFFIX));
+    let mut values: Map<_, _> = values.into_iter().collect();
+
+    for (name, path) in files {
+        let value = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {name} from '{path}'"))?;
+        let name = name[..name.len() - FILE_SUFFIX.len()].to_string();
+        values.insert(name, value.trim_end_matches(['\r', '\n']).to_string());
+    }
+    Ok(values)
+}
+
+/// `config` as `T`, failing with the key of the first setting which is
+/// missing or invalid.
+fn deserialize<T: DeserializeOwned>(config: Config) -> anyhow::Result<T> {
+    serde_path_to_error::deserialize(config).map_err(|error| {
+        let path = error.path().to_string();
+        match error.into_inner() {
+            ConfigError::NotFound(field) => {
+                // the path leads to the parent of the missing setting, whose
+                // own name is the last segment of `field`
+                let name = field.rsplit('.').next().unwrap_or(&field);
+                let key = if path == "." {
+                    name.to_string()
+                } else {
+                    format!("{path}.{name}")
+                };
+                anyhow::anyhow!(
+                    "missing setting '{}', set it in a config file or as {}",
+                    key,
+                    env_name(&key)
+                )
+            }
+            error => anyhow::anyhow!("invalid setting '{}': {}", path, error),
+        }
+    })
+}
+
+/// Environment variable of the setting `key`: "database.host" → "APP_DATABASE_HOST"
+fn env_name(key: &str) -> String {
+    format!(
+        "{ENV_PREFIX}_{}",
+        key.replace('.', "_").to_ascii_uppercase()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use config::FileFormat;
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct TestSettings {
+        port: u16,
+        database: TestDatabase,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct TestDatabase {
+        host: String,
+    }
+
+    fn config(yaml: &str) -> Config {
+        Config::builder()
+            .add_source(File::from_str(yaml, FileFormat::Yaml))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_errors_name_the_setting() {
+        let error = deserialize::<TestSettings>(config("port: 80\ndatabase: {name: luminair}"))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "missing setting 'database.host', set it in a config file or as APP_DATABASE_HOST"
+        );
+
+        let error = deserialize::<TestSettings>(config("database: {host: db}")).unwrap_err();
+        assert!(error.to_string().starts_with("missing setting 'port'"));
+
+        let error =
+            deserialize::<TestSettings>(config("port: eighty\ndatabase: {host: db}")).unwrap_err();
+        assert!(error.to_string().starts_with("invalid setting 'port'"));
+    }
+
+    #[test]
+    fn test_environment_reads_secret_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret = dir.path().join("db-password");
+        std::fs::write(&secret, "s3cret\n").unwrap();
+
+        let vars = [
+            ("APP_DATABASE_HOST", "db"),
+            (
+                "APP_DATABASE_CREDENTIALS_PASSWORD_FILE",
+                secret.to_str().unwrap(),
+            ),
+            ("HOME", "/root"),
+        ];
+        let values = environment(
+            vars.into_iter()
+                .map(|(name, value)| (name.to_string(), value.to_string())),
+        )
+        .unwrap();
+
+        assert_eq!(values.len(), 2);
+        assert_eq!(values["APP_DATABASE_HOST"], "db");
+        assert_eq!(values["APP_DATABASE_CREDENTIALS_PASSWORD"], "s3cret");
+
+        let missing = [("APP_TOKEN_FILE".to_string(), "/no/such/file".to_string())];
+        let error = environment(missing.into_iter()).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("failed to read APP_TOKEN_FILE")
+        );
+    }
+}
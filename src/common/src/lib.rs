@@ -21,16 +21,31 @@ pub const PUBLISHED_BY_FIELD_NAME: &str = "published_by_id";
 pub const VERSION_FIELD_NAME: &str = "version";
 pub const REVISION_FIELD_NAME: &str = "revision";
 
+pub const PUBLISH_AT_FIELD_NAME: &str = "publish_at";
+pub const UNPUBLISH_AT_FIELD_NAME: &str = "unpublish_at";
+
+pub const WORKFLOW_STAGE_FIELD_NAME: &str = "workflow_stage";
+
+pub const ARCHIVED_FIELD_NAME: &str = "archived_at";
+
+pub const LOCALES_UPDATED_FIELD_NAME: &str = "locales_updated_at";
+
 pub const OWNING_ID_FIELD_NAME: &str = "owning_id";
 pub const INVERSE_ID_FIELD_NAME: &str = "inverse_id";
 pub const OWNING_DOCUMENT_ID_FIELD_NAME: &str = "owning_document_id";
 pub const SNAPSHOT_ID_FIELD_NAME: &str = "snapshot_id";
+pub const REVISION_ID_FIELD_NAME: &str = "revision_id";
 
 // expose domain module
 
 pub use domain::*;
+pub use infrastructure::documents::lint as schema_lint;
 pub use infrastructure::documents::load as load_documents;
 
 // expose database module
 
 pub use infrastructure::database;
+
+// expose settings module
+
+pub use infrastructure::settings;
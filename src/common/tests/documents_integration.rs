@@ -9,6 +9,7 @@ fn load_documents_from_directory() {
     let content = r#"
     {
       "type": "collection",
+      "renamedFrom": "my-old-type",
       "info": {
         "title": "My Type",
         "description": "desc",
@@ -17,18 +18,35 @@ fn load_documents_from_directory() {
       },
       "options": {
         "draftAndPublish": true,
-        "localizations": ["en"]
+        "localizations": ["en"],
+        "workflow": {
+          "stages": [
+            { "name": "draft" },
+            { "name": "Review" },
+            { "name": "approved", "roles": ["editor"] }
+          ]
+        }
       },
       "attributes": {
         "name": {
           "type": "text",
           "unique": true,
           "required": true,
-          "constraints": []
+          "constraints": [],
+          "renamedFrom": "title",
+          "description": "Name shown in listings"
+        },
+        "opens": {
+          "type": "dateTime",
+          "timeZone": false
+        },
+        "closes": {
+          "type": "time"
         },
         "owner": {
-          "relation": "belongsToOne",
-          "target": "user"
+          "relation": "hasOne",
+          "target": "user",
+          "onDelete": "setNull"
         }
       }
     }
@@ -37,6 +55,28 @@ fn load_documents_from_directory() {
     file.write_all(content.as_bytes()).expect("write");
     file.sync_all().expect("sync");
 
+    // read-only type backed by a materialized view
+    std::fs::create_dir(dir.path().join("views")).expect("create views dir");
+    let view_content = r#"
+    {
+      "type": "collection",
+      "info": {
+        "title": "My Type Totals",
+        "singularName": "mytype-total",
+        "pluralName": "mytype-totals"
+      },
+      "sql": "SELECT document_id, name FROM mytype",
+      "attributes": {
+        "name": { "type": "text" }
+      }
+    }
+    "#;
+    std::fs::write(
+        dir.path().join("views").join("mytype-total.json"),
+        view_content,
+    )
+    .expect("write view");
+
     // call public loader
     let registry = common::load_documents(dir.path().to_str().unwrap()).expect("load docs");
     // lookup by api id (plural for collection)
@@ -45,6 +85,51 @@ fn load_documents_from_directory() {
         .expect("found");
     assert_eq!(dt.id.as_ref(), "mytype");
     assert!(dt.has_draft_and_publish());
+    let workflow = dt.workflow().expect("workflow");
+    assert_eq!(workflow.initial_stage().id.as_ref(), "draft");
+    assert!(workflow.stage("review").is_some());
+    assert_eq!(workflow.stage("approved").unwrap().roles, vec!["editor"]);
+    assert_eq!(
+        dt.renamed_from.as_ref().map(|id| id.as_ref()),
+        Some("my-old-type")
+    );
+    let name = dt
+        .fields
+        .get(&common::AttributeId::try_new("name").unwrap())
+        .expect("field");
+    assert_eq!(
+        name.renamed_from.as_ref().map(|id| id.as_ref()),
+        Some("title")
+    );
+    assert_eq!(name.description.as_deref(), Some("Name shown in listings"));
+    let field_type = |id: &str| {
+        dt.fields
+            .get(&common::AttributeId::try_new(id).unwrap())
+            .map(|field| field.field_type)
+    };
+    assert_eq!(
+        field_type("opens"),
+        Some(common::entities::FieldType::LocalDateTime)
+    );
+    assert_eq!(
+        field_type("closes"),
+        Some(common::entities::FieldType::Time)
+    );
+    let owner = dt
+        .relations
+        .get(&common::AttributeId::try_new("owner").unwrap())
+        .expect("relation");
+    assert_eq!(owner.on_delete, common::entities::OnDeleteAction::SetNull);
+
+    let view = registry
+        .lookup(&common::DocumentTypeApiId::try_new("mytype-totals").unwrap())
+        .expect("found view");
+    assert_eq!(
+        view.view().map(|view| view.sql.as_str()),
+        Some("SELECT document_id, name FROM mytype")
+    );
+    assert!(view.exposes(common::entities::ApiOperation::FindOne));
+    assert!(!view.exposes(common::entities::ApiOperation::Create));
 
     // tempdir is dropped and cleaned up automatically
 }
@@ -1,18 +1,40 @@
+use crate::domain::history::{AppliedMigration, checksum};
 use crate::domain::migration::{
-    MigrationStep, MigrationStepItem, documents_into_tables, plan_migration,
+    MigrationOptions, MigrationStep, MigrationStepItem, documents_into_tables, plan_migration,
+    render_script,
 };
+use crate::domain::partitions::create_partitions_ddl;
+use crate::domain::seeds::Seed;
 use crate::domain::tables::Table;
+use crate::domain::views::{ExistingView, documents_into_views, plan_views, refresh_views_ddl};
+use chrono::NaiveDate;
 use luminair_common::DocumentTypesRegistry;
+use luminair_common::persistence::main_table_name;
 use std::future::Future;
 
 pub trait Persistence: Send + Sync + Clone + 'static {
     /// load persistence from database
     fn load(&self) -> impl Future<Output = Result<Vec<Table>, anyhow::Error>>;
+    /// load materialized views from database
+    fn load_views(&self) -> impl Future<Output = Result<Vec<ExistingView>, anyhow::Error>>;
     /// apply migration steps to database
     fn apply_migration_steps(
         &self,
         steps: Vec<MigrationStepItem>,
     ) -> impl Future<Output = Result<(), anyhow::Error>>;
+    /// load history of applied migration steps, oldest first
+    fn applied_migrations(
+        &self,
+    ) -> impl Future<Output = Result<Vec<AppliedMigration>, anyhow::Error>>;
+    /// write seed documents into database in one transaction, returns number of written documents
+    fn apply_seeds(&self, seeds: Vec<Seed>) -> impl Future<Output = Result<u64, anyhow::Error>>;
+    /// create partitions of partitioned tables, one DDL after the other
+    fn create_partitions(
+        &self,
+        ddls: Vec<String>,
+    ) -> impl Future<Output = Result<(), anyhow::Error>>;
+    /// refresh materialized views, one DDL after the other
+    fn refresh_views(&self, ddls: Vec<String>) -> impl Future<Output = Result<(), anyhow::Error>>;
     /// extract database schema
     fn database_schema(&self) -> &str;
 }
@@ -21,6 +43,7 @@ pub trait Persistence: Send + Sync + Clone + 'static {
 pub struct Migration<P: Persistence> {
     documents: &'static dyn DocumentTypesRegistry,
     persistence: P,
+    options: MigrationOptions,
 }
 
 impl<P: Persistence> Migration<P> {
@@ -28,37 +51,148 @@ impl<P: Persistence> Migration<P> {
         Self {
             documents,
             persistence,
+            options: MigrationOptions::default(),
         }
     }
 
+    /// override default options used while planning migration
+    pub fn with_options(mut self, options: MigrationOptions) -> Self {
+        self.options = options;
+        self
+    }
+
     /// migrate database schema conform documents configuration
     pub async fn migrate(&self, dry_run: bool) -> Result<(), anyhow::Error> {
+        if dry_run {
+            println!("--- DRY-RUN: The following SQL DDL would be executed ---");
+            print!("{}", self.dry_run_script().await?);
+            return Ok(());
+        }
+
+        let steps = self.plan().await?;
+        self.persistence.apply_migration_steps(steps).await?;
+
+        Ok(())
+    }
+
+    /// write initial content into migrated database
+    pub async fn seed(&self, seeds: Vec<Seed>) -> Result<(), anyhow::Error> {
+        if seeds.is_empty() {
+            return Ok(());
+        }
+        let documents: usize = seeds.iter().map(|seed| seed.documents.len()).sum();
+        let written = self.persistence.apply_seeds(seeds).await?;
+        println!(
+            "Seeds applied: {} of {} documents written",
+            written, documents
+        );
+        Ok(())
+    }
+
+    /// create the partitions of the partitioned types for the interval of `today`
+    /// and the ones ahead of it, returns the number of partitioned types
+    pub async fn create_partitions(&self, today: NaiveDate) -> Result<usize, anyhow::Error> {
+        let mut ddls = Vec::new();
+        let mut partitioned = 0;
+        for document in self.documents.iterate() {
+            let Some(partitioning) = document.partitioning() else {
+                continue;
+            };
+            ddls.extend(create_partitions_ddl(
+                self.persistence.database_schema(),
+                &main_table_name(&document.id),
+                partitioning,
+                today,
+            ));
+            partitioned += 1;
+        }
+        if !ddls.is_empty() {
+            self.persistence.create_partitions(ddls).await?;
+        }
+        Ok(partitioned)
+    }
+
+    /// refresh the views of the read-only types with the current results of
+    /// their queries, returns the number of refreshed views
+    pub async fn refresh_views(&self) -> Result<usize, anyhow::Error> {
+        let views = documents_into_views(self.documents);
+        if !views.is_empty() {
+            let ddls = refresh_views_ddl(self.persistence.database_schema(), &views);
+            self.persistence.refresh_views(ddls).await?;
+        }
+        Ok(views.len())
+    }
+
+    /// render SQL script with all migration steps without applying them
+    pub async fn dry_run_script(&self) -> Result<String, anyhow::Error> {
+        let steps = self.plan().await?;
+        Ok(render_script(self.persistence.database_schema(), steps))
+    }
+
+    /// count migration steps pending to bring database conform documents configuration
+    pub async fn pending_steps(&self) -> Result<usize, anyhow::Error> {
+        Ok(self.plan().await?.len())
+    }
+
+    async fn plan(&self) -> Result<Vec<MigrationStepItem>, anyhow::Error> {
         let needed_schema = documents_into_tables(self.documents);
         let actual_schema = self.persistence.load().await?;
 
-        let steps = plan_migration(
+        let mut steps = plan_migration(
             &needed_schema,
             &actual_schema,
             self.persistence.database_schema(),
+            &self.options,
         )?;
 
-        if dry_run {
-            println!("--- DRY-RUN: The following SQL DDL would be executed ---");
-            if steps.is_empty() {
-                println!("No migration steps needed. Database schema is up to date.");
-            } else {
-                for step in &steps {
-                    println!("-- Context: {}", step.ctx());
-                    for ddl in step.clone().ddls() {
-                        println!("{};", ddl);
-                    }
-                }
-            }
-            return Ok(());
+        // views select from the tables, so they follow the table steps
+        let needed_views = documents_into_views(self.documents);
+        let actual_views = self.persistence.load_views().await?;
+        steps.extend(plan_views(
+            &needed_views,
+            &actual_views,
+            self.persistence.database_schema(),
+        ));
+
+        Ok(steps)
+    }
+
+    /// print applied migration steps and steps pending to bring database conform documents configuration
+    pub async fn status(&self) -> Result<(), anyhow::Error> {
+        let applied = self.persistence.applied_migrations().await?;
+        let steps = self.plan().await?;
+
+        println!("--- Applied migration steps ---");
+        if applied.is_empty() {
+            println!("No migration steps applied yet.");
+        }
+        for migration in &applied {
+            println!(
+                "{}  {:<14} {}  ({} ms)",
+                migration.applied_at.format("%Y-%m-%d %H:%M:%S"),
+                migration.ctx,
+                short_checksum(&migration.checksum),
+                migration.duration_ms
+            );
         }
 
-        self.persistence.apply_migration_steps(steps).await?;
+        println!("--- Pending migration steps ---");
+        if steps.is_empty() {
+            println!("No migration steps needed. Database schema is up to date.");
+        }
+        for step in steps {
+            let ctx = step.ctx();
+            let ddls = step.ddls();
+            println!("-- Context: {} ({})", ctx, short_checksum(&checksum(&ddls)));
+            for ddl in ddls {
+                println!("{};", ddl);
+            }
+        }
 
         Ok(())
     }
 }
+
+fn short_checksum(checksum: &str) -> &str {
+    checksum.get(..12).unwrap_or(checksum)
+}
@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+/// Name of the table which keeps history of applied migration steps
+pub const MIGRATIONS_TABLE_NAME: &str = "luminair_migrations";
+
+/// Migration step recorded in the history table after it was applied
+#[derive(Debug, Clone)]
+pub struct AppliedMigration {
+    pub checksum: String,
+    pub ctx: String,
+    pub ddl: String,
+    pub applied_at: DateTime<Utc>,
+    pub duration_ms: i64,
+}
+
+/// Hex encoded SHA-256 of the step DDLs, identifies the step in the history table
+pub fn checksum(ddls: &[String]) -> String {
+    let mut hasher = Sha256::new();
+    for ddl in ddls {
+        hasher.update(ddl.as_bytes());
+        hasher.update(b";\n");
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum_is_stable_and_sensitive_to_ddls() {
+        let ddls = vec!["CREATE TABLE a".to_string(), "CREATE TABLE b".to_string()];
+        let same = vec!["CREATE TABLE a".to_string(), "CREATE TABLE b".to_string()];
+        let other = vec!["CREATE TABLE a".to_string()];
+
+        assert_eq!(checksum(&ddls), checksum(&same));
+        assert_ne!(checksum(&ddls), checksum(&other));
+        assert_eq!(checksum(&ddls).len(), 64);
+    }
+}
@@ -1,18 +1,62 @@
-use luminair_common::DocumentTypesRegistry;
+use luminair_common::{DOCUMENT_ID_FIELD_NAME, DocumentTypesRegistry};
 
 use crate::domain::DocumentTables;
 use crate::domain::dependency::{DependencyError, resolve_table_order};
-use crate::domain::tables::{Column, ColumnType, ForeignKeyConstraint, Index, Table};
+use crate::domain::history::checksum;
+use crate::domain::schema::{erasures_table, idempotency_table, jobs_table, outbox_table};
+use crate::domain::tables::{
+    Column, ColumnType, FieldConstraint, ForeignKeyConstraint, Index, IndexMethod, Table,
+};
+use crate::domain::views::{VIEW_COMMENT_PREFIX, View};
+
+/// Prefix given to columns which are renamed instead of dropped after their attribute disappeared
+pub const DELETED_COLUMN_PREFIX: &str = "_deleted_";
+
+/// Provides the operator class of the trigram GIN indexes
+const TRIGRAM_EXTENSION_DDL: &str = "CREATE EXTENSION IF NOT EXISTS pg_trgm";
 
 pub trait MigrationStep {
     fn ctx(&self) -> &'static str;
     fn ddls(self) -> Vec<String>;
+    /// whether DDLs of the step can be executed inside a transaction
+    fn transactional(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum MigrationStepItem {
     Create(CreateTableStep),
     Drop(DropTableStep),
+    RenameTable(RenameTableStep),
+    AddColumn(AddColumnStep),
+    DropColumn(DropColumnStep),
+    RenameColumn(RenameColumnStep),
+    CreateIndex(CreateIndexStep),
+    AlterForeignKey(AlterForeignKeyStep),
+    AlterChecks(AlterChecksStep),
+    CreateView(CreateViewStep),
+    DropView(DropViewStep),
+}
+
+/// How columns existing in the database but no longer described by the schema are handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RemovedColumnsPolicy {
+    /// Leave orphaned columns untouched and only report them
+    #[default]
+    Warn,
+    /// Rename orphaned columns to `_deleted_<name>` and make them nullable, keeping the data
+    Rename,
+    /// Drop orphaned columns together with their data
+    Drop,
+}
+
+/// Options which tune how the migration plan is built
+#[derive(Debug, Clone, Default)]
+pub struct MigrationOptions {
+    pub removed_columns: RemovedColumnsPolicy,
+    /// create indexes on existing tables with `CREATE INDEX CONCURRENTLY`
+    pub concurrent_indexes: bool,
 }
 
 impl MigrationStep for MigrationStepItem {
@@ -20,6 +64,15 @@ impl MigrationStep for MigrationStepItem {
         match self {
             MigrationStepItem::Create(step) => step.ctx(),
             MigrationStepItem::Drop(step) => step.ctx(),
+            MigrationStepItem::RenameTable(step) => step.ctx(),
+            MigrationStepItem::AddColumn(step) => step.ctx(),
+            MigrationStepItem::DropColumn(step) => step.ctx(),
+            MigrationStepItem::RenameColumn(step) => step.ctx(),
+            MigrationStepItem::CreateIndex(step) => step.ctx(),
+            MigrationStepItem::AlterForeignKey(step) => step.ctx(),
+            MigrationStepItem::AlterChecks(step) => step.ctx(),
+            MigrationStepItem::CreateView(step) => step.ctx(),
+            MigrationStepItem::DropView(step) => step.ctx(),
         }
     }
 
@@ -27,6 +80,22 @@ impl MigrationStep for MigrationStepItem {
         match self {
             MigrationStepItem::Create(step) => step.ddls(),
             MigrationStepItem::Drop(step) => step.ddls(),
+            MigrationStepItem::RenameTable(step) => step.ddls(),
+            MigrationStepItem::AddColumn(step) => step.ddls(),
+            MigrationStepItem::DropColumn(step) => step.ddls(),
+            MigrationStepItem::RenameColumn(step) => step.ddls(),
+            MigrationStepItem::CreateIndex(step) => step.ddls(),
+            MigrationStepItem::AlterForeignKey(step) => step.ddls(),
+            MigrationStepItem::AlterChecks(step) => step.ddls(),
+            MigrationStepItem::CreateView(step) => step.ddls(),
+            MigrationStepItem::DropView(step) => step.ddls(),
+        }
+    }
+
+    fn transactional(&self) -> bool {
+        match self {
+            MigrationStepItem::CreateIndex(step) => step.transactional(),
+            _ => true,
         }
     }
 }
@@ -78,11 +147,354 @@ impl MigrationStep for DropTableStep {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct RenameTableStep {
+    pub ddls: Vec<String>,
+}
+
+impl RenameTableStep {
+    /// Renames table together with the indexes created for it,
+    /// so that their names keep following the table name
+    pub fn new(database_schema: &str, old_name: &str, table: &Table) -> Self {
+        let mut ddls = vec![
+            rename_table_ddl(database_schema, old_name, &table.name),
+            rename_index_ddl(
+                database_schema,
+                &format!("{}_pkey", old_name),
+                &format!("{}_pkey", table.name),
+            ),
+        ];
+        for column in table.columns.iter().filter(|c| c.unique) {
+            let column_name = column.renamed_from.as_deref().unwrap_or(&column.name);
+            ddls.push(rename_index_ddl(
+                database_schema,
+                &format!("{}_{}_key", old_name, column_name),
+                &format!("{}_{}_key", table.name, column_name),
+            ));
+        }
+        for index in table.indexes.iter() {
+            ddls.push(rename_index_ddl(
+                database_schema,
+                &index_name(old_name, index),
+                &index_name(&table.name, index),
+            ));
+        }
+        Self { ddls }
+    }
+}
+
+impl MigrationStep for RenameTableStep {
+    fn ctx(&self) -> &'static str {
+        "RENAME TABLE"
+    }
+
+    fn ddls(self) -> Vec<String> {
+        self.ddls
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AddColumnStep {
+    pub ddls: Vec<String>,
+}
+
+impl AddColumnStep {
+    /// Adds `column` to the existing table; a NOT NULL column without a default
+    /// can't be added to rows which are already there, so it's added nullable
+    pub fn new(database_schema: &str, table_name: &str, column: &Column) -> Self {
+        let mut column = column.clone();
+        if column.not_null && column.default_value.is_none() {
+            eprintln!(
+                "Warning: column '{}' of table '{}' is added nullable, it has no default for the existing rows",
+                column.name, table_name
+            );
+            column.not_null = false;
+        }
+        Self {
+            ddls: vec![add_column_ddl(database_schema, table_name, &column)],
+        }
+    }
+}
+
+impl MigrationStep for AddColumnStep {
+    fn ctx(&self) -> &'static str {
+        "ADD COLUMN"
+    }
+
+    fn ddls(self) -> Vec<String> {
+        self.ddls
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DropColumnStep {
+    pub schema: String,
+    pub table_name: String,
+    pub column_name: String,
+}
+
+impl DropColumnStep {
+    pub fn new(database_schema: &str, table_name: &str, column_name: &str) -> Self {
+        Self {
+            schema: database_schema.to_string(),
+            table_name: table_name.to_string(),
+            column_name: column_name.to_string(),
+        }
+    }
+}
+
+impl MigrationStep for DropColumnStep {
+    fn ctx(&self) -> &'static str {
+        "DROP COLUMN"
+    }
+
+    fn ddls(self) -> Vec<String> {
+        vec![drop_column_ddl(
+            &self.schema,
+            &self.table_name,
+            &self.column_name,
+        )]
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RenameColumnStep {
+    pub ddls: Vec<String>,
+}
+
+impl RenameColumnStep {
+    /// Renames column and, when `drop_not_null` is set, relaxes its NOT NULL constraint
+    /// so that inserts which no longer know about the column keep working
+    pub fn new(
+        database_schema: &str,
+        table_name: &str,
+        column_name: &str,
+        new_name: &str,
+        drop_not_null: bool,
+    ) -> Self {
+        let mut ddls = vec![rename_column_ddl(
+            database_schema,
+            table_name,
+            column_name,
+            new_name,
+        )];
+        if drop_not_null {
+            ddls.push(drop_not_null_ddl(database_schema, table_name, new_name));
+        }
+        Self { ddls }
+    }
+}
+
+impl MigrationStep for RenameColumnStep {
+    fn ctx(&self) -> &'static str {
+        "RENAME COLUMN"
+    }
+
+    fn ddls(self) -> Vec<String> {
+        self.ddls
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateIndexStep {
+    pub ddls: Vec<String>,
+    pub concurrently: bool,
+}
+
+impl CreateIndexStep {
+    pub fn new(database_schema: &str, index: &Index, concurrently: bool) -> Self {
+        let mut ddls = Vec::new();
+        if index.method == IndexMethod::GinTrigram {
+            ddls.push(TRIGRAM_EXTENSION_DDL.to_string());
+        }
+        ddls.push(create_missing_index_ddl(
+            database_schema,
+            index,
+            concurrently,
+        ));
+        Self { ddls, concurrently }
+    }
+}
+
+impl MigrationStep for CreateIndexStep {
+    fn ctx(&self) -> &'static str {
+        "CREATE INDEX"
+    }
+
+    fn ddls(self) -> Vec<String> {
+        self.ddls
+    }
+
+    // CREATE INDEX CONCURRENTLY cannot run inside a transaction block
+    fn transactional(&self) -> bool {
+        !self.concurrently
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AlterForeignKeyStep {
+    pub ddls: Vec<String>,
+}
+
+impl AlterForeignKeyStep {
+    /// Replaces the `actual` foreign key with the `needed` one, whose
+    /// `ON DELETE` action differs
+    pub fn new(
+        database_schema: &str,
+        actual: &ForeignKeyConstraint,
+        needed: &ForeignKeyConstraint,
+    ) -> Self {
+        let actual_name = actual
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("{}_{}_fkey", actual.table_name, actual.column_name));
+        let ddls = vec![
+            format!(
+                "ALTER TABLE \"{}\".\"{}\" DROP CONSTRAINT IF EXISTS \"{}\"",
+                database_schema, needed.table_name, actual_name
+            ),
+            create_fk_ddl(database_schema, needed),
+        ];
+        Self { ddls }
+    }
+}
+
+impl MigrationStep for AlterForeignKeyStep {
+    fn ctx(&self) -> &'static str {
+        "ALTER FOREIGN KEY"
+    }
+
+    fn ddls(self) -> Vec<String> {
+        self.ddls
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AlterChecksStep {
+    pub ddls: Vec<String>,
+}
+
+impl AlterChecksStep {
+    /// Drops the `obsolete` CHECK constraints of the existing table and adds
+    /// the `missing` ones NOT VALID, so rows which are already there aren't
+    /// scanned and only new and updated rows are checked
+    pub fn new(
+        database_schema: &str,
+        table: &Table,
+        obsolete: &[&str],
+        missing: &[(&Column, &FieldConstraint)],
+    ) -> Self {
+        let mut ddls = obsolete
+            .iter()
+            .map(|name| {
+                format!(
+                    "ALTER TABLE \"{}\".\"{}\" DROP CONSTRAINT IF EXISTS \"{}\"",
+                    database_schema, table.name, name
+                )
+            })
+            .collect::<Vec<_>>();
+        // a partitioned table validates its constraint right away, rather
+        // than depend on NOT VALID being passed on to its partitions
+        let not_valid = if table.partition_by.is_some() {
+            ""
+        } else {
+            " NOT VALID"
+        };
+        ddls.extend(missing.iter().map(|(column, check)| {
+            format!(
+                "ALTER TABLE \"{}\".\"{}\" ADD {}{}",
+                database_schema,
+                table.name,
+                check_ddl(&table.name, &column.name, check),
+                not_valid
+            )
+        }));
+        Self { ddls }
+    }
+}
+
+impl MigrationStep for AlterChecksStep {
+    fn ctx(&self) -> &'static str {
+        "ALTER CHECK CONSTRAINTS"
+    }
+
+    fn ddls(self) -> Vec<String> {
+        self.ddls
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateViewStep {
+    pub ddls: Vec<String>,
+}
+
+impl CreateViewStep {
+    /// (Re)creates the view with its data, the unique index on the document id
+    /// its concurrent refreshes need, and the comment marking it as created by migration
+    pub fn new(database_schema: &str, view: &View) -> Self {
+        let ddls = vec![
+            drop_view_ddl(database_schema, &view.name),
+            format!(
+                "CREATE MATERIALIZED VIEW \"{}\".\"{}\" AS {}",
+                database_schema, view.name, view.query
+            ),
+            format!(
+                "CREATE UNIQUE INDEX \"{1}_{2}_key\" ON \"{0}\".\"{1}\" (\"{2}\")",
+                database_schema, view.name, DOCUMENT_ID_FIELD_NAME
+            ),
+            format!(
+                "COMMENT ON MATERIALIZED VIEW \"{}\".\"{}\" IS '{}{}'",
+                database_schema,
+                view.name,
+                VIEW_COMMENT_PREFIX,
+                view.checksum()
+            ),
+        ];
+        Self { ddls }
+    }
+}
+
+impl MigrationStep for CreateViewStep {
+    fn ctx(&self) -> &'static str {
+        "CREATE VIEW"
+    }
+
+    fn ddls(self) -> Vec<String> {
+        self.ddls
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DropViewStep {
+    pub schema: String,
+    pub view_name: String,
+}
+
+impl DropViewStep {
+    pub fn new(database_schema: &str, view_name: &str) -> Self {
+        Self {
+            schema: database_schema.to_string(),
+            view_name: view_name.to_string(),
+        }
+    }
+}
+
+impl MigrationStep for DropViewStep {
+    fn ctx(&self) -> &'static str {
+        "DROP VIEW"
+    }
+
+    fn ddls(self) -> Vec<String> {
+        vec![drop_view_ddl(&self.schema, &self.view_name)]
+    }
+}
+
 /// Pure domain logic: Generates a list of migration steps based on the needed and actual database schemas.
 pub fn plan_migration(
     needed_schema: &[Table],
     actual_schema: &[Table],
     database_schema: &str,
+    options: &MigrationOptions,
 ) -> Result<Vec<MigrationStepItem>, DependencyError> {
     let needed_names: std::collections::HashSet<String> = needed_schema
         .iter()
@@ -94,8 +506,30 @@ pub fn plan_migration(
         .map(|table| table.name.clone())
         .collect();
 
+    // tables which are renamed conform rename hints: needed table name -> actual table name
+    let renamed_tables: std::collections::HashMap<&str, &str> = needed_schema
+        .iter()
+        .filter_map(|table| {
+            let old_name = table.renamed_from.as_deref()?;
+            let applicable = !actual_names.contains(&table.name)
+                && actual_names.contains(old_name)
+                && !needed_names.contains(old_name);
+            applicable.then_some((table.name.as_str(), old_name))
+        })
+        .collect();
+
     let mut migration_steps = Vec::new();
 
+    for table in needed_schema {
+        if let Some(old_name) = renamed_tables.get(table.name.as_str()) {
+            migration_steps.push(MigrationStepItem::RenameTable(RenameTableStep::new(
+                database_schema,
+                old_name,
+                table,
+            )));
+        }
+    }
+
     // Resolve drop order of all actual tables from the database topologically
     let drop_order = match resolve_table_order(actual_schema) {
         Ok(ordered) => {
@@ -120,6 +554,7 @@ pub fn plan_migration(
     let obsolete_tables: Vec<String> = drop_order
         .into_iter()
         .filter(|name| !needed_names.contains(name))
+        .filter(|name| !renamed_tables.values().any(|old_name| old_name == name))
         .collect();
 
     for table_name in obsolete_tables {
@@ -129,10 +564,36 @@ pub fn plan_migration(
         )));
     }
 
+    // handle columns of existing tables which are renamed or not described by the schema anymore
+    for needed in needed_schema {
+        let actual_name = renamed_tables
+            .get(needed.name.as_str())
+            .copied()
+            .unwrap_or(&needed.name);
+        if let Some(actual) = actual_schema.iter().find(|t| t.name == actual_name) {
+            migration_steps.extend(plan_table_columns(
+                needed,
+                actual,
+                database_schema,
+                options.removed_columns,
+            ));
+            migration_steps.extend(plan_table_foreign_keys(needed, actual, database_schema));
+            migration_steps.extend(plan_table_checks(needed, actual, database_schema));
+            // indexes of partitioned tables can't be created concurrently
+            migration_steps.extend(plan_table_indexes(
+                needed,
+                actual,
+                database_schema,
+                options.concurrent_indexes && needed.partition_by.is_none(),
+            ));
+        }
+    }
+
     // create missing tables in needed order
     let ordered = resolve_table_order(needed_schema)?;
     for table in ordered {
-        if !actual_names.contains(&table.name) {
+        if !actual_names.contains(&table.name) && !renamed_tables.contains_key(table.name.as_str())
+        {
             migration_steps.push(MigrationStepItem::Create(CreateTableStep::new(
                 database_schema,
                 table,
@@ -143,6 +604,390 @@ pub fn plan_migration(
     Ok(migration_steps)
 }
 
+/// Renders migration steps into a SQL script which can be reviewed or applied by hand.
+/// Every transactional step is wrapped into its own transaction, the same way migration applies it.
+pub fn render_script(database_schema: &str, steps: Vec<MigrationStepItem>) -> String {
+    let mut script = format!(
+        "-- Luminair migration script for schema \"{}\"\n",
+        database_schema
+    );
+    if steps.is_empty() {
+        script.push_str("-- No migration steps needed. Database schema is up to date.\n");
+        return script;
+    }
+    for step in steps {
+        let transactional = step.transactional();
+        script.push_str(&format!("\n-- Context: {}\n", step.ctx()));
+        if transactional {
+            script.push_str("BEGIN;\n");
+        }
+        for ddl in step.ddls() {
+            script.push_str(&ddl);
+            script.push_str(";\n");
+        }
+        if transactional {
+            script.push_str("COMMIT;\n");
+        }
+    }
+    script
+}
+
+/// Renders the whole needed schema into one SQL script, tables ordered by their dependencies
+/// and followed by the views. Used to bootstrap a database without comparing it with the actual schema.
+pub fn render_snapshot(
+    database_schema: &str,
+    tables: &[Table],
+    views: &[View],
+) -> Result<String, DependencyError> {
+    let ordered = resolve_table_order(tables)?;
+
+    let mut script = format!(
+        "-- Luminair schema snapshot for schema \"{0}\"\nBEGIN;\nCREATE SCHEMA IF NOT EXISTS \"{0}\";\n",
+        database_schema
+    );
+    for table in ordered {
+        script.push_str(&format!("\n-- Table: {}\n", table.name));
+        for ddl in create_table_ddl(database_schema, table) {
+            script.push_str(&ddl);
+            script.push_str(";\n");
+        }
+    }
+    for view in views {
+        script.push_str(&format!("\n-- View: {}\n", view.name));
+        for ddl in CreateViewStep::new(database_schema, view).ddls() {
+            script.push_str(&ddl);
+            script.push_str(";\n");
+        }
+    }
+    script.push_str("COMMIT;\n");
+    Ok(script)
+}
+
+/// Checksum of the DDLs creating the needed schema, tables ordered by name, so it is
+/// the same on every instance loading the same documents configuration.
+pub fn schema_version(tables: &[Table]) -> String {
+    let mut ordered: Vec<&Table> = tables.iter().collect();
+    ordered.sort_by(|a, b| a.name.cmp(&b.name));
+    let ddls: Vec<String> = ordered
+        .into_iter()
+        .flat_map(|table| create_table_ddl("", table))
+        .collect();
+    checksum(&ddls)
+}
+
+/// Plans column renames conform rename hints, additions of missing columns and handling of orphaned columns.
+/// DDLs address the table by its needed name, since table renames are applied first.
+fn plan_table_columns(
+    needed: &Table,
+    actual: &Table,
+    database_schema: &str,
+    policy: RemovedColumnsPolicy,
+) -> Vec<MigrationStepItem> {
+    let mut steps = Vec::new();
+
+    if needed.partition_by.is_some() && actual.partition_by.is_none() {
+        eprintln!(
+            "Warning: table '{}' exists unpartitioned and can't be partitioned in place, recreate it to partition it",
+            needed.name
+        );
+    }
+
+    let has_column = |name: &str| actual.columns.iter().any(|c| c.name == name);
+
+    let renamed_columns = column_renames(needed, actual);
+    for (old_name, new_name) in renamed_columns.iter() {
+        steps.push(MigrationStepItem::RenameColumn(RenameColumnStep::new(
+            database_schema,
+            &needed.name,
+            old_name,
+            new_name,
+            false,
+        )));
+    }
+
+    let added = needed.columns.iter().filter(|column| {
+        !has_column(&column.name)
+            && !renamed_columns
+                .iter()
+                .any(|(_, new_name)| *new_name == column.name)
+    });
+    for column in added {
+        steps.push(MigrationStepItem::AddColumn(AddColumnStep::new(
+            database_schema,
+            &needed.name,
+            column,
+        )));
+    }
+
+    let orphaned = actual.columns.iter().filter(|column| {
+        !needed.columns.iter().any(|c| c.name == column.name)
+            && !renamed_columns
+                .iter()
+                .any(|(old_name, _)| *old_name == column.name)
+    });
+
+    for column in orphaned {
+        match policy {
+            RemovedColumnsPolicy::Drop => {
+                steps.push(MigrationStepItem::DropColumn(DropColumnStep::new(
+                    database_schema,
+                    &needed.name,
+                    &column.name,
+                )));
+            }
+            RemovedColumnsPolicy::Rename if column.name.starts_with(DELETED_COLUMN_PREFIX) => {
+                eprintln!(
+                    "Warning: column '{}' of table '{}' was already marked as deleted, use --destructive=drop to drop it",
+                    column.name, needed.name
+                );
+            }
+            RemovedColumnsPolicy::Rename => {
+                let new_name = format!("{}{}", DELETED_COLUMN_PREFIX, column.name);
+                if has_column(&new_name) {
+                    eprintln!(
+                        "Warning: column '{}' of table '{}' can't be renamed, column '{}' already exists",
+                        column.name, needed.name, new_name
+                    );
+                    continue;
+                }
+                steps.push(MigrationStepItem::RenameColumn(RenameColumnStep::new(
+                    database_schema,
+                    &needed.name,
+                    &column.name,
+                    &new_name,
+                    column.not_null,
+                )));
+            }
+            RemovedColumnsPolicy::Warn => {
+                eprintln!(
+                    "Warning: column '{}' of table '{}' is not described by the schema, use --destructive to handle it",
+                    column.name, needed.name
+                );
+            }
+        }
+    }
+
+    steps
+}
+
+/// Column renames conform rename hints, as pairs of (actual name, needed name)
+fn column_renames<'a>(needed: &'a Table, actual: &Table) -> Vec<(&'a str, &'a str)> {
+    let has_column = |name: &str| actual.columns.iter().any(|c| c.name == name);
+
+    needed
+        .columns
+        .iter()
+        .filter_map(|column| {
+            let old_name = column.renamed_from.as_deref()?;
+            let applicable = !has_column(&column.name)
+                && has_column(old_name)
+                && !needed.columns.iter().any(|c| c.name == old_name);
+            applicable.then_some((old_name, column.name.as_str()))
+        })
+        .collect()
+}
+
+/// Plans the replacement of the foreign keys of the existing table whose
+/// `ON DELETE` action differs from the needed one, matched by their column.
+/// Missing foreign keys are left alone, they come with the columns.
+fn plan_table_foreign_keys(
+    needed: &Table,
+    actual: &Table,
+    database_schema: &str,
+) -> Vec<MigrationStepItem> {
+    let renamed_columns = column_renames(needed, actual);
+    needed
+        .foreign_keys
+        .iter()
+        .filter_map(|fk| {
+            let actual_column = renamed_columns
+                .iter()
+                .find(|(_, new_name)| *new_name == fk.column_name)
+                .map_or(fk.column_name.as_str(), |(old_name, _)| old_name);
+            let existing = actual
+                .foreign_keys
+                .iter()
+                .find(|actual_fk| actual_fk.column_name == actual_column)?;
+            (existing.on_delete.to_sql_action() != fk.on_delete.to_sql_action()).then(|| {
+                MigrationStepItem::AlterForeignKey(AlterForeignKeyStep::new(
+                    database_schema,
+                    existing,
+                    fk,
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Plans CHECK constraints of the columns which the existing table already
+/// has: constraints are named after what they check, so a new or changed
+/// constraint is one whose name is missing and an obsolete one is one whose
+/// name isn't needed anymore. Added columns bring their constraints along.
+fn plan_table_checks(
+    needed: &Table,
+    actual: &Table,
+    database_schema: &str,
+) -> Option<MigrationStepItem> {
+    let renamed_columns = column_renames(needed, actual);
+    let mut obsolete = Vec::new();
+    let mut missing = Vec::new();
+    for column in needed.columns.iter() {
+        let actual_name = renamed_columns
+            .iter()
+            .find(|(_, new_name)| *new_name == column.name)
+            .map_or(column.name.as_str(), |(old_name, _)| old_name);
+        let Some(actual_column) = actual.columns.iter().find(|c| c.name == actual_name) else {
+            continue;
+        };
+        let needed_names = column
+            .checks
+            .iter()
+            .map(|check| (check_name(&needed.name, &column.name, check), check))
+            .collect::<Vec<_>>();
+        obsolete.extend(
+            actual_column
+                .check_names
+                .iter()
+                .filter(|name| !needed_names.iter().any(|(needed, _)| needed == *name))
+                .map(String::as_str),
+        );
+        missing.extend(
+            needed_names
+                .iter()
+                .filter(|(name, _)| !actual_column.check_names.contains(name))
+                .map(|(_, check)| (column, *check)),
+        );
+    }
+    (!obsolete.is_empty() || !missing.is_empty()).then(|| {
+        MigrationStepItem::AlterChecks(AlterChecksStep::new(
+            database_schema,
+            needed,
+            &obsolete,
+            &missing,
+        ))
+    })
+}
+
+/// Plans creation of indexes which are missing on the existing table,
+/// including unique indexes for columns newly marked unique.
+/// Indexes are matched by their columns, uniqueness, method and predicate,
+/// not by name.
+fn plan_table_indexes(
+    needed: &Table,
+    actual: &Table,
+    database_schema: &str,
+    concurrently: bool,
+) -> Vec<MigrationStepItem> {
+    let renamed_columns = column_renames(needed, actual);
+    let actual_indexes: Vec<(Vec<&str>, bool, IndexMethod, Option<&str>)> = actual
+        .indexes
+        .iter()
+        .map(|index| {
+            let columns = index
+                .columns
+                .iter()
+                .map(|column| {
+                    renamed_columns
+                        .iter()
+                        .find(|(old_name, _)| old_name == column)
+                        .map_or(column.as_str(), |(_, new_name)| new_name)
+                })
+                .collect();
+            (
+                columns,
+                index.unique,
+                index.method,
+                index.where_clause.as_deref(),
+            )
+        })
+        .collect();
+
+    let unique_columns = needed
+        .columns
+        .iter()
+        .filter(|column| column.unique)
+        .map(|column| Index::new(needed.name.as_str(), vec![column.name.as_str()], true));
+
+    needed
+        .indexes
+        .iter()
+        .cloned()
+        .chain(unique_columns)
+        .filter(|index| {
+            !actual_indexes
+                .iter()
+                .any(|(columns, unique, method, predicate)| {
+                    *unique == index.unique
+                        && *method == index.method
+                        && same_predicate(*predicate, index.where_clause.as_deref())
+                        && columns.len() == index.columns.len()
+                        && columns
+                            .iter()
+                            .zip(index.columns.iter())
+                            .all(|(actual, needed)| same_index_key(actual, needed))
+                })
+        })
+        .map(|index| {
+            MigrationStepItem::CreateIndex(CreateIndexStep::new(
+                database_schema,
+                &index,
+                concurrently,
+            ))
+        })
+        .collect()
+}
+
+fn add_column_ddl(schema: &str, table_name: &str, column: &Column) -> String {
+    format!(
+        "ALTER TABLE \"{}\".\"{}\" ADD COLUMN IF NOT EXISTS {}",
+        schema,
+        table_name,
+        column_ddl(table_name, column)
+    )
+}
+
+fn drop_column_ddl(schema: &str, table_name: &str, column_name: &str) -> String {
+    format!(
+        "ALTER TABLE \"{}\".\"{}\" DROP COLUMN IF EXISTS \"{}\"",
+        schema, table_name, column_name
+    )
+}
+
+fn rename_column_ddl(schema: &str, table_name: &str, column_name: &str, new_name: &str) -> String {
+    format!(
+        "ALTER TABLE \"{}\".\"{}\" RENAME COLUMN \"{}\" TO \"{}\"",
+        schema, table_name, column_name, new_name
+    )
+}
+
+fn drop_not_null_ddl(schema: &str, table_name: &str, column_name: &str) -> String {
+    format!(
+        "ALTER TABLE \"{}\".\"{}\" ALTER COLUMN \"{}\" DROP NOT NULL",
+        schema, table_name, column_name
+    )
+}
+
+fn rename_table_ddl(schema: &str, table_name: &str, new_name: &str) -> String {
+    format!(
+        "ALTER TABLE \"{}\".\"{}\" RENAME TO \"{}\"",
+        schema, table_name, new_name
+    )
+}
+
+fn rename_index_ddl(schema: &str, index_name: &str, new_name: &str) -> String {
+    format!(
+        "ALTER INDEX IF EXISTS \"{}\".\"{}\" RENAME TO \"{}\"",
+        schema, index_name, new_name
+    )
+}
+
+fn drop_view_ddl(schema: &str, view_name: &str) -> String {
+    format!(
+        "DROP MATERIALIZED VIEW IF EXISTS \"{}\".\"{}\"",
+        schema, view_name
+    )
+}
+
 fn drop_table_ddl(schema: &str, table_name: &str) -> String {
     format!(
         "DROP TABLE IF EXISTS \"{}\".\"{}\" CASCADE",
@@ -155,7 +1000,7 @@ fn create_table_ddl(schema: &str, table: &Table) -> Vec<String> {
     let mut pk_columns = Vec::new();
 
     for column in table.columns.iter() {
-        columns.push(column_ddl(column));
+        columns.push(column_ddl(&table.name, column));
         if column.primary_key {
             pk_columns.push(&column.name as &str);
         }
@@ -164,12 +1009,30 @@ fn create_table_ddl(schema: &str, table: &Table) -> Vec<String> {
     let columns_sql = columns.join(",\n    ");
     let pk_columns_sql = pk_columns.join(",");
 
-    let table_ddl = format!(
+    let mut table_ddl = format!(
         "CREATE TABLE \"{}\".\"{}\" (\n    {},\n    PRIMARY KEY({})\n)",
         schema, table.name, columns_sql, pk_columns_sql
     );
+    if let Some(partition_by) = &table.partition_by {
+        table_ddl.push_str(&format!(" PARTITION BY RANGE (\"{}\")", partition_by));
+    }
 
-    let mut ddls = vec![table_ddl];
+    let mut ddls = Vec::new();
+    if table
+        .indexes
+        .iter()
+        .any(|index| index.method == IndexMethod::GinTrigram)
+    {
+        ddls.push(TRIGRAM_EXTENSION_DDL.to_string());
+    }
+    ddls.push(table_ddl);
+    // rows outside of the partitions created ahead, e.g. imported ones, are kept here
+    if table.partition_by.is_some() {
+        ddls.push(format!(
+            "CREATE TABLE \"{0}\".\"{1}_default\" PARTITION OF \"{0}\".\"{1}\" DEFAULT",
+            schema, table.name
+        ));
+    }
 
     for fk in table.foreign_keys.iter() {
         ddls.push(create_fk_ddl(schema, fk));
@@ -182,23 +1045,32 @@ fn create_table_ddl(schema: &str, table: &Table) -> Vec<String> {
     ddls
 }
 
-fn column_ddl(column: &Column) -> String {
-    let ct = match column.column_type {
+/// SQL type of `column_type`, as it's declared in a column definition
+pub(crate) fn column_type_sql(column_type: ColumnType) -> String {
+    match column_type {
         ColumnType::Identity(size) => {
-            let s = size.to_sql_type();
-            &format!("{} GENERATED ALWAYS AS IDENTITY", s)
+            format!("{} GENERATED ALWAYS AS IDENTITY", size.to_sql_type())
         }
-        ColumnType::Uuid => "UUID",
-        ColumnType::Text => "TEXT",
-        ColumnType::Varchar => "VARCHAR",
-        ColumnType::Integer(size) => size.to_sql_type(),
-        ColumnType::Decimal { precision, scale } => &format!("DECIMAL({},{})", precision, scale),
-        ColumnType::Date => "DATE",
-        ColumnType::TimestampTZ => "TIMESTAMPTZ",
-        ColumnType::Boolean => "BOOLEAN",
-        ColumnType::JsonB => "JSONB",
-    };
-    let mut sql = format!("\"{}\" {}", column.name, ct);
+        ColumnType::Uuid => "UUID".to_string(),
+        ColumnType::Text => "TEXT".to_string(),
+        ColumnType::Varchar => "VARCHAR".to_string(),
+        ColumnType::Integer(size) => size.to_sql_type().to_string(),
+        ColumnType::Decimal { precision, scale } => format!("DECIMAL({},{})", precision, scale),
+        ColumnType::Date => "DATE".to_string(),
+        ColumnType::Timestamp => "TIMESTAMP".to_string(),
+        ColumnType::TimestampTZ => "TIMESTAMPTZ".to_string(),
+        ColumnType::Time => "TIME".to_string(),
+        ColumnType::Boolean => "BOOLEAN".to_string(),
+        ColumnType::JsonB => "JSONB".to_string(),
+    }
+}
+
+fn column_ddl(table_name: &str, column: &Column) -> String {
+    let mut sql = format!(
+        "\"{}\" {}",
+        column.name,
+        column_type_sql(column.column_type)
+    );
     if let Some(length) = column.column_length {
         sql.push_str(&format!("({})", length));
     }
@@ -211,12 +1083,48 @@ fn column_ddl(column: &Column) -> String {
     if column.unique {
         sql.push_str(" UNIQUE");
     }
+    for check in column.checks.iter() {
+        sql.push(' ');
+        sql.push_str(&check_ddl(table_name, &column.name, check));
+    }
     sql
 }
 
+fn check_ddl(table_name: &str, column_name: &str, constraint: &FieldConstraint) -> String {
+    format!(
+        "CONSTRAINT \"{}\" CHECK ({})",
+        check_name(table_name, column_name, constraint),
+        check_expression(column_name, constraint)
+    )
+}
+
+/// `{table}_{column}_{checksum}_check`, the start of the checksum of its
+/// expression telling a changed constraint from the one it replaces.
+fn check_name(table_name: &str, column_name: &str, constraint: &FieldConstraint) -> String {
+    let mut expression = checksum(&[check_expression(column_name, constraint)]);
+    expression.truncate(8);
+    format!("{}_{}_{}_check", table_name, column_name, expression)
+}
+
+fn check_expression(column_name: &str, constraint: &FieldConstraint) -> String {
+    match constraint {
+        FieldConstraint::Pattern(pattern) => {
+            format!("\"{}\" ~ '{}'", column_name, pattern.replace('\'', "''"))
+        }
+        FieldConstraint::MinimalLength(length) => {
+            format!("char_length(\"{}\") >= {}", column_name, length)
+        }
+        FieldConstraint::MaximalLength(length) => {
+            format!("char_length(\"{}\") <= {}", column_name, length)
+        }
+        FieldConstraint::MinimalIntegerValue(value) => format!("\"{}\" >= {}", column_name, value),
+        FieldConstraint::MaximalIntegerValue(value) => format!("\"{}\" <= {}", column_name, value),
+    }
+}
+
 fn create_fk_ddl(schema: &str, fk: &ForeignKeyConstraint) -> String {
     format!(
-        "ALTER TABLE \"{}\".\"{}\" ADD CONSTRAINT \"{}_{}_fkey\" FOREIGN KEY (\"{}\") REFERENCES \"{}\".\"{}\" (\"{}\") ON DELETE CASCADE",
+        "ALTER TABLE \"{}\".\"{}\" ADD CONSTRAINT \"{}_{}_fkey\" FOREIGN KEY (\"{}\") REFERENCES \"{}\".\"{}\" (\"{}\") ON DELETE {}",
         schema,
         fk.table_name,
         fk.table_name,
@@ -224,20 +1132,35 @@ fn create_fk_ddl(schema: &str, fk: &ForeignKeyConstraint) -> String {
         fk.column_name,
         schema,
         fk.referenced_table_name,
-        fk.referenced_column_name
+        fk.referenced_column_name,
+        fk.on_delete.to_sql_action()
     )
 }
 
 fn create_index_ddl(schema: &str, index: &Index) -> String {
-    let columns_sql = index.columns.join(", ");
     let mut ddl = format!(
-        "CREATE {} INDEX \"{}_{}_idx\" ON \"{}\".\"{}\" ({})",
+        "CREATE {} INDEX \"{}\" ON \"{}\".\"{}\" {}",
         if index.unique { "UNIQUE" } else { "" },
+        index_name(&index.table_name, index),
+        schema,
         index.table_name,
-        index.columns.join("_"),
+        index_keys_ddl(index)
+    );
+    if let Some(where_clause) = &index.where_clause {
+        ddl.push_str(&format!(" WHERE {}", where_clause));
+    }
+    ddl
+}
+
+fn create_missing_index_ddl(schema: &str, index: &Index, concurrently: bool) -> String {
+    let mut ddl = format!(
+        "CREATE {}INDEX {}IF NOT EXISTS \"{}\" ON \"{}\".\"{}\" {}",
+        if index.unique { "UNIQUE " } else { "" },
+        if concurrently { "CONCURRENTLY " } else { "" },
+        index_name(&index.table_name, index),
         schema,
         index.table_name,
-        columns_sql
+        index_keys_ddl(index)
     );
     if let Some(where_clause) = &index.where_clause {
         ddl.push_str(&format!(" WHERE {}", where_clause));
@@ -245,14 +1168,85 @@ fn create_index_ddl(schema: &str, index: &Index) -> String {
     ddl
 }
 
+/// `(key, ...)` of a B-tree index, `USING gin (key gin_trgm_ops, ...)` of a trigram one
+fn index_keys_ddl(index: &Index) -> String {
+    match index.method {
+        IndexMethod::BTree => format!("({})", index.columns.join(", ")),
+        IndexMethod::GinTrigram => {
+            let keys = index
+                .columns
+                .iter()
+                .map(|key| format!("{} gin_trgm_ops", key))
+                .collect::<Vec<_>>();
+            format!("USING gin ({})", keys.join(", "))
+        }
+    }
+}
+
+/// Whether two keys of an index are the same column or expression.
+/// Postgres reads expressions back in its own form, e.g. `(title ->> 'de')`
+/// as `(title ->> 'de'::text)`, so they are compared without parentheses,
+/// quotes, whitespace and text casts.
+fn same_index_key(actual: &str, needed: &str) -> bool {
+    let normalize = |key: &str| {
+        key.replace("::text", "")
+            .chars()
+            .filter(|c| !c.is_whitespace() && !matches!(c, '(' | ')' | '"'))
+            .collect::<String>()
+    };
+    normalize(actual) == normalize(needed)
+}
+
+/// Whether two partial index predicates are the same, compared like
+/// [`same_index_key`] compares keys; Postgres reads them back parenthesized.
+fn same_predicate(actual: Option<&str>, needed: Option<&str>) -> bool {
+    match (actual, needed) {
+        (None, None) => true,
+        (Some(actual), Some(needed)) => same_index_key(actual, needed),
+        _ => false,
+    }
+}
+
+/// `{table}_{columns}_key` of a unique index, like the ones backing the
+/// UNIQUE constraints of CREATE TABLE, `{table}_{columns}_idx` of another
+/// one, and `{table}_{columns}_trgm_idx` of a trigram one. Expressions
+/// contribute the words they are made of, e.g. `title_de` for
+/// `(title ->> 'de')`, and a predicate the start of its checksum, e.g.
+/// `{table}_{columns}_1a2b3c4d_idx`, so indexes of the same keys don't clash.
+fn index_name(table_name: &str, index: &Index) -> String {
+    let mut words = index
+        .columns
+        .iter()
+        .flat_map(|column| column.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_')))
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    if let Some(where_clause) = &index.where_clause {
+        let mut predicate = checksum(std::slice::from_ref(where_clause));
+        predicate.truncate(8);
+        words.push(predicate);
+    }
+    let suffix = match (index.method, index.unique) {
+        (IndexMethod::BTree, true) => "key",
+        (IndexMethod::BTree, false) => "idx",
+        (IndexMethod::GinTrigram, _) => "trgm_idx",
+    };
+    format!("{}_{}_{}", table_name, words.join("_"), suffix)
+}
+
 // returns database persistence for given documents schema, sorted conform dependency order
 pub fn documents_into_tables(documents: &dyn DocumentTypesRegistry) -> Vec<Table> {
     let mut tables = Vec::new();
 
-    for d in documents.iterate() {
+    // read-only types are read from their views, they have no tables
+    for d in documents.iterate().filter(|d| d.view().is_none()) {
         let doc_tables = DocumentTables::new(d, documents);
         tables.extend(doc_tables.tables);
     }
+    tables.push(outbox_table());
+    tables.push(idempotency_table());
+    tables.push(jobs_table());
+    tables.push(erasures_table());
 
     tables
 }
@@ -260,7 +1254,9 @@ pub fn documents_into_tables(documents: &dyn DocumentTypesRegistry) -> Vec<Table
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::tables::{Column, ColumnType, ForeignKeyConstraint, Index};
+    use crate::domain::tables::{
+        Column, ColumnType, ForeignKeyConstraint, Index, IntegerSize, OnDeleteAction,
+    };
 
     #[test]
     fn test_drop_table_ddl() {
@@ -296,6 +1292,58 @@ mod tests {
         assert!(ddl.contains("PRIMARY KEY(id)"));
     }
 
+    #[test]
+    fn test_column_ddl_with_checks() {
+        let column =
+            Column::new("code", ColumnType::Text, None, true, false, None).with_checks(vec![
+                FieldConstraint::MinimalLength(4),
+                FieldConstraint::Pattern("^[a-z']+$".to_string()),
+            ]);
+        assert_eq!(
+            column_ddl("t1", &column),
+            format!(
+                "\"code\" TEXT NOT NULL CONSTRAINT \"{}\" CHECK (char_length(\"code\") >= 4) CONSTRAINT \"{}\" CHECK (\"code\" ~ '^[a-z'']+$')",
+                check_name("t1", "code", &column.checks[0]),
+                check_name("t1", "code", &column.checks[1])
+            )
+        );
+        assert!(check_name("t1", "code", &column.checks[0]).starts_with("t1_code_"));
+        assert!(check_name("t1", "code", &column.checks[0]).ends_with("_check"));
+        assert_ne!(
+            check_name("t1", "code", &FieldConstraint::MinimalLength(4)),
+            check_name("t1", "code", &FieldConstraint::MinimalLength(5))
+        );
+
+        let column = Column::new(
+            "amount",
+            ColumnType::Integer(IntegerSize::Int32),
+            None,
+            false,
+            false,
+            None,
+        )
+        .with_checks(vec![FieldConstraint::MaximalIntegerValue(100)]);
+        assert_eq!(
+            column_ddl("t1", &column),
+            format!(
+                "\"amount\" INT CONSTRAINT \"{}\" CHECK (\"amount\" <= 100)",
+                check_name("t1", "amount", &column.checks[0])
+            )
+        );
+    }
+
+    #[test]
+    fn test_column_ddl_of_times() {
+        for (column_type, ddl) in [
+            (ColumnType::TimestampTZ, "\"at\" TIMESTAMPTZ"),
+            (ColumnType::Timestamp, "\"at\" TIMESTAMP"),
+            (ColumnType::Time, "\"at\" TIME"),
+        ] {
+            let column = Column::new("at", column_type, None, false, false, None);
+            assert_eq!(column_ddl("t1", &column), ddl);
+        }
+    }
+
     #[test]
     fn test_create_fk_ddl() {
         let fk = ForeignKeyConstraint::new("child_table", "parent_id", "parent_table", "id");
@@ -306,6 +1354,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_create_fk_ddl_with_on_delete() {
+        let fk = ForeignKeyConstraint::new("child_table", "parent_id", "parent_table", "id")
+            .with_on_delete(OnDeleteAction::Restrict);
+        let ddl = create_fk_ddl("my_schema", &fk);
+        assert!(
+            ddl.ends_with("REFERENCES \"my_schema\".\"parent_table\" (\"id\") ON DELETE RESTRICT")
+        );
+    }
+
     #[test]
     fn test_create_index_ddl() {
         let index = Index::new("my_table", vec!["col1", "col2"], false);
@@ -319,7 +1377,31 @@ mod tests {
         let ddl_unique = create_index_ddl("my_schema", &unique_index);
         assert_eq!(
             ddl_unique,
-            "CREATE UNIQUE INDEX \"my_table_col1_idx\" ON \"my_schema\".\"my_table\" (col1)"
+            "CREATE UNIQUE INDEX \"my_table_col1_key\" ON \"my_schema\".\"my_table\" (col1)"
+        );
+    }
+
+    #[test]
+    fn test_create_partitioned_table_ddl() {
+        let columns = vec![
+            Column::primary_key("document_id", ColumnType::Uuid, None),
+            Column::primary_key("created_at", ColumnType::TimestampTZ, None),
+        ];
+        let table = Table::new("events".to_string(), columns, vec![], vec![])
+            .with_partition_by(Some("created_at"));
+
+        let ddls = create_table_ddl("public", &table);
+        assert_eq!(ddls.len(), 2);
+        assert!(
+            ddls[0].ends_with(
+                "PRIMARY KEY(document_id,created_at)\n) PARTITION BY RANGE (\"created_at\")"
+            ),
+            "{}",
+            ddls[0]
+        );
+        assert_eq!(
+            ddls[1],
+            "CREATE TABLE \"public\".\"events_default\" PARTITION OF \"public\".\"events\" DEFAULT"
         );
     }
 
@@ -333,7 +1415,8 @@ mod tests {
         let needed = vec![t1.clone()];
         let actual = vec![t1];
 
-        let steps = plan_migration(&needed, &actual, "public").unwrap();
+        let steps =
+            plan_migration(&needed, &actual, "public", &MigrationOptions::default()).unwrap();
         assert!(steps.is_empty());
     }
 
@@ -343,7 +1426,8 @@ mod tests {
         let needed = vec![t1];
         let actual = vec![];
 
-        let steps = plan_migration(&needed, &actual, "public").unwrap();
+        let steps =
+            plan_migration(&needed, &actual, "public", &MigrationOptions::default()).unwrap();
         assert_eq!(steps.len(), 1);
         assert!(matches!(steps[0], MigrationStepItem::Create(_)));
     }
@@ -354,7 +1438,8 @@ mod tests {
         let needed = vec![];
         let actual = vec![t1];
 
-        let steps = plan_migration(&needed, &actual, "public").unwrap();
+        let steps =
+            plan_migration(&needed, &actual, "public", &MigrationOptions::default()).unwrap();
         assert_eq!(steps.len(), 1);
         assert!(matches!(steps[0], MigrationStepItem::Drop(_)));
     }
@@ -366,10 +1451,491 @@ mod tests {
         let needed = vec![t1]; // We want t1
         let actual = vec![t2]; // Database currently has t2
 
-        let steps = plan_migration(&needed, &actual, "public").unwrap();
+        let steps =
+            plan_migration(&needed, &actual, "public", &MigrationOptions::default()).unwrap();
         assert_eq!(steps.len(), 2);
         // Drops obsolete tables first, then creates needed ones
         assert!(matches!(steps[0], MigrationStepItem::Drop(_)));
         assert!(matches!(steps[1], MigrationStepItem::Create(_)));
     }
+
+    fn make_table_with_columns(name: &str, columns: &[(&str, bool)]) -> Table {
+        let columns = columns
+            .iter()
+            .map(|(column, not_null)| {
+                Column::new(*column, ColumnType::Text, None, *not_null, false, None)
+            })
+            .collect();
+        Table::new(name.to_string(), columns, vec![], vec![])
+    }
+
+    fn plan_with_policy(
+        needed: &[Table],
+        actual: &[Table],
+        policy: RemovedColumnsPolicy,
+    ) -> Vec<MigrationStepItem> {
+        let options = MigrationOptions {
+            removed_columns: policy,
+            ..Default::default()
+        };
+        plan_migration(needed, actual, "public", &options).unwrap()
+    }
+
+    #[test]
+    fn test_plan_migration_removed_column_warns_by_default() {
+        let needed = vec![make_table_with_columns("t1", &[("a", true)])];
+        let actual = vec![make_table_with_columns("t1", &[("a", true), ("b", true)])];
+
+        let steps = plan_with_policy(&needed, &actual, RemovedColumnsPolicy::Warn);
+        assert!(steps.is_empty());
+    }
+
+    #[test]
+    fn test_plan_migration_removed_column_renamed() {
+        let needed = vec![make_table_with_columns("t1", &[("a", true)])];
+        let actual = vec![make_table_with_columns("t1", &[("a", true), ("b", true)])];
+
+        let steps = plan_with_policy(&needed, &actual, RemovedColumnsPolicy::Rename);
+        assert_eq!(steps.len(), 1);
+        assert!(matches!(steps[0], MigrationStepItem::RenameColumn(_)));
+        assert_eq!(
+            steps[0].clone().ddls(),
+            vec![
+                "ALTER TABLE \"public\".\"t1\" RENAME COLUMN \"b\" TO \"_deleted_b\"",
+                "ALTER TABLE \"public\".\"t1\" ALTER COLUMN \"_deleted_b\" DROP NOT NULL",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_migration_already_deleted_column_not_renamed_again() {
+        let needed = vec![make_table_with_columns("t1", &[("a", true)])];
+        let actual = vec![make_table_with_columns(
+            "t1",
+            &[("a", true), ("_deleted_b", false)],
+        )];
+
+        let steps = plan_with_policy(&needed, &actual, RemovedColumnsPolicy::Rename);
+        assert!(steps.is_empty());
+    }
+
+    #[test]
+    fn test_plan_migration_removed_column_dropped() {
+        let needed = vec![make_table_with_columns("t1", &[("a", true)])];
+        let actual = vec![make_table_with_columns(
+            "t1",
+            &[("a", true), ("b", false), ("_deleted_c", false)],
+        )];
+
+        let steps = plan_with_policy(&needed, &actual, RemovedColumnsPolicy::Drop);
+        assert_eq!(steps.len(), 2);
+        assert_eq!(
+            steps[0].clone().ddls(),
+            vec!["ALTER TABLE \"public\".\"t1\" DROP COLUMN IF EXISTS \"b\""]
+        );
+        assert_eq!(
+            steps[1].clone().ddls(),
+            vec!["ALTER TABLE \"public\".\"t1\" DROP COLUMN IF EXISTS \"_deleted_c\""]
+        );
+    }
+
+    #[test]
+    fn test_plan_migration_renamed_table() {
+        let needed = vec![make_test_table("t2").with_renamed_from(Some("t1"))];
+        let actual = vec![make_test_table("t1")];
+
+        let steps =
+            plan_migration(&needed, &actual, "public", &MigrationOptions::default()).unwrap();
+        assert_eq!(steps.len(), 1);
+        assert!(matches!(steps[0], MigrationStepItem::RenameTable(_)));
+        let ddls = steps[0].clone().ddls();
+        assert_eq!(ddls[0], "ALTER TABLE \"public\".\"t1\" RENAME TO \"t2\"");
+        assert_eq!(
+            ddls[1],
+            "ALTER INDEX IF EXISTS \"public\".\"t1_pkey\" RENAME TO \"t2_pkey\""
+        );
+    }
+
+    #[test]
+    fn test_plan_migration_rename_hint_ignored_when_table_exists() {
+        let needed = vec![make_test_table("t2").with_renamed_from(Some("t1"))];
+        let actual = vec![make_test_table("t2")];
+
+        let steps =
+            plan_migration(&needed, &actual, "public", &MigrationOptions::default()).unwrap();
+        assert!(steps.is_empty());
+    }
+
+    #[test]
+    fn test_plan_migration_renamed_column() {
+        let mut t1 = make_table_with_columns("t1", &[("a", true), ("c", true)]);
+        t1.columns[1] = t1.columns[1].clone().with_renamed_from(Some("b"));
+        let needed = vec![t1];
+        let actual = vec![make_table_with_columns("t1", &[("a", true), ("b", true)])];
+
+        let steps = plan_with_policy(&needed, &actual, RemovedColumnsPolicy::Drop);
+        assert_eq!(steps.len(), 1);
+        assert_eq!(
+            steps[0].clone().ddls(),
+            vec!["ALTER TABLE \"public\".\"t1\" RENAME COLUMN \"b\" TO \"c\""]
+        );
+    }
+
+    #[test]
+    fn test_plan_migration_missing_column_added() {
+        let mut needed_table = make_table_with_columns("t1", &[("a", true), ("b", true)]);
+        needed_table.columns.push(Column::new(
+            "c",
+            ColumnType::JsonB,
+            None,
+            true,
+            false,
+            Some("'{}'"),
+        ));
+        let needed = vec![needed_table];
+        let actual = vec![make_table_with_columns("t1", &[("a", true)])];
+
+        let steps = plan_with_policy(&needed, &actual, RemovedColumnsPolicy::Warn);
+        assert_eq!(steps.len(), 2);
+        assert_eq!(
+            steps[0].clone().ddls(),
+            vec!["ALTER TABLE \"public\".\"t1\" ADD COLUMN IF NOT EXISTS \"b\" TEXT"]
+        );
+        assert_eq!(
+            steps[1].clone().ddls(),
+            vec![
+                "ALTER TABLE \"public\".\"t1\" ADD COLUMN IF NOT EXISTS \"c\" JSONB NOT NULL DEFAULT '{}'"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_migration_creates_missing_indexes() {
+        let mut needed_table = make_table_with_columns("t1", &[("a", true), ("b", true)]);
+        needed_table.columns[1].unique = true;
+        needed_table.indexes = vec![
+            Index::new("t1", vec!["a"], false),
+            Index::new("t1", vec!["a", "b"], false),
+        ];
+        let mut actual_table = make_table_with_columns("t1", &[("a", true), ("b", true)]);
+        actual_table.indexes = vec![Index::new("t1", vec!["a"], false)];
+
+        let steps = plan_migration(
+            &[needed_table],
+            &[actual_table],
+            "public",
+            &MigrationOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(steps.len(), 2);
+        assert!(steps.iter().all(|step| step.transactional()));
+        assert_eq!(
+            steps[0].clone().ddls(),
+            vec!["CREATE INDEX IF NOT EXISTS \"t1_a_b_idx\" ON \"public\".\"t1\" (a, b)"]
+        );
+        assert_eq!(
+            steps[1].clone().ddls(),
+            vec!["CREATE UNIQUE INDEX IF NOT EXISTS \"t1_b_key\" ON \"public\".\"t1\" (b)"]
+        );
+    }
+
+    #[test]
+    fn test_index_names_tell_uniqueness_and_predicate() {
+        let plain = Index::new("t1", vec!["a"], false);
+        let unique = Index::new("t1", vec!["a"], true);
+        let partial = Index::new("t1", vec!["a"], false).with_where("a IS NOT NULL");
+
+        assert_eq!(index_name("t1", &plain), "t1_a_idx");
+        assert_eq!(index_name("t1", &unique), "t1_a_key");
+        let partial_name = index_name("t1", &partial);
+        assert!(partial_name.starts_with("t1_a_"), "{partial_name}");
+        assert!(partial_name.ends_with("_idx"), "{partial_name}");
+        assert_ne!(partial_name, index_name("t1", &plain));
+    }
+
+    #[test]
+    fn test_plan_migration_partial_index_matched_by_predicate() {
+        let mut needed_table = make_table_with_columns("t1", &[("a", true)]);
+        needed_table.indexes = vec![Index::new("t1", vec!["a"], false).with_where("a IS NOT NULL")];
+        let mut actual_table = make_table_with_columns("t1", &[("a", true)]);
+        actual_table.indexes = vec![Index::new("t1", vec!["a"], false)];
+
+        let steps = plan_migration(
+            &[needed_table.clone()],
+            &[actual_table.clone()],
+            "public",
+            &MigrationOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(steps.len(), 1, "the index without a predicate doesn't do");
+
+        // as read back by Postgres
+        actual_table.indexes =
+            vec![Index::new("t1", vec!["a"], false).with_where("(a IS NOT NULL)")];
+        let steps = plan_migration(
+            &[needed_table],
+            &[actual_table],
+            "public",
+            &MigrationOptions::default(),
+        )
+        .unwrap();
+        assert!(steps.is_empty());
+    }
+
+    #[test]
+    fn test_plan_migration_alters_changed_on_delete() {
+        let mut needed_table = make_table_with_columns("t1", &[("parent_id", true)]);
+        needed_table.foreign_keys = vec![
+            ForeignKeyConstraint::new("t1", "parent_id", "parents", "id")
+                .with_on_delete(OnDeleteAction::Restrict),
+        ];
+        let mut actual_table = make_table_with_columns("t1", &[("parent_id", true)]);
+        actual_table.foreign_keys = vec![
+            ForeignKeyConstraint::new("t1", "parent_id", "parents", "id").with_name("t1_parent_fk"),
+        ];
+
+        let steps = plan_migration(
+            &[needed_table.clone()],
+            &[actual_table.clone()],
+            "public",
+            &MigrationOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(
+            steps[0].clone().ddls(),
+            vec![
+                "ALTER TABLE \"public\".\"t1\" DROP CONSTRAINT IF EXISTS \"t1_parent_fk\""
+                    .to_string(),
+                create_fk_ddl("public", &needed_table.foreign_keys[0]),
+            ]
+        );
+
+        // detach is a cascade in the database
+        needed_table.foreign_keys[0].on_delete = OnDeleteAction::Detach;
+        let steps = plan_migration(
+            &[needed_table],
+            &[actual_table],
+            "public",
+            &MigrationOptions::default(),
+        )
+        .unwrap();
+        assert!(steps.is_empty());
+    }
+
+    #[test]
+    fn test_plan_migration_alters_changed_checks() {
+        let mut needed_table = make_table_with_columns("t1", &[("code", true), ("name", true)]);
+        needed_table.columns[0].checks = vec![FieldConstraint::MaximalLength(10)];
+        needed_table.columns[1].checks = vec![FieldConstraint::MinimalLength(2)];
+        let mut actual_table = make_table_with_columns("t1", &[("code", true), ("name", true)]);
+        actual_table.columns[0].check_names = vec!["t1_code_check".to_string()];
+        actual_table.columns[1].check_names =
+            vec![check_name("t1", "name", &FieldConstraint::MinimalLength(2))];
+
+        let steps = plan_migration(
+            &[needed_table.clone()],
+            &[actual_table],
+            "public",
+            &MigrationOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].ctx(), "ALTER CHECK CONSTRAINTS");
+        assert_eq!(
+            steps[0].clone().ddls(),
+            vec![
+                "ALTER TABLE \"public\".\"t1\" DROP CONSTRAINT IF EXISTS \"t1_code_check\""
+                    .to_string(),
+                format!(
+                    "ALTER TABLE \"public\".\"t1\" ADD CONSTRAINT \"{}\" CHECK (char_length(\"code\") <= 10) NOT VALID",
+                    check_name("t1", "code", &FieldConstraint::MaximalLength(10))
+                ),
+            ]
+        );
+
+        // an added column brings its constraints along
+        let mut actual_table = make_table_with_columns("t1", &[("name", true)]);
+        actual_table.columns[0].check_names =
+            vec![check_name("t1", "name", &FieldConstraint::MinimalLength(2))];
+        let steps = plan_migration(
+            &[needed_table],
+            &[actual_table],
+            "public",
+            &MigrationOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].ctx(), "ADD COLUMN");
+    }
+
+    #[test]
+    fn test_plan_migration_creates_indexes_concurrently() {
+        let mut needed_table = make_table_with_columns("t1", &[("a", true)]);
+        needed_table.indexes = vec![Index::new("t1", vec!["a"], false)];
+        let actual_table = make_table_with_columns("t1", &[("a", true)]);
+        let options = MigrationOptions {
+            concurrent_indexes: true,
+            ..Default::default()
+        };
+
+        let steps = plan_migration(&[needed_table], &[actual_table], "public", &options).unwrap();
+        assert_eq!(steps.len(), 1);
+        assert!(!steps[0].transactional());
+        assert_eq!(
+            steps[0].clone().ddls(),
+            vec!["CREATE INDEX CONCURRENTLY IF NOT EXISTS \"t1_a_idx\" ON \"public\".\"t1\" (a)"]
+        );
+    }
+
+    #[test]
+    fn test_plan_migration_existing_unique_index_matched_by_columns() {
+        let mut needed_table = make_table_with_columns("t1", &[("a", true)]);
+        needed_table.columns[0].unique = true;
+        let mut actual_table = make_table_with_columns("t1", &[("a", true)]);
+        // inline UNIQUE constraints are backed by "t1_a_key" unique index
+        actual_table.indexes = vec![Index::new("t1", vec!["a"], true)];
+
+        let steps = plan_migration(
+            &[needed_table],
+            &[actual_table],
+            "public",
+            &MigrationOptions::default(),
+        )
+        .unwrap();
+        assert!(steps.is_empty());
+    }
+
+    #[test]
+    fn test_plan_migration_localized_unique_index_matched_by_expression() {
+        let mut needed_table = make_table_with_columns("t1", &[("title", false)]);
+        needed_table.indexes = vec![Index::new("t1", vec!["(title ->> 'de')"], true)];
+        let actual_table = make_table_with_columns("t1", &[("title", false)]);
+
+        let steps = plan_migration(
+            &[needed_table.clone()],
+            std::slice::from_ref(&actual_table),
+            "public",
+            &MigrationOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            steps[0].clone().ddls(),
+            vec![
+                "CREATE UNIQUE INDEX IF NOT EXISTS \"t1_title_de_key\" ON \"public\".\"t1\" ((title ->> 'de'))"
+            ]
+        );
+
+        // as read back by Postgres
+        let mut actual_table = actual_table;
+        actual_table.indexes = vec![Index::new("t1", vec!["(title ->> 'de'::text)"], true)];
+        let steps = plan_migration(
+            &[needed_table],
+            &[actual_table],
+            "public",
+            &MigrationOptions::default(),
+        )
+        .unwrap();
+        assert!(steps.is_empty());
+    }
+
+    #[test]
+    fn test_plan_migration_trigram_index_matched_by_method() {
+        let mut needed_table = make_table_with_columns("t1", &[("name", false)]);
+        needed_table.indexes = vec![
+            Index::new("t1", vec!["name"], false),
+            Index::new("t1", vec!["name"], false).with_method(IndexMethod::GinTrigram),
+        ];
+        let mut actual_table = make_table_with_columns("t1", &[("name", false)]);
+        actual_table.indexes = vec![Index::new("t1", vec!["name"], false)];
+
+        let steps = plan_migration(
+            &[needed_table.clone()],
+            &[actual_table.clone()],
+            "public",
+            &MigrationOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(
+            steps[0].clone().ddls(),
+            vec![
+                "CREATE EXTENSION IF NOT EXISTS pg_trgm",
+                "CREATE INDEX IF NOT EXISTS \"t1_name_trgm_idx\" ON \"public\".\"t1\" USING gin (name gin_trgm_ops)"
+            ]
+        );
+
+        actual_table.indexes = needed_table.indexes.clone();
+        let steps = plan_migration(
+            &[needed_table],
+            &[actual_table],
+            "public",
+            &MigrationOptions::default(),
+        )
+        .unwrap();
+        assert!(steps.is_empty());
+    }
+
+    #[test]
+    fn test_render_script() {
+        let steps = vec![
+            MigrationStepItem::Drop(DropTableStep::new("public", "t1")),
+            MigrationStepItem::CreateIndex(CreateIndexStep::new(
+                "public",
+                &Index::new("t2", vec!["a"], false),
+                true,
+            )),
+        ];
+
+        let script = render_script("public", steps);
+        assert_eq!(
+            script,
+            "-- Luminair migration script for schema \"public\"\n\
+             \n-- Context: DROP TABLE\n\
+             BEGIN;\n\
+             DROP TABLE IF EXISTS \"public\".\"t1\" CASCADE;\n\
+             COMMIT;\n\
+             \n-- Context: CREATE INDEX\n\
+             CREATE INDEX CONCURRENTLY IF NOT EXISTS \"t2_a_idx\" ON \"public\".\"t2\" (a);\n"
+        );
+    }
+
+    #[test]
+    fn test_render_snapshot_orders_tables() {
+        let parent = make_test_table("parent");
+        let mut child = make_test_table("child");
+        child.foreign_keys.push(ForeignKeyConstraint::new(
+            "child",
+            "parent_id",
+            "parent",
+            "id",
+        ));
+
+        let script = render_snapshot("public", &[child, parent], &[]).unwrap();
+        assert!(script.starts_with("-- Luminair schema snapshot for schema \"public\"\nBEGIN;\n"));
+        assert!(script.contains("CREATE SCHEMA IF NOT EXISTS \"public\";"));
+        let parent_at = script.find("CREATE TABLE \"public\".\"parent\"").unwrap();
+        let child_at = script.find("CREATE TABLE \"public\".\"child\"").unwrap();
+        assert!(
+            parent_at < child_at,
+            "referenced table must be created first"
+        );
+        assert!(script.ends_with("COMMIT;\n"));
+    }
+
+    #[test]
+    fn test_schema_version_ignores_table_order() {
+        let version = schema_version(&[make_test_table("a"), make_test_table("b")]);
+        assert_eq!(
+            version,
+            schema_version(&[make_test_table("b"), make_test_table("a")])
+        );
+        assert_ne!(version, schema_version(&[make_test_table("a")]));
+    }
+
+    #[test]
+    fn test_render_script_without_steps() {
+        let script = render_script("public", vec![]);
+        assert!(script.contains("Database schema is up to date"));
+    }
 }
@@ -1,6 +1,10 @@
 pub mod dependency;
+pub mod history;
 pub mod migration;
+pub mod partitions;
 pub mod schema;
+pub mod seeds;
 pub mod tables;
+pub mod views;
 
 pub use schema::DocumentTables;
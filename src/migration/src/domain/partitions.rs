@@ -0,0 +1,65 @@
+use chrono::{Datelike, Months, NaiveDate};
+use luminair_common::entities::{PartitionInterval, Partitioning};
+
+/// DDLs creating the partitions of the partitioned table `table_name` for
+/// the interval `today` falls into and `premake` intervals ahead of it.
+/// Partitions which exist already are left as they are.
+pub fn create_partitions_ddl(
+    schema: &str,
+    table_name: &str,
+    partitioning: &Partitioning,
+    today: NaiveDate,
+) -> Vec<String> {
+    match partitioning.interval {
+        PartitionInterval::Month => {
+            let first = today.with_day(1).unwrap_or(today);
+            (0..=partitioning.premake)
+                .filter_map(|ahead| {
+                    let from = first.checked_add_months(Months::new(ahead))?;
+                    let to = from.checked_add_months(Months::new(1))?;
+                    Some(create_partition_ddl(schema, table_name, from, to))
+                })
+                .collect()
+        }
+    }
+}
+
+/// `{table}_p{year}_{month}`, e.g. `events_p2026_10`
+pub fn partition_name(table_name: &str, from: NaiveDate) -> String {
+    format!("{}_p{}_{:02}", table_name, from.year(), from.month())
+}
+
+fn create_partition_ddl(schema: &str, table_name: &str, from: NaiveDate, to: NaiveDate) -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS \"{0}\".\"{1}\" PARTITION OF \"{0}\".\"{2}\" FOR VALUES FROM ('{3} 00:00:00+00') TO ('{4} 00:00:00+00')",
+        schema,
+        partition_name(table_name, from),
+        table_name,
+        from,
+        to
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_monthly_partitions_ahead() {
+        let partitioning = Partitioning {
+            interval: PartitionInterval::Month,
+            premake: 2,
+        };
+        let today = NaiveDate::from_ymd_opt(2026, 11, 16).unwrap();
+
+        let ddls = create_partitions_ddl("public", "events", &partitioning, today);
+        assert_eq!(
+            ddls,
+            vec![
+                "CREATE TABLE IF NOT EXISTS \"public\".\"events_p2026_11\" PARTITION OF \"public\".\"events\" FOR VALUES FROM ('2026-11-01 00:00:00+00') TO ('2026-12-01 00:00:00+00')",
+                "CREATE TABLE IF NOT EXISTS \"public\".\"events_p2026_12\" PARTITION OF \"public\".\"events\" FOR VALUES FROM ('2026-12-01 00:00:00+00') TO ('2027-01-01 00:00:00+00')",
+                "CREATE TABLE IF NOT EXISTS \"public\".\"events_p2027_01\" PARTITION OF \"public\".\"events\" FOR VALUES FROM ('2027-01-01 00:00:00+00') TO ('2027-02-01 00:00:00+00')",
+            ]
+        );
+    }
+}
@@ -1,11 +1,18 @@
-use crate::domain::tables::{Column, ColumnType, ForeignKeyConstraint, Index, Table};
+use crate::domain::tables::{Column, ColumnType, ForeignKeyConstraint, Index, IndexMethod, Table};
 
-use luminair_common::entities::{DocumentField, IntegerSize};
+use luminair_common::entities::{DocumentField, FieldConstraint, IntegerSize};
+use luminair_common::persistence::{
+    ERASURES_TABLE_NAME, IDEMPOTENCY_TABLE_NAME, JOBS_TABLE_NAME, OUTBOX_TABLE_NAME,
+    archived_table_name, main_table_name, relation_snapshot_table_name, relation_table_name,
+    revision_table_name, snapshot_table_name,
+};
 use luminair_common::{
-    CREATED_BY_FIELD_NAME, CREATED_FIELD_NAME, DOCUMENT_ID_FIELD_NAME, DocumentType,
-    DocumentTypesRegistry, OWNING_DOCUMENT_ID_FIELD_NAME, PUBLISHED_BY_FIELD_NAME,
-    PUBLISHED_FIELD_NAME, REVISION_FIELD_NAME, SNAPSHOT_ID_FIELD_NAME, STATUS_FIELD_NAME,
-    TARGET_DOCUMENT_ID_FIELD_NAME, UPDATED_BY_FIELD_NAME, UPDATED_FIELD_NAME, VERSION_FIELD_NAME,
+    ARCHIVED_FIELD_NAME, CREATED_BY_FIELD_NAME, CREATED_FIELD_NAME, DOCUMENT_ID_FIELD_NAME,
+    DocumentType, DocumentTypesRegistry, LOCALES_UPDATED_FIELD_NAME, OWNING_DOCUMENT_ID_FIELD_NAME,
+    PUBLISH_AT_FIELD_NAME, PUBLISHED_BY_FIELD_NAME, PUBLISHED_FIELD_NAME, REVISION_FIELD_NAME,
+    REVISION_ID_FIELD_NAME, SNAPSHOT_ID_FIELD_NAME, STATUS_FIELD_NAME,
+    TARGET_DOCUMENT_ID_FIELD_NAME, UNPUBLISH_AT_FIELD_NAME, UPDATED_BY_FIELD_NAME,
+    UPDATED_FIELD_NAME, VERSION_FIELD_NAME, WORKFLOW_STAGE_FIELD_NAME,
     entities::{DocumentRelation, FieldType},
 };
 
@@ -18,6 +25,7 @@ impl DocumentTables {
         let mut tables = Vec::new();
 
         let mut main_table_builder = MainTableBuilder::new(document);
+        let mut revisions_table_builder = RevisionsTableBuilder::new(document);
 
         if document.has_draft_and_publish() {
             let mut snapshots_table_builder = SnapshotsTableBuilder::new(document);
@@ -25,6 +33,7 @@ impl DocumentTables {
                 document,
                 &mut main_table_builder,
                 Some(&mut snapshots_table_builder),
+                &mut revisions_table_builder,
             );
             let main_table = main_table_builder.into();
             let snapshots_table = snapshots_table_builder.into();
@@ -32,10 +41,23 @@ impl DocumentTables {
             tables.push(main_table);
             tables.push(snapshots_table);
         } else {
-            handle_document_fields(document, &mut main_table_builder, None);
+            handle_document_fields(
+                document,
+                &mut main_table_builder,
+                None,
+                &mut revisions_table_builder,
+            );
             let main_table = main_table_builder.into();
             tables.push(main_table);
         }
+        if document
+            .retention()
+            .is_some_and(|retention| retention.archives())
+        {
+            let archived_table = archived_table(document, &tables[0]);
+            tables.push(archived_table);
+        }
+        tables.push(revisions_table_builder.into());
 
         for relation in document.relations.iter() {
             if relation.relation_type.is_owning() {
@@ -52,14 +74,194 @@ impl DocumentTables {
     }
 }
 
+/// Content change events written with the changes themselves, until they
+/// are published to the event bus by the service.
+pub fn outbox_table() -> Table {
+    let columns = vec![
+        Column::primary_key("event_id", ColumnType::Identity(IntegerSize::Int64), None),
+        Column::new("document_type", ColumnType::Text, None, true, false, None),
+        Column::new(
+            DOCUMENT_ID_FIELD_NAME,
+            ColumnType::Uuid,
+            None,
+            true,
+            false,
+            None,
+        ),
+        Column::new("action", ColumnType::Text, None, true, false, None),
+        Column::new("actor", ColumnType::Text, None, false, false, None),
+        Column::new(
+            "occurred_at",
+            ColumnType::TimestampTZ,
+            None,
+            true,
+            false,
+            Some("now()"),
+        ),
+        Column::new("diff", ColumnType::JsonB, None, true, false, None),
+        Column::new(
+            "published_at",
+            ColumnType::TimestampTZ,
+            None,
+            false,
+            false,
+            None,
+        ),
+    ];
+
+    // the relay only ever looks up events which are not published yet
+    let indexes = vec![
+        Index::new(OUTBOX_TABLE_NAME, vec!["event_id"], false).with_where("published_at IS NULL"),
+    ];
+
+    Table::new(OUTBOX_TABLE_NAME.to_string(), columns, vec![], indexes)
+}
+
+/// Table of the responses of the writes sent with an `Idempotency-Key`; the
+/// status is null while the first request is still being handled
+pub fn idempotency_table() -> Table {
+    let columns = vec![
+        Column::primary_key("idempotency_key", ColumnType::Text, None),
+        Column::new("request", ColumnType::Text, None, true, false, None),
+        Column::new(
+            "status",
+            ColumnType::Integer(IntegerSize::Int32),
+            None,
+            false,
+            false,
+            None,
+        ),
+        Column::new("headers", ColumnType::JsonB, None, false, false, None),
+        Column::new("body", ColumnType::Text, None, false, false, None),
+        Column::new(
+            "created_at",
+            ColumnType::TimestampTZ,
+            None,
+            true,
+            false,
+            Some("now()"),
+        ),
+    ];
+
+    // expired keys are deleted by their age
+    let indexes = vec![Index::new(
+        IDEMPOTENCY_TABLE_NAME,
+        vec!["created_at"],
+        false,
+    )];
+
+    Table::new(IDEMPOTENCY_TABLE_NAME.to_string(), columns, vec![], indexes)
+}
+
+/// Table of the background jobs: pending until a worker claims them, running,
+/// then succeeded, failed once out of attempts, or cancelled
+pub fn jobs_table() -> Table {
+    let columns = vec![
+        Column::primary_key("job_id", ColumnType::Identity(IntegerSize::Int64), None),
+        Column::new("kind", ColumnType::Text, None, true, false, None),
+        Column::new("payload", ColumnType::JsonB, None, true, false, None),
+        Column::new(
+            "status",
+            ColumnType::Text,
+            None,
+            true,
+            false,
+            Some("'pending'"),
+        ),
+        Column::new(
+            "attempts",
+            ColumnType::Integer(IntegerSize::Int32),
+            None,
+            true,
+            false,
+            Some("0"),
+        ),
+        Column::new(
+            "max_attempts",
+            ColumnType::Integer(IntegerSize::Int32),
+            None,
+            true,
+            false,
+            None,
+        ),
+        Column::new(
+            "run_at",
+            ColumnType::TimestampTZ,
+            None,
+            true,
+            false,
+            Some("now()"),
+        ),
+        Column::new(
+            "started_at",
+            ColumnType::TimestampTZ,
+            None,
+            false,
+            false,
+            None,
+        ),
+        Column::new(
+            "finished_at",
+            ColumnType::TimestampTZ,
+            None,
+            false,
+            false,
+            None,
+        ),
+        Column::new("last_error", ColumnType::Text, None, false, false, None),
+        Column::new(
+            "created_at",
+            ColumnType::TimestampTZ,
+            None,
+            true,
+            false,
+            Some("now()"),
+        ),
+    ];
+
+    // the workers look up the due jobs only
+    let indexes =
+        vec![Index::new(JOBS_TABLE_NAME, vec!["run_at"], false).with_where("status = 'pending'")];
+
+    Table::new(JOBS_TABLE_NAME.to_string(), columns, vec![], indexes)
+}
+
+/// Table of the audit records of the erasures of personal data; the subject
+/// is kept as the SHA-256 of the identifying value, never the value itself
+pub fn erasures_table() -> Table {
+    let columns = vec![
+        Column::primary_key("erasure_id", ColumnType::Identity(IntegerSize::Int64), None),
+        Column::new("subject", ColumnType::Text, None, true, false, None),
+        Column::new("erased_by", ColumnType::Text, None, false, false, None),
+        Column::new("documents", ColumnType::JsonB, None, true, false, None),
+        Column::new(
+            "erased_at",
+            ColumnType::TimestampTZ,
+            None,
+            true,
+            false,
+            Some("now()"),
+        ),
+    ];
+
+    // the erasures of a subject are looked up by the hash of its value
+    let indexes = vec![Index::new(ERASURES_TABLE_NAME, vec!["subject"], false)];
+
+    Table::new(ERASURES_TABLE_NAME.to_string(), columns, vec![], indexes)
+}
+
 struct MainTableBuilder {
     table_name: String,
+    renamed_from: Option<String>,
     columns: Vec<Column>,
+    indexes: Vec<Index>,
+    partitioned: bool,
 }
 
 impl MainTableBuilder {
     fn new(document: &DocumentType) -> Self {
-        let table_name = document.id.normalized();
+        let table_name = main_table_name(&document.id);
+        let renamed_from = document.renamed_from.as_ref().map(main_table_name);
 
         let mut columns = vec![
             Column::primary_key(DOCUMENT_ID_FIELD_NAME, ColumnType::Uuid, None),
@@ -83,9 +285,66 @@ impl MainTableBuilder {
 
         columns.extend(common_columns());
 
+        // the primary key of a partitioned table includes its partition key
+        let partitioned = document.partitioning().is_some();
+        if partitioned {
+            for column in columns.iter_mut() {
+                if column.name == CREATED_FIELD_NAME {
+                    column.primary_key = true;
+                }
+            }
+        }
+
+        // scheduled publication, looked up by the scheduler on every run
+        let mut indexes = vec![];
+        if document.has_draft_and_publish() {
+            for column_name in [PUBLISH_AT_FIELD_NAME, UNPUBLISH_AT_FIELD_NAME] {
+                columns.push(Column::new(
+                    column_name,
+                    ColumnType::TimestampTZ,
+                    None,
+                    false,
+                    false,
+                    None,
+                ));
+                indexes.push(
+                    Index::new(&table_name as &str, vec![column_name], false)
+                        .with_where(format!("{} IS NOT NULL", column_name)),
+                );
+            }
+        }
+
+        // NULL until the document is moved out of the initial workflow stage
+        if document.workflow().is_some() {
+            columns.push(Column::new(
+                WORKFLOW_STAGE_FIELD_NAME,
+                ColumnType::Text,
+                None,
+                false,
+                false,
+                None,
+            ));
+        }
+
+        // when the texts of each locale last changed, to tell stale translations;
+        // NULL in rows restored from archives made before it was tracked
+        if document.has_localization() {
+            columns.push(Column::new(
+                LOCALES_UPDATED_FIELD_NAME,
+                ColumnType::JsonB,
+                None,
+                false,
+                false,
+                Some("'{}'"),
+            ));
+        }
+
         Self {
             table_name,
+            renamed_from,
             columns,
+            indexes,
+            partitioned,
         }
     }
 
@@ -93,22 +352,35 @@ impl MainTableBuilder {
         self.columns.push(column);
     }
 
+    fn push_index(&mut self, index: Index) {
+        self.indexes.push(index);
+    }
+
     fn into(self) -> Table {
         let foreign_keys = vec![];
-        let indexes = vec![];
+        let partition_by = self.partitioned.then_some(CREATED_FIELD_NAME);
 
-        Table::new(self.table_name, self.columns, foreign_keys, indexes)
+        Table::new(self.table_name, self.columns, foreign_keys, self.indexes)
+            .with_renamed_from(self.renamed_from)
+            .with_partition_by(partition_by)
     }
 }
 
 struct SnapshotsTableBuilder {
     table_name: String,
+    main_table_name: String,
+    renamed_from: Option<String>,
     columns: Vec<Column>,
+    indexes: Vec<Index>,
+    /// whether the main table is partitioned, and can't be referenced
+    partitioned: bool,
 }
 
 impl SnapshotsTableBuilder {
     fn new(document: &DocumentType) -> Self {
-        let table_name = format!("{}_snapshots", document.id.normalized());
+        let table_name = snapshot_table_name(&document.id);
+        let main_table_name = main_table_name(&document.id);
+        let renamed_from = document.renamed_from.as_ref().map(snapshot_table_name);
         let mut columns = vec![
             Column::primary_key(
                 SNAPSHOT_ID_FIELD_NAME,
@@ -127,9 +399,19 @@ impl SnapshotsTableBuilder {
 
         columns.extend(common_columns());
 
+        let indexes = vec![Index::new(
+            &table_name as &str,
+            vec![DOCUMENT_ID_FIELD_NAME, REVISION_FIELD_NAME],
+            true,
+        )];
+
         Self {
             table_name,
+            main_table_name,
+            renamed_from,
             columns,
+            indexes,
+            partitioned: document.partitioning().is_some(),
         }
     }
 
@@ -137,24 +419,135 @@ impl SnapshotsTableBuilder {
         self.columns.push(column);
     }
 
+    fn push_index(&mut self, index: Index) {
+        self.indexes.push(index);
+    }
+
     fn into(self) -> Table {
-        let main_table_name = self.table_name.strip_suffix("_snapshots").unwrap();
+        let foreign_keys =
+            main_table_reference(&self.table_name, &self.main_table_name, self.partitioned);
+        let indexes = self.indexes;
 
-        let foreign_keys = vec![ForeignKeyConstraint::new(
-            &self.table_name as &str,
-            DOCUMENT_ID_FIELD_NAME,
+        Table::new(self.table_name, self.columns, foreign_keys, indexes)
+            .with_renamed_from(self.renamed_from)
+    }
+}
+
+/// Previous contents of the main table row, one row per update or publish
+/// of the document, identified by the version the document had.
+struct RevisionsTableBuilder {
+    table_name: String,
+    main_table_name: String,
+    renamed_from: Option<String>,
+    columns: Vec<Column>,
+    /// whether the main table is partitioned, and can't be referenced
+    partitioned: bool,
+}
+
+impl RevisionsTableBuilder {
+    fn new(document: &DocumentType) -> Self {
+        let table_name = revision_table_name(&document.id);
+        let main_table_name = main_table_name(&document.id);
+        let renamed_from = document.renamed_from.as_ref().map(revision_table_name);
+        let mut columns = vec![
+            Column::primary_key(
+                REVISION_ID_FIELD_NAME,
+                ColumnType::Identity(IntegerSize::Int64),
+                None,
+            ),
+            Column::new(
+                DOCUMENT_ID_FIELD_NAME,
+                ColumnType::Uuid,
+                None,
+                true,
+                false,
+                None,
+            ),
+            Column::new(STATUS_FIELD_NAME, ColumnType::Text, None, true, false, None),
+            Column::new(
+                VERSION_FIELD_NAME,
+                ColumnType::Integer(IntegerSize::Int32),
+                None,
+                true,
+                false,
+                None,
+            ),
+        ];
+
+        columns.extend(common_columns());
+
+        Self {
+            table_name,
             main_table_name,
-            DOCUMENT_ID_FIELD_NAME,
-        )];
+            renamed_from,
+            columns,
+            partitioned: document.partitioning().is_some(),
+        }
+    }
+
+    fn push(&mut self, column: Column) {
+        self.columns.push(column);
+    }
+
+    fn into(self) -> Table {
+        let foreign_keys =
+            main_table_reference(&self.table_name, &self.main_table_name, self.partitioned);
 
         let indexes = vec![Index::new(
             &self.table_name as &str,
-            vec![DOCUMENT_ID_FIELD_NAME, REVISION_FIELD_NAME],
+            vec![DOCUMENT_ID_FIELD_NAME, VERSION_FIELD_NAME],
             true,
         )];
 
         Table::new(self.table_name, self.columns, foreign_keys, indexes)
+            .with_renamed_from(self.renamed_from)
+    }
+}
+
+/// Foreign key of the rows of `table_name` to their document in the main
+/// table. A partitioned main table has no unique key on the document id
+/// alone to be referenced by, its rows are deleted by the service instead.
+fn main_table_reference(
+    table_name: &str,
+    main_table_name: &str,
+    partitioned: bool,
+) -> Vec<ForeignKeyConstraint> {
+    if partitioned {
+        return Vec::new();
     }
+    vec![ForeignKeyConstraint::new(
+        table_name,
+        DOCUMENT_ID_FIELD_NAME,
+        main_table_name,
+        DOCUMENT_ID_FIELD_NAME,
+    )]
+}
+
+/// Table the expired documents of a type with an archiving retention are
+/// moved to: the columns of its main table without their constraints, and
+/// the time each document was archived at.
+fn archived_table(document: &DocumentType, main_table: &Table) -> Table {
+    let mut columns = main_table
+        .columns
+        .iter()
+        .map(|column| Column {
+            unique: false,
+            primary_key: column.name == DOCUMENT_ID_FIELD_NAME,
+            checks: Vec::new(),
+            ..column.clone()
+        })
+        .collect::<Vec<_>>();
+    columns.push(Column::new(
+        ARCHIVED_FIELD_NAME,
+        ColumnType::TimestampTZ,
+        None,
+        true,
+        false,
+        Some("now()"),
+    ));
+
+    Table::new(archived_table_name(&document.id), columns, vec![], vec![])
+        .with_renamed_from(document.renamed_from.as_ref().map(archived_table_name))
 }
 
 fn common_columns() -> Vec<Column> {
@@ -227,17 +620,18 @@ impl RelationTablesBuilder {
         documents: &dyn DocumentTypesRegistry,
     ) -> (Table, Table) {
         let target_document = documents.get(&relation.target).unwrap();
-        let target_table_name = target_document.id.normalized();
-        let relation_table_name = format!(
-            "{}_{}_relation",
-            document.id.normalized(),
-            relation.id.normalized()
-        );
-        let snapshot_relation_table_name = format!(
-            "{}_{}_relation_snapshots",
-            document.id.normalized(),
-            relation.id.normalized()
-        );
+        let target_table_name = main_table_name(&target_document.id);
+        let owning_table_name = main_table_name(&document.id);
+        let working_table_name = relation_table_name(&document.id, &relation.id);
+        let snapshot_relation_table_name = relation_snapshot_table_name(&document.id, &relation.id);
+        // relation tables are renamed when either the document type or the relation is renamed
+        let previous_ids = (document.renamed_from.is_some() || relation.renamed_from.is_some())
+            .then(|| {
+                (
+                    document.renamed_from.as_ref().unwrap_or(&document.id),
+                    relation.renamed_from.as_ref().unwrap_or(&relation.id),
+                )
+            });
 
         // Working relation table
         let working_columns = vec![
@@ -247,30 +641,34 @@ impl RelationTablesBuilder {
 
         let working_foreign_keys = vec![
             ForeignKeyConstraint::new(
-                &relation_table_name as &str,
+                &working_table_name as &str,
                 OWNING_DOCUMENT_ID_FIELD_NAME,
-                &document.id.normalized(),
+                &owning_table_name,
                 DOCUMENT_ID_FIELD_NAME,
             ),
             ForeignKeyConstraint::new(
-                &relation_table_name as &str,
+                &working_table_name as &str,
                 TARGET_DOCUMENT_ID_FIELD_NAME,
                 &target_table_name,
                 DOCUMENT_ID_FIELD_NAME,
-            ),
+            )
+            .with_on_delete(relation.on_delete),
         ];
 
         let working_indexes = vec![Index::new(
-            &relation_table_name as &str,
+            &working_table_name as &str,
             vec![TARGET_DOCUMENT_ID_FIELD_NAME],
             false,
         )];
 
         let working_table = Table::new(
-            relation_table_name.clone(),
+            working_table_name,
             working_columns,
             working_foreign_keys,
             working_indexes,
+        )
+        .with_renamed_from(
+            previous_ids.map(|(document, relation)| relation_table_name(document, relation)),
         );
 
         // Snapshot relation table
@@ -295,7 +693,7 @@ impl RelationTablesBuilder {
             ForeignKeyConstraint::new(
                 &snapshot_relation_table_name as &str,
                 SNAPSHOT_ID_FIELD_NAME,
-                &format!("{}_snapshots", document.id.normalized()),
+                &snapshot_table_name(&document.id),
                 SNAPSHOT_ID_FIELD_NAME,
             ),
             ForeignKeyConstraint::new(
@@ -303,11 +701,12 @@ impl RelationTablesBuilder {
                 TARGET_DOCUMENT_ID_FIELD_NAME,
                 &target_table_name,
                 DOCUMENT_ID_FIELD_NAME,
-            ),
+            )
+            .with_on_delete(relation.on_delete),
             ForeignKeyConstraint::new(
                 &snapshot_relation_table_name as &str,
                 OWNING_DOCUMENT_ID_FIELD_NAME,
-                &document.id.normalized(),
+                &owning_table_name,
                 DOCUMENT_ID_FIELD_NAME,
             ),
         ];
@@ -330,6 +729,10 @@ impl RelationTablesBuilder {
             snapshot_columns,
             snapshot_foreign_keys,
             snapshot_indexes,
+        )
+        .with_renamed_from(
+            previous_ids
+                .map(|(document, relation)| relation_snapshot_table_name(document, relation)),
         );
 
         (working_table, snapshot_table)
@@ -340,26 +743,64 @@ fn handle_document_fields(
     document: &DocumentType,
     main_table_builder: &mut MainTableBuilder,
     mut snapshots_table_builder: Option<&mut SnapshotsTableBuilder>,
+    revisions_table_builder: &mut RevisionsTableBuilder,
 ) {
     for field in document.fields.iter() {
         let column_type = infer_column_type(field);
+        let renamed_from = field.renamed_from.as_ref().map(|id| id.normalized());
 
+        // values of a localized field are unique per locale, not as a whole
+        let localized = field.field_type == FieldType::LocalizedText;
         let column = Column::new(
             field.id.normalized(),
             column_type,
             None,
             field.required,
-            field.unique,
+            field.unique && !localized,
             None,
-        );
+        )
+        .with_checks(column_checks(field, column_type))
+        .with_renamed_from(renamed_from.clone());
+
+        if field.unique && localized {
+            for locale in document
+                .options
+                .iter()
+                .flat_map(|options| options.localizations.iter())
+            {
+                main_table_builder.push_index(Index::new(
+                    main_table_builder.table_name.clone(),
+                    vec![localized_value_expression(&column.name, locale.as_ref())],
+                    true,
+                ));
+            }
+        }
+
+        // unique values are already indexed by the unique indexes
+        for index in field_indexes(
+            document,
+            field,
+            &main_table_builder.table_name,
+            &column.name,
+            field.unique,
+        ) {
+            main_table_builder.push_index(index);
+        }
 
         main_table_builder.push(column.clone());
         if let Some(ref mut stb) = snapshots_table_builder {
+            // published documents are read from the snapshot table,
+            // so it's indexed for the same filters and sorts
+            for index in field_indexes(document, field, &stb.table_name, &column.name, false) {
+                stb.push_index(index);
+            }
             // In snapshot tables, field-level uniqueness constraints must NOT be
             // inherited: the table is a historical revision log — multiple revision
             // rows for the same document share identical field values (e.g. the same
             // `uid`). Uniqueness is already guaranteed by the composite unique index
             // on (document_id, revision) that SnapshotsTableBuilder creates.
+            // CHECK constraints aren't inherited either, revisions keep values
+            // which were valid when they were published.
             let snapshot_column = Column::new(
                 field.id.normalized(),
                 column_type,
@@ -367,13 +808,81 @@ fn handle_document_fields(
                 field.required,
                 false, // never unique in snapshot table
                 None,
-            );
+            )
+            .with_renamed_from(renamed_from.clone());
             stb.push(snapshot_column);
         }
+
+        // Revisions keep the content of every field like snapshots, without
+        // NOT NULL either: fields added later are empty in older revisions.
+        let revision_column =
+            Column::new(field.id.normalized(), column_type, None, false, false, None)
+                .with_renamed_from(renamed_from);
+        revisions_table_builder.push(revision_column);
+    }
+}
+
+/// Indexes backing the filters and sorts clients run on a field flagged
+/// `filterable` or `sortable`: a B-tree on its value, one per locale for
+/// localized text, and a trigram GIN on filterable text for the contains,
+/// starts with and ends with filters a B-tree can't serve.
+/// JSON fields aren't indexed, they are neither filtered nor sorted by.
+fn field_indexes(
+    document: &DocumentType,
+    field: &DocumentField,
+    table_name: &str,
+    column_name: &str,
+    unique: bool,
+) -> Vec<Index> {
+    let keys = match field.field_type {
+        FieldType::Json => return Vec::new(),
+        FieldType::LocalizedText => document
+            .options
+            .iter()
+            .flat_map(|options| options.localizations.iter())
+            .map(|locale| localized_value_expression(column_name, locale.as_ref()))
+            .collect(),
+        _ => vec![column_name.to_string()],
+    };
+    let text = matches!(
+        field.field_type,
+        FieldType::Text | FieldType::Uid | FieldType::LocalizedText
+    );
+
+    let mut indexes = Vec::new();
+    for key in keys {
+        if (field.filterable || field.sortable) && !unique {
+            indexes.push(Index::new(table_name.to_string(), vec![key.clone()], false));
+        }
+        if field.filterable && text {
+            indexes.push(
+                Index::new(table_name.to_string(), vec![key], false)
+                    .with_method(IndexMethod::GinTrigram),
+            );
+        }
+    }
+    indexes
+}
+
+/// `(column ->> 'locale')`, the text of one locale of a localized field as
+/// indexed by the per-locale unique indexes
+fn localized_value_expression(column_name: &str, locale: &str) -> String {
+    format!("({} ->> '{}')", column_name, locale.replace('\'', "''"))
+}
+
+/// Field constraints which can be checked by the database,
+/// values of localized fields are JSON objects and are validated by the API only
+fn column_checks(field: &DocumentField, column_type: ColumnType) -> Vec<FieldConstraint> {
+    if column_type == ColumnType::JsonB {
+        return Vec::new();
     }
+    let mut checks: Vec<FieldConstraint> = field.constraints.iter().cloned().collect();
+    // stable order of constraints in generated DDL
+    checks.sort_by_key(|constraint| format!("{:?}", constraint));
+    checks
 }
 
-fn infer_column_type(field: &DocumentField) -> ColumnType {
+pub(crate) fn infer_column_type(field: &DocumentField) -> ColumnType {
     match field.field_type {
         FieldType::Uid => ColumnType::Text,
         FieldType::Uuid => ColumnType::Uuid,
@@ -383,6 +892,8 @@ fn infer_column_type(field: &DocumentField) -> ColumnType {
         FieldType::Decimal { precision, scale } => ColumnType::Decimal { precision, scale },
         FieldType::Date => ColumnType::Date,
         FieldType::DateTime => ColumnType::TimestampTZ,
+        FieldType::LocalDateTime => ColumnType::Timestamp,
+        FieldType::Time => ColumnType::Time,
         FieldType::Boolean => ColumnType::Boolean,
         FieldType::Json => ColumnType::JsonB,
     }
@@ -0,0 +1,174 @@
+use luminair_common::persistence::main_table_name;
+use luminair_common::{AttributeId, DOCUMENT_ID_FIELD_NAME, DocumentType};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+/// Key of the document identifier in seed documents
+pub const SEED_DOCUMENT_ID_KEY: &str = "documentId";
+
+#[derive(Debug, thiserror::Error)]
+pub enum SeedError {
+    #[error("Seed for document type '{0}' has no matching document type")]
+    UnknownDocumentType(String),
+    #[error("Seed document #{index} of '{document_type}' has no valid '{SEED_DOCUMENT_ID_KEY}'")]
+    InvalidDocumentId { document_type: String, index: usize },
+    #[error("Seed document #{index} of '{document_type}' has unknown field '{attribute}'")]
+    UnknownField {
+        document_type: String,
+        index: usize,
+        attribute: String,
+    },
+}
+
+/// How seed documents are written when a document with the same id already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SeedMode {
+    /// Insert only missing documents, existing ones are kept as they are
+    #[default]
+    Insert,
+    /// Insert missing documents and overwrite seeded fields of existing ones
+    Upsert,
+}
+
+/// Initial content of one document type, ready to be written into its main table
+#[derive(Debug, Clone)]
+pub struct Seed {
+    pub table_name: String,
+    pub mode: SeedMode,
+    pub documents: Vec<SeedDocument>,
+}
+
+/// One seeded document: values keyed by column name, including `document_id`
+#[derive(Debug, Clone)]
+pub struct SeedDocument {
+    pub document_id: Uuid,
+    pub values: Map<String, Value>,
+}
+
+impl Seed {
+    /// Validates raw seed documents against the document type and maps attribute ids into column names
+    pub fn new(
+        document: &DocumentType,
+        mode: SeedMode,
+        raw_documents: Vec<Map<String, Value>>,
+    ) -> Result<Self, SeedError> {
+        let document_type = document.id.to_string();
+
+        let mut documents = Vec::with_capacity(raw_documents.len());
+        for (index, raw) in raw_documents.into_iter().enumerate() {
+            let document_id = raw
+                .get(SEED_DOCUMENT_ID_KEY)
+                .and_then(Value::as_str)
+                .and_then(|id| Uuid::parse_str(id).ok())
+                .ok_or_else(|| SeedError::InvalidDocumentId {
+                    document_type: document_type.clone(),
+                    index,
+                })?;
+
+            let mut values = Map::new();
+            values.insert(
+                DOCUMENT_ID_FIELD_NAME.to_string(),
+                Value::String(document_id.to_string()),
+            );
+            for (key, value) in raw {
+                if key == SEED_DOCUMENT_ID_KEY {
+                    continue;
+                }
+                let field = AttributeId::try_new(key.as_str())
+                    .ok()
+                    .and_then(|id| document.fields.get(&id))
+                    .ok_or_else(|| SeedError::UnknownField {
+                        document_type: document_type.clone(),
+                        index,
+                        attribute: key.clone(),
+                    })?;
+                values.insert(field.id.normalized(), value);
+            }
+
+            documents.push(SeedDocument {
+                document_id,
+                values,
+            });
+        }
+
+        Ok(Self {
+            table_name: main_table_name(&document.id),
+            mode,
+            documents,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use luminair_common::entities::{DocumentField, FieldType};
+    use serde_json::json;
+    use std::collections::HashSet;
+
+    fn make_document() -> DocumentType {
+        let mut document =
+            DocumentType::new_bare_collection("app-settings", "app-settings", "app-settings")
+                .unwrap();
+        document.fields = HashSet::from([DocumentField {
+            id: AttributeId::try_new("site-name").unwrap(),
+            field_type: FieldType::Text,
+            unique: false,
+            required: true,
+            constraints: HashSet::new(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        }]);
+        document
+    }
+
+    fn raw(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn test_seed_maps_fields_into_columns() {
+        let id = "0190f3a0-0000-7000-8000-000000000001";
+        let seed = Seed::new(
+            &make_document(),
+            SeedMode::Upsert,
+            vec![raw(json!({ "documentId": id, "site-name": "Luminair" }))],
+        )
+        .unwrap();
+
+        assert_eq!(seed.table_name, "app_settings");
+        let document = &seed.documents[0];
+        assert_eq!(document.document_id.to_string(), id);
+        assert_eq!(document.values["document_id"], json!(id));
+        assert_eq!(document.values["site_name"], json!("Luminair"));
+    }
+
+    #[test]
+    fn test_seed_requires_document_id() {
+        let result = Seed::new(
+            &make_document(),
+            SeedMode::Insert,
+            vec![raw(json!({ "site-name": "Luminair" }))],
+        );
+        assert!(matches!(result, Err(SeedError::InvalidDocumentId { .. })));
+    }
+
+    #[test]
+    fn test_seed_rejects_unknown_field() {
+        let result = Seed::new(
+            &make_document(),
+            SeedMode::Insert,
+            vec![raw(json!({
+                "documentId": "0190f3a0-0000-7000-8000-000000000001",
+                "title": "Luminair"
+            }))],
+        );
+        assert!(matches!(result, Err(SeedError::UnknownField { .. })));
+    }
+}
@@ -1,4 +1,4 @@
-pub(crate) use luminair_common::entities::IntegerSize;
+pub(crate) use luminair_common::entities::{FieldConstraint, IntegerSize, OnDeleteAction};
 
 /// Represents table in a database, used for ddl generation
 #[derive(Debug, Clone)]
@@ -7,6 +7,10 @@ pub struct Table {
     pub columns: Vec<Column>,
     pub foreign_keys: Vec<ForeignKeyConstraint>,
     pub indexes: Vec<Index>,
+    /// previous name of the table, if it should be renamed instead of recreated
+    pub renamed_from: Option<String>,
+    /// column the table is range partitioned by, if any
+    pub partition_by: Option<String>,
 }
 
 /// Represents one column in the database table
@@ -19,6 +23,12 @@ pub struct Column {
     pub unique: bool,
     pub primary_key: bool,
     pub default_value: Option<String>,
+    /// attribute constraints enforced by CHECK constraints of the column
+    pub checks: Vec<FieldConstraint>,
+    /// names of the existing CHECK constraints of the column, as read from the database
+    pub check_names: Vec<String>,
+    /// previous name of the column, if it should be renamed instead of recreated
+    pub renamed_from: Option<String>,
 }
 
 // TODO: contextual column properties depends on column type:
@@ -33,7 +43,9 @@ pub enum ColumnType {
     Integer(IntegerSize),
     Decimal { precision: usize, scale: u32 },
     Date,
+    Timestamp,
     TimestampTZ,
+    Time,
     Boolean,
     JsonB,
 }
@@ -45,6 +57,9 @@ pub struct ForeignKeyConstraint {
     pub column_name: String,
     pub referenced_table_name: String,
     pub referenced_column_name: String,
+    pub on_delete: OnDeleteAction,
+    /// name of an existing constraint, as read from the database
+    pub name: Option<String>,
 }
 
 /// Represents an index in the database table
@@ -54,6 +69,18 @@ pub struct Index {
     pub columns: Vec<String>,
     pub unique: bool,
     pub where_clause: Option<String>,
+    pub method: IndexMethod,
+}
+
+/// Access method of an index
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IndexMethod {
+    /// B-tree, backing equality and range filters and sorts
+    #[default]
+    BTree,
+    /// GIN over the trigrams of text values, backing `LIKE` filters like
+    /// contains, which a B-tree can't serve; needs the `pg_trgm` extension
+    GinTrigram,
 }
 
 impl Table {
@@ -68,8 +95,20 @@ impl Table {
             columns,
             foreign_keys,
             indexes,
+            renamed_from: None,
+            partition_by: None,
         }
     }
+
+    pub fn with_renamed_from<T: Into<String>>(mut self, renamed_from: Option<T>) -> Self {
+        self.renamed_from = renamed_from.map(T::into);
+        self
+    }
+
+    pub fn with_partition_by<T: Into<String>>(mut self, partition_by: Option<T>) -> Self {
+        self.partition_by = partition_by.map(T::into);
+        self
+    }
 }
 
 impl Column {
@@ -90,6 +129,9 @@ impl Column {
             unique,
             primary_key,
             default_value: default_value.map(T::into),
+            checks: Vec::new(),
+            check_names: Vec::new(),
+            renamed_from: None,
         }
     }
 
@@ -106,8 +148,21 @@ impl Column {
             unique: false,
             primary_key: true,
             default_value: None,
+            checks: Vec::new(),
+            check_names: Vec::new(),
+            renamed_from: None,
         }
     }
+
+    pub fn with_renamed_from<T: Into<String>>(mut self, renamed_from: Option<T>) -> Self {
+        self.renamed_from = renamed_from.map(T::into);
+        self
+    }
+
+    pub fn with_checks(mut self, checks: Vec<FieldConstraint>) -> Self {
+        self.checks = checks;
+        self
+    }
 }
 
 impl ForeignKeyConstraint {
@@ -122,8 +177,20 @@ impl ForeignKeyConstraint {
             column_name: column_name.into(),
             referenced_table_name: referenced_table_name.into(),
             referenced_column_name: referenced_column_name.into(),
+            on_delete: OnDeleteAction::Cascade,
+            name: None,
         }
     }
+
+    pub fn with_on_delete(mut self, on_delete: OnDeleteAction) -> Self {
+        self.on_delete = on_delete;
+        self
+    }
+
+    pub fn with_name<T: Into<String>>(mut self, name: T) -> Self {
+        self.name = Some(name.into());
+        self
+    }
 }
 
 impl Index {
@@ -133,6 +200,7 @@ impl Index {
             columns: columns.into_iter().map(T::into).collect(),
             unique,
             where_clause: None,
+            method: IndexMethod::default(),
         }
     }
 
@@ -140,4 +208,9 @@ impl Index {
         self.where_clause = Some(where_clause.into());
         self
     }
+
+    pub fn with_method(mut self, method: IndexMethod) -> Self {
+        self.method = method;
+        self
+    }
 }
@@ -0,0 +1,194 @@
+use luminair_common::persistence::main_table_name;
+use luminair_common::{
+    CREATED_BY_FIELD_NAME, CREATED_FIELD_NAME, DOCUMENT_ID_FIELD_NAME, DocumentType,
+    DocumentTypesRegistry, PUBLISHED_BY_FIELD_NAME, PUBLISHED_FIELD_NAME, REVISION_FIELD_NAME,
+    STATUS_FIELD_NAME, UPDATED_BY_FIELD_NAME, UPDATED_FIELD_NAME, VERSION_FIELD_NAME,
+};
+
+use crate::domain::history::checksum;
+use crate::domain::migration::{CreateViewStep, DropViewStep, MigrationStepItem, column_type_sql};
+use crate::domain::schema::infer_column_type;
+
+/// Prefix of the comment marking the materialized views created by migration,
+/// followed by the checksum of the query they were created with
+pub const VIEW_COMMENT_PREFIX: &str = "luminair:";
+
+/// Materialized view the documents of a read-only type are read from,
+/// in place of its main table
+#[derive(Debug, Clone)]
+pub struct View {
+    pub name: String,
+    /// query of the view, selecting the columns of a main table
+    pub query: String,
+}
+
+/// Materialized view as it was found in the database
+#[derive(Debug, Clone)]
+pub struct ExistingView {
+    pub name: String,
+    /// checksum of the query it was created with, `None` for views
+    /// which weren't created by migration
+    pub checksum: Option<String>,
+}
+
+impl View {
+    pub fn new(document: &DocumentType, sql: &str) -> Self {
+        Self {
+            name: main_table_name(&document.id),
+            query: view_query(document, sql),
+        }
+    }
+
+    /// Identifies the query of the view, so it's recreated when its definition changes
+    pub fn checksum(&self) -> String {
+        checksum(std::slice::from_ref(&self.query))
+    }
+}
+
+/// Views of the read-only types of the documents configuration
+pub fn documents_into_views(documents: &dyn DocumentTypesRegistry) -> Vec<View> {
+    let mut views: Vec<View> = documents
+        .iterate()
+        .filter_map(|document| Some(View::new(document, &document.view()?.sql)))
+        .collect();
+    views.sort_by(|a, b| a.name.cmp(&b.name));
+    views
+}
+
+/// Plans creation of the views which are missing or were created with another query,
+/// and dropping of the views migration created for types which are gone.
+/// Views created by hand are left alone.
+pub fn plan_views(
+    needed: &[View],
+    actual: &[ExistingView],
+    database_schema: &str,
+) -> Vec<MigrationStepItem> {
+    let mut steps = Vec::new();
+
+    for existing in actual {
+        let obsolete =
+            existing.checksum.is_some() && !needed.iter().any(|v| v.name == existing.name);
+        if obsolete {
+            steps.push(MigrationStepItem::DropView(DropViewStep::new(
+                database_schema,
+                &existing.name,
+            )));
+        }
+    }
+
+    for view in needed {
+        let up_to_date = actual.iter().any(|existing| {
+            existing.name == view.name && existing.checksum == Some(view.checksum())
+        });
+        if !up_to_date {
+            steps.push(MigrationStepItem::CreateView(CreateViewStep::new(
+                database_schema,
+                view,
+            )));
+        }
+    }
+
+    steps
+}
+
+/// DDLs refreshing the views without locking out their readers,
+/// which the unique index on the document id allows
+pub fn refresh_views_ddl(database_schema: &str, views: &[View]) -> Vec<String> {
+    views
+        .iter()
+        .map(|view| {
+            format!(
+                "REFRESH MATERIALIZED VIEW CONCURRENTLY \"{}\".\"{}\"",
+                database_schema, view.name
+            )
+        })
+        .collect()
+}
+
+/// Wraps `sql` of the view definition into a query with the columns the
+/// service reads from a main table. `sql` selects the `document_id` and the
+/// fields of the documents; the rest are the same for all of them, the
+/// documents of a view are published as of its last refresh.
+fn view_query(document: &DocumentType, sql: &str) -> String {
+    let mut columns = vec![
+        format!("v.\"{0}\"::UUID AS \"{0}\"", DOCUMENT_ID_FIELD_NAME),
+        format!("'PUBLISHED'::TEXT AS \"{}\"", STATUS_FIELD_NAME),
+        format!("1 AS \"{}\"", VERSION_FIELD_NAME),
+        format!("now() AS \"{}\"", CREATED_FIELD_NAME),
+        format!("now() AS \"{}\"", UPDATED_FIELD_NAME),
+        format!("NULL::TEXT AS \"{}\"", CREATED_BY_FIELD_NAME),
+        format!("NULL::TEXT AS \"{}\"", UPDATED_BY_FIELD_NAME),
+        format!("1 AS \"{}\"", REVISION_FIELD_NAME),
+        format!("now() AS \"{}\"", PUBLISHED_FIELD_NAME),
+        format!("NULL::TEXT AS \"{}\"", PUBLISHED_BY_FIELD_NAME),
+    ];
+
+    // fields are ordered by name, the same documents configuration gives the same query
+    let mut fields: Vec<_> = document.fields.iter().collect();
+    fields.sort_by_key(|field| field.id.normalized());
+    for field in fields {
+        columns.push(format!(
+            "v.\"{0}\"::{1} AS \"{0}\"",
+            field.id.normalized(),
+            column_type_sql(infer_column_type(field))
+        ));
+    }
+
+    format!(
+        "SELECT {} FROM ({}) AS v",
+        columns.join(", "),
+        sql.trim().trim_end_matches(';')
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::migration::MigrationStep;
+
+    #[test]
+    fn test_plan_views_recreates_changed_and_drops_obsolete() {
+        let view = |name: &str, query: &str| View {
+            name: name.to_string(),
+            query: query.to_string(),
+        };
+        let existing = |name: &str, checksum: Option<String>| ExistingView {
+            name: name.to_string(),
+            checksum,
+        };
+        let kept = view("totals", "SELECT 1");
+        let changed = view("ranking", "SELECT 2");
+        let needed = vec![kept.clone(), changed.clone(), view("stats", "SELECT 3")];
+        let actual = vec![
+            existing("totals", Some(kept.checksum())),
+            existing("ranking", Some(view("ranking", "SELECT 1").checksum())),
+            existing("legacy", Some(view("legacy", "SELECT 4").checksum())),
+            existing("by_hand", None),
+        ];
+
+        let steps = plan_views(&needed, &actual, "public");
+        let ctxs: Vec<(&str, Vec<String>)> = steps
+            .into_iter()
+            .map(|step| (step.ctx(), step.ddls()))
+            .collect();
+        assert_eq!(ctxs.len(), 3);
+        assert_eq!(ctxs[0].0, "DROP VIEW");
+        assert_eq!(
+            ctxs[0].1,
+            vec!["DROP MATERIALIZED VIEW IF EXISTS \"public\".\"legacy\""]
+        );
+        assert_eq!(ctxs[1].0, "CREATE VIEW");
+        assert_eq!(
+            ctxs[1].1[1],
+            "CREATE MATERIALIZED VIEW \"public\".\"ranking\" AS SELECT 2"
+        );
+        assert_eq!(
+            ctxs[1].1[3],
+            format!(
+                "COMMENT ON MATERIALIZED VIEW \"public\".\"ranking\" IS 'luminair:{}'",
+                changed.checksum()
+            )
+        );
+        assert_eq!(ctxs[2].0, "CREATE VIEW");
+    }
+}
@@ -1,2 +1,3 @@
 pub mod persistence;
+pub mod seeds;
 pub mod settings;
@@ -1,14 +1,25 @@
-use crate::domain::tables::{ForeignKeyConstraint, Table};
+use crate::domain::tables::{
+    Column, ColumnType, ForeignKeyConstraint, Index, IndexMethod, IntegerSize, OnDeleteAction,
+    Table,
+};
 use anyhow::Context;
-use sqlx::{Executor, PgPool};
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, PgPool, Postgres, Transaction};
+use std::time::Instant;
 
 use crate::application::Persistence;
+use crate::domain::history::{AppliedMigration, MIGRATIONS_TABLE_NAME, checksum};
 use crate::domain::migration::MigrationStep;
+use crate::domain::seeds::{Seed, SeedDocument, SeedMode};
+use crate::domain::views::{ExistingView, VIEW_COMMENT_PREFIX};
+use luminair_common::database::set_local_statement_timeout;
+use luminair_common::{DOCUMENT_ID_FIELD_NAME, UPDATED_FIELD_NAME, VERSION_FIELD_NAME};
 
 #[derive(Clone)]
 pub struct PersistenceAdapter {
     pool: PgPool,
     schema: String,
+    statement_timeout_ms: Option<u64>,
 }
 
 impl PersistenceAdapter {
@@ -16,35 +27,117 @@ impl PersistenceAdapter {
         Self {
             pool,
             schema: schema.into(),
+            statement_timeout_ms: None,
         }
     }
+
+    /// Bound the statements of the migration and seed transactions by
+    /// `timeout_ms`, when it's set. Indexes built concurrently run outside of
+    /// a transaction, and have the timeout of the connections instead.
+    pub fn with_statement_timeout(mut self, timeout_ms: Option<u64>) -> Self {
+        self.statement_timeout_ms = timeout_ms;
+        self
+    }
+
+    /// Create the schema unless it exists, e.g. for a new tenant.
+    pub async fn create_schema(&self) -> Result<(), anyhow::Error> {
+        let ddl = format!("CREATE SCHEMA IF NOT EXISTS \"{}\"", self.schema);
+        self.pool
+            .execute(sqlx::AssertSqlSafe(ddl))
+            .await
+            .with_context(|| format!("failed to create schema '{}'", self.schema))?;
+        Ok(())
+    }
+
+    async fn ensure_history_table(&self) -> Result<(), anyhow::Error> {
+        let ddl = format!(
+            "CREATE TABLE IF NOT EXISTS \"{}\".\"{}\" (
+                id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+                checksum TEXT NOT NULL,
+                context TEXT NOT NULL,
+                ddl TEXT NOT NULL,
+                applied_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+                duration_ms BIGINT NOT NULL
+            )",
+            self.schema, MIGRATIONS_TABLE_NAME
+        );
+        self.pool
+            .execute(sqlx::AssertSqlSafe(ddl))
+            .await
+            .context("failed to create migration history table")?;
+        Ok(())
+    }
 }
 
 impl Persistence for PersistenceAdapter {
     async fn load(&self) -> Result<Vec<Table>, anyhow::Error> {
-        let tables_sql = "SELECT table_name
-            FROM information_schema.tables
+        // partitions are part of their partitioned table, not tables of their own
+        let tables_sql = "SELECT
+            t.table_name::text,
+            (
+                SELECT a.attname::text
+                FROM pg_partitioned_table p
+                    JOIN pg_attribute a ON a.attrelid = p.partrelid AND a.attnum = p.partattrs[0]
+                WHERE p.partrelid = c.oid
+            ) AS partition_by
+            FROM information_schema.tables t
+                JOIN pg_namespace n ON n.nspname = t.table_schema
+                JOIN pg_class c ON c.relnamespace = n.oid AND c.relname = t.table_name
             WHERE
-              table_schema = $1
-              AND table_type = 'BASE TABLE'
-              AND table_name != 'geometry_columns'
-              AND table_name != 'spatial_ref_sys'";
+              t.table_schema = $1
+              AND t.table_type = 'BASE TABLE'
+              AND NOT c.relispartition
+              AND t.table_name != 'geometry_columns'
+              AND t.table_name != 'spatial_ref_sys'
+              AND t.table_name != $2";
 
-        let table_names = sqlx::query_scalar::<_, String>(tables_sql)
+        let table_rows = sqlx::query_as::<_, (String, Option<String>)>(tables_sql)
             .bind(&self.schema)
+            .bind(MIGRATIONS_TABLE_NAME)
             .fetch_all(&self.pool)
             .await?;
 
         let mut tables_map = std::collections::HashMap::new();
-        for name in table_names {
-            tables_map.insert(name.clone(), Table::new(name, vec![], vec![], vec![]));
+        for (name, partition_by) in table_rows {
+            tables_map.insert(
+                name.clone(),
+                Table::new(name, vec![], vec![], vec![]).with_partition_by(partition_by),
+            );
         }
 
+        let columns_sql = "SELECT
+            table_name::text,
+            column_name::text,
+            data_type::text,
+            is_nullable::text,
+            column_default::text,
+            is_identity::text,
+            numeric_precision::int,
+            numeric_scale::int
+        FROM information_schema.columns
+        WHERE table_schema = $1
+        ORDER BY table_name, ordinal_position";
+
+        let column_rows = sqlx::query_as::<_, ColumnRow>(columns_sql)
+            .bind(&self.schema)
+            .fetch_all(&self.pool)
+            .await?;
+
+        for row in column_rows {
+            if let Some(table) = tables_map.get_mut(&row.0) {
+                table.columns.push(column_from_row(row));
+            }
+        }
+
+        // the delete rule is read back as the action of the relation it
+        // was created for, NO ACTION restricts deletes too
         let fkeys_sql = "SELECT
             tc.table_name,
             kcu.column_name,
             ccu.table_name AS referenced_table_name,
-            ccu.column_name AS referenced_column_name
+            ccu.column_name AS referenced_column_name,
+            tc.constraint_name,
+            rc.delete_rule
         FROM
             information_schema.table_constraints AS tc
             JOIN information_schema.key_column_usage AS kcu
@@ -53,40 +146,252 @@ impl Persistence for PersistenceAdapter {
             JOIN information_schema.constraint_column_usage AS ccu
               ON ccu.constraint_name = tc.constraint_name
               AND ccu.table_schema = tc.table_schema
+            JOIN information_schema.referential_constraints AS rc
+              ON rc.constraint_name = tc.constraint_name
+              AND rc.constraint_schema = tc.table_schema
         WHERE tc.constraint_type = 'FOREIGN KEY' AND tc.table_schema = $1";
 
-        let fk_rows = sqlx::query_as::<_, (String, String, String, String)>(fkeys_sql)
+        let fk_rows =
+            sqlx::query_as::<_, (String, String, String, String, String, String)>(fkeys_sql)
+                .bind(&self.schema)
+                .fetch_all(&self.pool)
+                .await?;
+
+        for (table_name, column_name, ref_table, ref_col, name, delete_rule) in fk_rows {
+            if let Some(table) = tables_map.get_mut(&table_name) {
+                let on_delete = match delete_rule.as_str() {
+                    "CASCADE" => OnDeleteAction::Cascade,
+                    "SET NULL" => OnDeleteAction::SetNull,
+                    _ => OnDeleteAction::Restrict,
+                };
+                table.foreign_keys.push(
+                    ForeignKeyConstraint::new(table_name, column_name, ref_table, ref_col)
+                        .with_on_delete(on_delete)
+                        .with_name(name),
+                );
+            }
+        }
+
+        // CHECK constraints of one column, by the column they check
+        let checks_sql = "SELECT
+            t.relname::text AS table_name,
+            a.attname::text AS column_name,
+            con.conname::text AS constraint_name
+        FROM pg_constraint con
+            JOIN pg_class t ON t.oid = con.conrelid
+            JOIN pg_namespace n ON n.oid = t.relnamespace
+            JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = con.conkey[1]
+        WHERE n.nspname = $1 AND con.contype = 'c' AND cardinality(con.conkey) = 1";
+
+        let check_rows = sqlx::query_as::<_, (String, String, String)>(checks_sql)
             .bind(&self.schema)
             .fetch_all(&self.pool)
             .await?;
 
-        for (table_name, column_name, ref_table, ref_col) in fk_rows {
+        for (table_name, column_name, name) in check_rows {
+            if let Some(column) = tables_map
+                .get_mut(&table_name)
+                .and_then(|table| table.columns.iter_mut().find(|c| c.name == column_name))
+            {
+                column.check_names.push(name);
+            }
+        }
+
+        // primary keys are part of the table definition and not diffed as indexes,
+        // keys of expression indexes are read as their expression,
+        // indexes with a trigram operator class as trigram ones,
+        // and partial indexes with their predicate
+        let indexes_sql = "SELECT
+            t.relname::text AS table_name,
+            ix.indisunique AS is_unique,
+            EXISTS (
+                SELECT 1 FROM pg_opclass oc
+                WHERE oc.oid = ANY(ix.indclass::oid[]) AND oc.opcname = 'gin_trgm_ops'
+            ) AS is_trigram,
+            array_agg(
+                COALESCE(a.attname::text, pg_get_indexdef(ix.indexrelid, k.ordinality::int, true))
+                ORDER BY k.ordinality
+            ) AS columns,
+            pg_get_expr(ix.indpred, ix.indrelid) AS predicate
+        FROM pg_index ix
+            JOIN pg_class t ON t.oid = ix.indrelid
+            JOIN pg_namespace n ON n.oid = t.relnamespace
+            CROSS JOIN LATERAL unnest(ix.indkey) WITH ORDINALITY AS k(attnum, ordinality)
+            LEFT JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = k.attnum AND k.attnum <> 0
+        WHERE n.nspname = $1 AND NOT ix.indisprimary
+        GROUP BY t.relname, ix.indexrelid, ix.indisunique, ix.indclass,
+            pg_get_expr(ix.indpred, ix.indrelid)";
+
+        let index_rows =
+            sqlx::query_as::<_, (String, bool, bool, Vec<String>, Option<String>)>(indexes_sql)
+                .bind(&self.schema)
+                .fetch_all(&self.pool)
+                .await?;
+
+        for (table_name, unique, trigram, columns, predicate) in index_rows {
             if let Some(table) = tables_map.get_mut(&table_name) {
-                table.foreign_keys.push(ForeignKeyConstraint::new(
-                    table_name,
-                    column_name,
-                    ref_table,
-                    ref_col,
-                ));
+                let method = if trigram {
+                    IndexMethod::GinTrigram
+                } else {
+                    IndexMethod::BTree
+                };
+                let mut index = Index::new(table_name, columns, unique).with_method(method);
+                index.where_clause = predicate;
+                table.indexes.push(index);
             }
         }
 
         Ok(tables_map.into_values().collect())
     }
 
+    // views carry the checksum of their query in the comment migration gave them
+    async fn load_views(&self) -> Result<Vec<ExistingView>, anyhow::Error> {
+        let views_sql = "SELECT
+            c.relname::text,
+            obj_description(c.oid, 'pg_class') AS comment
+        FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = $1 AND c.relkind = 'm'";
+
+        let rows = sqlx::query_as::<_, (String, Option<String>)>(views_sql)
+            .bind(&self.schema)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(name, comment)| ExistingView {
+                name,
+                checksum: comment
+                    .as_deref()
+                    .and_then(|comment| comment.strip_prefix(VIEW_COMMENT_PREFIX))
+                    .map(String::from),
+            })
+            .collect())
+    }
+
     async fn apply_migration_steps(
         &self,
         steps: Vec<crate::domain::migration::MigrationStepItem>,
     ) -> Result<(), anyhow::Error> {
         use futures::stream::{self, StreamExt};
 
+        self.ensure_history_table().await?;
+
         let mut stream = stream::iter(steps);
         while let Some(step) = stream.next().await {
             let ctx = step.ctx();
-            let ddls = step.clone().ddls();
-            execute_in_transaction(&self.pool, ddls, ctx).await?;
+            let transactional = step.transactional();
+            let ddls = step.ddls();
+            let record = HistoryRecord {
+                schema: &self.schema,
+                checksum: checksum(&ddls),
+                ddl: ddls.join(";\n"),
+            };
+            if transactional {
+                execute_in_transaction(&self.pool, self.statement_timeout_ms, ddls, ctx, record)
+                    .await?;
+            } else {
+                execute_without_transaction(&self.pool, ddls, ctx, record).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn applied_migrations(&self) -> Result<Vec<AppliedMigration>, anyhow::Error> {
+        let exists_sql = "SELECT EXISTS (
+            SELECT 1 FROM information_schema.tables
+            WHERE table_schema = $1 AND table_name = $2
+        )";
+        let exists = sqlx::query_scalar::<_, bool>(exists_sql)
+            .bind(&self.schema)
+            .bind(MIGRATIONS_TABLE_NAME)
+            .fetch_one(&self.pool)
+            .await?;
+        if !exists {
+            return Ok(vec![]);
+        }
+
+        let history_sql = format!(
+            "SELECT checksum, context, ddl, applied_at, duration_ms
+            FROM \"{}\".\"{}\"
+            ORDER BY id",
+            self.schema, MIGRATIONS_TABLE_NAME
+        );
+        let rows = sqlx::query_as::<_, (String, String, String, DateTime<Utc>, i64)>(
+            sqlx::AssertSqlSafe(history_sql),
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(checksum, ctx, ddl, applied_at, duration_ms)| AppliedMigration {
+                    checksum,
+                    ctx,
+                    ddl,
+                    applied_at,
+                    duration_ms,
+                },
+            )
+            .collect())
+    }
+
+    async fn apply_seeds(&self, seeds: Vec<Seed>) -> Result<u64, anyhow::Error> {
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .context("failed to start SEED transaction")?;
+        limit(&mut transaction, self.statement_timeout_ms).await?;
+
+        let mut affected = 0;
+        for seed in seeds {
+            for document in seed.documents {
+                let sql = seed_sql(&self.schema, &seed.table_name, seed.mode, &document);
+                let result = sqlx::query(sqlx::AssertSqlSafe(sql))
+                    .bind(serde_json::Value::Object(document.values))
+                    .execute(&mut *transaction)
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "failed to seed document '{}' into '{}'",
+                            document.document_id, seed.table_name
+                        )
+                    })?;
+                affected += result.rows_affected();
+            }
         }
 
+        transaction
+            .commit()
+            .await
+            .context("failed to commit SEED transaction")?;
+
+        Ok(affected)
+    }
+
+    // a statement per partition, the existing ones are skipped by IF NOT EXISTS
+    async fn create_partitions(&self, ddls: Vec<String>) -> Result<(), anyhow::Error> {
+        for ddl in ddls {
+            self.pool
+                .execute(sqlx::AssertSqlSafe(ddl.clone()))
+                .await
+                .with_context(|| format!("failed to create partition: {}", ddl))?;
+        }
+        Ok(())
+    }
+
+    // a statement per view, each refresh is a transaction of its own
+    async fn refresh_views(&self, ddls: Vec<String>) -> Result<(), anyhow::Error> {
+        for ddl in ddls {
+            self.pool
+                .execute(sqlx::AssertSqlSafe(ddl.clone()))
+                .await
+                .with_context(|| format!("failed to refresh view: {}", ddl))?;
+        }
         Ok(())
     }
 
@@ -95,15 +400,145 @@ impl Persistence for PersistenceAdapter {
     }
 }
 
+/// Insert of one seed document; values are converted into column types by `jsonb_populate_record`
+fn seed_sql(schema: &str, table_name: &str, mode: SeedMode, document: &SeedDocument) -> String {
+    let columns: Vec<String> = document
+        .values
+        .keys()
+        .map(|column| format!("\"{}\"", column))
+        .collect();
+    let columns_sql = columns.join(", ");
+
+    let conflict_sql = match mode {
+        SeedMode::Insert => "DO NOTHING".to_string(),
+        SeedMode::Upsert => {
+            let mut assignments: Vec<String> = document
+                .values
+                .keys()
+                .filter(|column| *column != DOCUMENT_ID_FIELD_NAME)
+                .map(|column| format!("\"{0}\" = EXCLUDED.\"{0}\"", column))
+                .collect();
+            assignments.push(format!(
+                "\"{0}\" = \"{1}\".\"{0}\" + 1",
+                VERSION_FIELD_NAME, table_name
+            ));
+            assignments.push(format!("\"{}\" = now()", UPDATED_FIELD_NAME));
+            format!("DO UPDATE SET {}", assignments.join(", "))
+        }
+    };
+
+    format!(
+        "INSERT INTO \"{schema}\".\"{table_name}\" ({columns_sql})
+        SELECT {columns_sql} FROM jsonb_populate_record(NULL::\"{schema}\".\"{table_name}\", $1)
+        ON CONFLICT (\"{DOCUMENT_ID_FIELD_NAME}\") {conflict_sql}"
+    )
+}
+
+/// table name, column name, data type, is nullable, default, is identity, numeric precision and scale
+type ColumnRow = (
+    String,
+    String,
+    String,
+    String,
+    Option<String>,
+    String,
+    Option<i32>,
+    Option<i32>,
+);
+
+fn column_from_row(row: ColumnRow) -> Column {
+    let (_, name, data_type, is_nullable, default_value, is_identity, precision, scale) = row;
+
+    let column_type = if is_identity == "YES" {
+        ColumnType::Identity(integer_size(&data_type))
+    } else {
+        match data_type.as_str() {
+            "uuid" => ColumnType::Uuid,
+            "character varying" => ColumnType::Varchar,
+            "smallint" | "integer" | "bigint" => ColumnType::Integer(integer_size(&data_type)),
+            "numeric" => ColumnType::Decimal {
+                precision: precision.and_then(|p| usize::try_from(p).ok()).unwrap_or(0),
+                scale: scale.and_then(|s| u32::try_from(s).ok()).unwrap_or(0),
+            },
+            "date" => ColumnType::Date,
+            "timestamp without time zone" => ColumnType::Timestamp,
+            "timestamp with time zone" => ColumnType::TimestampTZ,
+            "time without time zone" => ColumnType::Time,
+            "boolean" => ColumnType::Boolean,
+            "jsonb" => ColumnType::JsonB,
+            _ => ColumnType::Text,
+        }
+    };
+
+    Column {
+        name,
+        column_type,
+        column_length: None,
+        not_null: is_nullable == "NO",
+        unique: false,
+        primary_key: false,
+        default_value,
+        checks: Vec::new(),
+        check_names: Vec::new(),
+        renamed_from: None,
+    }
+}
+
+fn integer_size(data_type: &str) -> IntegerSize {
+    match data_type {
+        "smallint" => IntegerSize::Int16,
+        "bigint" => IntegerSize::Int64,
+        _ => IntegerSize::Int32,
+    }
+}
+
+/// History row written for each applied migration step
+struct HistoryRecord<'a> {
+    schema: &'a str,
+    checksum: String,
+    ddl: String,
+}
+
+impl HistoryRecord<'_> {
+    async fn insert<'e, E>(
+        self,
+        executor: E,
+        ctx: &'static str,
+        started: Instant,
+    ) -> Result<(), anyhow::Error>
+    where
+        E: Executor<'e, Database = sqlx::Postgres>,
+    {
+        let duration_ms = i64::try_from(started.elapsed().as_millis()).unwrap_or(i64::MAX);
+        let sql = format!(
+            "INSERT INTO \"{}\".\"{}\" (checksum, context, ddl, duration_ms) VALUES ($1, $2, $3, $4)",
+            self.schema, MIGRATIONS_TABLE_NAME
+        );
+        sqlx::query(sqlx::AssertSqlSafe(sql))
+            .bind(self.checksum)
+            .bind(ctx)
+            .bind(self.ddl)
+            .bind(duration_ms)
+            .execute(executor)
+            .await
+            .context(format!("failed to record {} in migration history", ctx))?;
+        Ok(())
+    }
+}
+
 async fn execute_in_transaction(
     pool: &PgPool,
+    statement_timeout_ms: Option<u64>,
     queries: Vec<String>,
     ctx: &'static str,
+    record: HistoryRecord<'_>,
 ) -> Result<(), anyhow::Error> {
+    let started = Instant::now();
     let mut transaction = pool
         .begin()
         .await
         .context(format!("failed to start {} transaction", ctx))?;
+    limit(&mut transaction, statement_timeout_ms).await?;
 
     for ddl in queries {
         let query = sqlx::AssertSqlSafe(ddl);
@@ -113,6 +548,8 @@ async fn execute_in_transaction(
             .context(format!("failed to execute {} query", ctx))?;
     }
 
+    record.insert(&mut *transaction, ctx, started).await?;
+
     transaction
         .commit()
         .await
@@ -120,3 +557,36 @@ async fn execute_in_transaction(
 
     Ok(())
 }
+
+async fn execute_without_transaction(
+    pool: &PgPool,
+    queries: Vec<String>,
+    ctx: &'static str,
+    record: HistoryRecord<'_>,
+) -> Result<(), anyhow::Error> {
+    let started = Instant::now();
+    for ddl in queries {
+        let query = sqlx::AssertSqlSafe(ddl);
+        pool.execute(query)
+            .await
+            .context(format!("failed to execute {} query", ctx))?;
+    }
+
+    record.insert(pool, ctx, started).await?;
+
+    Ok(())
+}
+
+/// Bound the statements of `transaction` by `timeout_ms`, when it's set.
+async fn limit(
+    transaction: &mut Transaction<'static, Postgres>,
+    timeout_ms: Option<u64>,
+) -> Result<(), anyhow::Error> {
+    if let Some(timeout_ms) = timeout_ms {
+        sqlx::query(sqlx::AssertSqlSafe(set_local_statement_timeout(timeout_ms)))
+            .execute(&mut **transaction)
+            .await
+            .context("failed to set the statement timeout")?;
+    }
+    Ok(())
+}
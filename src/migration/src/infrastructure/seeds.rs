@@ -0,0 +1,64 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use luminair_common::{DocumentTypeId, DocumentTypesRegistry};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::domain::seeds::{Seed, SeedError, SeedMode};
+
+/// Loads seed files from the directory, one `<document-type-id>.json` file per document type.
+/// Returns no seeds when the directory doesn't exist.
+pub fn load_seeds(
+    seeds_path: &str,
+    documents: &dyn DocumentTypesRegistry,
+) -> Result<Vec<Seed>, anyhow::Error> {
+    let dir_path = Path::new(seeds_path);
+    if !dir_path.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut paths = fs::read_dir(dir_path)
+        .with_context(|| format!("failed to read seeds directory: {}", seeds_path))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("failed to read seeds directory: {}", seeds_path))?;
+    paths.retain(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"));
+    // apply seeds in a stable order
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| load_seed(path, documents))
+        .collect()
+}
+
+fn load_seed(path: &Path, documents: &dyn DocumentTypesRegistry) -> Result<Seed, anyhow::Error> {
+    let path_str = path.to_string_lossy().into_owned();
+
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("failed to read seed file '{}'", path_str))?;
+    let record = serde_json::from_str::<SeedRecord>(&content)
+        .with_context(|| format!("failed to parse JSON seed file '{}'", path_str))?;
+
+    let id = path
+        .file_stem()
+        .and_then(|os_str| os_str.to_str())
+        .with_context(|| format!("failed to get file stem for path '{}'", path_str))?;
+    let document = DocumentTypeId::try_new(id)
+        .ok()
+        .and_then(|id| documents.get(&id))
+        .ok_or_else(|| SeedError::UnknownDocumentType(id.to_string()))?;
+
+    Seed::new(document, record.mode, record.documents)
+        .with_context(|| format!("invalid seed file '{}'", path_str))
+}
+
+// internal struct for Deserializing
+
+#[derive(Debug, Deserialize)]
+struct SeedRecord {
+    #[serde(default)]
+    mode: SeedMode,
+    documents: Vec<Map<String, Value>>,
+}
@@ -1,32 +1,24 @@
 use std::env;
 
-use anyhow::Context;
-use config::{Config, Environment, File};
-use dotenvy::dotenv;
 use luminair_common::database::DatabaseSettings;
+use luminair_common::settings;
 use serde::Deserialize;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Settings {
     pub schema_config_path: String,
+    /// directory with seed files, seeding is skipped when not configured or missing
+    #[serde(default)]
+    pub seeds_path: Option<String>,
     pub database: DatabaseSettings,
 }
 
 impl Settings {
+    /// The settings of the config files and the environment, see
+    /// [`settings::load`].
     pub fn from_env() -> anyhow::Result<Self> {
-        dotenv().ok();
-        let run_mode = load_env("RUN_MODE", "development");
-
-        let s = Config::builder()
-            .add_source(File::with_name("./config/default"))
-            .add_source(File::with_name(&format!("./config/{run_mode}")).required(false))
-            .add_source(Environment::with_prefix("app").separator("_"))
-            .build()?;
-
-        s.try_deserialize().with_context(|| "failed to read config")
+        settings::load(|builder, _| {
+            builder.set_override_option("database.url", env::var("DATABASE_URL").ok())
+        })
     }
 }
-
-fn load_env(key: &str, default_value: &'static str) -> String {
-    env::var(key).unwrap_or_else(|_| default_value.into())
-}
@@ -1,8 +1,143 @@
 //! Library interface for the `migration` crate.
 //!
 //! Exposes the application, domain, and infrastructure modules so that
-//! integration tests in `tests/` can reference them.
+//! integration tests in `tests/` can reference them, and [`migrate_database`]
+//! so that the service can migrate the database on startup and report how
+//! far it is from the documents configuration.
+
+use chrono::Utc;
+use luminair_common::{
+    DocumentTypesRegistry,
+    database::{self, Database, StatementKind},
+};
+
+use crate::application::Migration;
+use crate::domain::migration::documents_into_tables;
+use crate::infrastructure::{persistence::PersistenceAdapter, seeds::load_seeds};
 
 pub mod application;
 pub mod domain;
 pub mod infrastructure;
+
+/// Key of the Postgres advisory lock held while [`migrate_database`] runs, so
+/// that replicas starting together migrate one after the other.
+pub const MIGRATION_LOCK_KEY: i64 = 0x4c75_6d69_6e4d_6967;
+
+/// Migrates the database schema conform documents configuration with default options,
+/// refreshes the views of the read-only types, creates the partitions of the
+/// partitioned types ahead, then applies seeds from `seeds_path` when it's given
+///
+/// With tenants, the schema of every tenant is migrated and seeded, and
+/// created first when it doesn't exist yet.
+///
+/// Waits for the [`MIGRATION_LOCK_KEY`] advisory lock first: the replica
+/// coming after finds the schema migrated and only checks it.
+pub async fn migrate_database(
+    documents: &'static dyn DocumentTypesRegistry,
+    database: &Database,
+    seeds_path: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    // the lock belongs to the session: it is taken and released on the same
+    // connection, which is kept aside meanwhile
+    let mut connection = database.database_pool().acquire().await?;
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *connection)
+        .await?;
+
+    let result = migrate_tenants(documents, database, seeds_path).await;
+
+    let unlocked = sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *connection)
+        .await;
+    if let Err(e) = unlocked {
+        // closing the session releases the lock, instead of returning the
+        // connection to the pool with it
+        drop(connection.detach());
+        return Err(e.into());
+    }
+    result
+}
+
+/// Creates the partitions of the partitioned types for the current interval
+/// and the ones ahead of it, in the schemas of all tenants. Run periodically,
+/// so the partitions of the coming intervals exist before their rows do.
+pub async fn create_partitions(
+    documents: &'static dyn DocumentTypesRegistry,
+    database: &Database,
+) -> Result<(), anyhow::Error> {
+    let today = Utc::now().date_naive();
+    for tenant in database.tenant_scopes() {
+        database::with_tenant(tenant, async {
+            let persistence = PersistenceAdapter::new(
+                database.database_pool().clone(),
+                database.database_schema(),
+            );
+            Migration::new(documents, persistence)
+                .create_partitions(today)
+                .await
+        })
+        .await?;
+    }
+    Ok(())
+}
+
+/// Counts migration steps pending to bring the database schema conform documents
+/// configuration, over the schemas of all tenants
+pub async fn pending_migration_steps(
+    documents: &'static dyn DocumentTypesRegistry,
+    database: &Database,
+) -> Result<usize, anyhow::Error> {
+    let mut pending = 0;
+    for tenant in database.tenant_scopes() {
+        pending += database::with_tenant(tenant, async {
+            let persistence = PersistenceAdapter::new(
+                database.database_pool().clone(),
+                database.database_schema(),
+            );
+            Migration::new(documents, persistence).pending_steps().await
+        })
+        .await?;
+    }
+    Ok(pending)
+}
+
+/// Version of the database schema documents configuration needs, see
+/// [`domain::migration::schema_version`]
+pub fn schema_version(documents: &dyn DocumentTypesRegistry) -> String {
+    domain::migration::schema_version(&documents_into_tables(documents))
+}
+
+/// Migrates and seeds the schemas of all tenants, see [`migrate_database`]
+async fn migrate_tenants(
+    documents: &'static dyn DocumentTypesRegistry,
+    database: &Database,
+    seeds_path: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    for tenant in database.tenant_scopes() {
+        let is_tenant = tenant.is_some();
+        database::with_tenant(tenant, async {
+            let seeds = match seeds_path {
+                Some(seeds_path) => load_seeds(seeds_path, documents)?,
+                None => vec![],
+            };
+
+            let persistence = PersistenceAdapter::new(
+                database.database_pool().clone(),
+                database.database_schema(),
+            )
+            .with_statement_timeout(database.statement_timeout(StatementKind::Migration));
+            if is_tenant {
+                persistence.create_schema().await?;
+            }
+            let migration = Migration::new(documents, persistence);
+            migration.migrate(false).await?;
+            migration.refresh_views().await?;
+            migration.create_partitions(Utc::now().date_naive()).await?;
+            migration.seed(seeds).await
+        })
+        .await?;
+    }
+    Ok(())
+}
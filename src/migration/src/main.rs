@@ -1,7 +1,13 @@
-use luminair_common::{database, load_documents};
+use anyhow::Context;
+use luminair_common::database::{self, StatementKind};
+use luminair_common::load_documents;
 use migration::{
     application::Migration,
-    infrastructure::{persistence::PersistenceAdapter, settings::Settings},
+    domain::migration::{
+        MigrationOptions, RemovedColumnsPolicy, documents_into_tables, render_snapshot,
+    },
+    domain::views::documents_into_views,
+    infrastructure::{persistence::PersistenceAdapter, seeds::load_seeds, settings::Settings},
 };
 
 #[tokio::main]
@@ -10,7 +16,18 @@ async fn main() -> anyhow::Result<()> {
 
     let args: Vec<String> = std::env::args().collect();
     let is_check = args.contains(&"--check".to_string()) || args.contains(&"-c".to_string());
-    let is_dry_run = args.contains(&"--dry-run".to_string()) || args.contains(&"-d".to_string());
+    let output = option_value(&args, "--output", Some("-o"));
+    let snapshot = option_value(&args, "--snapshot", None);
+    // writing the script into a file never applies it
+    let is_dry_run = args.contains(&"--dry-run".to_string())
+        || args.contains(&"-d".to_string())
+        || output.is_some();
+    let is_status = args.contains(&"--status".to_string()) || args.contains(&"-s".to_string());
+    let skip_seeds = args.contains(&"--skip-seeds".to_string());
+    let removed_columns = removed_columns_policy(&args)?;
+    let concurrent_indexes = args.contains(&"--concurrently".to_string());
+    // only the schema of this tenant, rather than those of all tenants
+    let tenant = option_value(&args, "--tenant", None);
 
     if is_check {
         println!("Checking document configuration validity...");
@@ -36,16 +53,93 @@ async fn main() -> anyhow::Result<()> {
     }
 
     let documents = load_documents(&settings.schema_config_path)?;
+    let seeds = match &settings.seeds_path {
+        Some(seeds_path) if !skip_seeds => load_seeds(seeds_path, documents)?,
+        _ => vec![],
+    };
     println!("Configuration loaded");
 
+    // snapshot of the whole schema is rendered without connecting to the database
+    if let Some(snapshot) = snapshot {
+        let tables = documents_into_tables(documents);
+        let views = documents_into_views(documents);
+        let schema = match &tenant {
+            Some(tenant) => database::tenant_schema(&settings.database.schema, tenant),
+            None => settings.database.schema.clone(),
+        };
+        let script = render_snapshot(&schema, &tables, &views)?;
+        std::fs::write(&snapshot, script)
+            .with_context(|| format!("failed to write schema snapshot to '{}'", snapshot))?;
+        println!("Schema snapshot written to {}", snapshot);
+        return Ok(());
+    }
+
     let database = database::connect(&settings.database).await?;
     println!("Connected to DB");
-    let persistence =
-        PersistenceAdapter::new(database.database_pool().clone(), database.database_schema());
 
-    // migrate database schema conform documents configuration
-    let migration = Migration::new(documents, persistence);
-    migration.migrate(is_dry_run).await?;
+    // every tenant has a schema of its own, migrated one after the other
+    let tenants = match &tenant {
+        Some(tenant) => {
+            anyhow::ensure!(database.has_tenant(tenant), "unknown tenant '{}'", tenant);
+            vec![Some(tenant.clone())]
+        }
+        None => database.tenant_scopes(),
+    };
+
+    let mut scripts = Vec::new();
+    for tenant in tenants {
+        if let Some(tenant) = &tenant {
+            println!("Tenant {}", tenant);
+        }
+        let is_tenant = tenant.is_some();
+        let seeds = seeds.clone();
+        database::with_tenant(tenant, async {
+            let persistence = PersistenceAdapter::new(
+                database.database_pool().clone(),
+                database.database_schema(),
+            )
+            .with_statement_timeout(database.statement_timeout(StatementKind::Migration));
+            if is_tenant && !is_dry_run {
+                persistence.create_schema().await?;
+            }
+
+            // migrate database schema conform documents configuration
+            let migration = Migration::new(documents, persistence).with_options(MigrationOptions {
+                removed_columns,
+                concurrent_indexes,
+            });
+
+            if is_status {
+                return migration.status().await;
+            }
+
+            if output.is_some() {
+                scripts.push(migration.dry_run_script().await?);
+            } else {
+                migration.migrate(is_dry_run).await?;
+            }
+
+            if !is_dry_run {
+                migration.refresh_views().await?;
+                migration
+                    .create_partitions(chrono::Utc::now().date_naive())
+                    .await?;
+                migration.seed(seeds).await?;
+            }
+            anyhow::Ok(())
+        })
+        .await?;
+    }
+
+    if is_status {
+        return Ok(());
+    }
+
+    if let Some(output) = output {
+        std::fs::write(&output, scripts.join("\n"))
+            .with_context(|| format!("failed to write migration script to '{}'", output))?;
+        println!("Migration script written to {}", output);
+    }
 
     if is_dry_run {
         println!("Dry-run migration complete (no changes applied)");
@@ -55,3 +149,33 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// `--destructive` or `--destructive=drop` drops orphaned columns,
+/// `--destructive=rename` renames them to `_deleted_<name>`, otherwise they are only reported
+fn removed_columns_policy(args: &[String]) -> anyhow::Result<RemovedColumnsPolicy> {
+    let Some(arg) = args.iter().find(|a| a.starts_with("--destructive")) else {
+        return Ok(RemovedColumnsPolicy::Warn);
+    };
+    match arg.as_str() {
+        "--destructive" | "--destructive=drop" => Ok(RemovedColumnsPolicy::Drop),
+        "--destructive=rename" => Ok(RemovedColumnsPolicy::Rename),
+        _ => anyhow::bail!(
+            "Unknown option '{}', expected --destructive[=rename|drop]",
+            arg
+        ),
+    }
+}
+
+/// value of `--name=<value>`, `--name <value>` or `-n <value>` option
+fn option_value(args: &[String], name: &str, short: Option<&str>) -> Option<String> {
+    let prefix = format!("{}=", name);
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            Some(value.to_string())
+        } else if arg == name || Some(arg.as_str()) == short {
+            args.get(i + 1).cloned()
+        } else {
+            None
+        }
+    })
+}
@@ -9,13 +9,20 @@
 //! ```
 
 use luminair_common::{
-    DocumentTypesRegistry, InMemoryDocumentTypesRegistry, entities::DocumentType,
+    AttributeId, DocumentTypesRegistry, InMemoryDocumentTypesRegistry,
+    entities::{DocumentField, DocumentType, DocumentTypeOptions, FieldType, LocalizationId},
 };
 use migration::{
     application::{Migration, Persistence},
+    domain::{
+        migration::{MigrationOptions, RemovedColumnsPolicy},
+        seeds::{Seed, SeedMode},
+        tables::IndexMethod,
+    },
     infrastructure::persistence::PersistenceAdapter,
 };
 use sqlx::{PgPool, postgres::PgPoolOptions};
+use std::collections::HashSet;
 use testcontainers_modules::{postgres::Postgres, testcontainers::runners::AsyncRunner};
 
 // ---------------------------------------------------------------------------
@@ -71,6 +78,29 @@ fn make_document(name: &str) -> DocumentType {
         .unwrap_or_else(|e| panic!("make_document({name}): {e}"))
 }
 
+/// Builds a [`DocumentType`] with one required text field per given name.
+fn make_document_with_fields(name: &str, fields: &[&str]) -> DocumentType {
+    let mut document = make_document(name);
+    document.fields = fields
+        .iter()
+        .map(|field| DocumentField {
+            id: AttributeId::try_new(*field)
+                .unwrap_or_else(|e| panic!("make_document_with_fields({field}): {e}")),
+            field_type: FieldType::Text,
+            unique: false,
+            required: true,
+            constraints: HashSet::new(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        })
+        .collect();
+    document
+}
+
 // ---------------------------------------------------------------------------
 // Migration execution helper
 // ---------------------------------------------------------------------------
@@ -83,18 +113,42 @@ async fn run_migration(
     pool: &PgPool,
     schema: &str,
     docs: Vec<DocumentType>,
+) -> anyhow::Result<PersistenceAdapter> {
+    run_migration_with_options(pool, schema, docs, MigrationOptions::default()).await
+}
+
+/// Same as [`run_migration`], but with explicit [`MigrationOptions`].
+async fn run_migration_with_options(
+    pool: &PgPool,
+    schema: &str,
+    docs: Vec<DocumentType>,
+    options: MigrationOptions,
 ) -> anyhow::Result<PersistenceAdapter> {
     let registry = InMemoryDocumentTypesRegistry::from_vec(docs);
     let static_registry: &'static dyn DocumentTypesRegistry = Box::leak(Box::new(registry));
 
     let persistence = PersistenceAdapter::new(pool.clone(), schema);
     Migration::new(static_registry, persistence.clone())
+        .with_options(options)
         .migrate(false)
         .await?;
 
     Ok(persistence)
 }
 
+/// Loads column names of the given table through the persistence port.
+async fn column_names(
+    persistence: &PersistenceAdapter,
+    table: &str,
+) -> anyhow::Result<Vec<String>> {
+    let tables = persistence.load().await?;
+    Ok(tables
+        .into_iter()
+        .find(|t| t.name == table)
+        .map(|t| t.columns.into_iter().map(|c| c.name).collect())
+        .unwrap_or_default())
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -216,3 +270,360 @@ async fn test_remove_obsolete_table() -> anyhow::Result<()> {
     drop_schema(&pool, &schema).await?;
     Ok(())
 }
+
+/// Verifies that a column whose attribute was removed from the schema is
+/// renamed to `_deleted_<name>` with `--destructive=rename` and dropped with `--destructive`.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_removed_column_renamed_then_dropped() -> anyhow::Result<()> {
+    let (pool, _container) = start_postgres().await?;
+    let schema = isolated_schema(&pool).await?;
+
+    // --- First pass: 'eta' with 'title' and 'subtitle' ---
+    run_migration(
+        &pool,
+        &schema,
+        vec![make_document_with_fields("eta", &["title", "subtitle"])],
+    )
+    .await?;
+
+    // --- Second pass: 'subtitle' removed, rename policy ---
+    let rename = MigrationOptions {
+        removed_columns: RemovedColumnsPolicy::Rename,
+        ..Default::default()
+    };
+    let persistence_v2 = run_migration_with_options(
+        &pool,
+        &schema,
+        vec![make_document_with_fields("eta", &["title"])],
+        rename,
+    )
+    .await?;
+
+    let columns_v2 = column_names(&persistence_v2, "eta").await?;
+    assert!(
+        columns_v2.contains(&"_deleted_subtitle".to_string()),
+        "column 'subtitle' must be renamed; got: {columns_v2:?}"
+    );
+    assert!(
+        !columns_v2.contains(&"subtitle".to_string()),
+        "column 'subtitle' must not exist anymore; got: {columns_v2:?}"
+    );
+
+    // --- Third pass: drop policy ---
+    let drop = MigrationOptions {
+        removed_columns: RemovedColumnsPolicy::Drop,
+        ..Default::default()
+    };
+    let persistence_v3 = run_migration_with_options(
+        &pool,
+        &schema,
+        vec![make_document_with_fields("eta", &["title"])],
+        drop,
+    )
+    .await?;
+
+    let columns_v3 = column_names(&persistence_v3, "eta").await?;
+    assert!(
+        !columns_v3.contains(&"_deleted_subtitle".to_string()),
+        "column '_deleted_subtitle' must have been dropped; got: {columns_v3:?}"
+    );
+    assert!(
+        columns_v3.contains(&"title".to_string()),
+        "column 'title' must still exist; got: {columns_v3:?}"
+    );
+
+    drop_schema(&pool, &schema).await?;
+    Ok(())
+}
+
+/// Verifies that marking an existing attribute as unique creates a unique
+/// index on the already existing table.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_unique_index_created_for_existing_table() -> anyhow::Result<()> {
+    let (pool, _container) = start_postgres().await?;
+    let schema = isolated_schema(&pool).await?;
+
+    // --- First pass: 'theta' with non unique 'code' ---
+    run_migration(
+        &pool,
+        &schema,
+        vec![make_document_with_fields("theta", &["code"])],
+    )
+    .await?;
+
+    // --- Second pass: 'code' is unique now ---
+    let mut document = make_document_with_fields("theta", &["code"]);
+    document.fields = document
+        .fields
+        .into_iter()
+        .map(|field| DocumentField {
+            unique: true,
+            ..field
+        })
+        .collect();
+    let persistence = run_migration(&pool, &schema, vec![document]).await?;
+
+    let tables = persistence.load().await?;
+    let theta = tables
+        .iter()
+        .find(|t| t.name == "theta")
+        .expect("table 'theta' must exist");
+    assert!(
+        theta
+            .indexes
+            .iter()
+            .any(|index| index.unique && index.columns == vec!["code".to_string()]),
+        "unique index on 'code' must be created; got: {:?}",
+        theta.indexes
+    );
+
+    drop_schema(&pool, &schema).await?;
+    Ok(())
+}
+
+/// Verifies that a unique localized attribute is unique per locale, by a
+/// unique index on the text of every locale instead of the whole JSON value,
+/// and that a rerun finds the indexes in place.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_localized_unique_index_per_locale() -> anyhow::Result<()> {
+    let (pool, _container) = start_postgres().await?;
+    let schema = isolated_schema(&pool).await?;
+
+    let document = || {
+        let mut document = make_document("iota");
+        document.options = Some(DocumentTypeOptions {
+            draft_and_publish: false,
+            localizations: vec![
+                LocalizationId::try_new("en").unwrap(),
+                LocalizationId::try_new("ro").unwrap(),
+            ],
+            workflow: None,
+            cache_control: None,
+            feed: None,
+            operations: None,
+            partitioning: None,
+            view: None,
+            retention: None,
+        });
+        document.fields = HashSet::from([DocumentField {
+            id: AttributeId::try_new("title").unwrap(),
+            field_type: FieldType::LocalizedText,
+            unique: true,
+            required: false,
+            constraints: HashSet::new(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        }]);
+        document
+    };
+    let persistence = run_migration(&pool, &schema, vec![document()]).await?;
+
+    let tables = persistence.load().await?;
+    let iota = tables
+        .iter()
+        .find(|t| t.name == "iota")
+        .expect("table 'iota' must exist");
+    let unique_keys = iota
+        .indexes
+        .iter()
+        .filter(|index| index.unique)
+        .map(|index| index.columns.join(","))
+        .collect::<Vec<_>>();
+    assert!(
+        !unique_keys.contains(&"title".to_string()),
+        "the whole JSON value must not be unique; got: {unique_keys:?}"
+    );
+    for locale in ["en", "ro"] {
+        assert!(
+            unique_keys
+                .iter()
+                .any(|key| key.contains(&format!("'{locale}'"))),
+            "unique index on the '{locale}' text must be created; got: {unique_keys:?}"
+        );
+    }
+
+    let insert = format!(
+        r#"INSERT INTO "{schema}"."iota" (document_id, title) VALUES (gen_random_uuid(), $1::jsonb)"#
+    );
+    sqlx::query(sqlx::AssertSqlSafe(insert.as_str()))
+        .bind(r#"{"en": "Wine", "ro": "Vin"}"#)
+        .execute(&pool)
+        .await?;
+    // the same text in another locale is fine, in the same locale it isn't
+    sqlx::query(sqlx::AssertSqlSafe(insert.as_str()))
+        .bind(r#"{"en": "Vin", "ro": "Vinuri"}"#)
+        .execute(&pool)
+        .await?;
+    assert!(
+        sqlx::query(sqlx::AssertSqlSafe(insert.as_str()))
+            .bind(r#"{"en": "Wines", "ro": "Vin"}"#)
+            .execute(&pool)
+            .await
+            .is_err()
+    );
+
+    // --- Rerun: the expression indexes are recognized ---
+    let applied = persistence.applied_migrations().await?;
+    let persistence = run_migration(&pool, &schema, vec![document()]).await?;
+    assert_eq!(
+        applied.len(),
+        persistence.applied_migrations().await?.len(),
+        "rerun without changes must not apply any step"
+    );
+
+    drop_schema(&pool, &schema).await?;
+    Ok(())
+}
+
+/// Verifies that a filterable and sortable text attribute is indexed by a
+/// B-tree and a trigram GIN, and that a rerun finds the indexes in place.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_filterable_field_indexes() -> anyhow::Result<()> {
+    let (pool, _container) = start_postgres().await?;
+    let schema = isolated_schema(&pool).await?;
+
+    let document = || {
+        let mut document = make_document_with_fields("kappa", &["code"]);
+        document.fields = document
+            .fields
+            .into_iter()
+            .map(|field| DocumentField {
+                filterable: true,
+                sortable: true,
+                personal: false,
+                ..field
+            })
+            .collect();
+        document
+    };
+    let persistence = run_migration(&pool, &schema, vec![document()]).await?;
+
+    let tables = persistence.load().await?;
+    let kappa = tables
+        .iter()
+        .find(|t| t.name == "kappa")
+        .expect("table 'kappa' must exist");
+    for method in [IndexMethod::BTree, IndexMethod::GinTrigram] {
+        assert!(
+            kappa
+                .indexes
+                .iter()
+                .any(|index| index.method == method && index.columns == vec!["code".to_string()]),
+            "{method:?} index on 'code' must be created; got: {:?}",
+            kappa.indexes
+        );
+    }
+
+    // --- Rerun: the indexes are recognized by their method ---
+    let applied = persistence.applied_migrations().await?;
+    let persistence = run_migration(&pool, &schema, vec![document()]).await?;
+    assert_eq!(
+        applied.len(),
+        persistence.applied_migrations().await?.len(),
+        "rerun without changes must not apply any step"
+    );
+
+    drop_schema(&pool, &schema).await?;
+    Ok(())
+}
+
+/// Verifies that applied steps are recorded in the history table, that the
+/// history table itself is not treated as obsolete, and that a rerun without
+/// schema changes applies nothing.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_migration_history_recorded() -> anyhow::Result<()> {
+    let (pool, _container) = start_postgres().await?;
+    let schema = isolated_schema(&pool).await?;
+
+    let persistence = run_migration(&pool, &schema, vec![make_document("iota")]).await?;
+    let applied_v1 = persistence.applied_migrations().await?;
+    assert!(
+        applied_v1.iter().any(|m| m.ctx == "CREATE TABLE"),
+        "CREATE TABLE step must be recorded; got: {applied_v1:?}"
+    );
+
+    let persistence = run_migration(&pool, &schema, vec![make_document("iota")]).await?;
+    let applied_v2 = persistence.applied_migrations().await?;
+    assert_eq!(
+        applied_v1.len(),
+        applied_v2.len(),
+        "rerun without changes must not apply any step"
+    );
+
+    let names: Vec<String> = persistence
+        .load()
+        .await?
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    assert!(
+        !names.contains(&"luminair_migrations".to_string()),
+        "history table must not be part of the loaded schema; got: {names:?}"
+    );
+
+    drop_schema(&pool, &schema).await?;
+    Ok(())
+}
+
+/// Verifies that seeds insert missing documents, keep existing ones in
+/// insert mode and overwrite seeded fields in upsert mode.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_seeds_insert_and_upsert() -> anyhow::Result<()> {
+    let (pool, _container) = start_postgres().await?;
+    let schema = isolated_schema(&pool).await?;
+
+    let document = make_document_with_fields("kappa", &["title"]);
+    let registry = InMemoryDocumentTypesRegistry::from_vec(vec![document]);
+    let static_registry: &'static dyn DocumentTypesRegistry = Box::leak(Box::new(registry));
+    let kappa = static_registry
+        .iterate()
+        .next()
+        .expect("document 'kappa' must be registered");
+
+    let persistence = PersistenceAdapter::new(pool.clone(), schema.as_str());
+    let migration = Migration::new(static_registry, persistence);
+    migration.migrate(false).await?;
+
+    let id = "0190f3a0-0000-7000-8000-000000000001";
+    let seed = |mode, title: &str| -> anyhow::Result<Seed> {
+        let raw = serde_json::json!({ "documentId": id, "title": title });
+        let raw = raw.as_object().cloned().unwrap_or_default();
+        Ok(Seed::new(kappa, mode, vec![raw])?)
+    };
+    let title = || {
+        sqlx::query_scalar::<_, String>(sqlx::AssertSqlSafe(format!(
+            "SELECT title FROM \"{schema}\".\"kappa\""
+        )))
+        .fetch_all(&pool)
+    };
+
+    migration
+        .seed(vec![seed(SeedMode::Insert, "first")?])
+        .await?;
+    assert_eq!(title().await?, vec!["first".to_string()]);
+
+    migration
+        .seed(vec![seed(SeedMode::Insert, "second")?])
+        .await?;
+    assert_eq!(
+        title().await?,
+        vec!["first".to_string()],
+        "insert mode must keep existing document"
+    );
+
+    migration
+        .seed(vec![seed(SeedMode::Upsert, "third")?])
+        .await?;
+    assert_eq!(
+        title().await?,
+        vec!["third".to_string()],
+        "upsert mode must overwrite seeded fields"
+    );
+
+    drop_schema(&pool, &schema).await?;
+    Ok(())
+}
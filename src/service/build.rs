@@ -0,0 +1,6 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // needs `protoc`, e.g. from the `protobuf-compiler` package
+    tonic_prost_build::configure()
+        .compile_protos(&["proto/luminair/documents/v1/documents.proto"], &["proto"])?;
+    Ok(())
+}
@@ -1,7 +1,13 @@
+use crate::domain::actor::ActorContext;
 use crate::domain::document::DocumentInstanceId;
 use crate::domain::document::content::ContentValue;
-use crate::domain::document::lifecycle::UserId;
-use crate::domain::query::DocumentInstanceQuery;
+use crate::domain::document::lifecycle::PublicationSchedule;
+use crate::domain::query::{
+    AggregateQuery, DocumentInstanceQuery, DocumentStatus, PopulateOptions, PopulateStrategy,
+};
+use crate::domain::repository::ArchiveRow;
+use chrono::{DateTime, Utc};
+use luminair_common::entities::{LocalizationId, WorkflowStageId};
 use luminair_common::{AttributeId, DocumentType};
 use std::collections::HashMap;
 
@@ -9,45 +15,134 @@ pub struct FindDocumentsCommand {
     pub document_type: &'static DocumentType,
     pub populate: Option<Vec<AttributeId>>,
     pub populate_filters: Option<HashMap<AttributeId, crate::domain::query::FilterExpression>>,
+    pub populate_options: HashMap<AttributeId, PopulateOptions>,
+    pub populate_strategy: PopulateStrategy,
+    /// relations whose related documents are counted per document
+    pub with_count: Vec<AttributeId>,
     pub query: DocumentInstanceQuery,
 }
 
+/// Export of all documents matching a query, without pagination or
+/// populated relations.
+pub struct StreamDocumentsCommand {
+    pub document_type: &'static DocumentType,
+    pub query: DocumentInstanceQuery,
+}
+
+pub struct AggregateDocumentsCommand {
+    pub document_type: &'static DocumentType,
+    pub query: AggregateQuery,
+}
+
+/// Full-text search of the published documents of `document_types`.
+pub struct SearchDocumentsCommand {
+    pub document_types: Vec<&'static DocumentType>,
+    /// query in the web search syntax
+    pub text: String,
+    /// most documents found
+    pub limit: u64,
+}
+
 pub struct FindByIdCommand {
     pub document_type: &'static DocumentType,
     pub document_instance_id: DocumentInstanceId,
     pub populate: Option<Vec<AttributeId>>,
     pub populate_filters: Option<HashMap<AttributeId, crate::domain::query::FilterExpression>>,
+    pub populate_options: HashMap<AttributeId, PopulateOptions>,
     pub query: DocumentInstanceQuery,
 }
 
+pub struct DocumentExistsCommand {
+    pub document_type: &'static DocumentType,
+    pub document_instance_id: DocumentInstanceId,
+    pub status: DocumentStatus,
+}
+
 pub struct CreateDocumentCommand {
     pub document_type: &'static DocumentType,
     pub fields: HashMap<AttributeId, ContentValue>,
-    pub user_id: Option<UserId>,
+    pub actor: ActorContext,
 }
 
 pub struct UpdateDocumentCommand {
     pub document_type: &'static DocumentType,
     pub document_id: DocumentInstanceId,
     pub fields: HashMap<AttributeId, ContentValue>,
-    pub user_id: Option<UserId>,
+    /// locale of a single-locale edit, other translations of localized fields are kept
+    pub locale: Option<LocalizationId>,
+    /// version the client based its changes on, if it wants them checked
+    pub expected_version: Option<i32>,
+    pub actor: ActorContext,
 }
 
 pub struct DeleteDocumentCommand {
     pub document_type: &'static DocumentType,
     pub document_instance_id: DocumentInstanceId,
+    pub actor: ActorContext,
+}
+
+pub struct FindReferencesCommand {
+    pub document_type: &'static DocumentType,
+    pub document_id: DocumentInstanceId,
 }
 
 pub struct PublishDocumentCommand {
     pub document_type: &'static DocumentType,
     pub document_id: DocumentInstanceId,
-    pub user_id: Option<UserId>,
+    pub expected_version: Option<i32>,
+    /// a future `publish_at` only schedules the publish, `unpublish_at`
+    /// schedules the document to be unpublished again
+    pub schedule: PublicationSchedule,
+    pub actor: ActorContext,
+}
+
+/// Carry out the scheduled publishes and unpublishes of a document type
+/// which are due at `now`.
+pub struct ApplyScheduleCommand {
+    pub document_type: &'static DocumentType,
+    pub now: DateTime<Utc>,
+}
+
+/// Move a document to another stage of the workflow of its type.
+pub struct MoveToStageCommand {
+    pub document_type: &'static DocumentType,
+    pub document_id: DocumentInstanceId,
+    pub stage: WorkflowStageId,
+    pub expected_version: Option<i32>,
+    /// checked against the roles the stage admits
+    pub actor: ActorContext,
+}
+
+pub struct FindRevisionsCommand {
+    pub document_type: &'static DocumentType,
+    pub document_id: DocumentInstanceId,
+}
+
+pub struct RestoreRevisionCommand {
+    pub document_type: &'static DocumentType,
+    pub document_id: DocumentInstanceId,
+    /// version of the document the revision was saved as
+    pub version: i32,
+    pub expected_version: Option<i32>,
+    pub actor: ActorContext,
+}
+
+pub struct CloneLocaleCommand {
+    pub document_type: &'static DocumentType,
+    pub document_id: DocumentInstanceId,
+    /// locale whose localized texts are copied
+    pub source: LocalizationId,
+    /// new locale receiving the copies
+    pub target: LocalizationId,
+    pub actor: ActorContext,
 }
 
 pub struct ModifyRelationsCommand {
     pub document_type: &'static DocumentType,
     pub document_id: DocumentInstanceId,
     pub operations: HashMap<AttributeId, RelationOperation>,
+    pub expected_version: Option<i32>,
+    pub actor: ActorContext,
 }
 
 #[derive(Debug)]
@@ -65,7 +160,7 @@ pub struct CreateDocumentWithRelationsCommand {
     pub document_type: &'static DocumentType,
     pub fields: HashMap<AttributeId, ContentValue>,
     pub relation_operations: HashMap<AttributeId, RelationOperation>,
-    pub user_id: Option<UserId>,
+    pub actor: ActorContext,
 }
 
 pub struct UpdateDocumentWithRelationsCommand {
@@ -73,5 +168,53 @@ pub struct UpdateDocumentWithRelationsCommand {
     pub document_id: DocumentInstanceId,
     pub fields: HashMap<AttributeId, ContentValue>,
     pub relation_operations: HashMap<AttributeId, RelationOperation>,
-    pub user_id: Option<UserId>,
+    pub locale: Option<LocalizationId>,
+    pub expected_version: Option<i32>,
+    pub actor: ActorContext,
+}
+
+/// Restore the rows of a content export into an empty database.
+pub struct ImportArchiveCommand {
+    pub rows: Vec<ArchiveRow>,
+}
+
+/// Delete, or archive, the documents whose type keeps them for fewer days
+/// than they exist, or only count them.
+pub struct ApplyRetentionCommand {
+    /// the time the age of the documents is measured at
+    pub now: DateTime<Utc>,
+    /// count the expired documents rather than remove them
+    pub dry_run: bool,
+    /// most documents removed by one transaction
+    pub batch_size: u64,
+}
+
+/// Gather everything stored of a data subject, for their access request.
+pub struct ExportSubjectCommand {
+    /// identifying value of the subject, e.g. their email address
+    pub value: String,
+}
+
+/// Erase the personal data of a data subject, on their request.
+pub struct EraseSubjectCommand {
+    /// identifying value of the subject, e.g. their email address
+    pub value: String,
+    pub actor: ActorContext,
+}
+
+/// Report how far the translations of the documents of a localized type are,
+/// a page of the documents at a time.
+pub struct TranslationStatusCommand {
+    pub document_type: &'static DocumentType,
+    /// page of the documents, from 1
+    pub page: u16,
+    pub page_size: u16,
+}
+
+/// Count, or remove, the relation links to or from documents which don't exist.
+pub struct CleanOrphanedLinksCommand {
+    /// remove the links rather than count them only
+    pub remove: bool,
+    /// most links removed by one transaction
+    pub batch_size: u64,
 }
@@ -1,5 +1,5 @@
 use crate::domain::document::error::DocumentError;
-use crate::domain::repository::RepositoryError;
+use crate::domain::repository::{InboundReference, RepositoryError};
 
 #[derive(thiserror::Error, Debug)]
 pub enum ServiceError {
@@ -9,18 +9,39 @@ pub enum ServiceError {
     #[error("Document not found")]
     DocumentNotFound,
 
+    #[error("Revision {0} not found")]
+    RevisionNotFound(i32),
+
     #[error("Relation '{0}' not found")]
     RelationNotFound(String),
 
     #[error("Relation '{0}' is not an owning relation")]
     NotOwningRelation(String),
 
+    #[error("Document has no texts in locale '{0}'")]
+    NoLocaleTexts(String),
+
+    #[error("Document type has no workflow")]
+    WorkflowNotFound,
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("Validation error: {0}")]
     Validation(#[from] DocumentError),
 
     #[error("Unique constraint violated: {0}")]
     Conflict(String),
 
+    #[error("Document version mismatch: expected {expected}, found {actual}")]
+    VersionMismatch { expected: i32, actual: i32 },
+
+    #[error("Document was modified concurrently")]
+    ConcurrentModification,
+
+    #[error("Document is linked by relations restricting its delete")]
+    Referenced(Vec<InboundReference>),
+
     #[error(transparent)]
     Internal(#[from] anyhow::Error),
 }
@@ -36,7 +57,10 @@ impl From<RepositoryError> for ServiceError {
                     reason: msg,
                 })
             }
+            RepositoryError::Conflict(_) => Self::ConcurrentModification,
             RepositoryError::UniqueViolation(msg) => Self::Conflict(msg),
+            RepositoryError::Referenced(references) => Self::Referenced(references),
+            cause @ RepositoryError::NotEmpty => Self::Conflict(cause.to_string()),
             RepositoryError::DatabaseError(msg) => Self::Internal(anyhow::anyhow!(msg)),
         }
     }
@@ -1,20 +1,37 @@
 use crate::application::commands::{
-    CreateDocumentCommand, CreateDocumentWithRelationsCommand, DeleteDocumentCommand,
-    FindByIdCommand, FindDocumentsCommand, ModifyRelationsCommand, PublishDocumentCommand,
-    RelationOperation, UpdateDocumentCommand, UpdateDocumentWithRelationsCommand,
+    AggregateDocumentsCommand, ApplyRetentionCommand, ApplyScheduleCommand,
+    CleanOrphanedLinksCommand, CloneLocaleCommand, CreateDocumentCommand,
+    CreateDocumentWithRelationsCommand, DeleteDocumentCommand, DocumentExistsCommand,
+    EraseSubjectCommand, ExportSubjectCommand, FindByIdCommand, FindDocumentsCommand,
+    FindReferencesCommand, FindRevisionsCommand, ImportArchiveCommand, ModifyRelationsCommand,
+    MoveToStageCommand, PublishDocumentCommand, RelationOperation, RestoreRevisionCommand,
+    SearchDocumentsCommand, StreamDocumentsCommand, TranslationStatusCommand,
+    UpdateDocumentCommand, UpdateDocumentWithRelationsCommand,
 };
 use crate::application::error::ServiceError;
 use crate::application::service::DocumentsService;
-use crate::domain::document::content::DocumentContent;
+use crate::domain::actor::ActorContext;
+use crate::domain::document::content::{ContentValue, DocumentContent, DomainValue};
 use crate::domain::document::error::DocumentError;
 use crate::domain::document::{
-    DatabaseRowId, DocumentInstance, DocumentInstanceId, lifecycle::PublicationState,
+    DatabaseRowId, DocumentInstance, DocumentInstanceId,
+    lifecycle::{PublicationSchedule, PublicationState},
 };
-use crate::domain::query::{DocumentInstanceQuery, DocumentStatus};
-use crate::domain::repository::{DocumentsRepository, RelationMap, RelationOps, RepositoryError};
-use chrono::Utc;
-use luminair_common::{AttributeId, DocumentType};
-use std::collections::HashMap;
+use crate::domain::query::{
+    AggregateGroup, DocumentInstanceQuery, DocumentStatus, FilterExpression, PopulateOptions,
+    PopulateStrategy, SearchHit,
+};
+use crate::domain::repository::{
+    ArchiveRow, DocumentsRepository, InboundReference, RelationMap, RelationOps, RepositoryError,
+};
+use crate::domain::subject::{Erasure, SubjectDocument, SubjectReference};
+use crate::domain::translation::TranslationStatus;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use luminair_common::entities::LocalizationId;
+use luminair_common::{AttributeId, DOCUMENT_ID_FIELD_NAME, DocumentType};
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Clone)]
 pub struct DocumentsServiceImpl<R>
@@ -29,6 +46,193 @@ impl<R: DocumentsRepository> DocumentsServiceImpl<R> {
         Self { repository }
     }
 
+    /// Repository the documents are stored by.
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Validate and insert a new document instance with its initial relation links.
+    async fn insert_new(
+        &self,
+        document_type: &DocumentType,
+        fields: HashMap<AttributeId, ContentValue>,
+        relations: HashMap<AttributeId, RelationOps>,
+        actor: &ActorContext,
+    ) -> Result<DocumentInstanceId, ServiceError> {
+        // ContentValue::from_json catches explicit-null on required fields at parse time,
+        // but cannot see fields omitted from the payload altogether — closing that gap is the service's job.
+        for field in &document_type.fields {
+            if field.required && !fields.contains_key(&field.id) {
+                return Err(ServiceError::Validation(
+                    DocumentError::MissingRequiredField(field.id.to_string()),
+                ));
+            }
+        }
+
+        let content = DocumentContent::new(fields);
+        let mut instance = DocumentInstance::new(
+            DatabaseRowId(0), // placeholder — the DB assigns the actual row key
+            DocumentInstanceId::generate(),
+            content,
+            HashMap::new(),
+        );
+        instance.audit.created_by = actor.user_id.clone();
+        instance.audit.updated_by = actor.user_id.clone();
+        let created = self
+            .repository
+            .insert(document_type, &instance, &relations, actor)
+            .await?;
+        Ok(created.document_id)
+    }
+
+    /// Save field changes and relation operations to the draft row of a document.
+    async fn save_draft(
+        &self,
+        document_type: &DocumentType,
+        document_id: DocumentInstanceId,
+        changes: DraftChanges,
+        expected_version: Option<i32>,
+        actor: &ActorContext,
+    ) -> Result<(), ServiceError> {
+        let instance = self.load_draft(document_type, document_id).await?;
+        check_version(expected_version, instance.audit.version)?;
+        self.write_draft(document_type, instance, changes, actor)
+            .await
+    }
+
+    /// Load the draft row of a document.
+    async fn load_draft(
+        &self,
+        document_type: &DocumentType,
+        document_id: DocumentInstanceId,
+    ) -> Result<DocumentInstance, ServiceError> {
+        // Updates are applied to the draft row — the published row is immutable
+        // until the next `publish()` call propagates the draft forward.
+        let query = DocumentInstanceQuery::new().with_status(DocumentStatus::Draft);
+        self.repository
+            .find_by_id(document_type, document_id, &query)
+            .await?
+            .ok_or(ServiceError::DocumentNotFound)
+    }
+
+    /// Apply changes to a loaded draft and write it back, unless it was
+    /// modified since it was loaded.
+    async fn write_draft(
+        &self,
+        document_type: &DocumentType,
+        mut instance: DocumentInstance,
+        changes: DraftChanges,
+        actor: &ActorContext,
+    ) -> Result<(), ServiceError> {
+        let stored_version = instance.audit.version;
+
+        if changes.locale.is_some() {
+            instance.content.update_locale(changes.fields);
+        } else {
+            instance.content.fields.extend(changes.fields);
+        }
+
+        // Bump the version and transition status (e.g. from PUBLISHED to MODIFIED)
+        instance.audit.version += 1;
+        instance.audit.updated_at = Utc::now();
+        instance.audit.updated_by = actor.user_id.clone();
+
+        // Transition publication state to Draft (MODIFIED editorial status) if it's currently Published
+        if let PublicationState::Published { revision, .. } = &instance.content.publication_state {
+            instance.content.publication_state = PublicationState::Draft {
+                revision: *revision,
+            };
+        }
+
+        self.repository
+            .update(
+                document_type,
+                &instance,
+                stored_version,
+                &changes.relations,
+                actor,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Publish a loaded draft and write it back, unless it was modified since
+    /// it was loaded, scheduling its unpublish at `unpublish_at` when given.
+    async fn publish_draft(
+        &self,
+        document_type: &DocumentType,
+        mut instance: DocumentInstance,
+        unpublish_at: Option<DateTime<Utc>>,
+        actor: &ActorContext,
+    ) -> Result<(), ServiceError> {
+        // Publish always operates on the draft row — the state machine lives in
+        // `DocumentInstance::publish`, the repository only persists the result.
+        let stored_version = instance.audit.version;
+        instance.publish(actor.user_id.clone())?;
+        instance.audit.updated_by = actor.user_id.clone();
+
+        self.repository
+            .publish(
+                document_type,
+                &instance,
+                stored_version,
+                unpublish_at,
+                actor,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Carry out the part of the schedule of a document which is due at `now`,
+    /// and keep the rest of it.
+    async fn apply_due(
+        &self,
+        document_type: &DocumentType,
+        document_id: DocumentInstanceId,
+        schedule: PublicationSchedule,
+        now: DateTime<Utc>,
+    ) -> Result<(), ServiceError> {
+        // the scheduler acts for no one in particular
+        let actor = ActorContext::anonymous();
+        let mut remaining = schedule;
+
+        if schedule.publish_at.is_some_and(|at| at <= now) {
+            let instance = self.load_draft(document_type, document_id).await?;
+            match self
+                .publish_draft(document_type, instance, None, &actor)
+                .await
+            {
+                // published by hand meanwhile
+                Ok(()) | Err(ServiceError::Validation(DocumentError::AlreadyPublished)) => {}
+                Err(e) => return Err(e),
+            }
+            remaining.publish_at = None;
+        }
+
+        if schedule.unpublish_at.is_some_and(|at| at <= now) {
+            let mut instance = self.load_draft(document_type, document_id).await?;
+            let stored_version = instance.audit.version;
+            match instance.unpublish() {
+                Ok(()) => {
+                    instance.audit.updated_at = now;
+                    instance.audit.updated_by = None;
+                    self.repository
+                        .unpublish(document_type, &instance, stored_version, &actor)
+                        .await?;
+                }
+                // never published
+                Err(DocumentError::AlreadyDraft) => {}
+                Err(e) => return Err(e.into()),
+            }
+            remaining.unpublish_at = None;
+        }
+
+        self.repository
+            .schedule(document_type, document_id, &remaining)
+            .await?;
+        Ok(())
+    }
+
     /// Batch-load and attach relations to a set of document instances.
     ///
     /// If `populate` is `None` or the instance list is empty the documents are
@@ -38,6 +242,7 @@ impl<R: DocumentsRepository> DocumentsServiceImpl<R> {
         document_type: &DocumentType,
         populate: Option<Vec<AttributeId>>,
         populate_filters: Option<HashMap<AttributeId, crate::domain::query::FilterExpression>>,
+        populate_options: &HashMap<AttributeId, PopulateOptions>,
         status: DocumentStatus,
         instances: Vec<DocumentInstance>,
     ) -> Result<Vec<DocumentInstance>, RepositoryError> {
@@ -54,7 +259,14 @@ impl<R: DocumentsRepository> DocumentsServiceImpl<R> {
 
         let relation_map: RelationMap = self
             .repository
-            .fetch_relations(document_type, &fields, filters, status, &ids)
+            .fetch_relations(
+                document_type,
+                &fields,
+                filters,
+                populate_options,
+                status,
+                &ids,
+            )
             .await?;
 
         let enriched = instances
@@ -76,6 +288,46 @@ impl<R: DocumentsRepository> DocumentsServiceImpl<R> {
 
         Ok(enriched)
     }
+
+    /// Attach the number of related documents of the `fields` relations to a
+    /// set of document instances, counted in one query.
+    ///
+    /// Without fields or instances the documents are returned unchanged.
+    async fn count_related(
+        &self,
+        document_type: &DocumentType,
+        fields: &[AttributeId],
+        status: DocumentStatus,
+        instances: Vec<DocumentInstance>,
+    ) -> Result<Vec<DocumentInstance>, RepositoryError> {
+        if instances.is_empty() || fields.is_empty() {
+            return Ok(instances);
+        }
+
+        let ids: Vec<DocumentInstanceId> = instances.iter().map(|d| d.document_id).collect();
+        let counts = self
+            .repository
+            .count_relations(document_type, fields, status, &ids)
+            .await?;
+
+        Ok(instances
+            .into_iter()
+            .map(|instance| {
+                let per_doc = fields
+                    .iter()
+                    .map(|attr_id| {
+                        let count = counts
+                            .get(attr_id)
+                            .and_then(|by_id| by_id.get(&instance.document_id))
+                            .copied()
+                            .unwrap_or_default();
+                        (attr_id.clone(), count)
+                    })
+                    .collect();
+                instance.with_relation_counts(per_doc)
+            })
+            .collect())
+    }
 }
 
 impl<R: DocumentsRepository> DocumentsService for DocumentsServiceImpl<R> {
@@ -83,6 +335,35 @@ impl<R: DocumentsRepository> DocumentsService for DocumentsServiceImpl<R> {
         &self,
         cmd: FindDocumentsCommand,
     ) -> Result<(Vec<DocumentInstance>, u64), ServiceError> {
+        let lateral = cmd.populate_strategy == PopulateStrategy::Lateral;
+        if let Some(fields) = cmd
+            .populate
+            .as_ref()
+            .filter(|fields| lateral && !fields.is_empty())
+        {
+            let empty_filters = HashMap::new();
+            let filters = cmd.populate_filters.as_ref().unwrap_or(&empty_filters);
+            let (documents, count) = tokio::try_join!(
+                self.repository.find_with_relations(
+                    cmd.document_type,
+                    &cmd.query,
+                    fields,
+                    filters,
+                    &cmd.populate_options,
+                ),
+                self.repository.count(cmd.document_type, &cmd.query),
+            )?;
+            let documents = self
+                .count_related(
+                    cmd.document_type,
+                    &cmd.with_count,
+                    cmd.query.status,
+                    documents,
+                )
+                .await?;
+            return Ok((documents, count));
+        }
+
         let (instances, count) = tokio::try_join!(
             self.repository.find(cmd.document_type, &cmd.query),
             self.repository.count(cmd.document_type, &cmd.query),
@@ -92,11 +373,80 @@ impl<R: DocumentsRepository> DocumentsService for DocumentsServiceImpl<R> {
                 cmd.document_type,
                 cmd.populate,
                 cmd.populate_filters,
+                &cmd.populate_options,
                 cmd.query.status,
                 instances,
             )
             .await?;
-        Ok((enriched, count))
+        let counted = self
+            .count_related(
+                cmd.document_type,
+                &cmd.with_count,
+                cmd.query.status,
+                enriched,
+            )
+            .await?;
+        Ok((counted, count))
+    }
+
+    fn stream(
+        &self,
+        cmd: StreamDocumentsCommand,
+    ) -> BoxStream<'static, Result<DocumentInstance, ServiceError>> {
+        self.repository
+            .stream(cmd.document_type, &cmd.query)
+            .map_err(ServiceError::from)
+            .boxed()
+    }
+
+    async fn aggregate(
+        &self,
+        cmd: AggregateDocumentsCommand,
+    ) -> Result<Vec<AggregateGroup>, ServiceError> {
+        Ok(self
+            .repository
+            .aggregate(cmd.document_type, &cmd.query)
+            .await?)
+    }
+
+    async fn search(
+        &self,
+        cmd: SearchDocumentsCommand,
+    ) -> Result<Vec<(SearchHit, DocumentInstance)>, ServiceError> {
+        let hits = self
+            .repository
+            .search(&cmd.document_types, &cmd.text, cmd.limit)
+            .await?;
+
+        let mut documents = HashMap::new();
+        for document_type in &cmd.document_types {
+            let ids = hits
+                .iter()
+                .filter(|hit| hit.document_type == document_type.id)
+                .map(|hit| DomainValue::Uuid(hit.document_id.0))
+                .collect::<Vec<_>>();
+            if ids.is_empty() {
+                continue;
+            }
+            let query = DocumentInstanceQuery::new()
+                .with_status(DocumentStatus::Published)
+                .with_filter(FilterExpression::In {
+                    field: DOCUMENT_ID_FIELD_NAME.to_string(),
+                    values: ids,
+                });
+            for document in self.repository.find(document_type, &query).await? {
+                documents.insert((document_type.id.clone(), document.document_id), document);
+            }
+        }
+
+        // documents unpublished since the search are left out
+        Ok(hits
+            .into_iter()
+            .filter_map(|hit| {
+                let document = documents.remove(&(hit.document_type.clone(), hit.document_id))?;
+                Some((hit, document))
+            })
+            .collect())
     }
 
     async fn find_by_id(
@@ -116,6 +466,7 @@ impl<R: DocumentsRepository> DocumentsService for DocumentsServiceImpl<R> {
                 cmd.document_type,
                 cmd.populate,
                 cmd.populate_filters,
+                &cmd.populate_options,
                 cmd.query.status,
                 vec![instance],
             )
@@ -124,194 +475,445 @@ impl<R: DocumentsRepository> DocumentsService for DocumentsServiceImpl<R> {
         Ok(enriched.into_iter().next())
     }
 
-    async fn create(&self, cmd: CreateDocumentCommand) -> Result<DocumentInstanceId, ServiceError> {
-        // ContentValue::from_json catches explicit-null on required fields at parse time, 
-        // but cannot see fields omitted from the payload altogether — closing that gap is the service's job.
-        for field in &cmd.document_type.fields {
-            if field.required && !cmd.fields.contains_key(&field.id) {
-                return Err(ServiceError::Validation(
-                    DocumentError::MissingRequiredField(field.id.to_string()),
-                ));
-            }
-        }
+    async fn exists(&self, cmd: DocumentExistsCommand) -> Result<bool, ServiceError> {
+        Ok(self
+            .repository
+            .exists(cmd.document_type, cmd.document_instance_id, cmd.status)
+            .await?)
+    }
 
-        let document_id = DocumentInstanceId::generate();
-        let content = DocumentContent::new(cmd.fields);
-        let instance = DocumentInstance::new(
-            DatabaseRowId(0), // placeholder — the DB assigns the actual row key
-            document_id,
-            content,
-            HashMap::new(),
-        );
-        self.repository.insert(cmd.document_type, &instance).await?;
-        Ok(document_id)
+    async fn create(&self, cmd: CreateDocumentCommand) -> Result<DocumentInstanceId, ServiceError> {
+        self.insert_new(cmd.document_type, cmd.fields, HashMap::new(), &cmd.actor)
+            .await
     }
 
     async fn create_with_relations(
         &self,
         cmd: CreateDocumentWithRelationsCommand,
     ) -> Result<DocumentInstanceId, ServiceError> {
-        let create_cmd = CreateDocumentCommand {
-            document_type: cmd.document_type,
+        // initial relations are written together with the document, so a bad
+        // relation target never leaves a document without its links behind
+        let relations = relation_ops(cmd.document_type, cmd.relation_operations)?;
+        self.insert_new(cmd.document_type, cmd.fields, relations, &cmd.actor)
+            .await
+    }
+
+    async fn update(&self, cmd: UpdateDocumentCommand) -> Result<(), ServiceError> {
+        let changes = DraftChanges {
             fields: cmd.fields,
-            user_id: cmd.user_id.clone(),
+            locale: cmd.locale,
+            relations: HashMap::new(),
         };
-        let created_id = self.create(create_cmd).await?;
-
-        if !cmd.relation_operations.is_empty() {
-            let modify_cmd = ModifyRelationsCommand {
-                document_type: cmd.document_type,
-                document_id: created_id,
-                operations: cmd.relation_operations,
-            };
-            self.modify_relations(modify_cmd).await?;
-        }
+        self.save_draft(
+            cmd.document_type,
+            cmd.document_id,
+            changes,
+            cmd.expected_version,
+            &cmd.actor,
+        )
+        .await
+    }
 
-        Ok(created_id)
+    async fn update_with_relations(
+        &self,
+        cmd: UpdateDocumentWithRelationsCommand,
+    ) -> Result<(), ServiceError> {
+        // fields and relation links are saved by one repository write, so the
+        // document never ends up with only half of the request applied
+        let changes = DraftChanges {
+            fields: cmd.fields,
+            locale: cmd.locale,
+            relations: relation_ops(cmd.document_type, cmd.relation_operations)?,
+        };
+        self.save_draft(
+            cmd.document_type,
+            cmd.document_id,
+            changes,
+            cmd.expected_version,
+            &cmd.actor,
+        )
+        .await
     }
 
-    async fn update(&self, cmd: UpdateDocumentCommand) -> Result<(), ServiceError> {
-        // Updates are applied to the draft row — the published row is immutable
-        // until the next `publish()` call propagates the draft forward.
-        let query = DocumentInstanceQuery::new().with_status(DocumentStatus::Draft);
-        let mut instance = self
-            .repository
-            .find_by_id(cmd.document_type, cmd.document_id, &query)
-            .await?
-            .ok_or(ServiceError::DocumentNotFound)?;
+    async fn delete(&self, cmd: DeleteDocumentCommand) -> Result<(), ServiceError> {
+        self.repository
+            .delete(cmd.document_type, cmd.document_instance_id, &cmd.actor)
+            .await
+            .map_err(ServiceError::from)
+    }
 
-        instance.content.fields.extend(cmd.fields);
-        instance.audit.version += 1;
-        instance.audit.updated_at = Utc::now();
-        instance.audit.updated_by = cmd.user_id;
+    async fn find_references(
+        &self,
+        cmd: FindReferencesCommand,
+    ) -> Result<Vec<InboundReference>, ServiceError> {
+        // an unknown document is reported, rather than having no references
+        self.load_draft(cmd.document_type, cmd.document_id).await?;
+        self.repository
+            .find_references(cmd.document_type, cmd.document_id)
+            .await
+            .map_err(ServiceError::from)
+    }
 
-        // Transition publication state to Draft (MODIFIED editorial status) if it's currently Published
-        if let PublicationState::Published { revision, .. } = &instance.content.publication_state {
-            instance.content.publication_state = PublicationState::Draft {
-                revision: *revision,
-            };
+    async fn publish(&self, cmd: PublishDocumentCommand) -> Result<(), ServiceError> {
+        // TODO: if the document is already published, this will return an AlreadyPublished error.
+        check_schedule(&cmd.schedule)?;
+        let instance = self.load_draft(cmd.document_type, cmd.document_id).await?;
+        check_version(cmd.expected_version, instance.audit.version)?;
+
+        if let Some(publish_at) = cmd.schedule.publish_at.filter(|at| *at > Utc::now()) {
+            // the scheduler publishes the document once it's time
+            if matches!(
+                instance.content.publication_state,
+                PublicationState::Published { .. }
+            ) {
+                return Err(DocumentError::AlreadyPublished.into());
+            }
+            return self
+                .repository
+                .schedule_publish(
+                    cmd.document_type,
+                    cmd.document_id,
+                    publish_at,
+                    cmd.schedule.unpublish_at,
+                )
+                .await
+                .map_err(ServiceError::from);
         }
 
-        self.repository.update(cmd.document_type, &instance).await?;
-        Ok(())
+        // a pending scheduled publish is done with the publish, the unpublish
+        // is left to the scheduler
+        let unpublish_at = cmd.schedule.unpublish_at;
+        self.publish_draft(cmd.document_type, instance, unpublish_at, &cmd.actor)
+            .await
     }
 
-    async fn update_with_relations(
-        &self,
-        cmd: UpdateDocumentWithRelationsCommand,
-    ) -> Result<(), ServiceError> {
-        if !cmd.fields.is_empty() {
-            let update_cmd = UpdateDocumentCommand {
-                document_type: cmd.document_type,
-                document_id: cmd.document_id,
-                fields: cmd.fields,
-                user_id: cmd.user_id.clone(),
-            };
-            self.update(update_cmd).await?;
+    async fn apply_schedule(&self, cmd: ApplyScheduleCommand) -> Result<usize, ServiceError> {
+        let due = self
+            .repository
+            .find_scheduled(cmd.document_type, cmd.now)
+            .await?;
+
+        let mut applied = 0;
+        for (document_id, schedule) in due {
+            match self
+                .apply_due(cmd.document_type, document_id, schedule, cmd.now)
+                .await
+            {
+                Ok(()) => applied += 1,
+                // edited meanwhile: the schedule is kept for the next run
+                Err(ServiceError::ConcurrentModification) => {}
+                // deleted meanwhile
+                Err(ServiceError::DocumentNotFound) => {}
+                Err(e) => return Err(e),
+            }
         }
+        Ok(applied)
+    }
 
-        if !cmd.relation_operations.is_empty() {
-            let modify_cmd = ModifyRelationsCommand {
-                document_type: cmd.document_type,
-                document_id: cmd.document_id,
-                operations: cmd.relation_operations,
-            };
-            self.modify_relations(modify_cmd).await?;
+    async fn move_to_stage(&self, cmd: MoveToStageCommand) -> Result<(), ServiceError> {
+        let workflow = cmd
+            .document_type
+            .workflow()
+            .ok_or(ServiceError::WorkflowNotFound)?;
+        let stage = workflow
+            .stage(cmd.stage.as_ref())
+            .ok_or_else(|| DocumentError::UnknownWorkflowStage(cmd.stage.to_string()))?;
+        if !stage.admits(&cmd.actor.roles) {
+            return Err(ServiceError::Forbidden(format!(
+                "Moving documents to stage '{}' requires one of the roles: {}",
+                stage.id,
+                stage.roles.join(", ")
+            )));
         }
 
+        let mut instance = self.load_draft(cmd.document_type, cmd.document_id).await?;
+        check_version(cmd.expected_version, instance.audit.version)?;
+
+        let stored_version = instance.audit.version;
+        instance.move_to_stage(stage.id.clone())?;
+        instance.audit.updated_at = Utc::now();
+        instance.audit.updated_by = cmd.actor.user_id.clone();
+
+        self.repository
+            .move_to_stage(cmd.document_type, &instance, stored_version, &cmd.actor)
+            .await?;
         Ok(())
     }
 
-    async fn delete(&self, cmd: DeleteDocumentCommand) -> Result<(), ServiceError> {
+    async fn find_revisions(
+        &self,
+        cmd: FindRevisionsCommand,
+    ) -> Result<Vec<DocumentInstance>, ServiceError> {
+        // an unknown document is reported, rather than having no revisions
+        self.load_draft(cmd.document_type, cmd.document_id).await?;
         self.repository
-            .delete(cmd.document_type, cmd.document_instance_id)
+            .find_revisions(cmd.document_type, cmd.document_id)
             .await
             .map_err(ServiceError::from)
     }
 
-    async fn publish(&self, cmd: PublishDocumentCommand) -> Result<(), ServiceError> {
-        // Publish always operates on the draft row — the state machine lives in
-        // `DocumentInstance::publish`, the repository only persists the result.
-        // TODO: if the document is already published, this will return an AlreadyPublished error.
-        let query = DocumentInstanceQuery::new().with_status(DocumentStatus::Draft);
-        let mut instance = self
+    async fn restore_revision(&self, cmd: RestoreRevisionCommand) -> Result<(), ServiceError> {
+        let instance = self.load_draft(cmd.document_type, cmd.document_id).await?;
+        check_version(cmd.expected_version, instance.audit.version)?;
+
+        let revision = self
             .repository
-            .find_by_id(cmd.document_type, cmd.document_id, &query)
+            .find_revision(cmd.document_type, cmd.document_id, cmd.version)
             .await?
-            .ok_or(ServiceError::DocumentNotFound)?;
+            .ok_or(ServiceError::RevisionNotFound(cmd.version))?;
 
-        instance.publish(cmd.user_id.clone())?;
-        instance.audit.updated_at = Utc::now();
-        instance.audit.updated_by = cmd.user_id;
+        // fields made required after the revision was saved may be empty in it
+        for field in &cmd.document_type.fields {
+            if field.required
+                && matches!(
+                    revision.content.fields.get(&field.id),
+                    None | Some(ContentValue::Null)
+                )
+            {
+                return Err(ServiceError::Validation(
+                    DocumentError::MissingRequiredField(field.id.to_string()),
+                ));
+            }
+        }
 
-        self.repository.update(cmd.document_type, &instance).await?;
-        Ok(())
+        // restoring saves a new draft, so it is itself recorded as a revision
+        // and can be undone the same way
+        let changes = DraftChanges {
+            fields: revision.content.fields,
+            locale: None,
+            relations: HashMap::new(),
+        };
+        self.write_draft(cmd.document_type, instance, changes, &cmd.actor)
+            .await
     }
 
-    async fn modify_relations(&self, cmd: ModifyRelationsCommand) -> Result<(), ServiceError> {
-        // Validate every targeted attribute is an owning relation, then convert
-        // the command-layer `RelationOperation` enum into the repository's
-        // `RelationOps` struct in a single pass — all validation happens before
-        // any DB call so a bad payload never causes a partial write.
-        let mut ops: HashMap<AttributeId, RelationOps> =
-            HashMap::with_capacity(cmd.operations.len());
-        for (attr_id, operation) in cmd.operations {
-            let rel_meta = cmd
-                .document_type
-                .relations
-                .get(&attr_id)
-                .ok_or_else(|| ServiceError::RelationNotFound(attr_id.to_string()))?;
-            if !rel_meta.relation_type.is_owning() {
-                return Err(ServiceError::NotOwningRelation(attr_id.to_string()));
-            }
-            let rel_ops = match operation {
-                RelationOperation::ConnectDisconnect {
-                    connect,
-                    disconnect,
-                } => RelationOps {
-                    connect,
-                    disconnect,
-                },
-                // Full-replacement semantics land in Phase 5 (queries/relations.rs):
-                // the diff against the existing set needs DB access to compute.
-                RelationOperation::Set(_) => {
-                    return Err(ServiceError::Internal(anyhow::anyhow!(
-                        "`set` relation operation is not yet supported"
-                    )));
-                }
+    async fn clone_locale(&self, cmd: CloneLocaleCommand) -> Result<(), ServiceError> {
+        let instance = self.load_draft(cmd.document_type, cmd.document_id).await?;
+
+        let source: &str = cmd.source.as_ref();
+        let target: &str = cmd.target.as_ref();
+        let mut fields = HashMap::new();
+        for (attr_id, value) in &instance.content.fields {
+            let ContentValue::LocalizedText(texts) = value else {
+                continue;
             };
-            ops.insert(attr_id, rel_ops);
+            if texts.contains_key(target) {
+                return Err(ServiceError::Conflict(format!(
+                    "Locale '{}' already exists",
+                    target
+                )));
+            }
+            if let Some(text) = texts.get(source) {
+                let copy = HashMap::from([(target.to_owned(), text.clone())]);
+                fields.insert(attr_id.clone(), ContentValue::LocalizedText(copy));
+            }
+        }
+        // without texts to copy, the write would only bump the version
+        if fields.is_empty() {
+            return Err(ServiceError::NoLocaleTexts(source.to_owned()));
         }
+
+        // saving through the draft marks a published document as modified
+        let changes = DraftChanges {
+            fields,
+            locale: Some(cmd.target),
+            relations: HashMap::new(),
+        };
+        self.write_draft(cmd.document_type, instance, changes, &cmd.actor)
+            .await
+    }
+
+    async fn modify_relations(&self, cmd: ModifyRelationsCommand) -> Result<(), ServiceError> {
+        let changes = DraftChanges {
+            fields: HashMap::new(),
+            locale: None,
+            relations: relation_ops(cmd.document_type, cmd.operations)?,
+        };
+        self.save_draft(
+            cmd.document_type,
+            cmd.document_id,
+            changes,
+            cmd.expected_version,
+            &cmd.actor,
+        )
+        .await
+    }
+
+    fn export_archive(&self) -> BoxStream<'static, Result<ArchiveRow, ServiceError>> {
+        self.repository
+            .export_archive()
+            .map_err(ServiceError::from)
+            .boxed()
+    }
+
+    async fn import_archive(
+        &self,
+        cmd: ImportArchiveCommand,
+    ) -> Result<BTreeMap<String, u64>, ServiceError> {
         self.repository
-            .apply_relation_ops(cmd.document_type, cmd.document_id, &ops)
+            .import_archive(cmd.rows)
             .await
-            .map_err(ServiceError::from)?;
+            .map_err(ServiceError::from)
+    }
 
-        // Fetch draft/working copy of the document
-        let query = DocumentInstanceQuery::new().with_status(DocumentStatus::Draft);
-        let mut instance = self
-            .repository
-            .find_by_id(cmd.document_type, cmd.document_id, &query)
+    async fn clean_orphaned_links(
+        &self,
+        cmd: CleanOrphanedLinksCommand,
+    ) -> Result<BTreeMap<String, u64>, ServiceError> {
+        self.repository
+            .clean_orphaned_links(cmd.remove, cmd.batch_size)
             .await
-            .map_err(ServiceError::from)?
-            .ok_or(ServiceError::DocumentNotFound)?;
+            .map_err(ServiceError::from)
+    }
 
-        // Bump the version and transition status (e.g. from PUBLISHED to MODIFIED)
-        instance.audit.version += 1;
-        instance.audit.updated_at = Utc::now();
+    async fn apply_retention(
+        &self,
+        cmd: ApplyRetentionCommand,
+    ) -> Result<BTreeMap<String, u64>, ServiceError> {
+        self.repository
+            .apply_retention(cmd.now, cmd.dry_run, cmd.batch_size)
+            .await
+            .map_err(ServiceError::from)
+    }
 
-        // Transition publication state to Draft (MODIFIED editorial status) if it's currently Published
-        if let PublicationState::Published { revision, .. } = &instance.content.publication_state {
-            instance.content.publication_state = PublicationState::Draft {
-                revision: *revision,
-            };
+    async fn export_subject(
+        &self,
+        cmd: ExportSubjectCommand,
+    ) -> Result<Vec<SubjectDocument>, ServiceError> {
+        let published = DocumentInstanceQuery::new().with_status(DocumentStatus::Published);
+        let mut documents = Vec::new();
+        for subject in self.repository.find_subject_documents(&cmd.value).await? {
+            let document_type = subject.document_type;
+            for id in subject.document_ids {
+                let draft = self.load_draft(document_type, id).await?;
+                let published = if document_type.has_draft_and_publish() {
+                    self.repository
+                        .find_by_id(document_type, id, &published)
+                        .await?
+                } else {
+                    None
+                };
+                let revisions = self.repository.find_revisions(document_type, id).await?;
+
+                let mut references = Vec::new();
+                for reference in self.repository.find_references(document_type, id).await? {
+                    let mut linking = Vec::new();
+                    for linking_id in reference.document_ids {
+                        // a document deleted since is left out
+                        match self.load_draft(reference.document_type, linking_id).await {
+                            Ok(document) => linking.push(document),
+                            Err(ServiceError::DocumentNotFound) => {}
+                            Err(e) => return Err(e),
+                        }
+                    }
+                    references.push(SubjectReference {
+                        document_type: reference.document_type,
+                        relation: reference.relation,
+                        documents: linking,
+                    });
+                }
+
+                documents.push(SubjectDocument {
+                    document_type,
+                    draft,
+                    published,
+                    revisions,
+                    references,
+                });
+            }
         }
+        Ok(documents)
+    }
 
+    async fn erase_subject(&self, cmd: EraseSubjectCommand) -> Result<Erasure, ServiceError> {
         self.repository
-            .update(cmd.document_type, &instance)
+            .erase_subject(&cmd.value, &cmd.actor)
             .await
-            .map_err(ServiceError::from)?;
+            .map_err(ServiceError::from)
+    }
 
-        Ok(())
+    async fn translation_status(
+        &self,
+        cmd: TranslationStatusCommand,
+    ) -> Result<TranslationStatus, ServiceError> {
+        let document_type = cmd.document_type;
+        if !document_type.has_localization() || document_type.view().is_some() {
+            return Ok(TranslationStatus {
+                documents: Vec::new(),
+                counts: Vec::new(),
+            });
+        }
+        let offset = u64::from(cmd.page.max(1) - 1) * u64::from(cmd.page_size);
+        self.repository
+            .translation_status(document_type, offset, u64::from(cmd.page_size))
+            .await
+            .map_err(ServiceError::from)
+    }
+}
+
+/// Changes written by one save of a draft.
+struct DraftChanges {
+    fields: HashMap<AttributeId, ContentValue>,
+    /// set for single-locale edits, which keep the other translations
+    locale: Option<LocalizationId>,
+    relations: HashMap<AttributeId, RelationOps>,
+}
+
+/// Validate every targeted attribute is an owning relation, then convert
+/// the command-layer `RelationOperation` enum into the repository's
+/// `RelationOps` struct in a single pass — all validation happens before
+/// any DB call so a bad payload never causes a partial write.
+fn relation_ops(
+    document_type: &DocumentType,
+    operations: HashMap<AttributeId, RelationOperation>,
+) -> Result<HashMap<AttributeId, RelationOps>, ServiceError> {
+    let mut ops: HashMap<AttributeId, RelationOps> = HashMap::with_capacity(operations.len());
+    for (attr_id, operation) in operations {
+        let rel_meta = document_type
+            .relations
+            .get(&attr_id)
+            .ok_or_else(|| ServiceError::RelationNotFound(attr_id.to_string()))?;
+        if !rel_meta.relation_type.is_owning() {
+            return Err(ServiceError::NotOwningRelation(attr_id.to_string()));
+        }
+        let rel_ops = match operation {
+            RelationOperation::ConnectDisconnect {
+                connect,
+                disconnect,
+            } => RelationOps {
+                set: None,
+                connect,
+                disconnect,
+            },
+            RelationOperation::Set(targets) => RelationOps {
+                set: Some(targets),
+                ..Default::default()
+            },
+        };
+        ops.insert(attr_id, rel_ops);
+    }
+    Ok(ops)
+}
+
+/// Reject a schedule unpublishing the document before it's published.
+fn check_schedule(schedule: &PublicationSchedule) -> Result<(), ServiceError> {
+    match (schedule.publish_at, schedule.unpublish_at) {
+        (Some(publish_at), Some(unpublish_at)) if unpublish_at <= publish_at => {
+            Err(ServiceError::Validation(DocumentError::InvalidFieldValue {
+                field: "unpublishAt".to_string(),
+                reason: "must be later than publishAt".to_string(),
+            }))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Reject a write based on another version than the stored one.
+fn check_version(expected: Option<i32>, actual: i32) -> Result<(), ServiceError> {
+    match expected {
+        Some(expected) if expected != actual => {
+            Err(ServiceError::VersionMismatch { expected, actual })
+        }
+        _ => Ok(()),
     }
 }
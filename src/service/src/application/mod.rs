@@ -4,6 +4,7 @@ pub mod implementation;
 pub mod service;
 
 use crate::application::service::DocumentsService;
+use crate::domain::repository::DocumentsRepository;
 use luminair_common::DocumentTypesRegistry;
 
 /// The global application state shared between all HTTP request handlers.
@@ -13,12 +14,23 @@ use luminair_common::DocumentTypesRegistry;
 /// it references [`DocumentsService`], which is an application-layer contract.
 pub trait AppState: Clone + Send + Sync + 'static {
     type D: DocumentsService;
+    /// Repository the documents are stored by, under the documents service.
+    type R: DocumentsRepository;
 
     fn document_types(&self) -> &'static dyn DocumentTypesRegistry;
 
     fn documents_service(&self) -> &Self::D;
 
+    /// Direct access to the stored documents, for extensions needing what the
+    /// documents service doesn't offer; writes through it skip the checks of
+    /// the service.
+    fn documents_repository(&self) -> &Self::R;
+
     fn pagination_settings(&self) -> PaginationSettings;
+
+    fn query_limits(&self) -> QueryLimits;
+
+    fn response_format(&self) -> ResponseFormat;
 }
 
 #[derive(Debug, Clone, Copy, serde::Deserialize)]
@@ -35,3 +47,38 @@ impl Default for PaginationSettings {
         }
     }
 }
+
+/// Guards against queries costly enough to harm the database, exceeding them
+/// is answered with `422 Unprocessable Entity`.
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(default)]
+pub struct QueryLimits {
+    /// most filter conditions of a query, those on related documents included
+    pub max_filters: usize,
+    /// most relations a filter nests through, 2 for `filters[category][parent][slug]`
+    pub max_filter_depth: usize,
+    /// most relations populated by a query
+    pub max_populate: usize,
+}
+
+impl Default for QueryLimits {
+    fn default() -> Self {
+        Self {
+            max_filters: 20,
+            max_filter_depth: 3,
+            max_populate: 10,
+        }
+    }
+}
+
+/// Envelope of the documents of list and detail responses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseFormat {
+    /// `{data: [{id, documentId, ...fields}], meta: {page, pageSize, total}}`
+    #[default]
+    Native,
+    /// `{data: [{id, attributes: {...}}], meta: {pagination: {...}}}`, as
+    /// Strapi v4 answers, for clients written against Strapi
+    Strapi,
+}
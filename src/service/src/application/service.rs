@@ -1,10 +1,21 @@
 use crate::application::commands::{
-    CreateDocumentCommand, CreateDocumentWithRelationsCommand, DeleteDocumentCommand,
-    FindByIdCommand, FindDocumentsCommand, ModifyRelationsCommand, PublishDocumentCommand,
-    UpdateDocumentCommand, UpdateDocumentWithRelationsCommand,
+    AggregateDocumentsCommand, ApplyRetentionCommand, ApplyScheduleCommand,
+    CleanOrphanedLinksCommand, CloneLocaleCommand, CreateDocumentCommand,
+    CreateDocumentWithRelationsCommand, DeleteDocumentCommand, DocumentExistsCommand,
+    EraseSubjectCommand, ExportSubjectCommand, FindByIdCommand, FindDocumentsCommand,
+    FindReferencesCommand, FindRevisionsCommand, ImportArchiveCommand, ModifyRelationsCommand,
+    MoveToStageCommand, PublishDocumentCommand, RestoreRevisionCommand, SearchDocumentsCommand,
+    StreamDocumentsCommand, TranslationStatusCommand, UpdateDocumentCommand,
+    UpdateDocumentWithRelationsCommand,
 };
 use crate::application::error::ServiceError;
 use crate::domain::document::{DocumentInstance, DocumentInstanceId};
+use crate::domain::query::{AggregateGroup, SearchHit};
+use crate::domain::repository::{ArchiveRow, InboundReference};
+use crate::domain::subject::{Erasure, SubjectDocument};
+use crate::domain::translation::TranslationStatus;
+use futures::stream::BoxStream;
+use std::collections::BTreeMap;
 
 pub trait DocumentsService: Send + Sync + 'static {
     /// Returns (documents, total_count). total_count is used for pagination metadata.
@@ -13,11 +24,37 @@ pub trait DocumentsService: Send + Sync + 'static {
         cmd: FindDocumentsCommand,
     ) -> impl Future<Output = Result<(Vec<DocumentInstance>, u64), ServiceError>> + Send;
 
+    /// Stream all matching documents without collecting them into memory.
+    fn stream(
+        &self,
+        cmd: StreamDocumentsCommand,
+    ) -> BoxStream<'static, Result<DocumentInstance, ServiceError>>;
+
+    /// Returns the metrics of the matching documents per group.
+    fn aggregate(
+        &self,
+        cmd: AggregateDocumentsCommand,
+    ) -> impl Future<Output = Result<Vec<AggregateGroup>, ServiceError>> + Send;
+
+    /// Returns the published documents matching a full-text search across
+    /// document types, the most relevant first, with their hit.
+    fn search(
+        &self,
+        cmd: SearchDocumentsCommand,
+    ) -> impl Future<Output = Result<Vec<(SearchHit, DocumentInstance)>, ServiceError>> + Send;
+
     fn find_by_id(
         &self,
         cmd: FindByIdCommand,
     ) -> impl Future<Output = Result<Option<DocumentInstance>, ServiceError>> + Send;
 
+    /// Returns whether the document exists in the status of the command,
+    /// without loading it.
+    fn exists(
+        &self,
+        cmd: DocumentExistsCommand,
+    ) -> impl Future<Output = Result<bool, ServiceError>> + Send;
+
     fn create(
         &self,
         cmd: CreateDocumentCommand,
@@ -38,18 +75,123 @@ pub trait DocumentsService: Send + Sync + 'static {
         cmd: UpdateDocumentWithRelationsCommand,
     ) -> impl Future<Output = Result<(), ServiceError>> + Send;
 
+    /// Delete a document, failing with [`ServiceError::Referenced`] while
+    /// documents link to it by relations restricting its delete.
     fn delete(
         &self,
         cmd: DeleteDocumentCommand,
     ) -> impl Future<Output = Result<(), ServiceError>> + Send;
 
+    /// Returns the documents linking to a document per relation, to check
+    /// what deleting it would do.
+    fn find_references(
+        &self,
+        cmd: FindReferencesCommand,
+    ) -> impl Future<Output = Result<Vec<InboundReference>, ServiceError>> + Send;
+
+    /// Publish the draft of a document, or schedule it to be published later.
     fn publish(
         &self,
         cmd: PublishDocumentCommand,
     ) -> impl Future<Output = Result<(), ServiceError>> + Send;
 
+    /// Publish and unpublish the documents whose scheduled time has come,
+    /// returning how many documents were changed.
+    ///
+    /// A document which can't be changed now, e.g. because it is being
+    /// edited concurrently, keeps its schedule for the next run.
+    fn apply_schedule(
+        &self,
+        cmd: ApplyScheduleCommand,
+    ) -> impl Future<Output = Result<usize, ServiceError>> + Send;
+
+    /// Move the draft of a document to another stage of its editorial workflow.
+    ///
+    /// Fails with [`ServiceError::Forbidden`] unless the user has one of the
+    /// roles the stage admits.
+    fn move_to_stage(
+        &self,
+        cmd: MoveToStageCommand,
+    ) -> impl Future<Output = Result<(), ServiceError>> + Send;
+
+    /// Returns the revisions of a document, the most recent first.
+    fn find_revisions(
+        &self,
+        cmd: FindRevisionsCommand,
+    ) -> impl Future<Output = Result<Vec<DocumentInstance>, ServiceError>> + Send;
+
+    /// Save the field values of a revision as the draft of the document.
+    ///
+    /// Relation links are not part of revisions and are kept as they are.
+    fn restore_revision(
+        &self,
+        cmd: RestoreRevisionCommand,
+    ) -> impl Future<Output = Result<(), ServiceError>> + Send;
+
+    /// Start a new translation of the localized fields from an existing locale.
+    ///
+    /// Fails with [`ServiceError::Conflict`] if the document already has texts
+    /// in the target locale, and with [`ServiceError::NoLocaleTexts`] if it
+    /// has none in the source locale.
+    fn clone_locale(
+        &self,
+        cmd: CloneLocaleCommand,
+    ) -> impl Future<Output = Result<(), ServiceError>> + Send;
+
     fn modify_relations(
         &self,
         cmd: ModifyRelationsCommand,
     ) -> impl Future<Output = Result<(), ServiceError>> + Send;
+
+    /// Stream the stored rows of all content, for a backup or a copy of the
+    /// content to another environment.
+    fn export_archive(&self) -> BoxStream<'static, Result<ArchiveRow, ServiceError>>;
+
+    /// Restore the rows of an export into an empty database, returning how
+    /// many rows were restored per table.
+    ///
+    /// Fails with [`ServiceError::Conflict`] if any content is stored already.
+    fn import_archive(
+        &self,
+        cmd: ImportArchiveCommand,
+    ) -> impl Future<Output = Result<BTreeMap<String, u64>, ServiceError>> + Send;
+
+    /// Count the relation links to or from documents which don't exist, left
+    /// by edits of the database, per relation table; removing them in batches
+    /// when the command says so.
+    fn clean_orphaned_links(
+        &self,
+        cmd: CleanOrphanedLinksCommand,
+    ) -> impl Future<Output = Result<BTreeMap<String, u64>, ServiceError>> + Send;
+
+    /// Count the documents expired by the retention of their type, per
+    /// document type; deleting or archiving them in batches unless the
+    /// command is a dry run.
+    fn apply_retention(
+        &self,
+        cmd: ApplyRetentionCommand,
+    ) -> impl Future<Output = Result<BTreeMap<String, u64>, ServiceError>> + Send;
+
+    /// Return the documents holding the personal data of a data subject, by
+    /// the order of their type, with their published copies, revisions and
+    /// the documents linking to them.
+    fn export_subject(
+        &self,
+        cmd: ExportSubjectCommand,
+    ) -> impl Future<Output = Result<Vec<SubjectDocument>, ServiceError>> + Send;
+
+    /// Clear the personal fields of the documents of a data subject, in one
+    /// transaction, returning the audit record of the erasure.
+    fn erase_subject(
+        &self,
+        cmd: EraseSubjectCommand,
+    ) -> impl Future<Output = Result<Erasure, ServiceError>> + Send;
+
+    /// Return a page of the translations of the drafts of a localized type,
+    /// by the order of their ids, with the counts of all of them; none for a
+    /// type without locales or a read-only one.
+    fn translation_status(
+        &self,
+        cmd: TranslationStatusCommand,
+    ) -> impl Future<Output = Result<TranslationStatus, ServiceError>> + Send;
 }
@@ -0,0 +1,32 @@
+use std::net::IpAddr;
+
+use crate::domain::document::lifecycle::UserId;
+
+/// Who a request acts for: taken once from the request and passed along with
+/// its commands to the repository, the lifecycle hooks and the content change
+/// events.
+///
+/// The service doesn't authenticate users itself, the gateway in front of it
+/// does; a request it tells nothing about is anonymous.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ActorContext {
+    pub user_id: Option<UserId>,
+    /// id of the token the user was authenticated with, e.g. an API token
+    pub token_id: Option<String>,
+    pub roles: Vec<String>,
+    /// address of the client
+    pub ip: Option<IpAddr>,
+}
+
+impl ActorContext {
+    /// No one in particular, as the background workers and the requests
+    /// of unidentified clients.
+    pub fn anonymous() -> Self {
+        Self::default()
+    }
+
+    /// Whether the actor has one of `roles`.
+    pub fn has_any_role(&self, roles: &[String]) -> bool {
+        self.roles.iter().any(|role| roles.contains(role))
+    }
+}
@@ -2,13 +2,13 @@ use std::collections::HashMap;
 
 use crate::domain::document::error::DocumentError;
 use crate::domain::document::lifecycle::PublicationState;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use luminair_common::AttributeId;
 use luminair_common::entities::{DocumentField, FieldConstraint, FieldType};
 use nutype::nutype;
 use regex::Regex;
 use rust_decimal::Decimal;
-use rust_decimal::prelude::FromPrimitive;
 
 /// The actual data payload of a document.
 #[derive(Debug, Clone)]
@@ -26,6 +26,22 @@ impl DocumentContent {
             publication_state: PublicationState::Draft { revision: 0 },
         }
     }
+
+    /// Write the field values of a single-locale edit.
+    ///
+    /// Localized texts are merged into the stored translations, so locales
+    /// missing from `fields` are kept; all other values replace the stored ones.
+    pub fn update_locale(&mut self, fields: HashMap<AttributeId, ContentValue>) {
+        for (attribute_id, value) in fields {
+            if let (ContentValue::LocalizedText(texts), Some(ContentValue::LocalizedText(stored))) =
+                (&value, self.fields.get_mut(&attribute_id))
+            {
+                stored.extend(texts.clone());
+                continue;
+            }
+            self.fields.insert(attribute_id, value);
+        }
+    }
 }
 
 /// A single content value stored for a document field.
@@ -48,13 +64,16 @@ pub enum DomainValue {
     Boolean(bool),
     Date(chrono::NaiveDate),
     DateTime(DateTime<Utc>),
+    /// Wall-clock date and time, without a time zone.
+    LocalDateTime(NaiveDateTime),
+    Time(NaiveTime),
     /// Validated email address (lower-cased, trimmed).
     Email(Email),
     /// Validated URL (trimmed).
     Url(Url),
     Uuid(uuid::Uuid),
-    /// Flat JSON object stored as a string map.
-    Json(HashMap<String, String>),
+    /// JSON object, its values keeping their JSON types.
+    Json(serde_json::Map<String, serde_json::Value>),
 }
 
 // ── String → Domain codec ────────────────────────────────────────────────────
@@ -77,6 +96,16 @@ impl DomainValue {
     /// - The field type is `LocalizedText` or `Json` (compound types that cannot
     ///   be compared with a scalar filter operator).
     pub fn parse(raw: &str, field_type: FieldType) -> Result<Self, DocumentError> {
+        Self::parse_in(raw, field_type, None)
+    }
+
+    /// Parse a raw string like [`DomainValue::parse`], reading a `DateTime`
+    /// without an offset as a wall-clock time of `time_zone`.
+    pub fn parse_in(
+        raw: &str,
+        field_type: FieldType,
+        time_zone: Option<Tz>,
+    ) -> Result<Self, DocumentError> {
         let filter_err = |reason: String| DocumentError::InvalidFieldValue {
             field: "<filter>".into(),
             reason,
@@ -124,12 +153,36 @@ impl DomainValue {
             }
 
             FieldType::DateTime => {
-                let dt = chrono::DateTime::parse_from_rfc3339(raw).map_err(|_| {
+                if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(raw) {
+                    return Ok(DomainValue::DateTime(dt.with_timezone(&Utc)));
+                }
+                let (zone, local) = time_zone.zip(parse_local_date_time(raw)).ok_or_else(|| {
                     filter_err(format!("'{}' is not a valid RFC 3339 datetime", raw))
                 })?;
+                // a time skipped by a daylight saving change doesn't exist
+                let dt = zone.from_local_datetime(&local).earliest().ok_or_else(|| {
+                    filter_err(format!("'{}' doesn't exist in time zone {}", raw, zone))
+                })?;
                 Ok(DomainValue::DateTime(dt.with_timezone(&Utc)))
             }
 
+            FieldType::LocalDateTime => {
+                let dt = parse_local_date_time(raw).ok_or_else(|| {
+                    filter_err(format!(
+                        "'{}' is not a valid date and time (expected YYYY-MM-DDTHH:MM:SS)",
+                        raw
+                    ))
+                })?;
+                Ok(DomainValue::LocalDateTime(dt))
+            }
+
+            FieldType::Time => {
+                let t = parse_time(raw).ok_or_else(|| {
+                    filter_err(format!("'{}' is not a valid time (expected HH:MM:SS)", raw))
+                })?;
+                Ok(DomainValue::Time(t))
+            }
+
             // Compound types cannot be compared with a scalar filter operator.
             // Reject explicitly rather than silently falling back to text comparison.
             FieldType::LocalizedText | FieldType::Json => Err(filter_err(format!(
@@ -140,6 +193,20 @@ impl DomainValue {
     }
 }
 
+/// Format of a date and time without time zone, the RFC 3339 date and time
+/// without the offset.
+const LOCAL_DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+/// Format of a time of day, the RFC 3339 partial time.
+const TIME_FORMAT: &str = "%H:%M:%S%.f";
+
+fn parse_local_date_time(raw: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(raw, LOCAL_DATE_TIME_FORMAT).ok()
+}
+
+fn parse_time(raw: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(raw, TIME_FORMAT).ok()
+}
+
 // ── JSON codec ──────────────────────────────────────────────────────────────
 //
 // All four field-level conversions (JSON→Domain, Domain→JSON, DB→Domain,
@@ -178,9 +245,9 @@ impl ContentValue {
     /// `Uid` maps to `DomainValue::Text`, not `Uuid`, because a Uid is a
     /// human-readable slug, not a UUID. See `FieldType::Uuid` for the UUID case.
     ///
-    /// `Decimal` accepts both a JSON number and a quoted decimal string.
-    /// The string form is preferred because it preserves full precision without
-    /// rounding through `f64`.
+    /// `Decimal` accepts both a JSON number and a quoted decimal string, is
+    /// rounded to the scale of the field and rejected when it has more digits
+    /// than its precision.
     pub fn from_json(
         value: &serde_json::Value,
         field: &DocumentField,
@@ -275,20 +342,24 @@ impl ContentValue {
                 Ok(ContentValue::Scalar(DomainValue::Integer(n)))
             }
 
-            FieldType::Decimal { scale, .. } => {
-                // Prefer the string form (full precision); fall back to JSON number (f64).
-                let decimal: Decimal = if let Some(s) = value.as_str() {
-                    s.parse::<Decimal>()
-                        .map_err(|_| errf(format!("'{}' cannot be parsed as a decimal", s)))?
-                } else {
-                    let f = value
-                        .as_f64()
-                        .ok_or_else(|| err("expected a number or a quoted decimal string"))?;
-                    Decimal::from_f64(f)
-                        .ok_or_else(|| err("cannot represent value as a decimal"))?
+            FieldType::Decimal { precision, scale } => {
+                // A JSON number is read from its text, not through `f64`.
+                let text = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    serde_json::Value::Number(n) => n.to_string(),
+                    _ => return Err(err("expected a number or a quoted decimal string")),
                 };
-                let mut d = decimal;
+                let mut d = text
+                    .parse::<Decimal>()
+                    .or_else(|_| Decimal::from_scientific(&text))
+                    .map_err(|_| errf(format!("'{}' cannot be parsed as a decimal", text)))?;
                 d.rescale(scale);
+                if d.mantissa().unsigned_abs().to_string().len() > precision {
+                    return Err(errf(format!(
+                        "'{}' has more than {} digits",
+                        text, precision
+                    )));
+                }
                 Ok(ContentValue::Scalar(DomainValue::Decimal(d)))
             }
 
@@ -317,23 +388,34 @@ impl ContentValue {
                 Ok(ContentValue::Scalar(DomainValue::DateTime(dt)))
             }
 
+            FieldType::LocalDateTime => {
+                let s = value
+                    .as_str()
+                    .ok_or_else(|| err("expected a date and time string (YYYY-MM-DDTHH:MM:SS)"))?;
+                let dt = parse_local_date_time(s).ok_or_else(|| {
+                    errf(format!(
+                        "'{}' is not a valid date and time without time zone",
+                        s
+                    ))
+                })?;
+                Ok(ContentValue::Scalar(DomainValue::LocalDateTime(dt)))
+            }
+
+            FieldType::Time => {
+                let s = value
+                    .as_str()
+                    .ok_or_else(|| err("expected a time string (HH:MM:SS)"))?;
+                let t = parse_time(s).ok_or_else(|| {
+                    errf(format!("'{}' is not a valid time (expected HH:MM:SS)", s))
+                })?;
+                Ok(ContentValue::Scalar(DomainValue::Time(t)))
+            }
+
             FieldType::Json => {
                 let obj = value
                     .as_object()
                     .ok_or_else(|| err("expected a JSON object"))?;
-                // JSON fields are stored as flat string maps.
-                // Non-string values are serialised to their JSON representation.
-                let map = obj
-                    .iter()
-                    .map(|(k, v)| {
-                        let s = v
-                            .as_str()
-                            .map(str::to_owned)
-                            .unwrap_or_else(|| v.to_string());
-                        (k.clone(), s)
-                    })
-                    .collect();
-                Ok(ContentValue::Scalar(DomainValue::Json(map)))
+                Ok(ContentValue::Scalar(DomainValue::Json(obj.clone())))
             }
         }
     }
@@ -416,23 +498,28 @@ impl From<&DomainValue> for serde_json::Value {
             DomainValue::Text(s) => serde_json::Value::String(s.clone()),
             DomainValue::Integer(n) => serde_json::Value::Number((*n).into()),
             DomainValue::Decimal(d) => {
-                // Try to emit as a JSON number using the canonical decimal string.
-                // Falls back to a JSON string if the number cannot be represented
-                // (extremely rare for practical CMS content values).
-                serde_json::from_str(&d.to_string())
-                    .unwrap_or_else(|_| serde_json::Value::String(d.to_string()))
+                // Emitted as a JSON number when an `f64` holds it exactly,
+                // as a string otherwise so that no digit is lost.
+                match serde_json::from_str::<serde_json::Value>(&d.to_string()) {
+                    Ok(serde_json::Value::Number(n))
+                        if n.to_string().parse::<Decimal>().ok() == Some(*d) =>
+                    {
+                        serde_json::Value::Number(n)
+                    }
+                    _ => serde_json::Value::String(d.to_string()),
+                }
             }
             DomainValue::Boolean(b) => serde_json::Value::Bool(*b),
             DomainValue::Date(d) => serde_json::Value::String(d.to_string()),
             DomainValue::DateTime(dt) => serde_json::Value::String(dt.to_rfc3339()),
+            DomainValue::LocalDateTime(dt) => {
+                serde_json::Value::String(dt.format(LOCAL_DATE_TIME_FORMAT).to_string())
+            }
+            DomainValue::Time(t) => serde_json::Value::String(t.format(TIME_FORMAT).to_string()),
             DomainValue::Email(e) => serde_json::Value::String(e.as_ref().to_owned()),
             DomainValue::Url(u) => serde_json::Value::String(u.as_ref().to_owned()),
             DomainValue::Uuid(u) => serde_json::Value::String(u.to_string()),
-            DomainValue::Json(map) => serde_json::Value::Object(
-                map.iter()
-                    .map(|(k, v)| (k.clone(), serde_json::Value::String(v.clone())))
-                    .collect(),
-            ),
+            DomainValue::Json(map) => serde_json::Value::Object(map.clone()),
         }
     }
 }
@@ -523,6 +610,73 @@ mod tests {
         assert!(err.is_err());
     }
 
+    #[test]
+    fn test_decimal_keeps_its_digits() {
+        let field = DocumentField {
+            id: AttributeId::try_new("price").unwrap(),
+            field_type: FieldType::Decimal {
+                precision: 20,
+                scale: 2,
+            },
+            unique: false,
+            required: false,
+            constraints: Default::default(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        };
+        let decimal = |value: serde_json::Value| match ContentValue::from_json(&value, &field) {
+            Ok(ContentValue::Scalar(DomainValue::Decimal(d))) => d,
+            other => panic!("unexpected {:?}", other),
+        };
+
+        assert_eq!(decimal(serde_json::json!(0.1)).to_string(), "0.10");
+        assert_eq!(decimal(serde_json::json!(1e2)).to_string(), "100.00");
+        let big = decimal(serde_json::json!("123456789012345678.91"));
+        assert_eq!(big.to_string(), "123456789012345678.91");
+        // an f64 can't hold it, it is written back as a string
+        assert_eq!(
+            serde_json::Value::from(&DomainValue::Decimal(big)),
+            serde_json::json!("123456789012345678.91")
+        );
+        assert_eq!(
+            serde_json::Value::from(&DomainValue::Decimal(decimal(serde_json::json!(12.5)))),
+            serde_json::json!(12.5)
+        );
+
+        let too_long = ContentValue::from_json(&serde_json::json!("1234567890123456789.1"), &field);
+        assert!(too_long.is_err());
+    }
+
+    #[test]
+    fn test_json_keeps_value_types() {
+        let field = DocumentField {
+            id: AttributeId::try_new("meta").unwrap(),
+            field_type: FieldType::Json,
+            unique: false,
+            required: false,
+            constraints: Default::default(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        };
+        let json = serde_json::json!({
+            "color": "red",
+            "rank": 5,
+            "featured": true,
+            "tags": ["a", "b"],
+        });
+
+        let value = ContentValue::from_json(&json, &field).unwrap();
+        assert_eq!(serde_json::Value::from(&value), json);
+    }
+
     #[test]
     fn test_domain_value_parse_boolean() {
         let val = DomainValue::parse("true", FieldType::Boolean).unwrap();
@@ -563,6 +717,48 @@ mod tests {
         assert!(err.is_err());
     }
 
+    #[test]
+    fn test_domain_value_parse_datetime_in_time_zone() {
+        let zone = Some(chrono_tz::Europe::Bucharest);
+        let val = DomainValue::parse_in("2026-07-06T12:00:00", FieldType::DateTime, zone).unwrap();
+        assert_eq!(
+            val,
+            DomainValue::DateTime(Utc.with_ymd_and_hms(2026, 7, 6, 9, 0, 0).unwrap())
+        );
+
+        // an offset wins over the time zone
+        let val = DomainValue::parse_in("2026-07-06T12:00:00Z", FieldType::DateTime, zone).unwrap();
+        assert_eq!(
+            val,
+            DomainValue::DateTime(Utc.with_ymd_and_hms(2026, 7, 6, 12, 0, 0).unwrap())
+        );
+
+        // skipped by the switch to summer time
+        let err = DomainValue::parse_in("2026-03-29T03:30:00", FieldType::DateTime, zone);
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_local_date_time_and_time_round_trip() {
+        let val = DomainValue::parse("2026-07-06T12:34:56", FieldType::LocalDateTime).unwrap();
+        assert_eq!(
+            serde_json::Value::from(&val),
+            serde_json::json!("2026-07-06T12:34:56")
+        );
+        assert!(DomainValue::parse("2026-07-06T12:34:56Z", FieldType::LocalDateTime).is_err());
+
+        let val = DomainValue::parse("08:30:00.250", FieldType::Time).unwrap();
+        assert_eq!(
+            val,
+            DomainValue::Time(NaiveTime::from_hms_milli_opt(8, 30, 0, 250).unwrap())
+        );
+        assert_eq!(
+            serde_json::Value::from(&val),
+            serde_json::json!("08:30:00.250")
+        );
+        assert!(DomainValue::parse("25:00:00", FieldType::Time).is_err());
+    }
+
     #[test]
     fn test_domain_value_parse_compound_rejected() {
         let err = DomainValue::parse("foo", FieldType::LocalizedText);
@@ -571,4 +767,47 @@ mod tests {
         let err = DomainValue::parse("foo", FieldType::Json);
         assert!(err.is_err());
     }
+
+    #[test]
+    fn test_update_locale_keeps_other_translations() {
+        let name = AttributeId::try_new("name").unwrap();
+        let uid = AttributeId::try_new("uid").unwrap();
+        let mut content = DocumentContent::new(HashMap::from([
+            (
+                name.clone(),
+                ContentValue::LocalizedText(HashMap::from([
+                    ("en".to_owned(), "Shop".to_owned()),
+                    ("ro".to_owned(), "Magazin".to_owned()),
+                ])),
+            ),
+            (
+                uid.clone(),
+                ContentValue::Scalar(DomainValue::Text("shop".to_owned())),
+            ),
+        ]));
+
+        content.update_locale(HashMap::from([
+            (
+                name.clone(),
+                ContentValue::LocalizedText(HashMap::from([(
+                    "ro".to_owned(),
+                    "Prăvălie".to_owned(),
+                )])),
+            ),
+            (
+                uid.clone(),
+                ContentValue::Scalar(DomainValue::Text("store".to_owned())),
+            ),
+        ]));
+
+        let Some(ContentValue::LocalizedText(texts)) = content.fields.get(&name) else {
+            panic!("expected localized text");
+        };
+        assert_eq!(texts.get("en").unwrap(), "Shop");
+        assert_eq!(texts.get("ro").unwrap(), "Prăvălie");
+        assert!(matches!(
+            content.fields.get(&uid),
+            Some(ContentValue::Scalar(DomainValue::Text(text))) if text == "store"
+        ));
+    }
 }
@@ -25,4 +25,12 @@ pub enum DocumentError {
     /// Attempted to unpublish a document that is already in the `Draft` state.
     #[error("Document is already a draft")]
     AlreadyDraft,
+
+    /// The requested stage is not one of the workflow stages of the document type.
+    #[error("Unknown workflow stage: '{0}'")]
+    UnknownWorkflowStage(String),
+
+    /// Attempted to move a document to the workflow stage it is in already.
+    #[error("Document is already in workflow stage '{0}'")]
+    AlreadyInStage(String),
 }
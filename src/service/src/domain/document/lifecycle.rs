@@ -64,6 +64,14 @@ pub enum PublicationState {
     },
 }
 
+/// Publication changes of a document planned for later, carried out by the
+/// scheduler once their time has come.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PublicationSchedule {
+    pub publish_at: Option<DateTime<Utc>>,
+    pub unpublish_at: Option<DateTime<Utc>>,
+}
+
 /// System metadata: WHO did WHAT WHEN
 /// This is infrastructure/audit concern, not domain logic
 #[derive(Debug, Clone)]
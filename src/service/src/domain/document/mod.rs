@@ -11,6 +11,7 @@ use crate::domain::document::{
 };
 use chrono::Utc;
 use luminair_common::AttributeId;
+use luminair_common::entities::WorkflowStageId;
 use serde::{Deserialize, Serialize};
 use sqlx::types::uuid::Uuid;
 
@@ -31,8 +32,21 @@ pub struct DocumentInstance {
     /// Document relations
     pub relations: HashMap<AttributeId, Vec<DocumentRelation>>,
 
+    /// Number of related documents per relation, for the relations counted
+    /// rather than populated
+    pub relation_counts: HashMap<AttributeId, u64>,
+
     /// System/infrastructure metadata about this instance
     pub audit: AuditTrail,
+
+    /// Editorial workflow stage, `None` if the document type has no workflow
+    /// or the row doesn't keep it, like published snapshots
+    pub workflow_stage: Option<WorkflowStageId>,
+
+    /// Whether the draft was saved after its published version, i.e. the
+    /// published version is stale; only read for drafts of draft-and-publish
+    /// types, `false` otherwise
+    pub modified: bool,
 }
 
 impl DocumentInstance {
@@ -46,6 +60,16 @@ impl DocumentInstance {
             .collect();
         Self { relations, ..self }
     }
+
+    pub(crate) fn with_relation_counts(
+        self,
+        relation_counts: HashMap<AttributeId, u64>,
+    ) -> DocumentInstance {
+        Self {
+            relation_counts,
+            ..self
+        }
+    }
 }
 
 /// Wrapper to prevent ID confusion
@@ -59,7 +83,11 @@ impl From<i64> for DatabaseRowId {
 }
 
 /// Wrapper to prevent ID confusion
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+///
+/// Deserializes from the string form of its UUID, e.g. as an `{id}` path
+/// parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
 pub struct DocumentInstanceId(pub Uuid);
 
 impl From<Uuid> for DocumentInstanceId {
@@ -86,6 +114,14 @@ impl TryFrom<&String> for DocumentInstanceId {
     }
 }
 
+impl TryFrom<String> for DocumentInstanceId {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_str())
+    }
+}
+
 impl From<DocumentInstanceId> for String {
     fn from(value: DocumentInstanceId) -> Self {
         value.0.to_string()
@@ -111,6 +147,7 @@ impl DocumentInstance {
             document_id,
             content,
             relations,
+            relation_counts: HashMap::new(),
             audit: AuditTrail {
                 created_at: Utc::now(),
                 created_by: None,
@@ -118,6 +155,8 @@ impl DocumentInstance {
                 updated_by: None,
                 version: 1,
             },
+            workflow_stage: None,
+            modified: false,
         }
     }
 
@@ -175,15 +214,58 @@ impl DocumentInstance {
         // Increment version (every save increments version).
         self.audit.version += 1;
 
+        // The draft is saved at the time it's published, so the published
+        // version isn't older than it.
+        let now = Utc::now();
+        self.audit.updated_at = now;
+        self.modified = false;
+
         // Revision counter is independent: increment from the draft's last-known
         // published revision, not from version.
         self.content.publication_state = PublicationState::Published {
             revision: current_revision + 1,
-            published_at: Utc::now(),
+            published_at: now,
             published_by: user_id,
         };
         Ok(())
     }
+
+    /// Move the document to another stage of its editorial workflow.
+    ///
+    /// Like any other save, this increments `AuditTrail.version`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DocumentError::AlreadyInStage`] if the document is in `stage` already.
+    pub fn move_to_stage(&mut self, stage: WorkflowStageId) -> Result<(), DocumentError> {
+        if self.workflow_stage.as_ref() == Some(&stage) {
+            return Err(DocumentError::AlreadyInStage(stage.to_string()));
+        }
+        self.workflow_stage = Some(stage);
+        self.audit.version += 1;
+        Ok(())
+    }
+
+    /// Withdraw the published content of the document.
+    ///
+    /// A `Published` document becomes a `Draft` again, keeping the revision of
+    /// its last publication. A draft modified since its last publication
+    /// stays as it is, only its published content is withdrawn.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DocumentError::AlreadyDraft`] if the document was never published.
+    pub fn unpublish(&mut self) -> Result<(), DocumentError> {
+        let revision = match &self.content.publication_state {
+            PublicationState::Published { revision, .. } => *revision,
+            PublicationState::Draft { revision } if *revision > 0 => *revision,
+            PublicationState::Draft { .. } => return Err(DocumentError::AlreadyDraft),
+        };
+
+        self.audit.version += 1;
+        self.content.publication_state = PublicationState::Draft { revision };
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,177 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use luminair_common::{DocumentType, WORKFLOW_STAGE_FIELD_NAME};
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+/// Change of a document, announced on the event bus once it is committed.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentChangeEvent {
+    /// Identifies the event; it is the same on every delivery attempt, so
+    /// brokers and consumers can drop the duplicates.
+    pub event_id: i64,
+    #[serde(rename = "type")]
+    pub document_type: String,
+    #[serde(rename = "id")]
+    pub document_id: String,
+    pub action: ContentAction,
+    pub actor: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    /// Changed fields, with their values before and after the change.
+    pub diff: Value,
+    /// Tenant whose document changed, when the service has tenants; the
+    /// event ids are unique per tenant only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+}
+
+/// Write which changed a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentAction {
+    Create,
+    Update,
+    Publish,
+    Unpublish,
+    Delete,
+}
+
+impl ContentAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentAction::Create => "create",
+            ContentAction::Update => "update",
+            ContentAction::Publish => "publish",
+            ContentAction::Unpublish => "unpublish",
+            ContentAction::Delete => "delete",
+        }
+    }
+}
+
+impl Display for ContentAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ContentAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create" => Ok(ContentAction::Create),
+            "update" => Ok(ContentAction::Update),
+            "publish" => Ok(ContentAction::Publish),
+            "unpublish" => Ok(ContentAction::Unpublish),
+            "delete" => Ok(ContentAction::Delete),
+            other => Err(format!("Unknown content action: {other}")),
+        }
+    }
+}
+
+/// Fields of `document_type` whose stored value differs between the rows
+/// `before` and `after` of a write, as `{"field": {"old": .., "new": ..}}`.
+///
+/// The rows are keyed by column name; a missing row, before a create or
+/// after a delete, has no value for any field.
+pub fn content_diff(
+    document_type: &DocumentType,
+    before: Option<&Map<String, Value>>,
+    after: Option<&Map<String, Value>>,
+) -> Value {
+    let value = |row: Option<&Map<String, Value>>, column: &str| {
+        row.and_then(|row| row.get(column))
+            .cloned()
+            .unwrap_or(Value::Null)
+    };
+
+    let mut columns: Vec<(String, String)> = document_type
+        .fields
+        .iter()
+        .map(|field| (field.id.to_string(), field.id.normalized()))
+        .collect();
+    if document_type.workflow().is_some() {
+        columns.push((
+            WORKFLOW_STAGE_FIELD_NAME.to_string(),
+            WORKFLOW_STAGE_FIELD_NAME.to_string(),
+        ));
+    }
+
+    let mut diff = Map::new();
+    for (name, column) in columns {
+        let old = value(before, &column);
+        let new = value(after, &column);
+        if old != new {
+            diff.insert(name, json!({ "old": old, "new": new }));
+        }
+    }
+    Value::Object(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use luminair_common::AttributeId;
+    use luminair_common::entities::{DocumentField, FieldType};
+    use std::collections::HashSet;
+
+    fn brand() -> DocumentType {
+        let mut document = DocumentType::new_bare_collection("brand", "brand", "brands").unwrap();
+        for name in ["uid", "name"] {
+            document.fields.insert(DocumentField {
+                id: AttributeId::try_new(name).unwrap(),
+                field_type: FieldType::Text,
+                constraints: HashSet::new(),
+                required: false,
+                unique: false,
+                renamed_from: None,
+                api_name: None,
+                description: None,
+                filterable: false,
+                sortable: false,
+                personal: false,
+            });
+        }
+        document
+    }
+
+    fn row(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_diff_keeps_changed_fields_only() {
+        let before = row(json!({"uid": "b", "name": "Old", "version": 1}));
+        let after = row(json!({"uid": "b", "name": "New", "version": 2}));
+
+        let diff = content_diff(&brand(), Some(&before), Some(&after));
+
+        assert_eq!(diff, json!({"name": {"old": "Old", "new": "New"}}));
+    }
+
+    #[test]
+    fn test_diff_of_create_has_every_set_field() {
+        let after = row(json!({"uid": "b", "name": null}));
+
+        let diff = content_diff(&brand(), None, Some(&after));
+
+        assert_eq!(diff, json!({"uid": {"old": null, "new": "b"}}));
+    }
+
+    #[test]
+    fn test_action_round_trips_through_its_name() {
+        for action in [
+            ContentAction::Create,
+            ContentAction::Update,
+            ContentAction::Publish,
+            ContentAction::Unpublish,
+            ContentAction::Delete,
+        ] {
+            assert_eq!(action.as_str().parse::<ContentAction>(), Ok(action));
+        }
+        assert!("archive".parse::<ContentAction>().is_err());
+    }
+}
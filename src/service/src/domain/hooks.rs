@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use luminair_common::{DocumentType, DocumentTypeId};
+
+use crate::domain::actor::ActorContext;
+use crate::domain::document::{DocumentInstance, DocumentInstanceId};
+
+/// Callbacks on the writes of the documents of a type, for applications
+/// embedding the service as a library.
+///
+/// The `before_*` hooks run inside the write transaction, once the document is
+/// locked and before it is written. They may change the instance about to be
+/// written, or reject the write with a [`HookError`], which rolls the whole
+/// transaction back. The `after_*` hooks run once the transaction is committed,
+/// so they only ever see writes which are stored.
+///
+/// Every hook is told who the write is made for, e.g. to check permissions
+/// or record who made a change; background writes, such as scheduled
+/// publishes, are made for [`ActorContext::anonymous`].
+///
+/// Every hook does nothing by default, implementors override the ones they need.
+pub trait LifecycleHooks: Send + Sync + 'static {
+    fn before_create(
+        &self,
+        _document_type: &DocumentType,
+        _actor: &ActorContext,
+        _instance: &mut DocumentInstance,
+    ) -> Result<(), HookError> {
+        Ok(())
+    }
+
+    fn after_create(
+        &self,
+        _document_type: &DocumentType,
+        _actor: &ActorContext,
+        _instance: &DocumentInstance,
+    ) {
+    }
+
+    /// Also called before the draft is moved to another workflow stage.
+    fn before_update(
+        &self,
+        _document_type: &DocumentType,
+        _actor: &ActorContext,
+        _instance: &mut DocumentInstance,
+    ) -> Result<(), HookError> {
+        Ok(())
+    }
+
+    fn after_update(
+        &self,
+        _document_type: &DocumentType,
+        _actor: &ActorContext,
+        _instance: &DocumentInstance,
+    ) {
+    }
+
+    fn before_publish(
+        &self,
+        _document_type: &DocumentType,
+        _actor: &ActorContext,
+        _instance: &mut DocumentInstance,
+    ) -> Result<(), HookError> {
+        Ok(())
+    }
+
+    fn after_publish(
+        &self,
+        _document_type: &DocumentType,
+        _actor: &ActorContext,
+        _instance: &DocumentInstance,
+    ) {
+    }
+
+    fn before_unpublish(
+        &self,
+        _document_type: &DocumentType,
+        _actor: &ActorContext,
+        _instance: &mut DocumentInstance,
+    ) -> Result<(), HookError> {
+        Ok(())
+    }
+
+    fn after_unpublish(
+        &self,
+        _document_type: &DocumentType,
+        _actor: &ActorContext,
+        _instance: &DocumentInstance,
+    ) {
+    }
+
+    fn before_delete(
+        &self,
+        _document_type: &DocumentType,
+        _actor: &ActorContext,
+        _id: DocumentInstanceId,
+    ) -> Result<(), HookError> {
+        Ok(())
+    }
+
+    fn after_delete(
+        &self,
+        _document_type: &DocumentType,
+        _actor: &ActorContext,
+        _id: DocumentInstanceId,
+    ) {
+    }
+}
+
+/// Rejection of a write by a `before_*` lifecycle hook.
+#[derive(thiserror::Error, Debug)]
+#[error("Rejected by lifecycle hook: {0}")]
+pub struct HookError(pub String);
+
+/// Lifecycle hooks registered per document type.
+#[derive(Clone, Default)]
+pub struct LifecycleHooksRegistry {
+    hooks: HashMap<DocumentTypeId, Vec<Arc<dyn LifecycleHooks>>>,
+}
+
+impl LifecycleHooksRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `hooks` for the documents of `document_type`; they are called
+    /// after the hooks registered for the type before them.
+    pub fn register(mut self, document_type: DocumentTypeId, hooks: impl LifecycleHooks) -> Self {
+        self.hooks
+            .entry(document_type)
+            .or_default()
+            .push(Arc::new(hooks));
+        self
+    }
+
+    /// Returns the hooks of `document_type`, in their registration order.
+    pub fn for_type(&self, document_type: &DocumentType) -> &[Arc<dyn LifecycleHooks>] {
+        self.hooks
+            .get(&document_type.id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
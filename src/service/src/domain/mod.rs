@@ -1,3 +1,8 @@
+pub mod actor;
 pub mod document;
+pub mod events;
+pub mod hooks;
 pub mod query;
 pub mod repository;
+pub mod subject;
+pub mod translation;
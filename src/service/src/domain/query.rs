@@ -1,6 +1,10 @@
-use luminair_common::DocumentTypeId;
+use std::collections::HashMap;
 
-use crate::domain::document::content::DomainValue;
+use luminair_common::{AttributeId, DocumentType, DocumentTypeId};
+use rust_decimal::Decimal;
+
+use crate::domain::document::content::{ContentValue, DomainValue};
+use crate::domain::document::{DocumentInstance, DocumentInstanceId};
 
 /// Represents the publication status filter for document queries
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -12,6 +16,47 @@ pub enum DocumentStatus {
     Draft,
 }
 
+/// How populated relations of a list of documents are loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PopulateStrategy {
+    /// Load the documents, then each populated relation with one batched query
+    #[default]
+    Batched,
+    /// Load the documents together with their relations in a single statement
+    Lateral,
+}
+
+/// How the related documents of one populated relation are answered, e.g.
+/// `populate[brands][fields]=name&populate[brands][sort]=name&populate[brands][limit]=5`
+#[derive(Debug, Clone, Default)]
+pub struct PopulateOptions {
+    /// fields of the related documents to answer, all of them when `None`
+    pub fields: Option<Vec<AttributeId>>,
+    /// order of the related documents of each document, unspecified when empty
+    pub sort: Vec<Sort>,
+    /// most related documents answered per document
+    pub limit: Option<u64>,
+}
+
+impl PopulateOptions {
+    /// Whether the related documents are sorted or limited, rather than
+    /// answered as they are linked.
+    pub fn is_ordered(&self) -> bool {
+        !self.sort.is_empty() || self.limit.is_some()
+    }
+
+    /// Keep only the selected fields of a related document.
+    pub fn select_fields(&self, mut document: DocumentInstance) -> DocumentInstance {
+        if let Some(fields) = &self.fields {
+            document
+                .content
+                .fields
+                .retain(|attribute, _| fields.contains(attribute));
+        }
+        document
+    }
+}
+
 /// Query for finding DocumentInstances
 /// Used in repository for single DocumentType
 #[derive(Debug, Clone)]
@@ -20,6 +65,10 @@ pub struct DocumentInstanceQuery {
     pub sort: Vec<Sort>,
     pub limit: Option<i64>,
     pub offset: Option<i64>,
+    /// Order the instances at random, instead of by `sort`
+    pub random_order: Option<RandomOrder>,
+    /// Draw the instances from a sample of the rows of their table
+    pub sample: Option<TableSample>,
 
     /// Include draft instances?
     pub status: DocumentStatus,
@@ -39,6 +88,8 @@ impl DocumentInstanceQuery {
             sort: Vec::new(),
             limit: None,
             offset: None,
+            random_order: None,
+            sample: None,
             status: DocumentStatus::default(),
         }
     }
@@ -89,6 +140,11 @@ impl DocumentInstanceQuery {
         self.with_filter(FilterExpression::NotIn { field, values })
     }
 
+    /// Add range filter: from <= field <= to
+    pub fn filter_between(self, field: String, from: DomainValue, to: DomainValue) -> Self {
+        self.with_filter(FilterExpression::Between { field, from, to })
+    }
+
     /// Add contains filter: field contains value (for text fields)
     pub fn filter_contains(self, field: String, value: String) -> Self {
         self.with_filter(FilterExpression::Contains { field, value })
@@ -114,9 +170,18 @@ impl DocumentInstanceQuery {
         self.with_filter(FilterExpression::IsNotNull { field })
     }
 
-    /// Add relation filter: document has related document
-    pub fn filter_has_relation(self, field: String, id: DocumentTypeId) -> Self {
-        self.with_filter(FilterExpression::HasRelation { field, id })
+    /// Add relation filter: document has a related document matching the filter
+    pub fn filter_has_relation(
+        self,
+        field: AttributeId,
+        target: &'static DocumentType,
+        filter: FilterExpression,
+    ) -> Self {
+        self.with_filter(FilterExpression::HasRelation {
+            field,
+            target,
+            filter: Box::new(filter),
+        })
     }
 
     /// Combine current filter with AND operator
@@ -218,6 +283,13 @@ pub enum FilterExpression {
         values: Vec<DomainValue>,
     },
 
+    /// Range: from <= field <= to
+    Between {
+        field: String,
+        from: DomainValue,
+        to: DomainValue,
+    },
+
     /// Contains (for text fields)
     Contains { field: String, value: String },
 
@@ -233,8 +305,17 @@ pub enum FilterExpression {
     /// Is not null
     IsNotNull { field: String },
 
-    /// For relations: document has related document
-    HasRelation { field: String, id: DocumentTypeId },
+    /// For relations: document has a related document of the `target` type
+    /// matching `filter`
+    HasRelation {
+        field: AttributeId,
+        target: &'static DocumentType,
+        filter: Box<FilterExpression>,
+    },
+
+    /// Draft saved after its published version, with unpublished changes;
+    /// matches no published document
+    Modified,
 
     /// Combine filters with AND
     And(Box<FilterExpression>, Box<FilterExpression>),
@@ -254,3 +335,85 @@ pub enum SortDirection {
     Ascending,
     Descending,
 }
+
+/// Random order of the instances of a query, e.g. `?sort=random&seed=42`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RandomOrder {
+    /// seed of a reproducible order, the same on every page of the query; a
+    /// new order is drawn for every query without
+    pub seed: Option<i64>,
+}
+
+/// Sample of the rows of the table of a document type, taken with
+/// `TABLESAMPLE BERNOULLI`, the instances of a query are drawn from, e.g.
+/// `?sample=5` for about 5% of the documents
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TableSample {
+    /// percentage of the rows sampled, in `(0, 100]`
+    pub percent: f64,
+    /// seed of a reproducible sample, the same on every page of the query
+    /// while the table isn't changed; a new sample is drawn for every query
+    /// without
+    pub seed: Option<i64>,
+}
+
+/// Aggregate function computed for every group of an [`AggregateQuery`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFunction {
+    /// Name of the function in query parameters and responses
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Count => "count",
+            Self::Sum => "sum",
+            Self::Avg => "avg",
+            Self::Min => "min",
+            Self::Max => "max",
+        }
+    }
+}
+
+/// A metric of an [`AggregateQuery`]: the function applied to a field,
+/// or to the documents themselves for `count` without a field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metric {
+    pub function: AggregateFunction,
+    pub field: Option<AttributeId>,
+}
+
+/// Query aggregating DocumentInstances of a single DocumentType
+/// into groups with the same values of the `group_by` fields
+#[derive(Debug, Clone)]
+pub struct AggregateQuery {
+    pub filter: FilterExpression,
+    pub status: DocumentStatus,
+    pub group_by: Vec<AttributeId>,
+    pub metrics: Vec<Metric>,
+}
+
+/// One group of the result of an [`AggregateQuery`]
+#[derive(Debug, Clone)]
+pub struct AggregateGroup {
+    /// Values of the `group_by` fields shared by the documents of the group
+    pub group: HashMap<AttributeId, ContentValue>,
+    /// Metric values in the order of the query, `None` when there is no value
+    /// to aggregate (e.g. `min` over null fields only)
+    pub metrics: Vec<(Metric, Option<Decimal>)>,
+}
+
+/// A published document matching a full-text search, with its rank among
+/// the documents of all types
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub document_type: DocumentTypeId,
+    pub document_id: DocumentInstanceId,
+    /// relevance of the document, higher first
+    pub rank: f32,
+}
@@ -1,10 +1,25 @@
-use std::{collections::HashMap, future::Future};
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+};
 
+use futures::stream::BoxStream;
+use luminair_common::entities::OnDeleteAction;
 use luminair_common::{AttributeId, DocumentType};
 
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
 use crate::domain::{
-    document::{DocumentInstance, DocumentInstanceId},
-    query::{DocumentInstanceQuery, DocumentStatus},
+    actor::ActorContext,
+    document::{DocumentInstance, DocumentInstanceId, lifecycle::PublicationSchedule},
+    query::{
+        AggregateGroup, AggregateQuery, DocumentInstanceQuery, DocumentStatus, PopulateOptions,
+        SearchHit,
+    },
+    subject::{Erasure, SubjectDocuments},
+    translation::TranslationStatus,
 };
 
 /// Port: the persistence contract that infrastructure adapters must implement.
@@ -22,8 +37,10 @@ use crate::domain::{
 ///   UUID + status filter identifies at most one row.
 /// - `fetch_relations` is the single batch relation-loading method, replacing the
 ///   previous `fetch_relations_for_one` / `fetch_relations_for_many` pair.
-/// - `apply_relation_ops` accepts [`DocumentInstanceId`] (UUID) values; the
-///   repository resolves them to internal row IDs internally via batch SELECTs.
+/// - `insert` and `update` write relation operations in the same transaction
+///   as the document itself, after checking that every target exists.
+/// - the writes take the [`ActorContext`] of the request, passed on to the
+///   lifecycle hooks and recorded with the change events of the document.
 pub trait DocumentsRepository: Send + Sync + 'static {
     // ── Read ────────────────────────────────────────────────────────────────
 
@@ -42,6 +59,16 @@ pub trait DocumentsRepository: Send + Sync + 'static {
         query: &DocumentInstanceQuery,
     ) -> impl Future<Output = Result<u64, RepositoryError>> + Send;
 
+    /// Stream all instances matching the query, row by row.
+    ///
+    /// Unlike [`DocumentsRepository::find`], the rows are not collected into
+    /// memory, which keeps exports of large collections flat.
+    fn stream(
+        &self,
+        document_type: &'static DocumentType,
+        query: &DocumentInstanceQuery,
+    ) -> BoxStream<'static, Result<DocumentInstance, RepositoryError>>;
+
     /// Return the single instance identified by `id`, or `None` if not found.
     ///
     /// The `query` parameter carries the publication status filter.
@@ -54,57 +81,294 @@ pub trait DocumentsRepository: Send + Sync + 'static {
         query: &DocumentInstanceQuery,
     ) -> impl Future<Output = Result<Option<DocumentInstance>, RepositoryError>> + Send;
 
+    /// Return whether the instance identified by `id` exists in `status`,
+    /// without loading it.
+    fn exists(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+        status: DocumentStatus,
+    ) -> impl Future<Output = Result<bool, RepositoryError>> + Send;
+
     /// Batch-load relations for a set of main document rows.
     ///
-    /// Returns a nested map: `attribute_id → owning_document_id → related_instances`.
+    /// Returns a nested map: `attribute_id → owning_document_id → related_instances`,
+    /// the related instances of each document sorted, limited and with the
+    /// fields selected by the `options` of their relation.
     fn fetch_relations(
         &self,
         document_type: &DocumentType,
         fields: &[AttributeId],
         filters: &HashMap<AttributeId, crate::domain::query::FilterExpression>,
+        options: &HashMap<AttributeId, PopulateOptions>,
         status: DocumentStatus,
         ids: &[DocumentInstanceId],
     ) -> impl Future<Output = Result<RelationMap, RepositoryError>> + Send;
 
+    /// Count the related documents of a set of main document rows, for every
+    /// relation of `fields` at once.
+    ///
+    /// Returns a nested map: `attribute_id → owning_document_id → count`;
+    /// documents without related documents are left out.
+    fn count_relations(
+        &self,
+        document_type: &DocumentType,
+        fields: &[AttributeId],
+        status: DocumentStatus,
+        ids: &[DocumentInstanceId],
+    ) -> impl Future<Output = Result<RelationCounts, RepositoryError>> + Send;
+
+    /// Return all instances matching the query with the given relations
+    /// populated, loading documents and relations in a single statement.
+    ///
+    /// An alternative to [`DocumentsRepository::find`] followed by
+    /// [`DocumentsRepository::fetch_relations`] that saves a round trip per
+    /// populated relation.
+    fn find_with_relations(
+        &self,
+        document_type: &DocumentType,
+        query: &DocumentInstanceQuery,
+        fields: &[AttributeId],
+        filters: &HashMap<AttributeId, crate::domain::query::FilterExpression>,
+        options: &HashMap<AttributeId, PopulateOptions>,
+    ) -> impl Future<Output = Result<Vec<DocumentInstance>, RepositoryError>> + Send;
+
+    /// Group the instances matching the query and compute its metrics per group.
+    ///
+    /// Groups are ordered by the values of the `group_by` fields.
+    fn aggregate(
+        &self,
+        document_type: &DocumentType,
+        query: &AggregateQuery,
+    ) -> impl Future<Output = Result<Vec<AggregateGroup>, RepositoryError>> + Send;
+
+    /// Return the published documents of `document_types` whose text fields
+    /// match the full-text query `text`, the most relevant first, at most
+    /// `limit`.
+    ///
+    /// `text` takes the web search syntax: words, `"quoted phrases"`, `or`
+    /// and `-excluded` words.
+    fn search(
+        &self,
+        document_types: &[&DocumentType],
+        text: &str,
+        limit: u64,
+    ) -> impl Future<Output = Result<Vec<SearchHit>, RepositoryError>> + Send;
+
+    /// Return the revisions of the document identified by `id`, the most
+    /// recent first.
+    ///
+    /// A revision is the content the document had before one of its updates
+    /// or publishes, `audit.version` is the version it was saved as.
+    fn find_revisions(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+    ) -> impl Future<Output = Result<Vec<DocumentInstance>, RepositoryError>> + Send;
+
+    /// Return the documents linking to the document identified by `id`, per
+    /// owning relation targeting its document type, by their draft or
+    /// published links. Relations without links to it are left out.
+    fn find_references(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+    ) -> impl Future<Output = Result<Vec<InboundReference>, RepositoryError>> + Send;
+
+    /// Return the revision of the document saved as `version`, or `None` if
+    /// there is none.
+    fn find_revision(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+        version: i32,
+    ) -> impl Future<Output = Result<Option<DocumentInstance>, RepositoryError>> + Send;
+
+    /// Return the documents with a scheduled publish or unpublish due at
+    /// `until`, with their whole schedule.
+    fn find_scheduled(
+        &self,
+        document_type: &DocumentType,
+        until: DateTime<Utc>,
+    ) -> impl Future<
+        Output = Result<Vec<(DocumentInstanceId, PublicationSchedule)>, RepositoryError>,
+    > + Send;
+
     // ── Write ───────────────────────────────────────────────────────────────
 
-    /// Persist a newly created document instance.
+    /// Persist a newly created document instance together with its initial
+    /// relation links, atomically.
     ///
     /// The `instance.id` (database row key) is a placeholder; the database
     /// assigns the actual row ID. All other fields — `document_id`, `audit`,
     /// `content`, `publication_state` — are taken from the instance as-is.
+    ///
+    /// Returns the instance as it was stored, so callers don't need a
+    /// follow-up read.
     fn insert(
         &self,
         document_type: &DocumentType,
         instance: &DocumentInstance,
-    ) -> impl Future<Output = Result<(), RepositoryError>> + Send;
+        relations: &HashMap<AttributeId, RelationOps>,
+        actor: &ActorContext,
+    ) -> impl Future<Output = Result<DocumentInstance, RepositoryError>> + Send;
 
-    /// Persist changes to an existing document instance.
+    /// Persist changes to an existing document instance and its relation
+    /// links, atomically.
     ///
-    /// Identifies the row to update via `instance.document_id`.
+    /// Identifies the row to update via `instance.document_id`. The row is
+    /// only written while its stored version still equals `expected_version`,
+    /// otherwise [`RepositoryError::Conflict`] is returned and nothing changes.
+    /// The stored content is kept as a revision of the document first.
     fn update(
         &self,
         document_type: &DocumentType,
         instance: &DocumentInstance,
+        expected_version: i32,
+        relations: &HashMap<AttributeId, RelationOps>,
+        actor: &ActorContext,
     ) -> impl Future<Output = Result<(), RepositoryError>> + Send;
 
-    /// Delete the instance identified by `id`.
-    fn delete(
+    /// Persist the publishing of a document instance like
+    /// [`DocumentsRepository::update`] does, clearing its scheduled publish
+    /// and, with `unpublish_at`, scheduling its unpublish, atomically.
+    fn publish(
+        &self,
+        document_type: &DocumentType,
+        instance: &DocumentInstance,
+        expected_version: i32,
+        unpublish_at: Option<DateTime<Utc>>,
+        actor: &ActorContext,
+    ) -> impl Future<Output = Result<(), RepositoryError>> + Send;
+
+    /// Persist the unpublishing of a published document instance: its
+    /// published snapshot is removed, and the main row updated like
+    /// [`DocumentsRepository::update`] does.
+    fn unpublish(
+        &self,
+        document_type: &DocumentType,
+        instance: &DocumentInstance,
+        expected_version: i32,
+        actor: &ActorContext,
+    ) -> impl Future<Output = Result<(), RepositoryError>> + Send;
+
+    /// Persist the move of a document instance to another workflow stage: the
+    /// main row is updated like [`DocumentsRepository::update`] does, leaving
+    /// its content as it is.
+    fn move_to_stage(
+        &self,
+        document_type: &DocumentType,
+        instance: &DocumentInstance,
+        expected_version: i32,
+        actor: &ActorContext,
+    ) -> impl Future<Output = Result<(), RepositoryError>> + Send;
+
+    /// Replace the publication schedule of the document identified by `id`.
+    fn schedule(
         &self,
         document_type: &DocumentType,
         id: DocumentInstanceId,
+        schedule: &PublicationSchedule,
     ) -> impl Future<Output = Result<(), RepositoryError>> + Send;
 
-    /// Apply connect / disconnect relation operations atomically.
+    /// Schedule the publish of the document identified by `id` at
+    /// `publish_at`, and its unpublish at `unpublish_at` when given, keeping
+    /// the scheduled unpublish otherwise.
+    fn schedule_publish(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+        publish_at: DateTime<Utc>,
+        unpublish_at: Option<DateTime<Utc>>,
+    ) -> impl Future<Output = Result<(), RepositoryError>> + Send;
+
+    /// Delete the instance identified by `id`, handling the links to it as
+    /// the `onDelete` action of their relation says.
     ///
-    /// Resolves every [`DocumentInstanceId`] to its internal database row ID
-    /// via batch SELECT queries — callers never need to manage row IDs.
-    fn apply_relation_ops(
+    /// Links of `restrict` relations fail the delete with
+    /// [`RepositoryError::Referenced`]. Links of `detach` relations are
+    /// removed and their documents marked as updated, links of `cascade`
+    /// relations are deleted with the document.
+    fn delete(
         &self,
         document_type: &DocumentType,
-        document_id: DocumentInstanceId,
-        ops: &HashMap<AttributeId, RelationOps>,
+        id: DocumentInstanceId,
+        actor: &ActorContext,
     ) -> impl Future<Output = Result<(), RepositoryError>> + Send;
+
+    /// Stream every stored row of every document type — documents, published
+    /// snapshots, revisions and relation links — as of one point in time.
+    fn export_archive(&self) -> BoxStream<'static, Result<ArchiveRow, RepositoryError>>;
+
+    /// Restore the rows of an export, atomically, returning how many rows
+    /// were restored per table.
+    ///
+    /// Fails with [`RepositoryError::NotEmpty`] unless no document of any
+    /// type is stored yet, and with [`RepositoryError::ValidationFailed`] for
+    /// rows of tables the schema doesn't have.
+    fn import_archive(
+        &self,
+        rows: Vec<ArchiveRow>,
+    ) -> impl Future<Output = Result<BTreeMap<String, u64>, RepositoryError>> + Send;
+
+    // ── Maintenance ─────────────────────────────────────────────────────────
+
+    /// Count the relation links to or from documents which don't exist, per
+    /// relation table holding any, removing them when `remove`.
+    ///
+    /// Links are removed `batch_size` at a time, each batch committed on its
+    /// own, so the counts of a removal are the links removed.
+    fn clean_orphaned_links(
+        &self,
+        remove: bool,
+        batch_size: u64,
+    ) -> impl Future<Output = Result<BTreeMap<String, u64>, RepositoryError>> + Send;
+
+    /// Count the documents of the types with a retention which were created
+    /// longer ago than it keeps them at `now`, per document type holding
+    /// any, deleting or archiving them unless `dry_run`.
+    ///
+    /// Documents are removed `batch_size` at a time, each batch committed on
+    /// its own, so the counts of a run are the documents removed.
+    fn apply_retention(
+        &self,
+        now: DateTime<Utc>,
+        dry_run: bool,
+        batch_size: u64,
+    ) -> impl Future<Output = Result<BTreeMap<String, u64>, RepositoryError>> + Send;
+
+    /// Return the documents whose personal text fields hold `value`, an
+    /// identifying value of a data subject such as their email address,
+    /// ignoring case, per document type holding any.
+    ///
+    /// Drafts, published copies and revisions are all looked up, so a value
+    /// changed since is found too.
+    fn find_subject_documents(
+        &self,
+        value: &str,
+    ) -> impl Future<Output = Result<Vec<SubjectDocuments>, RepositoryError>> + Send;
+
+    /// Clear the personal fields of the documents holding `value`, in their
+    /// drafts, published copies, revisions and archived rows, and record the
+    /// erasure for audit, all in one transaction.
+    ///
+    /// The drafts get a new version and an `update` content change event.
+    fn erase_subject(
+        &self,
+        value: &str,
+        actor: &ActorContext,
+    ) -> impl Future<Output = Result<Erasure, RepositoryError>> + Send;
+
+    /// Return the translations of the drafts of the localized `document_type`,
+    /// ordered by document id, at most `limit` after the first `offset`: per
+    /// locale of the type, whether a localized field has a text in it and
+    /// when its texts last changed. The counts are of all the drafts.
+    fn translation_status(
+        &self,
+        document_type: &DocumentType,
+        offset: u64,
+        limit: u64,
+    ) -> impl Future<Output = Result<TranslationStatus, RepositoryError>> + Send;
 }
 
 // ── Supporting types ─────────────────────────────────────────────────────────
@@ -112,15 +376,45 @@ pub trait DocumentsRepository: Send + Sync + 'static {
 /// `attribute_id → owning_document_id → related_instances`
 pub type RelationMap = HashMap<AttributeId, HashMap<DocumentInstanceId, Vec<DocumentInstance>>>;
 
-/// Connect / disconnect sets for a single relation attribute.
+/// `attribute_id → owning_document_id → number of related documents`
+pub type RelationCounts = HashMap<AttributeId, HashMap<DocumentInstanceId, u64>>;
+
+/// Set / connect / disconnect operations for a single relation attribute.
+///
+/// `set` replaces all existing links and is applied before `connect` and
+/// `disconnect`.
 #[derive(Debug, Default)]
 pub struct RelationOps {
+    /// UUIDs of documents to replace the relation with, if any.
+    pub set: Option<Vec<DocumentInstanceId>>,
     /// UUIDs of documents to add to the relation.
     pub connect: Vec<DocumentInstanceId>,
     /// UUIDs of documents to remove from the relation.
     pub disconnect: Vec<DocumentInstanceId>,
 }
 
+/// The documents linking to a document by one relation.
+#[derive(Debug, Clone)]
+pub struct InboundReference {
+    /// document type declaring the relation
+    pub document_type: &'static DocumentType,
+    pub relation: AttributeId,
+    /// action of the relation when the linked document is deleted
+    pub on_delete: OnDeleteAction,
+    /// ids of the linking documents, in order
+    pub document_ids: Vec<DocumentInstanceId>,
+}
+
+/// One stored row of a content archive, by the table it is stored in.
+///
+/// Rows are kept as they are stored, so an archive restores into a database
+/// of the same schema only.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveRow {
+    pub table: String,
+    pub row: Value,
+}
+
 /// Errors that can be returned by any repository method.
 #[derive(thiserror::Error, Debug)]
 pub enum RepositoryError {
@@ -130,8 +424,14 @@ pub enum RepositoryError {
     DocumentInstanceNotFound,
     #[error("Validation failed: {0}")]
     ValidationFailed(String),
+    #[error("Document was modified concurrently, expected version {0}")]
+    Conflict(i32),
     #[error("Unique constraint violated: {0}")]
     UniqueViolation(String),
+    #[error("Document is linked by relations restricting its delete")]
+    Referenced(Vec<InboundReference>),
+    #[error("Database already holds content")]
+    NotEmpty,
     #[error("Database error: {0}")]
     DatabaseError(String),
 }
@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use luminair_common::{AttributeId, DocumentType};
+use sha2::{Digest, Sha256};
+
+use crate::domain::document::{DocumentInstance, DocumentInstanceId};
+
+/// The documents of one type holding the personal data of a data subject,
+/// found by an identifying value of theirs, e.g. their email address.
+#[derive(Debug, Clone)]
+pub struct SubjectDocuments {
+    pub document_type: &'static DocumentType,
+    /// ids of the documents, in order
+    pub document_ids: Vec<DocumentInstanceId>,
+}
+
+/// A document holding the personal data of a data subject, with everything
+/// stored of it, for their data-subject access request.
+#[derive(Debug, Clone)]
+pub struct SubjectDocument {
+    pub document_type: &'static DocumentType,
+    pub draft: DocumentInstance,
+    /// the published copy of a draft-and-publish type, if published
+    pub published: Option<DocumentInstance>,
+    /// the former contents of the document, most recent first
+    pub revisions: Vec<DocumentInstance>,
+    /// the documents linking to the document, per relation
+    pub references: Vec<SubjectReference>,
+}
+
+/// The documents linking to a document of a data subject by one relation.
+#[derive(Debug, Clone)]
+pub struct SubjectReference {
+    /// document type declaring the relation
+    pub document_type: &'static DocumentType,
+    pub relation: AttributeId,
+    /// drafts of the linking documents
+    pub documents: Vec<DocumentInstance>,
+}
+
+/// Audit record of an erasure of the personal data of a data subject.
+#[derive(Debug, Clone)]
+pub struct Erasure {
+    pub id: i64,
+    /// SHA-256 of the identifying value, which isn't kept
+    pub subject: String,
+    /// the documents whose personal fields were cleared
+    pub documents: Vec<SubjectDocuments>,
+    /// id of the user who requested the erasure
+    pub erased_by: Option<String>,
+    pub erased_at: DateTime<Utc>,
+}
+
+/// What an erasure keeps of the identifying `value` of a data subject: the
+/// hex encoded SHA-256 of the value trimmed and in lower case, so the
+/// erasures of a subject are found again without keeping their data.
+pub fn subject_hash(value: &str) -> String {
+    Sha256::digest(value.trim().to_lowercase().as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subject_hash_ignores_case_and_spaces() {
+        assert_eq!(
+            subject_hash(" Jane@Example.com"),
+            subject_hash("jane@example.com")
+        );
+        assert_eq!(subject_hash("jane@example.com").len(), 64);
+    }
+}
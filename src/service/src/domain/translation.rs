@@ -0,0 +1,206 @@
+use chrono::{DateTime, Utc};
+use luminair_common::entities::FieldType;
+use luminair_common::{AttributeId, DocumentType};
+use std::collections::HashMap;
+
+use crate::domain::document::DocumentInstanceId;
+use crate::domain::document::content::{ContentValue, DocumentContent};
+
+/// Translations of the localized fields of one document, a locale of its
+/// type each, in the order of the type.
+#[derive(Debug, Clone)]
+pub struct DocumentTranslations {
+    pub document_id: DocumentInstanceId,
+    pub locales: Vec<LocaleTranslation>,
+}
+
+/// Translation of a document into one locale.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocaleTranslation {
+    pub locale: String,
+    /// whether a localized field has a text in the locale
+    pub present: bool,
+    /// when a text of the locale last changed, if it was since it's tracked
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Page of the translations of the drafts of a localized document type, with
+/// the counts of all of its drafts.
+#[derive(Debug, Clone)]
+pub struct TranslationStatus {
+    pub documents: Vec<DocumentTranslations>,
+    /// a locale of the type each, in the order of the type
+    pub counts: Vec<LocaleTranslationCounts>,
+}
+
+impl TranslationStatus {
+    /// Number of the drafts of the type, on every page.
+    pub fn total(&self) -> u64 {
+        self.counts.first().map_or(0, |counts| {
+            counts.translated + counts.stale + counts.missing
+        })
+    }
+}
+
+/// Number of the drafts of a document type in each state of their
+/// translation into one locale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocaleTranslationCounts {
+    pub locale: String,
+    pub translated: u64,
+    pub stale: u64,
+    pub missing: u64,
+}
+
+impl LocaleTranslationCounts {
+    /// Counts of `locale` without drafts.
+    pub fn new(locale: String) -> Self {
+        Self {
+            locale,
+            translated: 0,
+            stale: 0,
+            missing: 0,
+        }
+    }
+
+    /// Count one more draft whose translation is in `state`.
+    pub fn add(&mut self, state: TranslationState) {
+        match state {
+            TranslationState::Current => self.translated += 1,
+            TranslationState::Stale => self.stale += 1,
+            TranslationState::Missing => self.missing += 1,
+        }
+    }
+}
+
+/// State of the translation of a document into one locale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranslationState {
+    /// the locale has no text
+    Missing,
+    /// the texts of the source locale changed after the ones of the locale
+    Stale,
+    Current,
+}
+
+impl DocumentTranslations {
+    /// State of the translation into `locale`, against the source locale of
+    /// the type, its first one.
+    pub fn state(&self, locale: &LocaleTranslation) -> TranslationState {
+        if !locale.present {
+            return TranslationState::Missing;
+        }
+        let source = self.locales.first().and_then(|source| source.updated_at);
+        match (locale.updated_at, source) {
+            (Some(translated), Some(source)) if translated < source => TranslationState::Stale,
+            _ => TranslationState::Current,
+        }
+    }
+}
+
+/// Locales of `document_type` with a text in a localized field of `content`.
+pub fn written_locales(document_type: &DocumentType, content: &DocumentContent) -> Vec<String> {
+    locales(document_type)
+        .filter(|locale| {
+            localized_texts(document_type, content).any(|texts| has_text(texts, locale))
+        })
+        .collect()
+}
+
+/// Locales of `document_type` whose text in a localized field differs
+/// between `before` and `after`.
+pub fn changed_locales(
+    document_type: &DocumentType,
+    before: &DocumentContent,
+    after: &DocumentContent,
+) -> Vec<String> {
+    let text = |content: &DocumentContent, field: &AttributeId, locale: &str| match content
+        .fields
+        .get(field)
+    {
+        Some(ContentValue::LocalizedText(texts)) => texts.get(locale).cloned(),
+        _ => None,
+    };
+    locales(document_type)
+        .filter(|locale| {
+            document_type
+                .fields
+                .iter()
+                .filter(|field| field.field_type == FieldType::LocalizedText)
+                .any(|field| text(before, &field.id, locale) != text(after, &field.id, locale))
+        })
+        .collect()
+}
+
+/// Locales of `document_type`, its source locale first.
+pub fn locales(document_type: &DocumentType) -> impl Iterator<Item = String> + '_ {
+    document_type
+        .options
+        .iter()
+        .flat_map(|options| &options.localizations)
+        .map(|locale| locale.to_string())
+}
+
+fn localized_texts<'a>(
+    document_type: &'a DocumentType,
+    content: &'a DocumentContent,
+) -> impl Iterator<Item = &'a HashMap<String, String>> {
+    document_type
+        .fields
+        .iter()
+        .filter(|field| field.field_type == FieldType::LocalizedText)
+        .filter_map(|field| match content.fields.get(&field.id) {
+            Some(ContentValue::LocalizedText(texts)) => Some(texts),
+            _ => None,
+        })
+}
+
+fn has_text(texts: &HashMap<String, String>, locale: &str) -> bool {
+    texts.get(locale).is_some_and(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    fn translation(
+        locale: &str,
+        present: bool,
+        updated_at: Option<DateTime<Utc>>,
+    ) -> LocaleTranslation {
+        LocaleTranslation {
+            locale: locale.to_string(),
+            present,
+            updated_at,
+        }
+    }
+
+    #[test]
+    fn test_translations_older_than_the_source_are_stale() {
+        let now = Utc::now();
+        let translations = DocumentTranslations {
+            document_id: DocumentInstanceId::generate(),
+            locales: vec![
+                translation("en", true, Some(now)),
+                translation("ro", true, Some(now - TimeDelta::days(1))),
+                translation("ru", true, None),
+                translation("de", false, None),
+            ],
+        };
+        let states: Vec<_> = translations
+            .locales
+            .iter()
+            .map(|locale| translations.state(locale))
+            .collect();
+        assert_eq!(
+            states,
+            [
+                TranslationState::Current,
+                TranslationState::Stale,
+                TranslationState::Current,
+                TranslationState::Missing,
+            ]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::sync::{Mutex, OnceLock};
+
+use axum::Router;
+use axum::extract::Request;
+use axum::http::{HeaderName, HeaderValue};
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[cfg(feature = "sentry")]
+pub mod sentry;
+
+/// Header of the id of a request: taken from the request, or generated, and
+/// answered in the response.
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+tokio::task_local! {
+    /// Context of the errors of the request the running task handles.
+    static CONTEXT: Mutex<ErrorContext>;
+}
+
+/// Receiver of the internal errors, installed once for the process.
+static REPORTER: OnceLock<Box<dyn ErrorReporter>> = OnceLock::new();
+
+/// Settings of the reporting of internal errors
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ErrorReportingSettings {
+    /// Sentry DSN the internal errors are reported to, none are when unset
+    #[serde(default)]
+    pub sentry_dsn: Option<String>,
+    /// environment the errors are reported for, e.g. `production`
+    #[serde(default)]
+    pub environment: Option<String>,
+}
+
+/// An internal error of a request, with what is known of its cause.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorReport {
+    pub message: String,
+    pub request_id: Option<String>,
+    /// method and path of the request
+    pub request: Option<String>,
+    /// document type of the failed query
+    pub document_type: Option<String>,
+    /// SQL of the failed query; values are bound as parameters, so it holds
+    /// no content
+    pub sql: Option<String>,
+}
+
+/// Receiver of the internal errors of the requests, e.g. an error tracker.
+pub trait ErrorReporter: Send + Sync + 'static {
+    fn report(&self, report: &ErrorReport);
+}
+
+/// Marks a response answering an internal error, with its message, for
+/// [`report_errors`].
+#[derive(Debug, Clone)]
+pub struct InternalError(pub String);
+
+/// What is known of the cause of an error of a request while it is handled.
+#[derive(Debug, Clone, Default)]
+struct ErrorContext {
+    request_id: Option<String>,
+    request: Option<String>,
+    document_type: Option<String>,
+    sql: Option<String>,
+}
+
+/// Install the reporter configured in `settings`, if any.
+pub fn start_error_reporting(settings: &ErrorReportingSettings) -> anyhow::Result<()> {
+    match &settings.sentry_dsn {
+        #[cfg(feature = "sentry")]
+        Some(dsn) => {
+            let reporter = sentry::SentryReporter::new(dsn, settings.environment.clone())?;
+            install_error_reporter(reporter);
+        }
+        #[cfg(not(feature = "sentry"))]
+        Some(_) => anyhow::bail!("the service is built without support for Sentry"),
+        None => {}
+    }
+    Ok(())
+}
+
+/// Install `reporter` as the receiver of the internal errors, unless one is
+/// installed already. Returns whether it was installed.
+pub fn install_error_reporter(reporter: impl ErrorReporter) -> bool {
+    REPORTER.set(Box::new(reporter)).is_ok()
+}
+
+/// Handle the requests of `router` with an id and a context collecting the
+/// cause of their internal errors, and report these errors.
+pub fn report_errors<S: Clone + Send + Sync + 'static>(router: Router<S>) -> Router<S> {
+    router.layer(middleware::from_fn(handle_reporting_errors))
+}
+
+/// Note the failed query of `document_type` in the context of the error of
+/// the running request, if any.
+pub fn note_failed_query(document_type: Option<&str>, sql: &str) {
+    let _ = CONTEXT.try_with(|context| {
+        let mut context = context.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(document_type) = document_type {
+            context.document_type = Some(document_type.to_string());
+        }
+        context.sql = Some(sql.to_string());
+    });
+}
+
+async fn handle_reporting_errors(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|id| id.to_str().ok())
+        .filter(|id| !id.is_empty())
+        .map(String::from)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let context = ErrorContext {
+        request_id: Some(request_id.clone()),
+        request: Some(format!("{} {}", request.method(), request.uri().path())),
+        ..Default::default()
+    };
+
+    let (mut response, context) = CONTEXT
+        .scope(Mutex::new(context), async {
+            let response = next.run(request).await;
+            let context =
+                CONTEXT.with(|context| context.lock().unwrap_or_else(|e| e.into_inner()).clone());
+            (response, context)
+        })
+        .await;
+
+    if let Some(InternalError(message)) = response.extensions_mut().remove::<InternalError>() {
+        let report = context.into_report(message);
+        if let Some(reporter) = REPORTER.get() {
+            reporter.report(&report);
+        }
+    }
+    if let Ok(request_id) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+    }
+    response
+}
+
+impl ErrorContext {
+    fn into_report(self, message: String) -> ErrorReport {
+        ErrorReport {
+            message,
+            request_id: self.request_id,
+            request: self.request,
+            document_type: self.document_type,
+            sql: self.sql,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_failed_query_is_noted_in_the_request_context() {
+        let context = ErrorContext {
+            request_id: Some("42".to_string()),
+            ..Default::default()
+        };
+        let context = CONTEXT
+            .scope(Mutex::new(context), async {
+                note_failed_query(Some("brand"), "SELECT 1");
+                CONTEXT.with(|context| context.lock().unwrap().clone())
+            })
+            .await;
+
+        assert_eq!(
+            context.into_report("failed".to_string()),
+            ErrorReport {
+                message: "failed".to_string(),
+                request_id: Some("42".to_string()),
+                request: None,
+                document_type: Some("brand".to_string()),
+                sql: Some("SELECT 1".to_string()),
+            }
+        );
+        // out of a request, nothing is noted
+        note_failed_query(Some("brand"), "SELECT 1");
+    }
+}
@@ -0,0 +1,47 @@
+use anyhow::Context;
+
+use crate::infrastructure::error_reporting::{ErrorReport, ErrorReporter};
+
+/// Reports the internal errors to Sentry, tagged with the id of their
+/// request and their document type.
+///
+/// Events are sent in the background; those still queued are flushed when
+/// the reporter is dropped.
+pub struct SentryReporter {
+    _guard: sentry::ClientInitGuard,
+}
+
+impl SentryReporter {
+    pub fn new(dsn: &str, environment: Option<String>) -> anyhow::Result<Self> {
+        let dsn: sentry::types::Dsn = dsn.parse().context("invalid Sentry DSN")?;
+        let guard = sentry::init(sentry::ClientOptions {
+            dsn: Some(dsn),
+            environment: environment.map(Into::into),
+            release: sentry::release_name!(),
+            ..Default::default()
+        });
+        Ok(Self { _guard: guard })
+    }
+}
+
+impl ErrorReporter for SentryReporter {
+    fn report(&self, report: &ErrorReport) {
+        sentry::with_scope(
+            |scope| {
+                if let Some(request_id) = &report.request_id {
+                    scope.set_tag("request_id", request_id);
+                }
+                if let Some(document_type) = &report.document_type {
+                    scope.set_tag("document_type", document_type);
+                }
+                if let Some(request) = &report.request {
+                    scope.set_extra("request", request.as_str().into());
+                }
+                if let Some(sql) = &report.sql {
+                    scope.set_extra("sql", sql.as_str().into());
+                }
+            },
+            || sentry::capture_message(&report.message, sentry::Level::Error),
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use rdkafka::ClientConfig;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+
+use crate::domain::events::ContentChangeEvent;
+use crate::infrastructure::events::EventPublisher;
+
+/// How long a send waits for the brokers to acknowledge an event.
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Publishes the content change events to a Kafka topic.
+///
+/// The events of a document share its id as key, so they keep their order
+/// within a partition. The producer is idempotent, so its own retries aren't
+/// written twice; an event published again by the relay carries the same
+/// `event-id` header for consumers to drop it.
+pub struct KafkaPublisher {
+    producer: FutureProducer,
+    topic: String,
+}
+
+impl KafkaPublisher {
+    pub fn new(brokers: &str, topic: impl Into<String>) -> anyhow::Result<Self> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("enable.idempotence", "true")
+            .create()
+            .with_context(|| format!("failed to create Kafka producer for {brokers}"))?;
+        Ok(Self {
+            producer,
+            topic: topic.into(),
+        })
+    }
+}
+
+impl EventPublisher for KafkaPublisher {
+    async fn publish(&self, event: &ContentChangeEvent) -> anyhow::Result<()> {
+        let payload = serde_json::to_vec(event)?;
+        let event_id = event.event_id.to_string();
+        let headers = OwnedHeaders::new().insert(Header {
+            key: "event-id",
+            value: Some(event_id.as_str()),
+        });
+        let record = FutureRecord::to(&self.topic)
+            .key(&event.document_id)
+            .payload(&payload)
+            .headers(headers);
+
+        self.producer
+            .send(record, Timeout::After(SEND_TIMEOUT))
+            .await
+            .map_err(|(e, _)| e)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use luminair_common::DOCUMENT_ID_FIELD_NAME;
+use luminair_common::database::{self, Database, StatementKind};
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::postgres::PgRow;
+use sqlx::types::Json;
+use sqlx::{AssertSqlSafe, Row};
+use tokio::time::{Instant, MissedTickBehavior};
+use uuid::Uuid;
+
+use crate::application::AppState;
+use crate::domain::events::ContentChangeEvent;
+use crate::infrastructure::persistence::builders::outbox::{
+    delete_published_events, mark_events_published, query_pending_events,
+};
+use crate::infrastructure::response_cache::ResponseCache;
+use crate::infrastructure::search_index::SearchIndex;
+
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "nats")]
+pub mod nats;
+
+/// Key of the Postgres advisory lock held by the replica relaying the outbox,
+/// so events are published by one replica at a time, in their order.
+pub const OUTBOX_RELAY_LOCK_KEY: i64 = 0x4c75_6d69_6e65_7674;
+
+/// How often the published events past their retention are removed.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Settings of the content change events
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventsSettings {
+    /// record content change events and publish them to the broker
+    #[serde(default)]
+    pub enabled: bool,
+    /// how often the outbox is looked up for events to publish
+    #[serde(default = "default_interval_milliseconds")]
+    pub interval_milliseconds: u64,
+    /// most events published per lookup
+    #[serde(default = "default_batch_size")]
+    pub batch_size: u64,
+    /// how long published events are kept in the outbox, 0 to keep them
+    #[serde(default = "default_retention_hours")]
+    pub retention_hours: u64,
+    /// broker the events are published to
+    #[serde(default)]
+    pub broker: Option<EventBrokerSettings>,
+}
+
+impl Default for EventsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_milliseconds: default_interval_milliseconds(),
+            batch_size: default_batch_size(),
+            retention_hours: default_retention_hours(),
+            broker: None,
+        }
+    }
+}
+
+/// Broker the content change events are published to
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum EventBrokerSettings {
+    /// NATS JetStream, on the subjects `{subject_prefix}.{type}.{action}`,
+    /// which have to be bound to a stream
+    Nats { url: String, subject_prefix: String },
+    /// Kafka, on `topic`, keyed by the id of the document
+    Kafka { brokers: String, topic: String },
+}
+
+impl EventBrokerSettings {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventBrokerSettings::Nats { .. } => "nats",
+            EventBrokerSettings::Kafka { .. } => "kafka",
+        }
+    }
+}
+
+fn default_interval_milliseconds() -> u64 {
+    1000
+}
+
+fn default_batch_size() -> u64 {
+    100
+}
+
+fn default_retention_hours() -> u64 {
+    7 * 24
+}
+
+/// Adapter of the broker the content change events are published to.
+pub trait EventPublisher: Send + Sync + 'static {
+    /// Publish `event`, returning once the broker has stored it.
+    ///
+    /// An event may be published again when the relay fails before marking
+    /// it as published; adapters pass its `event_id` to the broker, so the
+    /// duplicates can be dropped.
+    fn publish(
+        &self,
+        event: &ContentChangeEvent,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+}
+
+/// Connect to the broker configured in `settings` and relay the outbox to it
+/// in the background, as long as the service runs, syncing `search_index`
+/// and invalidating the cached responses of `response_cache` with the events.
+pub async fn start_outbox_relay<S: AppState>(
+    settings: &EventsSettings,
+    database: &'static Database,
+    response_cache: Option<ResponseCache>,
+    search_index: Option<SearchIndex<S>>,
+) -> anyhow::Result<()> {
+    let relay = OutboxRelaySettings {
+        interval: Duration::from_millis(settings.interval_milliseconds),
+        batch_size: settings.batch_size,
+        retention: (settings.retention_hours > 0)
+            .then(|| Duration::from_secs(settings.retention_hours * 60 * 60)),
+    };
+    match &settings.broker {
+        #[cfg(feature = "nats")]
+        Some(EventBrokerSettings::Nats {
+            url,
+            subject_prefix,
+        }) => {
+            let publisher = nats::NatsPublisher::connect(url, subject_prefix).await?;
+            spawn_outbox_relay(publisher, response_cache, search_index, database, relay);
+        }
+        #[cfg(feature = "kafka")]
+        Some(EventBrokerSettings::Kafka { brokers, topic }) => {
+            let publisher = kafka::KafkaPublisher::new(brokers, topic)?;
+            spawn_outbox_relay(publisher, response_cache, search_index, database, relay);
+        }
+        #[allow(unreachable_patterns)]
+        Some(broker) => anyhow::bail!(
+            "the service is built without support for the {} event broker",
+            broker.name()
+        ),
+        None => anyhow::bail!("events are enabled, but no event broker is configured"),
+    }
+    Ok(())
+}
+
+/// How the outbox is relayed
+#[derive(Debug, Clone, Copy)]
+pub struct OutboxRelaySettings {
+    /// how often the outbox is looked up for events to publish
+    pub interval: Duration,
+    /// most events published per lookup
+    pub batch_size: u64,
+    /// how long published events are kept, forever without one
+    pub retention: Option<Duration>,
+}
+
+/// Publish the events of the outbox every `interval`, as long as the service
+/// runs; a full batch is followed by the next one right away. Published
+/// events past their retention are removed hourly.
+pub async fn run_outbox_relay<P: EventPublisher>(
+    publisher: P,
+    database: &'static Database,
+    settings: OutboxRelaySettings,
+) {
+    let batch_size = settings.batch_size;
+    let mut ticker = tokio::time::interval(settings.interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut next_prune = Instant::now();
+    loop {
+        ticker.tick().await;
+        let prune = settings.retention.filter(|_| Instant::now() >= next_prune);
+        if prune.is_some() {
+            next_prune = Instant::now() + PRUNE_INTERVAL;
+        }
+        // the outbox of every tenant in turn
+        for tenant in database.tenant_scopes() {
+            database::with_tenant(tenant, async {
+                loop {
+                    match relay_pending_events(&publisher, database, batch_size).await {
+                        Ok(Some(published)) if published as u64 == batch_size => continue,
+                        Ok(_) => {}
+                        Err(e) => {
+                            tracing::warn!("Publishing content change events failed: {:#}", e)
+                        }
+                    }
+                    break;
+                }
+                if let Some(retention) = prune
+                    && let Err(e) = prune_published_events(database, retention).await
+                {
+                    tracing::warn!("Removing published events failed: {:#}", e);
+                }
+            })
+            .await;
+        }
+    }
+}
+
+/// Remove the events published longer than `retention` ago from the outbox,
+/// returning how many.
+pub async fn prune_published_events(
+    database: &Database,
+    retention: Duration,
+) -> anyhow::Result<u64> {
+    let (sql, values) = delete_published_events(retention.as_secs());
+    let mut transaction = database.begin(StatementKind::Write).await?;
+    let removed = sqlx::query_with(AssertSqlSafe(sql), values)
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+    transaction.commit().await?;
+    Ok(removed)
+}
+
+/// Publish up to `batch_size` events of the outbox, oldest first, unless
+/// another replica is relaying the outbox.
+///
+/// The events are marked as published in the transaction which read them,
+/// once the broker has stored them: an event is published at least once,
+/// and only again after a failure between the broker and the commit.
+///
+/// The events of a document are published in their order: once one fails,
+/// the later events of its document are held back until it is published,
+/// while those of the other documents go on. The first failure is returned,
+/// after the published events are marked.
+///
+/// Returns the number of events published, or `None` when another replica
+/// is relaying the outbox.
+pub async fn relay_pending_events<P: EventPublisher>(
+    publisher: &P,
+    database: &Database,
+    batch_size: u64,
+) -> anyhow::Result<Option<usize>> {
+    let mut transaction = database.begin(StatementKind::Write).await?;
+    // released with the end of the transaction
+    let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_xact_lock($1)")
+        .bind(OUTBOX_RELAY_LOCK_KEY)
+        .fetch_one(&mut *transaction)
+        .await?;
+    if !locked {
+        return Ok(None);
+    }
+
+    let (sql, values) = query_pending_events(batch_size);
+    let rows = sqlx::query_with(AssertSqlSafe(sql), values)
+        .fetch_all(&mut *transaction)
+        .await?;
+
+    let mut published = Vec::new();
+    let mut held_back = HashSet::new();
+    let mut failure = None;
+    for row in &rows {
+        let document = match document_of(row) {
+            Ok(document) => document,
+            Err(e) => {
+                failure = Some(e);
+                break;
+            }
+        };
+        // later events of the document wait for the failed one
+        if held_back.contains(&document) {
+            continue;
+        }
+        let result = match row_to_event(row) {
+            Ok(event) => publisher
+                .publish(&event)
+                .await
+                .map(|()| event.event_id)
+                .map_err(|e| e.context(format!("failed to publish event {}", event.event_id))),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(event_id) => published.push(event_id),
+            Err(e) => {
+                held_back.insert(document);
+                failure.get_or_insert(e);
+            }
+        }
+    }
+
+    let count = published.len();
+    if !published.is_empty() {
+        let (sql, values) = mark_events_published(published);
+        sqlx::query_with(AssertSqlSafe(sql), values)
+            .execute(&mut *transaction)
+            .await?;
+    }
+    transaction.commit().await?;
+
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(Some(count)),
+    }
+}
+
+/// Relay the outbox to `publisher` in the background, syncing `search_index`
+/// and invalidating the cached responses of `response_cache` with the events.
+fn spawn_outbox_relay<P: EventPublisher, S: AppState>(
+    publisher: P,
+    response_cache: Option<ResponseCache>,
+    search_index: Option<SearchIndex<S>>,
+    database: &'static Database,
+    settings: OutboxRelaySettings,
+) {
+    match search_index {
+        Some(index) => spawn_invalidating_relay(
+            index.indexing(publisher),
+            response_cache,
+            database,
+            settings,
+        ),
+        None => spawn_invalidating_relay(publisher, response_cache, database, settings),
+    }
+}
+
+fn spawn_invalidating_relay<P: EventPublisher>(
+    publisher: P,
+    response_cache: Option<ResponseCache>,
+    database: &'static Database,
+    settings: OutboxRelaySettings,
+) {
+    match response_cache {
+        Some(cache) => tokio::spawn(run_outbox_relay(
+            cache.invalidating(publisher),
+            database,
+            settings,
+        )),
+        None => tokio::spawn(run_outbox_relay(publisher, database, settings)),
+    };
+}
+
+/// Type and id of the document of an event of the outbox.
+fn document_of(row: &PgRow) -> anyhow::Result<(String, Uuid)> {
+    Ok((
+        row.try_get("document_type")?,
+        row.try_get(DOCUMENT_ID_FIELD_NAME)?,
+    ))
+}
+
+fn row_to_event(row: &PgRow) -> anyhow::Result<ContentChangeEvent> {
+    let action: String = row.try_get("action")?;
+    let event_id: i64 = row.try_get("event_id")?;
+    Ok(ContentChangeEvent {
+        event_id,
+        document_type: row.try_get("document_type")?,
+        document_id: row.try_get::<Uuid, _>(DOCUMENT_ID_FIELD_NAME)?.to_string(),
+        action: action
+            .parse()
+            .map_err(anyhow::Error::msg)
+            .with_context(|| format!("failed to read event {event_id} from the outbox"))?,
+        actor: row.try_get("actor")?,
+        timestamp: row.try_get::<DateTime<Utc>, _>("occurred_at")?,
+        diff: row.try_get::<Json<Value>, _>("diff")?.0,
+        tenant: database::current_tenant(),
+    })
+}
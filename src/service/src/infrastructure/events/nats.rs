@@ -0,0 +1,47 @@
+use anyhow::Context;
+use async_nats::HeaderMap;
+use async_nats::header::NATS_MESSAGE_ID;
+use async_nats::jetstream;
+
+use crate::domain::events::ContentChangeEvent;
+use crate::infrastructure::events::EventPublisher;
+
+/// Publishes the content change events to NATS JetStream.
+///
+/// The event id is sent as the message id, so JetStream drops an event
+/// published again within the duplicate window of the stream.
+pub struct NatsPublisher {
+    jetstream: jetstream::Context,
+    subject_prefix: String,
+}
+
+impl NatsPublisher {
+    pub async fn connect(url: &str, subject_prefix: impl Into<String>) -> anyhow::Result<Self> {
+        let client = async_nats::connect(url)
+            .await
+            .with_context(|| format!("failed to connect to NATS at {url}"))?;
+        Ok(Self {
+            jetstream: jetstream::new(client),
+            subject_prefix: subject_prefix.into(),
+        })
+    }
+}
+
+impl EventPublisher for NatsPublisher {
+    async fn publish(&self, event: &ContentChangeEvent) -> anyhow::Result<()> {
+        let subject = format!(
+            "{}.{}.{}",
+            self.subject_prefix, event.document_type, event.action
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(NATS_MESSAGE_ID, event.event_id.to_string());
+        let payload = serde_json::to_vec(event)?;
+
+        // the second await waits for the stream to acknowledge the message
+        self.jetstream
+            .publish_with_headers(subject, headers, payload.into())
+            .await?
+            .await?;
+        Ok(())
+    }
+}
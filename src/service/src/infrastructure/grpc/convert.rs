@@ -0,0 +1,95 @@
+use prost_types::value::Kind;
+use prost_types::{ListValue, Struct, Value};
+use serde_json::{Map, Number, Value as JsonValue};
+
+/// JSON object of a protobuf `Struct`.
+///
+/// Protobuf has one number type; integral numbers become JSON integers, so
+/// they are accepted by integer fields.
+pub fn struct_to_json(value: Struct) -> Map<String, JsonValue> {
+    value
+        .fields
+        .into_iter()
+        .map(|(key, value)| (key, value_to_json(value)))
+        .collect()
+}
+
+/// Protobuf `Struct` of a JSON object.
+pub fn json_to_struct(value: Map<String, JsonValue>) -> Struct {
+    Struct {
+        fields: value
+            .into_iter()
+            .map(|(key, value)| (key, json_to_value(value)))
+            .collect(),
+    }
+}
+
+fn value_to_json(value: Value) -> JsonValue {
+    match value.kind {
+        None | Some(Kind::NullValue(_)) => JsonValue::Null,
+        Some(Kind::NumberValue(number)) => number_to_json(number),
+        Some(Kind::StringValue(string)) => JsonValue::String(string),
+        Some(Kind::BoolValue(boolean)) => JsonValue::Bool(boolean),
+        Some(Kind::StructValue(object)) => JsonValue::Object(struct_to_json(object)),
+        Some(Kind::ListValue(list)) => {
+            JsonValue::Array(list.values.into_iter().map(value_to_json).collect())
+        }
+    }
+}
+
+fn number_to_json(number: f64) -> JsonValue {
+    // beyond 2^53 integral doubles aren't exact integers anymore
+    const MAX_EXACT_INTEGER: f64 = 9_007_199_254_740_992.0;
+    if number.fract() == 0.0 && number.abs() <= MAX_EXACT_INTEGER {
+        JsonValue::from(number as i64)
+    } else {
+        Number::from_f64(number)
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null)
+    }
+}
+
+fn json_to_value(value: JsonValue) -> Value {
+    let kind = match value {
+        JsonValue::Null => Kind::NullValue(0),
+        JsonValue::Bool(boolean) => Kind::BoolValue(boolean),
+        JsonValue::Number(number) => Kind::NumberValue(number.as_f64().unwrap_or_default()),
+        JsonValue::String(string) => Kind::StringValue(string),
+        JsonValue::Array(values) => Kind::ListValue(ListValue {
+            values: values.into_iter().map(json_to_value).collect(),
+        }),
+        JsonValue::Object(object) => Kind::StructValue(json_to_struct(object)),
+    };
+    Value { kind: Some(kind) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_struct_round_trips_through_json() {
+        let object = json!({
+            "uid": "brand",
+            "priority": 3,
+            "ratio": 0.5,
+            "active": true,
+            "logo": null,
+            "name": {"en": "Brand"},
+            "tags": ["a", "b"],
+        });
+
+        let round_trip = struct_to_json(json_to_struct(object.as_object().unwrap().clone()));
+
+        assert_eq!(JsonValue::Object(round_trip), object);
+    }
+
+    #[test]
+    fn test_integral_numbers_become_integers() {
+        assert_eq!(number_to_json(42.0), json!(42));
+        assert_eq!(number_to_json(-1.0), json!(-1));
+        assert_eq!(number_to_json(1.5), json!(1.5));
+        assert_eq!(number_to_json(1e300), json!(1e300));
+    }
+}
@@ -0,0 +1,78 @@
+use std::net::SocketAddr;
+
+use anyhow::Context;
+use serde::Deserialize;
+use tokio::net;
+use tonic::transport::Server;
+use tonic::transport::server::TcpIncoming;
+
+use crate::application::AppState;
+
+pub mod convert;
+mod service;
+
+pub use service::DocumentsGrpcService;
+
+/// Code generated from `proto/luminair/documents/v1/documents.proto`.
+pub mod proto {
+    tonic::include_proto!("luminair.documents.v1");
+}
+
+/// Settings of the gRPC server
+#[derive(Debug, Clone, Deserialize)]
+pub struct GrpcSettings {
+    /// serve the gRPC API next to the HTTP API
+    #[serde(default)]
+    pub enabled: bool,
+    /// port of the gRPC server, which is separate from the HTTP server
+    #[serde(default = "default_port")]
+    pub port: u16,
+}
+
+impl Default for GrpcSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_port(),
+        }
+    }
+}
+
+fn default_port() -> u16 {
+    50051
+}
+
+/// The gRPC server of the documents, for services preferring gRPC over REST.
+pub struct GrpcServer {
+    router: tonic::transport::server::Router,
+    listener: net::TcpListener,
+}
+
+impl GrpcServer {
+    /// Returns a new gRPC server bound to `port`.
+    pub async fn new<S: AppState>(state: S, port: u16) -> anyhow::Result<Self> {
+        let router = Server::builder().add_service(proto::documents_server::DocumentsServer::new(
+            DocumentsGrpcService::new(state),
+        ));
+        let listener = net::TcpListener::bind(format!("0.0.0.0:{port}"))
+            .await
+            .with_context(|| format!("failed to listen on {port}"))?;
+
+        Ok(Self { router, listener })
+    }
+
+    /// Address the server listens on.
+    pub fn local_addr(&self) -> anyhow::Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Runs the gRPC server.
+    pub async fn run(self) -> anyhow::Result<()> {
+        tracing::debug!("gRPC listening on {:?}", self.listener.local_addr());
+        self.router
+            .serve_with_incoming(TcpIncoming::from(self.listener))
+            .await
+            .context("received error from running gRPC server")?;
+        Ok(())
+    }
+}
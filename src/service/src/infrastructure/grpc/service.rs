@@ -0,0 +1,432 @@
+use chrono::{DateTime, Utc};
+use luminair_common::entities::{ApiOperation, LocalizationId};
+use luminair_common::{AttributeId, DocumentType, DocumentTypeApiId, DocumentTypesRegistry};
+use serde_json::{Map, Value as JsonValue};
+use std::collections::HashMap;
+use std::str::FromStr;
+use tonic::{Request, Response, Status};
+
+use crate::application::AppState;
+use crate::application::commands::{
+    CreateDocumentWithRelationsCommand, DeleteDocumentCommand, FindByIdCommand,
+    FindDocumentsCommand, PublishDocumentCommand, RelationOperation,
+    UpdateDocumentWithRelationsCommand,
+};
+use crate::application::service::DocumentsService;
+use crate::domain::actor::ActorContext;
+use crate::domain::document::content::ContentValue;
+use crate::domain::document::lifecycle::PublicationSchedule;
+use crate::domain::document::{DocumentInstance, DocumentInstanceId};
+use crate::domain::query::{DocumentInstanceQuery, DocumentStatus};
+use crate::infrastructure::grpc::convert::{json_to_struct, struct_to_json};
+use crate::infrastructure::grpc::proto::documents_server::Documents;
+use crate::infrastructure::grpc::proto::{
+    CreateRequest, DeleteRequest, DeleteResponse, Document, FindRequest, FindResponse, GetRequest,
+    PageMeta, PublishRequest, PublishResponse, Status as StatusArgument, UpdateRequest,
+};
+use crate::infrastructure::http::actor::parse_actor;
+use crate::infrastructure::http::api::ApiError;
+use crate::infrastructure::http::handlers::content::query_params::{DocumentQuery, parse_query};
+use crate::infrastructure::http::handlers::content::request_body::{
+    build_fields_from_map, classify_document_data, parse_relation_operations, supported_locale,
+};
+use crate::infrastructure::http::handlers::content::response::DocumentInstanceResponse;
+
+/// The `Documents` gRPC service, backed by the documents service of the
+/// application state.
+pub struct DocumentsGrpcService<S: AppState> {
+    state: S,
+}
+
+impl<S: AppState> DocumentsGrpcService<S> {
+    pub fn new(state: S) -> Self {
+        Self { state }
+    }
+}
+
+#[tonic::async_trait]
+impl<S: AppState> Documents for DocumentsGrpcService<S> {
+    async fn find(&self, request: Request<FindRequest>) -> Result<Response<FindResponse>, Status> {
+        let request = request.into_inner();
+        let document_type = self.document_type(&request.document_type, ApiOperation::Find)?;
+        let status = request.status();
+
+        let mut query_map = Map::new();
+        if let Some(filters) = request.filters {
+            query_map.insert(
+                "filters".to_string(),
+                JsonValue::Object(struct_to_json(filters)),
+            );
+        }
+        if !request.sort.is_empty() {
+            query_map.insert("sort".to_string(), request.sort.join(",").into());
+        }
+        let mut pagination = Map::new();
+        if request.page > 0 {
+            pagination.insert("page".to_string(), request.page.into());
+        }
+        if request.page_size > 0 {
+            pagination.insert("pageSize".to_string(), request.page_size.into());
+        }
+        query_map.insert("pagination".to_string(), JsonValue::Object(pagination));
+        let q = self.document_query(query_map, document_type, status, &request.populate)?;
+
+        let (page, page_size) = q.pagination;
+        let mut query = DocumentInstanceQuery::new()
+            .paginate(page, page_size)
+            .with_status(q.status)
+            .with_filter(q.filter);
+        query.sort = q.sorts;
+        query.random_order = q.random_order;
+        query.sample = q.sample;
+        let cmd = FindDocumentsCommand {
+            document_type,
+            populate: q.populate,
+            populate_filters: q.populate_filters,
+            populate_options: q.populate_options,
+            populate_strategy: q.populate_strategy,
+            with_count: Vec::new(),
+            query,
+        };
+        let (documents, total) = self
+            .state
+            .documents_service()
+            .find(cmd)
+            .await
+            .map_err(|e| grpc_status(e.into()))?;
+
+        Ok(Response::new(FindResponse {
+            documents: documents
+                .into_iter()
+                .map(|document| {
+                    document_message(document, document_type, self.state.document_types())
+                })
+                .collect::<Result<_, _>>()?,
+            meta: Some(PageMeta {
+                page: page.into(),
+                page_size: page_size.into(),
+                total,
+            }),
+        }))
+    }
+
+    async fn get(&self, request: Request<GetRequest>) -> Result<Response<Document>, Status> {
+        let request = request.into_inner();
+        let document_type = self.document_type(&request.document_type, ApiOperation::FindOne)?;
+        let document_instance_id = document_id(&request.document_id)?;
+        let q = self.document_query(
+            Map::new(),
+            document_type,
+            request.status(),
+            &request.populate,
+        )?;
+
+        let cmd = FindByIdCommand {
+            document_type,
+            document_instance_id,
+            populate: q.populate,
+            populate_filters: q.populate_filters,
+            populate_options: q.populate_options,
+            query: DocumentInstanceQuery::new().with_status(q.status),
+        };
+        let document = self
+            .state
+            .documents_service()
+            .find_by_id(cmd)
+            .await
+            .map_err(|e| grpc_status(e.into()))?
+            .ok_or_else(|| {
+                Status::not_found(format!(
+                    "Document instance with ID '{}' not found",
+                    request.document_id
+                ))
+            })?;
+
+        document_message(document, document_type, self.state.document_types()).map(Response::new)
+    }
+
+    async fn create(&self, request: Request<CreateRequest>) -> Result<Response<Document>, Status> {
+        let actor = request_actor(&request)?;
+        let request = request.into_inner();
+        let document_type = self.document_type(&request.document_type, ApiOperation::Create)?;
+        let locale = request_locale(document_type, request.locale.as_deref())?;
+        let (fields, relation_operations) =
+            document_data(document_type, request.data, locale.as_ref())?;
+
+        let cmd = CreateDocumentWithRelationsCommand {
+            document_type,
+            fields,
+            relation_operations,
+            actor,
+        };
+        let document_id = self
+            .state
+            .documents_service()
+            .create_with_relations(cmd)
+            .await
+            .map_err(|e| grpc_status(e.into()))?;
+
+        self.written_document(document_type, document_id, DocumentStatus::Draft)
+            .await?
+            .ok_or_else(|| Status::internal("The created document can't be read"))
+            .map(Response::new)
+    }
+
+    async fn update(&self, request: Request<UpdateRequest>) -> Result<Response<Document>, Status> {
+        let actor = request_actor(&request)?;
+        let request = request.into_inner();
+        let document_type = self.document_type(&request.document_type, ApiOperation::Update)?;
+        let document_id = document_id(&request.document_id)?;
+        let locale = request_locale(document_type, request.locale.as_deref())?;
+        let (fields, relation_operations) =
+            document_data(document_type, request.data, locale.as_ref())?;
+
+        let cmd = UpdateDocumentWithRelationsCommand {
+            document_type,
+            document_id,
+            fields,
+            relation_operations,
+            locale,
+            expected_version: request.expected_version,
+            actor,
+        };
+        self.state
+            .documents_service()
+            .update_with_relations(cmd)
+            .await
+            .map_err(|e| grpc_status(e.into()))?;
+
+        self.written_document(document_type, document_id, DocumentStatus::Draft)
+            .await?
+            .ok_or_else(|| Status::internal("The updated document can't be read"))
+            .map(Response::new)
+    }
+
+    async fn delete(
+        &self,
+        request: Request<DeleteRequest>,
+    ) -> Result<Response<DeleteResponse>, Status> {
+        let actor = request_actor(&request)?;
+        let request = request.into_inner();
+        let document_type = self.document_type(&request.document_type, ApiOperation::Delete)?;
+        let document_instance_id = document_id(&request.document_id)?;
+
+        let cmd = DeleteDocumentCommand {
+            document_type,
+            document_instance_id,
+            actor,
+        };
+        self.state
+            .documents_service()
+            .delete(cmd)
+            .await
+            .map_err(|e| grpc_status(e.into()))?;
+
+        Ok(Response::new(DeleteResponse {}))
+    }
+
+    async fn publish(
+        &self,
+        request: Request<PublishRequest>,
+    ) -> Result<Response<PublishResponse>, Status> {
+        let actor = request_actor(&request)?;
+        let request = request.into_inner();
+        let document_type = self.document_type(&request.document_type, ApiOperation::Publish)?;
+        let document_id = document_id(&request.document_id)?;
+        let schedule = PublicationSchedule {
+            publish_at: request.publish_at.map(timestamp).transpose()?,
+            unpublish_at: request.unpublish_at.map(timestamp).transpose()?,
+        };
+
+        let cmd = PublishDocumentCommand {
+            document_type,
+            document_id,
+            expected_version: request.expected_version,
+            schedule,
+            actor,
+        };
+        self.state
+            .documents_service()
+            .publish(cmd)
+            .await
+            .map_err(|e| grpc_status(e.into()))?;
+
+        let document = self
+            .written_document(document_type, document_id, DocumentStatus::Published)
+            .await?;
+        Ok(Response::new(PublishResponse { document }))
+    }
+}
+
+impl<S: AppState> DocumentsGrpcService<S> {
+    /// Document type of `api_type`, which must expose `operation`.
+    fn document_type(
+        &self,
+        api_type: &str,
+        operation: ApiOperation,
+    ) -> Result<&'static DocumentType, Status> {
+        let api_id = DocumentTypeApiId::from_str(api_type)
+            .map_err(|_| Status::invalid_argument(format!("Invalid document type: {api_type}")))?;
+        let document_type =
+            self.state.document_types().lookup(&api_id).ok_or_else(|| {
+                Status::not_found(format!("Document type '{api_type}' not found"))
+            })?;
+        if !document_type.exposes(operation) {
+            return Err(Status::unimplemented(format!(
+                "Document type '{api_type}' doesn't expose the '{}' operation",
+                operation.as_str()
+            )));
+        }
+        Ok(document_type)
+    }
+
+    /// Query of a request, validated as the query parameters of the REST API.
+    fn document_query(
+        &self,
+        mut query_map: Map<String, JsonValue>,
+        document_type: &DocumentType,
+        status: StatusArgument,
+        populate: &[String],
+    ) -> Result<DocumentQuery, Status> {
+        let status = match status {
+            StatusArgument::Unspecified => None,
+            StatusArgument::Draft => Some("draft"),
+            StatusArgument::Published => Some("published"),
+        };
+        if let Some(status) = status {
+            query_map.insert("status".to_string(), status.into());
+        }
+        if !populate.is_empty() {
+            query_map.insert("populate".to_string(), populate.into());
+        }
+
+        parse_query(
+            &query_map,
+            document_type,
+            self.state.document_types(),
+            &self.state.pagination_settings(),
+            &self.state.query_limits(),
+        )
+        .map_err(grpc_status)
+    }
+
+    /// The document written by a request, read in `status`.
+    async fn written_document(
+        &self,
+        document_type: &'static DocumentType,
+        document_instance_id: DocumentInstanceId,
+        status: DocumentStatus,
+    ) -> Result<Option<Document>, Status> {
+        let cmd = FindByIdCommand {
+            document_type,
+            document_instance_id,
+            populate: None,
+            populate_filters: None,
+            populate_options: HashMap::new(),
+            query: DocumentInstanceQuery::new().with_status(status),
+        };
+        self.state
+            .documents_service()
+            .find_by_id(cmd)
+            .await
+            .map_err(|e| grpc_status(e.into()))?
+            .map(|document| document_message(document, document_type, self.state.document_types()))
+            .transpose()
+    }
+}
+
+/// Message of a document, with the JSON of the REST API as `data`.
+fn document_message(
+    document: DocumentInstance,
+    document_type: &DocumentType,
+    registry: &dyn DocumentTypesRegistry,
+) -> Result<Document, Status> {
+    let response = DocumentInstanceResponse::new(document, document_type, registry);
+    let document_id = response.document_id.clone();
+    let status = response.status.clone();
+    let version = response.audit.version;
+    let data = match serde_json::to_value(response) {
+        Ok(JsonValue::Object(data)) => data,
+        Ok(_) => Map::new(),
+        Err(e) => return Err(grpc_status(ApiError::InternalServerError(e.to_string()))),
+    };
+
+    Ok(Document {
+        document_id,
+        status,
+        version,
+        data: Some(json_to_struct(data)),
+    })
+}
+
+fn request_locale(
+    document_type: &DocumentType,
+    locale: Option<&str>,
+) -> Result<Option<LocalizationId>, Status> {
+    locale
+        .map(|locale| supported_locale(locale, document_type))
+        .transpose()
+        .map_err(grpc_status)
+}
+
+/// Field values and relation operations of a write.
+type DocumentData = (
+    HashMap<AttributeId, ContentValue>,
+    HashMap<AttributeId, RelationOperation>,
+);
+
+/// Fields and relation operations of the `data` of a write, validated as the
+/// `data` of a REST request.
+fn document_data(
+    document_type: &DocumentType,
+    data: Option<prost_types::Struct>,
+    locale: Option<&LocalizationId>,
+) -> Result<DocumentData, Status> {
+    let data = struct_to_json(data.unwrap_or_default());
+    let classified = classify_document_data(&data, document_type).map_err(grpc_status)?;
+    let fields = build_fields_from_map(document_type, &classified.fields, locale)
+        .map_err(|e| grpc_status(e.into()))?;
+    let relation_operations =
+        parse_relation_operations(&classified.relations).map_err(grpc_status)?;
+    Ok((fields, relation_operations))
+}
+
+/// Who `request` acts for, from its metadata, which carries the headers of
+/// the HTTP/2 request.
+fn request_actor<T>(request: &Request<T>) -> Result<ActorContext, Status> {
+    let headers = request.metadata().clone().into_headers();
+    let peer = request.remote_addr().map(|address| address.ip());
+    parse_actor(&headers, peer).map_err(grpc_status)
+}
+
+fn document_id(id: &str) -> Result<DocumentInstanceId, Status> {
+    DocumentInstanceId::try_from(id)
+        .map_err(|_| Status::invalid_argument(format!("Invalid document id: {id}")))
+}
+
+fn timestamp(value: prost_types::Timestamp) -> Result<DateTime<Utc>, Status> {
+    u32::try_from(value.nanos)
+        .ok()
+        .and_then(|nanos| DateTime::from_timestamp(value.seconds, nanos))
+        .ok_or_else(|| Status::invalid_argument(format!("Invalid timestamp: {value}")))
+}
+
+/// gRPC status of an API error; internal errors are logged, not shown.
+fn grpc_status(error: ApiError) -> Status {
+    match error {
+        ApiError::InternalServerError(msg) => {
+            tracing::error!("{}", msg);
+            Status::internal("An internal server error occurred")
+        }
+        ApiError::UnprocessableEntity(msg) => Status::invalid_argument(msg),
+        ApiError::InvalidFields { detail, .. } => Status::invalid_argument(detail),
+        ApiError::ConflictWithServerState(msg) | ApiError::Referenced { detail: msg, .. } => {
+            Status::aborted(msg)
+        }
+        ApiError::PreconditionFailed(msg) => Status::failed_precondition(msg),
+        ApiError::NotFound(msg) => Status::not_found(msg),
+        ApiError::Forbidden(msg) => Status::permission_denied(msg),
+        ApiError::MethodNotAllowed(msg) => Status::unimplemented(msg),
+        ApiError::NotAcceptable(msg) => Status::invalid_argument(msg),
+    }
+}
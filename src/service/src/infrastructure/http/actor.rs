@@ -0,0 +1,123 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::HeaderMap;
+use axum::http::request::Parts;
+
+use crate::domain::actor::ActorContext;
+use crate::domain::document::lifecycle::UserId;
+use crate::infrastructure::http::api::ApiError;
+
+/// Header with the id of the user making the request.
+pub const USER_ID_HEADER: &str = "x-user-id";
+
+/// Header with the comma separated roles of the user making the request.
+pub const USER_ROLES_HEADER: &str = "x-user-roles";
+
+/// Header with the id of the token the user was authenticated with.
+pub const TOKEN_ID_HEADER: &str = "x-token-id";
+
+/// Header with the addresses the request was forwarded for, each proxy
+/// appending the one it received the request from.
+pub const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// Who the request acts for, taken once from the request by [`parse_actor`],
+/// for the handlers to pass along with their commands.
+#[derive(Debug, Clone, Default)]
+pub struct Actor(pub ActorContext);
+
+impl<S> FromRequestParts<S> for Actor
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| address.ip());
+        parse_actor(&parts.headers, peer).map(Actor)
+    }
+}
+
+/// Parse who the request acts for: the user, their roles and token, and the
+/// address of the client, the last one it was forwarded for or else `peer`,
+/// the one it was received from.
+///
+/// The service doesn't authenticate users itself: the headers are set by the
+/// gateway in front of it, and a request without them is anonymous. Only the
+/// last forwarded address is appended by the gateway, the ones before it are
+/// sent by the client and can be anything.
+pub fn parse_actor(headers: &HeaderMap, peer: Option<IpAddr>) -> Result<ActorContext, ApiError> {
+    let header = |name: &str| -> Result<Option<&str>, ApiError> {
+        headers
+            .get(name)
+            .map(|value| {
+                value
+                    .to_str()
+                    .map_err(|_| ApiError::UnprocessableEntity(format!("Invalid {} header", name)))
+            })
+            .transpose()
+    };
+
+    let user_id = header(USER_ID_HEADER)?.and_then(|id| UserId::try_new(id.to_string()).ok());
+    let roles = header(USER_ROLES_HEADER)?
+        .map(|roles| {
+            roles
+                .split(',')
+                .map(str::trim)
+                .filter(|role| !role.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    let token_id = header(TOKEN_ID_HEADER)?
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(String::from);
+    let forwarded_for = header(FORWARDED_FOR_HEADER)?
+        .and_then(|addresses| addresses.rsplit(',').next())
+        .and_then(|address| address.trim().parse().ok());
+    Ok(ActorContext {
+        user_id,
+        token_id,
+        roles,
+        ip: forwarded_for.or(peer),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_parse_actor() {
+        let actor = parse_actor(&HeaderMap::new(), None).unwrap();
+        assert_eq!(actor, ActorContext::anonymous());
+
+        let peer = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_ID_HEADER, "alice".parse().unwrap());
+        headers.insert(USER_ROLES_HEADER, "author, editor,".parse().unwrap());
+        headers.insert(TOKEN_ID_HEADER, "ci".parse().unwrap());
+        let actor = parse_actor(&headers, Some(peer)).unwrap();
+        assert_eq!(actor.user_id.unwrap().as_ref(), "alice");
+        assert_eq!(actor.roles, vec!["author", "editor"]);
+        assert_eq!(actor.token_id.as_deref(), Some("ci"));
+        assert_eq!(actor.ip, Some(peer));
+
+        // the first address is the client's own claim
+        headers.insert(
+            FORWARDED_FOR_HEADER,
+            "198.51.100.9, 203.0.113.7".parse().unwrap(),
+        );
+        let actor = parse_actor(&headers, Some(peer)).unwrap();
+        assert_eq!(actor.ip, Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7))));
+
+        headers.insert(FORWARDED_FOR_HEADER, "unknown".parse().unwrap());
+        let actor = parse_actor(&headers, Some(peer)).unwrap();
+        assert_eq!(actor.ip, Some(peer));
+    }
+}
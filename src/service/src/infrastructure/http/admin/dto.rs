@@ -0,0 +1,615 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use luminair_common::entities::{DocumentKind, FieldType, RelationType};
+use luminair_common::{AttributeId, DocumentType, DocumentTypesRegistry};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::domain::document::DocumentInstance;
+use crate::domain::subject::{Erasure, SubjectDocument, SubjectReference};
+use crate::domain::translation::{LocaleTranslationCounts, TranslationState, TranslationStatus};
+use crate::infrastructure::http::handlers::content::response::{
+    DocumentInstanceResponse, MetadataResponse, attribute_key,
+};
+use crate::infrastructure::jobs::{Job, JobStatus};
+
+/// Content type with the hints the admin UI renders its forms and lists with
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentTypeResponse {
+    id: String,
+    /// plural name, which the routes take
+    api_id: String,
+    title: String,
+    #[serde(rename = "type")]
+    kind: DocumentKind,
+    description: Option<String>,
+    /// field naming the entries in lists and relation pickers
+    display_field: Option<String>,
+    draft_and_publish: bool,
+    localizations: Vec<String>,
+    attributes: Vec<AttributeHintResponse>,
+}
+
+impl ContentTypeResponse {
+    pub fn new(document_type: &DocumentType, registry: &dyn DocumentTypesRegistry) -> Self {
+        let fields = document_type.ordered_fields();
+        let texts = fields
+            .iter()
+            .filter(|field| is_text(field.field_type))
+            .collect::<Vec<_>>();
+        let display_field = texts
+            .iter()
+            .find(|field| field.required)
+            .or(texts.first())
+            .map(|field| field.id.as_ref().to_string());
+
+        let mut attributes = fields
+            .iter()
+            .map(|field| AttributeHintResponse {
+                id: field.id.as_ref().to_string(),
+                label: label(&field.id),
+                widget: widget(field.field_type),
+                required: field.required,
+                unique: field.unique,
+                target: None,
+                multiple: None,
+                editable: true,
+            })
+            .collect::<Vec<_>>();
+
+        let mut relations = document_type.relations.iter().collect::<Vec<_>>();
+        relations.sort_by(|a, b| a.id.cmp(&b.id));
+        attributes.extend(relations.into_iter().map(|relation| {
+            let target = registry
+                .get(&relation.target)
+                .map(|target| target.info.plural_name.as_ref())
+                .unwrap_or(relation.target.as_ref());
+            AttributeHintResponse {
+                id: relation.id.as_ref().to_string(),
+                label: label(&relation.id),
+                widget: "relation",
+                required: false,
+                unique: false,
+                target: Some(target.to_string()),
+                multiple: Some(matches!(
+                    relation.relation_type,
+                    RelationType::HasMany | RelationType::BelongsToMany
+                )),
+                // the links of inverse relations are edited on the owning side
+                editable: relation.relation_type.is_owning(),
+            }
+        }));
+
+        let options = document_type.options.as_ref();
+        Self {
+            id: document_type.id.as_ref().to_string(),
+            api_id: document_type.info.plural_name.as_ref().to_string(),
+            title: document_type.info.title.as_ref().to_string(),
+            kind: document_type.kind,
+            description: document_type.info.description.clone(),
+            display_field,
+            draft_and_publish: document_type.has_draft_and_publish(),
+            localizations: options
+                .map(|options| {
+                    options
+                        .localizations
+                        .iter()
+                        .map(|locale| locale.to_string())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            attributes,
+        }
+    }
+}
+
+/// Attribute of a content type, with the input the admin UI edits it with
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttributeHintResponse {
+    id: String,
+    label: String,
+    widget: &'static str,
+    required: bool,
+    unique: bool,
+    /// api id of the related content type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
+    /// whether the relation links many documents
+    #[serde(skip_serializing_if = "Option::is_none")]
+    multiple: Option<bool>,
+    editable: bool,
+}
+
+/// Page of entries, with the number of documents every relation links
+#[derive(Debug, Clone, Serialize)]
+pub struct EntriesResponse {
+    pub data: Vec<EntryResponse>,
+    pub meta: MetadataResponse,
+}
+
+impl EntriesResponse {
+    pub fn new(
+        documents: Vec<DocumentInstance>,
+        document_type: &DocumentType,
+        registry: &dyn DocumentTypesRegistry,
+        relations: &[AttributeId],
+        page: u16,
+        page_size: u16,
+        total: u64,
+    ) -> Self {
+        Self {
+            data: documents
+                .into_iter()
+                .map(|document| EntryResponse::new(document, document_type, registry, relations))
+                .collect(),
+            meta: MetadataResponse {
+                page,
+                page_size,
+                total,
+            },
+        }
+    }
+}
+
+/// The document of an entry, without its related documents, which are only
+/// counted
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryResponse {
+    #[serde(flatten)]
+    document: DocumentInstanceResponse,
+    relation_counts: BTreeMap<String, usize>,
+}
+
+impl EntryResponse {
+    fn new(
+        mut document: DocumentInstance,
+        document_type: &DocumentType,
+        registry: &dyn DocumentTypesRegistry,
+        relations: &[AttributeId],
+    ) -> Self {
+        let relation_counts = relations
+            .iter()
+            .map(|relation| {
+                let count = document.relations.get(relation).map_or(0, Vec::len);
+                (attribute_key(document_type, relation), count)
+            })
+            .collect();
+        document.relations.clear();
+        Self {
+            document: DocumentInstanceResponse::new(document, document_type, registry),
+            relation_counts,
+        }
+    }
+}
+
+/// `{"documentIds": ["..."]}`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkActionRequest {
+    pub document_ids: Vec<String>,
+}
+
+/// Outcome of a bulk action, which carries on past the documents it fails for
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BulkActionResponse {
+    pub data: BulkActionResultsResponse,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BulkActionResultsResponse {
+    pub succeeded: Vec<String>,
+    pub failed: Vec<FailedActionResponse>,
+}
+
+/// A document a bulk action failed for, with the `code` and `detail` of the
+/// error answered for it alone
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedActionResponse {
+    pub document_id: String,
+    pub code: &'static str,
+    pub detail: String,
+}
+
+/// First line of a content export, naming its format and carrying the schema
+/// the rows were stored with
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveHeaderResponse {
+    pub format: &'static str,
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub schema: Vec<&'static DocumentType>,
+}
+
+/// The first line of a content export to restore, of which the format is checked
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArchiveHeaderRequest {
+    pub format: String,
+    pub version: u32,
+}
+
+/// Rows a restore inserted, per table
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportResponse {
+    pub data: ImportResultsResponse,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportResultsResponse {
+    pub rows: BTreeMap<String, u64>,
+}
+
+/// `{"remove": true, "batchSize": 500}`, both optional: orphaned links are
+/// only counted by default
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanedLinksRequest {
+    #[serde(default)]
+    pub remove: bool,
+    #[serde(default)]
+    pub batch_size: Option<u64>,
+}
+
+/// Orphaned links found, or removed, per relation table
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanedLinksResponse {
+    pub data: OrphanedLinksResultsResponse,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanedLinksResultsResponse {
+    pub removed: bool,
+    pub links: BTreeMap<String, u64>,
+}
+
+/// `{"dryRun": false, "batchSize": 1000}`, both optional: expired documents
+/// are only counted by default
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionRequest {
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub batch_size: Option<u64>,
+}
+
+/// Expired documents found, or removed, per document type
+#[derive(Debug, Clone, Serialize)]
+pub struct RetentionResponse {
+    pub data: RetentionResultsResponse,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionResultsResponse {
+    pub dry_run: bool,
+    pub documents: BTreeMap<String, u64>,
+}
+
+/// `{"value": "jane@example.com"}`: the identifying value of a data subject,
+/// looked up in the personal fields of the documents
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubjectRequest {
+    pub value: String,
+}
+
+/// The documents holding the personal data of a data subject
+#[derive(Debug, Clone, Serialize)]
+pub struct SubjectExportResponse {
+    pub data: Vec<SubjectDocumentResponse>,
+}
+
+/// A document of a data subject, with everything stored of it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubjectDocumentResponse {
+    /// plural name of the content type
+    content_type: String,
+    document: DocumentInstanceResponse,
+    published: Option<DocumentInstanceResponse>,
+    revisions: Vec<DocumentInstanceResponse>,
+    references: Vec<SubjectReferenceResponse>,
+}
+
+impl SubjectDocumentResponse {
+    pub fn new(document: SubjectDocument, registry: &dyn DocumentTypesRegistry) -> Self {
+        let document_type = document.document_type;
+        let response = |instance| DocumentInstanceResponse::new(instance, document_type, registry);
+        Self {
+            content_type: document_type.info.plural_name.to_string(),
+            document: response(document.draft),
+            published: document.published.map(response),
+            revisions: document.revisions.into_iter().map(response).collect(),
+            references: document
+                .references
+                .into_iter()
+                .map(|reference| SubjectReferenceResponse::new(reference, registry))
+                .collect(),
+        }
+    }
+}
+
+/// The documents linking to a document of a data subject by one relation
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubjectReferenceResponse {
+    /// plural name of the content type declaring the relation
+    content_type: String,
+    relation: String,
+    documents: Vec<DocumentInstanceResponse>,
+}
+
+impl SubjectReferenceResponse {
+    fn new(reference: SubjectReference, registry: &dyn DocumentTypesRegistry) -> Self {
+        let document_type = reference.document_type;
+        Self {
+            content_type: document_type.info.plural_name.to_string(),
+            relation: attribute_key(document_type, &reference.relation),
+            documents: reference
+                .documents
+                .into_iter()
+                .map(|document| DocumentInstanceResponse::new(document, document_type, registry))
+                .collect(),
+        }
+    }
+}
+
+/// Audit record of an erasure
+#[derive(Debug, Clone, Serialize)]
+pub struct ErasureResponse {
+    pub data: ErasureDataResponse,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErasureDataResponse {
+    id: i64,
+    /// SHA-256 of the identifying value
+    subject: String,
+    /// ids of the erased documents per plural name of their content type
+    documents: BTreeMap<String, Vec<String>>,
+    erased_by: Option<String>,
+    erased_at: DateTime<Utc>,
+}
+
+impl From<Erasure> for ErasureDataResponse {
+    fn from(erasure: Erasure) -> Self {
+        Self {
+            id: erasure.id,
+            subject: erasure.subject,
+            documents: erasure
+                .documents
+                .into_iter()
+                .map(|subject| {
+                    let ids = subject
+                        .document_ids
+                        .iter()
+                        .map(|id| id.0.to_string())
+                        .collect();
+                    (subject.document_type.info.plural_name.to_string(), ids)
+                })
+                .collect(),
+            erased_by: erasure.erased_by,
+            erased_at: erasure.erased_at,
+        }
+    }
+}
+
+/// Translations of the entries of the localized content types
+#[derive(Debug, Clone, Serialize)]
+pub struct TranslationStatusResponse {
+    pub data: Vec<TranslationsResponse>,
+}
+
+/// Translations of a page of the entries of one content type, all of them
+/// counted per locale
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranslationsResponse {
+    /// plural name of the content type
+    content_type: String,
+    /// locale the others are translated from, the first of the type
+    source_locale: Option<String>,
+    locales: Vec<LocaleCountsResponse>,
+    entries: Vec<EntryTranslationsResponse>,
+    pagination: MetadataResponse,
+}
+
+/// Number of the entries of a content type in each state of their
+/// translation into one locale
+#[derive(Debug, Clone, Serialize)]
+pub struct LocaleCountsResponse {
+    locale: String,
+    translated: u64,
+    stale: u64,
+    missing: u64,
+}
+
+/// Translations of one entry
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryTranslationsResponse {
+    document_id: String,
+    locales: Vec<EntryLocaleResponse>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryLocaleResponse {
+    locale: String,
+    /// `translated`, `stale` or `missing`
+    status: &'static str,
+    /// when the texts of the locale last changed, unknown for texts written
+    /// before it was tracked
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl TranslationsResponse {
+    pub fn new(
+        document_type: &DocumentType,
+        status: TranslationStatus,
+        page: u16,
+        page_size: u16,
+    ) -> Self {
+        let total = status.total();
+        let entries = status
+            .documents
+            .iter()
+            .map(|translation| EntryTranslationsResponse {
+                document_id: translation.document_id.0.to_string(),
+                locales: translation
+                    .locales
+                    .iter()
+                    .map(|locale| EntryLocaleResponse {
+                        locale: locale.locale.clone(),
+                        status: match translation.state(locale) {
+                            TranslationState::Current => "translated",
+                            TranslationState::Stale => "stale",
+                            TranslationState::Missing => "missing",
+                        },
+                        updated_at: locale.updated_at,
+                    })
+                    .collect(),
+            })
+            .collect();
+        Self {
+            content_type: document_type.info.plural_name.to_string(),
+            source_locale: status.counts.first().map(|counts| counts.locale.clone()),
+            locales: status
+                .counts
+                .into_iter()
+                .map(LocaleCountsResponse::from)
+                .collect(),
+            entries,
+            pagination: MetadataResponse {
+                page,
+                page_size,
+                total,
+            },
+        }
+    }
+}
+
+impl From<LocaleTranslationCounts> for LocaleCountsResponse {
+    fn from(counts: LocaleTranslationCounts) -> Self {
+        Self {
+            locale: counts.locale,
+            translated: counts.translated,
+            stale: counts.stale,
+            missing: counts.missing,
+        }
+    }
+}
+
+/// `{"kind": "clean-orphaned-links", "payload": {...}, "runAt": "..."}`, the
+/// payload and the time to run at being optional
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnqueueJobRequest {
+    pub kind: String,
+    #[serde(default)]
+    pub payload: Value,
+    #[serde(default)]
+    pub run_at: Option<DateTime<Utc>>,
+}
+
+/// A background job
+#[derive(Debug, Clone, Serialize)]
+pub struct JobResponse {
+    pub data: JobDataResponse,
+}
+
+/// Newest background jobs first
+#[derive(Debug, Clone, Serialize)]
+pub struct JobsResponse {
+    pub data: Vec<JobDataResponse>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobDataResponse {
+    id: i64,
+    kind: String,
+    payload: Value,
+    status: JobStatus,
+    attempts: i32,
+    max_attempts: i32,
+    run_at: DateTime<Utc>,
+    started_at: Option<DateTime<Utc>>,
+    finished_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<Job> for JobDataResponse {
+    fn from(job: Job) -> Self {
+        Self {
+            id: job.id,
+            kind: job.kind,
+            payload: job.payload,
+            status: job.status,
+            attempts: job.attempts,
+            max_attempts: job.max_attempts,
+            run_at: job.run_at,
+            started_at: job.started_at,
+            finished_at: job.finished_at,
+            last_error: job.last_error,
+            created_at: job.created_at,
+        }
+    }
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+fn is_text(field_type: FieldType) -> bool {
+    matches!(
+        field_type,
+        FieldType::Text | FieldType::LocalizedText | FieldType::Uid
+    )
+}
+
+fn widget(field_type: FieldType) -> &'static str {
+    match field_type {
+        FieldType::Uid => "uid",
+        FieldType::Uuid => "uuid",
+        FieldType::Text => "text",
+        FieldType::LocalizedText => "localizedText",
+        FieldType::Integer(_) | FieldType::Decimal { .. } => "number",
+        FieldType::Date => "date",
+        FieldType::DateTime => "datetime",
+        FieldType::LocalDateTime => "datetime-local",
+        FieldType::Time => "time",
+        FieldType::Boolean => "toggle",
+        FieldType::Json => "json",
+    }
+}
+
+/// "legal_entity" → "Legal entity"
+fn label(id: &AttributeId) -> String {
+    let words = id.as_ref().replace(['_', '-'], " ");
+    let mut chars = words.trim().chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label() {
+        let label_of = |id: &str| label(&AttributeId::try_new(id).unwrap());
+        assert_eq!(label_of("legal_entity"), "Legal entity");
+        assert_eq!(label_of("uid"), "Uid");
+        assert_eq!(label_of("published-on"), "Published on");
+    }
+}
@@ -0,0 +1,663 @@
+use axum::Json;
+use axum::body::Body;
+use axum::extract::{Path, State};
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use chrono::Utc;
+use futures::{StreamExt, TryStreamExt, future, stream};
+use luminair_common::AttributeId;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::application::AppState;
+use crate::application::commands::{
+    ApplyRetentionCommand, CleanOrphanedLinksCommand, DeleteDocumentCommand, EraseSubjectCommand,
+    ExportSubjectCommand, FindDocumentsCommand, ImportArchiveCommand, PublishDocumentCommand,
+    TranslationStatusCommand,
+};
+use crate::application::error::ServiceError;
+use crate::application::service::DocumentsService;
+use crate::domain::document::DocumentInstanceId;
+use crate::domain::document::lifecycle::PublicationSchedule;
+use crate::domain::query::DocumentInstanceQuery;
+use crate::domain::repository::ArchiveRow;
+use crate::infrastructure::http::actor::Actor;
+use crate::infrastructure::http::admin::dto::{
+    ArchiveHeaderRequest, ArchiveHeaderResponse, BulkActionRequest, BulkActionResponse,
+    ContentTypeResponse, EnqueueJobRequest, EntriesResponse, ErasureDataResponse, ErasureResponse,
+    FailedActionResponse, ImportResponse, ImportResultsResponse, JobResponse, JobsResponse,
+    OrphanedLinksRequest, OrphanedLinksResponse, OrphanedLinksResultsResponse, RetentionRequest,
+    RetentionResponse, RetentionResultsResponse, SubjectDocumentResponse, SubjectExportResponse,
+    SubjectRequest, TranslationStatusResponse, TranslationsResponse,
+};
+use crate::infrastructure::http::api::{ApiError, ApiSuccess};
+use crate::infrastructure::http::handlers::content::response::NDJSON_CONTENT_TYPE;
+use crate::infrastructure::http::handlers::content::{query_params, resolve_document_type};
+use crate::infrastructure::http::querystring::QueryMap;
+use crate::infrastructure::jobs::{JobQueue, JobStatus};
+
+/// Format named by the header line of a content export.
+const ARCHIVE_FORMAT: &str = "luminair-export";
+
+/// Version of the format of the content exports, moved forward with changes
+/// older exports can't be restored after.
+const ARCHIVE_VERSION: u32 = 1;
+
+/// Orphaned links removed by one transaction unless the request says otherwise.
+const ORPHANED_LINKS_BATCH_SIZE: u64 = 500;
+
+/// Most orphaned links a request may have removed by one transaction.
+const MAX_ORPHANED_LINKS_BATCH_SIZE: u64 = 10_000;
+
+/// Expired documents removed by one transaction unless the request says otherwise.
+const RETENTION_BATCH_SIZE: u64 = 1000;
+
+/// Most expired documents a request may have removed by one transaction.
+const MAX_RETENTION_BATCH_SIZE: u64 = 10_000;
+
+/// Jobs listed unless the request says otherwise.
+const JOBS_LIMIT: u64 = 50;
+
+/// Most jobs a request may list.
+const MAX_JOBS_LIMIT: u64 = 500;
+
+/// Handle listing the content types with their display hints.
+pub async fn content_types<S: AppState>(
+    State(state): State<S>,
+) -> Result<ApiSuccess<Vec<ContentTypeResponse>>, ApiError> {
+    let registry = state.document_types();
+    let mut document_types = registry.iterate().collect::<Vec<_>>();
+    document_types.sort_by(|a, b| a.id.as_ref().cmp(b.id.as_ref()));
+    let result = document_types
+        .into_iter()
+        .map(|document_type| ContentTypeResponse::new(document_type, registry))
+        .collect();
+
+    Ok(ApiSuccess::new(StatusCode::OK, result))
+}
+
+/// Handle reading one content type with its display hints.
+pub async fn content_type<S: AppState>(
+    State(state): State<S>,
+    Path(api_type): Path<String>,
+) -> Result<ApiSuccess<ContentTypeResponse>, ApiError> {
+    let document_type = resolve_document_type(&state, &api_type)?;
+    let result = ContentTypeResponse::new(document_type, state.document_types());
+
+    Ok(ApiSuccess::new(StatusCode::OK, result))
+}
+
+/// Handle listing the entries of a content type for the admin UI.
+///
+/// Takes the query parameters of the content API, but lists drafts unless
+/// `status` says otherwise, and answers with the number of documents each
+/// owning relation links in `relationCounts` instead of populating them.
+pub async fn find_entries<S: AppState>(
+    State(state): State<S>,
+    Path(api_type): Path<String>,
+    QueryMap(mut query_map): QueryMap,
+) -> Result<ApiSuccess<EntriesResponse>, ApiError> {
+    let document_type = resolve_document_type(&state, &api_type)?;
+    query_map
+        .entry("status")
+        .or_insert_with(|| Value::from("draft"));
+    query_map.remove("populate");
+    let q = query_params::parse_query(
+        &query_map,
+        document_type,
+        state.document_types(),
+        &state.pagination_settings(),
+        &state.query_limits(),
+    )?;
+
+    let mut relations = document_type
+        .relations
+        .iter()
+        .filter(|relation| relation.relation_type.is_owning())
+        .map(|relation| relation.id.clone())
+        .collect::<Vec<AttributeId>>();
+    relations.sort();
+
+    let (page, page_size) = q.pagination;
+    let mut query = DocumentInstanceQuery::new()
+        .paginate(page, page_size)
+        .with_status(q.status)
+        .with_filter(q.filter);
+    query.sort = q.sorts;
+    query.random_order = q.random_order;
+    query.sample = q.sample;
+
+    let cmd = FindDocumentsCommand {
+        document_type,
+        populate: (!relations.is_empty()).then(|| relations.clone()),
+        populate_filters: None,
+        populate_options: HashMap::new(),
+        populate_strategy: q.populate_strategy,
+        with_count: Vec::new(),
+        query,
+    };
+    let (documents, total) = state.documents_service().find(cmd).await?;
+
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        EntriesResponse::new(
+            documents,
+            document_type,
+            state.document_types(),
+            &relations,
+            page,
+            page_size,
+            total,
+        ),
+    ))
+}
+
+/// Handle publishing the drafts of `documentIds` now, one after the other.
+pub async fn bulk_publish<S: AppState>(
+    State(state): State<S>,
+    Path(api_type): Path<String>,
+    Actor(actor): Actor,
+    Json(request): Json<BulkActionRequest>,
+) -> Result<ApiSuccess<BulkActionResponse>, ApiError> {
+    let document_type = resolve_document_type(&state, &api_type)?;
+    let document_ids = bulk_document_ids(&state, &request)?;
+
+    let mut response = BulkActionResponse::default();
+    for (id, document_id) in document_ids {
+        let result = match document_id {
+            Ok(document_id) => {
+                let cmd = PublishDocumentCommand {
+                    document_type,
+                    document_id,
+                    expected_version: None,
+                    schedule: PublicationSchedule::default(),
+                    actor: actor.clone(),
+                };
+                state
+                    .documents_service()
+                    .publish(cmd)
+                    .await
+                    .map_err(ApiError::from)
+            }
+            Err(e) => Err(e),
+        };
+        record_outcome(&mut response, id, result);
+    }
+
+    Ok(ApiSuccess::new(StatusCode::OK, response))
+}
+
+/// Handle deleting the documents of `documentIds`, one after the other.
+pub async fn bulk_delete<S: AppState>(
+    State(state): State<S>,
+    Path(api_type): Path<String>,
+    Actor(actor): Actor,
+    Json(request): Json<BulkActionRequest>,
+) -> Result<ApiSuccess<BulkActionResponse>, ApiError> {
+    let document_type = resolve_document_type(&state, &api_type)?;
+    let document_ids = bulk_document_ids(&state, &request)?;
+
+    let mut response = BulkActionResponse::default();
+    for (id, document_instance_id) in document_ids {
+        let result = match document_instance_id {
+            Ok(document_instance_id) => {
+                let cmd = DeleteDocumentCommand {
+                    document_type,
+                    document_instance_id,
+                    actor: actor.clone(),
+                };
+                state
+                    .documents_service()
+                    .delete(cmd)
+                    .await
+                    .map_err(ApiError::from)
+            }
+            Err(e) => Err(e),
+        };
+        record_outcome(&mut response, id, result);
+    }
+
+    Ok(ApiSuccess::new(StatusCode::OK, response))
+}
+
+/// Handle exporting all content as newline-delimited JSON: a header line with
+/// the format and the schema, then one line per stored row of every table of
+/// every document type, relation links and translations included.
+///
+/// The rows are streamed, read in one statement so they are consistent.
+pub async fn export<S: AppState>(State(state): State<S>) -> Result<Response, ApiError> {
+    let mut schema = state.document_types().iterate().collect::<Vec<_>>();
+    schema.sort_by(|a, b| a.id.as_ref().cmp(b.id.as_ref()));
+    let header_line = ArchiveHeaderResponse {
+        format: ARCHIVE_FORMAT,
+        version: ARCHIVE_VERSION,
+        exported_at: Utc::now(),
+        schema,
+    };
+    let header_line = archive_line(&header_line)
+        .map_err(|e| ApiError::InternalServerError(format!("Failed to write the export: {e}")))?;
+
+    let rows = state
+        .documents_service()
+        .export_archive()
+        .and_then(|row| {
+            future::ready(
+                archive_line(&row).map_err(|e| ServiceError::Internal(anyhow::Error::from(e))),
+            )
+        })
+        .inspect_err(|e| tracing::error!("Content export failed: {}", e));
+    let lines = stream::once(future::ready(Ok(header_line))).chain(rows);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, NDJSON_CONTENT_TYPE),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"luminair-export.ndjson\"",
+            ),
+        ],
+        Body::from_stream(lines),
+    )
+        .into_response())
+}
+
+/// Handle restoring a content export into a database without content, in one
+/// transaction, answering with the number of rows restored per table.
+///
+/// The schema has to be migrated beforehand; rows of tables it doesn't have
+/// are rejected, and so is the restore into a database holding content.
+pub async fn import<S: AppState>(
+    State(state): State<S>,
+    body: String,
+) -> Result<ApiSuccess<ImportResponse>, ApiError> {
+    let mut lines = body
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (_, header) = lines
+        .next()
+        .ok_or_else(|| ApiError::UnprocessableEntity("The export is empty".to_string()))?;
+    let header = serde_json::from_str::<ArchiveHeaderRequest>(header).map_err(|e| {
+        ApiError::UnprocessableEntity(format!("The export has no valid header: {e}"))
+    })?;
+    if header.format != ARCHIVE_FORMAT || header.version != ARCHIVE_VERSION {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "Unsupported export format '{}' version {}",
+            header.format, header.version
+        )));
+    }
+    let rows = lines
+        .map(|(number, line)| {
+            serde_json::from_str::<ArchiveRow>(line).map_err(|e| {
+                ApiError::UnprocessableEntity(format!(
+                    "Line {} is not a valid row: {e}",
+                    number + 1
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let rows = state
+        .documents_service()
+        .import_archive(ImportArchiveCommand { rows })
+        .await?;
+
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        ImportResponse {
+            data: ImportResultsResponse { rows },
+        },
+    ))
+}
+
+/// Handle the cleanup of the relation links to or from documents which don't
+/// exist, which only edits of the database bypassing its foreign keys leave
+/// behind, answering with the links found per relation table.
+///
+/// The links are only counted unless `remove` is set; they are then removed
+/// in batches of `batchSize`, each committed on its own.
+pub async fn orphaned_links<S: AppState>(
+    State(state): State<S>,
+    Json(request): Json<OrphanedLinksRequest>,
+) -> Result<ApiSuccess<OrphanedLinksResponse>, ApiError> {
+    let batch_size = request.batch_size.unwrap_or(ORPHANED_LINKS_BATCH_SIZE);
+    if !(1..=MAX_ORPHANED_LINKS_BATCH_SIZE).contains(&batch_size) {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "The batch size must be between 1 and {MAX_ORPHANED_LINKS_BATCH_SIZE}"
+        )));
+    }
+
+    let links = state
+        .documents_service()
+        .clean_orphaned_links(CleanOrphanedLinksCommand {
+            remove: request.remove,
+            batch_size,
+        })
+        .await?;
+    if request.remove && !links.is_empty() {
+        tracing::warn!("Removed orphaned relation links: {:?}", links);
+    }
+
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        OrphanedLinksResponse {
+            data: OrphanedLinksResultsResponse {
+                removed: request.remove,
+                links,
+            },
+        },
+    ))
+}
+
+/// Handle the retention of the document types which have one, answering with
+/// the expired documents found per document type.
+///
+/// The documents are only counted by default, a dry run; unless `dryRun` is
+/// unset, when they are deleted or archived in batches of `batchSize`, each
+/// committed on its own.
+pub async fn retention<S: AppState>(
+    State(state): State<S>,
+    Json(request): Json<RetentionRequest>,
+) -> Result<ApiSuccess<RetentionResponse>, ApiError> {
+    let batch_size = request.batch_size.unwrap_or(RETENTION_BATCH_SIZE);
+    if !(1..=MAX_RETENTION_BATCH_SIZE).contains(&batch_size) {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "The batch size must be between 1 and {MAX_RETENTION_BATCH_SIZE}"
+        )));
+    }
+
+    let documents = state
+        .documents_service()
+        .apply_retention(ApplyRetentionCommand {
+            now: Utc::now(),
+            dry_run: request.dry_run,
+            batch_size,
+        })
+        .await?;
+    if !request.dry_run && !documents.is_empty() {
+        tracing::info!("Removed expired documents: {:?}", documents);
+    }
+
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        RetentionResponse {
+            data: RetentionResultsResponse {
+                dry_run: request.dry_run,
+                documents,
+            },
+        },
+    ))
+}
+
+/// Handle the access request of a data subject, answering with the documents
+/// whose personal fields hold the identifying `value`, with their published
+/// copies, revisions and the documents linking to them.
+///
+/// The value is taken from the body, so it stays out of the request logs.
+pub async fn export_subject<S: AppState>(
+    State(state): State<S>,
+    Json(request): Json<SubjectRequest>,
+) -> Result<ApiSuccess<SubjectExportResponse>, ApiError> {
+    let value = subject_value(request)?;
+    let documents = state
+        .documents_service()
+        .export_subject(ExportSubjectCommand { value })
+        .await?;
+
+    let registry = state.document_types();
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        SubjectExportResponse {
+            data: documents
+                .into_iter()
+                .map(|document| SubjectDocumentResponse::new(document, registry))
+                .collect(),
+        },
+    ))
+}
+
+/// Handle the erasure request of a data subject: the personal fields of the
+/// documents holding the identifying `value` are cleared in one transaction,
+/// answering with the audit record of the erasure, which keeps the hash of
+/// the value only.
+pub async fn erase_subject<S: AppState>(
+    State(state): State<S>,
+    Actor(actor): Actor,
+    Json(request): Json<SubjectRequest>,
+) -> Result<ApiSuccess<ErasureResponse>, ApiError> {
+    let value = subject_value(request)?;
+    let erasure = state
+        .documents_service()
+        .erase_subject(EraseSubjectCommand { value, actor })
+        .await?;
+    tracing::info!(
+        "Erased the personal data of subject {} (erasure {})",
+        erasure.subject,
+        erasure.id
+    );
+
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        ErasureResponse {
+            data: ErasureDataResponse::from(erasure),
+        },
+    ))
+}
+
+/// Handle reporting the translations of the entries of the localized content
+/// types, or of the `contentType` only: per entry and locale, whether it's
+/// translated, missing, or stale since the source locale changed after it.
+/// The entries of every type are paged by `pagination[page]` and
+/// `pagination[pageSize]`, the counts per locale are of all of them.
+pub async fn translation_status<S: AppState>(
+    State(state): State<S>,
+    QueryMap(query_map): QueryMap,
+) -> Result<ApiSuccess<TranslationStatusResponse>, ApiError> {
+    let (page, page_size) =
+        query_params::parse_pagination(&query_map, &state.pagination_settings())?;
+    let document_types = match query_map.get("contentType") {
+        Some(api_type) => {
+            let api_type = api_type.as_str().ok_or_else(|| {
+                ApiError::UnprocessableEntity(format!("Invalid content type: {api_type}"))
+            })?;
+            let document_type = resolve_document_type(&state, api_type)?;
+            if !document_type.has_localization() || document_type.view().is_some() {
+                return Err(ApiError::UnprocessableEntity(format!(
+                    "Content type '{api_type}' has no translations"
+                )));
+            }
+            vec![document_type]
+        }
+        None => {
+            let mut document_types = state
+                .document_types()
+                .iterate()
+                .filter(|document_type| {
+                    document_type.has_localization() && document_type.view().is_none()
+                })
+                .collect::<Vec<_>>();
+            document_types.sort_by(|a, b| a.id.as_ref().cmp(b.id.as_ref()));
+            document_types
+        }
+    };
+
+    let mut data = Vec::with_capacity(document_types.len());
+    for document_type in document_types {
+        let status = state
+            .documents_service()
+            .translation_status(TranslationStatusCommand {
+                document_type,
+                page,
+                page_size,
+            })
+            .await?;
+        data.push(TranslationsResponse::new(
+            document_type,
+            status,
+            page,
+            page_size,
+        ));
+    }
+
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        TranslationStatusResponse { data },
+    ))
+}
+
+/// Handle listing the background jobs, newest first, of the `status` if any,
+/// at most `limit`.
+pub async fn jobs(
+    State(queue): State<JobQueue>,
+    QueryMap(query_map): QueryMap,
+) -> Result<ApiSuccess<JobsResponse>, ApiError> {
+    let status = query_map
+        .get("status")
+        .map(|status| {
+            status
+                .as_str()
+                .and_then(|status| status.parse::<JobStatus>().ok())
+                .ok_or_else(|| {
+                    ApiError::UnprocessableEntity(format!("Invalid job status: {status}"))
+                })
+        })
+        .transpose()?;
+    let limit = match query_map.get("limit") {
+        Some(limit) => limit
+            .as_str()
+            .and_then(|limit| limit.parse::<u64>().ok())
+            .filter(|limit| (1..=MAX_JOBS_LIMIT).contains(limit))
+            .ok_or_else(|| {
+                ApiError::UnprocessableEntity(format!(
+                    "The limit must be between 1 and {MAX_JOBS_LIMIT}"
+                ))
+            })?,
+        None => JOBS_LIMIT,
+    };
+
+    let jobs = queue.list(status, limit).await?;
+
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        JobsResponse {
+            data: jobs.into_iter().map(Into::into).collect(),
+        },
+    ))
+}
+
+/// Handle enqueueing a background job, run by the workers of any replica.
+pub async fn enqueue_job(
+    State(queue): State<JobQueue>,
+    Json(request): Json<EnqueueJobRequest>,
+) -> Result<ApiSuccess<JobResponse>, ApiError> {
+    if request.kind.trim().is_empty() {
+        return Err(ApiError::UnprocessableEntity(
+            "The job needs a kind".to_string(),
+        ));
+    }
+    let job = queue
+        .enqueue(&request.kind, request.payload, request.run_at)
+        .await?;
+
+    Ok(ApiSuccess::new(
+        StatusCode::CREATED,
+        JobResponse { data: job.into() },
+    ))
+}
+
+/// Handle reading a background job.
+pub async fn job(
+    State(queue): State<JobQueue>,
+    Path(id): Path<i64>,
+) -> Result<ApiSuccess<JobResponse>, ApiError> {
+    let job = queue.get(id).await?;
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        JobResponse { data: job.into() },
+    ))
+}
+
+/// Handle running a failed or cancelled job again, with all its attempts.
+pub async fn retry_job(
+    State(queue): State<JobQueue>,
+    Path(id): Path<i64>,
+) -> Result<ApiSuccess<JobResponse>, ApiError> {
+    let job = queue.retry(id).await?;
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        JobResponse { data: job.into() },
+    ))
+}
+
+/// Handle cancelling a pending job.
+pub async fn cancel_job(
+    State(queue): State<JobQueue>,
+    Path(id): Path<i64>,
+) -> Result<ApiSuccess<JobResponse>, ApiError> {
+    let job = queue.cancel(id).await?;
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        JobResponse { data: job.into() },
+    ))
+}
+
+/// The identifying value of the subject of `request`, which can't be blank.
+fn subject_value(request: SubjectRequest) -> Result<String, ApiError> {
+    let value = request.value.trim();
+    if value.is_empty() {
+        return Err(ApiError::UnprocessableEntity(
+            "The identifying value of the subject is required".to_string(),
+        ));
+    }
+    Ok(value.to_string())
+}
+
+/// Each document id of a bulk action as sent, with the id it parses into.
+type BulkDocumentIds = Vec<(String, Result<DocumentInstanceId, ApiError>)>;
+
+/// The document ids of a bulk action, parsed one by one so an invalid id
+/// fails for itself only; a bulk action takes at most a page of documents.
+fn bulk_document_ids<S: AppState>(
+    state: &S,
+    request: &BulkActionRequest,
+) -> Result<BulkDocumentIds, ApiError> {
+    let max = usize::from(state.pagination_settings().max_page_size);
+    if request.document_ids.len() > max {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "A bulk action takes at most {max} documents"
+        )));
+    }
+    Ok(request
+        .document_ids
+        .iter()
+        .map(|id| {
+            let document_id = DocumentInstanceId::try_from(id)
+                .map_err(|_| ApiError::UnprocessableEntity(format!("Invalid document id: {id}")));
+            (id.clone(), document_id)
+        })
+        .collect())
+}
+
+fn record_outcome(response: &mut BulkActionResponse, id: String, result: Result<(), ApiError>) {
+    match result {
+        Ok(()) => response.data.succeeded.push(id),
+        Err(error) => {
+            if let ApiError::InternalServerError(msg) = &error {
+                tracing::error!("{}", msg);
+            }
+            response.data.failed.push(FailedActionResponse {
+                document_id: id,
+                code: error.code(),
+                detail: error.detail().to_string(),
+            });
+        }
+    }
+}
+
+/// Encode `value` as a line of an export.
+fn archive_line(value: &impl Serialize) -> Result<String, serde_json::Error> {
+    let mut line = serde_json::to_string(value)?;
+    line.push('\n');
+    Ok(line)
+}
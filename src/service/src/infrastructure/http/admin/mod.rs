@@ -0,0 +1,191 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::Router;
+use axum::extract::{DefaultBodyLimit, Request};
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use axum::routing::{get, post};
+use serde::Deserialize;
+use tokio::net;
+
+use crate::application::AppState;
+use crate::infrastructure::error_reporting::report_errors;
+use crate::infrastructure::http::actor::parse_actor;
+use crate::infrastructure::http::api::ApiError;
+use crate::infrastructure::http::slow_requests::SlowRequestLog;
+use crate::infrastructure::http::tenancy::TenantResolver;
+use crate::infrastructure::jobs::JobQueue;
+
+mod dto;
+mod handlers;
+
+/// Path the admin API is nested under.
+pub const ADMIN_PATH: &str = "/admin/api";
+
+/// Settings of the admin API
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdminSettings {
+    /// serve the admin API
+    #[serde(default)]
+    pub enabled: bool,
+    /// serve the admin API on its own port rather than next to the content API
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// roles admitted to the admin API, one of which users need
+    #[serde(default = "default_roles")]
+    pub roles: Vec<String>,
+}
+
+impl Default for AdminSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: None,
+            roles: default_roles(),
+        }
+    }
+}
+
+fn default_roles() -> Vec<String> {
+    vec!["admin".to_string()]
+}
+
+/// Routes of the admin API, for the admin UI, admitting users with one of
+/// `roles` only; the background jobs of `jobs` are managed there too.
+pub fn admin_routes<S: AppState>(roles: Vec<String>, jobs: Option<JobQueue>) -> Router<S> {
+    let roles: Arc<[String]> = roles.into();
+    let mut router = Router::new()
+        .route("/content-types", get(handlers::content_types::<S>))
+        .route(
+            "/content-types/{api_type}",
+            get(handlers::content_type::<S>),
+        )
+        .route(
+            "/content-types/{api_type}/entries",
+            get(handlers::find_entries::<S>),
+        )
+        .route(
+            "/content-types/{api_type}/actions/bulk-publish",
+            post(handlers::bulk_publish::<S>),
+        )
+        .route(
+            "/content-types/{api_type}/actions/bulk-delete",
+            post(handlers::bulk_delete::<S>),
+        )
+        .route("/export", post(handlers::export::<S>))
+        .route(
+            "/import",
+            post(handlers::import::<S>).layer(DefaultBodyLimit::disable()),
+        )
+        .route(
+            "/maintenance/orphaned-links",
+            post(handlers::orphaned_links::<S>),
+        )
+        .route("/maintenance/retention", post(handlers::retention::<S>))
+        .route("/subjects/export", post(handlers::export_subject::<S>))
+        .route("/subjects/erase", post(handlers::erase_subject::<S>))
+        .route(
+            "/translation-status",
+            get(handlers::translation_status::<S>),
+        );
+    if let Some(jobs) = jobs {
+        router = router.merge(job_routes(jobs));
+    }
+    router.route_layer(middleware::from_fn(move |request: Request, next: Next| {
+        let roles = roles.clone();
+        async move { require_admin(&roles, request, next).await }
+    }))
+}
+
+/// Routes inspecting, enqueueing, retrying and cancelling the jobs of `jobs`.
+fn job_routes<S: AppState>(jobs: JobQueue) -> Router<S> {
+    Router::new()
+        .route("/jobs", get(handlers::jobs).post(handlers::enqueue_job))
+        .route("/jobs/{id}", get(handlers::job))
+        .route("/jobs/{id}/retry", post(handlers::retry_job))
+        .route("/jobs/{id}/cancel", post(handlers::cancel_job))
+        .with_state(jobs)
+}
+
+/// Admit requests of an identified user with one of `roles`.
+///
+/// Unlike the content API, which serves anonymous requests, the admin API
+/// needs the `X-User-Id` and `X-User-Roles` headers set by the gateway.
+async fn require_admin(
+    roles: &[String],
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let actor = parse_actor(request.headers(), None)?;
+    if actor.user_id.is_none() {
+        return Err(ApiError::Forbidden(
+            "The admin API requires an identified user".to_string(),
+        ));
+    }
+    if !actor.has_any_role(roles) {
+        return Err(ApiError::Forbidden(format!(
+            "The admin API requires one of the roles: {}",
+            roles.join(", ")
+        )));
+    }
+    Ok(next.run(request).await)
+}
+
+/// The HTTP server of the admin API, when it has a port of its own.
+pub struct AdminServer {
+    router: Router,
+    listener: net::TcpListener,
+}
+
+impl AdminServer {
+    /// Returns a new admin server bound to `port`, admitting users with one
+    /// of `roles`, for the tenant `tenants` resolves if any, managing the
+    /// background jobs of `jobs`, its requests timed by `slow_requests`.
+    pub async fn new<S: AppState>(
+        state: S,
+        port: u16,
+        roles: Vec<String>,
+        tenants: Option<TenantResolver>,
+        jobs: Option<JobQueue>,
+        slow_requests: &SlowRequestLog,
+    ) -> anyhow::Result<Self> {
+        let trace_layer = tower_http::trace::TraceLayer::new_for_http().make_span_with(
+            |request: &axum::extract::Request<_>| {
+                let uri = request.uri().to_string();
+                tracing::info_span!("admin_request", method = ?request.method(), uri)
+            },
+        );
+        let mut routes = admin_routes(roles, jobs);
+        if let Some(tenants) = &tenants {
+            routes = tenants.resolve_tenants(routes);
+        }
+        let routes = slow_requests.observe_requests(routes);
+        let router = report_errors(Router::new().nest(ADMIN_PATH, routes))
+            .layer(trace_layer)
+            .with_state(state);
+        let listener = net::TcpListener::bind(format!("0.0.0.0:{port}"))
+            .await
+            .with_context(|| format!("failed to listen on {port}"))?;
+
+        Ok(Self { router, listener })
+    }
+
+    /// Address the server listens on.
+    pub fn local_addr(&self) -> anyhow::Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Runs the admin server.
+    pub async fn run(self) -> anyhow::Result<()> {
+        tracing::debug!("admin API listening on {:?}", self.listener.local_addr());
+        let router = self
+            .router
+            .into_make_service_with_connect_info::<SocketAddr>();
+        axum::serve(self.listener, router)
+            .await
+            .context("received error from running admin server")?;
+        Ok(())
+    }
+}
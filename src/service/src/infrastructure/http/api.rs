@@ -4,6 +4,11 @@ use axum::response::{IntoResponse, Response};
 use serde::Serialize;
 
 use crate::application::error::ServiceError;
+use crate::domain::document::error::DocumentError;
+use crate::domain::repository::InboundReference;
+use crate::infrastructure::error_reporting::InternalError;
+use crate::infrastructure::jobs::JobError;
+use luminair_common::entities::OnDeleteAction;
 
 // ApiSuccess is a wrapper around a response that includes a status code.
 
@@ -24,7 +29,8 @@ impl<T: Serialize> IntoResponse for ApiSuccess<T> {
 
 /// The error type returned by all API handlers.
 ///
-/// Each variant maps to an HTTP status code in the [`IntoResponse`] impl.
+/// Each variant maps to an HTTP status code and a stable [`code`](ApiError::code)
+/// in the [`IntoResponse`] impl.
 /// Implements [`std::error::Error`] via `thiserror` so it participates in the
 /// standard Rust error chain and can be inspected programmatically.
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
@@ -35,11 +41,130 @@ pub enum ApiError {
     #[error("Unprocessable entity: {0}")]
     UnprocessableEntity(String),
 
+    /// Fields of the request which failed validation, answered as an
+    /// unprocessable entity listing the `errors` per field.
+    #[error("Invalid fields: {detail}")]
+    InvalidFields {
+        detail: String,
+        errors: Vec<FieldError>,
+    },
+
     #[error("Conflict: {0}")]
     ConflictWithServerState(String),
 
+    /// A delete of a document other documents link to by relations
+    /// restricting it, answered as a conflict listing the `references`.
+    #[error("Referenced: {detail}")]
+    Referenced {
+        detail: String,
+        references: Vec<ReferenceResponse>,
+    },
+
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+
     #[error("Not found: {0}")]
     NotFound(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    /// An operation the document type doesn't expose.
+    #[error("Method not allowed: {0}")]
+    MethodNotAllowed(String),
+
+    /// A version of the API the service doesn't answer in.
+    #[error("Not acceptable: {0}")]
+    NotAcceptable(String),
+}
+
+/// A field of the request which failed validation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    /// id of the field, as it is given in the request
+    pub field: String,
+    /// kind of the failure: `required`, `invalid`, `constraint` or `unknown`
+    pub code: &'static str,
+    pub detail: String,
+}
+
+/// The documents linking to a document by one relation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceResponse {
+    /// api id of the document type declaring the relation
+    pub document_type: String,
+    pub relation: String,
+    /// what deleting the linked document does: `cascade`, `detach` or `restrict`
+    pub on_delete: OnDeleteAction,
+    pub document_ids: Vec<String>,
+}
+
+impl From<&InboundReference> for ReferenceResponse {
+    fn from(reference: &InboundReference) -> Self {
+        Self {
+            document_type: reference
+                .document_type
+                .info
+                .plural_name
+                .as_ref()
+                .to_string(),
+            relation: reference.relation.as_ref().to_string(),
+            on_delete: reference.on_delete,
+            document_ids: reference
+                .document_ids
+                .iter()
+                .map(|id| id.0.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl ApiError {
+    /// Stable code of the kind of error, for clients to program against.
+    pub fn code(&self) -> &'static str {
+        use ApiError::*;
+
+        match self {
+            InternalServerError(_) => "INTERNAL_SERVER_ERROR",
+            UnprocessableEntity(_) | InvalidFields { .. } => "UNPROCESSABLE_ENTITY",
+            ConflictWithServerState(_) | Referenced { .. } => "CONFLICT",
+            PreconditionFailed(_) => "PRECONDITION_FAILED",
+            NotFound(_) => "NOT_FOUND",
+            Forbidden(_) => "FORBIDDEN",
+            MethodNotAllowed(_) => "METHOD_NOT_ALLOWED",
+            NotAcceptable(_) => "NOT_ACCEPTABLE",
+        }
+    }
+
+    /// Message of the error for the client; internal errors are not disclosed.
+    pub fn detail(&self) -> &str {
+        use ApiError::*;
+
+        match self {
+            InternalServerError(_) => "An internal server error occurred",
+            InvalidFields { detail, .. } | Referenced { detail, .. } => detail,
+            UnprocessableEntity(msg)
+            | ConflictWithServerState(msg)
+            | PreconditionFailed(msg)
+            | NotFound(msg)
+            | Forbidden(msg)
+            | MethodNotAllowed(msg)
+            | NotAcceptable(msg) => msg,
+        }
+    }
+
+    /// Error of the single field `field` failing validation.
+    pub(crate) fn invalid_field(field: &str, code: &'static str, detail: String) -> Self {
+        Self::InvalidFields {
+            detail: detail.clone(),
+            errors: vec![FieldError {
+                field: field.to_string(),
+                code,
+                detail,
+            }],
+        }
+    }
 }
 
 impl From<anyhow::Error> for ApiError {
@@ -48,6 +173,16 @@ impl From<anyhow::Error> for ApiError {
     }
 }
 
+impl From<JobError> for ApiError {
+    fn from(e: JobError) -> Self {
+        match e {
+            JobError::NotFound(_) => Self::NotFound(e.to_string()),
+            JobError::Conflict { .. } => Self::ConflictWithServerState(e.to_string()),
+            JobError::Database(e) => Self::InternalServerError(format!("{e:#}")),
+        }
+    }
+}
+
 impl From<ServiceError> for ApiError {
     fn from(value: ServiceError) -> Self {
         match value {
@@ -55,6 +190,7 @@ impl From<ServiceError> for ApiError {
                 Self::NotFound("Document type not found".to_string())
             }
             ServiceError::DocumentNotFound => Self::NotFound("Document not found".to_string()),
+            cause @ ServiceError::RevisionNotFound(_) => Self::NotFound(cause.to_string()),
             ServiceError::RelationNotFound(relation) => {
                 Self::NotFound(format!("Relation '{}' not found", relation))
             }
@@ -62,44 +198,95 @@ impl From<ServiceError> for ApiError {
                 "Relation is not an owning relation: {}",
                 relation
             )),
-            ServiceError::Validation(cause) => Self::UnprocessableEntity(cause.to_string()),
+            cause @ ServiceError::NoLocaleTexts(_) => Self::UnprocessableEntity(cause.to_string()),
+            cause @ ServiceError::WorkflowNotFound => Self::NotFound(cause.to_string()),
+            ServiceError::Forbidden(cause) => Self::Forbidden(cause),
+            ServiceError::Validation(cause) => cause.into(),
             ServiceError::Conflict(cause) => Self::ConflictWithServerState(cause),
+            cause @ ServiceError::VersionMismatch { .. } => {
+                Self::PreconditionFailed(cause.to_string())
+            }
+            cause @ ServiceError::ConcurrentModification => {
+                Self::ConflictWithServerState(cause.to_string())
+            }
+            ServiceError::Referenced(references) => Self::Referenced {
+                detail: "Document is linked by relations restricting its delete".to_string(),
+                references: references.iter().map(ReferenceResponse::from).collect(),
+            },
             ServiceError::Internal(internal) => internal.into(),
         }
     }
 }
 
+impl From<DocumentError> for ApiError {
+    fn from(value: DocumentError) -> Self {
+        let detail = value.to_string();
+        match value {
+            DocumentError::MissingRequiredField(field) => {
+                Self::invalid_field(&field, "required", detail)
+            }
+            DocumentError::InvalidFieldValue { field, .. } => {
+                Self::invalid_field(&field, "invalid", detail)
+            }
+            DocumentError::ConstraintViolation { field, .. } => {
+                Self::invalid_field(&field, "constraint", detail)
+            }
+            _ => Self::UnprocessableEntity(detail),
+        }
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         use ApiError::*;
 
-        let (status, detail, problem_type) = match self {
+        let code = self.code();
+        let mut references = Vec::new();
+        let mut internal_error = None;
+        let (status, detail, errors) = match self {
             InternalServerError(msg) => {
                 tracing::error!("{}", msg);
+                internal_error = Some(InternalError(msg));
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     "An internal server error occurred".to_string(),
-                    "/errors/internal-server-error".to_string(),
+                    Vec::new(),
                 )
             }
-            UnprocessableEntity(msg) => (
-                StatusCode::UNPROCESSABLE_ENTITY,
-                msg,
-                "/errors/unprocessable-entity".to_string(),
-            ),
-            ConflictWithServerState(msg) => {
-                (StatusCode::CONFLICT, msg, "/errors/conflict".to_string())
+            UnprocessableEntity(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg, Vec::new()),
+            InvalidFields { detail, errors } => (StatusCode::UNPROCESSABLE_ENTITY, detail, errors),
+            ConflictWithServerState(msg) => (StatusCode::CONFLICT, msg, Vec::new()),
+            Referenced {
+                detail,
+                references: referrers,
+            } => {
+                references = referrers;
+                (StatusCode::CONFLICT, detail, Vec::new())
             }
-            NotFound(msg) => (StatusCode::NOT_FOUND, msg, "/errors/not-found".to_string()),
+            PreconditionFailed(msg) => (StatusCode::PRECONDITION_FAILED, msg, Vec::new()),
+            NotFound(msg) => (StatusCode::NOT_FOUND, msg, Vec::new()),
+            Forbidden(msg) => (StatusCode::FORBIDDEN, msg, Vec::new()),
+            MethodNotAllowed(msg) => (StatusCode::METHOD_NOT_ALLOWED, msg, Vec::new()),
+            NotAcceptable(msg) => (StatusCode::NOT_ACCEPTABLE, msg, Vec::new()),
         };
 
-        let problem = ProblemDetails::new(status, detail).with_type(problem_type);
-        (
+        // "UNPROCESSABLE_ENTITY" → "/errors/unprocessable-entity"
+        let problem_type = format!("/errors/{}", code.to_lowercase().replace('_', "-"));
+        let problem = ProblemDetails::new(status, detail)
+            .with_type(problem_type)
+            .with_code(code)
+            .with_errors(errors)
+            .with_references(references);
+        let mut response = (
             status,
             [("content-type", "application/problem+json")],
             Json(problem),
         )
-            .into_response()
+            .into_response();
+        if let Some(internal_error) = internal_error {
+            response.extensions_mut().insert(internal_error);
+        }
+        response
     }
 }
 
@@ -111,8 +298,17 @@ pub struct ProblemDetails {
     pub title: String,
     pub status: u16,
     pub detail: String,
+    /// stable code of the kind of error, see [`ApiError::code`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub instance: Option<String>,
+    /// fields which failed validation
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    /// documents linking to a document which can't be deleted
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<ReferenceResponse>,
 }
 
 impl ProblemDetails {
@@ -125,7 +321,10 @@ impl ProblemDetails {
                 .to_string(),
             status: status.as_u16(),
             detail,
+            code: None,
             instance: None,
+            errors: Vec::new(),
+            references: Vec::new(),
         }
     }
 
@@ -133,4 +332,19 @@ impl ProblemDetails {
         self.problem_type = problem_type;
         self
     }
+
+    pub fn with_code(mut self, code: &str) -> Self {
+        self.code = Some(code.to_string());
+        self
+    }
+
+    pub fn with_errors(mut self, errors: Vec<FieldError>) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    pub fn with_references(mut self, references: Vec<ReferenceResponse>) -> Self {
+        self.references = references;
+        self
+    }
 }
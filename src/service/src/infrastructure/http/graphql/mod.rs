@@ -0,0 +1,92 @@
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{Error as GraphqlError, ErrorExtensions, Value as GraphqlValue};
+use axum::Json;
+use axum::Router;
+use axum::response::Html;
+use axum::routing::post;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::application::AppState;
+use crate::infrastructure::http::actor::Actor;
+use crate::infrastructure::http::api::{ApiError, FieldError};
+
+mod mutations;
+mod schema;
+
+pub use schema::build_schema;
+
+/// Path of the GraphQL endpoint, and of GraphiQL when it is served.
+pub const GRAPHQL_PATH: &str = "/graphql";
+
+/// Settings of the GraphQL endpoint
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GraphqlSettings {
+    /// serve the GraphiQL IDE on `GET /graphql`, by default in development mode only
+    #[serde(default)]
+    pub graphiql: bool,
+}
+
+/// Routes of the GraphQL endpoint, with a schema generated from the document
+/// types of `state`.
+///
+/// Queries are posted as JSON, and run with the [`Actor`] of their request
+/// as context data; with `graphiql` the IDE is served on `GET`.
+pub fn graphql_routes<S: AppState>(state: &S, graphiql: bool) -> anyhow::Result<Router<S>> {
+    let schema = build_schema(state.clone())?;
+    let mut route = post(
+        move |Actor(actor): Actor, Json(request): Json<async_graphql::Request>| async move {
+            Json(schema.execute(request.data(actor)).await)
+        },
+    );
+    if graphiql {
+        route = route.get(graphiql_page);
+    }
+    Ok(Router::new().route(GRAPHQL_PATH, route))
+}
+
+async fn graphiql_page() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint(GRAPHQL_PATH).finish())
+}
+
+/// GraphQL error of an API error, with the kind of error as `code` extension;
+/// internal errors are logged, not shown.
+fn graphql_error(error: ApiError) -> GraphqlError {
+    use ApiError::*;
+
+    let code = error.code();
+    let (message, errors) = match error {
+        InternalServerError(msg) => {
+            tracing::error!("{}", msg);
+            ("An internal server error occurred".to_string(), Vec::new())
+        }
+        InvalidFields { detail, errors } => (detail, errors),
+        Referenced { detail, .. } => (detail, Vec::new()),
+        UnprocessableEntity(msg)
+        | ConflictWithServerState(msg)
+        | PreconditionFailed(msg)
+        | NotFound(msg)
+        | Forbidden(msg)
+        | MethodNotAllowed(msg)
+        | NotAcceptable(msg) => (msg, Vec::new()),
+    };
+    GraphqlError::new(message).extend_with(|_, extensions| {
+        extensions.set("code", code);
+        if !errors.is_empty() {
+            let errors = errors
+                .iter()
+                .map(field_error)
+                .collect::<Vec<GraphqlValue>>();
+            extensions.set("errors", errors);
+        }
+    })
+}
+
+fn field_error(error: &FieldError) -> GraphqlValue {
+    GraphqlValue::from_json(json!({
+        "field": error.field,
+        "code": error.code,
+        "detail": error.detail,
+    }))
+    .unwrap_or_default()
+}
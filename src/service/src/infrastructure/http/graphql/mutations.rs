@@ -0,0 +1,377 @@
+//! Mutations of the GraphQL schema.
+//!
+//! Every document type gets `create{Type}`, `update{Type}`, `delete{Type}`
+//! and `publish{Type}`, e.g. `createPartnerCategory(data: PartnerCategoryInput!)`,
+//! of the operations its schema options expose.
+//! The input object has the fields and owning relations of the type; its
+//! values are validated as the `data` of the REST API, relations take
+//! `{connect, disconnect}` or `{set}` lists of document ids.
+
+use std::collections::HashMap;
+
+use async_graphql::dynamic::{
+    Field, FieldFuture, FieldValue, InputObject, InputValue, ResolverContext, TypeRef,
+};
+use async_graphql::{Error as GraphqlError, Value as GraphqlValue};
+use luminair_common::entities::{ApiOperation, LocalizationId};
+use luminair_common::{AttributeId, DocumentType};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::application::AppState;
+use crate::application::commands::{
+    CreateDocumentWithRelationsCommand, DeleteDocumentCommand, FindByIdCommand,
+    PublishDocumentCommand, RelationOperation, UpdateDocumentWithRelationsCommand,
+};
+use crate::application::service::DocumentsService;
+use crate::domain::actor::ActorContext;
+use crate::domain::document::DocumentInstanceId;
+use crate::domain::document::content::ContentValue;
+use crate::domain::query::{DocumentInstanceQuery, DocumentStatus};
+use crate::infrastructure::http::api::ApiError;
+use crate::infrastructure::http::graphql::graphql_error;
+use crate::infrastructure::http::graphql::schema::{
+    ResolvedDocument, SYSTEM_FIELDS, attribute_name, content_type, document_id_argument,
+    selected_relations, service_error,
+};
+use crate::infrastructure::http::handlers::content::request_body::{
+    build_fields_from_map, classify_document_data, parse_publication_schedule,
+    parse_relation_operations, supported_locale,
+};
+
+const RELATION_INPUT: &str = "RelationInput";
+
+/// Operations of the content API with a mutation.
+pub(super) const OPERATIONS: [ApiOperation; 4] = [
+    ApiOperation::Create,
+    ApiOperation::Update,
+    ApiOperation::Delete,
+    ApiOperation::Publish,
+];
+
+/// Input object of the operations on a relation, as in the REST API.
+pub(super) fn relation_input() -> InputObject {
+    InputObject::new(RELATION_INPUT)
+        .field(InputValue::new(
+            "connect",
+            TypeRef::named_nn_list(TypeRef::ID),
+        ))
+        .field(InputValue::new(
+            "disconnect",
+            TypeRef::named_nn_list(TypeRef::ID),
+        ))
+        .field(InputValue::new("set", TypeRef::named_nn_list(TypeRef::ID)))
+}
+
+/// Input object of the fields and owning relations of `document_type`.
+///
+/// Every field is optional: an update only changes the fields it is given,
+/// and the required fields of a create are checked by the service.
+pub(super) fn document_input(document_type: &DocumentType, input_type: &str) -> InputObject {
+    let mut input = InputObject::new(input_type);
+    for field in document_type.ordered_fields() {
+        let name = attribute_name(document_type, &field.id);
+        if !SYSTEM_FIELDS.contains(&name.as_str()) {
+            input = input.field(InputValue::new(name, content_type(field.field_type)));
+        }
+    }
+
+    let mut relations: Vec<&AttributeId> = document_type
+        .relations
+        .iter()
+        .filter(|relation| relation.relation_type.is_owning())
+        .map(|relation| &relation.id)
+        .collect();
+    relations.sort();
+    for relation in relations {
+        input = input.field(InputValue::new(
+            attribute_name(document_type, relation),
+            TypeRef::named(RELATION_INPUT),
+        ));
+    }
+    input
+}
+
+/// Mutation fields of the operations `document_type` exposes, answering with
+/// the document written; `publish{Type}` answers `null` when the publish is
+/// only scheduled.
+pub(super) fn mutation_fields<S: AppState>(
+    document_type: &'static DocumentType,
+    object_type: &str,
+    input_type: &str,
+) -> Vec<Field> {
+    let document_id = || InputValue::new("documentId", TypeRef::named_nn(TypeRef::ID));
+    let expected_version = || InputValue::new("expectedVersion", TypeRef::named(TypeRef::INT));
+    let locale = || InputValue::new("locale", TypeRef::named(TypeRef::STRING));
+    let data = || InputValue::new("data", TypeRef::named_nn(input_type));
+
+    let create = Field::new(
+        format!("create{object_type}"),
+        TypeRef::named(object_type),
+        move |ctx| {
+            FieldFuture::new(async move {
+                let state = ctx.data::<S>()?;
+                let locale = locale_argument(&ctx, document_type)?;
+                let (fields, relation_operations) =
+                    document_data(&ctx, document_type, locale.as_ref())?;
+
+                let cmd = CreateDocumentWithRelationsCommand {
+                    document_type,
+                    fields,
+                    relation_operations,
+                    actor: actor_data(&ctx),
+                };
+                let document_id = state
+                    .documents_service()
+                    .create_with_relations(cmd)
+                    .await
+                    .map_err(service_error)?;
+
+                let document = written_document(
+                    &ctx,
+                    state,
+                    document_type,
+                    document_id,
+                    DocumentStatus::Draft,
+                )
+                .await?;
+                Ok(document.map(FieldValue::owned_any))
+            })
+        },
+    )
+    .argument(data())
+    .argument(locale());
+
+    let update = Field::new(
+        format!("update{object_type}"),
+        TypeRef::named(object_type),
+        move |ctx| {
+            FieldFuture::new(async move {
+                let state = ctx.data::<S>()?;
+                let document_id = document_id_argument(&ctx)?;
+                let locale = locale_argument(&ctx, document_type)?;
+                let (fields, relation_operations) =
+                    document_data(&ctx, document_type, locale.as_ref())?;
+
+                let cmd = UpdateDocumentWithRelationsCommand {
+                    document_type,
+                    document_id,
+                    fields,
+                    relation_operations,
+                    locale,
+                    expected_version: expected_version_argument(&ctx)?,
+                    actor: actor_data(&ctx),
+                };
+                state
+                    .documents_service()
+                    .update_with_relations(cmd)
+                    .await
+                    .map_err(service_error)?;
+
+                let document = written_document(
+                    &ctx,
+                    state,
+                    document_type,
+                    document_id,
+                    DocumentStatus::Draft,
+                )
+                .await?;
+                Ok(document.map(FieldValue::owned_any))
+            })
+        },
+    )
+    .argument(document_id())
+    .argument(data())
+    .argument(locale())
+    .argument(expected_version());
+
+    let delete = Field::new(
+        format!("delete{object_type}"),
+        TypeRef::named_nn(TypeRef::ID),
+        move |ctx| {
+            FieldFuture::new(async move {
+                let state = ctx.data::<S>()?;
+                let document_instance_id = document_id_argument(&ctx)?;
+
+                let cmd = DeleteDocumentCommand {
+                    document_type,
+                    document_instance_id,
+                    actor: actor_data(&ctx),
+                };
+                state
+                    .documents_service()
+                    .delete(cmd)
+                    .await
+                    .map_err(service_error)?;
+
+                Ok(Some(GraphqlValue::from(String::from(document_instance_id))))
+            })
+        },
+    )
+    .argument(document_id());
+
+    let publish = Field::new(
+        format!("publish{object_type}"),
+        TypeRef::named(object_type),
+        move |ctx| {
+            FieldFuture::new(async move {
+                let state = ctx.data::<S>()?;
+                let document_id = document_id_argument(&ctx)?;
+                let mut schedule = Map::new();
+                for key in ["publishAt", "unpublishAt"] {
+                    if let Some(at) = ctx.args.get(key) {
+                        schedule.insert(key.to_string(), at.as_value().clone().into_json()?);
+                    }
+                }
+                let schedule = parse_publication_schedule(Some(&JsonValue::Object(schedule)))
+                    .map_err(graphql_error)?;
+
+                let cmd = PublishDocumentCommand {
+                    document_type,
+                    document_id,
+                    expected_version: expected_version_argument(&ctx)?,
+                    schedule,
+                    actor: actor_data(&ctx),
+                };
+                state
+                    .documents_service()
+                    .publish(cmd)
+                    .await
+                    .map_err(service_error)?;
+
+                let document = written_document(
+                    &ctx,
+                    state,
+                    document_type,
+                    document_id,
+                    DocumentStatus::Published,
+                )
+                .await?;
+                Ok(document.map(FieldValue::owned_any))
+            })
+        },
+    )
+    .argument(document_id())
+    .argument(expected_version())
+    .argument(InputValue::new(
+        "publishAt",
+        TypeRef::named(TypeRef::STRING),
+    ))
+    .argument(InputValue::new(
+        "unpublishAt",
+        TypeRef::named(TypeRef::STRING),
+    ));
+
+    OPERATIONS
+        .into_iter()
+        .zip([create, update, delete, publish])
+        .filter(|(operation, _)| document_type.exposes(*operation))
+        .map(|(_, field)| field)
+        .collect()
+}
+
+/// Field values and relation operations of a mutation.
+type DocumentData = (
+    HashMap<AttributeId, ContentValue>,
+    HashMap<AttributeId, RelationOperation>,
+);
+
+/// Fields and relation operations of the `data` argument of `ctx`, validated
+/// as the `data` of a REST request.
+fn document_data(
+    ctx: &ResolverContext<'_>,
+    document_type: &DocumentType,
+    locale: Option<&LocalizationId>,
+) -> Result<DocumentData, GraphqlError> {
+    let mut data = Map::new();
+    for (name, value) in ctx.args.try_get("data")?.object()?.iter() {
+        if let Some(attribute) = input_attribute(document_type, name) {
+            data.insert(attribute.to_string(), value.as_value().clone().into_json()?);
+        }
+    }
+
+    let mut classified = classify_document_data(&data, document_type).map_err(graphql_error)?;
+    for operations in classified
+        .relations
+        .values_mut()
+        .filter_map(JsonValue::as_object_mut)
+    {
+        // an operation explicitly given as null is left out
+        operations.retain(|_, ids| !ids.is_null());
+    }
+
+    let fields = build_fields_from_map(document_type, &classified.fields, locale)
+        .map_err(|e| graphql_error(e.into()))?;
+    let relation_operations =
+        parse_relation_operations(&classified.relations).map_err(graphql_error)?;
+    Ok((fields, relation_operations))
+}
+
+/// Field or owning relation of `document_type` with the GraphQL name `name`.
+fn input_attribute<'a>(document_type: &'a DocumentType, name: &str) -> Option<&'a AttributeId> {
+    let fields = document_type.fields.iter().map(|field| &field.id);
+    let relations = document_type
+        .relations
+        .iter()
+        .filter(|relation| relation.relation_type.is_owning())
+        .map(|relation| &relation.id);
+    fields
+        .chain(relations)
+        .find(|id| attribute_name(document_type, id) == name)
+}
+
+fn locale_argument(
+    ctx: &ResolverContext<'_>,
+    document_type: &DocumentType,
+) -> Result<Option<LocalizationId>, GraphqlError> {
+    ctx.args
+        .get("locale")
+        .map(|locale| supported_locale(locale.string()?, document_type).map_err(graphql_error))
+        .transpose()
+}
+
+fn expected_version_argument(ctx: &ResolverContext<'_>) -> Result<Option<i32>, GraphqlError> {
+    ctx.args
+        .get("expectedVersion")
+        .map(|version| {
+            let version = version.i64()?;
+            i32::try_from(version).map_err(|_| {
+                graphql_error(ApiError::UnprocessableEntity(format!(
+                    "Invalid expectedVersion: {version}"
+                )))
+            })
+        })
+        .transpose()
+}
+
+/// Who the mutation acts for, anonymous when the schema is executed without
+/// an actor.
+fn actor_data(ctx: &ResolverContext<'_>) -> ActorContext {
+    ctx.data_opt::<ActorContext>().cloned().unwrap_or_default()
+}
+
+/// The document written by a mutation, read in `status` with the relations
+/// selected on it.
+async fn written_document<S: AppState>(
+    ctx: &ResolverContext<'_>,
+    state: &S,
+    document_type: &'static DocumentType,
+    document_instance_id: DocumentInstanceId,
+    status: DocumentStatus,
+) -> Result<Option<ResolvedDocument>, GraphqlError> {
+    let populate = selected_relations(document_type, ctx.look_ahead());
+    let cmd = FindByIdCommand {
+        document_type,
+        document_instance_id,
+        populate: (!populate.is_empty()).then_some(populate),
+        populate_filters: None,
+        populate_options: HashMap::new(),
+        query: DocumentInstanceQuery::new().with_status(status),
+    };
+    let document = state
+        .documents_service()
+        .find_by_id(cmd)
+        .await
+        .map_err(service_error)?;
+
+    Ok(document.map(|instance| ResolvedDocument { instance, status }))
+}
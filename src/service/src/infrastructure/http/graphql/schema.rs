@@ -0,0 +1,680 @@
+use async_graphql::dynamic::{
+    Enum, Field, FieldFuture, FieldValue, InputObject, InputValue, Object, ResolverContext, Scalar,
+    Schema, SchemaError, TypeRef,
+};
+use async_graphql::{Error as GraphqlError, Lookahead, Value as GraphqlValue};
+use luminair_common::entities::{
+    ApiOperation, DocumentKind, DocumentRelation as RelationMetadata, FieldType, RelationType,
+};
+use luminair_common::{AttributeId, DocumentType, DocumentTypeId};
+use serde_json::{Map, Value as JsonValue, json};
+use std::collections::HashMap;
+
+use crate::application::AppState;
+use crate::application::commands::{FindByIdCommand, FindDocumentsCommand};
+use crate::application::error::ServiceError;
+use crate::application::service::DocumentsService;
+use crate::domain::document::lifecycle::PublicationState;
+use crate::domain::document::{DocumentInstance, DocumentInstanceId, DocumentRelation};
+use crate::domain::query::{DocumentInstanceQuery, DocumentStatus};
+use crate::infrastructure::http::api::ApiError;
+use crate::infrastructure::http::graphql::{graphql_error, mutations};
+use crate::infrastructure::http::handlers::content::query_params::{DocumentQuery, parse_query};
+
+const QUERY_TYPE: &str = "Query";
+const MUTATION_TYPE: &str = "Mutation";
+pub(super) const JSON_SCALAR: &str = "JSON";
+const STATUS_ENUM: &str = "DocumentStatus";
+const PAGINATION_INPUT: &str = "PaginationInput";
+const PAGE_META_TYPE: &str = "PageMeta";
+
+/// Fields of every document object, next to the fields of its type.
+pub(super) const SYSTEM_FIELDS: [&str; 11] = [
+    "documentId",
+    "status",
+    "version",
+    "createdAt",
+    "updatedAt",
+    "createdBy",
+    "updatedBy",
+    "publishedAt",
+    "publishedBy",
+    "revision",
+    "workflowStage",
+];
+
+/// Build the GraphQL schema of the document types of `state`.
+///
+/// Every document type gets an object type named after its singular name
+/// (`partner-category` → `PartnerCategory`), with its fields and owning
+/// relations, and a query field to read one document. Collections also get
+/// a query field for pages of documents, e.g. `partnerCategories(filters,
+/// sort, pagination, status)`, whose arguments work as the query parameters
+/// of the REST API. The mutations of every type are described in
+/// [`mutations`](super::mutations).
+pub fn build_schema<S: AppState>(state: S) -> Result<Schema, SchemaError> {
+    let registry = state.document_types();
+    let mut document_types: Vec<&'static DocumentType> = registry.iterate().collect();
+    document_types.sort_by(|a, b| a.id.cmp(&b.id));
+    // a schema has no mutation type when no document type is writable
+    let writable = document_types.iter().any(|document_type| {
+        mutations::OPERATIONS
+            .into_iter()
+            .any(|operation| document_type.exposes(operation))
+    });
+
+    let mut query = Object::new(QUERY_TYPE);
+    let mut mutation = Object::new(MUTATION_TYPE);
+    let mut schema = Schema::build(QUERY_TYPE, writable.then_some(MUTATION_TYPE), None)
+        .register(Scalar::new(JSON_SCALAR))
+        .register(Enum::new(STATUS_ENUM).item("DRAFT").item("PUBLISHED"))
+        .register(
+            InputObject::new(PAGINATION_INPUT)
+                .field(InputValue::new("page", TypeRef::named(TypeRef::INT)))
+                .field(InputValue::new("pageSize", TypeRef::named(TypeRef::INT))),
+        )
+        .register(page_meta_object())
+        .register(mutations::relation_input());
+
+    for document_type in document_types {
+        let object_type = type_name(document_type.info.singular_name.as_ref());
+        schema = schema.register(document_object::<S>(document_type, &object_type, |id| {
+            registry.get(id)
+        }));
+        if document_type.exposes(ApiOperation::FindOne) {
+            query = query.field(document_query_field::<S>(document_type, &object_type));
+        }
+
+        let input_type = format!("{object_type}Input");
+        schema = schema.register(mutations::document_input(document_type, &input_type));
+        for field in mutations::mutation_fields::<S>(document_type, &object_type, &input_type) {
+            mutation = mutation.field(field);
+        }
+
+        if document_type.kind == DocumentKind::Collection
+            && document_type.exposes(ApiOperation::Find)
+        {
+            let page_type = format!("{object_type}Page");
+            schema = schema.register(page_object(&page_type, &object_type));
+            query = query.field(collection_query_field::<S>(document_type, &page_type));
+        }
+    }
+
+    if writable {
+        schema = schema.register(mutation);
+    }
+    schema.register(query).data(state).finish()
+}
+
+/// Document resolved by the schema, with the status it was read in, which
+/// its relations are read in too.
+pub(super) struct ResolvedDocument {
+    pub(super) instance: DocumentInstance,
+    pub(super) status: DocumentStatus,
+}
+
+/// Page of documents resolved by a collection query.
+struct ResolvedPage {
+    documents: Vec<ResolvedDocument>,
+    page: u16,
+    page_size: u16,
+    total: u64,
+}
+
+fn document_object<S: AppState>(
+    document_type: &'static DocumentType,
+    object_type: &str,
+    target_type: impl Fn(&DocumentTypeId) -> Option<&'static DocumentType>,
+) -> Object {
+    let mut object = Object::new(object_type);
+    if let Some(description) = &document_type.info.description {
+        object = object.description(description);
+    }
+
+    object = object
+        .field(document_field(
+            "documentId",
+            TypeRef::named_nn(TypeRef::ID),
+            |instance| Some(instance.document_id.0.to_string().into()),
+        ))
+        .field(document_field(
+            "status",
+            TypeRef::named_nn(TypeRef::STRING),
+            |instance| Some(publication_status(instance).into()),
+        ))
+        .field(document_field(
+            "version",
+            TypeRef::named_nn(TypeRef::INT),
+            |instance| Some(instance.audit.version.into()),
+        ))
+        .field(document_field(
+            "createdAt",
+            TypeRef::named_nn(TypeRef::STRING),
+            |instance| Some(instance.audit.created_at.to_rfc3339().into()),
+        ))
+        .field(document_field(
+            "updatedAt",
+            TypeRef::named_nn(TypeRef::STRING),
+            |instance| Some(instance.audit.updated_at.to_rfc3339().into()),
+        ))
+        .field(document_field(
+            "createdBy",
+            TypeRef::named(TypeRef::STRING),
+            |instance| {
+                instance
+                    .audit
+                    .created_by
+                    .as_ref()
+                    .map(|user| user.to_string().into())
+            },
+        ))
+        .field(document_field(
+            "updatedBy",
+            TypeRef::named(TypeRef::STRING),
+            |instance| {
+                instance
+                    .audit
+                    .updated_by
+                    .as_ref()
+                    .map(|user| user.to_string().into())
+            },
+        ))
+        .field(document_field(
+            "publishedAt",
+            TypeRef::named(TypeRef::STRING),
+            |instance| match &instance.content.publication_state {
+                PublicationState::Published { published_at, .. } => {
+                    Some(published_at.to_rfc3339().into())
+                }
+                PublicationState::Draft { .. } => None,
+            },
+        ))
+        .field(document_field(
+            "publishedBy",
+            TypeRef::named(TypeRef::STRING),
+            |instance| match &instance.content.publication_state {
+                PublicationState::Published { published_by, .. } => {
+                    published_by.as_ref().map(|user| user.to_string().into())
+                }
+                PublicationState::Draft { .. } => None,
+            },
+        ))
+        .field(document_field(
+            "revision",
+            TypeRef::named_nn(TypeRef::INT),
+            |instance| match &instance.content.publication_state {
+                PublicationState::Published { revision, .. }
+                | PublicationState::Draft { revision } => Some((*revision).into()),
+            },
+        ));
+    if document_type.workflow().is_some() {
+        object = object.field(document_field(
+            "workflowStage",
+            TypeRef::named(TypeRef::STRING),
+            |instance| {
+                instance
+                    .workflow_stage
+                    .as_ref()
+                    .map(|stage| stage.to_string().into())
+            },
+        ));
+    }
+
+    for field in document_type.ordered_fields() {
+        let name = attribute_name(document_type, &field.id);
+        if SYSTEM_FIELDS.contains(&name.as_str()) {
+            tracing::warn!(
+                "Field '{}' of '{}' is hidden by a GraphQL system field",
+                field.id,
+                document_type.id
+            );
+            continue;
+        }
+        let attribute = field.id.clone();
+        object = object.field(document_field(
+            &name,
+            content_type(field.field_type),
+            move |instance| {
+                instance
+                    .content
+                    .fields
+                    .get(&attribute)
+                    .and_then(|value| GraphqlValue::from_json(JsonValue::from(value)).ok())
+            },
+        ));
+    }
+
+    let mut relations: Vec<&'static RelationMetadata> = document_type
+        .relations
+        .iter()
+        .filter(|relation| relation.relation_type.is_owning())
+        .collect();
+    relations.sort_by(|a, b| a.id.cmp(&b.id));
+    for relation in relations {
+        let Some(target) = target_type(&relation.target) else {
+            continue;
+        };
+        let target_type = type_name(target.info.singular_name.as_ref());
+        object = object.field(relation_field::<S>(document_type, relation, &target_type));
+    }
+
+    object
+}
+
+/// Field of a document object with the value `value` reads from the document.
+fn document_field(
+    name: &str,
+    type_ref: TypeRef,
+    value: impl Fn(&DocumentInstance) -> Option<GraphqlValue> + Send + Sync + 'static,
+) -> Field {
+    Field::new(name, type_ref, move |ctx| {
+        let value = ctx
+            .parent_value
+            .try_downcast_ref::<ResolvedDocument>()
+            .map(|document| value(&document.instance));
+        FieldFuture::new(async move { value })
+    })
+}
+
+/// Field of the documents linked by an owning relation.
+///
+/// The relations selected in a query are populated with the documents of the
+/// query, by the batch relation fetchers; the relations of related documents
+/// are read document by document.
+fn relation_field<S: AppState>(
+    document_type: &'static DocumentType,
+    relation: &'static RelationMetadata,
+    target_type: &str,
+) -> Field {
+    let many = relation.relation_type != RelationType::HasOne;
+    let type_ref = if many {
+        TypeRef::named_nn_list_nn(target_type)
+    } else {
+        TypeRef::named(target_type)
+    };
+
+    Field::new(
+        attribute_name(document_type, &relation.id),
+        type_ref,
+        move |ctx| {
+            FieldFuture::new(async move {
+                let parent = ctx.parent_value.try_downcast_ref::<ResolvedDocument>()?;
+                let related = match parent.instance.relations.get(&relation.id) {
+                    Some(related) => related.clone(),
+                    None => {
+                        let cmd = FindByIdCommand {
+                            document_type,
+                            document_instance_id: parent.instance.document_id,
+                            populate: Some(vec![relation.id.clone()]),
+                            populate_filters: None,
+                            populate_options: HashMap::new(),
+                            query: DocumentInstanceQuery::new().with_status(parent.status),
+                        };
+                        ctx.data::<S>()?
+                            .documents_service()
+                            .find_by_id(cmd)
+                            .await
+                            .map_err(service_error)?
+                            .and_then(|mut document| document.relations.remove(&relation.id))
+                            .unwrap_or_default()
+                    }
+                };
+
+                let status = parent.status;
+                let mut documents = related.into_iter().filter_map(|related| match related {
+                    DocumentRelation::Instance(instance) => {
+                        Some(FieldValue::owned_any(ResolvedDocument {
+                            instance: *instance,
+                            status,
+                        }))
+                    }
+                    DocumentRelation::Id(_) => None,
+                });
+                if many {
+                    Ok(Some(FieldValue::list(documents)))
+                } else {
+                    Ok(documents.next())
+                }
+            })
+        },
+    )
+}
+
+/// Query field of one document: by `documentId` in a collection, the only
+/// document of a single type.
+fn document_query_field<S: AppState>(
+    document_type: &'static DocumentType,
+    object_type: &str,
+) -> Field {
+    let collection = document_type.kind == DocumentKind::Collection;
+    let mut field = Field::new(
+        field_name(document_type.info.singular_name.as_ref()),
+        TypeRef::named(object_type),
+        move |ctx| {
+            FieldFuture::new(async move {
+                let state = ctx.data::<S>()?;
+                let populate = selected_relations(document_type, ctx.look_ahead());
+                let query = document_query(&ctx, state, document_type, populate)?;
+                let status = query.status;
+
+                let instance = if collection {
+                    let document_instance_id = document_id_argument(&ctx)?;
+                    let cmd = FindByIdCommand {
+                        document_type,
+                        document_instance_id,
+                        populate: query.populate,
+                        populate_filters: query.populate_filters,
+                        populate_options: query.populate_options,
+                        query: DocumentInstanceQuery::new().with_status(status),
+                    };
+                    state
+                        .documents_service()
+                        .find_by_id(cmd)
+                        .await
+                        .map_err(service_error)?
+                } else {
+                    let cmd = FindDocumentsCommand {
+                        document_type,
+                        populate: query.populate,
+                        populate_filters: query.populate_filters,
+                        populate_options: query.populate_options,
+                        populate_strategy: query.populate_strategy,
+                        with_count: Vec::new(),
+                        query: DocumentInstanceQuery::new()
+                            .paginate(1, 1)
+                            .with_status(status),
+                    };
+                    let (documents, _) = state
+                        .documents_service()
+                        .find(cmd)
+                        .await
+                        .map_err(service_error)?;
+                    documents.into_iter().next()
+                };
+
+                Ok(instance
+                    .map(|instance| FieldValue::owned_any(ResolvedDocument { instance, status })))
+            })
+        },
+    )
+    .argument(InputValue::new("status", TypeRef::named(STATUS_ENUM)));
+    if collection {
+        field = field.argument(InputValue::new(
+            "documentId",
+            TypeRef::named_nn(TypeRef::ID),
+        ));
+    }
+    field
+}
+
+/// Query field of pages of documents of a collection.
+///
+/// `filters` and `sort` take the attribute ids of the REST API, e.g.
+/// `sort: ["name:desc"]`; as the operators of filters start with `$`, filters
+/// are passed as a variable: `{"name": {"$contains": "a"}}`.
+fn collection_query_field<S: AppState>(
+    document_type: &'static DocumentType,
+    page_type: &str,
+) -> Field {
+    Field::new(
+        field_name(document_type.info.plural_name.as_ref()),
+        TypeRef::named_nn(page_type),
+        move |ctx| {
+            FieldFuture::new(async move {
+                let state = ctx.data::<S>()?;
+                let populate = selected_relations(document_type, ctx.look_ahead().field("data"));
+                let query = document_query(&ctx, state, document_type, populate)?;
+                let (page, page_size) = query.pagination;
+                let status = query.status;
+
+                let mut instance_query = DocumentInstanceQuery::new()
+                    .paginate(page, page_size)
+                    .with_status(status)
+                    .with_filter(query.filter);
+                instance_query.sort = query.sorts;
+                instance_query.random_order = query.random_order;
+                instance_query.sample = query.sample;
+                let cmd = FindDocumentsCommand {
+                    document_type,
+                    populate: query.populate,
+                    populate_filters: query.populate_filters,
+                    populate_options: query.populate_options,
+                    populate_strategy: query.populate_strategy,
+                    with_count: Vec::new(),
+                    query: instance_query,
+                };
+                let (documents, total) = state
+                    .documents_service()
+                    .find(cmd)
+                    .await
+                    .map_err(service_error)?;
+
+                let documents = documents
+                    .into_iter()
+                    .map(|instance| ResolvedDocument { instance, status })
+                    .collect();
+                Ok(Some(FieldValue::owned_any(ResolvedPage {
+                    documents,
+                    page,
+                    page_size,
+                    total,
+                })))
+            })
+        },
+    )
+    .argument(InputValue::new("filters", TypeRef::named(JSON_SCALAR)))
+    .argument(InputValue::new(
+        "sort",
+        TypeRef::named_nn_list(TypeRef::STRING),
+    ))
+    .argument(InputValue::new(
+        "pagination",
+        TypeRef::named(PAGINATION_INPUT),
+    ))
+    .argument(InputValue::new("status", TypeRef::named(STATUS_ENUM)))
+}
+
+fn page_object(page_type: &str, object_type: &str) -> Object {
+    Object::new(page_type)
+        .field(Field::new(
+            "data",
+            TypeRef::named_nn_list_nn(object_type),
+            |ctx| {
+                let parent = ctx.parent_value;
+                FieldFuture::new(async move {
+                    let page = parent.try_downcast_ref::<ResolvedPage>()?;
+                    Ok(Some(FieldValue::list(
+                        page.documents
+                            .iter()
+                            .map(|document| FieldValue::borrowed_any(document)),
+                    )))
+                })
+            },
+        ))
+        .field(Field::new(
+            "meta",
+            TypeRef::named_nn(PAGE_META_TYPE),
+            |ctx| {
+                let parent = ctx.parent_value;
+                FieldFuture::new(async move {
+                    let page = parent.try_downcast_ref::<ResolvedPage>()?;
+                    Ok(Some(FieldValue::borrowed_any(page)))
+                })
+            },
+        ))
+}
+
+fn page_meta_object() -> Object {
+    let meta_field = |name: &str, value: fn(&ResolvedPage) -> GraphqlValue| {
+        Field::new(name, TypeRef::named_nn(TypeRef::INT), move |ctx| {
+            let value = ctx
+                .parent_value
+                .try_downcast_ref::<ResolvedPage>()
+                .map(|page| Some(value(page)));
+            FieldFuture::new(async move { value })
+        })
+    };
+    Object::new(PAGE_META_TYPE)
+        .field(meta_field("page", |page| page.page.into()))
+        .field(meta_field("pageSize", |page| page.page_size.into()))
+        .field(meta_field("total", |page| page.total.into()))
+}
+
+/// Owning relations of `document_type` selected in `selection`, which are
+/// populated with the documents.
+pub(super) fn selected_relations(
+    document_type: &DocumentType,
+    selection: Lookahead<'_>,
+) -> Vec<AttributeId> {
+    document_type
+        .relations
+        .iter()
+        .filter(|relation| relation.relation_type.is_owning())
+        .filter(|relation| {
+            selection
+                .field(&attribute_name(document_type, &relation.id))
+                .exists()
+        })
+        .map(|relation| relation.id.clone())
+        .collect()
+}
+
+/// The `documentId` argument of `ctx`.
+pub(super) fn document_id_argument(
+    ctx: &ResolverContext<'_>,
+) -> Result<DocumentInstanceId, GraphqlError> {
+    let id = ctx.args.try_get("documentId")?.string()?;
+    DocumentInstanceId::try_from(id).map_err(|_| {
+        graphql_error(ApiError::UnprocessableEntity(format!(
+            "Invalid documentId: {id}"
+        )))
+    })
+}
+
+/// Query of the arguments of `ctx`, validated as the query parameters of the
+/// REST API.
+fn document_query<S: AppState>(
+    ctx: &ResolverContext<'_>,
+    state: &S,
+    document_type: &DocumentType,
+    populate: Vec<AttributeId>,
+) -> Result<DocumentQuery, GraphqlError> {
+    let mut query_map = Map::new();
+    if let Some(filters) = ctx.args.get("filters") {
+        query_map.insert(
+            "filters".to_string(),
+            filters.as_value().clone().into_json()?,
+        );
+    }
+    if let Some(sort) = ctx.args.get("sort") {
+        let sort = sort.list()?;
+        let sorts = sort
+            .iter()
+            .map(|item| item.string())
+            .collect::<Result<Vec<_>, _>>()?;
+        query_map.insert("sort".to_string(), JsonValue::String(sorts.join(",")));
+    }
+    if let Some(pagination) = ctx.args.get("pagination") {
+        query_map.insert(
+            "pagination".to_string(),
+            pagination.as_value().clone().into_json()?,
+        );
+    }
+    if let Some(status) = ctx.args.get("status") {
+        query_map.insert(
+            "status".to_string(),
+            JsonValue::String(status.enum_name()?.to_lowercase()),
+        );
+    }
+    if !populate.is_empty() {
+        let populate: Vec<String> = populate.iter().map(ToString::to_string).collect();
+        query_map.insert("populate".to_string(), json!(populate));
+    }
+
+    parse_query(
+        &query_map,
+        document_type,
+        state.document_types(),
+        &state.pagination_settings(),
+        &state.query_limits(),
+    )
+    .map_err(graphql_error)
+}
+
+pub(super) fn service_error(error: ServiceError) -> GraphqlError {
+    graphql_error(ApiError::from(error))
+}
+
+fn publication_status(instance: &DocumentInstance) -> &'static str {
+    match instance.content.publication_state {
+        PublicationState::Published { .. } => "published",
+        PublicationState::Draft { .. } if instance.modified => "modified",
+        PublicationState::Draft { .. } => "draft",
+    }
+}
+
+pub(super) fn content_type(field_type: FieldType) -> TypeRef {
+    let name = match field_type {
+        FieldType::Integer(_) => TypeRef::INT,
+        FieldType::Decimal { .. } => TypeRef::FLOAT,
+        FieldType::Boolean => TypeRef::BOOLEAN,
+        FieldType::LocalizedText | FieldType::Json => JSON_SCALAR,
+        FieldType::Uid
+        | FieldType::Uuid
+        | FieldType::Text
+        | FieldType::Date
+        | FieldType::DateTime
+        | FieldType::LocalDateTime
+        | FieldType::Time => TypeRef::STRING,
+    };
+    TypeRef::named(name)
+}
+
+/// GraphQL field name of an id: `partner-categories` → `partnerCategories`.
+pub(super) fn field_name(id: &str) -> String {
+    let mut name = String::with_capacity(id.len());
+    let mut word_start = false;
+    for c in id.chars() {
+        if !c.is_ascii_alphanumeric() {
+            word_start = !name.is_empty();
+        } else if word_start {
+            name.push(c.to_ascii_uppercase());
+            word_start = false;
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+/// GraphQL field name of an attribute of `document_type`: the `apiName` its
+/// schema gives it, or else the field name of its id.
+pub(super) fn attribute_name(document_type: &DocumentType, attribute: &AttributeId) -> String {
+    document_type
+        .api_name(attribute)
+        .map(String::from)
+        .unwrap_or_else(|| field_name(attribute.as_ref()))
+}
+
+/// GraphQL type name of an id: `partner-category` → `PartnerCategory`.
+fn type_name(id: &str) -> String {
+    let mut name = field_name(id);
+    if let Some(first) = name.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_are_camel_cased() {
+        assert_eq!(field_name("partner-categories"), "partnerCategories");
+        assert_eq!(field_name("short_description"), "shortDescription");
+        assert_eq!(field_name("name"), "name");
+        assert_eq!(type_name("partner-category"), "PartnerCategory");
+        assert_eq!(type_name("brand"), "Brand");
+    }
+}
@@ -0,0 +1,198 @@
+use chrono::{DateTime, Utc};
+use luminair_common::entities::{Feed, LocalizationId};
+use luminair_common::{AttributeId, DocumentType};
+
+use crate::domain::document::DocumentInstance;
+use crate::domain::document::content::{ContentValue, DomainValue};
+
+/// Media type of the RSS feeds of the collections.
+pub const RSS_CONTENT_TYPE: &str = "application/rss+xml; charset=utf-8";
+
+/// An RSS 2.0 feed of the newest published documents of a collection type,
+/// mapped to entries by the [`Feed`] of its options.
+#[derive(Debug, Clone)]
+pub struct RssFeed {
+    pub title: String,
+    pub description: String,
+    /// URL of the collection the entries are read from
+    pub link: String,
+    /// URL the feed is served at
+    pub self_link: String,
+    pub entries: Vec<FeedEntry>,
+}
+
+/// An entry of an [`RssFeed`].
+#[derive(Debug, Clone)]
+pub struct FeedEntry {
+    pub title: String,
+    pub description: String,
+    pub date: DateTime<Utc>,
+    /// URL of the document
+    pub link: String,
+    /// document id, the permanent id of the entry
+    pub guid: String,
+}
+
+impl RssFeed {
+    /// Feed of `documents` of `document_type`, whose links are below
+    /// `link`; localized texts are taken in `locale`, else in the first
+    /// localization of the type.
+    pub fn new(
+        document_type: &DocumentType,
+        feed: &Feed,
+        documents: Vec<DocumentInstance>,
+        locale: Option<&LocalizationId>,
+        link: String,
+        self_link: String,
+    ) -> Self {
+        let locale = locale.or_else(|| {
+            document_type
+                .options
+                .as_ref()
+                .and_then(|options| options.localizations.first())
+        });
+        let entries = documents
+            .into_iter()
+            .filter_map(|document| {
+                let field = |id: &AttributeId| document.content.fields.get(id);
+                let date = field(&feed.date).and_then(date_of)?;
+                Some(FeedEntry {
+                    title: field(&feed.title)
+                        .and_then(|value| text_of(value, locale))
+                        .unwrap_or_default(),
+                    description: field(&feed.description)
+                        .and_then(|value| text_of(value, locale))
+                        .unwrap_or_default(),
+                    date,
+                    link: format!("{}/{}", link, document.document_id.0),
+                    guid: document.document_id.0.to_string(),
+                })
+            })
+            .collect();
+        Self {
+            title: document_type.info.title.to_string(),
+            description: document_type
+                .info
+                .description
+                .clone()
+                .unwrap_or_else(|| document_type.info.title.to_string()),
+            link,
+            self_link,
+            entries,
+        }
+    }
+
+    /// The feed as an RSS 2.0 document, linking itself the Atom way.
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        xml.push_str(r#"<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom"><channel>"#);
+        xml.push_str(&format!(
+            "<title>{}</title><link>{}</link><description>{}</description>",
+            escape(&self.title),
+            escape(&self.link),
+            escape(&self.description)
+        ));
+        xml.push_str(&format!(
+            r#"<atom:link href="{}" rel="self" type="application/rss+xml"/>"#,
+            escape(&self.self_link)
+        ));
+        if let Some(newest) = self.entries.iter().map(|entry| entry.date).max() {
+            xml.push_str(&format!(
+                "<lastBuildDate>{}</lastBuildDate>",
+                newest.to_rfc2822()
+            ));
+        }
+        for entry in &self.entries {
+            xml.push_str(&format!(
+                concat!(
+                    "<item><title>{}</title><link>{}</link><description>{}</description>",
+                    r#"<pubDate>{}</pubDate><guid isPermaLink="false">{}</guid></item>"#
+                ),
+                escape(&entry.title),
+                escape(&entry.link),
+                escape(&entry.description),
+                entry.date.to_rfc2822(),
+                escape(&entry.guid)
+            ));
+        }
+        xml.push_str("</channel></rss>");
+        xml
+    }
+}
+
+fn text_of(value: &ContentValue, locale: Option<&LocalizationId>) -> Option<String> {
+    match value {
+        ContentValue::Scalar(DomainValue::Text(text)) => Some(text.clone()),
+        ContentValue::LocalizedText(texts) => texts.get(locale?.as_ref()).cloned(),
+        _ => None,
+    }
+}
+
+fn date_of(value: &ContentValue) -> Option<DateTime<Utc>> {
+    match value {
+        ContentValue::Scalar(DomainValue::DateTime(date)) => Some(*date),
+        ContentValue::Scalar(DomainValue::Date(date)) => Some(date.and_hms_opt(0, 0, 0)?.and_utc()),
+        _ => None,
+    }
+}
+
+/// `text` with the XML markup characters escaped.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_feed_is_escaped_rss() {
+        let date = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let feed = RssFeed {
+            title: "News & Views".to_string(),
+            description: "News".to_string(),
+            link: "https://cms.example.com/api/documents/articles".to_string(),
+            self_link: "https://cms.example.com/api/documents/articles/feed.rss".to_string(),
+            entries: vec![FeedEntry {
+                title: "<b>Launch</b>".to_string(),
+                description: "It's \"here\"".to_string(),
+                date,
+                link: "https://cms.example.com/api/documents/articles/1".to_string(),
+                guid: "1".to_string(),
+            }],
+        };
+
+        let xml = feed.to_xml();
+        assert!(xml.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?><rss version="2.0""#));
+        assert!(xml.contains("<title>News &amp; Views</title>"), "{xml}");
+        assert!(
+            xml.contains(r#"<atom:link href="https://cms.example.com/api/documents/articles/feed.rss" rel="self""#),
+            "{xml}"
+        );
+        assert!(
+            xml.contains("<title>&lt;b&gt;Launch&lt;/b&gt;</title>"),
+            "{xml}"
+        );
+        assert!(
+            xml.contains("<description>It&apos;s &quot;here&quot;</description>"),
+            "{xml}"
+        );
+        assert!(
+            xml.contains("<pubDate>Sun, 1 Mar 2026 12:00:00 +0000</pubDate>"),
+            "{xml}"
+        );
+        assert!(xml.ends_with("</item></channel></rss>"), "{xml}");
+    }
+}
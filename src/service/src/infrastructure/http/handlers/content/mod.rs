@@ -1,28 +1,45 @@
-use crate::application::AppState;
 use crate::application::commands::{
-    CreateDocumentWithRelationsCommand, DeleteDocumentCommand, FindByIdCommand,
-    FindDocumentsCommand, PublishDocumentCommand, UpdateDocumentWithRelationsCommand,
+    AggregateDocumentsCommand, CloneLocaleCommand, CreateDocumentWithRelationsCommand,
+    DeleteDocumentCommand, DocumentExistsCommand, FindByIdCommand, FindDocumentsCommand,
+    FindReferencesCommand, FindRevisionsCommand, MoveToStageCommand, PublishDocumentCommand,
+    RestoreRevisionCommand, SearchDocumentsCommand, StreamDocumentsCommand,
+    UpdateDocumentWithRelationsCommand,
 };
+use crate::application::error::ServiceError;
 use crate::application::service::DocumentsService;
+use crate::application::{AppState, ResponseFormat};
 use crate::domain::document::DocumentInstanceId;
-use crate::domain::query::DocumentInstanceQuery;
+use crate::domain::query::{DocumentInstanceQuery, DocumentStatus, Sort, SortDirection};
+use crate::infrastructure::http::actor::Actor;
 use crate::infrastructure::http::api::{ApiError, ApiSuccess};
+use crate::infrastructure::http::handlers::content::feed::{RSS_CONTENT_TYPE, RssFeed};
 use crate::infrastructure::http::handlers::content::response::{
-    ManyDocumentsResponse, OneDocumentResponse,
+    AggregateResponse, CapabilitiesResponse, DistinctValuesResponse, ManyDocumentsResponse,
+    NDJSON_CONTENT_TYPE, OneDocumentResponse, ReferencesResponse, RevisionsResponse,
+    SearchResponse, StrapiManyDocumentsResponse, StrapiOneDocumentResponse, cache_headers,
 };
+use crate::infrastructure::http::path::ApiPath;
 use crate::infrastructure::http::querystring::QueryMap;
 use axum::Json;
-use axum::extract::{Path, State};
-use axum::http::StatusCode;
+use axum::body::Body;
+use axum::extract::{OriginalUri, Path, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use futures::{TryStreamExt, future};
+use luminair_common::entities::{ApiOperation, DocumentKind, WorkflowStageId};
 use luminair_common::{DocumentType, DocumentTypeApiId};
 use std::str::FromStr;
 
-mod query_params;
-mod request_body;
-mod response;
+pub(crate) mod feed;
+pub(crate) mod query_params;
+pub(crate) mod request_body;
+pub(crate) mod response;
+
+/// Longest search query accepted, in characters.
+const MAX_SEARCH_LENGTH: usize = 256;
 
 /// Resolve a `{api_type}` path segment to a registered [`DocumentType`].
-fn resolve_document_type<S: AppState>(
+pub(crate) fn resolve_document_type<S: AppState>(
     state: &S,
     api_type: &str,
 ) -> Result<&'static DocumentType, ApiError> {
@@ -34,11 +51,49 @@ fn resolve_document_type<S: AppState>(
         .ok_or_else(|| ApiError::NotFound(format!("Document type '{}' not found", api_type)))
 }
 
+/// Resolve a `{api_type}` path segment like [`resolve_document_type`], for
+/// an `operation` the document type must expose; the operations it doesn't
+/// are answered as not allowed.
+pub(crate) fn resolve_exposed_document_type<S: AppState>(
+    state: &S,
+    api_type: &str,
+    operation: ApiOperation,
+) -> Result<&'static DocumentType, ApiError> {
+    let document_type = resolve_document_type(state, api_type)?;
+    if !document_type.exposes(operation) {
+        return Err(ApiError::MethodNotAllowed(format!(
+            "Document type '{}' doesn't expose the '{}' operation",
+            api_type,
+            operation.as_str()
+        )));
+    }
+    Ok(document_type)
+}
+
+/// Whether the client asked for a newline-delimited JSON export.
+fn accepts_ndjson(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media| {
+            media
+                .split(';')
+                .next()
+                .is_some_and(|media| media.trim() == NDJSON_CONTENT_TYPE)
+        })
+}
+
+/// Handle reading one document.
+///
+/// The document is answered in the configured [`ResponseFormat`], with the
+/// caching headers of [`cache_headers`].
 pub async fn find_document_by_id<S: AppState>(
     State(state): State<S>,
-    Path((api_type, id)): Path<(String, String)>,
+    ApiPath((api_type, document_instance_id)): ApiPath<(String, DocumentInstanceId)>,
     QueryMap(query_map): QueryMap,
-) -> Result<ApiSuccess<OneDocumentResponse>, ApiError> {
+) -> Result<Response, ApiError> {
     if query_map.contains_key("pagination") {
         return Err(ApiError::UnprocessableEntity(
             "Pagination param isn't eligible for find_by_id query".to_string(),
@@ -49,14 +104,19 @@ pub async fn find_document_by_id<S: AppState>(
             "Sort param isn't eligible for find_by_id query".to_string(),
         ));
     }
+    if query_map.contains_key("withCount") {
+        return Err(ApiError::UnprocessableEntity(
+            "WithCount param isn't eligible for find_by_id query".to_string(),
+        ));
+    }
 
-    let document_type = resolve_document_type(&state, &api_type)?;
-    let document_instance_id = DocumentInstanceId::try_from(&id)?;
+    let document_type = resolve_exposed_document_type(&state, &api_type, ApiOperation::FindOne)?;
     let q = query_params::parse_query(
         &query_map,
         document_type,
         state.document_types(),
         &state.pagination_settings(),
+        &state.query_limits(),
     )?;
 
     let query = DocumentInstanceQuery::new().with_status(q.status);
@@ -66,29 +126,125 @@ pub async fn find_document_by_id<S: AppState>(
         document_instance_id,
         populate: q.populate,
         populate_filters: q.populate_filters,
+        populate_options: q.populate_options,
         query,
     };
 
     let document_instance = state.documents_service().find_by_id(cmd).await?;
+    let headers = cache_headers(document_type, q.status, document_instance.as_slice());
+
+    match state.response_format() {
+        ResponseFormat::Native => OneDocumentResponse::from_optional(
+            document_instance,
+            document_type,
+            state.document_types(),
+        )
+        .map(|response| ApiSuccess::new(StatusCode::OK, response).into_response()),
+        ResponseFormat::Strapi => document_instance.map(|document| {
+            let response =
+                StrapiOneDocumentResponse::new(document, document_type, state.document_types());
+            ApiSuccess::new(StatusCode::OK, response).into_response()
+        }),
+    }
+    .map(|response| (headers, response).into_response())
+    .ok_or_else(|| {
+        ApiError::NotFound(format!(
+            "Document instance with ID '{}' not found",
+            document_instance_id.0
+        ))
+    })
+}
+
+/// Handle checking that a document exists, in `?status=published` (default)
+/// or `draft`, answering `200` without a body, or `404`.
+///
+/// The document isn't read, which keeps referential checks of clients cheap.
+pub async fn document_exists<S: AppState>(
+    State(state): State<S>,
+    ApiPath((api_type, document_instance_id)): ApiPath<(String, DocumentInstanceId)>,
+    QueryMap(query_map): QueryMap,
+) -> Result<StatusCode, ApiError> {
+    let document_type = resolve_exposed_document_type(&state, &api_type, ApiOperation::FindOne)?;
+    let status = query_params::parse_exists_query(&query_map)?;
 
-    OneDocumentResponse::from_optional(document_instance)
-        .map(|response| ApiSuccess::new(StatusCode::OK, response))
-        .ok_or_else(|| ApiError::NotFound(format!("Document instance with ID '{}' not found", id)))
+    let cmd = DocumentExistsCommand {
+        document_type,
+        document_instance_id,
+        status,
+    };
+
+    if state.documents_service().exists(cmd).await? {
+        Ok(StatusCode::OK)
+    } else {
+        Err(ApiError::NotFound(format!(
+            "Document instance with ID '{}' not found",
+            document_instance_id.0
+        )))
+    }
 }
 
+/// Handle listing documents, in the configured [`ResponseFormat`], with the
+/// caching headers of [`cache_headers`].
+///
+/// With `Accept: application/x-ndjson` the whole filtered and sorted
+/// collection is streamed as one document per line instead of a page, so
+/// large exports don't have to fit into memory.
 pub async fn find_all_documents<S: AppState>(
     State(state): State<S>,
     Path(api_type): Path<String>,
     QueryMap(query_map): QueryMap,
-) -> Result<ApiSuccess<ManyDocumentsResponse>, ApiError> {
-    let document_type = resolve_document_type(&state, &api_type)?;
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let document_type = resolve_exposed_document_type(&state, &api_type, ApiOperation::Find)?;
     let q = query_params::parse_query(
         &query_map,
         document_type,
         state.document_types(),
         &state.pagination_settings(),
+        &state.query_limits(),
     )?;
 
+    if accepts_ndjson(&headers) {
+        if q.populate.is_some() {
+            return Err(ApiError::UnprocessableEntity(
+                "populate is not supported for NDJSON exports".to_string(),
+            ));
+        }
+        if !q.with_count.is_empty() {
+            return Err(ApiError::UnprocessableEntity(
+                "withCount is not supported for NDJSON exports".to_string(),
+            ));
+        }
+
+        let mut query = DocumentInstanceQuery::new()
+            .with_status(q.status)
+            .with_filter(q.filter);
+        query.sort = q.sorts;
+        query.random_order = q.random_order;
+        query.sample = q.sample;
+
+        let registry = state.document_types();
+        let lines = state
+            .documents_service()
+            .stream(StreamDocumentsCommand {
+                document_type,
+                query,
+            })
+            .and_then(|document| {
+                future::ready(
+                    response::ndjson_line(document, document_type, registry)
+                        .map_err(|e| ServiceError::Internal(anyhow::Error::from(e))),
+                )
+            })
+            .inspect_err(|e| tracing::error!("NDJSON export failed: {}", e));
+
+        return Ok((
+            [(header::CONTENT_TYPE, NDJSON_CONTENT_TYPE)],
+            Body::from_stream(lines),
+        )
+            .into_response());
+    }
+
     let (page, page_size) = q.pagination;
     let mut query = DocumentInstanceQuery::new()
         .paginate(page, page_size)
@@ -96,40 +252,319 @@ pub async fn find_all_documents<S: AppState>(
         .with_filter(q.filter);
 
     query.sort = q.sorts;
+    query.random_order = q.random_order;
+    query.sample = q.sample;
 
     let cmd = FindDocumentsCommand {
         document_type,
         populate: q.populate,
         populate_filters: q.populate_filters,
+        populate_options: q.populate_options,
+        populate_strategy: q.populate_strategy,
+        with_count: q.with_count,
         query,
     };
 
     let (documents, total) = state.documents_service().find(cmd).await?;
+    let headers = cache_headers(document_type, q.status, &documents);
+
+    let response = match state.response_format() {
+        ResponseFormat::Native => ApiSuccess::new(
+            StatusCode::OK,
+            ManyDocumentsResponse::new(
+                documents,
+                document_type,
+                state.document_types(),
+                page,
+                page_size,
+                total,
+            ),
+        )
+        .into_response(),
+        ResponseFormat::Strapi => ApiSuccess::new(
+            StatusCode::OK,
+            StrapiManyDocumentsResponse::new(
+                documents,
+                document_type,
+                state.document_types(),
+                page,
+                page_size,
+                total,
+            ),
+        )
+        .into_response(),
+    };
+    Ok((headers, response).into_response())
+}
+
+/// Handle the RSS feed of a collection type whose options declare a feed:
+/// its newest published documents by the date field of the feed, as many
+/// as a page of the default size holds.
+///
+/// The links of the feed are absolute, by the `Host` of the request and
+/// its `X-Forwarded-Proto`, if any.
+pub async fn document_feed<S: AppState>(
+    State(state): State<S>,
+    Path(api_type): Path<String>,
+    OriginalUri(uri): OriginalUri,
+    QueryMap(query_map): QueryMap,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let document_type = resolve_exposed_document_type(&state, &api_type, ApiOperation::Find)?;
+    let feed = document_type
+        .options
+        .as_ref()
+        .and_then(|options| options.feed.as_ref())
+        .ok_or_else(|| {
+            ApiError::NotFound(format!("Document type '{}' has no feed", document_type.id))
+        })?;
+    let locale = request_body::parse_locale(&query_map, document_type)?;
+
+    let mut query = DocumentInstanceQuery::new()
+        .paginate(1, state.pagination_settings().default_page_size)
+        .with_status(DocumentStatus::Published)
+        .filter_is_not_null(feed.date.to_string());
+    query.sort = vec![Sort {
+        field: feed.date.to_string(),
+        direction: SortDirection::Descending,
+    }];
+    let cmd = FindDocumentsCommand {
+        document_type,
+        populate: None,
+        populate_filters: None,
+        populate_options: Default::default(),
+        populate_strategy: Default::default(),
+        with_count: Vec::new(),
+        query,
+    };
+    let (documents, _) = state.documents_service().find(cmd).await?;
+    let mut response_headers = cache_headers(document_type, DocumentStatus::Published, &documents);
+
+    let origin = format!(
+        "{}://{}",
+        header_value(&headers, "x-forwarded-proto").unwrap_or("http"),
+        header_value(&headers, header::HOST.as_str()).unwrap_or("localhost")
+    );
+    let feed_path = uri.path();
+    let collection_path = feed_path.strip_suffix("/feed.rss").unwrap_or(feed_path);
+    let rss = RssFeed::new(
+        document_type,
+        feed,
+        documents,
+        locale.as_ref(),
+        format!("{origin}{collection_path}"),
+        format!("{origin}{feed_path}"),
+    );
+
+    response_headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(RSS_CONTENT_TYPE),
+    );
+    Ok((response_headers, rss.to_xml()).into_response())
+}
+
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Handle `OPTIONS` on the documents of a type: the methods the route
+/// allows by the operations the type exposes, in `Allow`, and what the
+/// documents can be queried by, see [`CapabilitiesResponse`].
+pub async fn document_collection_options<S: AppState>(
+    State(state): State<S>,
+    Path(api_type): Path<String>,
+) -> Result<Response, ApiError> {
+    let routes = [("GET", ApiOperation::Find), ("POST", ApiOperation::Create)];
+    capabilities(&state, &api_type, &routes)
+}
+
+/// Handle `OPTIONS` on a document, like [`document_collection_options`].
+pub async fn document_options<S: AppState>(
+    State(state): State<S>,
+    Path((api_type, _document_id)): Path<(String, String)>,
+) -> Result<Response, ApiError> {
+    let routes = [
+        ("GET", ApiOperation::FindOne),
+        ("PUT", ApiOperation::Update),
+        ("DELETE", ApiOperation::Delete),
+    ];
+    capabilities(&state, &api_type, &routes)
+}
+
+/// Capabilities of a route of `api_type` with the methods of `routes`, each
+/// allowed when the document type exposes its operation; a `GET` allows
+/// `HEAD` too.
+fn capabilities<S: AppState>(
+    state: &S,
+    api_type: &str,
+    routes: &[(&'static str, ApiOperation)],
+) -> Result<Response, ApiError> {
+    let document_type = resolve_document_type(state, api_type)?;
+    let mut methods = Vec::new();
+    for (method, operation) in routes {
+        if document_type.exposes(*operation) {
+            methods.push(*method);
+            if *method == "GET" {
+                methods.push("HEAD");
+            }
+        }
+    }
+    methods.push("OPTIONS");
+
+    let allow = HeaderValue::from_str(&methods.join(", "))
+        .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+    let response = CapabilitiesResponse::new(document_type, methods, &state.pagination_settings());
+    Ok((
+        [(header::ALLOW, allow)],
+        ApiSuccess::new(StatusCode::OK, response),
+    )
+        .into_response())
+}
+
+/// Handle grouping documents and computing metrics per group,
+/// e.g. `?groupBy=category&sum=price`.
+pub async fn aggregate_documents<S: AppState>(
+    State(state): State<S>,
+    Path(api_type): Path<String>,
+    QueryMap(query_map): QueryMap,
+) -> Result<ApiSuccess<AggregateResponse>, ApiError> {
+    let document_type = resolve_exposed_document_type(&state, &api_type, ApiOperation::Find)?;
+    let query =
+        query_params::parse_aggregate_query(&query_map, document_type, state.document_types())?;
+
+    let groups = state
+        .documents_service()
+        .aggregate(AggregateDocumentsCommand {
+            document_type,
+            query,
+        })
+        .await?;
 
     Ok(ApiSuccess::new(
         StatusCode::OK,
-        ManyDocumentsResponse::new(documents, page, page_size, total),
+        AggregateResponse::new(groups),
     ))
 }
 
+/// Handle listing the sorted distinct values of a field, counted with `?count`.
+pub async fn distinct_field_values<S: AppState>(
+    State(state): State<S>,
+    Path((api_type, attribute)): Path<(String, String)>,
+    QueryMap(query_map): QueryMap,
+) -> Result<ApiSuccess<DistinctValuesResponse>, ApiError> {
+    let document_type = resolve_exposed_document_type(&state, &api_type, ApiOperation::Find)?;
+    let query = query_params::parse_distinct_query(
+        &query_map,
+        document_type,
+        state.document_types(),
+        &attribute,
+    )?;
+    let field = query.group_by[0].clone();
+
+    let groups = state
+        .documents_service()
+        .aggregate(AggregateDocumentsCommand {
+            document_type,
+            query,
+        })
+        .await?;
+
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        DistinctValuesResponse::new(&field, groups),
+    ))
+}
+
+/// Handle the full-text search of `?q=` across the published documents of
+/// every collection type with text fields, the most relevant first, at most
+/// `?limit=` of them.
+pub async fn search_documents<S: AppState>(
+    State(state): State<S>,
+    QueryMap(query_map): QueryMap,
+) -> Result<ApiSuccess<SearchResponse>, ApiError> {
+    let text = query_map
+        .get("q")
+        .and_then(|q| q.as_str())
+        .map(str::trim)
+        .filter(|q| !q.is_empty())
+        .ok_or_else(|| {
+            ApiError::UnprocessableEntity("The search needs a query in 'q'".to_string())
+        })?;
+    if text.chars().count() > MAX_SEARCH_LENGTH {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "The search query is longer than {} characters",
+            MAX_SEARCH_LENGTH
+        )));
+    }
+    let pagination = state.pagination_settings();
+    let limit = match query_map.get("limit") {
+        Some(limit) => limit
+            .as_str()
+            .and_then(|limit| limit.parse::<u16>().ok())
+            .filter(|limit| (1..=pagination.max_page_size).contains(limit))
+            .ok_or_else(|| {
+                ApiError::UnprocessableEntity(format!(
+                    "'limit' must be a number from 1 to {}",
+                    pagination.max_page_size
+                ))
+            })?,
+        None => pagination.default_page_size,
+    };
+
+    let mut document_types = state
+        .document_types()
+        .iterate()
+        .filter(|document_type| {
+            document_type.kind == DocumentKind::Collection
+                && document_type.exposes(ApiOperation::Find)
+                && document_type
+                    .fields
+                    .iter()
+                    .any(|field| field.field_type.is_text())
+        })
+        .collect::<Vec<_>>();
+    document_types.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let results = state
+        .documents_service()
+        .search(SearchDocumentsCommand {
+            document_types,
+            text: text.to_string(),
+            limit: u64::from(limit),
+        })
+        .await?;
+
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        SearchResponse::new(results, state.document_types()),
+    ))
+}
+
+/// Handle creating a document with its initial relations.
+///
+/// With `?locale=xx`, localized fields take the plain text of that locale.
 pub async fn create_new_document<S: AppState>(
     State(state): State<S>,
     Path(api_type): Path<String>,
+    QueryMap(query_map): QueryMap,
+    Actor(actor): Actor,
     Json(payload): Json<serde_json::Value>,
 ) -> Result<(StatusCode, axum::http::HeaderMap), ApiError> {
-    let document_type = resolve_document_type(&state, &api_type)?;
+    let document_type = resolve_exposed_document_type(&state, &api_type, ApiOperation::Create)?;
+    let locale = request_body::parse_locale(&query_map, document_type)?;
     let data_obj = request_body::extract_data_envelope(&payload)?;
     let classified = request_body::classify_document_data(data_obj, document_type)?;
 
-    let fields = request_body::build_fields_from_map(document_type, &classified.fields)
-        .map_err(|e| ApiError::UnprocessableEntity(e.to_string()))?;
+    let fields =
+        request_body::build_fields_from_map(document_type, &classified.fields, locale.as_ref())
+            .map_err(ApiError::from)?;
     let relation_operations = request_body::parse_relation_operations(&classified.relations)?;
 
     let cmd = CreateDocumentWithRelationsCommand {
         document_type,
         fields,
         relation_operations,
-        user_id: None,
+        actor,
     };
 
     let created_document_id = state.documents_service().create_with_relations(cmd).await?;
@@ -149,19 +584,27 @@ pub async fn create_new_document<S: AppState>(
 /// Handle updating document fields and/or modifying relations in a single PUT request.
 ///
 /// Accepts a flat JSON payload or a nested `{ "data": { ... } }` payload.
+/// An `If-Match: "<version>"` header makes the update conditional on the
+/// document version. With `?locale=xx`, localized fields take the plain text
+/// of that locale and the other translations are kept.
 pub async fn update_document_handler<S: AppState>(
     State(state): State<S>,
-    Path((api_type, id)): Path<(String, String)>,
+    ApiPath((api_type, document_instance_id)): ApiPath<(String, DocumentInstanceId)>,
+    QueryMap(query_map): QueryMap,
+    headers: HeaderMap,
+    Actor(actor): Actor,
     Json(payload): Json<serde_json::Value>,
 ) -> Result<StatusCode, ApiError> {
-    let document_type = resolve_document_type(&state, &api_type)?;
-    let document_instance_id = DocumentInstanceId::try_from(&id)?;
+    let document_type = resolve_exposed_document_type(&state, &api_type, ApiOperation::Update)?;
+    let expected_version = request_body::parse_if_match(&headers)?;
+    let locale = request_body::parse_locale(&query_map, document_type)?;
 
     let data_obj = request_body::extract_data_envelope(&payload)?;
     let classified = request_body::classify_document_data(data_obj, document_type)?;
 
-    let fields = request_body::build_fields_from_map(document_type, &classified.fields)
-        .map_err(|e| ApiError::UnprocessableEntity(e.to_string()))?;
+    let fields =
+        request_body::build_fields_from_map(document_type, &classified.fields, locale.as_ref())
+            .map_err(ApiError::from)?;
     let relation_operations = request_body::parse_relation_operations(&classified.relations)?;
 
     let cmd = UpdateDocumentWithRelationsCommand {
@@ -169,7 +612,9 @@ pub async fn update_document_handler<S: AppState>(
         document_id: document_instance_id,
         fields,
         relation_operations,
-        user_id: None,
+        locale,
+        expected_version,
+        actor,
     };
 
     state.documents_service().update_with_relations(cmd).await?;
@@ -179,14 +624,15 @@ pub async fn update_document_handler<S: AppState>(
 
 pub async fn delete_existing_document<S: AppState>(
     State(state): State<S>,
-    Path((api_type, id)): Path<(String, String)>,
+    ApiPath((api_type, document_instance_id)): ApiPath<(String, DocumentInstanceId)>,
+    Actor(actor): Actor,
 ) -> Result<StatusCode, ApiError> {
-    let document_type = resolve_document_type(&state, &api_type)?;
-    let document_instance_id = DocumentInstanceId::try_from(&id)?;
+    let document_type = resolve_exposed_document_type(&state, &api_type, ApiOperation::Delete)?;
 
     let cmd = DeleteDocumentCommand {
         document_type,
         document_instance_id,
+        actor,
     };
 
     state.documents_service().delete(cmd).await?;
@@ -195,17 +641,28 @@ pub async fn delete_existing_document<S: AppState>(
 }
 
 /// Handle publishing a draft document.
+///
+/// Honors an `If-Match: "<version>"` header like [`update_document_handler`].
+/// A `{"publishAt": "<timestamp>"}` body in the future schedules the publish
+/// instead, and `"unpublishAt"` schedules the document to be unpublished.
 pub async fn publish_document<S: AppState>(
     State(state): State<S>,
-    Path((api_type, id)): Path<(String, String)>,
+    ApiPath((api_type, document_instance_id)): ApiPath<(String, DocumentInstanceId)>,
+    headers: HeaderMap,
+    Actor(actor): Actor,
+    payload: Option<Json<serde_json::Value>>,
 ) -> Result<StatusCode, ApiError> {
-    let document_type = resolve_document_type(&state, &api_type)?;
-    let document_instance_id = DocumentInstanceId::try_from(&id)?;
+    let document_type = resolve_exposed_document_type(&state, &api_type, ApiOperation::Publish)?;
+    let expected_version = request_body::parse_if_match(&headers)?;
+    let schedule =
+        request_body::parse_publication_schedule(payload.as_ref().map(|Json(body)| body))?;
 
     let cmd = PublishDocumentCommand {
         document_type,
         document_id: document_instance_id,
-        user_id: None,
+        expected_version,
+        schedule,
+        actor,
     };
 
     state
@@ -216,3 +673,153 @@ pub async fn publish_document<S: AppState>(
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Handle moving a document to another stage of its editorial workflow.
+///
+/// Honors an `If-Match: "<version>"` header like [`update_document_handler`].
+/// The roles of the user are taken from the `X-User-Roles` header.
+pub async fn move_document_to_stage<S: AppState>(
+    State(state): State<S>,
+    ApiPath((api_type, document_instance_id, stage)): ApiPath<(String, DocumentInstanceId, String)>,
+    headers: HeaderMap,
+    Actor(actor): Actor,
+) -> Result<StatusCode, ApiError> {
+    let document_type = resolve_exposed_document_type(&state, &api_type, ApiOperation::Update)?;
+    let stage = WorkflowStageId::try_new(stage.as_str())
+        .map_err(|_| ApiError::UnprocessableEntity(format!("Invalid workflow stage: {}", stage)))?;
+    let expected_version = request_body::parse_if_match(&headers)?;
+
+    let cmd = MoveToStageCommand {
+        document_type,
+        document_id: document_instance_id,
+        stage,
+        expected_version,
+        actor,
+    };
+
+    state.documents_service().move_to_stage(cmd).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handle listing the revisions of a document, the most recent first.
+pub async fn find_document_revisions<S: AppState>(
+    State(state): State<S>,
+    ApiPath((api_type, document_instance_id)): ApiPath<(String, DocumentInstanceId)>,
+) -> Result<ApiSuccess<RevisionsResponse>, ApiError> {
+    let document_type = resolve_exposed_document_type(&state, &api_type, ApiOperation::FindOne)?;
+
+    let cmd = FindRevisionsCommand {
+        document_type,
+        document_id: document_instance_id,
+    };
+
+    let revisions = state.documents_service().find_revisions(cmd).await?;
+
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        RevisionsResponse::new(revisions, document_type, state.document_types()),
+    ))
+}
+
+/// Handle listing the documents linking to a document, per relation, with
+/// the `onDelete` action deleting the document would take on their links.
+pub async fn find_document_references<S: AppState>(
+    State(state): State<S>,
+    ApiPath((api_type, document_instance_id)): ApiPath<(String, DocumentInstanceId)>,
+) -> Result<ApiSuccess<ReferencesResponse>, ApiError> {
+    let document_type = resolve_exposed_document_type(&state, &api_type, ApiOperation::FindOne)?;
+
+    let cmd = FindReferencesCommand {
+        document_type,
+        document_id: document_instance_id,
+    };
+
+    let references = state.documents_service().find_references(cmd).await?;
+
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        ReferencesResponse::new(&references),
+    ))
+}
+
+/// Handle restoring the fields of a revision into the draft of a document.
+///
+/// Honors an `If-Match: "<version>"` header like [`update_document_handler`].
+pub async fn restore_document_revision<S: AppState>(
+    State(state): State<S>,
+    ApiPath((api_type, document_instance_id, version)): ApiPath<(
+        String,
+        DocumentInstanceId,
+        String,
+    )>,
+    headers: HeaderMap,
+    Actor(actor): Actor,
+) -> Result<StatusCode, ApiError> {
+    let document_type = resolve_exposed_document_type(&state, &api_type, ApiOperation::Update)?;
+    let version = version
+        .parse::<i32>()
+        .map_err(|_| ApiError::UnprocessableEntity(format!("Invalid revision: {}", version)))?;
+    let expected_version = request_body::parse_if_match(&headers)?;
+
+    let cmd = RestoreRevisionCommand {
+        document_type,
+        document_id: document_instance_id,
+        version,
+        expected_version,
+        actor,
+    };
+
+    state.documents_service().restore_revision(cmd).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handle starting a new translation of a document.
+///
+/// Copies the localized texts of the `?from=xx` locale (the first locale of
+/// the document type by default) into `{locale}` of the draft.
+pub async fn clone_document_locale<S: AppState>(
+    State(state): State<S>,
+    ApiPath((api_type, document_instance_id, locale)): ApiPath<(
+        String,
+        DocumentInstanceId,
+        String,
+    )>,
+    QueryMap(query_map): QueryMap,
+    Actor(actor): Actor,
+) -> Result<StatusCode, ApiError> {
+    let document_type = resolve_exposed_document_type(&state, &api_type, ApiOperation::Update)?;
+    let target = request_body::supported_locale(&locale, document_type)?;
+    let source = match query_map.get("from").and_then(|v| v.as_str()) {
+        Some(from) => request_body::supported_locale(from, document_type)?,
+        None => document_type
+            .options
+            .as_ref()
+            .and_then(|options| options.localizations.first().cloned())
+            .ok_or_else(|| {
+                ApiError::UnprocessableEntity(format!(
+                    "Document type '{}' has no localizations",
+                    api_type
+                ))
+            })?,
+    };
+    if source == target {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "Locale '{}' can't be cloned into itself",
+            target
+        )));
+    }
+
+    let cmd = CloneLocaleCommand {
+        document_type,
+        document_id: document_instance_id,
+        source,
+        target,
+        actor,
+    };
+
+    state.documents_service().clone_locale(cmd).await?;
+
+    Ok(StatusCode::CREATED)
+}
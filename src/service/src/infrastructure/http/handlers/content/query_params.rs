@@ -1,11 +1,21 @@
 use std::collections::HashMap;
 
-use luminair_common::{AttributeId, DocumentType, DocumentTypesRegistry, entities::FieldType};
+use chrono_tz::Tz;
+use luminair_common::{
+    AttributeId, DocumentType, DocumentTypesRegistry,
+    entities::{DocumentRelation, FieldType},
+};
 use serde_json::Value;
 
+use crate::application::QueryLimits;
 use crate::domain::document::content::DomainValue;
-use crate::domain::query::{DocumentStatus, FilterExpression, Sort, SortDirection};
+use crate::domain::query::{
+    AggregateFunction, AggregateQuery, DocumentStatus, FilterExpression, Metric, PopulateOptions,
+    PopulateStrategy, RandomOrder, Sort, SortDirection, TableSample,
+};
+use crate::domain::translation::locales;
 use crate::infrastructure::http::api::ApiError;
+use crate::infrastructure::http::handlers::content::request_body::payload_attribute;
 
 // ─── Constants ────────────────────────────────────────────────────────────────
 
@@ -13,6 +23,10 @@ use crate::infrastructure::http::api::ApiError;
 /// expands to every owning relation declared on the document type.
 const POPULATE_WILDCARD: &str = "*";
 
+/// The `sort` item ordering the documents at random; a field named `random`
+/// is sorted by with an explicit direction, `random:asc`.
+const RANDOM_SORT: &str = "random";
+
 // ─── Public output types ──────────────────────────────────────────────────────
 
 /// Schema-agnostic representation of every bracket query parameter.
@@ -20,27 +34,49 @@ const POPULATE_WILDCARD: &str = "*";
 /// Produced by [`parse_raw_query`] without any domain knowledge.
 /// Use [`parse_query`] to validate and resolve it against a [`DocumentType`].
 pub(super) struct RawQueryParams {
-    /// `?populate=*` / `?populate[]=field` / `?populate=field`
+    /// `?populate=*` / `?populate[]=field` / `?populate=field` / `?populate[field]=true`
     pub populate: Option<std::collections::HashSet<String>>,
-    /// `?pagination[page]=N&pagination[pageSize]=M`
-    pub pagination: (u16, u16),
+    /// `?populate[field][limit]=5` — the options of each relation, kept opaque
+    pub populate_options: HashMap<String, serde_json::Map<String, Value>>,
+    /// `?pagination[page]=N&pagination[pageSize]=M` — the page size as requested, not yet checked
+    pub pagination: (u16, u64),
     /// `?status=draft|published` — raw string, not yet validated against the domain enum
     pub status: String,
     /// `?sort=field:asc,other:desc`
     pub sorts: Vec<(String, SortDirection)>,
+    /// `?sort=random` — whether the bare `random` item is among the sorts
+    pub random_order: bool,
+    /// `?seed=42` — raw string, not yet validated
+    pub seed: Option<String>,
+    /// `?sample=5` — raw string, not yet validated
+    pub sample: Option<String>,
     /// `?filters[...]` — the nested JSON subtree, kept opaque for the validation layer
     pub filters: Option<Value>,
+    /// `?populateStrategy=batched|lateral` — raw string, not yet validated
+    pub populate_strategy: Option<String>,
+    /// `?withCount=brands,tags` / `?withCount[]=brands` — relation names, in order
+    pub with_count: Vec<String>,
+    /// `?timeZone=Europe/Bucharest` — raw string, not yet validated
+    pub time_zone: Option<String>,
 }
 
 /// Fully resolved, domain-validated query parameters ready for the application layer.
 #[derive(Debug)]
 pub struct DocumentQuery {
     pub populate: Option<Vec<AttributeId>>,
+    pub populate_options: HashMap<AttributeId, PopulateOptions>,
     pub pagination: (u16, u16),
     pub status: DocumentStatus,
     pub filter: FilterExpression,
     pub populate_filters: Option<HashMap<AttributeId, FilterExpression>>,
+    pub populate_strategy: PopulateStrategy,
+    /// owning relations whose related documents are counted, not populated
+    pub with_count: Vec<AttributeId>,
     pub sorts: Vec<Sort>,
+    /// random order of the documents, instead of `sorts`
+    pub random_order: Option<RandomOrder>,
+    /// sample of the documents the page is drawn from
+    pub sample: Option<TableSample>,
 }
 
 // ─── Phase 0: structural parse (no schema knowledge) ─────────────────────────
@@ -57,6 +93,7 @@ pub(super) fn parse_raw_query(
     use std::collections::HashSet;
 
     // populate
+    let mut populate_options = HashMap::new();
     let populate = match query_map.get("populate") {
         Some(Value::String(s)) => {
             let mut set = HashSet::new();
@@ -70,32 +107,19 @@ pub(super) fn parse_raw_query(
                 .collect::<HashSet<_>>();
             Some(set)
         }
+        Some(Value::Object(map)) => {
+            for (name, options) in map {
+                if let Value::Object(options) = options {
+                    populate_options.insert(name.clone(), options.clone());
+                }
+            }
+            Some(map.keys().cloned().collect())
+        }
         _ => None,
     };
 
     // pagination
-    let pagination = if let Some(Value::Object(pag_map)) = query_map.get("pagination") {
-        let page = pag_map
-            .get("page")
-            .and_then(|v| {
-                v.as_str()
-                    .and_then(|s| s.parse::<u16>().ok())
-                    .or_else(|| v.as_u64().map(|n| n as u16))
-            })
-            .unwrap_or(1);
-        let page_size = pag_map
-            .get("pageSize")
-            .and_then(|v| {
-                v.as_str()
-                    .and_then(|s| s.parse::<u16>().ok())
-                    .or_else(|| v.as_u64().map(|n| n as u16))
-            })
-            .unwrap_or(pagination_settings.default_page_size)
-            .min(pagination_settings.max_page_size);
-        (page, page_size)
-    } else {
-        (1, pagination_settings.default_page_size)
-    };
+    let pagination = raw_pagination(query_map, pagination_settings);
 
     // status
     let status = query_map
@@ -104,36 +128,67 @@ pub(super) fn parse_raw_query(
         .unwrap_or("published")
         .to_string();
 
-    // sorts
-    let sorts = query_map
-        .get("sort")
-        .and_then(|v| v.as_str())
-        .map(|sort_val| {
-            sort_val
+    // sorts; the bare `random` item orders at random
+    let sort = query_map.get("sort").and_then(|v| v.as_str());
+    let random_order = sort.is_some_and(|sort| sort.split(',').any(|item| item == RANDOM_SORT));
+    let sorts = sort
+        .map(|sort| {
+            let items: Vec<&str> = sort
                 .split(',')
-                .filter(|item| !item.is_empty())
-                .map(|item| {
-                    let mut parts = item.splitn(2, ':');
-                    let field = parts.next().unwrap_or("").to_string();
-                    let direction = match parts.next().map(|d| d.to_ascii_lowercase()).as_deref() {
-                        Some("desc") => SortDirection::Descending,
-                        _ => SortDirection::Ascending,
-                    };
-                    (field, direction)
-                })
-                .collect()
+                .filter(|item| *item != RANDOM_SORT)
+                .collect();
+            parse_sort_items(&items.join(","))
         })
         .unwrap_or_default();
+    let seed = query_map
+        .get("seed")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let sample = query_map
+        .get("sample")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
 
     // filters — kept opaque for the validation phase
     let filters = query_map.get("filters").cloned();
 
+    // populate strategy
+    let populate_strategy = query_map
+        .get("populateStrategy")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    // relation counts
+    let with_count = match query_map.get("withCount") {
+        Some(Value::String(s)) => s.split(',').map(str::to_string).collect(),
+        Some(Value::Array(arr)) => arr
+            .iter()
+            .filter_map(|v| v.as_str())
+            .flat_map(|s| s.split(','))
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    // time zone of the filter date-times
+    let time_zone = query_map
+        .get("timeZone")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
     RawQueryParams {
         populate,
+        populate_options,
         pagination,
         status,
         sorts,
+        random_order,
+        seed,
+        sample,
         filters,
+        populate_strategy,
+        with_count,
+        time_zone,
     }
 }
 
@@ -152,25 +207,49 @@ pub(super) fn parse_raw_query(
 /// 3. **Domain mapping** — [`build_filter_expression`] converts the validated tree
 ///    into [`FilterExpression`] values using [`DomainValue::parse`] for type
 ///    coercion, keeping a single canonical string→domain codec.
+///
+/// Page sizes above the maximum and queries exceeding `query_limits` are
+/// answered with `422 Unprocessable Entity`.
 pub fn parse_query(
     query_map: &serde_json::Map<String, Value>,
     document_type: &DocumentType,
-    registry: &dyn DocumentTypesRegistry,
+    registry: &'static dyn DocumentTypesRegistry,
     pagination_settings: &crate::application::PaginationSettings,
+    query_limits: &QueryLimits,
 ) -> Result<DocumentQuery, ApiError> {
     let raw = parse_raw_query(query_map, pagination_settings);
 
-    let status = parse_status(&raw.status)?;
+    let (page, page_size) = raw.pagination;
+    let page_size = check_page_size(page_size, pagination_settings)?;
+
+    let (status, status_filter) = parse_list_status(&raw.status)?;
     let populate = resolve_populate(raw.populate, document_type)?;
+    let populated = populate.as_ref().map_or(0, Vec::len);
+    if populated > query_limits.max_populate {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "{} relations are populated, at most {} are allowed",
+            populated, query_limits.max_populate
+        )));
+    }
+    let populate_strategy = parse_populate_strategy(raw.populate_strategy.as_deref())?;
+    let with_count = resolve_with_count(raw.with_count, document_type)?;
     let sorts = resolve_sorts(raw.sorts, document_type)?;
+    let (random_order, sample) = parse_random_draw(
+        raw.random_order,
+        &sorts,
+        raw.seed.as_deref(),
+        raw.sample.as_deref(),
+    )?;
+    let time_zone = parse_time_zone(raw.time_zone.as_deref())?;
 
     let (filter, populate_filters) = if let Some(filter_value) = raw.filters {
         let validated = validate_filter_tree(&filter_value, "", document_type, registry)?;
+        check_filter_limits("filters", &validated, query_limits)?;
         let (main_nodes, rel_map) = split_relation_filters(validated);
-        let main_filter = build_filter_expression(main_nodes)?;
+        let main_filter = build_filter_expression(main_nodes, time_zone)?;
         let pop_filters = rel_map
             .into_iter()
-            .map(|(attr, nodes)| Ok((attr, build_filter_expression(nodes)?)))
+            .map(|(attr, nodes)| Ok((attr, build_filter_expression(nodes, time_zone)?)))
             .collect::<Result<HashMap<_, _>, ApiError>>()?;
         let pop_filters = if pop_filters.is_empty() {
             None
@@ -181,17 +260,237 @@ pub fn parse_query(
     } else {
         (FilterExpression::None, None)
     };
+    let filter = and_filters(filter, status_filter);
+
+    let mut populate_filters = populate_filters.unwrap_or_default();
+    let mut populate_options = HashMap::new();
+    for (name, options) in raw.populate_options {
+        let relation = find_relation(document_type, &name)
+            .filter(|relation| relation.relation_type.is_owning())
+            .ok_or_else(|| {
+                ApiError::UnprocessableEntity(format!("Unknown populate relation: '{}'", name))
+            })?;
+        let target = registry.get(&relation.target).ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "Target document type '{}' not found in registry",
+                relation.target
+            ))
+        })?;
+        let (options, filter) = resolve_populate_options(
+            &name,
+            options,
+            target,
+            registry,
+            pagination_settings,
+            query_limits,
+            time_zone,
+        )?;
+        if !matches!(filter, FilterExpression::None) {
+            let filter = match populate_filters.remove(&relation.id) {
+                Some(existing) => FilterExpression::And(Box::new(existing), Box::new(filter)),
+                None => filter,
+            };
+            populate_filters.insert(relation.id.clone(), filter);
+        }
+        populate_options.insert(relation.id.clone(), options);
+    }
+    let populate_filters = (!populate_filters.is_empty()).then_some(populate_filters);
 
     Ok(DocumentQuery {
         populate,
-        pagination: raw.pagination,
+        populate_options,
+        pagination: (page, page_size),
         status,
         filter,
         populate_filters,
+        populate_strategy,
+        with_count,
         sorts,
+        random_order,
+        sample,
+    })
+}
+
+/// Parse and validate the parameters of an aggregate request against the given
+/// [`DocumentType`] schema.
+///
+/// `?groupBy=a,b` names the fields to group by, `?count`, `?sum=`, `?avg=`,
+/// `?min=` and `?max=` the metrics (comma-separated numeric fields). Without
+/// any metric the documents of every group are counted. `status` and `filters`
+/// work as in [`parse_query`].
+pub fn parse_aggregate_query(
+    query_map: &serde_json::Map<String, Value>,
+    document_type: &DocumentType,
+    registry: &'static dyn DocumentTypesRegistry,
+) -> Result<AggregateQuery, ApiError> {
+    let (status, filter) = parse_aggregate_scope(query_map, document_type, registry)?;
+    let group_by = resolve_aggregate_fields(query_map, "groupBy", document_type, is_groupable)?;
+
+    let mut metrics = Vec::new();
+    if query_map.contains_key(AggregateFunction::Count.name()) {
+        metrics.push(Metric {
+            function: AggregateFunction::Count,
+            field: None,
+        });
+    }
+    for function in [
+        AggregateFunction::Sum,
+        AggregateFunction::Avg,
+        AggregateFunction::Min,
+        AggregateFunction::Max,
+    ] {
+        let fields =
+            resolve_aggregate_fields(query_map, function.name(), document_type, |field_type| {
+                matches!(
+                    field_type,
+                    FieldType::Integer(_) | FieldType::Decimal { .. }
+                )
+            })?;
+        metrics.extend(fields.into_iter().map(|field| Metric {
+            function,
+            field: Some(field),
+        }));
+    }
+    if metrics.is_empty() {
+        metrics.push(Metric {
+            function: AggregateFunction::Count,
+            field: None,
+        });
+    }
+
+    Ok(AggregateQuery {
+        filter,
+        status,
+        group_by,
+        metrics,
+    })
+}
+
+/// Parse and validate the parameters of a distinct values request of the
+/// `attribute` field against the given [`DocumentType`] schema.
+///
+/// The distinct values are the groups of an aggregate by the field, counted
+/// with `?count`. Documents without a value are left out. `status` and
+/// `filters` work as in [`parse_aggregate_query`].
+pub fn parse_distinct_query(
+    query_map: &serde_json::Map<String, Value>,
+    document_type: &DocumentType,
+    registry: &'static dyn DocumentTypesRegistry,
+    attribute: &str,
+) -> Result<AggregateQuery, ApiError> {
+    let (status, filter) = parse_aggregate_scope(query_map, document_type, registry)?;
+
+    let field = document_type
+        .fields
+        .iter()
+        .find(|f| f.id.as_ref() == attribute)
+        .ok_or_else(|| ApiError::NotFound(format!("Field '{}' not found", attribute)))?;
+    if !is_groupable(field.field_type) {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "Field '{}' has no distinct values",
+            attribute
+        )));
+    }
+
+    let not_null = FilterExpression::IsNotNull {
+        field: field.id.to_string(),
+    };
+    let filter = match filter {
+        FilterExpression::None => not_null,
+        filter => FilterExpression::And(Box::new(filter), Box::new(not_null)),
+    };
+
+    let metrics = if query_map.contains_key(AggregateFunction::Count.name()) {
+        vec![Metric {
+            function: AggregateFunction::Count,
+            field: None,
+        }]
+    } else {
+        Vec::new()
+    };
+
+    Ok(AggregateQuery {
+        filter,
+        status,
+        group_by: vec![field.id.clone()],
+        metrics,
     })
 }
 
+/// Parse the `status` of an existence check, the only parameter it takes:
+/// `published` (default) or `draft`.
+pub fn parse_exists_query(
+    query_map: &serde_json::Map<String, Value>,
+) -> Result<DocumentStatus, ApiError> {
+    if let Some(key) = query_map.keys().find(|key| *key != "status") {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "{} param isn't eligible for exists query",
+            key
+        )));
+    }
+    parse_status(
+        query_map
+            .get("status")
+            .and_then(|v| v.as_str())
+            .unwrap_or("published"),
+    )
+}
+
+/// Parse and validate `pagination[page]` and `pagination[pageSize]` only, for
+/// the listings which take no other query parameters.
+///
+/// Page sizes above the maximum are answered with `422 Unprocessable Entity`.
+pub fn parse_pagination(
+    query_map: &serde_json::Map<String, Value>,
+    pagination_settings: &crate::application::PaginationSettings,
+) -> Result<(u16, u16), ApiError> {
+    let (page, page_size) = raw_pagination(query_map, pagination_settings);
+    Ok((page, check_page_size(page_size, pagination_settings)?))
+}
+
+/// `?pagination[page]=N&pagination[pageSize]=M`, the page size as requested.
+fn raw_pagination(
+    query_map: &serde_json::Map<String, Value>,
+    pagination_settings: &crate::application::PaginationSettings,
+) -> (u16, u64) {
+    if let Some(Value::Object(pag_map)) = query_map.get("pagination") {
+        let page = pag_map
+            .get("page")
+            .and_then(|v| {
+                v.as_str()
+                    .and_then(|s| s.parse::<u16>().ok())
+                    .or_else(|| v.as_u64().map(|n| n as u16))
+            })
+            .unwrap_or(1);
+        let page_size = pag_map
+            .get("pageSize")
+            .and_then(|v| {
+                v.as_str()
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .or_else(|| v.as_u64())
+            })
+            .unwrap_or(u64::from(pagination_settings.default_page_size));
+        (page, page_size)
+    } else {
+        (1, u64::from(pagination_settings.default_page_size))
+    }
+}
+
+fn check_page_size(
+    page_size: u64,
+    pagination_settings: &crate::application::PaginationSettings,
+) -> Result<u16, ApiError> {
+    u16::try_from(page_size)
+        .ok()
+        .filter(|page_size| *page_size <= pagination_settings.max_page_size)
+        .ok_or_else(|| {
+            ApiError::UnprocessableEntity(format!(
+                "pagination[pageSize] must be at most {}",
+                pagination_settings.max_page_size
+            ))
+        })
+}
+
 // ─── Phase 1: Operator enum ───────────────────────────────────────────────────
 
 /// Recognized filter operators, resolved from their raw string representation.
@@ -208,6 +507,7 @@ enum FilterOperator {
     Lte,
     In,
     NotIn,
+    Between,
     Contains,
     StartsWith,
     EndsWith,
@@ -216,6 +516,23 @@ enum FilterOperator {
 }
 
 impl FilterOperator {
+    const ALL: [Self; 14] = [
+        Self::Eq,
+        Self::Ne,
+        Self::Gt,
+        Self::Gte,
+        Self::Lt,
+        Self::Lte,
+        Self::In,
+        Self::NotIn,
+        Self::Between,
+        Self::Contains,
+        Self::StartsWith,
+        Self::EndsWith,
+        Self::IsNull,
+        Self::IsNotNull,
+    ];
+
     fn from_str(s: &str) -> Result<Self, ApiError> {
         match s {
             "$eq" | "" => Ok(Self::Eq),
@@ -226,6 +543,7 @@ impl FilterOperator {
             "$lte" => Ok(Self::Lte),
             "$in" => Ok(Self::In),
             "$notIn" | "$not_in" => Ok(Self::NotIn),
+            "$between" => Ok(Self::Between),
             "$contains" => Ok(Self::Contains),
             "$startsWith" | "$starts_with" => Ok(Self::StartsWith),
             "$endsWith" | "$ends_with" => Ok(Self::EndsWith),
@@ -238,15 +556,50 @@ impl FilterOperator {
         }
     }
 
-    /// Whether this operator consumes a list of values (`$in` / `$notIn`).
+    /// Whether this operator consumes a list of values (`$in` / `$notIn` / `$between`).
     fn is_list_operator(self) -> bool {
-        matches!(self, Self::In | Self::NotIn)
+        matches!(self, Self::In | Self::NotIn | Self::Between)
     }
 
     /// Whether this operator is a null-check (`$null` / `$notNull`).
     fn is_null_check(self) -> bool {
         matches!(self, Self::IsNull | Self::IsNotNull)
     }
+
+    /// Canonical name of the operator, as written in a filter.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Eq => "$eq",
+            Self::Ne => "$ne",
+            Self::Gt => "$gt",
+            Self::Gte => "$gte",
+            Self::Lt => "$lt",
+            Self::Lte => "$lte",
+            Self::In => "$in",
+            Self::NotIn => "$notIn",
+            Self::Between => "$between",
+            Self::Contains => "$contains",
+            Self::StartsWith => "$startsWith",
+            Self::EndsWith => "$endsWith",
+            Self::IsNull => "$null",
+            Self::IsNotNull => "$notNull",
+        }
+    }
+
+    /// Whether the operator can compare values of `field_type`: equality for
+    /// every scalar type, ordering for numbers, dates and texts, and pattern
+    /// matching for texts only. Compound values can only be checked for null.
+    fn is_applicable_for(self, field_type: FieldType) -> bool {
+        match self {
+            Self::IsNull | Self::IsNotNull => true,
+            _ if matches!(field_type, FieldType::LocalizedText | FieldType::Json) => false,
+            Self::Eq | Self::Ne | Self::In | Self::NotIn => true,
+            Self::Gt | Self::Gte | Self::Lt | Self::Lte | Self::Between => {
+                !matches!(field_type, FieldType::Boolean | FieldType::Uuid)
+            }
+            Self::Contains | Self::StartsWith | Self::EndsWith => field_type.is_text(),
+        }
+    }
 }
 
 // ─── Phase 2: Validated intermediate tree ─────────────────────────────────────
@@ -256,6 +609,7 @@ impl FilterOperator {
 /// Produced by [`validate_filter_tree`].  Each node carries resolved field types
 /// and raw string values so the domain mapping phase can coerce them without
 /// re-consulting the schema.
+#[derive(Clone)]
 enum ValidatedFilterNode {
     /// A single scalar comparison: `field op value`.
     Scalar {
@@ -264,7 +618,7 @@ enum ValidatedFilterNode {
         field_type: FieldType,
         raw_value: String,
     },
-    /// A list comparison: `field $in [v1, v2, ...]`.
+    /// A list comparison: `field $in [v1, v2, ...]` or `field $between [from, to]`.
     List {
         field_path: String,
         operator: FilterOperator,
@@ -280,6 +634,7 @@ enum ValidatedFilterNode {
     /// Sub-filter targeting a relation's own fields.
     Relation {
         relation_id: AttributeId,
+        target: &'static DocumentType,
         children: Vec<ValidatedFilterNode>,
     },
 }
@@ -290,19 +645,24 @@ enum ValidatedFilterNode {
 /// returning a tree of [`ValidatedFilterNode`]s.
 ///
 /// `current_path` accumulates the dot-separated field path as we recurse into
-/// nested objects (e.g. `description` → `description.en`).
+/// nested objects (e.g. `description` → `description.en`). Fields and
+/// relations are named by their key in the responses or by their id, the
+/// paths by their id.
 ///
 /// # Errors
 ///
 /// Returns `ApiError::UnprocessableEntity` for:
 /// - Unknown field names (no silent fallback to `FieldType::Text`)
 /// - Relation keys whose target type is not in the registry
+/// - Relation keys of inverse relations, whose links the target stores
 /// - Unrecognized operator strings
+/// - Operators which can't compare values of the field's type, e.g. `$gt` on a
+///   boolean or `$contains` on an integer
 fn validate_filter_tree(
     value: &Value,
     current_path: &str,
     document_type: &DocumentType,
-    registry: &dyn DocumentTypesRegistry,
+    registry: &'static dyn DocumentTypesRegistry,
 ) -> Result<Vec<ValidatedFilterNode>, ApiError> {
     let mut nodes = Vec::new();
 
@@ -314,28 +674,25 @@ fn validate_filter_tree(
                     let operator = FilterOperator::from_str(key)?;
                     let node = build_validated_node(current_path, operator, child, document_type)?;
                     nodes.push(node);
-                } else if let Some(rel) = document_type
-                    .relations
-                    .iter()
-                    .find(|r| r.id.as_ref() == key)
-                {
+                } else if let Some(rel) = find_relation(document_type, key) {
                     // Relation key — recurse with the target document type.
-                    let target_type = registry.get(&rel.target).ok_or_else(|| {
-                        ApiError::NotFound(format!(
-                            "Target document type '{}' not found in registry",
-                            rel.target
-                        ))
-                    })?;
-
-                    let children = validate_filter_tree(child, "", target_type, registry)?;
-                    nodes.push(ValidatedFilterNode::Relation {
-                        relation_id: rel.id.clone(),
-                        children,
-                    });
+                    nodes.push(validate_relation_filter(rel, child, registry)?);
+                } else if let Some((rel, rest)) = key
+                    .split_once('.')
+                    .and_then(|(name, rest)| Some((find_relation(document_type, name)?, rest)))
+                {
+                    // `relation.field` path — same as `relation[field]`.
+                    let mut nested = serde_json::Map::new();
+                    nested.insert(rest.to_string(), child.clone());
+                    nodes.push(validate_relation_filter(
+                        rel,
+                        &Value::Object(nested),
+                        registry,
+                    )?);
                 } else {
                     // Regular field key or locale segment — extend the path and recurse.
                     let new_path = if current_path.is_empty() {
-                        key.clone()
+                        field_path_by_id(document_type, key)
                     } else {
                         format!("{}.{}", current_path, key)
                     };
@@ -356,26 +713,157 @@ fn validate_filter_tree(
     Ok(nodes)
 }
 
-/// Resolve a field path to its [`FieldType`] from the document type schema.
+/// Reject validated filters with more conditions, or relations nested deeper,
+/// than `query_limits` allow; `param` names the filters in the error.
+fn check_filter_limits(
+    param: &str,
+    nodes: &[ValidatedFilterNode],
+    query_limits: &QueryLimits,
+) -> Result<(), ApiError> {
+    // (conditions, depth of nested relations)
+    fn measure(nodes: &[ValidatedFilterNode]) -> (usize, usize) {
+        nodes
+            .iter()
+            .fold((0, 0), |(conditions, depth), node| match node {
+                ValidatedFilterNode::Relation { children, .. } => {
+                    let (child_conditions, child_depth) = measure(children);
+                    (conditions + child_conditions, depth.max(child_depth + 1))
+                }
+                _ => (conditions + 1, depth),
+            })
+    }
+
+    let (conditions, depth) = measure(nodes);
+    if conditions > query_limits.max_filters {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "{} has {} conditions, at most {} are allowed",
+            param, conditions, query_limits.max_filters
+        )));
+    }
+    if depth > query_limits.max_filter_depth {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "{} nests {} relations deep, at most {} are allowed",
+            param, depth, query_limits.max_filter_depth
+        )));
+    }
+    Ok(())
+}
+
+fn find_relation<'a>(document_type: &'a DocumentType, name: &str) -> Option<&'a DocumentRelation> {
+    let name = attribute_name(document_type, name);
+    document_type
+        .relations
+        .iter()
+        .find(|r| r.id.as_ref() == name)
+}
+
+/// Id of the field or relation of `document_type` a query parameter names,
+/// by its key in the responses or by its id, see [`payload_attribute`];
+/// names of neither are kept for the lookups to reject.
+fn attribute_name<'a>(document_type: &'a DocumentType, name: &'a str) -> &'a str {
+    payload_attribute(document_type, name).map_or(name, |id| id.as_ref())
+}
+
+/// `path` with its first segment, the field, named by [`attribute_name`],
+/// e.g. `legalEntity` or `title.en`; the nested segments are kept.
+fn field_path_by_id(document_type: &DocumentType, path: &str) -> String {
+    match path.split_once('.') {
+        Some((field, rest)) => format!("{}.{}", attribute_name(document_type, field), rest),
+        None => attribute_name(document_type, path).to_string(),
+    }
+}
+
+/// Validate the sub-filter of a relation against its target document type.
+///
+/// Only owning relations, which store the links, can be filtered by, like
+/// they are the only ones populated.
+fn validate_relation_filter(
+    relation: &DocumentRelation,
+    value: &Value,
+    registry: &'static dyn DocumentTypesRegistry,
+) -> Result<ValidatedFilterNode, ApiError> {
+    if !relation.relation_type.is_owning() {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "Relation '{}' is the inverse side of a relation of '{}' and can't be filtered by",
+            relation.id, relation.target
+        )));
+    }
+    let target = registry.get(&relation.target).ok_or_else(|| {
+        ApiError::NotFound(format!(
+            "Target document type '{}' not found in registry",
+            relation.target
+        ))
+    })?;
+
+    let children = validate_filter_tree(value, "", target, registry)?;
+    Ok(ValidatedFilterNode::Relation {
+        relation_id: relation.id.clone(),
+        target,
+        children,
+    })
+}
+
+/// Resolve a field path to the [`FieldType`] of the values it compares.
 ///
-/// Only the **base** field name (the first segment before any `.`) is looked up,
-/// because nested segments are locale codes or JSON sub-keys, not separate fields.
+/// The **base** field name (the first segment before any `.`) is looked up in
+/// the document type schema. A `LocalizedText` field takes a locale code as
+/// its only nested segment, e.g. `description.en`, and compares the text of
+/// that locale; other fields have no nested segments.
 ///
 /// Returns `Err` if the field does not exist on the document type — no silent
-/// fallback.
+/// fallback — or the path doesn't fit its type.
 fn resolve_field_type(
     field_path: &str,
     document_type: &DocumentType,
 ) -> Result<FieldType, ApiError> {
-    let base_field = field_path.split('.').next().unwrap_or(field_path);
-    document_type
+    let mut segments = field_path.split('.');
+    let base_field = segments.next().unwrap_or(field_path);
+    let field_type = document_type
         .fields
         .iter()
         .find(|f| f.id.as_ref() == base_field)
         .map(|f| f.field_type)
         .ok_or_else(|| {
             ApiError::UnprocessableEntity(format!("Unknown filter field: '{}'", base_field))
-        })
+        })?;
+
+    let nested: Vec<&str> = segments.collect();
+    match (field_type, nested.as_slice()) {
+        (_, []) => Ok(field_type),
+        (FieldType::LocalizedText, [_locale]) => Ok(FieldType::Text),
+        _ => Err(ApiError::UnprocessableEntity(format!(
+            "Filter field '{}' of type {} has no nested path '{}'",
+            base_field,
+            field_type_name(field_type),
+            nested.join(".")
+        ))),
+    }
+}
+
+/// Names of the filter operators applicable to the fields of `field_type`.
+pub fn filter_operators(field_type: FieldType) -> Vec<&'static str> {
+    FilterOperator::ALL
+        .into_iter()
+        .filter(|operator| operator.is_applicable_for(field_type))
+        .map(FilterOperator::name)
+        .collect()
+}
+
+/// Name of a field type as written in the schema files.
+fn field_type_name(field_type: FieldType) -> &'static str {
+    match field_type {
+        FieldType::Uid => "uid",
+        FieldType::Uuid => "uuid",
+        FieldType::Text => "text",
+        FieldType::LocalizedText => "localizedText",
+        FieldType::Integer(_) => "integer",
+        FieldType::Decimal { .. } => "decimal",
+        FieldType::Date => "date",
+        FieldType::DateTime | FieldType::LocalDateTime => "dateTime",
+        FieldType::Time => "time",
+        FieldType::Boolean => "boolean",
+        FieldType::Json => "json",
+    }
 }
 
 /// Build a single [`ValidatedFilterNode`] for a `(field_path, operator, json_value)` triple.
@@ -385,6 +873,16 @@ fn build_validated_node(
     value: &Value,
     document_type: &DocumentType,
 ) -> Result<ValidatedFilterNode, ApiError> {
+    let field_type = resolve_field_type(field_path, document_type)?;
+    if !operator.is_applicable_for(field_type) {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "Operator {} can't be applied to field '{}' of type {}",
+            operator.name(),
+            field_path,
+            field_type_name(field_type)
+        )));
+    }
+
     if operator.is_null_check() {
         // $null / $notNull — value is a boolean controlling polarity.
         let polarity = match value {
@@ -405,10 +903,8 @@ fn build_validated_node(
         });
     }
 
-    let field_type = resolve_field_type(field_path, document_type)?;
-
     if operator.is_list_operator() {
-        // $in / $notIn — value must be an array or a single string.
+        // $in / $notIn / $between — value must be an array or a single string.
         let raw_values: Vec<String> = match value {
             Value::Array(arr) => arr
                 .iter()
@@ -422,6 +918,12 @@ fn build_validated_node(
                 )));
             }
         };
+        if operator == FilterOperator::Between && raw_values.len() != 2 {
+            return Err(ApiError::UnprocessableEntity(format!(
+                "Expected exactly two values for operator $between on field '{}'",
+                field_path
+            )));
+        }
         return Ok(ValidatedFilterNode::List {
             field_path: field_path.to_owned(),
             operator,
@@ -460,46 +962,53 @@ fn json_value_to_raw_string(value: &Value) -> Option<String> {
 
 // ─── Phase 3: Split relation sub-filters ─────────────────────────────────────
 
-/// Separate top-level field filter nodes from per-relation sub-filter nodes.
+/// Collect the per-relation sub-filter nodes used to filter populated relations.
 ///
 /// Returns `(main_nodes, relation_map)` where `relation_map` keys are relation
 /// attribute IDs and values are the children of the corresponding
-/// [`ValidatedFilterNode::Relation`] nodes.
+/// [`ValidatedFilterNode::Relation`] nodes. The relation nodes stay in
+/// `main_nodes` too, so only documents having a matching related document
+/// are returned.
 fn split_relation_filters(
     nodes: Vec<ValidatedFilterNode>,
 ) -> (
     Vec<ValidatedFilterNode>,
     HashMap<AttributeId, Vec<ValidatedFilterNode>>,
 ) {
-    let mut main_nodes = Vec::new();
     let mut rel_map: HashMap<AttributeId, Vec<ValidatedFilterNode>> = HashMap::new();
 
-    for node in nodes {
-        match node {
-            ValidatedFilterNode::Relation {
-                relation_id,
-                children,
-            } => {
-                rel_map.entry(relation_id).or_default().extend(children);
-            }
-            other => main_nodes.push(other),
+    for node in &nodes {
+        if let ValidatedFilterNode::Relation {
+            relation_id,
+            children,
+            ..
+        } = node
+        {
+            rel_map
+                .entry(relation_id.clone())
+                .or_default()
+                .extend(children.iter().cloned());
         }
     }
 
-    (main_nodes, rel_map)
+    (nodes, rel_map)
 }
 
 // ─── Phase 4: Domain mapping ──────────────────────────────────────────────────
 
 /// Convert a list of [`ValidatedFilterNode`]s into a single [`FilterExpression`].
 ///
-/// Multiple nodes are combined with `And`.  Uses [`DomainValue::parse`] for all
-/// type coercion — the single canonical `&str → DomainValue` path.
-fn build_filter_expression(nodes: Vec<ValidatedFilterNode>) -> Result<FilterExpression, ApiError> {
+/// Multiple nodes are combined with `And`.  Uses [`DomainValue::parse_in`] for all
+/// type coercion — the single canonical `&str → DomainValue` path — reading
+/// date-times without an offset in `time_zone`.
+fn build_filter_expression(
+    nodes: Vec<ValidatedFilterNode>,
+    time_zone: Option<Tz>,
+) -> Result<FilterExpression, ApiError> {
     let mut result = FilterExpression::None;
 
     for node in nodes {
-        let expr = node_to_expression(node)?;
+        let expr = node_to_expression(node, time_zone)?;
         result = match result {
             FilterExpression::None => expr,
             existing => FilterExpression::And(Box::new(existing), Box::new(expr)),
@@ -510,7 +1019,10 @@ fn build_filter_expression(nodes: Vec<ValidatedFilterNode>) -> Result<FilterExpr
 }
 
 /// Convert a single [`ValidatedFilterNode`] into a [`FilterExpression`].
-fn node_to_expression(node: ValidatedFilterNode) -> Result<FilterExpression, ApiError> {
+fn node_to_expression(
+    node: ValidatedFilterNode,
+    time_zone: Option<Tz>,
+) -> Result<FilterExpression, ApiError> {
     match node {
         ValidatedFilterNode::NullCheck {
             field_path,
@@ -532,7 +1044,7 @@ fn node_to_expression(node: ValidatedFilterNode) -> Result<FilterExpression, Api
             let values = raw_values
                 .into_iter()
                 .map(|raw| {
-                    DomainValue::parse(&raw, field_type)
+                    DomainValue::parse_in(&raw, field_type, time_zone)
                         .map_err(|e| ApiError::UnprocessableEntity(e.to_string()))
                 })
                 .collect::<Result<Vec<_>, _>>()?;
@@ -546,7 +1058,19 @@ fn node_to_expression(node: ValidatedFilterNode) -> Result<FilterExpression, Api
                     field: field_path,
                     values,
                 }),
-                _ => unreachable!("only In/NotIn reach the List branch"),
+                FilterOperator::Between => {
+                    let [from, to]: [DomainValue; 2] = values.try_into().map_err(|_| {
+                        ApiError::InternalServerError(
+                            "$between filter without exactly two values".to_owned(),
+                        )
+                    })?;
+                    Ok(FilterExpression::Between {
+                        field: field_path,
+                        from,
+                        to,
+                    })
+                }
+                _ => unreachable!("only In/NotIn/Between reach the List branch"),
             }
         }
 
@@ -555,13 +1079,17 @@ fn node_to_expression(node: ValidatedFilterNode) -> Result<FilterExpression, Api
             operator,
             field_type,
             raw_value,
-        } => scalar_to_expression(field_path, operator, raw_value, field_type),
-
-        // Relation nodes are split out by split_relation_filters before this function
-        // is called; if one reaches here it is a logic error.
-        ValidatedFilterNode::Relation { .. } => Err(ApiError::InternalServerError(
-            "Relation filter node reached domain mapping phase unexpectedly".to_owned(),
-        )),
+        } => scalar_to_expression(field_path, operator, raw_value, field_type, time_zone),
+
+        ValidatedFilterNode::Relation {
+            relation_id,
+            target,
+            children,
+        } => Ok(FilterExpression::HasRelation {
+            field: relation_id,
+            target,
+            filter: Box::new(build_filter_expression(children, time_zone)?),
+        }),
     }
 }
 
@@ -574,6 +1102,7 @@ fn scalar_to_expression(
     operator: FilterOperator,
     raw: String,
     field_type: FieldType,
+    time_zone: Option<Tz>,
 ) -> Result<FilterExpression, ApiError> {
     match operator {
         // Text-only operators — no type coercion needed. Move the raw String directly.
@@ -583,7 +1112,7 @@ fn scalar_to_expression(
 
         // Typed comparison operators — parse via the canonical codec.
         op => {
-            let value = DomainValue::parse(&raw, field_type)
+            let value = DomainValue::parse_in(&raw, field_type, time_zone)
                 .map_err(|e| ApiError::UnprocessableEntity(e.to_string()))?;
 
             Ok(match op {
@@ -599,6 +1128,7 @@ fn scalar_to_expression(
                 | FilterOperator::EndsWith
                 | FilterOperator::In
                 | FilterOperator::NotIn
+                | FilterOperator::Between
                 | FilterOperator::IsNull
                 | FilterOperator::IsNotNull => unreachable!(),
             })
@@ -619,7 +1149,41 @@ fn parse_status(s: &str) -> Result<DocumentStatus, ApiError> {
     }
 }
 
-/// Resolve raw populate field names into validated [`AttributeId`]s.
+/// Validate the `status` of a list of documents: `modified` reads the drafts
+/// saved after their published version, the other ones are [`parse_status`].
+fn parse_list_status(s: &str) -> Result<(DocumentStatus, FilterExpression), ApiError> {
+    match s {
+        "modified" => Ok((DocumentStatus::Draft, FilterExpression::Modified)),
+        _ => parse_status(s)
+            .map(|status| (status, FilterExpression::None))
+            .map_err(|_| {
+                ApiError::UnprocessableEntity(
+                    "status must be 'published' (default), 'draft' or 'modified'".to_string(),
+                )
+            }),
+    }
+}
+
+/// `left` AND `right`, either of them when the other one is none.
+fn and_filters(left: FilterExpression, right: FilterExpression) -> FilterExpression {
+    match (left, right) {
+        (FilterExpression::None, filter) | (filter, FilterExpression::None) => filter,
+        (left, right) => FilterExpression::And(Box::new(left), Box::new(right)),
+    }
+}
+
+fn parse_populate_strategy(s: Option<&str>) -> Result<PopulateStrategy, ApiError> {
+    match s {
+        None | Some("batched") => Ok(PopulateStrategy::Batched),
+        Some("lateral") => Ok(PopulateStrategy::Lateral),
+        Some(_) => Err(ApiError::UnprocessableEntity(
+            "populateStrategy must be 'batched' (default) or 'lateral'".to_string(),
+        )),
+    }
+}
+
+/// Resolve raw populate field names, keys in the responses or ids, into
+/// validated [`AttributeId`]s.
 ///
 /// The wildcard `*` is expanded to every owning relation on the document type.
 fn resolve_populate(
@@ -642,7 +1206,8 @@ fn resolve_populate(
 
     let mut attributes = Vec::with_capacity(fields.len());
     for name in fields {
-        let attr = AttributeId::try_new(&name).map_err(|_| {
+        let name = attribute_name(document_type, &name);
+        let attr = AttributeId::try_new(name).map_err(|_| {
             ApiError::UnprocessableEntity(format!("Invalid populate field: {}", name))
         })?;
         attributes.push(attr);
@@ -650,9 +1215,139 @@ fn resolve_populate(
     Ok(Some(attributes))
 }
 
+/// Split `field:asc,other:desc` into field names and directions, ascending
+/// unless `desc` is given.
+fn parse_sort_items(value: &str) -> Vec<(String, SortDirection)> {
+    value
+        .split(',')
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            let mut parts = item.splitn(2, ':');
+            let field = parts.next().unwrap_or("").to_string();
+            let direction = match parts.next().map(|d| d.to_ascii_lowercase()).as_deref() {
+                Some("desc") => SortDirection::Descending,
+                _ => SortDirection::Ascending,
+            };
+            (field, direction)
+        })
+        .collect()
+}
+
+/// Resolve the options of one populated relation, `populate[name][..]`,
+/// against its `target` document type: the `fields` and `sort` of the related
+/// documents, their `limit` per document — at most the maximum page size —
+/// and the `filters` they are populated with.
+///
+/// Unknown options, fields, malformed limits and filters exceeding
+/// `query_limits` are answered with `422 Unprocessable Entity`.
+fn resolve_populate_options(
+    name: &str,
+    options: serde_json::Map<String, Value>,
+    target: &DocumentType,
+    registry: &'static dyn DocumentTypesRegistry,
+    pagination_settings: &crate::application::PaginationSettings,
+    query_limits: &QueryLimits,
+    time_zone: Option<Tz>,
+) -> Result<(PopulateOptions, FilterExpression), ApiError> {
+    let mut resolved = PopulateOptions::default();
+    let mut filter = FilterExpression::None;
+    for (key, value) in options {
+        match key.as_str() {
+            "fields" => {
+                let names: Vec<&str> = match &value {
+                    Value::String(s) => s.split(',').collect(),
+                    Value::Array(arr) => arr.iter().filter_map(|v| v.as_str()).collect(),
+                    _ => Vec::new(),
+                };
+                let fields = names
+                    .into_iter()
+                    .filter(|field| !field.is_empty())
+                    .map(|field| {
+                        let id = attribute_name(target, field);
+                        target
+                            .fields
+                            .iter()
+                            .find(|f| f.id.as_ref() == id)
+                            .map(|f| f.id.clone())
+                            .ok_or_else(|| {
+                                ApiError::UnprocessableEntity(format!(
+                                    "Unknown field '{}' in populate[{}][fields]",
+                                    field, name
+                                ))
+                            })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                resolved.fields = Some(fields);
+            }
+            "sort" => {
+                let sorts = value.as_str().map(parse_sort_items).unwrap_or_default();
+                resolved.sort = resolve_sorts(sorts, target)?;
+            }
+            "limit" => {
+                let limit = value
+                    .as_str()
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .or_else(|| value.as_u64())
+                    .filter(|limit| *limit > 0)
+                    .ok_or_else(|| {
+                        ApiError::UnprocessableEntity(format!(
+                            "populate[{}][limit] must be a positive number",
+                            name
+                        ))
+                    })?;
+                resolved.limit = Some(limit.min(u64::from(pagination_settings.max_page_size)));
+            }
+            "filters" => {
+                let validated = validate_filter_tree(&value, "", target, registry)?;
+                check_filter_limits(
+                    &format!("populate[{}][filters]", name),
+                    &validated,
+                    query_limits,
+                )?;
+                filter = build_filter_expression(validated, time_zone)?;
+            }
+            _ => {
+                return Err(ApiError::UnprocessableEntity(format!(
+                    "Unknown populate option: '{}'",
+                    key
+                )));
+            }
+        }
+    }
+    Ok((resolved, filter))
+}
+
+/// Resolve the relation names of `withCount` into the owning relations of the
+/// document type, keeping their order and dropping repeats.
+///
+/// Rejects unknown and inverse relations with `422 Unprocessable Entity`.
+fn resolve_with_count(
+    names: Vec<String>,
+    document_type: &DocumentType,
+) -> Result<Vec<AttributeId>, ApiError> {
+    let mut attributes: Vec<AttributeId> = Vec::with_capacity(names.len());
+    for name in names
+        .iter()
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+    {
+        let relation = find_relation(document_type, name)
+            .filter(|relation| relation.relation_type.is_owning())
+            .ok_or_else(|| {
+                ApiError::UnprocessableEntity(format!("Unknown withCount relation: '{}'", name))
+            })?;
+        if !attributes.contains(&relation.id) {
+            attributes.push(relation.id.clone());
+        }
+    }
+    Ok(attributes)
+}
+
 /// Validate sort field names against the document type schema and build [`Sort`] values.
 ///
-/// Rejects sorts on unknown fields with `422 Unprocessable Entity`.
+/// Fields are named by their key in the responses or by their id; the sorts
+/// name them by their id. Rejects sorts on unknown fields with
+/// `422 Unprocessable Entity`.
 fn resolve_sorts(
     raw_sorts: Vec<(String, SortDirection)>,
     document_type: &DocumentType,
@@ -660,14 +1355,159 @@ fn resolve_sorts(
     raw_sorts
         .into_iter()
         .map(|(field, direction)| {
-            let field_exists = document_type.fields.iter().any(|f| f.id.as_ref() == field);
-            if !field_exists {
+            // a localized text field is sorted by the text of a locale of
+            // the type, `title.ro`
+            let (base_field, locale) = match field.split_once('.') {
+                Some((base_field, locale)) => (base_field, Some(locale)),
+                None => (field.as_str(), None),
+            };
+            let id = attribute_name(document_type, base_field);
+            let found = document_type
+                .fields
+                .iter()
+                .find(|f| f.id.as_ref() == id)
+                .ok_or_else(|| {
+                    ApiError::UnprocessableEntity(format!("Unknown sort field: '{}'", field))
+                })?;
+            if let Some(locale) = locale {
+                let localized = found.field_type == FieldType::LocalizedText
+                    && locales(document_type).any(|declared| declared == locale);
+                if !localized {
+                    return Err(ApiError::UnprocessableEntity(format!(
+                        "Sort field '{}' has no locale '{}'",
+                        base_field, locale
+                    )));
+                }
+            }
+            Ok(Sort {
+                field: field_path_by_id(document_type, &field),
+                direction,
+            })
+        })
+        .collect()
+}
+
+/// Parse the random order, `sort=random`, and the `sample` of a query, in
+/// percent of the documents, with the `seed` making them reproducible.
+///
+/// A random order can't be combined with other sorts, and a `seed` is only
+/// taken with a random order or a sample; as the other malformed values, it
+/// is answered with `422 Unprocessable Entity`.
+fn parse_random_draw(
+    random_order: bool,
+    sorts: &[Sort],
+    seed: Option<&str>,
+    sample: Option<&str>,
+) -> Result<(Option<RandomOrder>, Option<TableSample>), ApiError> {
+    let seed = seed
+        .map(|seed| {
+            seed.parse::<i64>()
+                .map_err(|_| ApiError::UnprocessableEntity(format!("Invalid seed: '{}'", seed)))
+        })
+        .transpose()?;
+    if random_order && !sorts.is_empty() {
+        return Err(ApiError::UnprocessableEntity(
+            "sort=random can't be combined with other sorts".to_string(),
+        ));
+    }
+    if seed.is_some() && !random_order && sample.is_none() {
+        return Err(ApiError::UnprocessableEntity(
+            "seed is only taken with sort=random or sample".to_string(),
+        ));
+    }
+
+    let sample = sample
+        .map(|sample| {
+            sample
+                .parse::<f64>()
+                .ok()
+                .filter(|percent| *percent > 0.0 && *percent <= 100.0)
+                .map(|percent| TableSample { percent, seed })
+                .ok_or_else(|| {
+                    ApiError::UnprocessableEntity(format!(
+                        "sample must be a percentage of the documents, above 0 and at most 100: '{}'",
+                        sample
+                    ))
+                })
+        })
+        .transpose()?;
+    Ok((random_order.then_some(RandomOrder { seed }), sample))
+}
+
+/// Parse the `timeZone` the date-times of the filters without an offset are
+/// read in, an IANA name like `Europe/Bucharest`.
+///
+/// Unknown time zones are answered with `422 Unprocessable Entity`.
+fn parse_time_zone(raw: Option<&str>) -> Result<Option<Tz>, ApiError> {
+    raw.map(|name| {
+        name.parse::<Tz>()
+            .map_err(|_| ApiError::UnprocessableEntity(format!("Unknown time zone: '{}'", name)))
+    })
+    .transpose()
+}
+
+/// Parse the `status` and `filters` selecting the documents of an aggregate.
+fn parse_aggregate_scope(
+    query_map: &serde_json::Map<String, Value>,
+    document_type: &DocumentType,
+    registry: &'static dyn DocumentTypesRegistry,
+) -> Result<(DocumentStatus, FilterExpression), ApiError> {
+    let (status, status_filter) = parse_list_status(
+        query_map
+            .get("status")
+            .and_then(|v| v.as_str())
+            .unwrap_or("published"),
+    )?;
+
+    let time_zone = parse_time_zone(query_map.get("timeZone").and_then(|v| v.as_str()))?;
+    let filter = if let Some(filter_value) = query_map.get("filters") {
+        let validated = validate_filter_tree(filter_value, "", document_type, registry)?;
+        build_filter_expression(validated, time_zone)?
+    } else {
+        FilterExpression::None
+    };
+
+    Ok((status, and_filters(filter, status_filter)))
+}
+
+/// Whether documents can be grouped by the values of a field of this type.
+fn is_groupable(field_type: FieldType) -> bool {
+    !matches!(field_type, FieldType::LocalizedText | FieldType::Json)
+}
+
+/// Resolve the comma-separated field names of an aggregate parameter, keeping
+/// only fields whose type the parameter `accepts`.
+fn resolve_aggregate_fields(
+    query_map: &serde_json::Map<String, Value>,
+    key: &str,
+    document_type: &DocumentType,
+    accepts: fn(FieldType) -> bool,
+) -> Result<Vec<AttributeId>, ApiError> {
+    let Some(value) = query_map.get(key) else {
+        return Ok(Vec::new());
+    };
+    let names = value.as_str().ok_or_else(|| {
+        ApiError::UnprocessableEntity(format!("{} must be a comma-separated list of fields", key))
+    })?;
+
+    names
+        .split(',')
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let field = document_type
+                .fields
+                .iter()
+                .find(|f| f.id.as_ref() == name)
+                .ok_or_else(|| {
+                    ApiError::UnprocessableEntity(format!("Unknown field '{}' in {}", name, key))
+                })?;
+            if !accepts(field.field_type) {
                 return Err(ApiError::UnprocessableEntity(format!(
-                    "Unknown sort field: '{}'",
-                    field
+                    "Field '{}' can't be used in {}",
+                    name, key
                 )));
             }
-            Ok(Sort { field, direction })
+            Ok(field.id.clone())
         })
         .collect()
 }
@@ -720,8 +1560,15 @@ mod tests {
                 constraints: HashSet::new(),
                 required: false,
                 unique: false,
+                renamed_from: None,
+                api_name: None,
+                description: None,
+                filterable: false,
+                sortable: false,
+                personal: false,
             }]),
             relations: HashSet::new(),
+            renamed_from: None,
         }));
 
         let dt_restaurant: &'static DocumentType = Box::leak(Box::new(DocumentType {
@@ -741,6 +1588,12 @@ mod tests {
                     constraints: HashSet::new(),
                     required: false,
                     unique: false,
+                    renamed_from: None,
+                    api_name: None,
+                    description: None,
+                    filterable: false,
+                    sortable: false,
+                    personal: false,
                 },
                 DocumentField {
                     id: AttributeId::try_new("description").unwrap(),
@@ -748,19 +1601,30 @@ mod tests {
                     constraints: HashSet::new(),
                     required: false,
                     unique: false,
+                    renamed_from: None,
+                    api_name: None,
+                    description: None,
+                    filterable: false,
+                    sortable: false,
+                    personal: false,
                 },
             ]),
             relations: HashSet::from([DocumentRelation {
                 id: AttributeId::try_new("category").unwrap(),
                 target: DocumentTypeId::try_new("category").unwrap(),
                 relation_type: RelationType::HasOne,
+                on_delete: Default::default(),
+                renamed_from: None,
+                api_name: None,
+                description: None,
             }]),
+            renamed_from: None,
         }));
 
         let mut types = HashMap::new();
         types.insert(dt_category.id.clone(), dt_category);
         types.insert(dt_restaurant.id.clone(), dt_restaurant);
-        let registry = MockRegistry { types };
+        let registry: &'static MockRegistry = Box::leak(Box::new(MockRegistry { types }));
 
         let query = "filters[title][$eq]=hello\
             &filters[category][slug][$eq]=italian\
@@ -773,8 +1637,9 @@ mod tests {
         let q = parse_query(
             &query_map,
             dt_restaurant,
-            &registry,
+            registry,
             &crate::application::PaginationSettings::default(),
+            &QueryLimits::default(),
         )
         .unwrap();
 
@@ -787,6 +1652,7 @@ mod tests {
         let filter_str = format!("{:?}", q.filter);
         assert!(filter_str.contains("Equals"));
         assert!(filter_str.contains("title"));
+        assert!(filter_str.contains("HasRelation"));
 
         let pop_filters = q.populate_filters.unwrap();
         let cat_attr = AttributeId::try_new("category").unwrap();
@@ -815,21 +1681,29 @@ mod tests {
                 constraints: HashSet::new(),
                 required: false,
                 unique: false,
+                renamed_from: None,
+                api_name: None,
+                description: None,
+                filterable: false,
+                sortable: false,
+                personal: false,
             }]),
             relations: HashSet::new(),
+            renamed_from: None,
         }));
 
-        let registry = MockRegistry {
+        let registry: &'static MockRegistry = Box::leak(Box::new(MockRegistry {
             types: HashMap::new(),
-        };
+        }));
         let query = "filters[nonexistent][$eq]=foo";
         let query_map = parse_query_to_json(query);
 
         let result = parse_query(
             &query_map,
             dt,
-            &registry,
+            registry,
             &crate::application::PaginationSettings::default(),
+            &QueryLimits::default(),
         );
         assert!(matches!(result, Err(ApiError::UnprocessableEntity(_))));
         let msg = result.unwrap_err().to_string();
@@ -858,21 +1732,29 @@ mod tests {
                 constraints: HashSet::new(),
                 required: false,
                 unique: false,
+                renamed_from: None,
+                api_name: None,
+                description: None,
+                filterable: false,
+                sortable: false,
+                personal: false,
             }]),
             relations: HashSet::new(),
+            renamed_from: None,
         }));
 
-        let registry = MockRegistry {
+        let registry: &'static MockRegistry = Box::leak(Box::new(MockRegistry {
             types: HashMap::new(),
-        };
+        }));
         let query = "sort=ghost_field:asc";
         let query_map = parse_query_to_json(query);
 
         let result = parse_query(
             &query_map,
             dt,
-            &registry,
+            registry,
             &crate::application::PaginationSettings::default(),
+            &QueryLimits::default(),
         );
         assert!(matches!(result, Err(ApiError::UnprocessableEntity(_))));
     }
@@ -911,6 +1793,816 @@ mod tests {
             FilterOperator::from_str("$not_null").unwrap(),
             FilterOperator::IsNotNull
         );
+        assert_eq!(
+            FilterOperator::from_str("$between").unwrap(),
+            FilterOperator::Between
+        );
         assert!(FilterOperator::from_str("$bogus").is_err());
     }
+
+    #[test]
+    fn test_query_limits() {
+        let relation = |id: &str, target: &str| DocumentRelation {
+            id: AttributeId::try_new(id).unwrap(),
+            target: DocumentTypeId::try_new(target).unwrap(),
+            relation_type: RelationType::HasMany,
+            on_delete: Default::default(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+        };
+        let category: &'static DocumentType = Box::leak(Box::new(DocumentType {
+            id: DocumentTypeId::try_new("category").unwrap(),
+            kind: DocumentKind::Collection,
+            info: DocumentTypeInfo {
+                title: DocumentTitle::try_new("Category").unwrap(),
+                singular_name: DocumentTypeId::try_new("category").unwrap(),
+                plural_name: DocumentTypeId::try_new("categories").unwrap(),
+                description: None,
+            },
+            options: None,
+            fields: HashSet::from([DocumentField {
+                id: AttributeId::try_new("slug").unwrap(),
+                field_type: FieldType::Text,
+                constraints: HashSet::new(),
+                required: false,
+                unique: false,
+                renamed_from: None,
+                api_name: None,
+                description: None,
+                filterable: false,
+                sortable: false,
+                personal: false,
+            }]),
+            relations: HashSet::from([
+                relation("parent", "category"),
+                relation("children", "category"),
+            ]),
+            renamed_from: None,
+        }));
+        let registry: &'static MockRegistry = Box::leak(Box::new(MockRegistry {
+            types: HashMap::from([(category.id.clone(), category)]),
+        }));
+        let limits = QueryLimits {
+            max_filters: 2,
+            max_filter_depth: 1,
+            max_populate: 1,
+        };
+        let parse = |query: &str| {
+            parse_query(
+                &parse_query_to_json(query),
+                category,
+                registry,
+                &crate::application::PaginationSettings::default(),
+                &limits,
+            )
+        };
+
+        for query in [
+            "pagination[pageSize]=100",
+            "filters[slug][$ne]=a&filters[parent][slug][$eq]=b",
+            "populate=parent",
+        ] {
+            assert!(parse(query).is_ok(), "{query}");
+        }
+
+        for (query, detail) in [
+            (
+                "pagination[pageSize]=101",
+                "pagination[pageSize] must be at most 100",
+            ),
+            (
+                "pagination[pageSize]=100000",
+                "pagination[pageSize] must be at most 100",
+            ),
+            (
+                "filters[slug][$ne]=a&filters[slug][$startsWith]=b&filters[parent][slug][$eq]=c",
+                "filters has 3 conditions, at most 2 are allowed",
+            ),
+            (
+                "filters[parent][parent][slug][$eq]=a",
+                "filters nests 2 relations deep, at most 1 are allowed",
+            ),
+            (
+                "populate=*",
+                "2 relations are populated, at most 1 are allowed",
+            ),
+            (
+                "populate[children][filters][slug][$ne]=a\
+                &populate[children][filters][slug][$startsWith]=b\
+                &populate[children][filters][slug][$endsWith]=c",
+                "populate[children][filters] has 3 conditions, at most 2 are allowed",
+            ),
+        ] {
+            match parse(query) {
+                Err(ApiError::UnprocessableEntity(message)) => {
+                    assert_eq!(message, detail, "{query}")
+                }
+                other => panic!("{query}: unexpected {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_filter_operators_of_field_types() {
+        assert_eq!(filter_operators(FieldType::Json), ["$null", "$notNull"]);
+        assert!(!filter_operators(FieldType::Boolean).contains(&"$gt"));
+        assert!(filter_operators(FieldType::Text).contains(&"$startsWith"));
+        assert_eq!(
+            filter_operators(FieldType::Integer(
+                luminair_common::entities::IntegerSize::Int32
+            ))
+            .len(),
+            11
+        );
+    }
+
+    #[test]
+    fn test_filter_operators_checked_against_field_types() {
+        let field = |id: &str, field_type| DocumentField {
+            id: AttributeId::try_new(id).unwrap(),
+            field_type,
+            constraints: HashSet::new(),
+            required: false,
+            unique: false,
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        };
+        let dt: &'static DocumentType = Box::leak(Box::new(DocumentType {
+            id: DocumentTypeId::try_new("product").unwrap(),
+            kind: DocumentKind::Collection,
+            info: DocumentTypeInfo {
+                title: DocumentTitle::try_new("Product").unwrap(),
+                singular_name: DocumentTypeId::try_new("product").unwrap(),
+                plural_name: DocumentTypeId::try_new("products").unwrap(),
+                description: None,
+            },
+            options: None,
+            fields: HashSet::from([
+                field("active", FieldType::Boolean),
+                field("stock", FieldType::Integer(Default::default())),
+                field("name", FieldType::Text),
+                field("description", FieldType::LocalizedText),
+                field("meta", FieldType::Json),
+            ]),
+            relations: HashSet::new(),
+            renamed_from: None,
+        }));
+        let registry: &'static MockRegistry = Box::leak(Box::new(MockRegistry {
+            types: HashMap::new(),
+        }));
+        let parse = |query: &str| {
+            parse_query(
+                &parse_query_to_json(query),
+                dt,
+                registry,
+                &crate::application::PaginationSettings::default(),
+                &QueryLimits::default(),
+            )
+        };
+
+        for query in [
+            "filters[active][$eq]=true",
+            "filters[stock][$gt]=5",
+            "filters[stock][$between][]=1&filters[stock][$between][]=9",
+            "filters[name][$gte]=m",
+            "filters[name][$contains]=ac",
+            "filters[description][en][$startsWith]=Re",
+            "filters[description.en][$eq]=Red",
+            "filters[meta][$notNull]=true",
+        ] {
+            assert!(parse(query).is_ok(), "{query}");
+        }
+
+        for (query, detail) in [
+            (
+                "filters[active][$gt]=true",
+                "Operator $gt can't be applied to field 'active' of type boolean",
+            ),
+            (
+                "filters[stock][$contains]=5",
+                "Operator $contains can't be applied to field 'stock' of type integer",
+            ),
+            (
+                "filters[description][$eq]=Red",
+                "Operator $eq can't be applied to field 'description' of type localizedText",
+            ),
+            (
+                "filters[meta][$in][]=1",
+                "Operator $in can't be applied to field 'meta' of type json",
+            ),
+            (
+                "filters[stock][units][$eq]=5",
+                "Filter field 'stock' of type integer has no nested path 'units'",
+            ),
+            (
+                "filters[description][en][us][$eq]=Red",
+                "Filter field 'description' of type localizedText has no nested path 'en.us'",
+            ),
+            (
+                "filters[ghost][$null]=true",
+                "Unknown filter field: 'ghost'",
+            ),
+        ] {
+            match parse(query) {
+                Err(ApiError::UnprocessableEntity(message)) => {
+                    assert_eq!(message, detail, "{query}")
+                }
+                other => panic!("{query}: unexpected {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_filter_date_times_in_time_zone() {
+        let field = |id: &str, field_type| DocumentField {
+            id: AttributeId::try_new(id).unwrap(),
+            field_type,
+            constraints: HashSet::new(),
+            required: false,
+            unique: false,
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        };
+        let dt: &'static DocumentType = Box::leak(Box::new(DocumentType {
+            id: DocumentTypeId::try_new("event").unwrap(),
+            kind: DocumentKind::Collection,
+            info: DocumentTypeInfo {
+                title: DocumentTitle::try_new("Event").unwrap(),
+                singular_name: DocumentTypeId::try_new("event").unwrap(),
+                plural_name: DocumentTypeId::try_new("events").unwrap(),
+                description: None,
+            },
+            options: None,
+            fields: HashSet::from([
+                field("starts", FieldType::DateTime),
+                field("doors", FieldType::Time),
+            ]),
+            relations: HashSet::new(),
+            renamed_from: None,
+        }));
+        let registry: &'static MockRegistry = Box::leak(Box::new(MockRegistry {
+            types: HashMap::new(),
+        }));
+        let parse = |query: &str| {
+            parse_query(
+                &parse_query_to_json(query),
+                dt,
+                registry,
+                &crate::application::PaginationSettings::default(),
+                &QueryLimits::default(),
+            )
+        };
+
+        let query =
+            parse("filters[starts][$gte]=2026-07-06T12:00:00&timeZone=Europe/Bucharest").unwrap();
+        match query.filter {
+            FilterExpression::GreaterThanOrEqual { value, .. } => assert_eq!(
+                value,
+                DomainValue::DateTime(
+                    chrono::DateTime::parse_from_rfc3339("2026-07-06T09:00:00Z")
+                        .unwrap()
+                        .to_utc()
+                )
+            ),
+            other => panic!("unexpected {other:?}"),
+        }
+        assert!(parse("filters[doors][$lt]=20:00:00").is_ok());
+
+        for (query, detail) in [
+            (
+                "filters[starts][$gte]=2026-07-06T12:00:00",
+                "Invalid value for field '<filter>': '2026-07-06T12:00:00' is not a valid RFC 3339 datetime",
+            ),
+            (
+                "filters[starts][$gte]=2026-07-06T12:00:00Z&timeZone=Mars/Olympus",
+                "Unknown time zone: 'Mars/Olympus'",
+            ),
+        ] {
+            match parse(query) {
+                Err(ApiError::UnprocessableEntity(message)) => {
+                    assert_eq!(message, detail, "{query}")
+                }
+                other => panic!("{query}: unexpected {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_populate_strategy() {
+        let dt: &'static DocumentType = Box::leak(Box::new(DocumentType {
+            id: DocumentTypeId::try_new("article3").unwrap(),
+            kind: DocumentKind::Collection,
+            info: DocumentTypeInfo {
+                title: DocumentTitle::try_new("Article3").unwrap(),
+                singular_name: DocumentTypeId::try_new("article3").unwrap(),
+                plural_name: DocumentTypeId::try_new("article3s").unwrap(),
+                description: None,
+            },
+            options: None,
+            fields: HashSet::new(),
+            relations: HashSet::new(),
+            renamed_from: None,
+        }));
+        let registry: &'static MockRegistry = Box::leak(Box::new(MockRegistry {
+            types: HashMap::new(),
+        }));
+        let parse = |query: &str| {
+            parse_query(
+                &parse_query_to_json(query),
+                dt,
+                registry,
+                &crate::application::PaginationSettings::default(),
+                &QueryLimits::default(),
+            )
+        };
+
+        assert_eq!(
+            parse("").unwrap().populate_strategy,
+            PopulateStrategy::Batched
+        );
+        assert_eq!(
+            parse("populateStrategy=lateral").unwrap().populate_strategy,
+            PopulateStrategy::Lateral
+        );
+        assert!(matches!(
+            parse("populateStrategy=eager"),
+            Err(ApiError::UnprocessableEntity(_))
+        ));
+    }
+
+    #[test]
+    fn test_modified_status() {
+        let dt: &'static DocumentType = Box::leak(Box::new(DocumentType {
+            id: DocumentTypeId::try_new("article5").unwrap(),
+            kind: DocumentKind::Collection,
+            info: DocumentTypeInfo {
+                title: DocumentTitle::try_new("Article5").unwrap(),
+                singular_name: DocumentTypeId::try_new("article5").unwrap(),
+                plural_name: DocumentTypeId::try_new("article5s").unwrap(),
+                description: None,
+            },
+            options: None,
+            fields: HashSet::new(),
+            relations: HashSet::new(),
+            renamed_from: None,
+        }));
+        let registry: &'static MockRegistry = Box::leak(Box::new(MockRegistry {
+            types: HashMap::new(),
+        }));
+        let parse = |query: &str| {
+            parse_query(
+                &parse_query_to_json(query),
+                dt,
+                registry,
+                &crate::application::PaginationSettings::default(),
+                &QueryLimits::default(),
+            )
+        };
+
+        let query = parse("status=modified").unwrap();
+        assert_eq!(query.status, DocumentStatus::Draft);
+        assert!(matches!(query.filter, FilterExpression::Modified));
+        assert!(matches!(
+            parse("status=draft").unwrap().filter,
+            FilterExpression::None
+        ));
+        assert!(matches!(
+            parse("status=archived"),
+            Err(ApiError::UnprocessableEntity(_))
+        ));
+    }
+
+    #[test]
+    fn test_with_count() {
+        let relation = |id: &str, relation_type| DocumentRelation {
+            id: AttributeId::try_new(id).unwrap(),
+            target: DocumentTypeId::try_new("brand").unwrap(),
+            relation_type,
+            on_delete: Default::default(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+        };
+        let dt: &'static DocumentType = Box::leak(Box::new(DocumentType {
+            id: DocumentTypeId::try_new("partner").unwrap(),
+            kind: DocumentKind::Collection,
+            info: DocumentTypeInfo {
+                title: DocumentTitle::try_new("Partner").unwrap(),
+                singular_name: DocumentTypeId::try_new("partner").unwrap(),
+                plural_name: DocumentTypeId::try_new("partners").unwrap(),
+                description: None,
+            },
+            options: None,
+            fields: HashSet::new(),
+            relations: HashSet::from([
+                relation("brands", RelationType::HasMany),
+                relation("tags", RelationType::HasMany),
+                relation("owner", RelationType::BelongsToOne),
+            ]),
+            renamed_from: None,
+        }));
+        let registry: &'static MockRegistry = Box::leak(Box::new(MockRegistry {
+            types: HashMap::new(),
+        }));
+        let parse = |query: &str| {
+            parse_query(
+                &parse_query_to_json(query),
+                dt,
+                registry,
+                &crate::application::PaginationSettings::default(),
+                &QueryLimits::default(),
+            )
+        };
+        let names = |query: &str| {
+            parse(query)
+                .unwrap()
+                .with_count
+                .iter()
+                .map(|attribute| attribute.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(names("").is_empty());
+        assert_eq!(names("withCount=tags,brands,tags"), ["tags", "brands"]);
+        assert_eq!(
+            names("withCount[]=brands&withCount[]=tags"),
+            ["brands", "tags"]
+        );
+        for query in ["withCount=logo", "withCount=owner"] {
+            assert!(
+                matches!(parse(query), Err(ApiError::UnprocessableEntity(_))),
+                "{query}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_populate_options() {
+        let field = |id: &str| DocumentField {
+            id: AttributeId::try_new(id).unwrap(),
+            field_type: FieldType::Text,
+            constraints: HashSet::new(),
+            required: false,
+            unique: false,
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        };
+        let dt_brand: &'static DocumentType = Box::leak(Box::new(DocumentType {
+            id: DocumentTypeId::try_new("brand").unwrap(),
+            kind: DocumentKind::Collection,
+            info: DocumentTypeInfo {
+                title: DocumentTitle::try_new("Brand").unwrap(),
+                singular_name: DocumentTypeId::try_new("brand").unwrap(),
+                plural_name: DocumentTypeId::try_new("brands").unwrap(),
+                description: None,
+            },
+            options: None,
+            fields: HashSet::from([field("name"), field("country")]),
+            relations: HashSet::new(),
+            renamed_from: None,
+        }));
+        let dt_partner: &'static DocumentType = Box::leak(Box::new(DocumentType {
+            id: DocumentTypeId::try_new("partner").unwrap(),
+            kind: DocumentKind::Collection,
+            info: DocumentTypeInfo {
+                title: DocumentTitle::try_new("Partner").unwrap(),
+                singular_name: DocumentTypeId::try_new("partner").unwrap(),
+                plural_name: DocumentTypeId::try_new("partners").unwrap(),
+                description: None,
+            },
+            options: None,
+            fields: HashSet::new(),
+            relations: HashSet::from([DocumentRelation {
+                id: AttributeId::try_new("brands").unwrap(),
+                target: DocumentTypeId::try_new("brand").unwrap(),
+                relation_type: RelationType::HasMany,
+                on_delete: Default::default(),
+                renamed_from: None,
+                api_name: None,
+                description: None,
+            }]),
+            renamed_from: None,
+        }));
+        let registry: &'static MockRegistry = Box::leak(Box::new(MockRegistry {
+            types: HashMap::from([(dt_brand.id.clone(), dt_brand)]),
+        }));
+        let parse = |query: &str| {
+            parse_query(
+                &parse_query_to_json(query),
+                dt_partner,
+                registry,
+                &crate::application::PaginationSettings::default(),
+                &QueryLimits::default(),
+            )
+        };
+        let brands = AttributeId::try_new("brands").unwrap();
+
+        let q = parse(
+            "populate[brands][fields]=name\
+            &populate[brands][sort]=name:desc\
+            &populate[brands][limit]=5\
+            &populate[brands][filters][country][$eq]=de",
+        )
+        .unwrap();
+        assert_eq!(q.populate, Some(vec![brands.clone()]));
+        let options = &q.populate_options[&brands];
+        assert_eq!(
+            options.fields,
+            Some(vec![AttributeId::try_new("name").unwrap()])
+        );
+        assert_eq!(options.sort.len(), 1);
+        assert_eq!(options.sort[0].field, "name");
+        assert_eq!(options.sort[0].direction, SortDirection::Descending);
+        assert_eq!(options.limit, Some(5));
+        let filter = format!("{:?}", q.populate_filters.unwrap()[&brands]);
+        assert!(
+            filter.contains("country") && filter.contains("de"),
+            "{filter}"
+        );
+
+        // limits are capped at the maximum page size
+        let q = parse("populate[brands][limit]=100000").unwrap();
+        assert_eq!(
+            q.populate_options[&brands].limit,
+            Some(u64::from(
+                crate::application::PaginationSettings::default().max_page_size
+            ))
+        );
+
+        for query in [
+            "populate[brands][fields]=logo",
+            "populate[brands][sort]=logo",
+            "populate[brands][limit]=0",
+            "populate[brands][limit]=many",
+            "populate[brands][offset]=5",
+            "populate[logos][limit]=5",
+        ] {
+            assert!(
+                matches!(parse(query), Err(ApiError::UnprocessableEntity(_))),
+                "{query}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_aggregate_query() {
+        let dt: &'static DocumentType = Box::leak(Box::new(DocumentType {
+            id: DocumentTypeId::try_new("product").unwrap(),
+            kind: DocumentKind::Collection,
+            info: DocumentTypeInfo {
+                title: DocumentTitle::try_new("Product").unwrap(),
+                singular_name: DocumentTypeId::try_new("product").unwrap(),
+                plural_name: DocumentTypeId::try_new("products").unwrap(),
+                description: None,
+            },
+            options: None,
+            fields: HashSet::from([
+                DocumentField {
+                    id: AttributeId::try_new("category").unwrap(),
+                    field_type: FieldType::Text,
+                    constraints: HashSet::new(),
+                    required: false,
+                    unique: false,
+                    renamed_from: None,
+                    api_name: None,
+                    description: None,
+                    filterable: false,
+                    sortable: false,
+                    personal: false,
+                },
+                DocumentField {
+                    id: AttributeId::try_new("price").unwrap(),
+                    field_type: FieldType::Integer(luminair_common::entities::IntegerSize::Int32),
+                    constraints: HashSet::new(),
+                    required: false,
+                    unique: false,
+                    renamed_from: None,
+                    api_name: None,
+                    description: None,
+                    filterable: false,
+                    sortable: false,
+                    personal: false,
+                },
+            ]),
+            relations: HashSet::new(),
+            renamed_from: None,
+        }));
+        let registry: &'static MockRegistry = Box::leak(Box::new(MockRegistry {
+            types: HashMap::new(),
+        }));
+        let parse = |query: &str| parse_aggregate_query(&parse_query_to_json(query), dt, registry);
+
+        let q = parse("groupBy=category&sum=price&count").unwrap();
+        assert_eq!(q.group_by, vec![AttributeId::try_new("category").unwrap()]);
+        assert_eq!(
+            q.metrics,
+            vec![
+                Metric {
+                    function: AggregateFunction::Count,
+                    field: None,
+                },
+                Metric {
+                    function: AggregateFunction::Sum,
+                    field: Some(AttributeId::try_new("price").unwrap()),
+                },
+            ]
+        );
+
+        // count is the default metric
+        let q = parse("groupBy=category").unwrap();
+        assert_eq!(q.metrics.len(), 1);
+        assert_eq!(q.metrics[0].function, AggregateFunction::Count);
+
+        // sums are only computed over numeric fields
+        assert!(matches!(
+            parse("sum=category"),
+            Err(ApiError::UnprocessableEntity(_))
+        ));
+        assert!(matches!(
+            parse("groupBy=ghost"),
+            Err(ApiError::UnprocessableEntity(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_exists_query() {
+        let parse = |query: &str| parse_exists_query(&parse_query_to_json(query));
+
+        assert_eq!(parse("").unwrap(), DocumentStatus::Published);
+        assert_eq!(parse("status=draft").unwrap(), DocumentStatus::Draft);
+        assert!(matches!(
+            parse("status=archived"),
+            Err(ApiError::UnprocessableEntity(_))
+        ));
+        assert!(matches!(
+            parse("populate=*"),
+            Err(ApiError::UnprocessableEntity(_))
+        ));
+    }
+
+    #[test]
+    fn test_random_order_and_sample() {
+        let dt: &'static DocumentType = Box::leak(Box::new(DocumentType {
+            id: DocumentTypeId::try_new("article4").unwrap(),
+            kind: DocumentKind::Collection,
+            info: DocumentTypeInfo {
+                title: DocumentTitle::try_new("Article4").unwrap(),
+                singular_name: DocumentTypeId::try_new("article4").unwrap(),
+                plural_name: DocumentTypeId::try_new("article4s").unwrap(),
+                description: None,
+            },
+            options: None,
+            fields: HashSet::new(),
+            relations: HashSet::new(),
+            renamed_from: None,
+        }));
+        let registry: &'static MockRegistry = Box::leak(Box::new(MockRegistry {
+            types: HashMap::new(),
+        }));
+        let parse = |query: &str| {
+            parse_query(
+                &parse_query_to_json(query),
+                dt,
+                registry,
+                &crate::application::PaginationSettings::default(),
+                &QueryLimits::default(),
+            )
+        };
+
+        let q = parse("").unwrap();
+        assert_eq!(q.random_order, None);
+        assert_eq!(q.sample, None);
+
+        let q = parse("sort=random").unwrap();
+        assert_eq!(q.random_order, Some(RandomOrder { seed: None }));
+        assert!(q.sorts.is_empty());
+
+        let q = parse("sort=random&seed=42&sample=2.5").unwrap();
+        assert_eq!(q.random_order, Some(RandomOrder { seed: Some(42) }));
+        assert_eq!(
+            q.sample,
+            Some(TableSample {
+                percent: 2.5,
+                seed: Some(42),
+            })
+        );
+
+        for query in [
+            "seed=42",
+            "sort=random&seed=x",
+            "sample=0",
+            "sample=101",
+            "sample=some",
+        ] {
+            assert!(
+                matches!(parse(query), Err(ApiError::UnprocessableEntity(_))),
+                "{query}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_attributes_are_named_by_their_key_in_the_responses() {
+        let field = |id: &str, api_name: Option<&str>| DocumentField {
+            id: AttributeId::try_new(id).unwrap(),
+            field_type: FieldType::Text,
+            constraints: HashSet::new(),
+            required: false,
+            unique: false,
+            renamed_from: None,
+            api_name: api_name.map(String::from),
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        };
+        let dt_person: &'static DocumentType = Box::leak(Box::new(DocumentType {
+            id: DocumentTypeId::try_new("person").unwrap(),
+            kind: DocumentKind::Collection,
+            info: DocumentTypeInfo {
+                title: DocumentTitle::try_new("Person").unwrap(),
+                singular_name: DocumentTypeId::try_new("person").unwrap(),
+                plural_name: DocumentTypeId::try_new("people").unwrap(),
+                description: None,
+            },
+            options: None,
+            fields: HashSet::from([field("full_name", None)]),
+            relations: HashSet::new(),
+            renamed_from: None,
+        }));
+        let dt_post: &'static DocumentType = Box::leak(Box::new(DocumentType {
+            id: DocumentTypeId::try_new("post").unwrap(),
+            kind: DocumentKind::Collection,
+            info: DocumentTypeInfo {
+                title: DocumentTitle::try_new("Post").unwrap(),
+                singular_name: DocumentTypeId::try_new("post").unwrap(),
+                plural_name: DocumentTypeId::try_new("posts").unwrap(),
+                description: None,
+            },
+            options: None,
+            fields: HashSet::from([field("headline", Some("title")), field("sub_title", None)]),
+            relations: HashSet::from([DocumentRelation {
+                id: AttributeId::try_new("written_by").unwrap(),
+                target: DocumentTypeId::try_new("person").unwrap(),
+                relation_type: RelationType::HasOne,
+                on_delete: Default::default(),
+                renamed_from: None,
+                api_name: Some("author".to_string()),
+                description: None,
+            }]),
+            renamed_from: None,
+        }));
+        let registry: &'static MockRegistry = Box::leak(Box::new(MockRegistry {
+            types: HashMap::from([(dt_person.id.clone(), dt_person)]),
+        }));
+        let parse = |query: &str| {
+            parse_query(
+                &parse_query_to_json(query),
+                dt_post,
+                registry,
+                &crate::application::PaginationSettings::default(),
+                &QueryLimits::default(),
+            )
+        };
+        let written_by = AttributeId::try_new("written_by").unwrap();
+
+        let q = parse(
+            "sort=title:desc,subTitle\
+            &filters[title][$eq]=Hello\
+            &filters[author][fullName][$eq]=Jane\
+            &populate[author][fields]=fullName",
+        )
+        .unwrap();
+        let sorts: Vec<&str> = q.sorts.iter().map(|sort| sort.field.as_str()).collect();
+        assert_eq!(sorts, ["headline", "sub_title"]);
+        let filter = format!("{:?}", q.filter);
+        assert!(filter.contains("\"headline\""), "{filter}");
+        assert!(!filter.contains("\"title\""), "{filter}");
+        assert!(filter.contains("HasRelation"), "{filter}");
+        let populated = format!("{:?}", q.populate_filters.unwrap()[&written_by]);
+        assert!(populated.contains("\"full_name\""), "{populated}");
+        assert_eq!(q.populate, Some(vec![written_by.clone()]));
+        assert_eq!(
+            q.populate_options[&written_by].fields,
+            Some(vec![AttributeId::try_new("full_name").unwrap()])
+        );
+
+        // the ids name them as well
+        let q = parse("sort=headline&populate[written_by][fields]=full_name").unwrap();
+        assert_eq!(q.sorts[0].field, "headline");
+        assert_eq!(q.populate, Some(vec![written_by]));
+    }
 }
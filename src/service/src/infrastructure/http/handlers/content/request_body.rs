@@ -1,12 +1,18 @@
 use std::collections::HashMap;
 
+use axum::http::HeaderMap;
+use axum::http::header::IF_MATCH;
+use chrono::{DateTime, Utc};
+use luminair_common::entities::{FieldType, LocalizationId};
 use luminair_common::{AttributeId, DocumentType};
 
 use crate::application::commands::RelationOperation;
 use crate::domain::document::DocumentInstanceId;
 use crate::domain::document::content::ContentValue;
 use crate::domain::document::error::DocumentError;
+use crate::domain::document::lifecycle::PublicationSchedule;
 use crate::infrastructure::http::api::ApiError;
+use crate::infrastructure::http::handlers::content::response::attribute_key;
 
 /// Classified JSON fields and relations, ready for parsing into domain types/operations.
 #[derive(Debug)]
@@ -41,30 +47,81 @@ pub fn classify_document_data(
     let mut relations = HashMap::new();
 
     for (k, v) in data_obj {
-        let attr_id = AttributeId::try_new(k)
-            .map_err(|_| ApiError::UnprocessableEntity(format!("Invalid field name: {}", k)))?;
+        let Some(attr_id) = payload_attribute(document_type, k) else {
+            return Err(ApiError::invalid_field(
+                k,
+                "unknown",
+                format!("Unknown field or relation: {}", k),
+            ));
+        };
 
-        if document_type.fields.contains(&attr_id) {
-            fields.insert(attr_id, v.clone());
-        } else if document_type.relations.contains(&attr_id) {
-            relations.insert(attr_id, v.clone());
+        if document_type.fields.contains(attr_id) {
+            fields.insert(attr_id.clone(), v.clone());
         } else {
-            return Err(ApiError::UnprocessableEntity(format!(
-                "Unknown field or relation: {}",
-                k
-            )));
+            relations.insert(attr_id.clone(), v.clone());
         }
     }
 
     Ok(ClassifiedDocumentData { fields, relations })
 }
 
+/// Field or relation of `document_type` a key of a payload names: by its key
+/// in the responses, see [`attribute_key`], or by its id.
+pub fn payload_attribute<'a>(
+    document_type: &'a DocumentType,
+    key: &str,
+) -> Option<&'a AttributeId> {
+    let fields = document_type.fields.iter().map(|field| &field.id);
+    let relations = document_type.relations.iter().map(|relation| &relation.id);
+    let mut attributes = fields.chain(relations);
+    attributes.find(|id| attribute_key(document_type, id) == key || id.as_ref() == key)
+}
+
+/// Parse the `?locale=` query parameter of a write request.
+///
+/// The locale must be one of the localizations of the document type.
+pub fn parse_locale(
+    query_map: &serde_json::Map<String, serde_json::Value>,
+    document_type: &DocumentType,
+) -> Result<Option<LocalizationId>, ApiError> {
+    let Some(value) = query_map.get("locale") else {
+        return Ok(None);
+    };
+    let locale = value
+        .as_str()
+        .ok_or_else(|| ApiError::UnprocessableEntity(format!("Invalid locale: {}", value)))?;
+    supported_locale(locale, document_type).map(Some)
+}
+
+/// Resolve a locale code to one of the localizations of the document type.
+pub fn supported_locale(
+    locale: &str,
+    document_type: &DocumentType,
+) -> Result<LocalizationId, ApiError> {
+    let locale = LocalizationId::try_new(locale)
+        .map_err(|_| ApiError::UnprocessableEntity(format!("Invalid locale: {}", locale)))?;
+    let supported = document_type
+        .options
+        .as_ref()
+        .is_some_and(|options| options.localizations.contains(&locale));
+    if !supported {
+        return Err(ApiError::UnprocessableEntity(format!(
+            "Locale '{}' is not supported by document type '{}'",
+            locale, document_type.id
+        )));
+    }
+    Ok(locale)
+}
+
 /// Parse and validate a JSON request map into a field map.
 ///
 /// Each key in the payload must be a valid [`AttributeId`] that exists on the
 /// document type. Unknown fields are rejected. Fields that are declared
 /// `required` and supplied as `null` are rejected.
 ///
+/// With a `locale`, `LocalizedText` fields take the plain text of that
+/// locale instead of an object with all translations.
+///
 /// All type conversion and [`FieldConstraint`] validation is delegated to
 /// [`ContentValue::from_json`], which is the single canonical JSON → domain codec.
 ///
@@ -77,6 +134,7 @@ pub fn classify_document_data(
 pub fn build_fields_from_map(
     document_type: &DocumentType,
     fields_map: &HashMap<AttributeId, serde_json::Value>,
+    locale: Option<&LocalizationId>,
 ) -> Result<HashMap<AttributeId, ContentValue>, DocumentError> {
     let mut fields = HashMap::with_capacity(fields_map.len());
 
@@ -88,10 +146,23 @@ pub fn build_fields_from_map(
             }
         })?;
 
-        fields.insert(
-            attribute_id.clone(),
-            ContentValue::from_json(field_value, field_def)?,
-        );
+        let value = match locale {
+            Some(locale) if field_def.field_type == FieldType::LocalizedText => {
+                if !field_value.is_string() {
+                    return Err(DocumentError::InvalidFieldValue {
+                        field: attribute_id.as_ref().to_string(),
+                        reason: format!("expected the '{}' text as a string", locale),
+                    });
+                }
+                let texts = serde_json::Value::Object(serde_json::Map::from_iter([(
+                    locale.to_string(),
+                    field_value.clone(),
+                )]));
+                ContentValue::from_json(&texts, field_def)?
+            }
+            _ => ContentValue::from_json(field_value, field_def)?,
+        };
+        fields.insert(attribute_id.clone(), value);
     }
 
     Ok(fields)
@@ -107,11 +178,18 @@ pub fn parse_relation_operations(
             ApiError::UnprocessableEntity(format!("Field '{}' must be an object", attr_id.as_ref()))
         })?;
 
-        if field_obj.contains_key("set") {
-            return Err(ApiError::UnprocessableEntity(format!(
-                "Relation field '{}': 'set' operation is not yet supported in the MVP",
-                attr_id.as_ref()
-            )));
+        if let Some(set) = field_obj.get("set") {
+            if field_obj.contains_key("connect") || field_obj.contains_key("disconnect") {
+                return Err(ApiError::UnprocessableEntity(format!(
+                    "Relation field '{}': 'set' can't be combined with 'connect' or 'disconnect'",
+                    attr_id.as_ref()
+                )));
+            }
+            operations.insert(
+                attr_id.clone(),
+                RelationOperation::Set(parse_ids_from_list(set)?),
+            );
+            continue;
         }
 
         let connect = parse_ids_from_list(
@@ -137,11 +215,60 @@ pub fn parse_relation_operations(
     Ok(operations)
 }
 
+/// Parse the expected document version from an `If-Match` header.
+///
+/// The entity tag is the document `version` from the response metadata,
+/// quoted (`"3"`) or not (`3`). A missing header or `*` means the write
+/// isn't conditional.
+pub fn parse_if_match(headers: &HeaderMap) -> Result<Option<i32>, ApiError> {
+    let Some(value) = headers.get(IF_MATCH) else {
+        return Ok(None);
+    };
+    let invalid = || ApiError::UnprocessableEntity("If-Match must be a document version".into());
+    let tag = value.to_str().map_err(|_| invalid())?.trim();
+    if tag == "*" {
+        return Ok(None);
+    }
+    let tag = tag.strip_prefix("W/").unwrap_or(tag).trim_matches('"');
+    tag.parse::<i32>().map(Some).map_err(|_| invalid())
+}
+
+/// Parse the optional `publishAt` and `unpublishAt` RFC 3339 timestamps of a
+/// publish request body.
+pub fn parse_publication_schedule(
+    payload: Option<&serde_json::Value>,
+) -> Result<PublicationSchedule, ApiError> {
+    let Some(payload) = payload else {
+        return Ok(PublicationSchedule::default());
+    };
+    let body = payload
+        .as_object()
+        .ok_or_else(|| ApiError::UnprocessableEntity("body must be a JSON object".into()))?;
+
+    let timestamp = |key: &str| -> Result<Option<DateTime<Utc>>, ApiError> {
+        match body.get(key) {
+            None | Some(serde_json::Value::Null) => Ok(None),
+            Some(value) => value
+                .as_str()
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                .map(|at| Some(at.with_timezone(&Utc)))
+                .ok_or_else(|| {
+                    ApiError::UnprocessableEntity(format!("{} must be an RFC 3339 timestamp", key))
+                }),
+        }
+    };
+
+    Ok(PublicationSchedule {
+        publish_at: timestamp("publishAt")?,
+        unpublish_at: timestamp("unpublishAt")?,
+    })
+}
+
 /// Parse a JSON array of document IDs in shorthand (`"uuid-string"`) or
 /// longhand (`{ "documentId": "uuid-string" }`) format into `DocumentInstanceId`s.
 fn parse_ids_from_list(value: &serde_json::Value) -> Result<Vec<DocumentInstanceId>, ApiError> {
     let arr = value.as_array().ok_or_else(|| {
-        ApiError::UnprocessableEntity("connect/disconnect must be an array".into())
+        ApiError::UnprocessableEntity("connect/disconnect/set must be an array".into())
     })?;
 
     arr.iter()
@@ -195,12 +322,23 @@ mod tests {
                 constraints: HashSet::new(),
                 required: true,
                 unique: false,
+                renamed_from: None,
+                api_name: None,
+                description: None,
+                filterable: false,
+                sortable: false,
+                personal: false,
             }]),
             relations: HashSet::from([DocumentRelation {
                 id: AttributeId::try_new("author").unwrap(),
                 target: DocumentTypeId::try_new("author").unwrap(),
                 relation_type: RelationType::HasOne,
+                on_delete: Default::default(),
+                renamed_from: None,
+                api_name: None,
+                description: None,
             }]),
+            renamed_from: None,
         }
     }
 
@@ -253,6 +391,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_classify_document_data_by_api_name() {
+        let mut dt = mock_document_type();
+        dt.fields.insert(DocumentField {
+            id: AttributeId::try_new("first_name").unwrap(),
+            field_type: luminair_common::entities::FieldType::Text,
+            constraints: HashSet::new(),
+            required: false,
+            unique: false,
+            renamed_from: None,
+            api_name: Some("givenName".to_string()),
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        });
+        dt.fields.insert(DocumentField {
+            id: AttributeId::try_new("last_name").unwrap(),
+            field_type: luminair_common::entities::FieldType::Text,
+            constraints: HashSet::new(),
+            required: false,
+            unique: false,
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        });
+        let payload = json!({
+            "givenName": "Ada",
+            "lastName": "Lovelace",
+            "title": "Countess"
+        });
+
+        let classified = classify_document_data(payload.as_object().unwrap(), &dt).unwrap();
+        for (id, value) in [
+            ("first_name", "Ada"),
+            ("last_name", "Lovelace"),
+            ("title", "Countess"),
+        ] {
+            let field = classified.fields.get(&AttributeId::try_new(id).unwrap());
+            assert_eq!(field, Some(&json!(value)));
+        }
+    }
+
     #[test]
     fn test_classify_document_data_unknown_field() {
         let dt = mock_document_type();
@@ -271,7 +455,7 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_relation_operations_rejects_set() {
+    fn test_parse_relation_operations_set() {
         let payload = json!({
             "author": {
                 "set": ["9c00b05b-800e-436f-8705-d14bfb2875b4"]
@@ -283,12 +467,121 @@ mod tests {
             payload.get("author").unwrap().clone(),
         );
 
+        let operations = parse_relation_operations(&map).unwrap();
+        let operation = operations
+            .get(&AttributeId::try_new("author").unwrap())
+            .unwrap();
+        assert!(matches!(operation, RelationOperation::Set(ids) if ids.len() == 1));
+    }
+
+    #[test]
+    fn test_parse_relation_operations_rejects_set_with_connect() {
+        let payload = json!({
+            "author": {
+                "set": ["9c00b05b-800e-436f-8705-d14bfb2875b4"],
+                "connect": ["9c00b05b-800e-436f-8705-d14bfb2875b4"]
+            }
+        });
+        let mut map = HashMap::new();
+        map.insert(
+            AttributeId::try_new("author").unwrap(),
+            payload.get("author").unwrap().clone(),
+        );
+
         let res = parse_relation_operations(&map);
         assert!(res.is_err());
-        assert!(
-            res.unwrap_err()
-                .to_string()
-                .contains("set' operation is not yet supported")
+        assert!(res.unwrap_err().to_string().contains("can't be combined"));
+    }
+
+    #[test]
+    fn test_build_fields_from_map_with_locale() {
+        let mut dt = mock_document_type();
+        dt.fields.insert(DocumentField {
+            id: AttributeId::try_new("name").unwrap(),
+            field_type: luminair_common::entities::FieldType::LocalizedText,
+            constraints: HashSet::new(),
+            required: false,
+            unique: false,
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        });
+        let name = AttributeId::try_new("name").unwrap();
+        let locale = LocalizationId::try_new("ro").unwrap();
+
+        let map = HashMap::from([(name.clone(), json!("Magazin"))]);
+        let fields = build_fields_from_map(&dt, &map, Some(&locale)).unwrap();
+        let Some(ContentValue::LocalizedText(texts)) = fields.get(&name) else {
+            panic!("expected localized text");
+        };
+        assert_eq!(texts.len(), 1);
+        assert_eq!(texts.get("ro").unwrap(), "Magazin");
+
+        let map = HashMap::from([(name, json!({"ro": "Magazin"}))]);
+        assert!(build_fields_from_map(&dt, &map, Some(&locale)).is_err());
+    }
+
+    #[test]
+    fn test_parse_locale() {
+        let mut dt = mock_document_type();
+        let query = json!({"locale": "ro"});
+        let query = query.as_object().unwrap();
+        assert!(parse_locale(query, &dt).is_err());
+
+        dt.options = Some(luminair_common::entities::DocumentTypeOptions {
+            draft_and_publish: false,
+            localizations: vec![LocalizationId::try_new("ro").unwrap()],
+            workflow: None,
+            cache_control: None,
+            feed: None,
+            operations: None,
+            partitioning: None,
+            view: None,
+            retention: None,
+        });
+        assert_eq!(
+            parse_locale(query, &dt).unwrap(),
+            Some(LocalizationId::try_new("ro").unwrap())
         );
+        assert_eq!(parse_locale(&serde_json::Map::new(), &dt).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_if_match() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_if_match(&headers).unwrap(), None);
+
+        headers.insert(IF_MATCH, "\"3\"".parse().unwrap());
+        assert_eq!(parse_if_match(&headers).unwrap(), Some(3));
+
+        headers.insert(IF_MATCH, "W/\"4\"".parse().unwrap());
+        assert_eq!(parse_if_match(&headers).unwrap(), Some(4));
+
+        headers.insert(IF_MATCH, "*".parse().unwrap());
+        assert_eq!(parse_if_match(&headers).unwrap(), None);
+
+        headers.insert(IF_MATCH, "\"abc\"".parse().unwrap());
+        assert!(parse_if_match(&headers).is_err());
+    }
+    #[test]
+    fn test_parse_publication_schedule() {
+        assert_eq!(
+            parse_publication_schedule(None).unwrap(),
+            PublicationSchedule::default()
+        );
+
+        let payload = json!({"publishAt": "2030-01-01T10:00:00+02:00", "unpublishAt": null});
+        let schedule = parse_publication_schedule(Some(&payload)).unwrap();
+        assert_eq!(
+            schedule.publish_at.unwrap().to_rfc3339(),
+            "2030-01-01T08:00:00+00:00"
+        );
+        assert_eq!(schedule.unpublish_at, None);
+
+        let payload = json!({"unpublishAt": "tomorrow"});
+        assert!(parse_publication_schedule(Some(&payload)).is_err());
     }
 }
@@ -1,10 +1,23 @@
-use crate::domain::document::DocumentInstance;
+use crate::application::PaginationSettings;
+use crate::domain::document::content::DomainValue;
 use crate::domain::document::lifecycle::PublicationState;
+use crate::domain::document::{DocumentInstance, DocumentRelation};
+use crate::domain::query::{AggregateGroup, DocumentStatus, SearchHit};
+use crate::domain::repository::InboundReference;
+use crate::infrastructure::http::api::ReferenceResponse;
+use crate::infrastructure::http::handlers::content::query_params;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, header};
 use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use luminair_common::entities::RelationType;
+use luminair_common::{AttributeId, DocumentType, DocumentTypesRegistry};
 
 use serde::Serialize;
 use serde_json::Value as JsonValue;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+/// Media type of newline-delimited JSON exports of a collection.
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ManyDocumentsResponse {
@@ -13,7 +26,14 @@ pub struct ManyDocumentsResponse {
 }
 
 impl ManyDocumentsResponse {
-    pub fn new(documents: Vec<DocumentInstance>, page: u16, page_size: u16, total: u64) -> Self {
+    pub fn new(
+        documents: Vec<DocumentInstance>,
+        document_type: &DocumentType,
+        registry: &dyn DocumentTypesRegistry,
+        page: u16,
+        page_size: u16,
+        total: u64,
+    ) -> Self {
         let meta = MetadataResponse {
             page,
             page_size,
@@ -22,13 +42,78 @@ impl ManyDocumentsResponse {
         Self {
             data: documents
                 .into_iter()
-                .map(DocumentInstanceResponse::from)
+                .map(|document| DocumentInstanceResponse::new(document, document_type, registry))
                 .collect(),
             meta,
         }
     }
 }
 
+/// Encode one document as a line of a newline-delimited JSON export.
+pub fn ndjson_line(
+    document: DocumentInstance,
+    document_type: &DocumentType,
+    registry: &dyn DocumentTypesRegistry,
+) -> Result<String, serde_json::Error> {
+    let response = DocumentInstanceResponse::new(document, document_type, registry);
+    let mut line = serde_json::to_string(&response)?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Header listing the keys a CDN can purge the cached response by.
+pub const SURROGATE_KEY: HeaderName = HeaderName::from_static("surrogate-key");
+
+/// Caching headers of a response with `documents` of `document_type`, read
+/// with `status`.
+///
+/// The published documents get the `Cache-Control` of the options of the
+/// type, drafts `no-store` then. `Surrogate-Key` lists the plural name of the
+/// type and the ids of the documents, populated relations included, so a CDN
+/// can purge the responses including a document once it is published.
+pub fn cache_headers(
+    document_type: &DocumentType,
+    status: DocumentStatus,
+    documents: &[DocumentInstance],
+) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let cache_control = document_type
+        .options
+        .as_ref()
+        .and_then(|options| options.cache_control);
+    if let Some(cache_control) = cache_control {
+        let value = match status {
+            DocumentStatus::Published => cache_control.header_value(),
+            DocumentStatus::Draft => "no-store".to_string(),
+        };
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(header::CACHE_CONTROL, value);
+        }
+    }
+
+    let related_ids = documents
+        .iter()
+        .flat_map(|document| document.relations.values().flatten())
+        .map(|relation| match relation {
+            DocumentRelation::Id(id) => *id,
+            DocumentRelation::Instance(instance) => instance.document_id,
+        });
+    let keys = std::iter::once(document_type.info.plural_name.to_string())
+        .chain(
+            documents
+                .iter()
+                .map(|document| document.document_id)
+                .chain(related_ids)
+                .unique()
+                .map(|id| id.0.to_string()),
+        )
+        .join(" ");
+    if let Ok(value) = HeaderValue::from_str(&keys) {
+        headers.insert(SURROGATE_KEY, value);
+    }
+    headers
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct MetadataResponse {
     pub page: u16,
@@ -36,6 +121,212 @@ pub struct MetadataResponse {
     pub total: u64,
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct AggregateResponse {
+    pub data: Vec<AggregateGroupResponse>,
+}
+
+impl AggregateResponse {
+    pub fn new(groups: Vec<AggregateGroup>) -> Self {
+        Self {
+            data: groups
+                .into_iter()
+                .map(AggregateGroupResponse::from)
+                .collect(),
+        }
+    }
+}
+
+/// `{"group": {"category": "x"}, "metrics": {"count": 2, "sum": {"price": 10}}}`
+#[derive(Debug, Clone, Serialize)]
+pub struct AggregateGroupResponse {
+    pub group: serde_json::Map<String, JsonValue>,
+    pub metrics: serde_json::Map<String, JsonValue>,
+}
+
+impl From<AggregateGroup> for AggregateGroupResponse {
+    fn from(value: AggregateGroup) -> Self {
+        let group = value
+            .group
+            .iter()
+            .map(|(k, v)| (to_api_key(k.as_ref()), JsonValue::from(v)))
+            .collect();
+
+        let mut metrics = serde_json::Map::new();
+        for (metric, metric_value) in value.metrics {
+            let metric_value = metric_value
+                .map(|d| JsonValue::from(&DomainValue::Decimal(d)))
+                .unwrap_or(JsonValue::Null);
+            let name = metric.function.name().to_string();
+            match metric.field {
+                None => {
+                    metrics.insert(name, metric_value);
+                }
+                Some(field) => {
+                    if let JsonValue::Object(per_field) = metrics
+                        .entry(name)
+                        .or_insert_with(|| JsonValue::Object(serde_json::Map::new()))
+                    {
+                        per_field.insert(to_api_key(field.as_ref()), metric_value);
+                    }
+                }
+            }
+        }
+
+        Self { group, metrics }
+    }
+}
+
+/// `{"data": ["a", "b"]}`, or `{"data": [{"value": "a", "count": 2}, ...]}`
+/// when the values are counted.
+#[derive(Debug, Clone, Serialize)]
+pub struct DistinctValuesResponse {
+    pub data: Vec<JsonValue>,
+}
+
+impl DistinctValuesResponse {
+    pub fn new(attribute: &AttributeId, groups: Vec<AggregateGroup>) -> Self {
+        let data = groups
+            .into_iter()
+            .map(|group| {
+                let value = group
+                    .group
+                    .get(attribute)
+                    .map(JsonValue::from)
+                    .unwrap_or(JsonValue::Null);
+                match group.metrics.first() {
+                    Some((_, count)) => serde_json::json!({
+                        "value": value,
+                        "count": count.map(|d| JsonValue::from(&DomainValue::Decimal(d))),
+                    }),
+                    None => value,
+                }
+            })
+            .collect();
+        Self { data }
+    }
+}
+
+/// `{"data": {"methods": ["GET", ..], "filters": {"name": ["$eq", ..]}, ..}}`,
+/// what a route of a document type offers, for generic clients to configure
+/// themselves by an `OPTIONS` request.
+#[derive(Debug, Clone, Serialize)]
+pub struct CapabilitiesResponse {
+    pub data: DocumentTypeCapabilities,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentTypeCapabilities {
+    /// methods the route allows, as in its `Allow` header
+    pub methods: Vec<&'static str>,
+    /// filter operators of every field, by the id `filters` takes
+    pub filters: BTreeMap<String, Vec<&'static str>>,
+    pub default_page_size: u16,
+    pub max_page_size: u16,
+}
+
+impl CapabilitiesResponse {
+    pub fn new(
+        document_type: &DocumentType,
+        methods: Vec<&'static str>,
+        pagination: &PaginationSettings,
+    ) -> Self {
+        let filters = document_type
+            .fields
+            .iter()
+            .map(|field| {
+                (
+                    field.id.to_string(),
+                    query_params::filter_operators(field.field_type),
+                )
+            })
+            .collect();
+        Self {
+            data: DocumentTypeCapabilities {
+                methods,
+                filters,
+                default_page_size: pagination.default_page_size,
+                max_page_size: pagination.max_page_size,
+            },
+        }
+    }
+}
+
+/// Revisions of a document, the most recent first; `version` of every
+/// revision is the version of the document it was saved as.
+#[derive(Debug, Clone, Serialize)]
+pub struct RevisionsResponse {
+    pub data: Vec<DocumentInstanceResponse>,
+}
+
+impl RevisionsResponse {
+    pub fn new(
+        revisions: Vec<DocumentInstance>,
+        document_type: &DocumentType,
+        registry: &dyn DocumentTypesRegistry,
+    ) -> Self {
+        Self {
+            data: revisions
+                .into_iter()
+                .map(|revision| DocumentInstanceResponse::new(revision, document_type, registry))
+                .collect(),
+        }
+    }
+}
+
+/// Documents linking to a document, per relation, with what deleting the
+/// document would do to their links.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReferencesResponse {
+    pub data: Vec<ReferenceResponse>,
+}
+
+impl ReferencesResponse {
+    pub fn new(references: &[InboundReference]) -> Self {
+        Self {
+            data: references.iter().map(ReferenceResponse::from).collect(),
+        }
+    }
+}
+
+/// Documents of all types matching a search, the most relevant first
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResponse {
+    pub data: Vec<SearchResultResponse>,
+}
+
+impl SearchResponse {
+    pub fn new(
+        results: Vec<(SearchHit, DocumentInstance)>,
+        registry: &dyn DocumentTypesRegistry,
+    ) -> Self {
+        Self {
+            data: results
+                .into_iter()
+                .filter_map(|(hit, document)| {
+                    let document_type = registry.get(&hit.document_type)?;
+                    Some(SearchResultResponse {
+                        document_type: document_type.info.plural_name.to_string(),
+                        rank: hit.rank,
+                        document: DocumentInstanceResponse::new(document, document_type, registry),
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// `{"type": "brands", "rank": 0.06, "document": {...}}`, `type` being the
+/// plural name the routes of the document take
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResultResponse {
+    #[serde(rename = "type")]
+    pub document_type: String,
+    pub rank: f32,
+    pub document: DocumentInstanceResponse,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct OneDocumentResponse {
     pub data: DocumentInstanceResponse,
@@ -52,9 +343,13 @@ impl OneDocumentResponse {
     ///
     /// Returns `Some` with the serialisable response if the instance is present,
     /// or `None` if the caller should produce a 404.
-    pub fn from_optional(value: Option<DocumentInstance>) -> Option<Self> {
+    pub fn from_optional(
+        value: Option<DocumentInstance>,
+        document_type: &DocumentType,
+        registry: &dyn DocumentTypesRegistry,
+    ) -> Option<Self> {
         value.map(|row| OneDocumentResponse {
-            data: DocumentInstanceResponse::from(row),
+            data: DocumentInstanceResponse::new(row, document_type, registry),
         })
     }
 }
@@ -65,10 +360,15 @@ pub struct DocumentInstanceResponse {
     pub id: i64,
     pub document_id: String,
     pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workflow_stage: Option<String>,
     #[serde(flatten)]
     pub audit: DocumentInstanceAudit,
     #[serde(flatten)]
     pub published: Option<DocumentInstancePublicationState>,
+    /// number of related documents of the relations of `?withCount`
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub relation_counts: HashMap<String, u64>,
     #[serde(flatten)]
     fields: HashMap<String, AttributeResponse>,
 }
@@ -104,8 +404,14 @@ impl PartialEq for DocumentInstanceResponse {
     }
 }
 
-impl From<DocumentInstance> for DocumentInstanceResponse {
-    fn from(value: DocumentInstance) -> Self {
+impl DocumentInstanceResponse {
+    /// Response of `value`, a document of `document_type`, its fields and
+    /// relations keyed by [`attribute_key`].
+    pub fn new(
+        value: DocumentInstance,
+        document_type: &DocumentType,
+        registry: &dyn DocumentTypesRegistry,
+    ) -> Self {
         let id = value.id.0;
         let document_id = value.document_id.into();
 
@@ -121,15 +427,11 @@ impl From<DocumentInstance> for DocumentInstanceResponse {
             version: audit.version,
         };
 
+        // a draft is modified when it was saved after its published version
         let status = match &value.content.publication_state {
             PublicationState::Published { .. } => "published".to_string(),
-            PublicationState::Draft { revision } => {
-                if *revision == 0 {
-                    "draft".to_string()
-                } else {
-                    "modified".to_string()
-                }
-            }
+            PublicationState::Draft { .. } if value.modified => "modified".to_string(),
+            PublicationState::Draft { .. } => "draft".to_string(),
         };
 
         let published = match value.content.publication_state {
@@ -152,40 +454,202 @@ impl From<DocumentInstance> for DocumentInstanceResponse {
             .iter()
             .map(|(k, v)| {
                 let json_value = JsonValue::from(v);
-                (to_api_key(k.as_ref()), AttributeResponse::Field(json_value))
+                (
+                    attribute_key(document_type, k),
+                    AttributeResponse::Field(json_value),
+                )
             })
             .collect();
 
         for (rel_attr, rel_list) in value.relations {
+            let Some(relation) = registry.relation(&document_type.id, &rel_attr) else {
+                continue;
+            };
             let rel_responses: Vec<DocumentInstanceResponse> = rel_list
                 .into_iter()
                 .filter_map(|r| match r {
-                    crate::domain::document::DocumentRelation::Instance(inst) => {
-                        Some(DocumentInstanceResponse::from(*inst))
-                    }
+                    crate::domain::document::DocumentRelation::Instance(inst) => Some(
+                        DocumentInstanceResponse::new(*inst, relation.target, registry),
+                    ),
                     crate::domain::document::DocumentRelation::Id(_) => None,
                 })
                 .collect();
             if !rel_responses.is_empty() {
                 fields.insert(
-                    to_api_key(rel_attr.as_ref()),
+                    attribute_key(document_type, &rel_attr),
                     AttributeResponse::Relation(rel_responses),
                 );
             }
         }
 
+        let relation_counts = value
+            .relation_counts
+            .into_iter()
+            .map(|(attribute, count)| (attribute_key(document_type, &attribute), count))
+            .collect();
+
         Self {
             id,
             document_id,
             status,
+            workflow_stage: value.workflow_stage.map(|stage| stage.to_string()),
             audit,
             published,
+            relation_counts,
             fields,
         }
     }
 }
 
-fn to_api_key(snake: &str) -> String {
+/// `{"data": [{"id": .., "attributes": {..}}], "meta": {"pagination": {..}}}`,
+/// the list response of Strapi v4.
+#[derive(Debug, Clone, Serialize)]
+pub struct StrapiManyDocumentsResponse {
+    pub data: Vec<StrapiDocumentResponse>,
+    pub meta: StrapiMetadataResponse,
+}
+
+impl StrapiManyDocumentsResponse {
+    pub fn new(
+        documents: Vec<DocumentInstance>,
+        document_type: &DocumentType,
+        registry: &dyn DocumentTypesRegistry,
+        page: u16,
+        page_size: u16,
+        total: u64,
+    ) -> Self {
+        let page_count = match page_size {
+            0 => 0,
+            page_size => total.div_ceil(u64::from(page_size)),
+        };
+        Self {
+            data: documents
+                .into_iter()
+                .map(|document| StrapiDocumentResponse::new(document, document_type, registry))
+                .collect(),
+            meta: StrapiMetadataResponse {
+                pagination: Some(StrapiPaginationResponse {
+                    page,
+                    page_size,
+                    page_count,
+                    total,
+                }),
+            },
+        }
+    }
+}
+
+/// `{"data": {"id": .., "attributes": {..}}, "meta": {}}`, the detail
+/// response of Strapi v4.
+#[derive(Debug, Clone, Serialize)]
+pub struct StrapiOneDocumentResponse {
+    pub data: StrapiDocumentResponse,
+    pub meta: StrapiMetadataResponse,
+}
+
+impl StrapiOneDocumentResponse {
+    pub fn new(
+        document: DocumentInstance,
+        document_type: &DocumentType,
+        registry: &dyn DocumentTypesRegistry,
+    ) -> Self {
+        Self {
+            data: StrapiDocumentResponse::new(document, document_type, registry),
+            meta: StrapiMetadataResponse { pagination: None },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StrapiMetadataResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<StrapiPaginationResponse>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StrapiPaginationResponse {
+    pub page: u16,
+    pub page_size: u16,
+    pub page_count: u64,
+    pub total: u64,
+}
+
+/// A document in the Strapi v4 format.
+///
+/// `id` is the `documentId`, which the routes of this service take; the
+/// `attributes` are the other keys of the native response, with every
+/// populated relation as `{"data": ..}`: one document or `null` for to-one
+/// relations, a list for to-many relations. Counted relations are
+/// `{"data": {"attributes": {"count": ..}}}`, as Strapi answers them.
+#[derive(Debug, Clone, Serialize)]
+pub struct StrapiDocumentResponse {
+    pub id: String,
+    pub attributes: serde_json::Map<String, JsonValue>,
+}
+
+impl StrapiDocumentResponse {
+    pub fn new(
+        mut document: DocumentInstance,
+        document_type: &DocumentType,
+        registry: &dyn DocumentTypesRegistry,
+    ) -> Self {
+        let relations = std::mem::take(&mut document.relations);
+        let relation_counts = std::mem::take(&mut document.relation_counts);
+        let native = DocumentInstanceResponse::new(document, document_type, registry);
+        let id = native.document_id.clone();
+        let mut attributes = match serde_json::to_value(native) {
+            Ok(JsonValue::Object(attributes)) => attributes,
+            _ => serde_json::Map::new(),
+        };
+        attributes.remove("id");
+        attributes.remove("documentId");
+        for (attribute, count) in &relation_counts {
+            attributes.insert(
+                attribute_key(document_type, attribute),
+                serde_json::json!({ "data": { "attributes": { "count": count } } }),
+            );
+        }
+
+        for (attribute, related) in relations {
+            let Some(relation) = registry.relation(&document_type.id, &attribute) else {
+                continue;
+            };
+            let target = relation.target;
+            let mut documents = related.into_iter().filter_map(|related| match related {
+                crate::domain::document::DocumentRelation::Instance(instance) => Some(
+                    serde_json::json!(StrapiDocumentResponse::new(*instance, target, registry)),
+                ),
+                crate::domain::document::DocumentRelation::Id(_) => None,
+            });
+            let data = match relation.relation.relation_type {
+                RelationType::HasOne | RelationType::BelongsToOne => {
+                    documents.next().unwrap_or(JsonValue::Null)
+                }
+                RelationType::HasMany | RelationType::BelongsToMany => {
+                    JsonValue::Array(documents.collect())
+                }
+            };
+            attributes.insert(
+                attribute_key(document_type, &attribute),
+                serde_json::json!({ "data": data }),
+            );
+        }
+
+        Self { id, attributes }
+    }
+}
+
+/// Key of `attribute` of `document_type` in the payloads of the API: the
+/// `apiName` its schema gives it, or else its id in camelCase.
+pub(crate) fn attribute_key(document_type: &DocumentType, attribute: &AttributeId) -> String {
+    document_type
+        .api_name(attribute)
+        .map(String::from)
+        .unwrap_or_else(|| to_api_key(attribute.as_ref()))
+}
+
+pub(crate) fn to_api_key(snake: &str) -> String {
     // "first_name" → "firstName"
     let mut result = String::with_capacity(snake.len());
     let mut next_upper = false;
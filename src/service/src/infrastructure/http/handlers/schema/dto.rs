@@ -2,8 +2,9 @@ use luminair_common::entities::FieldConstraint;
 use luminair_common::{
     DocumentType,
     entities::{
-        DocumentField, DocumentKind, DocumentRelation, DocumentTypeInfo, DocumentTypeOptions,
-        FieldType, RelationType,
+        ApiOperation, CacheControl, DocumentField, DocumentKind, DocumentRelation,
+        DocumentTypeInfo, DocumentTypeOptions, Feed, FieldType, Partitioning, RelationType,
+        Workflow, WorkflowStage,
     },
 };
 use serde::Serialize;
@@ -63,6 +64,30 @@ pub struct DocumentInfoResponse {
 pub struct DocumentOptionsResponse {
     pub draft_and_publish: bool,
     pub localizations: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workflow: Option<WorkflowResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<CacheControl>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feed: Option<Feed>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operations: Option<Vec<ApiOperation>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partitioning: Option<Partitioning>,
+    /// documents are read from a materialized view, and never written
+    pub read_only: bool,
+}
+
+/// Editorial workflow of a document type
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkflowResponse {
+    pub stages: Vec<WorkflowStageResponse>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkflowStageResponse {
+    pub name: String,
+    pub roles: Vec<String>,
 }
 
 /// Attribute of a Document response
@@ -70,6 +95,11 @@ pub struct DocumentOptionsResponse {
 #[serde(rename_all = "camelCase")]
 pub struct AttributeResponse {
     id: String,
+    /// name of the attribute in the payloads, if the schema gives one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
     #[serde(flatten)]
     body: AttributeBodyResponse,
 }
@@ -84,6 +114,9 @@ pub enum AttributeBodyResponse {
         #[serde(default)]
         required: bool,
         constraints: Vec<FieldConstraint>,
+        filterable: bool,
+        sortable: bool,
+        personal: bool,
     },
     Relation {
         #[serde(rename = "relation")]
@@ -137,6 +170,33 @@ impl From<&DocumentTypeOptions> for DocumentOptionsResponse {
         Self {
             draft_and_publish: value.draft_and_publish,
             localizations: value.localizations.iter().map(|l| l.to_string()).collect(),
+            workflow: value.workflow.as_ref().map(WorkflowResponse::from),
+            cache_control: value.cache_control,
+            feed: value.feed.clone(),
+            operations: value.operations.clone(),
+            partitioning: value.partitioning,
+            read_only: value.view.is_some(),
+        }
+    }
+}
+
+impl From<&Workflow> for WorkflowResponse {
+    fn from(value: &Workflow) -> Self {
+        Self {
+            stages: value
+                .stages
+                .iter()
+                .map(WorkflowStageResponse::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<&WorkflowStage> for WorkflowStageResponse {
+    fn from(value: &WorkflowStage) -> Self {
+        Self {
+            name: value.id.to_string(),
+            roles: value.roles.clone(),
         }
     }
 }
@@ -150,8 +210,18 @@ impl From<&DocumentField> for AttributeResponse {
             unique: value.unique,
             required: value.required,
             constraints,
+            filterable: value.filterable,
+            sortable: value.sortable,
+            personal: value.personal,
         };
-        Self { id, body }
+        let api_name = value.api_name.clone();
+        let description = value.description.clone();
+        Self {
+            id,
+            api_name,
+            description,
+            body,
+        }
     }
 }
 
@@ -163,6 +233,13 @@ impl From<&DocumentRelation> for AttributeResponse {
             relation_type: value.relation_type,
             target,
         };
-        Self { id, body }
+        let api_name = value.api_name.clone();
+        let description = value.description.clone();
+        Self {
+            id,
+            api_name,
+            description,
+            body,
+        }
     }
 }
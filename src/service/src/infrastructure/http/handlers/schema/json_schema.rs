@@ -0,0 +1,319 @@
+use crate::infrastructure::http::handlers::content::response::attribute_key;
+use luminair_common::entities::{DocumentField, FieldConstraint, FieldType, IntegerSize};
+use luminair_common::{DocumentType, DocumentTypesRegistry};
+use serde_json::{Map, Value as JsonValue, json};
+use std::collections::BTreeMap;
+
+/// Dialect of the generated schemas.
+const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// JSON Schema of the documents of `document_type` as the content API
+/// answers them, for generating client models.
+///
+/// The types its relations lead to, directly or through other types, are
+/// described under `$defs` by their id, so the schema is self-contained; a
+/// relation back to `document_type` refers to the root of the schema.
+pub fn document_json_schema(
+    document_type: &DocumentType,
+    registry: &dyn DocumentTypesRegistry,
+) -> JsonValue {
+    let mut definitions = BTreeMap::new();
+    let mut pending = vec![&document_type.id];
+    while let Some(id) = pending.pop() {
+        for relation in registry.relations_of(id) {
+            let target = &relation.target.id;
+            if target != &document_type.id && !definitions.contains_key(target) {
+                definitions.insert(
+                    target.clone(),
+                    object_schema(relation.target, document_type, registry),
+                );
+                pending.push(target);
+            }
+        }
+    }
+
+    let mut schema = object_schema(document_type, document_type, registry);
+    schema.insert("$schema".to_string(), json!(JSON_SCHEMA_DIALECT));
+    if !definitions.is_empty() {
+        let definitions = definitions
+            .into_iter()
+            .map(|(id, definition)| (id.to_string(), JsonValue::Object(definition)))
+            .collect();
+        schema.insert("$defs".to_string(), JsonValue::Object(definitions));
+    }
+    JsonValue::Object(schema)
+}
+
+/// Schema of one document of `document_type`: the properties every document
+/// has, then its fields and relations under their keys in the payloads.
+fn object_schema(
+    document_type: &DocumentType,
+    root: &DocumentType,
+    registry: &dyn DocumentTypesRegistry,
+) -> Map<String, JsonValue> {
+    let mut properties = Map::new();
+    properties.insert("id".to_string(), json!({"type": "integer"}));
+    properties.insert(
+        "documentId".to_string(),
+        json!({"type": "string", "format": "uuid"}),
+    );
+    properties.insert(
+        "status".to_string(),
+        json!({"type": "string", "enum": ["draft", "modified", "published"]}),
+    );
+    if let Some(workflow) = document_type.workflow() {
+        let stages: Vec<_> = workflow
+            .stages
+            .iter()
+            .map(|stage| stage.id.to_string())
+            .collect();
+        properties.insert(
+            "workflowStage".to_string(),
+            json!({"type": "string", "enum": stages}),
+        );
+    }
+    for name in ["createdAt", "updatedAt"] {
+        properties.insert(
+            name.to_string(),
+            json!({"type": "string", "format": "date-time"}),
+        );
+    }
+    for name in ["createdBy", "updatedBy"] {
+        properties.insert(name.to_string(), json!({"type": ["string", "null"]}));
+    }
+    properties.insert("version".to_string(), json!({"type": "integer"}));
+    if document_type.has_draft_and_publish() {
+        properties.insert(
+            "publishedAt".to_string(),
+            json!({"type": "string", "format": "date-time"}),
+        );
+        properties.insert(
+            "publishedBy".to_string(),
+            json!({"type": ["string", "null"]}),
+        );
+        properties.insert("revision".to_string(), json!({"type": "integer"}));
+    }
+
+    let mut required: Vec<String> = [
+        "id",
+        "documentId",
+        "status",
+        "createdAt",
+        "updatedAt",
+        "createdBy",
+        "updatedBy",
+        "version",
+    ]
+    .map(String::from)
+    .to_vec();
+
+    let mut fields: Vec<_> = document_type.fields.iter().collect();
+    fields.sort_by_key(|field| &field.id);
+    for field in fields {
+        let key = attribute_key(document_type, &field.id);
+        if field.required {
+            required.push(key.clone());
+        }
+        properties.insert(key, field_schema(field, document_type));
+    }
+
+    let relations = registry.relations_of(&document_type.id);
+    if !relations.is_empty() {
+        let mut counts = Map::new();
+        for relation in relations {
+            let key = attribute_key(document_type, &relation.relation.id);
+            let target = if relation.target.id == root.id {
+                "#".to_string()
+            } else {
+                format!("#/$defs/{}", relation.target.id)
+            };
+            // populated on request, answered as a list of related documents
+            // whatever the cardinality of the relation
+            properties.insert(
+                key.clone(),
+                json!({"type": "array", "items": {"$ref": target}}),
+            );
+            counts.insert(key, json!({"type": "integer"}));
+        }
+        properties.insert(
+            "relationCounts".to_string(),
+            json!({"type": "object", "properties": counts, "additionalProperties": false}),
+        );
+    }
+
+    let mut schema = Map::new();
+    schema.insert(
+        "title".to_string(),
+        json!(document_type.info.title.to_string()),
+    );
+    if let Some(description) = &document_type.info.description {
+        schema.insert("description".to_string(), json!(description));
+    }
+    schema.insert("type".to_string(), json!("object"));
+    schema.insert("properties".to_string(), JsonValue::Object(properties));
+    schema.insert("required".to_string(), json!(required));
+    schema
+}
+
+/// Schema of the value of `field`, `null` included unless it's required.
+fn field_schema(field: &DocumentField, document_type: &DocumentType) -> JsonValue {
+    let mut schema = match field.field_type {
+        FieldType::Uid | FieldType::Text => json!({"type": "string"}),
+        FieldType::Uuid => json!({"type": "string", "format": "uuid"}),
+        FieldType::LocalizedText => {
+            let locales: Vec<_> = document_type
+                .options
+                .iter()
+                .flat_map(|options| &options.localizations)
+                .map(|locale| locale.to_string())
+                .collect();
+            json!({
+                "type": "object",
+                "propertyNames": {"enum": locales},
+                "additionalProperties": {"type": "string"},
+            })
+        }
+        FieldType::Integer(size) => {
+            let (minimum, maximum): (i64, i64) = match size {
+                IntegerSize::Int16 => (i16::MIN.into(), i16::MAX.into()),
+                IntegerSize::Int32 => (i32::MIN.into(), i32::MAX.into()),
+                IntegerSize::Int64 => (i64::MIN, i64::MAX),
+            };
+            json!({"type": "integer", "minimum": minimum, "maximum": maximum})
+        }
+        // a number when an f64 holds it exactly, a string otherwise
+        FieldType::Decimal { .. } => json!({"type": ["number", "string"]}),
+        FieldType::Date => json!({"type": "string", "format": "date"}),
+        FieldType::DateTime => json!({"type": "string", "format": "date-time"}),
+        FieldType::LocalDateTime => json!({
+            "type": "string",
+            "pattern": r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(\.\d+)?$",
+        }),
+        FieldType::Time => json!({
+            "type": "string",
+            "pattern": r"^\d{2}:\d{2}:\d{2}(\.\d+)?$",
+        }),
+        FieldType::Boolean => json!({"type": "boolean"}),
+        FieldType::Json => json!({"type": "object"}),
+    };
+
+    let mut constraints: Vec<_> = field.constraints.iter().collect();
+    constraints.sort_by_key(|constraint| format!("{constraint:?}"));
+    for constraint in constraints {
+        let (keyword, value) = match constraint {
+            FieldConstraint::Pattern(pattern) => ("pattern", json!(pattern)),
+            FieldConstraint::MinimalLength(length) => ("minLength", json!(length)),
+            FieldConstraint::MaximalLength(length) => ("maxLength", json!(length)),
+            FieldConstraint::MinimalIntegerValue(value) => ("minimum", json!(value)),
+            FieldConstraint::MaximalIntegerValue(value) => ("maximum", json!(value)),
+        };
+        schema[keyword] = value;
+    }
+
+    if let Some(description) = &field.description {
+        schema["description"] = json!(description);
+    }
+    if field.required {
+        return schema;
+    }
+    let nullable = match schema["type"].take() {
+        JsonValue::Array(mut types) => {
+            types.push(json!("null"));
+            JsonValue::Array(types)
+        }
+        single => json!([single, "null"]),
+    };
+    schema["type"] = nullable;
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use luminair_common::entities::{DocumentRelation, OnDeleteAction, RelationType};
+    use luminair_common::{AttributeId, DocumentTypeId, InMemoryDocumentTypesRegistry};
+    use std::collections::HashSet;
+
+    fn field(id: &str, field_type: FieldType, required: bool) -> DocumentField {
+        DocumentField {
+            id: AttributeId::try_new(id).unwrap(),
+            field_type,
+            constraints: HashSet::new(),
+            required,
+            unique: false,
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        }
+    }
+
+    #[test]
+    fn test_json_schema_describes_fields_and_related_types() {
+        let mut brand = DocumentType::new_bare_collection("brand", "brand", "brands").unwrap();
+        let mut name = field("name", FieldType::Text, true);
+        name.constraints.insert(FieldConstraint::MaximalLength(64));
+        brand.fields.insert(name);
+        brand
+            .fields
+            .insert(field("founded_on", FieldType::Date, false));
+        brand.relations.insert(DocumentRelation {
+            id: AttributeId::try_new("partners").unwrap(),
+            relation_type: RelationType::HasMany,
+            target: DocumentTypeId::try_new("partner").unwrap(),
+            on_delete: OnDeleteAction::default(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+        });
+        let mut partner =
+            DocumentType::new_bare_collection("partner", "partner", "partners").unwrap();
+        partner.relations.insert(DocumentRelation {
+            id: AttributeId::try_new("brand").unwrap(),
+            relation_type: RelationType::BelongsToOne,
+            target: DocumentTypeId::try_new("brand").unwrap(),
+            on_delete: OnDeleteAction::default(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+        });
+        let registry = InMemoryDocumentTypesRegistry::from_vec(vec![brand, partner]);
+        let brand = registry
+            .get(&DocumentTypeId::try_new("brand").unwrap())
+            .unwrap();
+
+        let schema = document_json_schema(brand, &registry);
+
+        assert_eq!(schema["$schema"], JSON_SCHEMA_DIALECT);
+        assert_eq!(
+            schema["properties"]["name"],
+            json!({"type": "string", "maxLength": 64})
+        );
+        assert_eq!(
+            schema["properties"]["foundedOn"],
+            json!({"type": ["string", "null"], "format": "date"})
+        );
+        assert!(
+            schema["required"]
+                .as_array()
+                .unwrap()
+                .contains(&json!("name"))
+        );
+        assert!(
+            !schema["required"]
+                .as_array()
+                .unwrap()
+                .contains(&json!("foundedOn"))
+        );
+        assert_eq!(
+            schema["properties"]["partners"]["items"],
+            json!({"$ref": "#/$defs/partner"})
+        );
+        assert_eq!(
+            schema["$defs"]["partner"]["properties"]["brand"]["items"],
+            json!({"$ref": "#"})
+        );
+    }
+}
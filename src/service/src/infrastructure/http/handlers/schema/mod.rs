@@ -3,11 +3,13 @@ use crate::infrastructure::http::api::{ApiError, ApiSuccess};
 use crate::infrastructure::http::handlers::schema::dto::{
     DetailedDocumentResponse, DocumentResponse,
 };
+use crate::infrastructure::http::handlers::schema::json_schema::document_json_schema;
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use luminair_common::DocumentTypeId;
 
 mod dto;
+mod json_schema;
 
 pub async fn documents_metadata<S: AppState>(
     State(state): State<S>,
@@ -38,3 +40,22 @@ pub async fn one_document_metadata<S: AppState>(
 
     Ok(ApiSuccess::new(StatusCode::OK, result))
 }
+
+/// JSON Schema of the documents of one type, to generate client models from.
+pub async fn one_document_json_schema<S: AppState>(
+    Path(id): Path<String>,
+    State(state): State<S>,
+) -> Result<ApiSuccess<serde_json::Value>, ApiError> {
+    let document_type_id = DocumentTypeId::try_new(&id)
+        .map_err(|err| ApiError::UnprocessableEntity(err.to_string()))?;
+
+    let registry = state.document_types();
+    let result = registry
+        .get(&document_type_id)
+        .map(|document_type| document_json_schema(document_type, registry))
+        .ok_or_else(|| {
+            ApiError::NotFound(format!("Document type metadata for ID '{}' not found", id))
+        })?;
+
+    Ok(ApiSuccess::new(StatusCode::OK, result))
+}
@@ -0,0 +1,343 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::Router;
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use chrono::{DateTime, Utc};
+use luminair_common::DocumentTypesRegistry;
+use luminair_common::database::{Database, StatementKind};
+use serde::{Deserialize, Serialize};
+use sqlx::AssertSqlSafe;
+
+use crate::application::AppState;
+use crate::infrastructure::http::actor::parse_actor;
+use crate::infrastructure::http::api::ApiError;
+use crate::infrastructure::http::handlers::health_check;
+use crate::infrastructure::persistence::builders::outbox::count_pending_events;
+
+/// Longest a component is waited for before it is reported down.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Settings of the detail of the health check
+#[derive(Debug, Clone, Deserialize)]
+pub struct HealthSettings {
+    /// answer `/health?detail=true` with the status of the components
+    #[serde(default)]
+    pub detail: bool,
+    /// roles admitted to the detail, one of which users need
+    #[serde(default = "default_roles")]
+    pub roles: Vec<String>,
+}
+
+impl Default for HealthSettings {
+    fn default() -> Self {
+        Self {
+            detail: false,
+            roles: default_roles(),
+        }
+    }
+}
+
+fn default_roles() -> Vec<String> {
+    vec!["admin".to_string()]
+}
+
+/// Status of a component, and of the service as the worst of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Up,
+    /// working, but needing attention
+    Degraded,
+    Down,
+}
+
+/// Status of the components of the service, answered to
+/// `/health?detail=true`.
+///
+/// The detail is for identified users with one of the roles of the settings,
+/// as the admin API; `/health` without it stays an anonymous `200` for load
+/// balancers. The detail is answered with `503` when a component is down.
+#[derive(Clone)]
+pub struct HealthCheck {
+    documents: &'static dyn DocumentTypesRegistry,
+    database: &'static Database,
+    roles: Arc<[String]>,
+    schema_version: Arc<str>,
+    schema_loaded_at: DateTime<Utc>,
+}
+
+/// The detail of the health check.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthDetail {
+    pub status: HealthStatus,
+    pub database: DatabaseHealth,
+    pub migrations: MigrationsHealth,
+    pub schema: SchemaHealth,
+    pub outbox: OutboxHealth,
+}
+
+/// The database answering a trivial query.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseHealth {
+    pub status: HealthStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Migration steps pending to bring the database conform the schema, over
+/// all tenants; pending steps degrade the service.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationsHealth {
+    pub status: HealthStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The schema loaded at startup.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaHealth {
+    pub status: HealthStatus,
+    /// checksum of the tables the schema needs, the same on all replicas
+    /// loading the same schema
+    pub version: String,
+    pub loaded_at: DateTime<Utc>,
+    pub document_types: usize,
+}
+
+/// Content change events waiting in the outbox to be published.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxHealth {
+    pub status: HealthStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HealthQuery {
+    #[serde(default)]
+    detail: bool,
+}
+
+impl HealthCheck {
+    /// Returns the health check of the schema of `documents`, loaded at
+    /// `schema_loaded_at`, stored in `database`.
+    pub fn new(
+        settings: &HealthSettings,
+        documents: &'static dyn DocumentTypesRegistry,
+        database: &'static Database,
+        schema_loaded_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            documents,
+            database,
+            roles: settings.roles.clone().into(),
+            schema_version: migration::schema_version(documents).into(),
+            schema_loaded_at,
+        }
+    }
+
+    /// Check the components, at the same time.
+    pub async fn detail(&self) -> HealthDetail {
+        let (database, migrations, outbox) =
+            tokio::join!(self.database(), self.migrations(), self.outbox());
+        let schema = SchemaHealth {
+            status: HealthStatus::Up,
+            version: self.schema_version.to_string(),
+            loaded_at: self.schema_loaded_at,
+            document_types: self.documents.iterate().count(),
+        };
+        let status = [
+            database.status,
+            migrations.status,
+            schema.status,
+            outbox.status,
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or(HealthStatus::Up);
+        HealthDetail {
+            status,
+            database,
+            migrations,
+            schema,
+            outbox,
+        }
+    }
+
+    async fn database(&self) -> DatabaseHealth {
+        let started = Instant::now();
+        let result = checked(async {
+            sqlx::query("SELECT 1")
+                .execute(self.database.database_pool())
+                .await?;
+            Ok(())
+        })
+        .await;
+        match result {
+            Ok(()) => DatabaseHealth {
+                status: HealthStatus::Up,
+                latency_ms: Some(started.elapsed().as_millis()),
+                error: None,
+            },
+            Err(e) => DatabaseHealth {
+                status: HealthStatus::Down,
+                latency_ms: None,
+                error: Some(e),
+            },
+        }
+    }
+
+    async fn migrations(&self) -> MigrationsHealth {
+        let result = checked(migration::pending_migration_steps(
+            self.documents,
+            self.database,
+        ))
+        .await;
+        match result {
+            Ok(pending) => MigrationsHealth {
+                status: if pending == 0 {
+                    HealthStatus::Up
+                } else {
+                    HealthStatus::Degraded
+                },
+                pending: Some(pending),
+                error: None,
+            },
+            Err(e) => MigrationsHealth {
+                status: HealthStatus::Down,
+                pending: None,
+                error: Some(e),
+            },
+        }
+    }
+
+    async fn outbox(&self) -> OutboxHealth {
+        let result = checked(async {
+            let (sql, values) = count_pending_events();
+            let mut transaction = self.database.begin(StatementKind::Read).await?;
+            let pending: i64 = sqlx::query_scalar_with(AssertSqlSafe(sql), values)
+                .fetch_one(&mut *transaction)
+                .await?;
+            transaction.commit().await?;
+            Ok(pending)
+        })
+        .await;
+        match result {
+            Ok(pending) => OutboxHealth {
+                status: HealthStatus::Up,
+                pending: Some(pending),
+                error: None,
+            },
+            Err(e) => OutboxHealth {
+                status: HealthStatus::Down,
+                pending: None,
+                error: Some(e),
+            },
+        }
+    }
+}
+
+impl std::fmt::Debug for HealthCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HealthCheck")
+            .field("roles", &self.roles)
+            .field("schema_version", &self.schema_version)
+            .field("schema_loaded_at", &self.schema_loaded_at)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Route of `/health`, answering the detail of `health` when there is one.
+pub fn health_routes<S: AppState>(health: Option<HealthCheck>) -> Router<S> {
+    match health {
+        Some(health) => Router::new()
+            .route("/health", get(health_detail))
+            .with_state(health),
+        None => Router::new().route("/health", get(health_check)),
+    }
+}
+
+async fn health_detail(
+    State(health): State<HealthCheck>,
+    Query(query): Query<HealthQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    if !query.detail {
+        return Ok(health_check().await.into_response());
+    }
+    admit(&health.roles, &headers)?;
+    let detail = health.detail().await;
+    let status = if detail.status == HealthStatus::Down {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    Ok((status, Json(detail)).into_response())
+}
+
+/// Admit identified users with one of `roles`.
+fn admit(roles: &[String], headers: &HeaderMap) -> Result<(), ApiError> {
+    let actor = parse_actor(headers, None)?;
+    if actor.user_id.is_none() || !actor.has_any_role(roles) {
+        return Err(ApiError::Forbidden(format!(
+            "The health detail requires an identified user with one of the roles: {}",
+            roles.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Run the check of a component, failing when it takes longer than
+/// [`CHECK_TIMEOUT`].
+async fn checked<T>(check: impl Future<Output = anyhow::Result<T>>) -> Result<T, String> {
+    match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(format!("{e:#}")),
+        Err(_) => Err(format!(
+            "no answer within {} seconds",
+            CHECK_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_detail_admits_identified_users_with_a_role() {
+        let roles = vec!["admin".to_string(), "ops".to_string()];
+        let mut headers = HeaderMap::new();
+        headers.insert("x-user-roles", HeaderValue::from_static("ops"));
+        assert!(matches!(
+            admit(&roles, &headers),
+            Err(ApiError::Forbidden(_))
+        ));
+
+        headers.insert("x-user-id", HeaderValue::from_static("42"));
+        assert!(admit(&roles, &headers).is_ok());
+
+        headers.insert("x-user-roles", HeaderValue::from_static("editor"));
+        assert!(matches!(
+            admit(&roles, &headers),
+            Err(ApiError::Forbidden(_))
+        ));
+    }
+}
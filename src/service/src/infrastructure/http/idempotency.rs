@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+
+use axum::Router;
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::{HeaderName, HeaderValue, Method, StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use luminair_common::database::{Database, StatementKind};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::types::Json;
+use sqlx::{AssertSqlSafe, Row};
+
+use crate::infrastructure::http::actor::{TOKEN_ID_HEADER, USER_ID_HEADER};
+use crate::infrastructure::http::api::ApiError;
+use crate::infrastructure::persistence::builders::idempotency::{
+    complete_idempotency_key, delete_expired_idempotency_keys, insert_idempotency_key,
+    query_idempotency_key, release_idempotency_key,
+};
+
+/// Header of a POST request carrying the key its retries are sent with.
+pub const IDEMPOTENCY_KEY_HEADER: HeaderName = HeaderName::from_static("idempotency-key");
+
+/// Header of a response replayed for a retried request: `true`.
+pub const IDEMPOTENT_REPLAYED_HEADER: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// Longest idempotency key accepted.
+const MAX_KEY_LENGTH: usize = 255;
+
+/// Keys of requests which were cut off before their response was stored are
+/// released after this long.
+const ABANDONED_AFTER_SECONDS: u64 = 300;
+
+/// Largest request body fingerprinted, the default body limit of the routes.
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Headers of a response which are stored with it.
+const STORED_HEADERS: [HeaderName; 3] = [header::CONTENT_TYPE, header::LOCATION, header::ETAG];
+
+/// Settings of the idempotency keys of the REST API writes
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdempotencySettings {
+    /// replay the responses of the POST requests sent with an `Idempotency-Key`
+    #[serde(default)]
+    pub enabled: bool,
+    /// how long a key and its response are kept
+    #[serde(default = "default_ttl_seconds")]
+    pub ttl_seconds: u64,
+}
+
+impl Default for IdempotencySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_seconds: default_ttl_seconds(),
+        }
+    }
+}
+
+fn default_ttl_seconds() -> u64 {
+    24 * 60 * 60
+}
+
+/// Replays the responses of the POST requests sent with an `Idempotency-Key`
+/// header, so that clients can retry a create without creating twice.
+///
+/// The first request with a key reserves it in the `luminair_idempotency`
+/// table and stores its response there; a retry with the key is answered
+/// with the stored response and an `Idempotent-Replayed: true` header,
+/// without running the request again. A retry while the first request is
+/// still handled is answered with `409`, one with the key of another
+/// request, by method, path and a SHA-256 of the body, with `422`.
+///
+/// Keys are scoped by the `X-User-Id` and `X-Token-Id` of the caller: the
+/// same key sent by other callers is another key.
+///
+/// Server errors are not stored, the key is released for the retry. Keys
+/// expire after the TTL of the settings.
+///
+/// With tenants, every tenant has the keys of its own schema.
+#[derive(Debug, Clone)]
+pub struct IdempotencyKeys {
+    database: &'static Database,
+    ttl_seconds: u64,
+}
+
+/// Outcome of reserving a key for a request.
+enum Reservation {
+    Reserved,
+    /// the key is taken by `request`, whose response is known once the
+    /// request has been handled
+    Taken {
+        request: String,
+        response: Option<Response>,
+    },
+}
+
+impl IdempotencyKeys {
+    /// Keys stored in `database` as configured by `settings`.
+    pub fn new(settings: &IdempotencySettings, database: &'static Database) -> Self {
+        Self {
+            database,
+            ttl_seconds: settings.ttl_seconds,
+        }
+    }
+
+    /// Replay the responses of the POST routes of `router` for their keys.
+    pub fn replay_retries<S: Clone + Send + Sync + 'static>(&self, router: Router<S>) -> Router<S> {
+        let keys = self.clone();
+        router.route_layer(middleware::from_fn(move |request: Request, next: Next| {
+            let keys = keys.clone();
+            async move { keys.serve(request, next).await }
+        }))
+    }
+
+    /// Answer `request` with the response stored for its key, or run it and
+    /// store its response.
+    async fn serve(&self, request: Request, next: Next) -> Result<Response, ApiError> {
+        if request.method() != Method::POST {
+            return Ok(next.run(request).await);
+        }
+        let Some(key) = request.headers().get(&IDEMPOTENCY_KEY_HEADER) else {
+            return Ok(next.run(request).await);
+        };
+        let key = key
+            .to_str()
+            .ok()
+            .filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LENGTH)
+            .ok_or_else(|| {
+                ApiError::UnprocessableEntity(format!(
+                    "The {} header must have 1 to {} visible ASCII characters",
+                    IDEMPOTENCY_KEY_HEADER, MAX_KEY_LENGTH
+                ))
+            })?
+            .to_string();
+        let stored_key = scoped_key(request.headers(), &key);
+        let target = format!(
+            "{} {}",
+            request.method(),
+            request
+                .uri()
+                .path_and_query()
+                .map(|path| path.as_str())
+                .unwrap_or_default()
+        );
+
+        let (parts, body) = request.into_parts();
+        let body = axum::body::to_bytes(body, MAX_BODY_BYTES)
+            .await
+            .map_err(|e| ApiError::UnprocessableEntity(format!("Failed to read the body: {e}")))?;
+        let fingerprint = format!("{}#{}", target, body_hash(&body));
+        let request = Request::from_parts(parts, Body::from(body));
+
+        let reservation = self.reserve(&stored_key, &fingerprint).await.map_err(|e| {
+            ApiError::InternalServerError(format!("Failed to reserve the idempotency key: {e:#}"))
+        })?;
+        if let Reservation::Taken { request, response } = reservation {
+            if request != fingerprint {
+                // keys stored before the bodies were fingerprinted have no hash
+                let other = request.split_once('#').map_or(request.as_str(), |(t, _)| t);
+                if other == target {
+                    return Err(ApiError::UnprocessableEntity(format!(
+                        "The idempotency key '{}' was used for the same request with another body",
+                        key
+                    )));
+                }
+                return Err(ApiError::UnprocessableEntity(format!(
+                    "The idempotency key '{}' was used for another request: {}",
+                    key, other
+                )));
+            }
+            return response.ok_or_else(|| {
+                ApiError::ConflictWithServerState(format!(
+                    "A request with the idempotency key '{}' is still being handled",
+                    key
+                ))
+            });
+        }
+        let key = stored_key;
+
+        let response = next.run(request).await;
+        if response.status().is_server_error() {
+            self.release(&key).await;
+            return Ok(response);
+        }
+
+        let (parts, body) = response.into_parts();
+        let body = match axum::body::to_bytes(body, usize::MAX).await {
+            Ok(body) => body,
+            Err(e) => {
+                self.release(&key).await;
+                return Err(ApiError::InternalServerError(format!(
+                    "Failed to read the response: {e}"
+                )));
+            }
+        };
+        let headers = STORED_HEADERS
+            .iter()
+            .filter_map(|name| {
+                let value = parts.headers.get(name)?.to_str().ok()?;
+                Some((name.to_string(), value.to_string()))
+            })
+            .collect::<HashMap<_, _>>();
+        match std::str::from_utf8(&body) {
+            Ok(text) => {
+                let stored = self
+                    .complete(&key, parts.status, headers, text.to_string())
+                    .await;
+                if let Err(e) = stored {
+                    tracing::warn!(
+                        "Failed to store the response of an idempotency key: {:#}",
+                        e
+                    );
+                    self.release(&key).await;
+                }
+            }
+            // the bodies of the API are JSON, others aren't replayed
+            Err(_) => self.release(&key).await,
+        }
+        Ok(Response::from_parts(parts, Body::from(body)))
+    }
+
+    /// Reserve `key` for `request`, after dropping the expired keys, unless
+    /// it is taken.
+    async fn reserve(&self, key: &str, request: &str) -> anyhow::Result<Reservation> {
+        let mut transaction = self.database.begin(StatementKind::Write).await?;
+
+        let (sql, values) =
+            delete_expired_idempotency_keys(self.ttl_seconds, ABANDONED_AFTER_SECONDS);
+        sqlx::query_with(AssertSqlSafe(sql), values)
+            .execute(&mut *transaction)
+            .await?;
+
+        let (sql, values) = insert_idempotency_key(key, request);
+        let inserted = sqlx::query_with(AssertSqlSafe(sql), values)
+            .execute(&mut *transaction)
+            .await?
+            .rows_affected();
+        if inserted == 1 {
+            transaction.commit().await?;
+            return Ok(Reservation::Reserved);
+        }
+
+        let (sql, values) = query_idempotency_key(key);
+        let row = sqlx::query_with(AssertSqlSafe(sql), values)
+            .fetch_optional(&mut *transaction)
+            .await?;
+        transaction.commit().await?;
+
+        // released by the request holding it in the meantime
+        let Some(row) = row else {
+            return Ok(Reservation::Taken {
+                request: request.to_string(),
+                response: None,
+            });
+        };
+        let status: Option<i32> = row.try_get("status")?;
+        let response = match status {
+            Some(status) => {
+                let Json(headers): Json<HashMap<String, String>> = row.try_get("headers")?;
+                let body: String = row.try_get("body")?;
+                Some(replayed(status, headers, body)?)
+            }
+            None => None,
+        };
+        Ok(Reservation::Taken {
+            request: row.try_get("request")?,
+            response,
+        })
+    }
+
+    async fn complete(
+        &self,
+        key: &str,
+        status: StatusCode,
+        headers: HashMap<String, String>,
+        body: String,
+    ) -> anyhow::Result<()> {
+        let headers = serde_json::to_value(headers)?;
+        let (sql, values) = complete_idempotency_key(key, status.as_u16(), headers, body);
+        let mut transaction = self.database.begin(StatementKind::Write).await?;
+        sqlx::query_with(AssertSqlSafe(sql), values)
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Release `key` for a retry; a key which can't be released is released
+    /// once abandoned.
+    async fn release(&self, key: &str) {
+        let released = async {
+            let (sql, values) = release_idempotency_key(key);
+            let mut transaction = self.database.begin(StatementKind::Write).await?;
+            sqlx::query_with(AssertSqlSafe(sql), values)
+                .execute(&mut *transaction)
+                .await?;
+            transaction.commit().await?;
+            Ok::<(), sqlx::Error>(())
+        };
+        if let Err(e) = released.await {
+            tracing::warn!("Failed to release an idempotency key: {:#}", e);
+        }
+    }
+}
+
+/// `key` as stored for the caller of the request: the key sent by another
+/// user or token is another key. Header values can't hold line breaks, so
+/// the parts can't run into each other.
+fn scoped_key(headers: &axum::http::HeaderMap, key: &str) -> String {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+    };
+    format!(
+        "{}\n{}\n{}",
+        header(USER_ID_HEADER),
+        header(TOKEN_ID_HEADER),
+        key
+    )
+}
+
+/// Hex encoded SHA-256 of a request body.
+fn body_hash(body: &[u8]) -> String {
+    Sha256::digest(body)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The stored response, marked as replayed.
+fn replayed(
+    status: i32,
+    headers: HashMap<String, String>,
+    body: String,
+) -> anyhow::Result<Response> {
+    let mut response = Body::from(body).into_response();
+    *response.status_mut() = StatusCode::from_u16(u16::try_from(status)?)?;
+    for (name, value) in headers {
+        response.headers_mut().insert(
+            HeaderName::from_bytes(name.as_bytes())?,
+            HeaderValue::from_str(&value)?,
+        );
+    }
+    response
+        .headers_mut()
+        .insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    Ok(response)
+}
@@ -1,32 +1,88 @@
+use std::net::SocketAddr;
+
 use anyhow::Context;
 use axum::Router;
 use axum::routing::get;
 use axum_prometheus::PrometheusMetricLayer;
 
 use crate::application::AppState;
-use crate::infrastructure::http::handlers::health_check;
+use crate::infrastructure::error_reporting::report_errors;
+use crate::infrastructure::http::admin::{ADMIN_PATH, admin_routes};
+use crate::infrastructure::http::graphql::graphql_routes;
+use crate::infrastructure::http::health::{HealthCheck, health_routes};
+use crate::infrastructure::http::idempotency::IdempotencyKeys;
 use crate::infrastructure::http::routes::api_routes;
+use crate::infrastructure::http::slow_requests::SlowRequestLog;
+use crate::infrastructure::http::tenancy::TenantResolver;
+use crate::infrastructure::http::versioning::ApiVersioning;
+use crate::infrastructure::jobs::JobQueue;
+use crate::infrastructure::response_cache::ResponseCache;
+use serde::Deserialize;
 use tokio::net;
 
+pub mod actor;
+pub mod admin;
 pub mod api;
+pub mod graphql;
 pub mod handlers;
+pub mod health;
+pub mod idempotency;
+mod path;
 mod querystring;
 pub mod routes;
+pub mod slow_requests;
+pub mod tenancy;
+pub mod versioning;
+
+/// Address of the internal listener of the HTTP server
+#[derive(Debug, Clone, Deserialize)]
+pub struct InternalListenerSettings {
+    /// interface listened on, e.g. that of the private network
+    #[serde(default = "default_host")]
+    pub host: String,
+    pub port: u16,
+}
 
 /// Configuration for the HTTP server.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct HttpServerConfig {
+    /// interface the content API listens on
+    pub host: String,
     pub port: u16,
+    /// listener of `/health`, `/metrics` and the admin API, apart from the
+    /// content API; they are served next to it when unset
+    pub internal_listener: Option<InternalListenerSettings>,
+    /// serve the GraphiQL IDE next to the GraphQL endpoint
+    pub graphiql: bool,
+    /// roles admitted to the admin API when it is served next to the content API
+    pub admin_roles: Option<Vec<String>>,
+    /// cache of the document reads of the REST API
+    pub response_cache: Option<ResponseCache>,
+    /// replay of the POST requests retried with an idempotency key
+    pub idempotency: Option<IdempotencyKeys>,
+    /// queue of the background jobs managed by the admin API
+    pub jobs: Option<JobQueue>,
+    /// resolver of the tenant of the API requests, when there are tenants
+    pub tenants: Option<TenantResolver>,
+    /// detail of the health check, when it is served
+    pub health: Option<HealthCheck>,
+    /// timing of the API requests
+    pub slow_requests: SlowRequestLog,
+    /// negotiation of the version of the content API responses
+    pub versioning: ApiVersioning,
 }
 
 /// The application's HTTP server. The underlying HTTP package is opaque to module consumers.
 pub struct HttpServer {
     router: axum::Router,
     listener: net::TcpListener,
+    /// the internal listener, with its routes
+    internal: Option<(axum::Router, net::TcpListener)>,
 }
 
 impl HttpServer {
-    /// Returns a new HTTP server bound to the port specified in `config`.
+    /// Returns a new HTTP server bound to the host and port specified in
+    /// `config`, and to its internal listener if any.
     pub async fn new<S: AppState>(state: S, config: HttpServerConfig) -> anyhow::Result<Self> {
         let trace_layer = tower_http::trace::TraceLayer::new_for_http().make_span_with(
             |request: &axum::extract::Request<_>| {
@@ -38,27 +94,94 @@ impl HttpServer {
         // see: https://github.com/Ptrskay3/axum-prometheus
         let (prometheus_layer, metric_handle) = PrometheusMetricLayer::pair();
 
-        let router = Router::new()
-            .route("/health", get(health_check))
-            .nest("/api", api_routes())
-            .route("/metrics", get(|| async move { metric_handle.render() }))
-            .layer(trace_layer)
-            .layer(prometheus_layer)
-            .with_state(state);
+        let mut api = api_routes();
+        if let Some(cache) = &config.response_cache {
+            api = cache.cache_responses(api);
+        }
+        if let Some(keys) = &config.idempotency {
+            api = keys.replay_retries(api);
+        }
+        api = config.versioning.negotiate_versions(api);
+        let mut graphql = graphql_routes(&state, config.graphiql)?;
+        let jobs = config.jobs;
+        let mut admin = config
+            .admin_roles
+            .map(|roles| admin_routes(roles, jobs.clone()));
+        // the cache and the idempotency keys run for the tenant too, their
+        // keys are the tenant's
+        if let Some(tenants) = &config.tenants {
+            api = tenants.resolve_tenants(api);
+            graphql = tenants.resolve_tenants(graphql);
+            admin = admin.map(|admin| tenants.resolve_tenants(admin));
+        }
+        let slow_requests = &config.slow_requests;
+        api = slow_requests.observe_requests(api);
+        graphql = slow_requests.observe_requests(graphql);
+        admin = admin.map(|admin| slow_requests.observe_requests(admin));
+        let public = Router::new().nest("/api", api).merge(graphql);
+        let mut internal = Router::new()
+            .merge(health_routes(config.health))
+            .route("/metrics", get(|| async move { metric_handle.render() }));
+        if let Some(admin) = admin {
+            internal = internal.nest(ADMIN_PATH, admin);
+        }
+        let serve = |router: Router<S>| {
+            report_errors(router)
+                .layer(trace_layer.clone())
+                .layer(prometheus_layer.clone())
+                .with_state(state.clone())
+        };
 
-        let listener = net::TcpListener::bind(format!("0.0.0.0:{}", config.port))
-            .await
-            .with_context(|| format!("failed to listen on {}", config.port))?;
+        let listener = bind(&config.host, config.port).await?;
+        let (router, internal) = match config.internal_listener {
+            Some(address) => (
+                serve(public),
+                Some((serve(internal), bind(&address.host, address.port).await?)),
+            ),
+            None => (serve(public.merge(internal)), None),
+        };
 
-        Ok(Self { router, listener })
+        Ok(Self {
+            router,
+            listener,
+            internal,
+        })
     }
 
     /// Runs the HTTP server.
     pub async fn run(self) -> anyhow::Result<()> {
         tracing::debug!("listening on {:?}", self.listener.local_addr());
-        axum::serve(self.listener, self.router)
-            .await
-            .context("received error from running server")?;
+        let public = async {
+            // the address of the client is kept for the actor of its requests
+            let router = self
+                .router
+                .into_make_service_with_connect_info::<SocketAddr>();
+            axum::serve(self.listener, router)
+                .await
+                .context("received error from running server")
+        };
+        let internal = async {
+            let Some((router, listener)) = self.internal else {
+                return Ok(());
+            };
+            tracing::debug!("internal listener on {:?}", listener.local_addr());
+            let router = router.into_make_service_with_connect_info::<SocketAddr>();
+            axum::serve(listener, router)
+                .await
+                .context("received error from running internal listener")
+        };
+        tokio::try_join!(public, internal)?;
         Ok(())
     }
 }
+
+/// Interface every listener listens on unless configured otherwise.
+pub fn default_host() -> String {
+    "0.0.0.0".to_string()
+}
+
+async fn bind(host: &str, port: u16) -> anyhow::Result<net::TcpListener> {
+    net::TcpListener::bind(format!("{host}:{port}"))
+        .await
+        .with_context(|| format!("failed to listen on {host}:{port}"))
+}
@@ -0,0 +1,136 @@
+use axum::extract::path::ErrorKind;
+use axum::extract::rejection::PathRejection;
+use axum::extract::{FromRequestParts, Path, RawPathParams};
+use axum::http::request::Parts;
+use serde::de::DeserializeOwned;
+
+use crate::infrastructure::http::api::ApiError;
+
+/// Typed path parameters, like [`Path`], whose malformed values are answered
+/// as an unprocessable entity problem instead of a plain text 400.
+///
+/// Tuples take the parameters in the order of the route, e.g.
+/// `ApiPath<(String, DocumentInstanceId)>` for `/documents/{api_type}/{id}`.
+#[derive(Debug, Clone)]
+pub struct ApiPath<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for ApiPath<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Path::<T>::from_request_parts(parts, state).await {
+            Ok(Path(params)) => Ok(ApiPath(params)),
+            Err(PathRejection::FailedToDeserializePathParams(rejection)) => {
+                match rejection.kind() {
+                    // the errors of custom types like `DocumentInstanceId`
+                    // don't name the parameter, the raw parameters are given
+                    // along with them
+                    ErrorKind::Message(message) => {
+                        let params = RawPathParams::from_request_parts(parts, state)
+                            .await
+                            .map(|params| {
+                                params
+                                    .iter()
+                                    .map(|(key, value)| format!("`{key}` = `{value}`"))
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            })
+                            .unwrap_or_default();
+                        Err(ApiError::UnprocessableEntity(format!(
+                            "Invalid path parameters {params}: {message}"
+                        )))
+                    }
+                    _ => Err(ApiError::UnprocessableEntity(rejection.body_text())),
+                }
+            }
+            // the route doesn't match the extracted parameters
+            Err(rejection) => Err(ApiError::InternalServerError(rejection.body_text())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::Router;
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Method, Request, StatusCode};
+    use luminair_common::InMemoryDocumentTypesRegistry;
+    use luminair_common::entities::DocumentType;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use crate::infrastructure::AppStateImpl;
+    use crate::infrastructure::http::routes::api_routes;
+    use crate::infrastructure::persistence::in_memory::InMemoryDocumentsRepository;
+
+    fn router() -> Router {
+        let registry = Box::leak(Box::new(InMemoryDocumentTypesRegistry::from_vec(vec![
+            DocumentType::new_bare_collection("brand", "brand", "brands").unwrap(),
+        ])));
+        api_routes::<AppStateImpl<InMemoryDocumentsRepository>>()
+            .with_state(AppStateImpl::in_memory(registry))
+    }
+
+    async fn send(method: Method, uri: &str) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        let response = router().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_malformed_document_id_is_unprocessable() {
+        for (method, uri) in [
+            (Method::GET, "/documents/brands/not-a-uuid"),
+            (Method::DELETE, "/documents/brands/not-a-uuid"),
+            (Method::GET, "/documents/brands/not-a-uuid/revisions"),
+            (
+                Method::POST,
+                "/documents/brands/not-a-uuid/revisions/1/restore",
+            ),
+            (
+                Method::POST,
+                "/documents/brands/not-a-uuid/localizations/de",
+            ),
+        ] {
+            let (status, problem) = send(method, uri).await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{uri}");
+            assert_eq!(problem["code"], "UNPROCESSABLE_ENTITY", "{uri}");
+            let detail = problem["detail"].as_str().unwrap();
+            assert!(detail.contains("`id`"), "{uri}: {detail}");
+            assert!(detail.contains("`not-a-uuid`"), "{uri}: {detail}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_document_path_reaches_the_handler() {
+        let id = "0192d3a4-5b6c-7d8e-9f00-112233445566";
+
+        let (status, problem) = send(Method::GET, &format!("/documents/brands/{id}")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(
+            problem["detail"],
+            format!("Document instance with ID '{id}' not found")
+        );
+
+        let (status, _) = send(Method::GET, &format!("/documents/cars/{id}")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, problem) = send(
+            Method::POST,
+            &format!("/documents/brands/{id}/revisions/latest/restore"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(problem["detail"], "Invalid revision: latest");
+    }
+}
@@ -1,17 +1,38 @@
 use crate::application::AppState;
 use crate::infrastructure::http::handlers::content::{
-    create_new_document, delete_existing_document, find_all_documents, find_document_by_id,
-    publish_document, update_document_handler,
+    aggregate_documents, clone_document_locale, create_new_document, delete_existing_document,
+    distinct_field_values, document_collection_options, document_exists, document_feed,
+    document_options, find_all_documents, find_document_by_id, find_document_references,
+    find_document_revisions, move_document_to_stage, publish_document, restore_document_revision,
+    search_documents, update_document_handler,
+};
+use crate::infrastructure::http::handlers::schema::{
+    documents_metadata, one_document_json_schema, one_document_metadata,
 };
-use crate::infrastructure::http::handlers::schema::{documents_metadata, one_document_metadata};
 use axum::Router;
-use axum::routing::{delete, get, post, put};
+use axum::routing::{delete, get, options, post, put};
 
+/// Routes of the content API; the `GET` routes answer `HEAD` too, with the
+/// headers of the `GET` response and no body.
 pub fn api_routes<S: AppState>() -> Router<S> {
     Router::new()
         .route("/meta/documents", get(documents_metadata::<S>))
         .route("/meta/documents/{id}", get(one_document_metadata::<S>))
+        .route(
+            "/meta/documents/{id}/json-schema",
+            get(one_document_json_schema::<S>),
+        )
+        .route("/search", get(search_documents::<S>))
         .route("/documents/{api_type}", get(find_all_documents::<S>))
+        .route(
+            "/documents/{api_type}/aggregate",
+            get(aggregate_documents::<S>),
+        )
+        .route(
+            "/documents/{api_type}/distinct/{attribute}",
+            get(distinct_field_values::<S>),
+        )
+        .route("/documents/{api_type}/feed.rss", get(document_feed::<S>))
         .route("/documents/{api_type}/{id}", get(find_document_by_id::<S>))
         .route("/documents/{api_type}", post(create_new_document::<S>))
         .route(
@@ -22,8 +43,37 @@ pub fn api_routes<S: AppState>() -> Router<S> {
             "/documents/{api_type}/{id}",
             put(update_document_handler::<S>),
         )
+        .route(
+            "/documents/{api_type}",
+            options(document_collection_options::<S>),
+        )
+        .route("/documents/{api_type}/{id}", options(document_options::<S>))
         .route(
             "/documents/{api_type}/{id}/publish",
             post(publish_document::<S>),
         )
+        .route(
+            "/documents/{api_type}/{id}/workflow/{stage}",
+            post(move_document_to_stage::<S>),
+        )
+        .route(
+            "/documents/{api_type}/{id}/exists",
+            get(document_exists::<S>),
+        )
+        .route(
+            "/documents/{api_type}/{id}/references",
+            get(find_document_references::<S>),
+        )
+        .route(
+            "/documents/{api_type}/{id}/revisions",
+            get(find_document_revisions::<S>),
+        )
+        .route(
+            "/documents/{api_type}/{id}/revisions/{version}/restore",
+            post(restore_document_revision::<S>),
+        )
+        .route(
+            "/documents/{api_type}/{id}/localizations/{locale}",
+            post(clone_document_locale::<S>),
+        )
 }
@@ -0,0 +1,160 @@
+use std::time::{Duration, Instant};
+
+use axum::Router;
+use axum::extract::rejection::RawPathParamsRejection;
+use axum::extract::{MatchedPath, RawPathParams, Request, State};
+use axum::http::Method;
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use axum_prometheus::metrics::histogram;
+use serde::Deserialize;
+
+/// Path parameter of the routes naming the document type.
+const DOCUMENT_TYPE_PARAM: &str = "api_type";
+
+/// Settings of the slow request log
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SlowRequestSettings {
+    /// reads, `GET` and `HEAD` requests, taking longer are logged as slow;
+    /// none is logged when unset
+    #[serde(default)]
+    pub read_threshold_ms: Option<u64>,
+    /// writes, the requests of the other methods, taking longer are logged
+    /// as slow; none is logged when unset
+    #[serde(default)]
+    pub write_threshold_ms: Option<u64>,
+}
+
+/// Group of routes sharing a threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteGroup {
+    Read,
+    Write,
+}
+
+/// Times the requests, recording their duration in the
+/// `http_request_group_duration_seconds` histogram, by route group and
+/// document type, and logging those exceeding the threshold of their group
+/// with their route, document type and query size.
+#[derive(Debug, Clone, Default)]
+pub struct SlowRequestLog {
+    read_threshold: Option<Duration>,
+    write_threshold: Option<Duration>,
+}
+
+impl RouteGroup {
+    /// Group of the requests of `method`.
+    pub fn of(method: &Method) -> Self {
+        if method == Method::GET || method == Method::HEAD {
+            Self::Read
+        } else {
+            Self::Write
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+        }
+    }
+}
+
+impl SlowRequestLog {
+    pub fn new(settings: &SlowRequestSettings) -> Self {
+        Self {
+            read_threshold: settings.read_threshold_ms.map(Duration::from_millis),
+            write_threshold: settings.write_threshold_ms.map(Duration::from_millis),
+        }
+    }
+
+    /// Time the requests of the routes of `router`.
+    pub fn observe_requests<S: Clone + Send + Sync + 'static>(
+        &self,
+        router: Router<S>,
+    ) -> Router<S> {
+        router.layer(middleware::from_fn_with_state(
+            self.clone(),
+            observe_request,
+        ))
+    }
+
+    /// Threshold of the requests of `group`, if they are logged.
+    fn threshold(&self, group: RouteGroup) -> Option<Duration> {
+        match group {
+            RouteGroup::Read => self.read_threshold,
+            RouteGroup::Write => self.write_threshold,
+        }
+    }
+}
+
+async fn observe_request(
+    State(log): State<SlowRequestLog>,
+    params: Result<RawPathParams, RawPathParamsRejection>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let group = RouteGroup::of(request.method());
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let document_type = params
+        .ok()
+        .and_then(|params| {
+            params
+                .iter()
+                .find(|(name, _)| *name == DOCUMENT_TYPE_PARAM)
+                .map(|(_, value)| value.to_string())
+        })
+        .unwrap_or_default();
+    let query_bytes = request.uri().query().map_or(0, str::len);
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    let elapsed = started.elapsed();
+
+    histogram!(
+        "http_request_group_duration_seconds",
+        "group" => group.as_str(),
+        "document_type" => document_type.clone(),
+    )
+    .record(elapsed.as_secs_f64());
+    if log
+        .threshold(group)
+        .is_some_and(|threshold| elapsed >= threshold)
+    {
+        tracing::warn!(
+            route = %route,
+            group = group.as_str(),
+            document_type = %document_type,
+            query_bytes,
+            status = response.status().as_u16(),
+            elapsed_ms = elapsed.as_millis() as u64,
+            "Slow request"
+        );
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_and_writes_have_their_own_threshold() {
+        let log = SlowRequestLog::new(&SlowRequestSettings {
+            read_threshold_ms: Some(200),
+            write_threshold_ms: None,
+        });
+
+        assert_eq!(RouteGroup::of(&Method::HEAD), RouteGroup::Read);
+        assert_eq!(RouteGroup::of(&Method::PATCH), RouteGroup::Write);
+        assert_eq!(
+            log.threshold(RouteGroup::of(&Method::GET)),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(log.threshold(RouteGroup::of(&Method::POST)), None);
+    }
+}
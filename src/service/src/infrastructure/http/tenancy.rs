@@ -0,0 +1,130 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use axum::Router;
+use axum::extract::Request;
+use axum::http::{HeaderName, header};
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use luminair_common::database::{self, Database};
+use serde::Deserialize;
+
+use crate::infrastructure::http::api::ApiError;
+
+/// Settings of the resolution of the tenant of a request
+#[derive(Debug, Clone, Deserialize)]
+pub struct TenancySettings {
+    /// header naming the tenant of a request
+    #[serde(default = "default_header")]
+    pub header: String,
+    /// domain whose subdomains name the tenant of a request without the
+    /// header, e.g. `cms.example.com` for `acme.cms.example.com`
+    #[serde(default)]
+    pub subdomain_of: Option<String>,
+}
+
+impl Default for TenancySettings {
+    fn default() -> Self {
+        Self {
+            header: default_header(),
+            subdomain_of: None,
+        }
+    }
+}
+
+fn default_header() -> String {
+    "x-tenant-id".to_string()
+}
+
+/// Resolves the tenant of every request, by its header or the subdomain of
+/// its `Host`, and runs the request with the pools of the schema of the
+/// tenant, see [`database::with_tenant`].
+///
+/// Requests of no tenant or of an unknown one are answered with `404`.
+#[derive(Debug, Clone)]
+pub struct TenantResolver {
+    header: HeaderName,
+    subdomain_of: Option<Arc<str>>,
+    tenants: Arc<BTreeSet<String>>,
+}
+
+impl TenantResolver {
+    /// Resolver of the tenants of `database` as configured by `settings`.
+    pub fn new(settings: &TenancySettings, database: &Database) -> anyhow::Result<Self> {
+        let header = HeaderName::try_from(settings.header.as_str())
+            .map_err(|_| anyhow::anyhow!("invalid tenant header '{}'", settings.header))?;
+        Ok(Self {
+            header,
+            subdomain_of: settings.subdomain_of.as_deref().map(Arc::from),
+            tenants: Arc::new(database.tenants().map(String::from).collect()),
+        })
+    }
+
+    /// Run the routes of `router` for the tenant of their requests.
+    pub fn resolve_tenants<S: Clone + Send + Sync + 'static>(
+        &self,
+        router: Router<S>,
+    ) -> Router<S> {
+        let resolver = self.clone();
+        router.layer(middleware::from_fn(move |request: Request, next: Next| {
+            let resolver = resolver.clone();
+            async move {
+                let tenant = resolver.tenant(&request)?;
+                Ok::<Response, ApiError>(
+                    database::with_tenant(Some(tenant), next.run(request)).await,
+                )
+            }
+        }))
+    }
+
+    /// The tenant of `request`, by its header, else by its subdomain.
+    fn tenant(&self, request: &Request) -> Result<String, ApiError> {
+        let from_header = request
+            .headers()
+            .get(&self.header)
+            .and_then(|value| value.to_str().ok());
+        let from_host = || {
+            let host = request.headers().get(header::HOST)?.to_str().ok()?;
+            subdomain(host, self.subdomain_of.as_deref()?)
+        };
+        let tenant = from_header.or_else(from_host).ok_or_else(|| {
+            ApiError::NotFound(format!(
+                "The request names no tenant, by the {} header or a subdomain",
+                self.header
+            ))
+        })?;
+
+        if !self.tenants.contains(tenant) {
+            return Err(ApiError::NotFound(format!("Tenant '{}' not found", tenant)));
+        }
+        Ok(tenant.to_string())
+    }
+}
+
+/// The subdomain of `domain` that `host` is: "acme.cms.example.com:8080" → "acme"
+fn subdomain<'a>(host: &'a str, domain: &str) -> Option<&'a str> {
+    let host = host.split(':').next()?;
+    let subdomain = host.strip_suffix(domain)?.strip_suffix('.')?;
+    (!subdomain.is_empty() && !subdomain.contains('.')).then_some(subdomain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subdomain() {
+        assert_eq!(
+            subdomain("acme.cms.example.com", "cms.example.com"),
+            Some("acme")
+        );
+        assert_eq!(
+            subdomain("acme.cms.example.com:8080", "cms.example.com"),
+            Some("acme")
+        );
+        assert_eq!(subdomain("cms.example.com", "cms.example.com"), None);
+        assert_eq!(subdomain("a.b.cms.example.com", "cms.example.com"), None);
+        assert_eq!(subdomain("acmecms.example.com", "cms.example.com"), None);
+        assert_eq!(subdomain("acme.example.org", "cms.example.com"), None);
+    }
+}
@@ -0,0 +1,149 @@
+use axum::Router;
+use axum::extract::{Request, State};
+use axum::http::header::{ACCEPT, CONTENT_TYPE, VARY};
+use axum::http::{HeaderMap, HeaderValue};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+
+use crate::infrastructure::http::api::ApiError;
+
+/// Prefix of the vendor media types of the versions of the content API,
+/// e.g. `application/vnd.luminair.v1+json`.
+const MEDIA_TYPE_PREFIX: &str = "application/vnd.luminair.";
+
+/// Version of the response envelope and field naming of the content API,
+/// negotiated per request by its `Accept` header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiVersion {
+    /// the envelope of the configured response format, with the attributes
+    /// by their API names
+    #[default]
+    V1,
+}
+
+/// Negotiates the [`ApiVersion`] of the requests, answering the ones asking
+/// for an unknown version with `406 Not Acceptable`.
+///
+/// The version is put in the extensions of the request, for the handlers to
+/// answer in; a response in a version asked for by its media type has that
+/// media type as its `Content-Type`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApiVersioning {
+    /// version of the requests asking for none
+    default_version: ApiVersion,
+}
+
+impl ApiVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::V1 => "v1",
+        }
+    }
+
+    /// Vendor media type of the responses in this version.
+    pub fn media_type(&self) -> String {
+        format!("{MEDIA_TYPE_PREFIX}{}+json", self.as_str())
+    }
+
+    fn parse(version: &str) -> Option<Self> {
+        match version {
+            "v1" => Some(Self::V1),
+            _ => None,
+        }
+    }
+}
+
+impl ApiVersioning {
+    pub fn new(default_version: ApiVersion) -> Self {
+        Self { default_version }
+    }
+
+    /// Negotiate the version of the requests of the routes of `router`.
+    pub fn negotiate_versions<S: Clone + Send + Sync + 'static>(
+        &self,
+        router: Router<S>,
+    ) -> Router<S> {
+        router.layer(middleware::from_fn_with_state(*self, negotiate_version))
+    }
+}
+
+/// The version the vendor media type of `headers`' `Accept` asks for, if
+/// any; the first vendor media type counts.
+pub fn requested_version(headers: &HeaderMap) -> Result<Option<ApiVersion>, ApiError> {
+    let media_type = headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|range| range.split(';').next().unwrap_or_default().trim())
+        .find(|media_type| media_type.starts_with(MEDIA_TYPE_PREFIX));
+    let Some(media_type) = media_type else {
+        return Ok(None);
+    };
+
+    media_type
+        .strip_prefix(MEDIA_TYPE_PREFIX)
+        .and_then(|version| version.strip_suffix("+json"))
+        .and_then(ApiVersion::parse)
+        .map(Some)
+        .ok_or_else(|| ApiError::NotAcceptable(format!("Unsupported API version: {}", media_type)))
+}
+
+async fn negotiate_version(
+    State(versioning): State<ApiVersioning>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let requested = match requested_version(request.headers()) {
+        Ok(requested) => requested,
+        Err(e) => return e.into_response(),
+    };
+    let version = requested.unwrap_or(versioning.default_version);
+    request.extensions_mut().insert(version);
+
+    let mut response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|content_type| content_type.as_bytes().starts_with(b"application/json"));
+    let media_type = requested
+        .filter(|_| is_json)
+        .and_then(|version| HeaderValue::from_str(&version.media_type()).ok());
+    if let Some(media_type) = media_type {
+        response.headers_mut().insert(CONTENT_TYPE, media_type);
+    }
+    response
+        .headers_mut()
+        .append(VARY, HeaderValue::from_static("accept"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requested_version() {
+        let accept = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(ACCEPT, value.parse().unwrap());
+            requested_version(&headers)
+        };
+
+        assert_eq!(requested_version(&HeaderMap::new()).unwrap(), None);
+        assert_eq!(accept("application/json").unwrap(), None);
+        assert_eq!(
+            accept("text/html, application/vnd.luminair.v1+json;q=0.9").unwrap(),
+            Some(ApiVersion::V1)
+        );
+        assert_eq!(
+            ApiVersion::V1.media_type(),
+            "application/vnd.luminair.v1+json"
+        );
+
+        let error = accept("application/vnd.luminair.v9+json").unwrap_err();
+        assert_eq!(error.code(), "NOT_ACCEPTABLE");
+    }
+}
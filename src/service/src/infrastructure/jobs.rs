@@ -0,0 +1,592 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use luminair_common::database::{self, Database, StatementKind};
+use sea_query_sqlx::SqlxValues;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::postgres::PgRow;
+use sqlx::types::Json;
+use sqlx::{AssertSqlSafe, Row};
+
+use crate::application::AppState;
+use crate::application::commands::{ApplyRetentionCommand, CleanOrphanedLinksCommand};
+use crate::application::service::DocumentsService;
+use crate::infrastructure::persistence::builders::jobs::{
+    cancel_job, claim_next_job, complete_job, fail_job, fail_stale_jobs, insert_job, query_job,
+    query_jobs, retry_job,
+};
+use crate::infrastructure::scheduler::apply_due_publications;
+
+/// Kind of the jobs removing the orphaned relation links, with the payload
+/// `{"remove": true, "batchSize": 500}`, both optional.
+pub const CLEAN_ORPHANED_LINKS_JOB: &str = "clean-orphaned-links";
+
+/// Kind of the jobs deleting or archiving the expired documents of the types
+/// with a retention, with the payload `{"dryRun": false, "batchSize": 1000}`,
+/// both optional.
+pub const APPLY_RETENTION_JOB: &str = "apply-retention";
+
+/// Kind of the jobs applying the scheduled publications which are due.
+pub const APPLY_SCHEDULED_PUBLICATIONS_JOB: &str = "apply-scheduled-publications";
+
+/// Longest delay before a failed job is retried.
+const MAX_RETRY_DELAY_SECONDS: u64 = 60 * 60;
+
+/// Settings of the background jobs
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobsSettings {
+    /// run the job workers in this instance of the service
+    #[serde(default)]
+    pub enabled: bool,
+    /// jobs run at the same time
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+    /// how often an idle worker looks for due jobs
+    #[serde(default = "default_poll_interval_milliseconds")]
+    pub poll_interval_milliseconds: u64,
+    /// runs of a job before it is given up
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: i32,
+    /// how long a job may run before it is taken for stopped and run again
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+    /// delay before the first retry of a failed job, doubled for each further one
+    #[serde(default = "default_retry_delay_seconds")]
+    pub retry_delay_seconds: u64,
+}
+
+impl Default for JobsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            workers: default_workers(),
+            poll_interval_milliseconds: default_poll_interval_milliseconds(),
+            max_attempts: default_max_attempts(),
+            timeout_seconds: default_timeout_seconds(),
+            retry_delay_seconds: default_retry_delay_seconds(),
+        }
+    }
+}
+
+fn default_workers() -> usize {
+    2
+}
+
+fn default_poll_interval_milliseconds() -> u64 {
+    1000
+}
+
+fn default_max_attempts() -> i32 {
+    5
+}
+
+fn default_timeout_seconds() -> u64 {
+    300
+}
+
+fn default_retry_delay_seconds() -> u64 {
+    30
+}
+
+/// Status of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// waiting for its `run_at`, or for a worker
+    Pending,
+    /// taken by a worker
+    Running,
+    Succeeded,
+    /// given up after its last attempt
+    Failed,
+    /// cancelled before it ran
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for JobStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(JobStatus::Pending),
+            "running" => Ok(JobStatus::Running),
+            "succeeded" => Ok(JobStatus::Succeeded),
+            "failed" => Ok(JobStatus::Failed),
+            "cancelled" => Ok(JobStatus::Cancelled),
+            _ => Err(format!("unknown job status '{s}'")),
+        }
+    }
+}
+
+/// A job of the `luminair_jobs` table
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: i64,
+    pub kind: String,
+    pub payload: Value,
+    pub status: JobStatus,
+    /// runs started so far
+    pub attempts: i32,
+    pub max_attempts: i32,
+    /// when the job is due, or retried
+    pub run_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// error of the last failed run
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<&PgRow> for Job {
+    type Error = anyhow::Error;
+
+    fn try_from(row: &PgRow) -> Result<Self, Self::Error> {
+        let id: i64 = row.try_get("job_id")?;
+        let status: String = row.try_get("status")?;
+        Ok(Job {
+            id,
+            kind: row.try_get("kind")?,
+            payload: row.try_get::<Json<Value>, _>("payload")?.0,
+            status: status
+                .parse()
+                .map_err(anyhow::Error::msg)
+                .with_context(|| format!("failed to read job {id}"))?,
+            attempts: row.try_get("attempts")?,
+            max_attempts: row.try_get("max_attempts")?,
+            run_at: row.try_get("run_at")?,
+            started_at: row.try_get("started_at")?,
+            finished_at: row.try_get("finished_at")?,
+            last_error: row.try_get("last_error")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+/// Error of an operation on a job of the queue
+#[derive(Debug, thiserror::Error)]
+pub enum JobError {
+    #[error("job {0} not found")]
+    NotFound(i64),
+    #[error("job {id} is {status}")]
+    Conflict { id: i64, status: JobStatus },
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+impl From<sqlx::Error> for JobError {
+    fn from(e: sqlx::Error) -> Self {
+        JobError::Database(e.into())
+    }
+}
+
+/// Persistent queue of background jobs, in the `luminair_jobs` table, run by
+/// [`JobWorkers`].
+///
+/// A job is claimed by one worker at a time, so it runs on one replica only;
+/// a failed job is retried later, with a growing delay, until it runs out of
+/// attempts. With tenants, every tenant has the jobs of its own schema.
+#[derive(Debug, Clone)]
+pub struct JobQueue {
+    database: &'static Database,
+    max_attempts: i32,
+}
+
+impl JobQueue {
+    /// Queue stored in `database` as configured by `settings`.
+    pub fn new(settings: &JobsSettings, database: &'static Database) -> Self {
+        Self {
+            database,
+            max_attempts: settings.max_attempts,
+        }
+    }
+
+    /// Enqueue a job of `kind`, due at `run_at` or right away.
+    pub async fn enqueue(
+        &self,
+        kind: &str,
+        payload: Value,
+        run_at: Option<DateTime<Utc>>,
+    ) -> Result<Job, JobError> {
+        let (sql, values) = insert_job(kind, payload, self.max_attempts, run_at);
+        self.write_one(sql, values)
+            .await?
+            .ok_or_else(|| JobError::Database(anyhow::anyhow!("the job wasn't inserted")))
+    }
+
+    /// The newest `limit` jobs, of `status` if any.
+    pub async fn list(&self, status: Option<JobStatus>, limit: u64) -> Result<Vec<Job>, JobError> {
+        let (sql, values) = query_jobs(status.map(|status| status.as_str()), limit);
+        let rows = sqlx::query_with(AssertSqlSafe(sql), values)
+            .fetch_all(self.database.database_pool())
+            .await?;
+        Ok(rows.iter().map(Job::try_from).collect::<Result<_, _>>()?)
+    }
+
+    /// The job `id`.
+    pub async fn get(&self, id: i64) -> Result<Job, JobError> {
+        let (sql, values) = query_job(id);
+        let row = sqlx::query_with(AssertSqlSafe(sql), values)
+            .fetch_optional(self.database.database_pool())
+            .await?
+            .ok_or(JobError::NotFound(id))?;
+        Ok(Job::try_from(&row)?)
+    }
+
+    /// Run a failed or cancelled job again, with all its attempts.
+    pub async fn retry(&self, id: i64) -> Result<Job, JobError> {
+        let (sql, values) = retry_job(id);
+        self.transition(id, sql, values).await
+    }
+
+    /// Cancel a pending job; running ones can't be.
+    pub async fn cancel(&self, id: i64) -> Result<Job, JobError> {
+        let (sql, values) = cancel_job(id);
+        self.transition(id, sql, values).await
+    }
+
+    /// Claim the next due job, or one whose worker hasn't finished it within
+    /// `timeout_seconds` while it has attempts left; those without any are
+    /// failed first.
+    async fn claim(&self, timeout_seconds: u64) -> Result<Option<Job>, JobError> {
+        let (sql, values) = fail_stale_jobs(timeout_seconds);
+        self.update(sql, values).await?;
+        let (sql, values) = claim_next_job(timeout_seconds);
+        self.write_one(sql, values).await
+    }
+
+    /// Complete the run of `job`, returning whether it was still the one
+    /// claimed.
+    async fn complete(&self, job: &Job) -> Result<bool, JobError> {
+        let (sql, values) = complete_job(job.id, claimed_at(job)?);
+        Ok(self.update(sql, values).await? > 0)
+    }
+
+    /// Record the failure of the run of `job`, to retry at `retry_at`, or for
+    /// good, returning whether it was still the one claimed.
+    async fn fail(
+        &self,
+        job: &Job,
+        error: String,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<bool, JobError> {
+        let (sql, values) = fail_job(job.id, claimed_at(job)?, error, retry_at);
+        Ok(self.update(sql, values).await? > 0)
+    }
+
+    /// Apply a change of status, which only jobs of some statuses take.
+    async fn transition(&self, id: i64, sql: String, values: SqlxValues) -> Result<Job, JobError> {
+        let updated = self.update(sql, values).await?;
+
+        let job = self.get(id).await?;
+        if updated == 0 {
+            return Err(JobError::Conflict {
+                id,
+                status: job.status,
+            });
+        }
+        Ok(job)
+    }
+
+    /// Run a write, returning the number of jobs it changed.
+    async fn update(&self, sql: String, values: SqlxValues) -> Result<u64, JobError> {
+        let mut transaction = self.database.begin(StatementKind::Write).await?;
+        let updated = sqlx::query_with(AssertSqlSafe(sql), values)
+            .execute(&mut *transaction)
+            .await?
+            .rows_affected();
+        transaction.commit().await?;
+        Ok(updated)
+    }
+
+    /// Run a write answering at most one job.
+    async fn write_one(&self, sql: String, values: SqlxValues) -> Result<Option<Job>, JobError> {
+        let mut transaction = self.database.begin(StatementKind::Write).await?;
+        let row = sqlx::query_with(AssertSqlSafe(sql), values)
+            .fetch_optional(&mut *transaction)
+            .await?;
+        transaction.commit().await?;
+        Ok(row.as_ref().map(Job::try_from).transpose()?)
+    }
+}
+
+/// Runs the jobs of a kind.
+pub trait JobHandler: Send + Sync + 'static {
+    /// Run a job with `payload`; an error fails the run, which is retried
+    /// while the job has attempts left.
+    fn run<'a>(&'a self, payload: &'a Value) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+/// Workers running the jobs of the queue by their kind.
+pub struct JobWorkers {
+    queue: JobQueue,
+    handlers: HashMap<String, Arc<dyn JobHandler>>,
+    workers: usize,
+    poll_interval: Duration,
+    timeout_seconds: u64,
+    retry_delay_seconds: u64,
+}
+
+impl JobWorkers {
+    /// Workers of `queue` as configured by `settings`, running the built-in
+    /// jobs with `state`.
+    pub fn new<S: AppState>(settings: &JobsSettings, queue: JobQueue, state: S) -> Self {
+        let database = queue.database;
+        Self {
+            queue,
+            handlers: HashMap::new(),
+            workers: settings.workers,
+            poll_interval: Duration::from_millis(settings.poll_interval_milliseconds),
+            timeout_seconds: settings.timeout_seconds,
+            retry_delay_seconds: settings.retry_delay_seconds,
+        }
+        .register(
+            CLEAN_ORPHANED_LINKS_JOB,
+            CleanOrphanedLinksJob {
+                state: state.clone(),
+            },
+        )
+        .register(
+            APPLY_RETENTION_JOB,
+            ApplyRetentionJob {
+                state: state.clone(),
+            },
+        )
+        .register(
+            APPLY_SCHEDULED_PUBLICATIONS_JOB,
+            ApplyScheduledPublicationsJob { state, database },
+        )
+    }
+
+    /// Run the jobs of `kind` with `handler`.
+    pub fn register(mut self, kind: &str, handler: impl JobHandler) -> Self {
+        self.handlers.insert(kind.to_string(), Arc::new(handler));
+        self
+    }
+
+    /// Start the workers, which run as long as the service runs.
+    pub fn spawn(self) {
+        let workers = Arc::new(self);
+        for _ in 0..workers.workers {
+            tokio::spawn(workers.clone().run());
+        }
+    }
+
+    /// Run the due jobs of every tenant in turn, waiting for the poll
+    /// interval once there are none.
+    async fn run(self: Arc<Self>) {
+        loop {
+            let mut ran = false;
+            for tenant in self.queue.database.tenant_scopes() {
+                match database::with_tenant(tenant, self.run_next()).await {
+                    Ok(true) => ran = true,
+                    Ok(false) => {}
+                    Err(e) => tracing::warn!("Job worker failed: {:#}", e),
+                }
+            }
+            if !ran {
+                tokio::time::sleep(self.poll_interval).await;
+            }
+        }
+    }
+
+    /// Claim and run the next due job, returning whether there was one.
+    pub async fn run_next(&self) -> Result<bool, JobError> {
+        let Some(job) = self.queue.claim(self.timeout_seconds).await? else {
+            return Ok(false);
+        };
+        let result = match self.handlers.get(&job.kind) {
+            Some(handler) => handler.run(&job.payload).await,
+            None => Err(anyhow::anyhow!(
+                "no handler runs jobs of kind '{}'",
+                job.kind
+            )),
+        };
+        let finished = match result {
+            Ok(()) => self.queue.complete(&job).await?,
+            Err(e) => {
+                let retry_at = (job.attempts < job.max_attempts)
+                    .then(|| Utc::now() + retry_delay(self.retry_delay_seconds, job.attempts));
+                if retry_at.is_none() {
+                    tracing::error!("Job {} ({}) failed for good: {:#}", job.id, job.kind, e);
+                } else {
+                    tracing::warn!("Job {} ({}) failed: {:#}", job.id, job.kind, e);
+                }
+                self.queue.fail(&job, format!("{e:#}"), retry_at).await?
+            }
+        };
+        if !finished {
+            tracing::warn!(
+                "Job {} ({}) was claimed again before its run finished, which is dropped",
+                job.id,
+                job.kind
+            );
+        }
+        Ok(true)
+    }
+}
+
+/// Delay before retrying a job failed on its `attempts`th run: the initial
+/// delay, doubled for every further run, up to an hour.
+fn retry_delay(initial_seconds: u64, attempts: i32) -> chrono::Duration {
+    let doublings = u32::try_from(attempts.saturating_sub(1))
+        .unwrap_or(0)
+        .min(16);
+    let seconds = initial_seconds
+        .saturating_mul(1 << doublings)
+        .min(MAX_RETRY_DELAY_SECONDS);
+    chrono::Duration::seconds(seconds as i64)
+}
+
+/// When the claimed run of `job` started, which tells it from a later run.
+fn claimed_at(job: &Job) -> Result<DateTime<Utc>, JobError> {
+    job.started_at
+        .ok_or_else(|| JobError::Database(anyhow::anyhow!("job {} wasn't claimed", job.id)))
+}
+
+/// Payload of the [`CLEAN_ORPHANED_LINKS_JOB`]s
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CleanOrphanedLinksPayload {
+    #[serde(default = "default_remove")]
+    remove: bool,
+    #[serde(default = "default_batch_size")]
+    batch_size: u64,
+}
+
+fn default_remove() -> bool {
+    true
+}
+
+fn default_batch_size() -> u64 {
+    500
+}
+
+struct CleanOrphanedLinksJob<S> {
+    state: S,
+}
+
+impl<S: AppState> JobHandler for CleanOrphanedLinksJob<S> {
+    fn run<'a>(&'a self, payload: &'a Value) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let payload = serde_json::from_value::<CleanOrphanedLinksPayload>(payload.clone())
+                .context("invalid payload")?;
+            anyhow::ensure!(payload.batch_size > 0, "the batch size must be positive");
+            let links = self
+                .state
+                .documents_service()
+                .clean_orphaned_links(CleanOrphanedLinksCommand {
+                    remove: payload.remove,
+                    batch_size: payload.batch_size,
+                })
+                .await?;
+            if payload.remove && !links.is_empty() {
+                tracing::warn!("Removed orphaned relation links: {:?}", links);
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Payload of the [`APPLY_RETENTION_JOB`]s
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApplyRetentionPayload {
+    #[serde(default)]
+    dry_run: bool,
+    #[serde(default = "default_retention_batch_size")]
+    batch_size: u64,
+}
+
+fn default_retention_batch_size() -> u64 {
+    1000
+}
+
+struct ApplyRetentionJob<S> {
+    state: S,
+}
+
+impl<S: AppState> JobHandler for ApplyRetentionJob<S> {
+    fn run<'a>(&'a self, payload: &'a Value) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let payload = serde_json::from_value::<ApplyRetentionPayload>(payload.clone())
+                .context("invalid payload")?;
+            anyhow::ensure!(payload.batch_size > 0, "the batch size must be positive");
+            let documents = self
+                .state
+                .documents_service()
+                .apply_retention(ApplyRetentionCommand {
+                    now: Utc::now(),
+                    dry_run: payload.dry_run,
+                    batch_size: payload.batch_size,
+                })
+                .await?;
+            if !documents.is_empty() {
+                if payload.dry_run {
+                    tracing::info!("Expired documents to remove: {:?}", documents);
+                } else {
+                    tracing::info!("Removed expired documents: {:?}", documents);
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+struct ApplyScheduledPublicationsJob<S> {
+    state: S,
+    database: &'static Database,
+}
+
+impl<S: AppState> JobHandler for ApplyScheduledPublicationsJob<S> {
+    fn run<'a>(&'a self, _payload: &'a Value) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            match apply_due_publications(&self.state, self.database, Utc::now()).await? {
+                Some(applied) if applied > 0 => {
+                    tracing::info!("Applied {} scheduled publications", applied)
+                }
+                Some(_) => {}
+                // retried later, when the other replica is done
+                None => anyhow::bail!("another replica is applying the scheduled publications"),
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retries_back_off_up_to_an_hour() {
+        assert_eq!(retry_delay(30, 1), chrono::Duration::seconds(30));
+        assert_eq!(retry_delay(30, 2), chrono::Duration::seconds(60));
+        assert_eq!(retry_delay(30, 4), chrono::Duration::seconds(240));
+        assert_eq!(retry_delay(30, 20), chrono::Duration::seconds(3600));
+    }
+}
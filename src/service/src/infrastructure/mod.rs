@@ -1,35 +1,66 @@
-use crate::application::AppState;
 use crate::application::implementation::DocumentsServiceImpl;
+use crate::application::{AppState, QueryLimits, ResponseFormat};
+use crate::domain::repository::DocumentsRepository;
 use crate::infrastructure::persistence::repository::PostgresDocumentsRepository;
 use luminair_common::DocumentTypesRegistry;
 
+pub mod error_reporting;
+pub mod events;
+pub mod grpc;
 pub mod http;
+pub mod jobs;
+pub mod partitions;
 pub mod persistence;
+pub mod response_cache;
+pub mod retention;
+pub mod scheduler;
+pub mod schema_metrics;
+pub mod search_index;
 pub mod settings;
 
+/// Application state of the documents stored by the repository `R`, Postgres
+/// unless tests swap in another one.
 #[derive(Clone)]
-pub struct AppStateImpl {
+pub struct AppStateImpl<R: DocumentsRepository + Clone = PostgresDocumentsRepository> {
     types: &'static dyn DocumentTypesRegistry,
-    documents_service: DocumentsServiceImpl<PostgresDocumentsRepository>,
+    documents_service: DocumentsServiceImpl<R>,
     pagination_settings: crate::application::PaginationSettings,
+    query_limits: QueryLimits,
+    response_format: ResponseFormat,
 }
 
-impl AppStateImpl {
+impl<R: DocumentsRepository + Clone> AppStateImpl<R> {
+    /// Application state of `types` stored by `documents_repository`, with
+    /// the default query limits and response format.
     pub fn new(
         types: &'static dyn DocumentTypesRegistry,
-        documents_repository: PostgresDocumentsRepository,
+        documents_repository: R,
         pagination_settings: crate::application::PaginationSettings,
     ) -> Self {
-        Self {
+        Self::builder(types, documents_repository)
+            .pagination_settings(pagination_settings)
+            .build()
+    }
+
+    /// Start wiring the application state of `types` stored by
+    /// `documents_repository`.
+    pub fn builder(
+        types: &'static dyn DocumentTypesRegistry,
+        documents_repository: R,
+    ) -> AppStateBuilder<R> {
+        AppStateBuilder {
             types,
-            documents_service: DocumentsServiceImpl::new(documents_repository),
-            pagination_settings,
+            documents_repository,
+            pagination_settings: Default::default(),
+            query_limits: QueryLimits::default(),
+            response_format: ResponseFormat::default(),
         }
     }
 }
 
-impl AppState for AppStateImpl {
-    type D = DocumentsServiceImpl<PostgresDocumentsRepository>;
+impl<R: DocumentsRepository + Clone> AppState for AppStateImpl<R> {
+    type D = DocumentsServiceImpl<R>;
+    type R = R;
 
     fn document_types(&self) -> &'static dyn DocumentTypesRegistry {
         self.types
@@ -39,7 +70,66 @@ impl AppState for AppStateImpl {
         &self.documents_service
     }
 
+    fn documents_repository(&self) -> &Self::R {
+        self.documents_service.repository()
+    }
+
     fn pagination_settings(&self) -> crate::application::PaginationSettings {
         self.pagination_settings
     }
+
+    fn query_limits(&self) -> QueryLimits {
+        self.query_limits
+    }
+
+    fn response_format(&self) -> ResponseFormat {
+        self.response_format
+    }
+}
+
+/// Wires an [`AppStateImpl`] from the repository the documents are stored by,
+/// so that embedding applications choose their own implementation.
+///
+/// Lifecycle hooks, the document cache and the outbox are options of the
+/// repository, set on it before it is handed to the builder.
+pub struct AppStateBuilder<R: DocumentsRepository + Clone> {
+    types: &'static dyn DocumentTypesRegistry,
+    documents_repository: R,
+    pagination_settings: crate::application::PaginationSettings,
+    query_limits: QueryLimits,
+    response_format: ResponseFormat,
+}
+
+impl<R: DocumentsRepository + Clone> AppStateBuilder<R> {
+    /// Page the lists by `pagination_settings`.
+    pub fn pagination_settings(
+        mut self,
+        pagination_settings: crate::application::PaginationSettings,
+    ) -> Self {
+        self.pagination_settings = pagination_settings;
+        self
+    }
+
+    /// Reject queries exceeding `query_limits`.
+    pub fn query_limits(mut self, query_limits: QueryLimits) -> Self {
+        self.query_limits = query_limits;
+        self
+    }
+
+    /// Answer list and detail requests in `response_format`.
+    pub fn response_format(mut self, response_format: ResponseFormat) -> Self {
+        self.response_format = response_format;
+        self
+    }
+
+    /// The application state wired so far.
+    pub fn build(self) -> AppStateImpl<R> {
+        AppStateImpl {
+            types: self.types,
+            documents_service: DocumentsServiceImpl::new(self.documents_repository),
+            pagination_settings: self.pagination_settings,
+            query_limits: self.query_limits,
+            response_format: self.response_format,
+        }
+    }
 }
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use luminair_common::DocumentTypesRegistry;
+use luminair_common::database::Database;
+use serde::Deserialize;
+use tokio::time::MissedTickBehavior;
+
+/// Settings of the maintenance creating the partitions of the partitioned types
+#[derive(Debug, Clone, Deserialize)]
+pub struct PartitionsSettings {
+    /// run the maintenance in this instance of the service
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// how often the partitions ahead are created
+    #[serde(default = "default_interval_seconds")]
+    pub interval_seconds: u64,
+}
+
+impl Default for PartitionsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_seconds: default_interval_seconds(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_seconds() -> u64 {
+    6 * 60 * 60
+}
+
+/// Create the partitions of the partitioned types for the current interval
+/// and the ones ahead of it, every `interval`, as long as the service runs.
+///
+/// Existing partitions are skipped, so only the first run of an interval
+/// creates any; a failed run is retried with the next tick.
+pub async fn run_partition_maintenance(
+    documents: &'static dyn DocumentTypesRegistry,
+    database: &'static Database,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        if let Err(e) = migration::create_partitions(documents, database).await {
+            tracing::warn!("Creating partitions failed: {:#}", e);
+        }
+    }
+}
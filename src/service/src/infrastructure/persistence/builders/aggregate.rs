@@ -0,0 +1,69 @@
+use crate::domain::query::{AggregateFunction, AggregateQuery, DocumentStatus, Metric};
+use crate::infrastructure::persistence::builders::find::build_condition;
+use luminair_common::DocumentType;
+use luminair_common::persistence::TableNameProviderConstructor;
+use sea_query::{Alias, Asterisk, Expr, ExprTrait, Func, Order, PostgresQueryBuilder, Query};
+use sea_query_sqlx::{SqlxBinder, SqlxValues};
+
+/// Name of the column with the value of the metric.
+pub fn metric_column(metric: &Metric) -> String {
+    match &metric.field {
+        Some(field) => format!("{}_{}", metric.function.name(), field.normalized()),
+        None => metric.function.name().to_string(),
+    }
+}
+
+/**
+ * SELECT m.category, CAST(COUNT(*) AS numeric) AS count, CAST(SUM(m.price) AS numeric) AS sum_price
+ * FROM articles m                      -- article_snapshots m if query.status == Published
+ * WHERE ...
+ * GROUP BY m.category
+ * ORDER BY m.category ASC
+ *
+ * Metrics are cast to numeric, so every metric is decoded the same way
+ * whatever the type of the aggregated field.
+ */
+pub fn query_aggregate_documents(
+    document: &DocumentType,
+    query: &AggregateQuery,
+) -> (String, SqlxValues) {
+    let mut select = Query::select();
+
+    if query.status == DocumentStatus::Published && document.has_draft_and_publish() {
+        select.from(document.snapshot_table());
+    } else {
+        select.from(document.main_table());
+    }
+
+    for field in &query.group_by {
+        let column = ("m", field.normalized());
+        select
+            .column(column.clone())
+            .group_by_col(column.clone())
+            .order_by(column, Order::Asc);
+    }
+
+    for metric in &query.metrics {
+        let argument = match &metric.field {
+            Some(field) => Expr::col(("m", field.normalized())),
+            None => Expr::col(Asterisk),
+        };
+        let aggregate = match metric.function {
+            AggregateFunction::Count => Func::count(argument),
+            AggregateFunction::Sum => Func::sum(argument),
+            AggregateFunction::Avg => Func::avg(argument),
+            AggregateFunction::Min => Func::min(argument),
+            AggregateFunction::Max => Func::max(argument),
+        };
+        select.expr_as(
+            Expr::from(aggregate).cast_as(Alias::new("numeric")),
+            Alias::new(metric_column(metric)),
+        );
+    }
+
+    if let Some(condition) = build_condition(&query.filter, document, "m", query.status) {
+        select.cond_where(condition);
+    }
+
+    select.build_sqlx(PostgresQueryBuilder)
+}
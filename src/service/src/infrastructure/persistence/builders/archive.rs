@@ -0,0 +1,148 @@
+use luminair_common::persistence::{
+    main_table_name, relation_snapshot_table_name, relation_table_name, revision_table_name,
+    snapshot_table_name,
+};
+use luminair_common::{DocumentTypesRegistry, REVISION_ID_FIELD_NAME, SNAPSHOT_ID_FIELD_NAME};
+use sea_query::{Alias, Expr, PostgresQueryBuilder, Query, UnionType, Values};
+use sea_query_sqlx::{SqlxBinder, SqlxValues};
+use serde_json::Value;
+
+/// Table holding content of a document type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentTable {
+    pub name: String,
+    /// column whose values the database generates, if any
+    pub identity: Option<&'static str>,
+}
+
+/// The tables of the content of all document types, in an order their rows
+/// can be inserted in: every table comes after the tables it references.
+/// Read-only types are left out, their views are refreshed from the others.
+pub fn content_tables(registry: &dyn DocumentTypesRegistry) -> Vec<ContentTable> {
+    let mut document_types = registry
+        .iterate()
+        .filter(|document| document.view().is_none())
+        .collect::<Vec<_>>();
+    document_types.sort_by(|a, b| a.id.as_ref().cmp(b.id.as_ref()));
+
+    let table = |name: String, identity: Option<&'static str>| ContentTable { name, identity };
+    let mut tables = document_types
+        .iter()
+        .map(|document| table(main_table_name(&document.id), None))
+        .collect::<Vec<_>>();
+    tables.extend(
+        document_types
+            .iter()
+            .filter(|document| document.has_draft_and_publish())
+            .map(|document| {
+                table(
+                    snapshot_table_name(&document.id),
+                    Some(SNAPSHOT_ID_FIELD_NAME),
+                )
+            }),
+    );
+    tables.extend(document_types.iter().map(|document| {
+        table(
+            revision_table_name(&document.id),
+            Some(REVISION_ID_FIELD_NAME),
+        )
+    }));
+    for document in &document_types {
+        let mut relations = document
+            .relations
+            .iter()
+            .filter(|relation| relation.relation_type.is_owning())
+            .collect::<Vec<_>>();
+        relations.sort_by(|a, b| a.id.cmp(&b.id));
+        tables.extend(
+            relations
+                .iter()
+                .map(|relation| table(relation_table_name(&document.id, &relation.id), None)),
+        );
+        if document.has_draft_and_publish() {
+            tables.extend(relations.iter().map(|relation| {
+                table(
+                    relation_snapshot_table_name(&document.id, &relation.id),
+                    None,
+                )
+            }));
+        }
+    }
+    tables
+}
+
+/// SELECT 'brands' AS "table", to_jsonb(t) AS "row" FROM brands t
+/// UNION ALL SELECT ... FROM brands_snapshots t UNION ALL ...
+///
+/// Every row of `tables`, read in one statement so they are consistent.
+pub fn query_export_rows(tables: &[ContentTable]) -> Option<(String, SqlxValues)> {
+    let mut selects = tables.iter().map(|table| {
+        Query::select()
+            .expr_as(Expr::val(table.name.as_str()), Alias::new("table"))
+            .expr_as(Expr::cust("to_jsonb(t)"), Alias::new("row"))
+            .from_as(Alias::new(table.name.as_str()), Alias::new("t"))
+            .to_owned()
+    });
+    let mut query = selects.next()?;
+    for select in selects {
+        query.union(UnionType::All, select);
+    }
+    Some(query.build_sqlx(PostgresQueryBuilder))
+}
+
+/// SELECT 1 FROM {table} LIMIT 1
+pub fn query_any_row(table: &ContentTable) -> (String, SqlxValues) {
+    Query::select()
+        .expr(Expr::val(1))
+        .from(Alias::new(table.name.as_str()))
+        .limit(1)
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// INSERT INTO {table} OVERRIDING SYSTEM VALUE
+/// SELECT * FROM jsonb_populate_recordset(NULL::{table}, $1)
+///
+/// `rows` as they were exported, with the values of identity columns kept.
+pub fn insert_archive_rows(table: &ContentTable, rows: Vec<Value>) -> (String, SqlxValues) {
+    let overriding = if table.identity.is_some() {
+        " OVERRIDING SYSTEM VALUE"
+    } else {
+        ""
+    };
+    let sql = format!(
+        r#"INSERT INTO "{table}"{overriding} SELECT * FROM jsonb_populate_recordset(NULL::"{table}", $1)"#,
+        table = table.name,
+    );
+    (sql, SqlxValues(Values(vec![Value::Array(rows).into()])))
+}
+
+/// SELECT setval(pg_get_serial_sequence('{table}', '{column}'), MAX({column}))
+/// FROM {table} HAVING MAX({column}) IS NOT NULL
+///
+/// Moves the identity of `table` past the restored rows.
+pub fn reset_identity(table: &ContentTable, column: &str) -> (String, SqlxValues) {
+    let sql = format!(
+        r#"SELECT setval(pg_get_serial_sequence('"{table}"', '{column}'), MAX("{column}")) FROM "{table}" HAVING MAX("{column}") IS NOT NULL"#,
+        table = table.name,
+    );
+    (sql, SqlxValues(Values(vec![])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_rows_keep_their_identity() {
+        let table = ContentTable {
+            name: "brands_snapshots".to_string(),
+            identity: Some(SNAPSHOT_ID_FIELD_NAME),
+        };
+        let (sql, _) = insert_archive_rows(&table, vec![]);
+
+        assert_eq!(
+            sql,
+            r#"INSERT INTO "brands_snapshots" OVERRIDING SYSTEM VALUE SELECT * FROM jsonb_populate_recordset(NULL::"brands_snapshots", $1)"#
+        );
+    }
+}
@@ -1,20 +1,28 @@
 use crate::domain::query::{
-    DocumentInstanceQuery, DocumentStatus, FilterExpression, SortDirection,
+    DocumentInstanceQuery, DocumentStatus, FilterExpression, RandomOrder, SortDirection,
+    TableSample,
 };
 use crate::infrastructure::persistence::builders::main_select_columns;
+use crate::infrastructure::persistence::collations::SortCollations;
+use crate::infrastructure::persistence::mapping::writer::array_value;
 
-use luminair_common::persistence::TableNameProviderConstructor;
+use luminair_common::persistence::{TableNameProvider, TableNameProviderConstructor};
 use luminair_common::{
-    DOCUMENT_ID_FIELD_NAME, DocumentType, STATUS_FIELD_NAME, VERSION_FIELD_NAME,
-    entities::FieldType,
+    AttributeId, DOCUMENT_ID_FIELD_NAME, DocumentType, OWNING_DOCUMENT_ID_FIELD_NAME,
+    PUBLISHED_FIELD_NAME, STATUS_FIELD_NAME, TARGET_DOCUMENT_ID_FIELD_NAME, UPDATED_FIELD_NAME,
+    VERSION_FIELD_NAME, entities::FieldType,
 };
+use sea_query::extension::postgres::PgFunc;
 use sea_query::{
-    Alias, ColumnRef, Condition, Expr, ExprTrait, Order, PostgresQueryBuilder, Query,
-    SelectStatement, TableRef,
+    Alias, ColumnRef, Condition, Expr, ExprTrait, JoinType, LikeExpr, Order, PostgresQueryBuilder,
+    Query, SelectStatement, TableRef, Value,
 };
 use sea_query_sqlx::{SqlxBinder, SqlxValues};
 use uuid::Uuid;
 
+/// Escape character of the LIKE patterns built from user input.
+const LIKE_ESCAPE: char = '\\';
+
 /**
  * Create query for find ONE document by document_id + status
  * THERE IS NO HISTORY IN MVP,
@@ -64,40 +72,141 @@ pub fn query_find_document_by_id(
     let mut select = main_document_select(document, query.status);
     select.and_where(Expr::col(("m", DOCUMENT_ID_FIELD_NAME)).eq(id));
 
-    if let Some(condition) = build_condition(&query.filter, document, "m") {
+    if let Some(condition) = build_condition(&query.filter, document, "m", query.status) {
         select.cond_where(condition);
     }
 
     select.build_sqlx(PostgresQueryBuilder)
 }
 
+/// Create query checking that the document `id` exists in `status`, without
+/// reading its columns:
+///
+/// SELECT 1 FROM articles m WHERE m.document_id = $1 LIMIT 1
+pub fn query_document_exists(
+    document: &DocumentType,
+    id: Uuid,
+    status: DocumentStatus,
+) -> (String, SqlxValues) {
+    let mut select = Query::select();
+    select
+        .expr(Expr::cust("1"))
+        .from(document_table(document, status))
+        .and_where(Expr::col(("m", DOCUMENT_ID_FIELD_NAME)).eq(id))
+        .limit(1);
+
+    select.build_sqlx(PostgresQueryBuilder)
+}
+
 pub fn query_find_document_by_criteria(
     document: &DocumentType,
     query: &DocumentInstanceQuery,
+    collations: &SortCollations,
 ) -> (String, SqlxValues) {
+    select_documents_by_criteria(document, query, collations).build_sqlx(PostgresQueryBuilder)
+}
+
+/// The filtered, sorted and paginated select of the documents, aliased `m`.
+pub(crate) fn select_documents_by_criteria(
+    document: &DocumentType,
+    query: &DocumentInstanceQuery,
+    collations: &SortCollations,
+) -> SelectStatement {
     let mut select = main_document_select(document, query.status);
 
-    if let Some(condition) = build_condition(&query.filter, document, "m") {
+    if let Some(condition) = build_condition(&query.filter, document, "m", query.status) {
         select.cond_where(condition);
     }
+    if let Some(sample) = query.sample {
+        let table = document_table(document, query.status).table_name();
+        select.and_where(sample_condition(&table, "m", sample));
+    }
 
     for sort in &query.sort {
-        let col = get_column_expr(&sort.field, document, "m");
+        let col = sort_column_expr(&sort.field, document, "m", collations);
         let order = match sort.direction {
             SortDirection::Ascending => Order::Asc,
             SortDirection::Descending => Order::Desc,
         };
         select.order_by_expr(col, order);
     }
+    if let Some(random_order) = query.random_order {
+        select.order_by_expr(random_order_expr(random_order, "m"), Order::Asc);
+    }
 
+    // LIMIT and OFFSET are bound as parameters like any other value,
+    // negative values are clamped to zero.
     if let Some(limit) = query.limit {
-        select.limit(limit as u64);
+        select.limit(u64::try_from(limit).unwrap_or(0));
     }
     if let Some(offset) = query.offset {
-        select.offset(offset as u64);
+        select.offset(u64::try_from(offset).unwrap_or(0));
     }
 
-    select.build_sqlx(PostgresQueryBuilder)
+    select
+}
+
+/// Order of the rows aliased `alias` at random; the rows are ordered by a
+/// hash of their document id and the seed for a reproducible order.
+pub(crate) fn random_order_expr(random_order: RandomOrder, alias: &str) -> Expr {
+    match random_order.seed {
+        Some(seed) => Expr::cust_with_values(
+            format!("md5(\"{alias}\".\"{DOCUMENT_ID_FIELD_NAME}\"::text || $1)"),
+            [seed.to_string()],
+        ),
+        None => Expr::cust("random()"),
+    }
+}
+
+/// Keep the rows aliased `alias` of `table` in the `TABLESAMPLE BERNOULLI`
+/// sample of the table.
+///
+/// sea-query has no `TABLESAMPLE` clause: the sample is taken by a subquery,
+/// whose row ids the rows are then looked up by. A `ctid` is only unique
+/// within one partition, so the rows are matched by the table they're stored
+/// in as well.
+fn sample_condition(table: &str, alias: &str, sample: TableSample) -> Expr {
+    let repeatable = if sample.seed.is_some() {
+        " REPEATABLE (CAST($2 AS double precision))"
+    } else {
+        ""
+    };
+    let mut values = vec![Value::from(sample.percent)];
+    values.extend(sample.seed.map(Value::from));
+    Expr::cust_with_values(
+        format!(
+            "(\"{alias}\".tableoid, \"{alias}\".ctid) IN (SELECT tableoid, ctid FROM \"{}\" TABLESAMPLE BERNOULLI (CAST($1 AS real)){repeatable})",
+            table.replace('"', "\"\"")
+        ),
+        values,
+    )
+}
+
+/// Table of the rows of `document` read with `status`.
+fn document_table(document: &DocumentType, status: DocumentStatus) -> TableNameProvider<'_> {
+    if status == DocumentStatus::Published && document.has_draft_and_publish() {
+        document.snapshot_table()
+    } else {
+        document.main_table()
+    }
+}
+
+/// (m.status <> 'PUBLISHED' AND EXISTS (SELECT 1 FROM {table}_snapshots m_s
+///         WHERE m_s.document_id = m.document_id AND m_s.published_at < m.updated_at))
+///
+/// Whether the draft row aliased `alias` of a draft-and-publish `document`
+/// was saved after its published version. Rows still `PUBLISHED` aren't,
+/// whatever their `updated_at`: touches and erasures stamp it without
+/// making a draft.
+pub(crate) fn modified_sql(document: &DocumentType, alias: &str) -> String {
+    format!(
+        r#"("{alias}"."{status}" <> 'PUBLISHED' AND EXISTS (SELECT 1 FROM "{snapshots}" "{alias}_s" WHERE "{alias}_s"."{id}" = "{alias}"."{id}" AND "{alias}_s"."{published}" < "{alias}"."{updated}"))"#,
+        snapshots = document.snapshot_table().table_name(),
+        status = STATUS_FIELD_NAME,
+        id = DOCUMENT_ID_FIELD_NAME,
+        published = PUBLISHED_FIELD_NAME,
+        updated = UPDATED_FIELD_NAME,
+    )
 }
 
 fn main_document_select(document: &DocumentType, status: DocumentStatus) -> SelectStatement {
@@ -130,6 +239,12 @@ fn main_document_select(document: &DocumentType, status: DocumentStatus) -> Sele
     // Add typed/custom expressions via .expr_as()
     select.expr_as(version_expr, Alias::new("version"));
     select.expr_as(status_expr, Alias::new("status"));
+    if status == DocumentStatus::Draft && document.has_draft_and_publish() {
+        select.expr_as(
+            Expr::cust(modified_sql(document, "m")),
+            Alias::new("modified"),
+        );
+    }
 
     select
 }
@@ -138,11 +253,10 @@ pub fn query_count_documents(
     document: &DocumentType,
     query: &DocumentInstanceQuery,
 ) -> (String, SqlxValues) {
-    let table_ref = if query.status == DocumentStatus::Published {
-        document.snapshot_table()
-    } else {
-        document.main_table()
-    };
+    let table_ref = document_table(document, query.status);
+    let sample = query
+        .sample
+        .map(|sample| sample_condition(&table_ref.table_name(), "m", sample));
 
     let mut select = Query::select();
     select
@@ -152,23 +266,31 @@ pub fn query_count_documents(
         )
         .from(table_ref);
 
-    if let Some(condition) = build_condition(&query.filter, document, "m") {
+    if let Some(condition) = build_condition(&query.filter, document, "m", query.status) {
         select.cond_where(condition);
     }
+    if let Some(sample) = sample {
+        select.and_where(sample);
+    }
 
     select.build_sqlx(PostgresQueryBuilder)
 }
 
+/// Build the `WHERE` condition of a filter over the document rows aliased `alias`.
+///
+/// `status` selects the relation and target tables that relation filters
+/// are checked against.
 pub fn build_condition(
     filter: &FilterExpression,
     document: &DocumentType,
     alias: &str,
+    status: DocumentStatus,
 ) -> Option<Condition> {
     match filter {
         FilterExpression::None => None,
         FilterExpression::And(left, right) => {
-            let left_cond = build_condition(left, document, alias);
-            let right_cond = build_condition(right, document, alias);
+            let left_cond = build_condition(left, document, alias, status);
+            let right_cond = build_condition(right, document, alias, status);
             match (left_cond, right_cond) {
                 (Some(l), Some(r)) => Some(Condition::all().add(l).add(r)),
                 (Some(l), None) => Some(l),
@@ -177,8 +299,8 @@ pub fn build_condition(
             }
         }
         FilterExpression::Or(left, right) => {
-            let left_cond = build_condition(left, document, alias);
-            let right_cond = build_condition(right, document, alias);
+            let left_cond = build_condition(left, document, alias, status);
+            let right_cond = build_condition(right, document, alias, status);
             match (left_cond, right_cond) {
                 (Some(l), Some(r)) => Some(Condition::any().add(l).add(r)),
                 (Some(l), None) => Some(l),
@@ -186,7 +308,8 @@ pub fn build_condition(
                 (None, None) => None,
             }
         }
-        _ => build_filter_expr(filter, document, alias).map(|expr| Condition::all().add(expr)),
+        _ => build_filter_expr(filter, document, alias, status)
+            .map(|expr| Condition::all().add(expr)),
     }
 }
 
@@ -194,6 +317,7 @@ fn build_filter_expr(
     filter: &FilterExpression,
     document: &DocumentType,
     alias: &str,
+    status: DocumentStatus,
 ) -> Option<Expr> {
     match filter {
         FilterExpression::Equals { field, value } => {
@@ -214,25 +338,37 @@ fn build_filter_expr(
         FilterExpression::LessThanOrEqual { field, value } => {
             Some(get_column_expr(field, document, alias).lte(Expr::from(value)))
         }
+        // `IN ()` is not valid SQL: nothing is in an empty list, everything is not in it.
+        FilterExpression::In { values, .. } if values.is_empty() => Some(Expr::cust("FALSE")),
         FilterExpression::In { field, values } => {
-            let exprs: Vec<Expr> = values.iter().map(Expr::from).collect();
-            Some(get_column_expr(field, document, alias).is_in(exprs))
+            let column = get_column_expr(field, document, alias);
+            Some(match array_value(values) {
+                Some(array) => column.eq(PgFunc::any(array)),
+                None => column.is_in(values.iter().map(Expr::from).collect::<Vec<_>>()),
+            })
         }
+        FilterExpression::NotIn { values, .. } if values.is_empty() => Some(Expr::cust("TRUE")),
         FilterExpression::NotIn { field, values } => {
-            let exprs: Vec<Expr> = values.iter().map(Expr::from).collect();
-            Some(get_column_expr(field, document, alias).is_not_in(exprs))
+            let column = get_column_expr(field, document, alias);
+            Some(match array_value(values) {
+                Some(array) => column.ne(PgFunc::all(array)),
+                None => column.is_not_in(values.iter().map(Expr::from).collect::<Vec<_>>()),
+            })
+        }
+        FilterExpression::Between { field, from, to } => {
+            Some(get_column_expr(field, document, alias).between(Expr::from(from), Expr::from(to)))
         }
         FilterExpression::Contains { field, value } => {
-            let pattern = format!("%{}%", value);
-            Some(get_column_expr(field, document, alias).like(pattern))
+            let pattern = format!("%{}%", escape_like(value));
+            Some(get_column_expr(field, document, alias).like(like_pattern(pattern)))
         }
         FilterExpression::StartsWith { field, value } => {
-            let pattern = format!("{}%", value);
-            Some(get_column_expr(field, document, alias).like(pattern))
+            let pattern = format!("{}%", escape_like(value));
+            Some(get_column_expr(field, document, alias).like(like_pattern(pattern)))
         }
         FilterExpression::EndsWith { field, value } => {
-            let pattern = format!("%{}", value);
-            Some(get_column_expr(field, document, alias).like(pattern))
+            let pattern = format!("%{}", escape_like(value));
+            Some(get_column_expr(field, document, alias).like(like_pattern(pattern)))
         }
         FilterExpression::IsNull { field } => {
             Some(get_column_expr(field, document, alias).is_null())
@@ -240,11 +376,134 @@ fn build_filter_expr(
         FilterExpression::IsNotNull { field } => {
             Some(get_column_expr(field, document, alias).is_not_null())
         }
-        FilterExpression::HasRelation { .. } => None,
+        FilterExpression::HasRelation {
+            field,
+            target,
+            filter,
+        } => Some(build_has_relation(
+            document, alias, status, field, target, filter,
+        )),
+        FilterExpression::Modified => Some(
+            if status == DocumentStatus::Draft && document.has_draft_and_publish() {
+                Expr::cust(modified_sql(document, alias))
+            } else {
+                Expr::cust("FALSE")
+            },
+        ),
         _ => None,
     }
 }
 
+/// Escape the LIKE wildcards `%` and `_` (and the escape character itself)
+/// in user input, so it is matched literally.
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == LIKE_ESCAPE || c == '%' || c == '_' {
+            escaped.push(LIKE_ESCAPE);
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn like_pattern(pattern: String) -> LikeExpr {
+    LikeExpr::new(pattern).escape(LIKE_ESCAPE)
+}
+
+/**
+ * EXISTS (
+ *     SELECT 1
+ *     FROM partners_brands_relation m_brands_r
+ *     JOIN brands m_brands ON m_brands.document_id = m_brands_r.target_document_id
+ *     WHERE m_brands_r.owning_document_id = m.document_id
+ *       AND <filter on m_brands>
+ * )
+ *
+ * The tables get aliases derived from the outer alias, so relation filters
+ * can be nested and used inside queries already aliasing `m` and `r`.
+ * With status == DocumentStatus::Published the snapshot tables are used.
+ */
+fn build_has_relation(
+    document: &DocumentType,
+    alias: &str,
+    status: DocumentStatus,
+    relation: &AttributeId,
+    target: &DocumentType,
+    filter: &FilterExpression,
+) -> Expr {
+    let target_alias = format!("{}_{}", alias, relation.normalized());
+    let relation_alias = format!("{}_r", target_alias);
+
+    let relation_table = if status == DocumentStatus::Published && document.has_draft_and_publish()
+    {
+        document.relation_snapshot_table(relation)
+    } else {
+        document.relation_table(relation)
+    };
+    let target_table = if status == DocumentStatus::Published && target.has_draft_and_publish() {
+        target.snapshot_table()
+    } else {
+        target.main_table()
+    };
+
+    let mut exists = Query::select();
+    exists
+        .expr(Expr::cust("1"))
+        .from(relation_table.with_alias(&relation_alias))
+        .join(
+            JoinType::InnerJoin,
+            target_table.with_alias(&target_alias),
+            Expr::col((target_alias.clone(), DOCUMENT_ID_FIELD_NAME))
+                .equals((relation_alias.clone(), TARGET_DOCUMENT_ID_FIELD_NAME)),
+        )
+        .and_where(
+            Expr::col((relation_alias, OWNING_DOCUMENT_ID_FIELD_NAME))
+                .equals((alias.to_owned(), DOCUMENT_ID_FIELD_NAME)),
+        );
+
+    if let Some(condition) = build_condition(filter, target, &target_alias, status) {
+        exists.cond_where(condition);
+    }
+
+    Expr::exists(exists)
+}
+
+/// Column expression of a sort by `field_path` like [`get_column_expr`]; a
+/// localized text field is sorted by the collation of its locale in
+/// `collations`, e.g. `("m"."title" ->> 'ro') COLLATE "ro-x-icu"`.
+pub(crate) fn sort_column_expr(
+    field_path: &str,
+    document: &DocumentType,
+    alias: &str,
+    collations: &SortCollations,
+) -> Expr {
+    let localized = field_path.split_once('.').and_then(|(base_field, locale)| {
+        document
+            .fields
+            .iter()
+            .find(|f| f.id.as_ref() == base_field && f.field_type == FieldType::LocalizedText)
+            .map(|field| (field.id.normalized(), locale))
+    });
+    let collated = localized.and_then(|(column_name, locale)| {
+        collations
+            .collation(locale)
+            .map(|collation| (column_name, locale, collation))
+    });
+    match collated {
+        Some((column_name, locale, collation)) => Expr::cust_with_values(
+            format!(
+                "(\"{}\".\"{}\" ->> $1) COLLATE \"{}\"",
+                alias,
+                column_name,
+                collation.replace('"', "\"\"")
+            ),
+            vec![locale.to_string()],
+        ),
+        None => get_column_expr(field_path, document, alias),
+    }
+}
+
 /// Translates a schema field path (e.g. "price" or "description.en") into a `sea-query` expression (`Expr`).
 ///
 /// This handles two distinct database column layout styles:
@@ -270,7 +529,7 @@ pub fn get_column_expr(field_path: &str, document: &DocumentType, alias: &str) -
     if is_localized && parts.len() > 1 {
         // Localized path extraction: compiles into standard JSONB query ("alias"."column_name" ->> 'locale')
         Expr::cust_with_values(
-            format!("\"{}\".\"{}\" ->> ?", alias, column_name),
+            format!("\"{}\".\"{}\" ->> $1", alias, column_name),
             vec![parts[1].to_string()],
         )
     } else {
@@ -278,3 +537,177 @@ pub fn get_column_expr(field_path: &str, document: &DocumentType, alias: &str) -
         Expr::col((alias.to_owned(), column_name))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::document::content::DomainValue;
+    use crate::infrastructure::persistence::collations::CollationSettings;
+    use luminair_common::entities::DocumentField;
+    use std::collections::HashSet;
+
+    fn brands() -> DocumentType {
+        let mut document = DocumentType::new_bare_collection("brand", "brand", "brands").unwrap();
+        document.fields.insert(DocumentField {
+            id: AttributeId::try_new("uid").unwrap(),
+            field_type: FieldType::Text,
+            constraints: HashSet::new(),
+            required: false,
+            unique: false,
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        });
+        document
+    }
+
+    #[test]
+    fn test_limit_and_offset_are_bound() {
+        let query = DocumentInstanceQuery::new()
+            .with_status(DocumentStatus::Draft)
+            .paginate(3, 20);
+        let (sql, _) =
+            query_find_document_by_criteria(&brands(), &query, &SortCollations::default());
+        assert!(sql.contains("LIMIT $1 OFFSET $2"), "{sql}");
+        assert!(!sql.contains("20"), "{sql}");
+    }
+
+    #[test]
+    fn test_empty_in_lists() {
+        let document = brands();
+        let find = |filter| {
+            let query = DocumentInstanceQuery::new()
+                .with_status(DocumentStatus::Draft)
+                .with_filter(filter);
+            query_find_document_by_criteria(&document, &query, &SortCollations::default()).0
+        };
+
+        let sql = find(FilterExpression::In {
+            field: "uid".to_string(),
+            values: Vec::new(),
+        });
+        assert!(sql.contains("WHERE FALSE"), "{sql}");
+
+        let sql = find(FilterExpression::NotIn {
+            field: "uid".to_string(),
+            values: Vec::new(),
+        });
+        assert!(sql.contains("WHERE TRUE"), "{sql}");
+
+        let sql = find(FilterExpression::In {
+            field: "uid".to_string(),
+            values: vec![DomainValue::Text("a".to_string())],
+        });
+        assert!(sql.contains(r#""m"."uid" = ANY($1)"#), "{sql}");
+
+        let sql = find(FilterExpression::NotIn {
+            field: "uid".to_string(),
+            values: vec![
+                DomainValue::Text("a".to_string()),
+                DomainValue::Text("b".to_string()),
+            ],
+        });
+        assert!(sql.contains(r#""m"."uid" <> ALL($1)"#), "{sql}");
+    }
+
+    #[test]
+    fn test_modified_drafts_are_not_published() {
+        let mut document = brands();
+        document.options = Some(luminair_common::entities::DocumentTypeOptions {
+            draft_and_publish: true,
+            localizations: Vec::new(),
+            workflow: None,
+            cache_control: None,
+            feed: None,
+            operations: None,
+            partitioning: None,
+            view: None,
+            retention: None,
+        });
+        let query = DocumentInstanceQuery::new()
+            .with_status(DocumentStatus::Draft)
+            .with_filter(FilterExpression::Modified);
+        let (sql, _) =
+            query_find_document_by_criteria(&document, &query, &SortCollations::default());
+        assert!(
+            sql.contains(
+                r#""m"."status" <> 'PUBLISHED' AND EXISTS (SELECT 1 FROM "brand_snapshots" "m_s""#
+            ),
+            "{sql}"
+        );
+    }
+
+    #[test]
+    fn test_random_order_and_sample() {
+        let document = brands();
+        let mut query = DocumentInstanceQuery::new().with_status(DocumentStatus::Draft);
+        query.random_order = Some(RandomOrder { seed: None });
+        let (sql, _) =
+            query_find_document_by_criteria(&document, &query, &SortCollations::default());
+        assert!(sql.ends_with("ORDER BY random() ASC"), "{sql}");
+
+        query.random_order = Some(RandomOrder { seed: Some(42) });
+        query.sample = Some(TableSample {
+            percent: 5.0,
+            seed: Some(42),
+        });
+        let (sql, _) =
+            query_find_document_by_criteria(&document, &query, &SortCollations::default());
+        assert!(
+            sql.contains(r#"TABLESAMPLE BERNOULLI (CAST($1 AS real)) REPEATABLE (CAST($2 AS double precision))"#),
+            "{sql}"
+        );
+        assert!(
+            sql.contains(r#"ORDER BY md5("m"."document_id"::text || $3)"#),
+            "{sql}"
+        );
+
+        let (sql, _) = query_count_documents(&document, &query);
+        assert!(
+            sql.contains(
+                r#"("m".tableoid, "m".ctid) IN (SELECT tableoid, ctid FROM "brand" TABLESAMPLE"#
+            ),
+            "{sql}"
+        );
+    }
+
+    #[test]
+    fn test_localized_sorts_use_the_collation_of_the_locale() {
+        let mut document = brands();
+        document.fields.insert(DocumentField {
+            id: AttributeId::try_new("title").unwrap(),
+            field_type: FieldType::LocalizedText,
+            constraints: HashSet::new(),
+            required: false,
+            unique: false,
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        });
+        let query = DocumentInstanceQuery::new()
+            .with_status(DocumentStatus::Draft)
+            .add_sort("title.ro".to_string(), SortDirection::Ascending)
+            .add_sort("uid".to_string(), SortDirection::Descending);
+
+        let collations = SortCollations::new(
+            &CollationSettings::default(),
+            ["ro".to_string()],
+            &HashSet::from(["ro-x-icu".to_string()]),
+        );
+        let (sql, _) = query_find_document_by_criteria(&document, &query, &collations);
+        assert!(
+            sql.contains(r#"ORDER BY ("m"."title" ->> $1) COLLATE "ro-x-icu" ASC, "m"."uid" DESC"#),
+            "{sql}"
+        );
+
+        let (sql, _) =
+            query_find_document_by_criteria(&document, &query, &SortCollations::default());
+        assert!(sql.contains(r#"ORDER BY "m"."title" ->> $1 ASC"#), "{sql}");
+    }
+}
@@ -0,0 +1,112 @@
+use luminair_common::persistence::IDEMPOTENCY_TABLE_NAME;
+use sea_query::{Alias, Cond, Expr, ExprTrait, OnConflict, PostgresQueryBuilder, Query};
+use sea_query_sqlx::{SqlxBinder, SqlxValues};
+use serde_json::Value;
+
+/// DELETE FROM luminair_idempotency
+/// WHERE created_at < CURRENT_TIMESTAMP - make_interval(secs => $1)
+///    OR (status IS NULL AND created_at < CURRENT_TIMESTAMP - make_interval(secs => $2))
+///
+/// The keys older than `ttl_seconds`, and the reservations of requests which
+/// never stored a response older than `abandoned_seconds`.
+pub fn delete_expired_idempotency_keys(
+    ttl_seconds: u64,
+    abandoned_seconds: u64,
+) -> (String, SqlxValues) {
+    let older_than = |seconds: u64| {
+        Expr::col(Alias::new("created_at")).lt(Expr::cust_with_values(
+            "CURRENT_TIMESTAMP - make_interval(secs => $1)",
+            [seconds as f64],
+        ))
+    };
+    Query::delete()
+        .from_table(Alias::new(IDEMPOTENCY_TABLE_NAME))
+        .cond_where(
+            Cond::any().add(older_than(ttl_seconds)).add(
+                Cond::all()
+                    .add(Expr::col(Alias::new("status")).is_null())
+                    .add(older_than(abandoned_seconds)),
+            ),
+        )
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// INSERT INTO luminair_idempotency (idempotency_key, request) VALUES ($1, $2)
+/// ON CONFLICT (idempotency_key) DO NOTHING
+///
+/// Reserves the key for `request`; no row is inserted when the key is taken.
+pub fn insert_idempotency_key(key: &str, request: &str) -> (String, SqlxValues) {
+    Query::insert()
+        .into_table(Alias::new(IDEMPOTENCY_TABLE_NAME))
+        .columns([Alias::new("idempotency_key"), Alias::new("request")])
+        .values_panic([key.into(), request.into()])
+        .on_conflict(
+            OnConflict::column(Alias::new("idempotency_key"))
+                .do_nothing()
+                .to_owned(),
+        )
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// SELECT request, status, headers, body FROM luminair_idempotency
+/// WHERE idempotency_key = $1
+pub fn query_idempotency_key(key: &str) -> (String, SqlxValues) {
+    Query::select()
+        .columns([
+            Alias::new("request"),
+            Alias::new("status"),
+            Alias::new("headers"),
+            Alias::new("body"),
+        ])
+        .from(Alias::new(IDEMPOTENCY_TABLE_NAME))
+        .and_where(Expr::col(Alias::new("idempotency_key")).eq(key))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// UPDATE luminair_idempotency SET status = $1, headers = $2, body = $3
+/// WHERE idempotency_key = $4
+pub fn complete_idempotency_key(
+    key: &str,
+    status: u16,
+    headers: Value,
+    body: String,
+) -> (String, SqlxValues) {
+    Query::update()
+        .table(Alias::new(IDEMPOTENCY_TABLE_NAME))
+        .value(Alias::new("status"), i32::from(status))
+        .value(Alias::new("headers"), headers)
+        .value(Alias::new("body"), body)
+        .and_where(Expr::col(Alias::new("idempotency_key")).eq(key))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// DELETE FROM luminair_idempotency WHERE idempotency_key = $1 AND status IS NULL
+///
+/// Releases a key whose request failed, so that it can be retried.
+pub fn release_idempotency_key(key: &str) -> (String, SqlxValues) {
+    Query::delete()
+        .from_table(Alias::new(IDEMPOTENCY_TABLE_NAME))
+        .and_where(Expr::col(Alias::new("idempotency_key")).eq(key))
+        .and_where(Expr::col(Alias::new("status")).is_null())
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_taken_keys_are_not_overwritten() {
+        let (sql, _) = insert_idempotency_key("key", "POST /documents/brands");
+
+        assert!(
+            sql.ends_with(r#"ON CONFLICT ("idempotency_key") DO NOTHING"#),
+            "{sql}"
+        );
+
+        let (sql, _) = delete_expired_idempotency_keys(3600, 300);
+        assert!(sql.contains("make_interval(secs => $1)"), "{sql}");
+        assert!(sql.contains(r#""status" IS NULL AND"#), "{sql}");
+        assert!(sql.contains("make_interval(secs => $2)"), "{sql}");
+    }
+}
@@ -0,0 +1,277 @@
+use chrono::{DateTime, Utc};
+use luminair_common::persistence::JOBS_TABLE_NAME;
+use sea_query::{
+    Alias, Cond, Expr, ExprTrait, LockBehavior, LockType, Order, PostgresQueryBuilder, Query,
+};
+use sea_query_sqlx::{SqlxBinder, SqlxValues};
+use serde_json::Value;
+
+/// Columns of a job, as the queries below answer it.
+const JOB_COLUMNS: [&str; 11] = [
+    "job_id",
+    "kind",
+    "payload",
+    "status",
+    "attempts",
+    "max_attempts",
+    "run_at",
+    "started_at",
+    "finished_at",
+    "last_error",
+    "created_at",
+];
+
+/// INSERT INTO luminair_jobs (kind, payload, max_attempts, run_at)
+/// VALUES ($1, $2, $3, $4) RETURNING ...
+///
+/// The job runs at `run_at`, or right away without one.
+pub fn insert_job(
+    kind: &str,
+    payload: Value,
+    max_attempts: i32,
+    run_at: Option<DateTime<Utc>>,
+) -> (String, SqlxValues) {
+    let run_at = match run_at {
+        Some(run_at) => Expr::val(run_at),
+        None => Expr::current_timestamp(),
+    };
+    Query::insert()
+        .into_table(Alias::new(JOBS_TABLE_NAME))
+        .columns([
+            Alias::new("kind"),
+            Alias::new("payload"),
+            Alias::new("max_attempts"),
+            Alias::new("run_at"),
+        ])
+        .values_panic([kind.into(), payload.into(), max_attempts.into(), run_at])
+        .returning(Query::returning().columns(JOB_COLUMNS.map(Alias::new)))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// UPDATE luminair_jobs SET status = 'running', attempts = attempts + 1,
+///   started_at = CURRENT_TIMESTAMP
+/// WHERE job_id IN (SELECT job_id FROM luminair_jobs
+///   WHERE (status = 'pending' AND run_at <= CURRENT_TIMESTAMP)
+///      OR (status = 'running' AND started_at < CURRENT_TIMESTAMP - make_interval(secs => $1)
+///          AND attempts < max_attempts)
+///   ORDER BY run_at LIMIT 1 FOR UPDATE SKIP LOCKED)
+/// RETURNING ...
+///
+/// Claims the next due job for a worker, or a running one whose worker
+/// hasn't finished it within `timeout_seconds`, so presumably stopped, while
+/// it has attempts left.
+pub fn claim_next_job(timeout_seconds: u64) -> (String, SqlxValues) {
+    let due = Query::select()
+        .column(Alias::new("job_id"))
+        .from(Alias::new(JOBS_TABLE_NAME))
+        .cond_where(
+            Cond::any()
+                .add(
+                    Cond::all()
+                        .add(Expr::col(Alias::new("status")).eq("pending"))
+                        .add(Expr::col(Alias::new("run_at")).lte(Expr::current_timestamp())),
+                )
+                .add(stale_jobs(timeout_seconds).add(
+                    Expr::col(Alias::new("attempts")).lt(Expr::col(Alias::new("max_attempts"))),
+                )),
+        )
+        .order_by(Alias::new("run_at"), Order::Asc)
+        .limit(1)
+        .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+        .to_owned();
+
+    Query::update()
+        .table(Alias::new(JOBS_TABLE_NAME))
+        .value(Alias::new("status"), "running")
+        .value(
+            Alias::new("attempts"),
+            Expr::col(Alias::new("attempts")).add(1),
+        )
+        .value(Alias::new("started_at"), Expr::current_timestamp())
+        .and_where(Expr::col(Alias::new("job_id")).in_subquery(due))
+        .returning(Query::returning().columns(JOB_COLUMNS.map(Alias::new)))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// UPDATE luminair_jobs SET status = 'failed', finished_at = CURRENT_TIMESTAMP,
+///   last_error = $1
+/// WHERE status = 'running' AND started_at < CURRENT_TIMESTAMP - make_interval(secs => $2)
+///   AND attempts >= max_attempts
+///
+/// Fails for good the running jobs whose worker hasn't finished their last
+/// attempt within `timeout_seconds`, which no worker claims again.
+pub fn fail_stale_jobs(timeout_seconds: u64) -> (String, SqlxValues) {
+    Query::update()
+        .table(Alias::new(JOBS_TABLE_NAME))
+        .value(Alias::new("status"), "failed")
+        .value(Alias::new("finished_at"), Expr::current_timestamp())
+        .value(
+            Alias::new("last_error"),
+            format!("the last attempt didn't finish within {timeout_seconds} seconds"),
+        )
+        .cond_where(
+            stale_jobs(timeout_seconds)
+                .add(Expr::col(Alias::new("attempts")).gte(Expr::col(Alias::new("max_attempts")))),
+        )
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// UPDATE luminair_jobs SET status = 'succeeded', finished_at = CURRENT_TIMESTAMP,
+///   last_error = NULL
+/// WHERE job_id = $1 AND status = 'running' AND started_at = $2
+///
+/// Only the run claimed at `started_at` completes the job: once another
+/// worker has claimed it again, the result of the first one is dropped.
+pub fn complete_job(job_id: i64, started_at: DateTime<Utc>) -> (String, SqlxValues) {
+    Query::update()
+        .table(Alias::new(JOBS_TABLE_NAME))
+        .value(Alias::new("status"), "succeeded")
+        .value(Alias::new("finished_at"), Expr::current_timestamp())
+        .value(Alias::new("last_error"), Option::<String>::None)
+        .cond_where(claimed_run(job_id, started_at))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// UPDATE luminair_jobs SET status = 'pending', run_at = $1, last_error = $2
+/// WHERE job_id = $3 AND status = 'running' AND started_at = $4
+///
+/// Or, without `retry_at`, SET status = 'failed', finished_at = CURRENT_TIMESTAMP.
+/// Only the run claimed at `started_at` fails the job, as with [`complete_job`].
+pub fn fail_job(
+    job_id: i64,
+    started_at: DateTime<Utc>,
+    error: String,
+    retry_at: Option<DateTime<Utc>>,
+) -> (String, SqlxValues) {
+    let mut update = Query::update();
+    update.table(Alias::new(JOBS_TABLE_NAME));
+    match retry_at {
+        Some(retry_at) => update
+            .value(Alias::new("status"), "pending")
+            .value(Alias::new("run_at"), retry_at),
+        None => update
+            .value(Alias::new("status"), "failed")
+            .value(Alias::new("finished_at"), Expr::current_timestamp()),
+    };
+    update
+        .value(Alias::new("last_error"), error)
+        .cond_where(claimed_run(job_id, started_at))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// SELECT ... FROM luminair_jobs [WHERE status = $1] ORDER BY job_id DESC LIMIT $2
+pub fn query_jobs(status: Option<&str>, limit: u64) -> (String, SqlxValues) {
+    let mut select = Query::select();
+    select
+        .columns(JOB_COLUMNS.map(Alias::new))
+        .from(Alias::new(JOBS_TABLE_NAME));
+    if let Some(status) = status {
+        select.and_where(Expr::col(Alias::new("status")).eq(status));
+    }
+    select
+        .order_by(Alias::new("job_id"), Order::Desc)
+        .limit(limit)
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// SELECT ... FROM luminair_jobs WHERE job_id = $1
+pub fn query_job(job_id: i64) -> (String, SqlxValues) {
+    Query::select()
+        .columns(JOB_COLUMNS.map(Alias::new))
+        .from(Alias::new(JOBS_TABLE_NAME))
+        .and_where(Expr::col(Alias::new("job_id")).eq(job_id))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// UPDATE luminair_jobs SET status = 'pending', attempts = 0,
+///   run_at = CURRENT_TIMESTAMP, started_at = NULL, finished_at = NULL
+/// WHERE job_id = $1 AND status IN ('failed', 'cancelled')
+pub fn retry_job(job_id: i64) -> (String, SqlxValues) {
+    Query::update()
+        .table(Alias::new(JOBS_TABLE_NAME))
+        .value(Alias::new("status"), "pending")
+        .value(Alias::new("attempts"), 0)
+        .value(Alias::new("run_at"), Expr::current_timestamp())
+        .value(Alias::new("started_at"), Option::<DateTime<Utc>>::None)
+        .value(Alias::new("finished_at"), Option::<DateTime<Utc>>::None)
+        .and_where(Expr::col(Alias::new("job_id")).eq(job_id))
+        .and_where(Expr::col(Alias::new("status")).is_in(["failed", "cancelled"]))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// UPDATE luminair_jobs SET status = 'cancelled', finished_at = CURRENT_TIMESTAMP
+/// WHERE job_id = $1 AND status = 'pending'
+pub fn cancel_job(job_id: i64) -> (String, SqlxValues) {
+    Query::update()
+        .table(Alias::new(JOBS_TABLE_NAME))
+        .value(Alias::new("status"), "cancelled")
+        .value(Alias::new("finished_at"), Expr::current_timestamp())
+        .and_where(Expr::col(Alias::new("job_id")).eq(job_id))
+        .and_where(Expr::col(Alias::new("status")).eq("pending"))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// status = 'running' AND started_at < CURRENT_TIMESTAMP - make_interval(secs => $1)
+fn stale_jobs(timeout_seconds: u64) -> Cond {
+    Cond::all()
+        .add(Expr::col(Alias::new("status")).eq("running"))
+        .add(
+            Expr::col(Alias::new("started_at")).lt(Expr::cust_with_values(
+                "CURRENT_TIMESTAMP - make_interval(secs => $1)",
+                [timeout_seconds as f64],
+            )),
+        )
+}
+
+/// job_id = $1 AND status = 'running' AND started_at = $2
+fn claimed_run(job_id: i64, started_at: DateTime<Utc>) -> Cond {
+    Cond::all()
+        .add(Expr::col(Alias::new("job_id")).eq(job_id))
+        .add(Expr::col(Alias::new("status")).eq("running"))
+        .add(Expr::col(Alias::new("started_at")).eq(started_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claimed_jobs_skip_those_of_other_workers() {
+        let (sql, _) = claim_next_job(300);
+
+        assert!(sql.starts_with(r#"UPDATE "luminair_jobs" SET"#), "{sql}");
+        assert!(sql.contains(r#""attempts" = "attempts" + "#), "{sql}");
+        assert!(sql.contains("make_interval(secs => "), "{sql}");
+        assert!(sql.contains(r#"ORDER BY "run_at" ASC LIMIT $"#), "{sql}");
+        assert!(sql.contains("FOR UPDATE SKIP LOCKED)"), "{sql}");
+        assert!(sql.contains(r#"RETURNING "job_id", "kind""#), "{sql}");
+        assert!(sql.contains(r#""attempts" < "max_attempts""#), "{sql}");
+    }
+
+    #[test]
+    fn test_stale_jobs_out_of_attempts_fail() {
+        let (sql, _) = fail_stale_jobs(300);
+
+        assert!(sql.contains("make_interval(secs => "), "{sql}");
+        assert!(sql.contains(r#""attempts" >= "max_attempts""#), "{sql}");
+    }
+
+    #[test]
+    fn test_only_the_claimed_run_finishes_a_job() {
+        let (sql, _) = complete_job(7, Utc::now());
+        assert!(sql.contains(r#"AND "status" = $"#), "{sql}");
+        assert!(sql.contains(r#"AND "started_at" = $"#), "{sql}");
+
+        let (sql, _) = fail_job(7, Utc::now(), "failed".to_string(), None);
+        assert!(sql.contains(r#"AND "started_at" = $"#), "{sql}");
+    }
+
+    #[test]
+    fn test_only_finished_jobs_are_retried() {
+        let (sql, _) = retry_job(7);
+        assert!(sql.contains(r#"AND "status" IN ("#), "{sql}");
+
+        let (sql, _) = cancel_job(7);
+        assert!(sql.contains(r#"AND "status" = "#), "{sql}");
+    }
+}
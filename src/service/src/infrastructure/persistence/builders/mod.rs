@@ -2,12 +2,24 @@ use crate::domain::query::DocumentStatus;
 use luminair_common::{
     CREATED_BY_FIELD_NAME, CREATED_FIELD_NAME, DOCUMENT_ID_FIELD_NAME, DocumentType,
     PUBLISHED_BY_FIELD_NAME, PUBLISHED_FIELD_NAME, REVISION_FIELD_NAME, SNAPSHOT_ID_FIELD_NAME,
-    UPDATED_BY_FIELD_NAME, UPDATED_FIELD_NAME,
+    UPDATED_BY_FIELD_NAME, UPDATED_FIELD_NAME, WORKFLOW_STAGE_FIELD_NAME,
 };
 use sea_query::ColumnRef;
 
+pub mod aggregate;
+pub mod archive;
 pub mod find;
+pub mod idempotency;
+pub mod jobs;
+pub mod orphans;
+pub mod outbox;
 pub mod relations;
+pub mod retention;
+pub mod revisions;
+pub mod schedule;
+pub mod search;
+pub mod subject;
+pub mod translation;
 pub mod write;
 
 const STANDARD_SELECT_COLUMNS: [(&str, &str); 8] = [
@@ -34,6 +46,12 @@ pub(crate) fn main_select_columns(
         columns.push(("m", SNAPSHOT_ID_FIELD_NAME).into());
     }
 
+    // the workflow stage is kept in main rows only
+    let reads_main_table = status == DocumentStatus::Draft || !document.has_draft_and_publish();
+    if document.workflow().is_some() && reads_main_table {
+        columns.push(("m", WORKFLOW_STAGE_FIELD_NAME).into());
+    }
+
     for field in &document.fields {
         columns.push(("m", field.id.normalized()).into());
     }
@@ -0,0 +1,177 @@
+use luminair_common::persistence::TableNameProviderConstructor;
+use luminair_common::{
+    DOCUMENT_ID_FIELD_NAME, OWNING_DOCUMENT_ID_FIELD_NAME, ResolvedRelation,
+    SNAPSHOT_ID_FIELD_NAME, TARGET_DOCUMENT_ID_FIELD_NAME,
+};
+use sea_query::{Alias, Cond, Condition, Expr, ExprTrait, PostgresQueryBuilder, Query};
+use sea_query_sqlx::{SqlxBinder, SqlxValues};
+
+/// SELECT COUNT(*) AS count FROM {relation_table} r WHERE <orphaned>
+///
+/// The links of `relation` to documents which don't exist, of the drafts, or
+/// of the published snapshots when `published`.
+pub fn query_count_orphaned_links(
+    relation: ResolvedRelation<'_>,
+    published: bool,
+) -> (String, SqlxValues) {
+    Query::select()
+        .expr_as(Expr::cust("COUNT(*)"), Alias::new("count"))
+        .from(relation.links_table(published))
+        .cond_where(orphaned_condition(relation, published))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// DELETE FROM {relation_table} r WHERE ctid IN
+///   (SELECT r.ctid FROM {relation_table} r WHERE <orphaned> LIMIT {batch_size})
+///
+/// Removes one batch of the links [`query_count_orphaned_links`] counts.
+pub fn delete_orphaned_links(
+    relation: ResolvedRelation<'_>,
+    published: bool,
+    batch_size: u64,
+) -> (String, SqlxValues) {
+    let batch = Query::select()
+        .column(("r", "ctid"))
+        .from(relation.links_table(published))
+        .cond_where(orphaned_condition(relation, published))
+        .limit(batch_size)
+        .to_owned();
+
+    Query::delete()
+        .from_table(relation.links_table(published))
+        .and_where(Expr::col(("r", "ctid")).in_subquery(batch))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// NOT EXISTS (SELECT 1 FROM {owner} o WHERE o.document_id = r.owning_document_id)
+/// OR NOT EXISTS (SELECT 1 FROM {target} t WHERE t.document_id = r.target_document_id)
+/// [OR NOT EXISTS (SELECT 1 FROM {owner}_snapshots s WHERE s.snapshot_id = r.snapshot_id)]
+///
+/// Foreign keys keep these from being written, so only edits of the database
+/// bypassing them leave such links behind.
+fn orphaned_condition(relation: ResolvedRelation<'_>, published: bool) -> Condition {
+    let missing = |table, alias: &str, column: &'static str, link_column: &'static str| {
+        let mut exists = Query::select();
+        exists
+            .expr(Expr::cust("1"))
+            .from(table)
+            .and_where(Expr::col((alias.to_owned(), column)).equals(("r", link_column)));
+        Expr::exists(exists).not()
+    };
+
+    let mut condition = Cond::any()
+        .add(missing(
+            relation.owner.main_table().with_alias("o"),
+            "o",
+            DOCUMENT_ID_FIELD_NAME,
+            OWNING_DOCUMENT_ID_FIELD_NAME,
+        ))
+        .add(missing(
+            relation.target.main_table().with_alias("t"),
+            "t",
+            DOCUMENT_ID_FIELD_NAME,
+            TARGET_DOCUMENT_ID_FIELD_NAME,
+        ));
+    if published && relation.owner.has_draft_and_publish() {
+        condition = condition.add(missing(
+            relation.owner.snapshot_table().with_alias("s"),
+            "s",
+            SNAPSHOT_ID_FIELD_NAME,
+            SNAPSHOT_ID_FIELD_NAME,
+        ));
+    }
+    condition
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use luminair_common::entities::{
+        DocumentRelation, DocumentType, DocumentTypeOptions, OnDeleteAction, RelationType,
+    };
+    use luminair_common::{AttributeId, DocumentTypeId};
+
+    fn partner(draft_and_publish: bool) -> DocumentType {
+        let mut partner =
+            DocumentType::new_bare_collection("partner", "partner", "partners").unwrap();
+        partner.options = Some(DocumentTypeOptions {
+            draft_and_publish,
+            localizations: Vec::new(),
+            workflow: None,
+            cache_control: None,
+            feed: None,
+            operations: None,
+            partitioning: None,
+            view: None,
+            retention: None,
+        });
+        partner
+    }
+
+    fn brands() -> DocumentRelation {
+        DocumentRelation {
+            id: AttributeId::try_new("brands").unwrap(),
+            relation_type: RelationType::HasMany,
+            target: DocumentTypeId::try_new("brand").unwrap(),
+            on_delete: OnDeleteAction::Cascade,
+            renamed_from: None,
+            api_name: None,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_orphaned_links_check_owner_and_target() {
+        let (owner, relation) = (partner(false), brands());
+        let target = DocumentType::new_bare_collection("brand", "brand", "brands").unwrap();
+        let relation = ResolvedRelation {
+            owner: &owner,
+            relation: &relation,
+            target: &target,
+        };
+
+        let (sql, _) = query_count_orphaned_links(relation, false);
+        assert!(
+            sql.contains(r#"FROM "partner_brands_relation" AS "r""#),
+            "{sql}"
+        );
+        assert!(
+            sql.contains(r#""o"."document_id" = "r"."owning_document_id""#),
+            "{sql}"
+        );
+        assert!(
+            sql.contains(r#""t"."document_id" = "r"."target_document_id""#),
+            "{sql}"
+        );
+        assert!(!sql.contains("snapshot_id"), "{sql}");
+
+        let (sql, _) = delete_orphaned_links(relation, false, 500);
+        assert!(
+            sql.starts_with(r#"DELETE FROM "partner_brands_relation""#),
+            "{sql}"
+        );
+        assert!(sql.contains(r#""ctid" IN (SELECT "r"."ctid""#), "{sql}");
+        assert!(sql.contains("LIMIT"), "{sql}");
+    }
+
+    #[test]
+    fn test_orphaned_snapshot_links_check_the_snapshot() {
+        let (owner, relation) = (partner(true), brands());
+        let target = DocumentType::new_bare_collection("brand", "brand", "brands").unwrap();
+        let relation = ResolvedRelation {
+            owner: &owner,
+            relation: &relation,
+            target: &target,
+        };
+
+        let (sql, _) = query_count_orphaned_links(relation, true);
+        assert!(
+            sql.contains(r#"FROM "partner_brands_relation_snapshots" AS "r""#),
+            "{sql}"
+        );
+        assert!(
+            sql.contains(r#""s"."snapshot_id" = "r"."snapshot_id""#),
+            "{sql}"
+        );
+    }
+}
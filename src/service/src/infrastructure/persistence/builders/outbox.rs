@@ -0,0 +1,116 @@
+use crate::domain::events::ContentAction;
+use luminair_common::persistence::{OUTBOX_TABLE_NAME, TableNameProviderConstructor};
+use luminair_common::{DOCUMENT_ID_FIELD_NAME, DocumentType};
+use sea_query::{Alias, Expr, ExprTrait, LockType, Order, PostgresQueryBuilder, Query};
+use sea_query_sqlx::{SqlxBinder, SqlxValues};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// SELECT to_jsonb(m) AS document FROM {table} m WHERE m.document_id = $1
+///
+/// The whole main row of the document as one JSON object keyed by column name.
+pub fn query_document_row(document: &DocumentType, id: Uuid) -> (String, SqlxValues) {
+    Query::select()
+        .expr_as(Expr::cust("to_jsonb(m)"), Alias::new("document"))
+        .from(document.main_table())
+        .and_where(Expr::col(("m", DOCUMENT_ID_FIELD_NAME)).eq(id))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// INSERT INTO luminair_outbox (document_type, document_id, action, actor, diff)
+/// VALUES ($1, $2, $3, $4, $5)
+pub fn insert_outbox_event(
+    document: &DocumentType,
+    id: Uuid,
+    action: ContentAction,
+    actor: Option<String>,
+    diff: Value,
+) -> (String, SqlxValues) {
+    Query::insert()
+        .into_table(Alias::new(OUTBOX_TABLE_NAME))
+        .columns([
+            Alias::new("document_type"),
+            Alias::new(DOCUMENT_ID_FIELD_NAME),
+            Alias::new("action"),
+            Alias::new("actor"),
+            Alias::new("diff"),
+        ])
+        .values_panic([
+            document.id.to_string().into(),
+            id.into(),
+            action.as_str().into(),
+            actor.into(),
+            diff.into(),
+        ])
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// SELECT ... FROM luminair_outbox WHERE published_at IS NULL
+/// ORDER BY event_id LIMIT $1 FOR UPDATE
+pub fn query_pending_events(limit: u64) -> (String, SqlxValues) {
+    Query::select()
+        .columns([
+            Alias::new("event_id"),
+            Alias::new("document_type"),
+            Alias::new(DOCUMENT_ID_FIELD_NAME),
+            Alias::new("action"),
+            Alias::new("actor"),
+            Alias::new("occurred_at"),
+            Alias::new("diff"),
+        ])
+        .from(Alias::new(OUTBOX_TABLE_NAME))
+        .and_where(Expr::col(Alias::new("published_at")).is_null())
+        .order_by(Alias::new("event_id"), Order::Asc)
+        .limit(limit)
+        .lock(LockType::Update)
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// SELECT COUNT(*) FROM luminair_outbox WHERE published_at IS NULL
+pub fn count_pending_events() -> (String, SqlxValues) {
+    Query::select()
+        .expr(Expr::cust("COUNT(*)"))
+        .from(Alias::new(OUTBOX_TABLE_NAME))
+        .and_where(Expr::col(Alias::new("published_at")).is_null())
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// UPDATE luminair_outbox SET published_at = CURRENT_TIMESTAMP WHERE event_id IN (...)
+pub fn mark_events_published(event_ids: Vec<i64>) -> (String, SqlxValues) {
+    Query::update()
+        .table(Alias::new(OUTBOX_TABLE_NAME))
+        .value(Alias::new("published_at"), Expr::current_timestamp())
+        .and_where(Expr::col(Alias::new("event_id")).is_in(event_ids))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// DELETE FROM luminair_outbox
+/// WHERE published_at < CURRENT_TIMESTAMP - make_interval(secs => $1)
+pub fn delete_published_events(retention_seconds: u64) -> (String, SqlxValues) {
+    Query::delete()
+        .from_table(Alias::new(OUTBOX_TABLE_NAME))
+        .and_where(
+            Expr::col(Alias::new("published_at")).lt(Expr::cust_with_values(
+                "CURRENT_TIMESTAMP - make_interval(secs => $1)",
+                [retention_seconds as f64],
+            )),
+        )
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_events_are_locked_in_order() {
+        let (sql, _) = query_pending_events(100);
+
+        assert!(
+            sql.ends_with(
+                r#"WHERE "published_at" IS NULL ORDER BY "event_id" ASC LIMIT $1 FOR UPDATE"#
+            ),
+            "{sql}"
+        );
+    }
+}
@@ -1,13 +1,19 @@
-use crate::domain::query::DocumentStatus;
+use crate::domain::query::{DocumentInstanceQuery, DocumentStatus, PopulateOptions, SortDirection};
+use crate::infrastructure::persistence::builders::find::{
+    modified_sql, random_order_expr, select_documents_by_criteria, sort_column_expr,
+};
 use crate::infrastructure::persistence::builders::main_select_columns;
+use crate::infrastructure::persistence::collations::SortCollations;
 use luminair_common::persistence::TableNameProviderConstructor;
 use luminair_common::{
     AttributeId, DOCUMENT_ID_FIELD_NAME, DocumentType, OWNING_DOCUMENT_ID_FIELD_NAME,
-    SNAPSHOT_ID_FIELD_NAME, STATUS_FIELD_NAME, TARGET_DOCUMENT_ID_FIELD_NAME, VERSION_FIELD_NAME,
+    ResolvedRelation, SNAPSHOT_ID_FIELD_NAME, STATUS_FIELD_NAME, TARGET_DOCUMENT_ID_FIELD_NAME,
+    VERSION_FIELD_NAME,
 };
 use sea_query::extension::postgres::PgExpr;
 use sea_query::{
-    Alias, ColumnRef, DynIden, Expr, ExprTrait, JoinType, Order, PostgresQueryBuilder, Query,
+    Alias, Asterisk, ColumnRef, DynIden, Expr, ExprTrait, JoinType, Order, PostgresQueryBuilder,
+    Query, UnionType,
 };
 use sea_query_sqlx::{SqlxBinder, SqlxValues};
 use uuid::Uuid;
@@ -29,28 +35,29 @@ use uuid::Uuid;
  * WHERE r.owning_document_id = ANY($1)
  * ORDER BY r.owning_document_id
  *
+ * The related documents of each owning document are ordered by the `sort`
+ * of `options` after the owning document id. With a `limit` they are ranked
+ * per owning document and the first ones kept:
+ *
+ * SELECT * FROM (
+ *     SELECT ..., ROW_NUMBER() OVER (PARTITION BY r.owning_document_id ORDER BY m.name) AS _rank
+ *     FROM ...
+ * ) p
+ * WHERE p._rank <= $2
+ * ORDER BY p.owning_document_id, p._rank
  */
 pub fn query_find_related_documents(
-    main_document: &DocumentType,
-    related_document: &DocumentType,
-    relation_attr: &AttributeId,
+    relation: ResolvedRelation<'_>,
     filter: &crate::domain::query::FilterExpression,
+    options: &PopulateOptions,
     status: DocumentStatus,
     params: Vec<Uuid>,
+    collations: &SortCollations,
 ) -> (String, SqlxValues) {
-    let related_table =
-        if status == DocumentStatus::Published && related_document.has_draft_and_publish() {
-            related_document.snapshot_table()
-        } else {
-            related_document.main_table()
-        };
-
-    let relation_table =
-        if status == DocumentStatus::Published && main_document.has_draft_and_publish() {
-            main_document.relation_snapshot_table(relation_attr)
-        } else {
-            main_document.relation_table(relation_attr)
-        };
+    let related_document = relation.target;
+    let published = status == DocumentStatus::Published;
+    let related_table = relation.target_table(published);
+    let relation_table = relation.links_table(published);
 
     let owning_document_id_column = ("r", OWNING_DOCUMENT_ID_FIELD_NAME);
 
@@ -67,8 +74,7 @@ pub fn query_find_related_documents(
             ColumnRef::from(("m", DOCUMENT_ID_FIELD_NAME))
                 .equals(ColumnRef::from(("r", TARGET_DOCUMENT_ID_FIELD_NAME))),
         )
-        .and_where(Expr::col(owning_document_id_column).eq_any(params))
-        .order_by(owning_document_id_column, Order::Asc);
+        .and_where(Expr::col(owning_document_id_column).eq_any(params));
 
     let (status_expr, version_expr) =
         if status == DocumentStatus::Published && related_document.has_draft_and_publish() {
@@ -82,15 +88,258 @@ pub fn query_find_related_documents(
 
     select.expr_as(status_expr, Alias::new("status"));
     select.expr_as(version_expr, Alias::new("version"));
+    if status == DocumentStatus::Draft && related_document.has_draft_and_publish() {
+        select.expr_as(
+            Expr::cust(modified_sql(related_document, "m")),
+            Alias::new("modified"),
+        );
+    }
 
     if let Some(condition) = crate::infrastructure::persistence::builders::find::build_condition(
         filter,
         related_document,
         "m",
+        status,
     ) {
         select.cond_where(condition);
     }
 
+    let Some(limit) = options.limit else {
+        select.order_by(owning_document_id_column, Order::Asc);
+        for sort in &options.sort {
+            let order = match sort.direction {
+                SortDirection::Ascending => Order::Asc,
+                SortDirection::Descending => Order::Desc,
+            };
+            select.order_by_expr(
+                sort_column_expr(&sort.field, related_document, "m", collations),
+                order,
+            );
+        }
+        return select.build_sqlx(PostgresQueryBuilder);
+    };
+
+    let mut window = String::from("ROW_NUMBER() OVER (PARTITION BY $1");
+    let mut window_exprs = vec![Expr::col(owning_document_id_column)];
+    for (index, sort) in options.sort.iter().enumerate() {
+        window.push_str(if index == 0 { " ORDER BY " } else { ", " });
+        window.push_str(&format!("${}", index + 2));
+        if sort.direction == SortDirection::Descending {
+            window.push_str(" DESC");
+        }
+        window_exprs.push(sort_column_expr(
+            &sort.field,
+            related_document,
+            "m",
+            collations,
+        ));
+    }
+    window.push(')');
+
+    let ranked = Alias::new("p");
+    let rank = Alias::new("_rank");
+    select.expr_as(Expr::cust_with_exprs(window, window_exprs), rank.clone());
+    Query::select()
+        .column(Asterisk)
+        .from_subquery(select, ranked.clone())
+        .and_where(Expr::col((ranked.clone(), rank.clone())).lte(limit))
+        .order_by(
+            (ranked.clone(), Alias::new(OWNING_DOCUMENT_ID_FIELD_NAME)),
+            Order::Asc,
+        )
+        .order_by((ranked, rank), Order::Asc)
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/**
+ * SELECT 'brands' AS attribute, r.owning_document_id, COUNT(*) AS count
+ * FROM partner_brands_relation r
+ * WHERE r.owning_document_id = ANY($1)
+ * GROUP BY r.owning_document_id
+ * UNION ALL
+ * SELECT 'tags' AS attribute, ...
+ *
+ * The links of the published snapshots are counted when `published`, like
+ * [`query_find_related_documents`] populates them. `None` without relations.
+ */
+pub fn query_count_related_documents(
+    relations: &[ResolvedRelation<'_>],
+    published: bool,
+    params: &[Uuid],
+) -> Option<(String, SqlxValues)> {
+    let owning_document_id_column = ("r", OWNING_DOCUMENT_ID_FIELD_NAME);
+    let mut selects = relations.iter().map(|relation| {
+        Query::select()
+            .expr_as(
+                Expr::val(relation.id().to_string()),
+                Alias::new("attribute"),
+            )
+            .column(owning_document_id_column)
+            .expr_as(Expr::cust("COUNT(*)"), Alias::new("count"))
+            .from(relation.links_table(published))
+            .and_where(Expr::col(owning_document_id_column).eq_any(params.to_vec()))
+            .group_by_col(owning_document_id_column)
+            .to_owned()
+    });
+    let mut select = selects.next()?;
+    for next in selects {
+        select.union(UnionType::All, next);
+    }
+    Some(select.build_sqlx(PostgresQueryBuilder))
+}
+
+/// A relation populated by [`query_find_documents_with_relations`].
+pub struct PopulatedRelation<'a> {
+    pub relation: ResolvedRelation<'a>,
+    pub filter: &'a crate::domain::query::FilterExpression,
+    /// sort and limit of the related documents of each document
+    pub options: &'a PopulateOptions,
+}
+
+/// Name of the column with the JSON array of the related documents of the
+/// `index`-th populated relation.
+pub fn populated_relation_column(index: usize) -> String {
+    format!("_populated_{}", index)
+}
+
+/**
+ * SELECT p.*, l0.documents AS _populated_0, ...
+ * FROM (SELECT ... FROM articles m WHERE ... ORDER BY ... LIMIT ... OFFSET ...) p
+ * LEFT JOIN LATERAL (
+ *     SELECT COALESCE(json_agg(to_jsonb(m)), '[]'::json) AS documents
+ *     FROM article_categories_relation r
+ *     JOIN categories m ON m.document_id = r.target_document_id
+ *     WHERE r.owning_document_id = p.document_id
+ * ) l0 ON TRUE
+ * ORDER BY ...
+ *
+ * With status == DocumentStatus::Published the snapshot tables are used as in
+ * query_find_related_documents, and the related rows get
+ * `|| jsonb_build_object('status', 'PUBLISHED', 'version', 0)`; drafts of
+ * draft-and-publish types get whether they're `modified` the same way.
+ *
+ * Related documents with a sort or limit are aggregated from a subquery:
+ *
+ * SELECT COALESCE(json_agg(d.document), '[]'::json) AS documents
+ * FROM (SELECT to_jsonb(m) AS document FROM ... ORDER BY m.name LIMIT 5) d
+ */
+pub fn query_find_documents_with_relations(
+    main_document: &DocumentType,
+    query: &DocumentInstanceQuery,
+    relations: &[PopulatedRelation<'_>],
+    collations: &SortCollations,
+) -> (String, SqlxValues) {
+    let status = query.status;
+    let documents = Alias::new("p");
+
+    let mut select = Query::select();
+    select.column((documents.clone(), Asterisk)).from_subquery(
+        select_documents_by_criteria(main_document, query, collations),
+        documents.clone(),
+    );
+
+    for (index, relation) in relations.iter().enumerate() {
+        let lateral = Alias::new(format!("l{}", index));
+        let published = status == DocumentStatus::Published;
+        let target = relation.relation.target;
+        let related_published = published && target.has_draft_and_publish();
+
+        let related_table = relation.relation.target_table(published);
+        let relation_table = relation.relation.links_table(published);
+
+        let document_json = if related_published {
+            "to_jsonb(m) || jsonb_build_object('status', 'PUBLISHED', 'version', 0)".to_string()
+        } else if !published && target.has_draft_and_publish() {
+            format!(
+                "to_jsonb(m) || jsonb_build_object('modified', {})",
+                modified_sql(target, "m")
+            )
+        } else {
+            "to_jsonb(m)".to_string()
+        };
+
+        let mut related = Query::select();
+        related
+            .from(relation_table)
+            .join(
+                JoinType::InnerJoin,
+                related_table,
+                ColumnRef::from(("m", DOCUMENT_ID_FIELD_NAME))
+                    .equals(ColumnRef::from(("r", TARGET_DOCUMENT_ID_FIELD_NAME))),
+            )
+            .and_where(
+                ColumnRef::from(("r", OWNING_DOCUMENT_ID_FIELD_NAME)).equals(ColumnRef::from((
+                    documents.clone(),
+                    Alias::new(DOCUMENT_ID_FIELD_NAME),
+                ))),
+            );
+
+        if let Some(condition) = crate::infrastructure::persistence::builders::find::build_condition(
+            relation.filter,
+            target,
+            "m",
+            status,
+        ) {
+            related.cond_where(condition);
+        }
+
+        let options = relation.options;
+        if options.is_ordered() {
+            // aggregate the sorted, limited related documents of a subquery
+            related.expr_as(Expr::cust(document_json), Alias::new("document"));
+            for sort in &options.sort {
+                let order = match sort.direction {
+                    SortDirection::Ascending => Order::Asc,
+                    SortDirection::Descending => Order::Desc,
+                };
+                related.order_by_expr(
+                    sort_column_expr(&sort.field, target, "m", collations),
+                    order,
+                );
+            }
+            if let Some(limit) = options.limit {
+                related.limit(limit);
+            }
+            related = Query::select()
+                .expr_as(
+                    Expr::cust("COALESCE(json_agg(d.document), '[]'::json)"),
+                    Alias::new("documents"),
+                )
+                .from_subquery(related, Alias::new("d"))
+                .to_owned();
+        } else {
+            related.expr_as(
+                Expr::cust(format!("COALESCE(json_agg({}), '[]'::json)", document_json)),
+                Alias::new("documents"),
+            );
+        }
+
+        select
+            .join_lateral(
+                JoinType::LeftJoin,
+                related,
+                lateral.clone(),
+                Expr::cust("TRUE"),
+            )
+            .expr_as(
+                Expr::col((lateral, Alias::new("documents"))),
+                Alias::new(populated_relation_column(index)),
+            );
+    }
+
+    // the order of the subquery isn't kept by the joins
+    for sort in &query.sort {
+        let col = sort_column_expr(&sort.field, main_document, "p", collations);
+        let order = match sort.direction {
+            SortDirection::Ascending => Order::Asc,
+            SortDirection::Descending => Order::Desc,
+        };
+        select.order_by_expr(col, order);
+    }
+    if let Some(random_order) = query.random_order {
+        select.order_by_expr(random_order_expr(random_order, "p"), Order::Asc);
+    }
+
     select.build_sqlx(PostgresQueryBuilder)
 }
 
@@ -141,6 +390,71 @@ pub fn delete_relation_entry(
         .build_sqlx(PostgresQueryBuilder)
 }
 
+/// DELETE FROM {relation_table} WHERE owning_document_id = $1
+pub fn delete_all_relation_entries(
+    document: &DocumentType,
+    relation_attr: &AttributeId,
+    owning_document_id: Uuid,
+) -> (String, SqlxValues) {
+    let relation_table = document.relation_table(relation_attr);
+    let owning_id_column = Expr::col(("r", OWNING_DOCUMENT_ID_FIELD_NAME));
+
+    Query::delete()
+        .from_table(relation_table)
+        .and_where(owning_id_column.eq(owning_document_id))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// SELECT DISTINCT owning_document_id FROM {relation_table} WHERE target_document_id = $1
+///
+/// The documents linking to `target_document_id` by `relation`, by the links
+/// of their drafts, or of their published snapshots when `published`.
+pub fn query_linking_document_ids(
+    relation: ResolvedRelation<'_>,
+    published: bool,
+    target_document_id: Uuid,
+) -> (String, SqlxValues) {
+    Query::select()
+        .distinct()
+        .column(("r", OWNING_DOCUMENT_ID_FIELD_NAME))
+        .from(relation.links_table(published))
+        .and_where(Expr::col(("r", TARGET_DOCUMENT_ID_FIELD_NAME)).eq(target_document_id))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// DELETE FROM {relation_table} WHERE target_document_id = $1
+///
+/// Removes the links to `target_document_id` by `relation`, of the drafts,
+/// or of the published snapshots when `published`.
+pub fn delete_links_to_document(
+    relation: ResolvedRelation<'_>,
+    published: bool,
+    target_document_id: Uuid,
+) -> (String, SqlxValues) {
+    Query::delete()
+        .from_table(relation.links_table(published))
+        .and_where(Expr::col(("r", TARGET_DOCUMENT_ID_FIELD_NAME)).eq(target_document_id))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// SELECT document_id FROM {target_table} WHERE document_id = ANY($1)
+///
+/// Used to validate relation targets before linking them. The ids are bound
+/// as a single `uuid[]` parameter, so the statement text doesn't depend on
+/// how many targets are checked.
+pub fn query_existing_document_ids(
+    target_document: &DocumentType,
+    document_ids: Vec<Uuid>,
+) -> (String, SqlxValues) {
+    let target_table = target_document.main_table();
+
+    Query::select()
+        .column(("m", DOCUMENT_ID_FIELD_NAME))
+        .from(target_table)
+        .and_where(Expr::col(("m", DOCUMENT_ID_FIELD_NAME)).eq_any(document_ids))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
 /// SELECT target_document_id FROM {relation_snapshot_table} WHERE owning_document_id = $1
 pub fn query_snapshot_relation_target_ids(
     main_document: &DocumentType,
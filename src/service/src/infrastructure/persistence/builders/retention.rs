@@ -0,0 +1,158 @@
+use chrono::{DateTime, Utc};
+use luminair_common::persistence::TableNameProviderConstructor;
+use luminair_common::{
+    CREATED_BY_FIELD_NAME, CREATED_FIELD_NAME, DOCUMENT_ID_FIELD_NAME, DocumentType,
+    LOCALES_UPDATED_FIELD_NAME, PUBLISH_AT_FIELD_NAME, PUBLISHED_BY_FIELD_NAME,
+    PUBLISHED_FIELD_NAME, REVISION_FIELD_NAME, STATUS_FIELD_NAME, UNPUBLISH_AT_FIELD_NAME,
+    UPDATED_BY_FIELD_NAME, UPDATED_FIELD_NAME, VERSION_FIELD_NAME, WORKFLOW_STAGE_FIELD_NAME,
+};
+use sea_query::{Alias, Expr, ExprTrait, PostgresQueryBuilder, Query, Values};
+use sea_query_sqlx::{SqlxBinder, SqlxValues};
+
+/// SELECT COUNT(*) AS count FROM {table} m WHERE m.created_at < $1
+///
+/// The documents of `document` created before `before`, which its retention
+/// removes.
+pub fn query_count_expired(document: &DocumentType, before: DateTime<Utc>) -> (String, SqlxValues) {
+    Query::select()
+        .expr_as(Expr::cust("COUNT(*)"), Alias::new("count"))
+        .from(document.main_table())
+        .and_where(Expr::col(("m", CREATED_FIELD_NAME)).lt(before))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// WITH expired AS (<batch>)
+/// DELETE FROM {table} m USING expired e WHERE m.document_id = e.document_id
+/// RETURNING m.document_id
+///
+/// Removes one batch of the documents [`query_count_expired`] counts. The
+/// snapshots and revisions of a partitioned type are removed with them, the
+/// others are by their foreign keys.
+pub fn delete_expired(
+    document: &DocumentType,
+    before: DateTime<Utc>,
+    batch_size: u64,
+) -> (String, SqlxValues) {
+    let table = document.main_table().table_name();
+    let sql = format!(
+        r#"{expired} DELETE FROM "{table}" m USING expired e WHERE m."{id}" = e."{id}" RETURNING m."{id}""#,
+        expired = expired_cte(document, batch_size),
+        id = DOCUMENT_ID_FIELD_NAME,
+    );
+    (sql, SqlxValues(Values(vec![before.into()])))
+}
+
+/// WITH expired AS (<batch>),
+///   moved AS (DELETE FROM {table} m USING expired e WHERE ... RETURNING m.*)
+/// INSERT INTO {table}_archived (<columns>) SELECT <columns> FROM moved
+/// RETURNING document_id
+///
+/// Moves one batch of the documents [`query_count_expired`] counts to the
+/// archived table; their snapshots, revisions and links are removed like
+/// by [`delete_expired`].
+pub fn archive_expired(
+    document: &DocumentType,
+    before: DateTime<Utc>,
+    batch_size: u64,
+) -> (String, SqlxValues) {
+    let table = document.main_table().table_name();
+    let archived_table = document.archived_table().table_name();
+    let columns = archived_columns(document)
+        .iter()
+        .map(|column| format!(r#""{column}""#))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let sql = format!(
+        r#"{expired}, moved AS (DELETE FROM "{table}" m USING expired e WHERE m."{id}" = e."{id}" RETURNING m.*) INSERT INTO "{archived_table}" ({columns}) SELECT {columns} FROM moved RETURNING "{id}""#,
+        expired = expired_cte(document, batch_size),
+        id = DOCUMENT_ID_FIELD_NAME,
+    );
+    (sql, SqlxValues(Values(vec![before.into()])))
+}
+
+/// WITH expired AS (SELECT document_id FROM {table} WHERE created_at < $1
+///   ORDER BY created_at LIMIT {batch_size} FOR UPDATE SKIP LOCKED)
+/// [, revisions AS (DELETE FROM {table}_revisions ...), snapshots AS (...)]
+///
+/// Replicas running the retention at the same time skip the documents
+/// another one is removing.
+fn expired_cte(document: &DocumentType, batch_size: u64) -> String {
+    let mut sql = format!(
+        r#"WITH expired AS (SELECT "{id}" FROM "{table}" WHERE "{created}" < $1 ORDER BY "{created}" LIMIT {batch_size} FOR UPDATE SKIP LOCKED)"#,
+        id = DOCUMENT_ID_FIELD_NAME,
+        table = document.main_table().table_name(),
+        created = CREATED_FIELD_NAME,
+    );
+    // the tables of a partitioned type have no foreign keys to cascade by
+    if document.partitioning().is_some() {
+        let mut tables = vec![("revisions", document.revision_table().table_name())];
+        if document.has_draft_and_publish() {
+            tables.push(("snapshots", document.snapshot_table().table_name()));
+        }
+        for (name, table) in tables {
+            sql.push_str(&format!(
+                r#", {name} AS (DELETE FROM "{table}" t USING expired e WHERE t."{id}" = e."{id}")"#,
+                id = DOCUMENT_ID_FIELD_NAME,
+            ));
+        }
+    }
+    sql
+}
+
+/// The columns of the main table the archived table has too.
+fn archived_columns(document: &DocumentType) -> Vec<String> {
+    let mut columns = [
+        DOCUMENT_ID_FIELD_NAME,
+        STATUS_FIELD_NAME,
+        VERSION_FIELD_NAME,
+        CREATED_FIELD_NAME,
+        UPDATED_FIELD_NAME,
+        CREATED_BY_FIELD_NAME,
+        UPDATED_BY_FIELD_NAME,
+        REVISION_FIELD_NAME,
+        PUBLISHED_FIELD_NAME,
+        PUBLISHED_BY_FIELD_NAME,
+    ]
+    .map(String::from)
+    .to_vec();
+    if document.has_draft_and_publish() {
+        columns.push(PUBLISH_AT_FIELD_NAME.to_string());
+        columns.push(UNPUBLISH_AT_FIELD_NAME.to_string());
+    }
+    if document.workflow().is_some() {
+        columns.push(WORKFLOW_STAGE_FIELD_NAME.to_string());
+    }
+    if document.has_localization() {
+        columns.push(LOCALES_UPDATED_FIELD_NAME.to_string());
+    }
+    columns.extend(document.fields.iter().map(|field| field.id.normalized()));
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expired_documents_are_removed_in_batches() {
+        let document = DocumentType::new_bare_collection("log", "log", "logs").unwrap();
+        let (sql, _) = query_count_expired(&document, Utc::now());
+        assert!(sql.ends_with(r#"WHERE "m"."created_at" < $1"#), "{sql}");
+
+        let (sql, _) = delete_expired(&document, Utc::now(), 500);
+        assert_eq!(
+            sql,
+            r#"WITH expired AS (SELECT "document_id" FROM "log" WHERE "created_at" < $1 ORDER BY "created_at" LIMIT 500 FOR UPDATE SKIP LOCKED) DELETE FROM "log" m USING expired e WHERE m."document_id" = e."document_id" RETURNING m."document_id""#
+        );
+
+        let (sql, _) = archive_expired(&document, Utc::now(), 500);
+        assert!(
+            sql.contains(r#"INSERT INTO "log_archived" ("document_id", "status", "version""#),
+            "{sql}"
+        );
+        assert!(
+            sql.ends_with(r#"FROM moved RETURNING "document_id""#),
+            "{sql}"
+        );
+    }
+}
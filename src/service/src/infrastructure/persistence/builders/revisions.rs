@@ -0,0 +1,121 @@
+use luminair_common::persistence::TableNameProviderConstructor;
+use luminair_common::{
+    CREATED_BY_FIELD_NAME, CREATED_FIELD_NAME, DOCUMENT_ID_FIELD_NAME, DocumentType,
+    PUBLISHED_BY_FIELD_NAME, PUBLISHED_FIELD_NAME, REVISION_FIELD_NAME, STATUS_FIELD_NAME,
+    UPDATED_BY_FIELD_NAME, UPDATED_FIELD_NAME, VERSION_FIELD_NAME,
+};
+use sea_query::{Asterisk, DynIden, Expr, ExprTrait, Order, PostgresQueryBuilder, Query};
+use sea_query_sqlx::{SqlxBinder, SqlxValues};
+use uuid::Uuid;
+
+/// INSERT INTO {table}_revisions (...) SELECT ... FROM {table} WHERE document_id = $1
+///
+/// Copies the main row of the document as it is before an update overwrites it.
+pub fn insert_revision(document: &DocumentType, id: Uuid) -> (String, SqlxValues) {
+    let columns = revision_columns(document);
+
+    let select_query = Query::select()
+        .columns(columns.clone())
+        .from(document.main_table())
+        .and_where(Expr::col(DOCUMENT_ID_FIELD_NAME).eq(id))
+        .to_owned();
+
+    let mut insert_query = Query::insert();
+    insert_query
+        .into_table(document.revision_table())
+        .columns(columns);
+    insert_query
+        .select_from(select_query)
+        .expect("valid select_from query");
+
+    insert_query.build_sqlx(PostgresQueryBuilder)
+}
+
+/// SELECT * FROM {table}_revisions m WHERE m.document_id = $1 ORDER BY m.version DESC
+pub fn query_find_revisions(document: &DocumentType, id: Uuid) -> (String, SqlxValues) {
+    Query::select()
+        .column(Asterisk)
+        .from(document.revision_table())
+        .and_where(Expr::col(("m", DOCUMENT_ID_FIELD_NAME)).eq(id))
+        .order_by(("m", VERSION_FIELD_NAME), Order::Desc)
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// SELECT * FROM {table}_revisions m WHERE m.document_id = $1 AND m.version = $2
+pub fn query_find_revision(
+    document: &DocumentType,
+    id: Uuid,
+    version: i32,
+) -> (String, SqlxValues) {
+    Query::select()
+        .column(Asterisk)
+        .from(document.revision_table())
+        .and_where(Expr::col(("m", DOCUMENT_ID_FIELD_NAME)).eq(id))
+        .and_where(Expr::col(("m", VERSION_FIELD_NAME)).eq(version))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// DELETE FROM {table}_revisions m WHERE m.document_id = $1
+///
+/// The revisions of a partitioned type are deleted with their document by
+/// the repository, they have no foreign key to it.
+pub fn delete_revisions(document: &DocumentType, id: Uuid) -> (String, SqlxValues) {
+    Query::delete()
+        .from_table(document.revision_table())
+        .and_where(Expr::col(("m", DOCUMENT_ID_FIELD_NAME)).eq(id))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// Columns shared by the main table and the revisions table.
+fn revision_columns(document: &DocumentType) -> Vec<DynIden> {
+    let mut columns: Vec<DynIden> = vec![
+        DOCUMENT_ID_FIELD_NAME.into(),
+        STATUS_FIELD_NAME.into(),
+        VERSION_FIELD_NAME.into(),
+        CREATED_FIELD_NAME.into(),
+        UPDATED_FIELD_NAME.into(),
+        CREATED_BY_FIELD_NAME.into(),
+        UPDATED_BY_FIELD_NAME.into(),
+        REVISION_FIELD_NAME.into(),
+        PUBLISHED_FIELD_NAME.into(),
+        PUBLISHED_BY_FIELD_NAME.into(),
+    ];
+
+    for field in &document.fields {
+        columns.push(field.id.normalized().into());
+    }
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_document() -> DocumentType {
+        DocumentType::new_bare_collection("article", "article", "articles").unwrap()
+    }
+
+    #[test]
+    fn test_insert_revision_copies_the_main_row() {
+        let document = make_document();
+        let (sql, _) = insert_revision(&document, Uuid::nil());
+
+        assert!(
+            sql.starts_with(r#"INSERT INTO "article_revisions""#),
+            "{sql}"
+        );
+        assert!(
+            sql.contains(r#"SELECT "document_id", "status", "version""#),
+            "{sql}"
+        );
+        assert!(sql.contains(r#"FROM "article" AS "m""#), "{sql}");
+    }
+
+    #[test]
+    fn test_find_revisions_most_recent_first() {
+        let document = make_document();
+        let (sql, _) = query_find_revisions(&document, Uuid::nil());
+
+        assert!(sql.ends_with(r#"ORDER BY "m"."version" DESC"#), "{sql}");
+    }
+}
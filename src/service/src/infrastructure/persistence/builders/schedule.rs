@@ -0,0 +1,95 @@
+use crate::domain::document::lifecycle::PublicationSchedule;
+use chrono::{DateTime, Utc};
+use luminair_common::persistence::TableNameProviderConstructor;
+use luminair_common::{
+    DOCUMENT_ID_FIELD_NAME, DocumentType, PUBLISH_AT_FIELD_NAME, UNPUBLISH_AT_FIELD_NAME,
+};
+use sea_query::{Condition, DynIden, Expr, ExprTrait, PostgresQueryBuilder, Query};
+use sea_query_sqlx::{SqlxBinder, SqlxValues};
+use uuid::Uuid;
+
+/// UPDATE {table} SET publish_at = $1, unpublish_at = $2 WHERE document_id = $3
+pub fn update_schedule(
+    document: &DocumentType,
+    id: Uuid,
+    schedule: &PublicationSchedule,
+) -> (String, SqlxValues) {
+    let column_values: Vec<(DynIden, Expr)> = vec![
+        (PUBLISH_AT_FIELD_NAME.into(), schedule.publish_at.into()),
+        (UNPUBLISH_AT_FIELD_NAME.into(), schedule.unpublish_at.into()),
+    ];
+
+    Query::update()
+        .table(document.main_table())
+        .values(column_values)
+        .and_where(Expr::col(DOCUMENT_ID_FIELD_NAME).eq(id))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// UPDATE {table} SET publish_at = $1[, unpublish_at = $2] WHERE document_id = $3
+///
+/// The scheduled unpublish is only replaced with an `unpublish_at`, otherwise
+/// the stored one is kept.
+pub fn update_publish_schedule(
+    document: &DocumentType,
+    id: Uuid,
+    publish_at: Option<DateTime<Utc>>,
+    unpublish_at: Option<DateTime<Utc>>,
+) -> (String, SqlxValues) {
+    let mut column_values: Vec<(DynIden, Expr)> =
+        vec![(PUBLISH_AT_FIELD_NAME.into(), publish_at.into())];
+    if let Some(unpublish_at) = unpublish_at {
+        column_values.push((UNPUBLISH_AT_FIELD_NAME.into(), unpublish_at.into()));
+    }
+
+    Query::update()
+        .table(document.main_table())
+        .values(column_values)
+        .and_where(Expr::col(DOCUMENT_ID_FIELD_NAME).eq(id))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// SELECT m.document_id, m.publish_at, m.unpublish_at FROM {table} m
+/// WHERE m.publish_at <= $1 OR m.unpublish_at <= $2
+pub fn query_due_schedules(document: &DocumentType, until: DateTime<Utc>) -> (String, SqlxValues) {
+    Query::select()
+        .columns([
+            ("m", DOCUMENT_ID_FIELD_NAME),
+            ("m", PUBLISH_AT_FIELD_NAME),
+            ("m", UNPUBLISH_AT_FIELD_NAME),
+        ])
+        .from(document.main_table())
+        .cond_where(
+            Condition::any()
+                .add(Expr::col(("m", PUBLISH_AT_FIELD_NAME)).lte(until))
+                .add(Expr::col(("m", UNPUBLISH_AT_FIELD_NAME)).lte(until)),
+        )
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_due_schedules_match_either_time() {
+        let document = DocumentType::new_bare_collection("article", "article", "articles").unwrap();
+        let (sql, _) = query_due_schedules(&document, Utc::now());
+
+        assert!(
+            sql.ends_with(r#"WHERE "m"."publish_at" <= $1 OR "m"."unpublish_at" <= $2"#),
+            "{sql}"
+        );
+    }
+
+    #[test]
+    fn test_publish_schedule_keeps_the_unpublish_unless_given() {
+        let document = DocumentType::new_bare_collection("article", "article", "articles").unwrap();
+
+        let (sql, _) = update_publish_schedule(&document, Uuid::nil(), None, None);
+        assert!(!sql.contains("unpublish_at"), "{sql}");
+
+        let (sql, _) = update_publish_schedule(&document, Uuid::nil(), None, Some(Utc::now()));
+        assert!(sql.contains(r#""unpublish_at" = $2"#), "{sql}");
+    }
+}
@@ -0,0 +1,167 @@
+use luminair_common::persistence::TableNameProviderConstructor;
+use luminair_common::{DOCUMENT_ID_FIELD_NAME, DocumentType, entities::FieldType};
+use sea_query::{
+    Alias, Asterisk, Expr, Order, PostgresQueryBuilder, Query, SelectStatement, TableRef, UnionType,
+};
+use sea_query_sqlx::{SqlxBinder, SqlxValues};
+
+/// Text search configuration of the queries and documents: words as they
+/// are, lowercased, whatever the language of the content.
+const TEXT_SEARCH_CONFIG: &str = "simple";
+
+/// SELECT * FROM (
+///   SELECT 'brands' AS document_type, m.document_id,
+///     ts_rank({vector}, websearch_to_tsquery('simple', $1)) AS rank
+///   FROM brands_snapshots m WHERE {vector} @@ websearch_to_tsquery('simple', $2)
+///   UNION ALL SELECT ... FROM partners m ...
+/// ) AS hits ORDER BY rank DESC, document_type, document_id LIMIT $n
+///
+/// `{vector}` joins the text fields of a type, of all locales, skipping the
+/// JSON keys of the localized ones; published copies are searched for types
+/// with draft and publish. `None` without a type having text fields.
+pub fn query_search_documents(
+    document_types: &[&DocumentType],
+    text: &str,
+    limit: u64,
+) -> Option<(String, SqlxValues)> {
+    let mut selects = document_types
+        .iter()
+        .filter_map(|document_type| search_select(document_type, text));
+    let mut union = selects.next()?;
+    for select in selects {
+        union.union(UnionType::All, select);
+    }
+
+    let query = Query::select()
+        .column(Asterisk)
+        .from_subquery(union, Alias::new("hits"))
+        .order_by(Alias::new("rank"), Order::Desc)
+        .order_by(Alias::new("document_type"), Order::Asc)
+        .order_by(Alias::new(DOCUMENT_ID_FIELD_NAME), Order::Asc)
+        .limit(limit)
+        .build_sqlx(PostgresQueryBuilder);
+    Some(query)
+}
+
+/// The matches of `text` among the published documents of `document_type`,
+/// unless it has no text fields.
+fn search_select(document_type: &DocumentType, text: &str) -> Option<SelectStatement> {
+    let vector = text_vector(document_type)?;
+    let table = if document_type.has_draft_and_publish() {
+        TableRef::from(document_type.snapshot_table())
+    } else {
+        TableRef::from(document_type.main_table())
+    };
+    let ts_query = format!("websearch_to_tsquery('{TEXT_SEARCH_CONFIG}', $1)");
+
+    let mut select = Query::select();
+    select
+        .expr_as(
+            Expr::val(document_type.id.as_ref()),
+            Alias::new("document_type"),
+        )
+        .column(("m", DOCUMENT_ID_FIELD_NAME))
+        .expr_as(
+            Expr::cust_with_values(format!("ts_rank({vector}, {ts_query})"), [text]),
+            Alias::new("rank"),
+        )
+        .from(table)
+        .and_where(Expr::cust_with_values(
+            format!("{vector} @@ {ts_query}"),
+            [text],
+        ));
+    Some(select)
+}
+
+/// The `tsvector` of the text fields of a document row aliased `m`.
+fn text_vector(document_type: &DocumentType) -> Option<String> {
+    let mut texts = Vec::new();
+    let mut localized = Vec::new();
+    for field in document_type.ordered_fields() {
+        if !field.field_type.is_text() {
+            continue;
+        }
+        let column = format!("m.\"{}\"", field.id.normalized().replace('"', "\"\""));
+        if field.field_type == FieldType::LocalizedText {
+            localized.push(format!(
+                "jsonb_to_tsvector('{TEXT_SEARCH_CONFIG}', coalesce({column}, '{{}}'::jsonb), '[\"string\"]')"
+            ));
+        } else {
+            texts.push(column);
+        }
+    }
+
+    let mut vectors = Vec::new();
+    if !texts.is_empty() {
+        vectors.push(format!(
+            "to_tsvector('{TEXT_SEARCH_CONFIG}', concat_ws(' ', {}))",
+            texts.join(", ")
+        ));
+    }
+    vectors.extend(localized);
+    (!vectors.is_empty()).then(|| format!("({})", vectors.join(" || ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use luminair_common::AttributeId;
+    use luminair_common::entities::DocumentField;
+    use std::collections::HashSet;
+
+    fn collection(singular: &str, plural: &str, fields: &[(&str, FieldType)]) -> DocumentType {
+        let mut document = DocumentType::new_bare_collection(singular, singular, plural).unwrap();
+        for (id, field_type) in fields {
+            document.fields.insert(DocumentField {
+                id: AttributeId::try_new(*id).unwrap(),
+                field_type: *field_type,
+                constraints: HashSet::new(),
+                required: false,
+                unique: false,
+                renamed_from: None,
+                api_name: None,
+                description: None,
+                filterable: false,
+                sortable: false,
+                personal: false,
+            });
+        }
+        document
+    }
+
+    #[test]
+    fn test_types_are_searched_by_their_text_fields() {
+        let brands = collection(
+            "brand",
+            "brands",
+            &[
+                ("name", FieldType::Text),
+                ("title", FieldType::LocalizedText),
+            ],
+        );
+        let numbers = collection("number", "numbers", &[("value", FieldType::Date)]);
+        let (sql, _) = query_search_documents(&[&brands, &numbers], "shoes", 10).unwrap();
+
+        assert!(sql.starts_with("SELECT * FROM (SELECT"), "{sql}");
+        assert!(
+            sql.contains(r#"to_tsvector('simple', concat_ws(' ', m."name"))"#),
+            "{sql}"
+        );
+        assert!(
+            sql.contains(
+                r#"jsonb_to_tsvector('simple', coalesce(m."title", '{}'::jsonb), '["string"]')"#
+            ),
+            "{sql}"
+        );
+        assert!(sql.contains("websearch_to_tsquery('simple', $"), "{sql}");
+        assert!(!sql.contains("UNION ALL"), "{sql}");
+        assert!(
+            sql.contains(
+                r#"AS "hits" ORDER BY "rank" DESC, "document_type" ASC, "document_id" ASC LIMIT $"#
+            ),
+            "{sql}"
+        );
+
+        assert!(query_search_documents(&[&numbers], "shoes", 10).is_none());
+    }
+}
@@ -0,0 +1,253 @@
+use crate::infrastructure::persistence::mapping::writer::typed_null;
+use luminair_common::entities::FieldType;
+use luminair_common::persistence::{
+    ERASURES_TABLE_NAME, OUTBOX_TABLE_NAME, TableNameProvider, TableNameProviderConstructor,
+};
+use luminair_common::{
+    DOCUMENT_ID_FIELD_NAME, DocumentType, UPDATED_FIELD_NAME, VERSION_FIELD_NAME,
+};
+use sea_query::{Alias, Expr, ExprTrait, PostgresQueryBuilder, Query, UpdateStatement, Values};
+use sea_query_sqlx::{SqlxBinder, SqlxValues};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// SELECT document_id FROM {table} WHERE lower(c1) = lower($1) OR ...
+/// UNION SELECT document_id FROM {table}_snapshots WHERE ...
+/// UNION SELECT document_id FROM {table}_revisions WHERE ...
+/// ORDER BY document_id
+///
+/// The documents whose personal text fields hold `value`, ignoring case,
+/// in their draft, published copy or one of their revisions; `None` when
+/// the type has no personal text fields to look it up by.
+pub fn query_subject_documents(
+    document: &DocumentType,
+    value: &str,
+) -> Option<(String, SqlxValues)> {
+    let condition = subject_condition(document)?;
+    let mut tables = vec![document.main_table().table_name()];
+    if document.has_draft_and_publish() {
+        tables.push(document.snapshot_table().table_name());
+    }
+    tables.push(document.revision_table().table_name());
+
+    let selects = tables
+        .iter()
+        .map(|table| {
+            format!(r#"SELECT "{DOCUMENT_ID_FIELD_NAME}" FROM "{table}" WHERE {condition}"#)
+        })
+        .collect::<Vec<_>>();
+    let sql = format!(
+        r#"{} ORDER BY "{DOCUMENT_ID_FIELD_NAME}""#,
+        selects.join(" UNION ")
+    );
+    Some((sql, SqlxValues(Values(vec![value.into()]))))
+}
+
+/// UPDATE {table} SET c1 = NULL, ..., updated_at = CURRENT_TIMESTAMP,
+///   version = version + 1 WHERE document_id = ANY($1)
+/// UPDATE {table}_snapshots SET c1 = NULL, ... WHERE document_id = ANY($1)
+/// UPDATE {table}_revisions SET c1 = NULL, ... WHERE document_id = ANY($1)
+///
+/// Clears the personal fields of the documents everywhere they are stored;
+/// the new version of the drafts tells the clients holding them apart.
+pub fn erase_personal_fields(document: &DocumentType, ids: Vec<Uuid>) -> Vec<(String, SqlxValues)> {
+    let mut statements = vec![
+        clear_personal_fields(document, document.main_table(), ids.clone())
+            .value(Alias::new(UPDATED_FIELD_NAME), Expr::current_timestamp())
+            .value(
+                Alias::new(VERSION_FIELD_NAME),
+                Expr::col(VERSION_FIELD_NAME).add(1),
+            )
+            .build_sqlx(PostgresQueryBuilder),
+    ];
+    if document.has_draft_and_publish() {
+        statements.push(
+            clear_personal_fields(document, document.snapshot_table(), ids.clone())
+                .build_sqlx(PostgresQueryBuilder),
+        );
+    }
+    statements.push(
+        clear_personal_fields(document, document.revision_table(), ids)
+            .build_sqlx(PostgresQueryBuilder),
+    );
+    statements
+}
+
+/// UPDATE {table}_archived SET c1 = NULL, ... WHERE lower(c1) = lower($1) OR ...
+/// RETURNING document_id
+///
+/// Clears the personal fields of the archived documents holding `value`;
+/// `None` when the type has no personal text fields to look it up by.
+pub fn erase_archived_personal_fields(
+    document: &DocumentType,
+    value: &str,
+) -> Option<(String, SqlxValues)> {
+    let condition = subject_condition(document)?;
+    let columns = document
+        .personal_fields()
+        .iter()
+        .map(|field| format!(r#""{}" = NULL"#, field.id.normalized()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let sql = format!(
+        r#"UPDATE "{}" SET {columns} WHERE {condition} RETURNING "{DOCUMENT_ID_FIELD_NAME}""#,
+        document.archived_table().table_name()
+    );
+    Some((sql, SqlxValues(Values(vec![value.into()]))))
+}
+
+/// UPDATE luminair_outbox SET diff = diff || (SELECT jsonb_object_agg(key,
+///   '{"old": null, "new": null}') FROM jsonb_object_keys(diff) key WHERE key = ANY($1))
+/// WHERE document_type = $2 AND document_id = ANY($3) AND diff ?| $1
+///
+/// Clears the old and new values of the personal fields in the content
+/// change events of the documents, keeping that they changed; `None` when
+/// there are no documents or the type has no personal fields.
+pub fn erase_outbox_personal_fields(
+    document: &DocumentType,
+    ids: Vec<Uuid>,
+) -> Option<(String, SqlxValues)> {
+    let fields = document
+        .personal_fields()
+        .iter()
+        .map(|field| field.id.to_string())
+        .collect::<Vec<_>>();
+    if fields.is_empty() || ids.is_empty() {
+        return None;
+    }
+    let sql = format!(
+        r#"UPDATE "{OUTBOX_TABLE_NAME}" SET "diff" = "diff" || COALESCE((SELECT jsonb_object_agg("key", '{{"old": null, "new": null}}'::jsonb) FROM jsonb_object_keys("diff") AS "key" WHERE "key" = ANY($1)), '{{}}'::jsonb) WHERE "document_type" = $2 AND "{DOCUMENT_ID_FIELD_NAME}" = ANY($3) AND "diff" ?| $1"#
+    );
+    Some((
+        sql,
+        SqlxValues(Values(vec![
+            fields.into(),
+            document.id.to_string().into(),
+            ids.into(),
+        ])),
+    ))
+}
+
+/// INSERT INTO luminair_erasures (subject, erased_by, documents)
+/// VALUES ($1, $2, $3) RETURNING erasure_id, erased_at
+///
+/// The audit record of an erasure; `subject` is the hash of the identifying
+/// value, see [`subject_hash`](crate::domain::subject::subject_hash).
+pub fn insert_erasure(
+    subject: String,
+    erased_by: Option<String>,
+    documents: Value,
+) -> (String, SqlxValues) {
+    Query::insert()
+        .into_table(Alias::new(ERASURES_TABLE_NAME))
+        .columns([
+            Alias::new("subject"),
+            Alias::new("erased_by"),
+            Alias::new("documents"),
+        ])
+        .values_panic([subject.into(), erased_by.into(), documents.into()])
+        .returning(Query::returning().columns([Alias::new("erasure_id"), Alias::new("erased_at")]))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// lower(c1) = lower($1) OR lower(c2) = lower($1) ... over the personal text
+/// fields, the ones an identifying value is looked up in.
+fn subject_condition(document: &DocumentType) -> Option<String> {
+    let columns = document
+        .personal_fields()
+        .into_iter()
+        .filter(|field| matches!(field.field_type, FieldType::Text | FieldType::Uid))
+        .map(|field| format!(r#"lower("{}") = lower($1)"#, field.id.normalized()))
+        .collect::<Vec<_>>();
+    (!columns.is_empty()).then(|| columns.join(" OR "))
+}
+
+fn clear_personal_fields(
+    document: &DocumentType,
+    table: TableNameProvider,
+    ids: Vec<Uuid>,
+) -> UpdateStatement {
+    // in scope here only, its `contains` would shadow the one of strings
+    use sea_query::extension::postgres::PgExpr;
+
+    let mut update = Query::update();
+    update.table(table);
+    for field in document.personal_fields() {
+        update.value(
+            Alias::new(field.id.normalized()),
+            typed_null(field.field_type),
+        );
+    }
+    update.and_where(Expr::col(DOCUMENT_ID_FIELD_NAME).eq_any(ids));
+    update
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use luminair_common::AttributeId;
+    use luminair_common::entities::DocumentField;
+
+    fn personal_field(id: &str, field_type: FieldType) -> DocumentField {
+        DocumentField {
+            id: AttributeId::try_new(id).unwrap(),
+            field_type,
+            unique: false,
+            required: false,
+            constraints: Default::default(),
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: true,
+        }
+    }
+
+    #[test]
+    fn test_subject_is_looked_up_in_personal_text_fields() {
+        let mut document =
+            DocumentType::new_bare_collection("customer", "customer", "customers").unwrap();
+        assert!(query_subject_documents(&document, "jane@example.com").is_none());
+
+        document
+            .fields
+            .insert(personal_field("email", FieldType::Text));
+        document
+            .fields
+            .insert(personal_field("born", FieldType::Date));
+        let (sql, _) = query_subject_documents(&document, "jane@example.com").unwrap();
+        assert_eq!(
+            sql,
+            r#"SELECT "document_id" FROM "customer" WHERE lower("email") = lower($1) UNION SELECT "document_id" FROM "customer_revisions" WHERE lower("email") = lower($1) ORDER BY "document_id""#
+        );
+
+        let statements = erase_personal_fields(&document, vec![Uuid::nil()]);
+        assert_eq!(statements.len(), 2);
+        assert!(
+            statements[0].0.contains(r#"SET "born" = $1, "email" = $2"#),
+            "{}",
+            statements[0].0
+        );
+        assert!(statements[0].0.contains(r#""version" = "version" + $3"#));
+    }
+
+    #[test]
+    fn test_outbox_events_lose_the_personal_values() {
+        let mut document =
+            DocumentType::new_bare_collection("customer", "customer", "customers").unwrap();
+        assert!(erase_outbox_personal_fields(&document, vec![Uuid::nil()]).is_none());
+
+        document
+            .fields
+            .insert(personal_field("email", FieldType::Text));
+        assert!(erase_outbox_personal_fields(&document, Vec::new()).is_none());
+        let (sql, values) = erase_outbox_personal_fields(&document, vec![Uuid::nil()]).unwrap();
+        assert!(
+            sql.starts_with(r#"UPDATE "luminair_outbox" SET "diff" = "diff" || COALESCE("#),
+            "{sql}"
+        );
+        assert!(sql.ends_with(r#"AND "diff" ?| $1"#), "{sql}");
+        assert_eq!(values.0.0.len(), 3);
+    }
+}
@@ -0,0 +1,146 @@
+use luminair_common::entities::FieldType;
+use luminair_common::persistence::TableNameProviderConstructor;
+use luminair_common::{DOCUMENT_ID_FIELD_NAME, DocumentType, LOCALES_UPDATED_FIELD_NAME};
+use sea_query::Values;
+use sea_query_sqlx::SqlxValues;
+
+use crate::domain::translation::locales;
+
+/// SELECT m.document_id,
+///   array_agg(COALESCE(m.c1 ->> l.locale, '') <> '' OR ... ORDER BY l.position) AS present,
+///   array_agg((m.locales_updated_at ->> l.locale)::timestamptz ORDER BY l.position) AS updated_at
+/// FROM (SELECT * FROM {table} ORDER BY document_id LIMIT $2 OFFSET $3) m
+///   CROSS JOIN unnest($1::text[]) WITH ORDINALITY AS l(locale, position)
+/// GROUP BY m.document_id ORDER BY m.document_id
+///
+/// Per draft of a localized document, whether each locale of its type has a
+/// text in a localized field and when its texts last changed, the locales
+/// in the order of the type. The drafts are paged before their locales are
+/// unnested, so a page never groups more than `limit` of them.
+pub fn query_translation_status(
+    document: &DocumentType,
+    offset: u64,
+    limit: u64,
+) -> (String, SqlxValues) {
+    let table = document.main_table().table_name();
+    let sql = format!(
+        r#"SELECT m."{id}", array_agg({present} ORDER BY l."position") AS "present", array_agg((m."{updated}" ->> l."locale")::timestamptz ORDER BY l."position") AS "updated_at" FROM (SELECT * FROM "{table}" ORDER BY "{id}" LIMIT $2 OFFSET $3) m CROSS JOIN unnest($1::text[]) WITH ORDINALITY AS l("locale", "position") GROUP BY m."{id}" ORDER BY m."{id}""#,
+        id = DOCUMENT_ID_FIELD_NAME,
+        present = present_expr(document),
+        updated = LOCALES_UPDATED_FIELD_NAME,
+    );
+    let locales = locales(document).collect::<Vec<_>>();
+    let values = vec![
+        locales.into(),
+        (limit as i64).into(),
+        (offset as i64).into(),
+    ];
+    (sql, SqlxValues(Values(values)))
+}
+
+/// SELECT t.locale,
+///   count(*) FILTER (WHERE t.present AND NOT COALESCE(t.updated_at < t.source_updated_at, FALSE)) AS translated,
+///   count(*) FILTER (WHERE t.present AND t.updated_at < t.source_updated_at) AS stale,
+///   count(*) FILTER (WHERE NOT t.present) AS missing
+/// FROM (SELECT l.locale, l.position, <present> AS present,
+///         (m.locales_updated_at ->> l.locale)::timestamptz AS updated_at,
+///         (m.locales_updated_at ->> ($1::text[])[1])::timestamptz AS source_updated_at
+///       FROM {table} m CROSS JOIN unnest($1::text[]) WITH ORDINALITY AS l(locale, position)) t
+/// GROUP BY t.locale, t.position ORDER BY t.position
+///
+/// Number of the drafts of a localized document type in each state of their
+/// translation into each locale of the type, as
+/// [`DocumentTranslations::state`](crate::domain::translation::DocumentTranslations::state)
+/// tells them apart, counted by the database rather than from every draft.
+/// Locales without drafts have no row.
+pub fn query_translation_counts(document: &DocumentType) -> (String, SqlxValues) {
+    let table = document.main_table().table_name();
+    let sql = format!(
+        r#"SELECT t."locale", count(*) FILTER (WHERE t."present" AND NOT COALESCE(t."updated_at" < t."source_updated_at", FALSE)) AS "translated", count(*) FILTER (WHERE t."present" AND t."updated_at" < t."source_updated_at") AS "stale", count(*) FILTER (WHERE NOT t."present") AS "missing" FROM (SELECT l."locale", l."position", {present} AS "present", (m."{updated}" ->> l."locale")::timestamptz AS "updated_at", (m."{updated}" ->> ($1::text[])[1])::timestamptz AS "source_updated_at" FROM "{table}" m CROSS JOIN unnest($1::text[]) WITH ORDINALITY AS l("locale", "position")) t GROUP BY t."locale", t."position" ORDER BY t."position""#,
+        present = present_expr(document),
+        updated = LOCALES_UPDATED_FIELD_NAME,
+    );
+    let locales = locales(document).collect::<Vec<_>>();
+    (sql, SqlxValues(Values(vec![locales.into()])))
+}
+
+/// COALESCE(m.c1 ->> l.locale, '') <> '' OR ...
+///
+/// Whether a localized field of the row `m` has a text in the locale `l`.
+fn present_expr(document: &DocumentType) -> String {
+    let texts = document
+        .fields
+        .iter()
+        .filter(|field| field.field_type == FieldType::LocalizedText)
+        .map(|field| {
+            format!(
+                r#"COALESCE(m."{}" ->> l."locale", '') <> ''"#,
+                field.id.normalized()
+            )
+        })
+        .collect::<Vec<_>>();
+    if texts.is_empty() {
+        "FALSE".to_string()
+    } else {
+        texts.join(" OR ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use luminair_common::AttributeId;
+    use luminair_common::entities::{DocumentField, DocumentTypeOptions, LocalizationId};
+
+    #[test]
+    fn test_drafts_are_paged_and_counted_per_locale() {
+        let mut document =
+            DocumentType::new_bare_collection("article", "article", "articles").unwrap();
+        document.fields.insert(DocumentField {
+            id: AttributeId::try_new("title").unwrap(),
+            field_type: FieldType::LocalizedText,
+            constraints: Default::default(),
+            required: false,
+            unique: false,
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        });
+        document.options = Some(DocumentTypeOptions {
+            draft_and_publish: false,
+            localizations: vec![
+                LocalizationId::try_new("en").unwrap(),
+                LocalizationId::try_new("ro").unwrap(),
+            ],
+            workflow: None,
+            cache_control: None,
+            feed: None,
+            operations: None,
+            partitioning: None,
+            view: None,
+            retention: None,
+        });
+
+        let (sql, values) = query_translation_status(&document, 20, 10);
+        assert!(
+            sql.contains(
+                r#"FROM (SELECT * FROM "article" ORDER BY "document_id" LIMIT $2 OFFSET $3) m"#
+            ),
+            "{sql}"
+        );
+        assert_eq!(values.0.0.len(), 3);
+
+        let (sql, _) = query_translation_counts(&document);
+        assert!(
+            sql.contains(r#"count(*) FILTER (WHERE NOT t."present") AS "missing""#),
+            "{sql}"
+        );
+        assert!(
+            sql.contains(r#"COALESCE(m."title" ->> l."locale", '') <> '' AS "present""#),
+            "{sql}"
+        );
+    }
+}
@@ -1,15 +1,25 @@
+use crate::domain::document::content::{ContentValue, DocumentContent};
 use crate::domain::document::{DocumentInstance, lifecycle::PublicationState};
-use luminair_common::persistence::TableNameProviderConstructor;
+use crate::domain::translation::locales;
+use crate::infrastructure::persistence::mapping::writer::{field_expr, typed_null};
+use chrono::{DateTime, Utc};
+use luminair_common::entities::FieldType;
+use luminair_common::persistence::{TableNameProviderConstructor, snapshot_table_name};
 use luminair_common::{
     AttributeId, CREATED_FIELD_NAME, DOCUMENT_ID_FIELD_NAME, DocumentType,
-    OWNING_DOCUMENT_ID_FIELD_NAME, PUBLISHED_BY_FIELD_NAME, PUBLISHED_FIELD_NAME,
-    REVISION_FIELD_NAME, SNAPSHOT_ID_FIELD_NAME, STATUS_FIELD_NAME, TARGET_DOCUMENT_ID_FIELD_NAME,
-    UPDATED_FIELD_NAME, VERSION_FIELD_NAME,
+    LOCALES_UPDATED_FIELD_NAME, OWNING_DOCUMENT_ID_FIELD_NAME, PUBLISHED_BY_FIELD_NAME,
+    PUBLISHED_FIELD_NAME, REVISION_FIELD_NAME, SNAPSHOT_ID_FIELD_NAME, STATUS_FIELD_NAME,
+    TARGET_DOCUMENT_ID_FIELD_NAME, UPDATED_FIELD_NAME, VERSION_FIELD_NAME,
 };
-use sea_query::{Alias, DynIden, Expr, ExprTrait, PostgresQueryBuilder, Query};
+use sea_query::extension::postgres::PgExpr;
+use sea_query::{Alias, DynIden, Expr, ExprTrait, LockType, PostgresQueryBuilder, Query};
 use sea_query_sqlx::{SqlxBinder, SqlxValues};
+use serde_json::json;
 use uuid::Uuid;
 
+/// INSERT INTO {table} (...) VALUES (...) RETURNING *
+///
+/// The inserted row is returned, so the created document is read back without a follow-up SELECT.
 pub fn insert_document(document: &DocumentType, params: Vec<Expr>) -> (String, SqlxValues) {
     let table = document.main_table();
 
@@ -17,17 +27,21 @@ pub fn insert_document(document: &DocumentType, params: Vec<Expr>) -> (String, S
         .into_table(table)
         .columns(main_insert_columns(document))
         .values_panic(params)
+        .returning_all()
         .build_sqlx(PostgresQueryBuilder)
 }
 
-/// UPDATE {table} SET col1 = $1, col2 = $2, ... WHERE document_id = $id
+/// UPDATE {table} SET col1 = $1, col2 = $2, ... WHERE document_id = $id AND version = $expected
 ///
 /// `column_values` is the full set of columns to write. Identity columns
 /// (`document_id`, `created_at`) are not included by callers; everything else
 /// — `updated_at`, `version`, publication state, and dynamic fields — is.
+/// The version condition makes the update a no-op when the row was modified
+/// since it was read.
 pub fn update_document(
     document: &DocumentType,
     document_id: Uuid,
+    expected_version: i32,
     column_values: Vec<(DynIden, Expr)>,
 ) -> (String, SqlxValues) {
     let table = document.main_table();
@@ -36,6 +50,79 @@ pub fn update_document(
         .table(table)
         .values(column_values)
         .and_where(Expr::col(DOCUMENT_ID_FIELD_NAME).eq(document_id))
+        .and_where(Expr::col(VERSION_FIELD_NAME).eq(expected_version))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// COALESCE(locales_updated_at, '{}') || jsonb_strip_nulls(jsonb_build_object(
+///     'en', CASE WHEN (title ->> 'en') IS DISTINCT FROM ($1::jsonb ->> 'en') OR ... THEN $2 END, ...))
+///
+/// New value of the update times of the locales of a localized document:
+/// `changed_at` for the locales whose text in a localized field differs
+/// from the stored one, the stored times for the others.
+pub fn locales_updated_expr(
+    document: &DocumentType,
+    content: &DocumentContent,
+    changed_at: DateTime<Utc>,
+) -> Expr {
+    let localized_fields = document
+        .fields
+        .iter()
+        .filter(|field| field.field_type == FieldType::LocalizedText)
+        .collect::<Vec<_>>();
+
+    let mut sql = format!(
+        "COALESCE(\"{LOCALES_UPDATED_FIELD_NAME}\", '{{}}') || jsonb_strip_nulls(jsonb_build_object("
+    );
+    let mut values: Vec<sea_query::Value> = Vec::new();
+    // the placeholders of Postgres are numbered
+    let mut param = |value: sea_query::Value| {
+        values.push(value);
+        format!("${}", values.len())
+    };
+    for (index, locale) in locales(document).enumerate() {
+        if index > 0 {
+            sql.push_str(", ");
+        }
+        sql.push_str(&format!("{}, CASE WHEN ", param(locale.clone().into())));
+        if localized_fields.is_empty() {
+            sql.push_str("FALSE");
+        }
+        for (index, field) in localized_fields.iter().enumerate() {
+            if index > 0 {
+                sql.push_str(" OR ");
+            }
+            let value = match content.fields.get(&field.id) {
+                Some(ContentValue::LocalizedText(texts)) => Some(json!(texts)),
+                _ => None,
+            };
+            sql.push_str(&format!(
+                "(\"{}\" ->> {}) IS DISTINCT FROM ({}::jsonb ->> {})",
+                field.id.normalized(),
+                param(locale.clone().into()),
+                param(value.into()),
+                param(locale.clone().into())
+            ));
+        }
+        sql.push_str(&format!(
+            " THEN {}::timestamptz END",
+            param(changed_at.into())
+        ));
+    }
+    sql.push_str("))");
+    Expr::cust_with_values(sql, values)
+}
+
+/// SELECT m.version FROM {table} m WHERE m.document_id = $1 FOR UPDATE
+///
+/// Locks the main row until the end of the transaction, so concurrent
+/// updates and publishes of the same document run one after another.
+pub fn lock_document(document: &DocumentType, id: Uuid) -> (String, SqlxValues) {
+    Query::select()
+        .column(("m", VERSION_FIELD_NAME))
+        .from(document.main_table())
+        .and_where(Expr::col(("m", DOCUMENT_ID_FIELD_NAME)).eq(id))
+        .lock(LockType::Update)
         .build_sqlx(PostgresQueryBuilder)
 }
 
@@ -49,6 +136,37 @@ pub fn delete_document(document: &DocumentType, id: Uuid) -> (String, SqlxValues
         .build_sqlx(PostgresQueryBuilder)
 }
 
+/// UPDATE {table} SET updated_at = CURRENT_TIMESTAMP WHERE document_id = ANY($1)
+///
+/// Marks the documents as updated without a new version, for changes made to
+/// them by the writes of other documents.
+pub fn touch_documents(document: &DocumentType, ids: Vec<Uuid>) -> (String, SqlxValues) {
+    Query::update()
+        .table(document.main_table())
+        .value(Alias::new(UPDATED_FIELD_NAME), Expr::current_timestamp())
+        .and_where(Expr::col(DOCUMENT_ID_FIELD_NAME).eq_any(ids))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// SELECT m.snapshot_id FROM {table}_snapshots m WHERE m.document_id = $1
+pub fn query_snapshot_id(document: &DocumentType, id: Uuid) -> (String, SqlxValues) {
+    Query::select()
+        .column(("m", SNAPSHOT_ID_FIELD_NAME))
+        .from(document.snapshot_table())
+        .and_where(Expr::col(("m", DOCUMENT_ID_FIELD_NAME)).eq(id))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
+/// DELETE FROM {table}_snapshots m WHERE m.document_id = $1
+///
+/// The links of the snapshot are deleted with it by their foreign key.
+pub fn delete_snapshot(document: &DocumentType, id: Uuid) -> (String, SqlxValues) {
+    Query::delete()
+        .from_table(document.snapshot_table())
+        .and_where(Expr::col(("m", DOCUMENT_ID_FIELD_NAME)).eq(id))
+        .build_sqlx(PostgresQueryBuilder)
+}
+
 fn main_insert_columns(document: &DocumentType) -> Vec<DynIden> {
     let mut columns: Vec<DynIden> = vec![
         DOCUMENT_ID_FIELD_NAME.into(),
@@ -64,6 +182,9 @@ fn main_insert_columns(document: &DocumentType) -> Vec<DynIden> {
     for field in &document.fields {
         columns.push(field.id.normalized().into());
     }
+    if document.has_localization() {
+        columns.push(LOCALES_UPDATED_FIELD_NAME.into());
+    }
     columns
 }
 
@@ -71,8 +192,7 @@ pub fn build_snapshot_insert(
     document: &DocumentType,
     instance: &DocumentInstance,
 ) -> (String, SqlxValues) {
-    let table_name = format!("{}_snapshots", document.id.normalized());
-    let table = sea_query::TableName::from(table_name);
+    let table = sea_query::TableName::from(snapshot_table_name(&document.id));
 
     let mut columns: Vec<sea_query::DynIden> = vec![
         DOCUMENT_ID_FIELD_NAME.into(),
@@ -89,17 +209,17 @@ pub fn build_snapshot_insert(
         instance.document_id.0.into(),
         match &instance.content.publication_state {
             PublicationState::Published { published_at, .. } => Expr::from(*published_at),
-            _ => Expr::null(),
+            _ => typed_null(FieldType::DateTime),
         },
         match &instance.content.publication_state {
             PublicationState::Published { published_by, .. } => {
                 if let Some(user_id) = published_by {
                     Expr::from(user_id.to_string())
                 } else {
-                    Expr::null()
+                    typed_null(FieldType::Text)
                 }
             }
-            _ => Expr::null(),
+            _ => typed_null(FieldType::Text),
         },
         match &instance.content.publication_state {
             PublicationState::Published { revision, .. } | PublicationState::Draft { revision } => {
@@ -109,10 +229,7 @@ pub fn build_snapshot_insert(
     ];
 
     for field in &document.fields {
-        let expr = match instance.content.fields.get(&field.id) {
-            Some(val) => val.into(),
-            None => Expr::null(),
-        };
+        let expr = field_expr(field, instance.content.fields.get(&field.id));
         values.push(expr);
     }
 
@@ -160,8 +277,7 @@ pub fn build_snapshot_update(
     document: &DocumentType,
     instance: &DocumentInstance,
 ) -> (String, SqlxValues) {
-    let table_name = format!("{}_snapshots", document.id.normalized());
-    let table = sea_query::TableName::from(table_name);
+    let table = sea_query::TableName::from(snapshot_table_name(&document.id));
 
     let mut query = Query::update();
     query.table(table);
@@ -171,7 +287,7 @@ pub fn build_snapshot_update(
         Alias::new(PUBLISHED_FIELD_NAME),
         match &instance.content.publication_state {
             PublicationState::Published { published_at, .. } => Expr::from(*published_at),
-            _ => Expr::null(),
+            _ => typed_null(FieldType::DateTime),
         },
     );
 
@@ -182,10 +298,10 @@ pub fn build_snapshot_update(
                 if let Some(user_id) = published_by {
                     Expr::from(user_id.to_string())
                 } else {
-                    Expr::null()
+                    typed_null(FieldType::Text)
                 }
             }
-            _ => Expr::null(),
+            _ => typed_null(FieldType::Text),
         },
     );
 
@@ -199,10 +315,7 @@ pub fn build_snapshot_update(
     );
 
     for field in &document.fields {
-        let expr = match instance.content.fields.get(&field.id) {
-            Some(val) => val.into(),
-            None => Expr::null(),
-        };
+        let expr = field_expr(field, instance.content.fields.get(&field.id));
         query.value(Alias::new(field.id.normalized()), expr);
     }
 
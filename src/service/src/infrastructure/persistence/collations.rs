@@ -0,0 +1,139 @@
+use std::collections::{HashMap, HashSet};
+
+use luminair_common::DocumentTypesRegistry;
+use luminair_common::database::{Database, StatementKind};
+use serde::Deserialize;
+
+use crate::domain::translation::locales;
+
+/// Settings of the collations the localized text fields are sorted by
+#[derive(Debug, Clone, Deserialize)]
+pub struct CollationSettings {
+    /// collation of the sorts in every locale listed, by its id, e.g.
+    /// `ro: "ro-x-icu"`; an empty name sorts by the collation of the database
+    #[serde(default)]
+    pub locales: HashMap<String, String>,
+    /// sort the locales of the document types not listed by the ICU
+    /// collation of their id, `{locale}-x-icu`, when the database has it; by
+    /// the collation of the database otherwise
+    #[serde(default = "default_derive_icu")]
+    pub derive_icu: bool,
+}
+
+impl Default for CollationSettings {
+    fn default() -> Self {
+        Self {
+            locales: HashMap::new(),
+            derive_icu: default_derive_icu(),
+        }
+    }
+}
+
+fn default_derive_icu() -> bool {
+    true
+}
+
+/// Collations of the sorts by localized text fields, per locale, so the
+/// values of a locale sort in the order of its language, e.g. `ș` right
+/// after `s` in Romanian.
+///
+/// Only the locales of the document types get one, and only a collation the
+/// database has, so a sort never names a collation Postgres doesn't know.
+#[derive(Debug, Clone, Default)]
+pub struct SortCollations {
+    locales: HashMap<String, String>,
+}
+
+impl SortCollations {
+    /// Collations of `locales` as configured by `settings`, among the
+    /// `available` collations of the database; a missing one is warned about
+    /// and the locale sorted by the collation of the database.
+    pub fn new(
+        settings: &CollationSettings,
+        locales: impl IntoIterator<Item = String>,
+        available: &HashSet<String>,
+    ) -> Self {
+        let mut collations = HashMap::new();
+        for locale in locales {
+            let collation = match settings.locales.get(&locale) {
+                Some(collation) => collation.clone(),
+                None if settings.derive_icu => format!("{locale}-x-icu"),
+                None => continue,
+            };
+            if collation.is_empty() {
+                continue;
+            }
+            if !available.contains(&collation) {
+                tracing::warn!(
+                    "Collation '{}' of locale '{}' doesn't exist, its values are sorted by the collation of the database",
+                    collation,
+                    locale
+                );
+                continue;
+            }
+            collations.insert(locale, collation);
+        }
+        Self {
+            locales: collations,
+        }
+    }
+
+    /// Collations of the locales of the document types of `registry`, as
+    /// configured by `settings`, checked against the `pg_collation` of
+    /// `database`.
+    pub async fn load(
+        settings: &CollationSettings,
+        registry: &'static dyn DocumentTypesRegistry,
+        database: &Database,
+    ) -> anyhow::Result<Self> {
+        let mut transaction = database.begin(StatementKind::Read).await?;
+        let available: Vec<String> = sqlx::query_scalar("SELECT collname::text FROM pg_collation")
+            .fetch_all(&mut *transaction)
+            .await?;
+        transaction.commit().await?;
+
+        let declared = registry.iterate().flat_map(locales).collect::<HashSet<_>>();
+        Ok(Self::new(
+            settings,
+            declared,
+            &available.into_iter().collect(),
+        ))
+    }
+
+    /// Collation the values of `locale` are sorted by, `None` for the
+    /// collation of the database.
+    pub fn collation(&self, locale: &str) -> Option<String> {
+        self.locales.get(locale).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collation_of_locales() {
+        let settings = CollationSettings {
+            locales: HashMap::from([
+                ("ro".to_string(), "ro-RO-x-icu".to_string()),
+                ("en".to_string(), String::new()),
+            ]),
+            derive_icu: true,
+        };
+        let available = ["ro-RO-x-icu", "de-x-icu"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let locales = ["ro", "en", "de", "xx"].into_iter().map(String::from);
+        let collations = SortCollations::new(&settings, locales, &available);
+        assert_eq!(collations.collation("ro").as_deref(), Some("ro-RO-x-icu"));
+        assert_eq!(collations.collation("en"), None);
+        assert_eq!(collations.collation("de").as_deref(), Some("de-x-icu"));
+        // not in the database
+        assert_eq!(collations.collation("xx"), None);
+        // not a locale of the document types
+        assert_eq!(collations.collation("fr"), None);
+
+        assert_eq!(SortCollations::default().collation("de"), None);
+    }
+}
@@ -0,0 +1,115 @@
+use std::time::{Duration, Instant};
+
+use luminair_common::DocumentType;
+use sea_query_sqlx::SqlxValues;
+use serde::Deserialize;
+use sqlx::types::Json;
+use sqlx::{AssertSqlSafe, PgPool, Row};
+
+use crate::domain::repository::RepositoryError;
+use crate::infrastructure::error_reporting::note_failed_query;
+
+/// Settings of the slow query log
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct QueryDiagnosticsSettings {
+    /// read queries running longer are logged as slow, no query is logged when unset
+    #[serde(default)]
+    pub slow_query_threshold_ms: Option<u64>,
+    /// log the plan of slow queries, obtained with `EXPLAIN (ANALYZE, FORMAT JSON)`
+    #[serde(default)]
+    pub explain_slow_queries: bool,
+}
+
+/// Traces the SQL of read queries and reports the slow ones, and the failed
+/// ones to the error reporting.
+///
+/// `EXPLAIN ANALYZE` runs the query a second time, so plans are only
+/// collected for read queries and only when they exceed the threshold.
+#[derive(Debug, Clone, Default)]
+pub struct QueryDiagnostics {
+    slow_query_threshold: Option<Duration>,
+    explain_slow_queries: bool,
+}
+
+impl QueryDiagnostics {
+    pub fn new(settings: &QueryDiagnosticsSettings) -> Self {
+        Self {
+            slow_query_threshold: settings.slow_query_threshold_ms.map(Duration::from_millis),
+            explain_slow_queries: settings.explain_slow_queries,
+        }
+    }
+
+    /// Run a read query of the document type with `run`, and log it when it
+    /// took longer than the threshold, with its plan if enabled.
+    pub async fn observe<T, F, Fut>(
+        &self,
+        pool: &PgPool,
+        document_type: &DocumentType,
+        sql: String,
+        values: SqlxValues,
+        run: F,
+    ) -> Result<T, RepositoryError>
+    where
+        F: FnOnce(String, SqlxValues) -> Fut,
+        Fut: Future<Output = Result<T, RepositoryError>>,
+    {
+        tracing::debug!(document_type = %document_type.id, sql = %sql, "Generated SQL");
+
+        let logged_sql = sql.clone();
+        let Some(threshold) = self.slow_query_threshold else {
+            return run(sql, values)
+                .await
+                .inspect_err(|_| note_failed_query(Some(document_type.id.as_ref()), &logged_sql));
+        };
+
+        let explain_values = self.explain_slow_queries.then(|| values.clone());
+        let started = Instant::now();
+        let result = run(sql, values).await;
+        let elapsed = started.elapsed();
+
+        if elapsed >= threshold {
+            let elapsed_ms = elapsed.as_millis() as u64;
+            match explain_values {
+                Some(values) => {
+                    let plan = match explain_analyze(pool, &logged_sql, values).await {
+                        Ok(plan) => plan.to_string(),
+                        Err(e) => format!("failed to explain the query: {}", e),
+                    };
+                    tracing::warn!(
+                        document_type = %document_type.id,
+                        elapsed_ms,
+                        sql = %logged_sql,
+                        plan = %plan,
+                        "Slow query"
+                    );
+                }
+                None => tracing::warn!(
+                    document_type = %document_type.id,
+                    elapsed_ms,
+                    sql = %logged_sql,
+                    "Slow query"
+                ),
+            }
+        }
+
+        if result.is_err() {
+            note_failed_query(Some(document_type.id.as_ref()), &logged_sql);
+        }
+        result
+    }
+}
+
+async fn explain_analyze(
+    pool: &PgPool,
+    sql: &str,
+    values: SqlxValues,
+) -> Result<serde_json::Value, sqlx::Error> {
+    let row = sqlx::query_with(
+        AssertSqlSafe(format!("EXPLAIN (ANALYZE, FORMAT JSON) {}", sql)),
+        values,
+    )
+    .fetch_one(pool)
+    .await?;
+    let plan: Json<serde_json::Value> = row.try_get(0)?;
+    Ok(plan.0)
+}
@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use axum_prometheus::metrics::counter;
+use luminair_common::database::current_tenant;
+use luminair_common::{DocumentType, DocumentTypeId};
+use moka::sync::Cache;
+use serde::Deserialize;
+
+use crate::domain::actor::ActorContext;
+use crate::domain::document::{DocumentInstance, DocumentInstanceId};
+use crate::domain::hooks::LifecycleHooks;
+use crate::domain::query::{DocumentInstanceQuery, DocumentStatus, FilterExpression};
+
+/// Settings of the in-process cache of single document lookups
+#[derive(Debug, Clone, Deserialize)]
+pub struct DocumentCacheSettings {
+    /// cache the published documents looked up by their id in memory
+    #[serde(default)]
+    pub enabled: bool,
+    /// most documents kept, the least recently used ones are evicted first
+    #[serde(default = "default_max_entries")]
+    pub max_entries: u64,
+    /// how long a document is served from the cache at most
+    #[serde(default = "default_ttl_seconds")]
+    pub ttl_seconds: u64,
+}
+
+impl Default for DocumentCacheSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: default_max_entries(),
+            ttl_seconds: default_ttl_seconds(),
+        }
+    }
+}
+
+fn default_max_entries() -> u64 {
+    10_000
+}
+
+fn default_ttl_seconds() -> u64 {
+    60
+}
+
+/// Bounded in-process cache of the published documents looked up by id.
+///
+/// Only published lookups are cached: the drafts are read by the writes,
+/// which must see the stored version. The cache is invalidated by the
+/// lifecycle hooks of the writes of this process; the writes of other
+/// replicas are only seen once the cached document expires.
+///
+/// Documents are cached per tenant, of the running task.
+///
+/// Lookups are counted in `document_cache_requests_total` by `result`,
+/// `hit` or `miss`.
+#[derive(Clone)]
+pub struct DocumentCache {
+    documents: Cache<DocumentKey, DocumentInstance>,
+}
+
+/// Tenant, document type and id of a cached document.
+type DocumentKey = (Option<String>, DocumentTypeId, DocumentInstanceId);
+
+impl DocumentCache {
+    pub fn new(settings: &DocumentCacheSettings) -> Self {
+        let documents = Cache::builder()
+            .max_capacity(settings.max_entries)
+            .time_to_live(Duration::from_secs(settings.ttl_seconds))
+            .build();
+        Self { documents }
+    }
+
+    /// Whether the documents found by `query` are cached.
+    pub fn caches(query: &DocumentInstanceQuery) -> bool {
+        query.status == DocumentStatus::Published && matches!(query.filter, FilterExpression::None)
+    }
+
+    /// The cached document `id` of `document_type`.
+    pub fn get(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+    ) -> Option<DocumentInstance> {
+        let document = self.documents.get(&key(document_type, id));
+        let result = if document.is_some() { "hit" } else { "miss" };
+        counter!("document_cache_requests_total", "result" => result).increment(1);
+        document
+    }
+
+    /// Cache `document` of `document_type`.
+    pub fn insert(&self, document_type: &DocumentType, document: DocumentInstance) {
+        self.documents
+            .insert(key(document_type, document.document_id), document);
+    }
+
+    /// Drop the cached document `id` of `document_type`.
+    pub fn invalidate(&self, document_type: &DocumentType, id: DocumentInstanceId) {
+        self.documents.invalidate(&key(document_type, id));
+    }
+}
+
+impl LifecycleHooks for DocumentCache {
+    fn after_update(
+        &self,
+        document_type: &DocumentType,
+        _actor: &ActorContext,
+        instance: &DocumentInstance,
+    ) {
+        self.invalidate(document_type, instance.document_id);
+    }
+
+    fn after_publish(
+        &self,
+        document_type: &DocumentType,
+        _actor: &ActorContext,
+        instance: &DocumentInstance,
+    ) {
+        self.invalidate(document_type, instance.document_id);
+    }
+
+    fn after_unpublish(
+        &self,
+        document_type: &DocumentType,
+        _actor: &ActorContext,
+        instance: &DocumentInstance,
+    ) {
+        self.invalidate(document_type, instance.document_id);
+    }
+
+    fn after_delete(
+        &self,
+        document_type: &DocumentType,
+        _actor: &ActorContext,
+        id: DocumentInstanceId,
+    ) {
+        self.invalidate(document_type, id);
+    }
+}
+
+fn key(document_type: &DocumentType, id: DocumentInstanceId) -> DocumentKey {
+    (current_tenant(), document_type.id.clone(), id)
+}
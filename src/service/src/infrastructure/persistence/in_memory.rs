@@ -0,0 +1,2413 @@
+//! An in-memory [`DocumentsRepository`], to unit test handlers and lifecycle
+//! hooks without a Postgres instance.
+//!
+//! Available only when the `test-helpers` feature is enabled.
+//! Never compiled into production builds.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, DefaultHasher, Hash, Hasher, RandomState};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use futures::{StreamExt, stream};
+use luminair_common::entities::{DocumentField, FieldType, OnDeleteAction};
+use luminair_common::{
+    AttributeId, DOCUMENT_ID_FIELD_NAME, DocumentType, DocumentTypeId, DocumentTypesRegistry,
+};
+use rust_decimal::Decimal;
+
+use crate::application::PaginationSettings;
+use crate::domain::actor::ActorContext;
+use crate::domain::document::content::{ContentValue, DomainValue};
+use crate::domain::document::lifecycle::{PublicationSchedule, PublicationState};
+use crate::domain::document::{DatabaseRowId, DocumentInstance, DocumentInstanceId};
+use crate::domain::hooks::{HookError, LifecycleHooks, LifecycleHooksRegistry};
+use crate::domain::query::{
+    AggregateFunction, AggregateGroup, AggregateQuery, DocumentInstanceQuery, DocumentStatus,
+    FilterExpression, PopulateOptions, SearchHit, Sort, SortDirection,
+};
+use crate::domain::repository::{
+    ArchiveRow, DocumentsRepository, InboundReference, RelationCounts, RelationMap, RelationOps,
+    RepositoryError,
+};
+use crate::domain::subject::{Erasure, SubjectDocuments, subject_hash};
+use crate::domain::translation::{
+    DocumentTranslations, LocaleTranslation, LocaleTranslationCounts, TranslationStatus,
+    changed_locales, locales, written_locales,
+};
+use crate::infrastructure::AppStateImpl;
+
+/// A [`DocumentsRepository`] keeping the documents in a `HashMap`.
+///
+/// It stores the documents like the Postgres repository does: the draft of
+/// every document, the published copy of those of draft-and-publish types,
+/// their revisions, schedules and relation links. Writes check the version
+/// they are based on and the unique fields, and call the lifecycle hooks of
+/// [`Self::with_hooks`].
+///
+/// Clones share their documents, so a test can keep one to arrange and
+/// inspect the documents of the application state it builds. Archives can't
+/// be exported or imported.
+///
+/// # Example
+/// ```rust,ignore
+/// let repository = InMemoryDocumentsRepository::new(registry).with_hooks(hooks);
+/// let state = AppStateImpl::new(registry, repository.clone(), Default::default());
+/// let router = api_routes().with_state(state);
+/// ```
+#[derive(Clone)]
+pub struct InMemoryDocumentsRepository {
+    schema_registry: &'static dyn DocumentTypesRegistry,
+    hooks: LifecycleHooksRegistry,
+    store: Arc<Mutex<Store>>,
+}
+
+impl InMemoryDocumentsRepository {
+    /// An empty repository of the document types of `schema_registry`.
+    pub fn new(schema_registry: &'static dyn DocumentTypesRegistry) -> Self {
+        Self {
+            schema_registry,
+            hooks: LifecycleHooksRegistry::default(),
+            store: Arc::default(),
+        }
+    }
+
+    /// Call the lifecycle hooks of `hooks` around the writes of their document types.
+    pub fn with_hooks(mut self, hooks: LifecycleHooksRegistry) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    fn store(&self) -> MutexGuard<'_, Store> {
+        self.store.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The document types a data subject is looked up in, by the order of
+    /// their ids.
+    fn subject_types(&self) -> Vec<&'static DocumentType> {
+        let mut types = self
+            .schema_registry
+            .iterate()
+            .filter(|document_type| document_type.view().is_none())
+            .collect::<Vec<_>>();
+        types.sort_by(|a, b| a.id.cmp(&b.id));
+        types
+    }
+
+    /// Call `before` on every lifecycle hooks of the document type, with a
+    /// copy of the instance about to be written, which they may change.
+    fn run_before_hooks(
+        &self,
+        document_type: &DocumentType,
+        instance: &DocumentInstance,
+        before: impl Fn(
+            &dyn LifecycleHooks,
+            &DocumentType,
+            &mut DocumentInstance,
+        ) -> Result<(), HookError>,
+    ) -> Result<DocumentInstance, RepositoryError> {
+        let mut instance = instance.clone();
+        for hooks in self.hooks.for_type(document_type) {
+            before(hooks.as_ref(), document_type, &mut instance)
+                .map_err(|e| RepositoryError::ValidationFailed(e.to_string()))?;
+        }
+        Ok(instance)
+    }
+
+    /// Resolve the target document type of an owning relation to populate.
+    fn owning_relation_target(
+        &self,
+        document_type: &DocumentType,
+        attr_id: &AttributeId,
+    ) -> Result<&'static DocumentType, RepositoryError> {
+        let relation = self
+            .schema_registry
+            .relation(&document_type.id, attr_id)
+            .ok_or_else(|| {
+                RepositoryError::ValidationFailed(format!("Relation not found: {}", attr_id))
+            })?;
+
+        if !relation.is_owning() {
+            return Err(RepositoryError::ValidationFailed(format!(
+                "Relation is not owning: {}",
+                attr_id
+            )));
+        }
+        Ok(relation.target)
+    }
+
+    /// Apply set / connect / disconnect relation operations to `links`,
+    /// failing on links to documents which don't exist.
+    fn write_relation_ops(
+        &self,
+        store: &Store,
+        document_type: &DocumentType,
+        links: &mut HashMap<AttributeId, Vec<DocumentInstanceId>>,
+        ops: &HashMap<AttributeId, RelationOps>,
+    ) -> Result<(), RepositoryError> {
+        for (attr_id, rel_ops) in ops {
+            let rel_meta = document_type.relations.get(attr_id).ok_or_else(|| {
+                RepositoryError::ValidationFailed(format!("Relation not found: {}", attr_id))
+            })?;
+            let related_type = self
+                .schema_registry
+                .get(&rel_meta.target)
+                .ok_or(RepositoryError::DocumentTypeNotFound)?;
+
+            let set = rel_ops.set.as_deref().unwrap_or_default();
+            let missing: Vec<String> = set
+                .iter()
+                .chain(&rel_ops.connect)
+                .filter(|id| store.document(related_type, **id).is_none())
+                .map(|id| id.0.to_string())
+                .collect();
+            if !missing.is_empty() {
+                return Err(RepositoryError::ValidationFailed(format!(
+                    "Relation constraint violation: '{}' targets unknown documents: {}",
+                    attr_id,
+                    missing.join(", ")
+                )));
+            }
+
+            let targets = links.entry(attr_id.clone()).or_default();
+            if rel_ops.set.is_some() {
+                targets.clear();
+            }
+            for id in set.iter().chain(&rel_ops.connect) {
+                if !targets.contains(id) {
+                    targets.push(*id);
+                }
+            }
+            targets.retain(|id| !rel_ops.disconnect.contains(id));
+        }
+        Ok(())
+    }
+
+    /// The documents linking to the document `id` per owning relation
+    /// targeting `document_type`, by document type and attribute, like
+    /// [`DocumentsRepository::find_references`] answers them.
+    fn references(
+        &self,
+        store: &Store,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+    ) -> Vec<InboundReference> {
+        let mut owners = self.schema_registry.iterate().collect::<Vec<_>>();
+        owners.sort_by(|a, b| a.id.as_ref().cmp(b.id.as_ref()));
+        let mut references = Vec::new();
+        for owner in owners {
+            for relation in self.schema_registry.relations_of(&owner.id) {
+                if !relation.is_owning() || relation.target.id != document_type.id {
+                    continue;
+                }
+                let mut document_ids = store
+                    .documents(owner)
+                    .iter()
+                    .filter(|stored| {
+                        [&stored.links, &stored.published_links]
+                            .iter()
+                            .any(|links| {
+                                links
+                                    .get(relation.id())
+                                    .is_some_and(|ids| ids.contains(&id))
+                            })
+                    })
+                    .map(|stored| stored.draft.document_id)
+                    .collect::<Vec<_>>();
+                if document_ids.is_empty() {
+                    continue;
+                }
+                document_ids.sort_by_key(|id| id.0);
+                references.push(InboundReference {
+                    document_type: relation.owner,
+                    relation: relation.id().clone(),
+                    on_delete: relation.relation.on_delete,
+                    document_ids,
+                });
+            }
+        }
+        references
+    }
+}
+
+impl DocumentsRepository for InMemoryDocumentsRepository {
+    async fn find(
+        &self,
+        document_type: &DocumentType,
+        query: &DocumentInstanceQuery,
+    ) -> Result<Vec<DocumentInstance>, RepositoryError> {
+        Ok(self.store().find(document_type, query))
+    }
+
+    async fn count(
+        &self,
+        document_type: &DocumentType,
+        query: &DocumentInstanceQuery,
+    ) -> Result<u64, RepositoryError> {
+        let store = self.store();
+        Ok(store.sampled(document_type, query).len() as u64)
+    }
+
+    fn stream(
+        &self,
+        document_type: &'static DocumentType,
+        query: &DocumentInstanceQuery,
+    ) -> BoxStream<'static, Result<DocumentInstance, RepositoryError>> {
+        let documents = self.store().find(document_type, query);
+        stream::iter(documents.into_iter().map(Ok)).boxed()
+    }
+
+    async fn find_by_id(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+        query: &DocumentInstanceQuery,
+    ) -> Result<Option<DocumentInstance>, RepositoryError> {
+        Ok(self
+            .store()
+            .document(document_type, id)
+            .and_then(|stored| stored.row(document_type, query.status))
+            .cloned())
+    }
+
+    async fn exists(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+        status: DocumentStatus,
+    ) -> Result<bool, RepositoryError> {
+        Ok(self
+            .store()
+            .document(document_type, id)
+            .and_then(|stored| stored.row(document_type, status))
+            .is_some())
+    }
+
+    async fn fetch_relations(
+        &self,
+        document_type: &DocumentType,
+        fields: &[AttributeId],
+        filters: &HashMap<AttributeId, FilterExpression>,
+        options: &HashMap<AttributeId, PopulateOptions>,
+        status: DocumentStatus,
+        ids: &[DocumentInstanceId],
+    ) -> Result<RelationMap, RepositoryError> {
+        let store = self.store();
+        let mut result = HashMap::new();
+        let default_options = PopulateOptions::default();
+
+        for attr_id in fields {
+            let related_document_type = self.owning_relation_target(document_type, attr_id)?;
+            let rel_filter = filters.get(attr_id).unwrap_or(&FilterExpression::None);
+            let rel_options = options.get(attr_id).unwrap_or(&default_options);
+
+            let mut grouped = HashMap::new();
+            for id in ids {
+                let Some(stored) = store.document(document_type, *id) else {
+                    continue;
+                };
+                let related = populated(
+                    store.related(
+                        document_type,
+                        status,
+                        stored,
+                        attr_id,
+                        related_document_type,
+                        rel_filter,
+                    ),
+                    rel_options,
+                );
+                if !related.is_empty() {
+                    grouped.insert(*id, related);
+                }
+            }
+            result.insert(attr_id.clone(), grouped);
+        }
+
+        Ok(result)
+    }
+
+    async fn count_relations(
+        &self,
+        document_type: &DocumentType,
+        fields: &[AttributeId],
+        status: DocumentStatus,
+        ids: &[DocumentInstanceId],
+    ) -> Result<RelationCounts, RepositoryError> {
+        let store = self.store();
+        let mut result = HashMap::new();
+
+        for attr_id in fields {
+            self.owning_relation_target(document_type, attr_id)?;
+
+            let mut counts = HashMap::new();
+            for id in ids {
+                let Some(stored) = store.document(document_type, *id) else {
+                    continue;
+                };
+                let count = stored.links(document_type, status, attr_id).len() as u64;
+                if count > 0 {
+                    counts.insert(*id, count);
+                }
+            }
+            result.insert(attr_id.clone(), counts);
+        }
+
+        Ok(result)
+    }
+
+    async fn find_with_relations(
+        &self,
+        document_type: &DocumentType,
+        query: &DocumentInstanceQuery,
+        fields: &[AttributeId],
+        filters: &HashMap<AttributeId, FilterExpression>,
+        options: &HashMap<AttributeId, PopulateOptions>,
+    ) -> Result<Vec<DocumentInstance>, RepositoryError> {
+        let default_options = PopulateOptions::default();
+        let relations = fields
+            .iter()
+            .map(|attr_id| {
+                Ok((
+                    attr_id,
+                    self.owning_relation_target(document_type, attr_id)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, RepositoryError>>()?;
+
+        let store = self.store();
+        let documents = store.find(document_type, query);
+        Ok(documents
+            .into_iter()
+            .map(|document| {
+                let stored = store.document(document_type, document.document_id);
+                let populated = relations
+                    .iter()
+                    .map(|(attr_id, target)| {
+                        let filter = filters.get(*attr_id).unwrap_or(&FilterExpression::None);
+                        let options = options.get(*attr_id).unwrap_or(&default_options);
+                        let related = stored
+                            .map(|stored| {
+                                store.related(
+                                    document_type,
+                                    query.status,
+                                    stored,
+                                    attr_id,
+                                    target,
+                                    filter,
+                                )
+                            })
+                            .unwrap_or_default();
+                        let related = populated(related, options);
+                        ((*attr_id).clone(), related)
+                    })
+                    .collect();
+                document.with_relations(populated)
+            })
+            .collect())
+    }
+
+    async fn aggregate(
+        &self,
+        document_type: &DocumentType,
+        query: &AggregateQuery,
+    ) -> Result<Vec<AggregateGroup>, RepositoryError> {
+        let store = self.store();
+        let rows = store.matching(document_type, query.status, &query.filter);
+
+        let mut groups: Vec<(Vec<Option<DomainValue>>, Vec<&DocumentInstance>)> = Vec::new();
+        for row in rows {
+            let key: Vec<_> = query
+                .group_by
+                .iter()
+                .map(|attr_id| field_value(row, attr_id))
+                .collect();
+            match groups.iter_mut().find(|(group, _)| *group == key) {
+                Some((_, members)) => members.push(row),
+                None => groups.push((key, vec![row])),
+            }
+        }
+        // without grouping, the metrics of no documents are a group too
+        if query.group_by.is_empty() && groups.is_empty() {
+            groups.push((Vec::new(), Vec::new()));
+        }
+        groups.sort_by(|(a, _), (b, _)| {
+            a.iter()
+                .zip(b)
+                .map(|(a, b)| compare_nullable(a.as_ref(), b.as_ref()))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+
+        Ok(groups
+            .into_iter()
+            .map(|(key, members)| AggregateGroup {
+                group: query
+                    .group_by
+                    .iter()
+                    .cloned()
+                    .zip(key)
+                    .map(|(attr_id, value)| {
+                        (
+                            attr_id,
+                            value.map_or(ContentValue::Null, ContentValue::Scalar),
+                        )
+                    })
+                    .collect(),
+                metrics: query
+                    .metrics
+                    .iter()
+                    .map(|metric| {
+                        let value = aggregate(metric.function, metric.field.as_ref(), &members);
+                        (metric.clone(), value)
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+
+    /// Matches the documents holding every word of `text` in their text
+    /// fields, ranked by the occurrences; the web search syntax beyond words
+    /// isn't supported.
+    async fn search(
+        &self,
+        document_types: &[&DocumentType],
+        text: &str,
+        limit: u64,
+    ) -> Result<Vec<SearchHit>, RepositoryError> {
+        let words = text
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+        let store = self.store();
+        let mut hits = Vec::new();
+        for document_type in document_types {
+            let rows = store.matching(
+                document_type,
+                DocumentStatus::Published,
+                &FilterExpression::None,
+            );
+            for row in rows {
+                let texts = searchable_texts(document_type, row);
+                let occurrences = words
+                    .iter()
+                    .map(|word| {
+                        texts
+                            .iter()
+                            .map(|text| text.matches(word.as_str()).count())
+                            .sum::<usize>()
+                    })
+                    .collect::<Vec<_>>();
+                if occurrences.contains(&0) {
+                    continue;
+                }
+                hits.push(SearchHit {
+                    document_type: document_type.id.clone(),
+                    document_id: row.document_id,
+                    rank: occurrences.iter().sum::<usize>() as f32,
+                });
+            }
+        }
+        hits.sort_by(|a, b| {
+            b.rank
+                .total_cmp(&a.rank)
+                .then_with(|| a.document_type.as_ref().cmp(b.document_type.as_ref()))
+                .then_with(|| a.document_id.0.cmp(&b.document_id.0))
+        });
+        hits.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+        Ok(hits)
+    }
+
+    async fn find_revisions(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+    ) -> Result<Vec<DocumentInstance>, RepositoryError> {
+        Ok(self
+            .store()
+            .document(document_type, id)
+            .map(|stored| stored.revisions.iter().rev().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn find_references(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+    ) -> Result<Vec<InboundReference>, RepositoryError> {
+        Ok(self.references(&self.store(), document_type, id))
+    }
+
+    async fn find_revision(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+        version: i32,
+    ) -> Result<Option<DocumentInstance>, RepositoryError> {
+        Ok(self.store().document(document_type, id).and_then(|stored| {
+            stored
+                .revisions
+                .iter()
+                .find(|revision| revision.audit.version == version)
+                .cloned()
+        }))
+    }
+
+    async fn find_scheduled(
+        &self,
+        document_type: &DocumentType,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<(DocumentInstanceId, PublicationSchedule)>, RepositoryError> {
+        let is_due = |at: Option<DateTime<Utc>>| at.is_some_and(|at| at <= until);
+        Ok(self
+            .store()
+            .documents(document_type)
+            .iter()
+            .filter(|stored| {
+                is_due(stored.schedule.publish_at) || is_due(stored.schedule.unpublish_at)
+            })
+            .map(|stored| (stored.draft.document_id, stored.schedule))
+            .collect())
+    }
+
+    async fn insert(
+        &self,
+        document_type: &DocumentType,
+        instance: &DocumentInstance,
+        relations: &HashMap<AttributeId, RelationOps>,
+        actor: &ActorContext,
+    ) -> Result<DocumentInstance, RepositoryError> {
+        let created = {
+            let mut store = self.store();
+            let instance =
+                self.run_before_hooks(document_type, instance, |hooks, dt, instance| {
+                    hooks.before_create(dt, actor, instance)
+                })?;
+            if store
+                .document(document_type, instance.document_id)
+                .is_some()
+            {
+                return Err(RepositoryError::UniqueViolation(format!(
+                    "Document {} exists already",
+                    instance.document_id.0
+                )));
+            }
+            store.check_unique(document_type, &instance)?;
+
+            let mut links = HashMap::new();
+            self.write_relation_ops(&store, document_type, &mut links, relations)?;
+
+            store.next_row_id += 1;
+            let created = DocumentInstance {
+                id: DatabaseRowId(store.next_row_id),
+                relations: HashMap::new(),
+                ..instance
+            };
+            let locales_updated_at = written_locales(document_type, &created.content)
+                .into_iter()
+                .map(|locale| (locale, created.audit.created_at))
+                .collect();
+            store
+                .documents
+                .entry(document_type.id.clone())
+                .or_default()
+                .push(StoredDocument {
+                    draft: created.clone(),
+                    published: None,
+                    revisions: Vec::new(),
+                    schedule: PublicationSchedule::default(),
+                    links,
+                    published_links: HashMap::new(),
+                    locales_updated_at,
+                });
+            created
+        };
+
+        for hooks in self.hooks.for_type(document_type) {
+            hooks.after_create(document_type, actor, &created);
+        }
+        Ok(created)
+    }
+
+    async fn update(
+        &self,
+        document_type: &DocumentType,
+        instance: &DocumentInstance,
+        expected_version: i32,
+        relations: &HashMap<AttributeId, RelationOps>,
+        actor: &ActorContext,
+    ) -> Result<(), RepositoryError> {
+        let is_publishing = document_type.has_draft_and_publish()
+            && matches!(
+                instance.content.publication_state,
+                PublicationState::Published { .. }
+            );
+
+        let instance = {
+            let mut store = self.store();
+            let mut links = store
+                .locked(document_type, instance.document_id, expected_version)?
+                .links
+                .clone();
+            let instance =
+                self.run_before_hooks(document_type, instance, |hooks, dt, instance| {
+                    if is_publishing {
+                        hooks.before_publish(dt, actor, instance)
+                    } else {
+                        hooks.before_update(dt, actor, instance)
+                    }
+                })?;
+            store.check_unique(document_type, &instance)?;
+            self.write_relation_ops(&store, document_type, &mut links, relations)?;
+
+            let stored = store
+                .document_mut(document_type, instance.document_id)
+                .ok_or(RepositoryError::Conflict(expected_version))?;
+            stored.revisions.push(stored.draft.clone());
+            stored.links = links;
+            if is_publishing {
+                // the draft keeps its content, the published copy gets the
+                // content and the links of the instance
+                write_metadata(&mut stored.draft, document_type, &instance);
+                stored.published = Some(DocumentInstance {
+                    id: stored.draft.id,
+                    relations: HashMap::new(),
+                    workflow_stage: None,
+                    ..instance.clone()
+                });
+                stored.published_links = stored.links.clone();
+            } else {
+                for locale in
+                    changed_locales(document_type, &stored.draft.content, &instance.content)
+                {
+                    stored
+                        .locales_updated_at
+                        .insert(locale, instance.audit.updated_at);
+                }
+                stored.draft = DocumentInstance {
+                    id: stored.draft.id,
+                    relations: HashMap::new(),
+                    workflow_stage: stored.draft.workflow_stage.clone(),
+                    ..instance.clone()
+                };
+            }
+            stored.refresh_modified();
+            instance
+        };
+
+        for hooks in self.hooks.for_type(document_type) {
+            if is_publishing {
+                hooks.after_publish(document_type, actor, &instance);
+            } else {
+                hooks.after_update(document_type, actor, &instance);
+            }
+        }
+        Ok(())
+    }
+
+    async fn publish(
+        &self,
+        document_type: &DocumentType,
+        instance: &DocumentInstance,
+        expected_version: i32,
+        unpublish_at: Option<DateTime<Utc>>,
+        actor: &ActorContext,
+    ) -> Result<(), RepositoryError> {
+        self.update(
+            document_type,
+            instance,
+            expected_version,
+            &HashMap::new(),
+            actor,
+        )
+        .await?;
+        let mut store = self.store();
+        let stored = store
+            .document_mut(document_type, instance.document_id)
+            .ok_or(RepositoryError::DocumentInstanceNotFound)?;
+        stored.schedule.publish_at = None;
+        if unpublish_at.is_some() {
+            stored.schedule.unpublish_at = unpublish_at;
+        }
+        Ok(())
+    }
+
+    async fn unpublish(
+        &self,
+        document_type: &DocumentType,
+        instance: &DocumentInstance,
+        expected_version: i32,
+        actor: &ActorContext,
+    ) -> Result<(), RepositoryError> {
+        let instance = {
+            let mut store = self.store();
+            store.locked(document_type, instance.document_id, expected_version)?;
+            let instance =
+                self.run_before_hooks(document_type, instance, |hooks, dt, instance| {
+                    hooks.before_unpublish(dt, actor, instance)
+                })?;
+
+            let stored = store
+                .document_mut(document_type, instance.document_id)
+                .ok_or(RepositoryError::Conflict(expected_version))?;
+            stored.revisions.push(stored.draft.clone());
+            write_metadata(&mut stored.draft, document_type, &instance);
+            stored.published = None;
+            stored.published_links.clear();
+            stored.refresh_modified();
+            instance
+        };
+
+        for hooks in self.hooks.for_type(document_type) {
+            hooks.after_unpublish(document_type, actor, &instance);
+        }
+        Ok(())
+    }
+
+    async fn move_to_stage(
+        &self,
+        document_type: &DocumentType,
+        instance: &DocumentInstance,
+        expected_version: i32,
+        actor: &ActorContext,
+    ) -> Result<(), RepositoryError> {
+        let instance = {
+            let mut store = self.store();
+            store.locked(document_type, instance.document_id, expected_version)?;
+            let instance =
+                self.run_before_hooks(document_type, instance, |hooks, dt, instance| {
+                    hooks.before_update(dt, actor, instance)
+                })?;
+
+            let stored = store
+                .document_mut(document_type, instance.document_id)
+                .ok_or(RepositoryError::Conflict(expected_version))?;
+            stored.revisions.push(stored.draft.clone());
+            write_metadata(&mut stored.draft, document_type, &instance);
+            stored.refresh_modified();
+            instance
+        };
+
+        for hooks in self.hooks.for_type(document_type) {
+            hooks.after_update(document_type, actor, &instance);
+        }
+        Ok(())
+    }
+
+    async fn schedule(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+        schedule: &PublicationSchedule,
+    ) -> Result<(), RepositoryError> {
+        let mut store = self.store();
+        let stored = store
+            .document_mut(document_type, id)
+            .ok_or(RepositoryError::DocumentInstanceNotFound)?;
+        stored.schedule = *schedule;
+        Ok(())
+    }
+
+    async fn schedule_publish(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+        publish_at: DateTime<Utc>,
+        unpublish_at: Option<DateTime<Utc>>,
+    ) -> Result<(), RepositoryError> {
+        let mut store = self.store();
+        let stored = store
+            .document_mut(document_type, id)
+            .ok_or(RepositoryError::DocumentInstanceNotFound)?;
+        stored.schedule.publish_at = Some(publish_at);
+        if unpublish_at.is_some() {
+            stored.schedule.unpublish_at = unpublish_at;
+        }
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+        actor: &ActorContext,
+    ) -> Result<(), RepositoryError> {
+        {
+            let mut store = self.store();
+            if store.document(document_type, id).is_none() {
+                return Err(RepositoryError::DocumentInstanceNotFound);
+            }
+            for hooks in self.hooks.for_type(document_type) {
+                hooks
+                    .before_delete(document_type, actor, id)
+                    .map_err(|e| RepositoryError::ValidationFailed(e.to_string()))?;
+            }
+            let references = self.references(&store, document_type, id);
+            let restricting = references
+                .iter()
+                .filter(|reference| reference.on_delete == OnDeleteAction::Restrict)
+                .cloned()
+                .collect::<Vec<_>>();
+            if !restricting.is_empty() {
+                return Err(RepositoryError::Referenced(restricting));
+            }
+            let now = Utc::now();
+            for reference in references {
+                if !reference.on_delete.detaches() {
+                    continue;
+                }
+                for linking_id in reference.document_ids {
+                    if let Some(stored) = store.document_mut(reference.document_type, linking_id) {
+                        stored.draft.audit.updated_at = now;
+                        stored.refresh_modified();
+                    }
+                }
+            }
+
+            if let Some(documents) = store.documents.get_mut(&document_type.id) {
+                documents.retain(|stored| stored.draft.document_id != id);
+            }
+            // the links to the document go with it
+            for stored in store.documents.values_mut().flatten() {
+                for ids in stored
+                    .links
+                    .values_mut()
+                    .chain(stored.published_links.values_mut())
+                {
+                    ids.retain(|target| *target != id);
+                }
+            }
+        }
+
+        for hooks in self.hooks.for_type(document_type) {
+            hooks.after_delete(document_type, actor, id);
+        }
+        Ok(())
+    }
+
+    fn export_archive(&self) -> BoxStream<'static, Result<ArchiveRow, RepositoryError>> {
+        stream::once(async { Err(unsupported("Exporting an archive")) }).boxed()
+    }
+
+    async fn import_archive(
+        &self,
+        _rows: Vec<ArchiveRow>,
+    ) -> Result<BTreeMap<String, u64>, RepositoryError> {
+        Err(unsupported("Importing an archive"))
+    }
+
+    /// Links are kept by the documents owning them and removed with the
+    /// documents they link to, so none are ever orphaned.
+    async fn clean_orphaned_links(
+        &self,
+        _remove: bool,
+        _batch_size: u64,
+    ) -> Result<BTreeMap<String, u64>, RepositoryError> {
+        Ok(BTreeMap::new())
+    }
+
+    /// There's no archived table to move the documents to, archiving ones
+    /// are removed like the deleted ones; batches don't apply.
+    async fn apply_retention(
+        &self,
+        now: DateTime<Utc>,
+        dry_run: bool,
+        _batch_size: u64,
+    ) -> Result<BTreeMap<String, u64>, RepositoryError> {
+        let mut store = self.store();
+        let mut expired = BTreeMap::new();
+        let mut removed = Vec::new();
+        for document_type in self.schema_registry.iterate() {
+            let Some(retention) = document_type.retention() else {
+                continue;
+            };
+            let before = now - chrono::Duration::days(i64::from(retention.days()));
+            let Some(documents) = store.documents.get_mut(&document_type.id) else {
+                continue;
+            };
+            let ids = documents
+                .iter()
+                .filter(|stored| stored.draft.audit.created_at < before)
+                .map(|stored| stored.draft.document_id)
+                .collect::<Vec<_>>();
+            if ids.is_empty() {
+                continue;
+            }
+            if !dry_run {
+                documents.retain(|stored| !ids.contains(&stored.draft.document_id));
+            }
+            expired.insert(document_type.id.to_string(), ids.len() as u64);
+            removed.extend(ids);
+        }
+        if !dry_run {
+            for stored in store.documents.values_mut().flatten() {
+                for ids in stored
+                    .links
+                    .values_mut()
+                    .chain(stored.published_links.values_mut())
+                {
+                    ids.retain(|target| !removed.contains(target));
+                }
+            }
+        }
+        Ok(expired)
+    }
+
+    async fn find_subject_documents(
+        &self,
+        value: &str,
+    ) -> Result<Vec<SubjectDocuments>, RepositoryError> {
+        let store = self.store();
+        Ok(self
+            .subject_types()
+            .into_iter()
+            .filter_map(|document_type| {
+                let fields = subject_fields(document_type);
+                let document_ids = store
+                    .documents(document_type)
+                    .iter()
+                    .filter(|stored| stored.holds(&fields, value))
+                    .map(|stored| stored.draft.document_id)
+                    .collect::<Vec<_>>();
+                (!document_ids.is_empty()).then_some(SubjectDocuments {
+                    document_type,
+                    document_ids,
+                })
+            })
+            .collect())
+    }
+
+    /// Erasures are numbered, not kept; no lifecycle hooks are called.
+    async fn erase_subject(
+        &self,
+        value: &str,
+        actor: &ActorContext,
+    ) -> Result<Erasure, RepositoryError> {
+        let mut store = self.store();
+        let now = Utc::now();
+        let mut subjects = Vec::new();
+        for document_type in self.subject_types() {
+            let fields = subject_fields(document_type);
+            let Some(documents) = store.documents.get_mut(&document_type.id) else {
+                continue;
+            };
+            let mut document_ids = Vec::new();
+            for stored in documents.iter_mut() {
+                if stored.holds(&fields, value) {
+                    stored.erase(document_type, now);
+                    document_ids.push(stored.draft.document_id);
+                }
+            }
+            if !document_ids.is_empty() {
+                subjects.push(SubjectDocuments {
+                    document_type,
+                    document_ids,
+                });
+            }
+        }
+        store.next_erasure_id += 1;
+        Ok(Erasure {
+            id: store.next_erasure_id,
+            subject: subject_hash(value),
+            documents: subjects,
+            erased_by: actor.user_id.as_ref().map(ToString::to_string),
+            erased_at: now,
+        })
+    }
+
+    async fn translation_status(
+        &self,
+        document_type: &DocumentType,
+        offset: u64,
+        limit: u64,
+    ) -> Result<TranslationStatus, RepositoryError> {
+        let store = self.store();
+        let mut translations = store
+            .documents(document_type)
+            .iter()
+            .map(|stored| DocumentTranslations {
+                document_id: stored.draft.document_id,
+                locales: locales(document_type)
+                    .map(|locale| LocaleTranslation {
+                        present: written_locales(document_type, &stored.draft.content)
+                            .contains(&locale),
+                        updated_at: stored.locales_updated_at.get(&locale).copied(),
+                        locale,
+                    })
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        translations.sort_by_key(|translation| translation.document_id.0);
+
+        let mut counts = locales(document_type)
+            .map(LocaleTranslationCounts::new)
+            .collect::<Vec<_>>();
+        for translation in &translations {
+            for (locale, counts) in translation.locales.iter().zip(counts.iter_mut()) {
+                counts.add(translation.state(locale));
+            }
+        }
+        let documents = translations
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect();
+        Ok(TranslationStatus { documents, counts })
+    }
+}
+
+impl AppStateImpl<InMemoryDocumentsRepository> {
+    /// Application state over an empty [`InMemoryDocumentsRepository`] of the
+    /// document types of `types`, with the default pagination.
+    ///
+    /// Use [`AppStateImpl::new`] with a repository of its own to arrange
+    /// documents or register lifecycle hooks.
+    pub fn in_memory(types: &'static dyn DocumentTypesRegistry) -> Self {
+        Self::new(
+            types,
+            InMemoryDocumentsRepository::new(types),
+            PaginationSettings::default(),
+        )
+    }
+}
+
+/// The documents of every document type.
+#[derive(Default)]
+struct Store {
+    documents: HashMap<DocumentTypeId, Vec<StoredDocument>>,
+    next_row_id: i64,
+    next_erasure_id: i64,
+}
+
+/// A document with everything stored for it.
+struct StoredDocument {
+    /// the main row, the draft of draft-and-publish types
+    draft: DocumentInstance,
+    /// the published copy of draft-and-publish types, if published
+    published: Option<DocumentInstance>,
+    /// the former contents of the draft, the oldest first
+    revisions: Vec<DocumentInstance>,
+    schedule: PublicationSchedule,
+    links: HashMap<AttributeId, Vec<DocumentInstanceId>>,
+    /// the links of the published copy
+    published_links: HashMap<AttributeId, Vec<DocumentInstanceId>>,
+    /// when the texts of each locale of the draft last changed
+    locales_updated_at: HashMap<String, DateTime<Utc>>,
+}
+
+impl StoredDocument {
+    /// The row of the document read with `status`, if there is one.
+    fn row(
+        &self,
+        document_type: &DocumentType,
+        status: DocumentStatus,
+    ) -> Option<&DocumentInstance> {
+        if status == DocumentStatus::Published && document_type.has_draft_and_publish() {
+            self.published.as_ref()
+        } else {
+            Some(&self.draft)
+        }
+    }
+
+    /// The targets of the `relation` links of the document read with `status`.
+    fn links(
+        &self,
+        document_type: &DocumentType,
+        status: DocumentStatus,
+        relation: &AttributeId,
+    ) -> &[DocumentInstanceId] {
+        let links = if status == DocumentStatus::Published && document_type.has_draft_and_publish()
+        {
+            &self.published_links
+        } else {
+            &self.links
+        };
+        links.get(relation).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Whether a personal text field among `fields` holds `value`, ignoring
+    /// case, in the draft, the published copy or a revision.
+    fn holds(&self, fields: &[&DocumentField], value: &str) -> bool {
+        let value = value.to_lowercase();
+        std::iter::once(&self.draft)
+            .chain(&self.published)
+            .chain(&self.revisions)
+            .any(|instance| {
+                fields.iter().any(|field| {
+                    let text = instance
+                        .content
+                        .fields
+                        .get(&field.id)
+                        .map(serde_json::Value::from);
+                    matches!(text, Some(serde_json::Value::String(text)) if text.to_lowercase() == value)
+                })
+            })
+    }
+
+    /// Clear the personal fields everywhere they are stored, as a new
+    /// version of the draft.
+    fn erase(&mut self, document_type: &DocumentType, now: DateTime<Utc>) {
+        let personal = document_type.personal_fields();
+        for instance in std::iter::once(&mut self.draft)
+            .chain(&mut self.published)
+            .chain(&mut self.revisions)
+        {
+            for field in &personal {
+                instance
+                    .content
+                    .fields
+                    .insert(field.id.clone(), ContentValue::Null);
+            }
+        }
+        self.draft.audit.version += 1;
+        self.draft.audit.updated_at = now;
+        self.refresh_modified();
+    }
+
+    /// Note whether the draft was saved after the published copy.
+    fn refresh_modified(&mut self) {
+        let published_at = self.published.as_ref().and_then(|published| {
+            match &published.content.publication_state {
+                PublicationState::Published { published_at, .. } => Some(*published_at),
+                PublicationState::Draft { .. } => None,
+            }
+        });
+        self.draft.modified = matches!(
+            self.draft.content.publication_state,
+            PublicationState::Draft { .. }
+        ) && published_at
+            .is_some_and(|published_at| published_at < self.draft.audit.updated_at);
+    }
+}
+
+impl Store {
+    fn documents(&self, document_type: &DocumentType) -> &[StoredDocument] {
+        self.documents
+            .get(&document_type.id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn document(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+    ) -> Option<&StoredDocument> {
+        self.documents(document_type)
+            .iter()
+            .find(|stored| stored.draft.document_id == id)
+    }
+
+    fn document_mut(
+        &mut self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+    ) -> Option<&mut StoredDocument> {
+        self.documents
+            .get_mut(&document_type.id)?
+            .iter_mut()
+            .find(|stored| stored.draft.document_id == id)
+    }
+
+    /// The document `id`, as long as it still has the version the write is
+    /// based on.
+    fn locked(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+        expected_version: i32,
+    ) -> Result<&StoredDocument, RepositoryError> {
+        self.document(document_type, id)
+            .filter(|stored| stored.draft.audit.version == expected_version)
+            .ok_or(RepositoryError::Conflict(expected_version))
+    }
+
+    /// Fail when another document has the value of a unique field of `instance`,
+    /// the text of a locale for localized fields.
+    fn check_unique(
+        &self,
+        document_type: &DocumentType,
+        instance: &DocumentInstance,
+    ) -> Result<(), RepositoryError> {
+        for field in document_type.fields.iter().filter(|field| field.unique) {
+            let Some(value) = instance.content.fields.get(&field.id) else {
+                continue;
+            };
+            let taken = self
+                .documents(document_type)
+                .iter()
+                .filter(|stored| stored.draft.document_id != instance.document_id)
+                .any(
+                    |stored| match (value, stored.draft.content.fields.get(&field.id)) {
+                        (ContentValue::Scalar(value), Some(ContentValue::Scalar(other))) => {
+                            value == other
+                        }
+                        (
+                            ContentValue::LocalizedText(texts),
+                            Some(ContentValue::LocalizedText(others)),
+                        ) => texts
+                            .iter()
+                            .any(|(locale, text)| others.get(locale) == Some(text)),
+                        _ => false,
+                    },
+                );
+            if taken {
+                return Err(RepositoryError::UniqueViolation(format!(
+                    "Another document has the value of the unique field '{}'",
+                    field.id
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The rows read with `status` which match `filter`, in storage order.
+    fn matching(
+        &self,
+        document_type: &DocumentType,
+        status: DocumentStatus,
+        filter: &FilterExpression,
+    ) -> Vec<&DocumentInstance> {
+        self.documents(document_type)
+            .iter()
+            .filter_map(|stored| Some((stored, stored.row(document_type, status)?)))
+            .filter(|(stored, row)| self.matches(document_type, status, stored, row, filter))
+            .map(|(_, row)| row)
+            .collect()
+    }
+
+    /// The rows of `query` in the sample it is drawn from, if any, in
+    /// storage order.
+    fn sampled(
+        &self,
+        document_type: &DocumentType,
+        query: &DocumentInstanceQuery,
+    ) -> Vec<&DocumentInstance> {
+        let mut rows = self.matching(document_type, query.status, &query.filter);
+        if let Some(sample) = query.sample {
+            let random = RandomState::new();
+            let threshold = (sample.percent / 100.0 * u64::MAX as f64) as u64;
+            rows.retain(|row| random_draw(row.document_id, sample.seed, &random) <= threshold);
+        }
+        rows
+    }
+
+    /// The rows of `query`, sorted and paginated.
+    fn find(
+        &self,
+        document_type: &DocumentType,
+        query: &DocumentInstanceQuery,
+    ) -> Vec<DocumentInstance> {
+        let mut rows = self.sampled(document_type, query);
+        rows.sort_by(|a, b| compare_rows(a, b, &query.sort));
+        if let Some(random_order) = query.random_order {
+            let random = RandomState::new();
+            rows.sort_by_cached_key(|row| random_draw(row.document_id, random_order.seed, &random));
+        }
+
+        let offset = query.offset.unwrap_or_default().max(0) as usize;
+        let limit = query
+            .limit
+            .map_or(usize::MAX, |limit| limit.max(0) as usize);
+        rows.into_iter().skip(offset).take(limit).cloned().collect()
+    }
+
+    /// The documents of `target` the `relation` of `stored` links to, read
+    /// with `status` and matching `filter`.
+    fn related(
+        &self,
+        document_type: &DocumentType,
+        status: DocumentStatus,
+        stored: &StoredDocument,
+        relation: &AttributeId,
+        target: &DocumentType,
+        filter: &FilterExpression,
+    ) -> Vec<DocumentInstance> {
+        stored
+            .links(document_type, status, relation)
+            .iter()
+            .filter_map(|id| self.document(target, *id))
+            .filter_map(|related| Some((related, related.row(target, status)?)))
+            .filter(|(related, row)| self.matches(target, status, related, row, filter))
+            .map(|(_, row)| row.clone())
+            .collect()
+    }
+
+    /// Whether `row` of `stored` matches `filter`, with the semantics of SQL:
+    /// comparisons with a missing value are false.
+    fn matches(
+        &self,
+        document_type: &DocumentType,
+        status: DocumentStatus,
+        stored: &StoredDocument,
+        row: &DocumentInstance,
+        filter: &FilterExpression,
+    ) -> bool {
+        let compare_to = |field: &str, value: &DomainValue| compare(&value_of(row, field)?, value);
+        let text_matches = |field: &str, matches: &dyn Fn(&str) -> bool| {
+            value_of(row, field)
+                .as_ref()
+                .and_then(text_of)
+                .is_some_and(matches)
+        };
+
+        match filter {
+            FilterExpression::None => true,
+            FilterExpression::Equals { field, value } => {
+                compare_to(field, value) == Some(Ordering::Equal)
+            }
+            FilterExpression::NotEquals { field, value } => {
+                compare_to(field, value).is_some_and(Ordering::is_ne)
+            }
+            FilterExpression::GreaterThan { field, value } => {
+                compare_to(field, value) == Some(Ordering::Greater)
+            }
+            FilterExpression::GreaterThanOrEqual { field, value } => {
+                compare_to(field, value).is_some_and(Ordering::is_ge)
+            }
+            FilterExpression::LessThan { field, value } => {
+                compare_to(field, value) == Some(Ordering::Less)
+            }
+            FilterExpression::LessThanOrEqual { field, value } => {
+                compare_to(field, value).is_some_and(Ordering::is_le)
+            }
+            FilterExpression::In { field, values } => values
+                .iter()
+                .any(|value| compare_to(field, value) == Some(Ordering::Equal)),
+            FilterExpression::NotIn { field, values } => values
+                .iter()
+                .all(|value| compare_to(field, value).is_some_and(Ordering::is_ne)),
+            FilterExpression::Between { field, from, to } => {
+                compare_to(field, from).is_some_and(Ordering::is_ge)
+                    && compare_to(field, to).is_some_and(Ordering::is_le)
+            }
+            FilterExpression::Contains { field, value } => {
+                text_matches(field, &|text| text.contains(value.as_str()))
+            }
+            FilterExpression::StartsWith { field, value } => {
+                text_matches(field, &|text| text.starts_with(value.as_str()))
+            }
+            FilterExpression::EndsWith { field, value } => {
+                text_matches(field, &|text| text.ends_with(value.as_str()))
+            }
+            FilterExpression::IsNull { field } => value_of(row, field).is_none(),
+            FilterExpression::IsNotNull { field } => value_of(row, field).is_some(),
+            FilterExpression::HasRelation {
+                field,
+                target,
+                filter,
+            } => !self
+                .related(document_type, status, stored, field, target, filter)
+                .is_empty(),
+            FilterExpression::Modified => status == DocumentStatus::Draft && row.modified,
+            FilterExpression::And(a, b) => {
+                self.matches(document_type, status, stored, row, a)
+                    && self.matches(document_type, status, stored, row, b)
+            }
+            FilterExpression::Or(a, b) => {
+                self.matches(document_type, status, stored, row, a)
+                    || self.matches(document_type, status, stored, row, b)
+            }
+        }
+    }
+}
+
+/// Write the metadata of `instance` to `row`, leaving its content as it is.
+fn write_metadata(
+    row: &mut DocumentInstance,
+    document_type: &DocumentType,
+    instance: &DocumentInstance,
+) {
+    row.audit.updated_at = instance.audit.updated_at;
+    row.audit.version = instance.audit.version;
+    row.content.publication_state = instance.content.publication_state.clone();
+    if instance.workflow_stage.is_some() && document_type.workflow().is_some() {
+        row.workflow_stage = instance.workflow_stage.clone();
+    }
+}
+
+/// The value of `field_path` of `row`, like "price", or "description.en" for
+/// a translation of a localized text, or "document_id".
+fn value_of(row: &DocumentInstance, field_path: &str) -> Option<DomainValue> {
+    if field_path == DOCUMENT_ID_FIELD_NAME {
+        return Some(DomainValue::Uuid(row.document_id.0));
+    }
+    let (field, locale) = match field_path.split_once('.') {
+        Some((field, locale)) => (field, Some(locale)),
+        None => (field_path, None),
+    };
+    let (_, value) = row
+        .content
+        .fields
+        .iter()
+        .find(|(id, _)| id.as_ref() == field)?;
+    match (value, locale) {
+        (ContentValue::Scalar(value), _) => Some(value.clone()),
+        (ContentValue::LocalizedText(texts), Some(locale)) => {
+            texts.get(locale).cloned().map(DomainValue::Text)
+        }
+        _ => None,
+    }
+}
+
+/// The scalar value of the field `attr_id` of `row`.
+fn field_value(row: &DocumentInstance, attr_id: &AttributeId) -> Option<DomainValue> {
+    match row.content.fields.get(attr_id) {
+        Some(ContentValue::Scalar(value)) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+/// The texts of the text fields of `row`, of all locales, lowercased.
+fn searchable_texts(document_type: &DocumentType, row: &DocumentInstance) -> Vec<String> {
+    document_type
+        .fields
+        .iter()
+        .filter(|field| field.field_type.is_text())
+        .flat_map(|field| match row.content.fields.get(&field.id) {
+            Some(ContentValue::Scalar(value)) => text_of(value).into_iter().collect(),
+            Some(ContentValue::LocalizedText(texts)) => {
+                texts.values().map(String::as_str).collect::<Vec<_>>()
+            }
+            _ => Vec::new(),
+        })
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn text_of(value: &DomainValue) -> Option<&str> {
+    match value {
+        DomainValue::Text(text) => Some(text),
+        DomainValue::Email(email) => Some(email.as_ref()),
+        DomainValue::Url(url) => Some(url.as_ref()),
+        _ => None,
+    }
+}
+
+/// The order of two values of the same type, `None` for values which can't
+/// be compared.
+fn compare(a: &DomainValue, b: &DomainValue) -> Option<Ordering> {
+    match (a, b) {
+        (DomainValue::Integer(a), DomainValue::Decimal(b)) => Some(Decimal::from(*a).cmp(b)),
+        (DomainValue::Decimal(a), DomainValue::Integer(b)) => Some(a.cmp(&Decimal::from(*b))),
+        (DomainValue::Integer(a), DomainValue::Integer(b)) => Some(a.cmp(b)),
+        (DomainValue::Decimal(a), DomainValue::Decimal(b)) => Some(a.cmp(b)),
+        (DomainValue::Boolean(a), DomainValue::Boolean(b)) => Some(a.cmp(b)),
+        (DomainValue::Date(a), DomainValue::Date(b)) => Some(a.cmp(b)),
+        (DomainValue::DateTime(a), DomainValue::DateTime(b)) => Some(a.cmp(b)),
+        (DomainValue::LocalDateTime(a), DomainValue::LocalDateTime(b)) => Some(a.cmp(b)),
+        (DomainValue::Time(a), DomainValue::Time(b)) => Some(a.cmp(b)),
+        (DomainValue::Uuid(a), DomainValue::Uuid(b)) => Some(a.cmp(b)),
+        (a, b) => Some(text_of(a)?.cmp(text_of(b)?)),
+    }
+}
+
+/// The order of two values, missing values last like SQL sorts them.
+fn compare_nullable(a: Option<&DomainValue>, b: Option<&DomainValue>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => compare(a, b).unwrap_or(Ordering::Equal),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// The `related` documents of one document sorted, limited and with the
+/// fields selected by `options`.
+fn populated(
+    mut related: Vec<DocumentInstance>,
+    options: &PopulateOptions,
+) -> Vec<DocumentInstance> {
+    related.sort_by(|a, b| compare_rows(a, b, &options.sort));
+    if let Some(limit) = options.limit {
+        related.truncate(limit as usize);
+    }
+    related
+        .into_iter()
+        .map(|document| options.select_fields(document))
+        .collect()
+}
+
+fn compare_rows(a: &DocumentInstance, b: &DocumentInstance, sorts: &[Sort]) -> Ordering {
+    sorts
+        .iter()
+        .map(|sort| {
+            let ordering = compare_nullable(
+                value_of(a, &sort.field).as_ref(),
+                value_of(b, &sort.field).as_ref(),
+            );
+            match sort.direction {
+                SortDirection::Ascending => ordering,
+                SortDirection::Descending => ordering.reverse(),
+            }
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Hash of the document `id` drawing its place in a random order or sample:
+/// the same for the same `seed`, else drawn anew with the keys of `random`.
+fn random_draw(id: DocumentInstanceId, seed: Option<i64>, random: &RandomState) -> u64 {
+    match seed {
+        Some(seed) => {
+            let mut hasher = DefaultHasher::new();
+            (seed, id).hash(&mut hasher);
+            hasher.finish()
+        }
+        None => random.hash_one(id),
+    }
+}
+
+/// `function` of the `field` values of `rows`, `None` without values to
+/// aggregate; `count` without a field counts the rows.
+fn aggregate(
+    function: AggregateFunction,
+    field: Option<&AttributeId>,
+    rows: &[&DocumentInstance],
+) -> Option<Decimal> {
+    let Some(field) = field else {
+        return Some(Decimal::from(rows.len()));
+    };
+    let values: Vec<DomainValue> = rows
+        .iter()
+        .filter_map(|row| field_value(row, field))
+        .collect();
+    let numbers: Vec<Decimal> = values
+        .iter()
+        .filter_map(|value| match value {
+            DomainValue::Integer(value) => Some(Decimal::from(*value)),
+            DomainValue::Decimal(value) => Some(*value),
+            _ => None,
+        })
+        .collect();
+
+    match function {
+        AggregateFunction::Count => Some(Decimal::from(values.len())),
+        AggregateFunction::Sum => (!numbers.is_empty()).then(|| numbers.iter().sum()),
+        AggregateFunction::Avg => (!numbers.is_empty())
+            .then(|| numbers.iter().sum::<Decimal>() / Decimal::from(numbers.len())),
+        AggregateFunction::Min => numbers.iter().min().copied(),
+        AggregateFunction::Max => numbers.iter().max().copied(),
+    }
+}
+
+/// The personal text fields of `document_type`, the ones a data subject is
+/// looked up by.
+fn subject_fields(document_type: &DocumentType) -> Vec<&DocumentField> {
+    document_type
+        .personal_fields()
+        .into_iter()
+        .filter(|field| matches!(field.field_type, FieldType::Text | FieldType::Uid))
+        .collect()
+}
+
+fn unsupported(operation: &str) -> RepositoryError {
+    RepositoryError::DatabaseError(format!(
+        "{} is not supported by the in-memory repository",
+        operation
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::translation::TranslationState;
+    use luminair_common::DocumentTypeApiId;
+    use luminair_common::entities::{
+        DocumentField, DocumentRelation, DocumentTypeOptions, FieldType, LocalizationId,
+        RelationType,
+    };
+    use std::collections::HashSet;
+
+    #[derive(Debug)]
+    struct MockRegistry {
+        types: Vec<&'static DocumentType>,
+    }
+
+    impl DocumentTypesRegistry for MockRegistry {
+        fn iterate(&self) -> Box<dyn Iterator<Item = &DocumentType> + '_> {
+            Box::new(self.types.iter().copied())
+        }
+        fn get(&self, id: &DocumentTypeId) -> Option<&DocumentType> {
+            self.types.iter().copied().find(|dt| &dt.id == id)
+        }
+        fn lookup(&self, _api_id: &DocumentTypeApiId) -> Option<&DocumentType> {
+            None
+        }
+    }
+
+    /// Brands with a unique `name`, with draft and publish when `draft_and_publish`.
+    fn brands(draft_and_publish: bool) -> &'static DocumentType {
+        let mut document = DocumentType::new_bare_collection("brand", "brand", "brands").unwrap();
+        document.fields.insert(DocumentField {
+            id: AttributeId::try_new("name").unwrap(),
+            field_type: FieldType::Text,
+            constraints: HashSet::new(),
+            required: false,
+            unique: true,
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        });
+        document.options = Some(DocumentTypeOptions {
+            draft_and_publish,
+            localizations: Vec::new(),
+            workflow: None,
+            cache_control: None,
+            feed: None,
+            operations: None,
+            partitioning: None,
+            view: None,
+            retention: None,
+        });
+        Box::leak(Box::new(document))
+    }
+
+    fn repository(document_type: &'static DocumentType) -> InMemoryDocumentsRepository {
+        let registry = Box::leak(Box::new(MockRegistry {
+            types: vec![document_type],
+        }));
+        InMemoryDocumentsRepository::new(registry)
+    }
+
+    fn brand(name: &str) -> DocumentInstance {
+        let fields = HashMap::from([(
+            AttributeId::try_new("name").unwrap(),
+            ContentValue::Scalar(DomainValue::Text(name.to_string())),
+        )]);
+        DocumentInstance::new(
+            DatabaseRowId(0),
+            DocumentInstanceId::generate(),
+            crate::domain::document::content::DocumentContent::new(fields),
+            HashMap::new(),
+        )
+    }
+
+    fn names(documents: &[DocumentInstance]) -> Vec<String> {
+        documents
+            .iter()
+            .map(|document| match value_of(document, "name") {
+                Some(DomainValue::Text(name)) => name,
+                other => panic!("unexpected name {other:?}"),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_find_filters_sorts_and_paginates() {
+        let document_type = brands(false);
+        let repository = repository(document_type);
+        for name in ["acme", "globex", "initech", "umbrella"] {
+            repository
+                .insert(
+                    document_type,
+                    &brand(name),
+                    &HashMap::new(),
+                    &ActorContext::anonymous(),
+                )
+                .await
+                .unwrap();
+        }
+
+        let query = DocumentInstanceQuery::new()
+            .filter_not_equals("name".to_string(), DomainValue::Text("globex".to_string()))
+            .add_sort("name".to_string(), SortDirection::Descending)
+            .limit(2)
+            .offset(1);
+        let found = repository.find(document_type, &query).await.unwrap();
+        assert_eq!(names(&found), ["initech", "acme"]);
+        assert_eq!(repository.count(document_type, &query).await.unwrap(), 3);
+
+        let error = repository
+            .insert(
+                document_type,
+                &brand("acme"),
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, RepositoryError::UniqueViolation(_)));
+    }
+
+    #[tokio::test]
+    async fn test_app_state_is_built_over_the_repository() {
+        use crate::application::{AppState, QueryLimits};
+
+        let document_type = brands(false);
+        let registry = Box::leak(Box::new(MockRegistry {
+            types: vec![document_type],
+        }));
+        let repository = InMemoryDocumentsRepository::new(registry);
+        let query_limits = QueryLimits {
+            max_populate: 1,
+            ..Default::default()
+        };
+        let state = AppStateImpl::builder(registry, repository.clone())
+            .query_limits(query_limits)
+            .build();
+        assert_eq!(state.query_limits().max_populate, 1);
+
+        repository
+            .insert(
+                document_type,
+                &brand("acme"),
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+        let query = DocumentInstanceQuery::new();
+        let count = state.documents_repository().count(document_type, &query);
+        assert_eq!(count.await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_localized_unique_field_is_unique_per_locale() {
+        let mut document_type =
+            DocumentType::new_bare_collection("brand", "brand", "brands").unwrap();
+        document_type.fields.insert(DocumentField {
+            id: AttributeId::try_new("title").unwrap(),
+            field_type: FieldType::LocalizedText,
+            constraints: HashSet::new(),
+            required: false,
+            unique: true,
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        });
+        let document_type: &'static DocumentType = Box::leak(Box::new(document_type));
+        let repository = repository(document_type);
+        let titled = |texts: &[(&str, &str)]| {
+            let texts = texts
+                .iter()
+                .map(|(locale, text)| (locale.to_string(), text.to_string()))
+                .collect();
+            DocumentInstance::new(
+                DatabaseRowId(0),
+                DocumentInstanceId::generate(),
+                crate::domain::document::content::DocumentContent::new(HashMap::from([(
+                    AttributeId::try_new("title").unwrap(),
+                    ContentValue::LocalizedText(texts),
+                )])),
+                HashMap::new(),
+            )
+        };
+
+        repository
+            .insert(
+                document_type,
+                &titled(&[("en", "Wine"), ("ro", "Vin")]),
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+        // the same text in another locale is fine, in the same locale it isn't
+        repository
+            .insert(
+                document_type,
+                &titled(&[("en", "Vin"), ("ro", "Vinuri")]),
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+        let error = repository
+            .insert(
+                document_type,
+                &titled(&[("en", "Wines"), ("ro", "Vin")]),
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, RepositoryError::UniqueViolation(_)));
+    }
+
+    #[tokio::test]
+    async fn test_update_checks_the_version_and_keeps_revisions() {
+        let document_type = brands(false);
+        let repository = repository(document_type);
+        let created = repository
+            .insert(
+                document_type,
+                &brand("acme"),
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+
+        let mut renamed = brand("acme corp");
+        renamed.document_id = created.document_id;
+        renamed.audit.version = 2;
+        repository
+            .update(
+                document_type,
+                &renamed,
+                1,
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+        let error = repository
+            .update(
+                document_type,
+                &renamed,
+                1,
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, RepositoryError::Conflict(1)));
+
+        let query = DocumentInstanceQuery::new();
+        let stored = repository
+            .find_by_id(document_type, created.document_id, &query)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(names(&[stored]), ["acme corp"]);
+        let revisions = repository
+            .find_revisions(document_type, created.document_id)
+            .await
+            .unwrap();
+        assert_eq!(names(&revisions), ["acme"]);
+    }
+
+    #[tokio::test]
+    async fn test_published_reads_see_the_published_copy() {
+        let document_type = brands(true);
+        let repository = repository(document_type);
+        let created = repository
+            .insert(
+                document_type,
+                &brand("acme"),
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+        let published = DocumentInstanceQuery::new();
+        let drafts = DocumentInstanceQuery::new().with_status(DocumentStatus::Draft);
+        assert!(
+            repository
+                .find(document_type, &published)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let mut instance = created.clone();
+        instance.publish(None).unwrap();
+        repository
+            .update(
+                document_type,
+                &instance,
+                1,
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+
+        let mut draft = brand("acme corp");
+        draft.document_id = created.document_id;
+        draft.audit.version = 3;
+        repository
+            .update(
+                document_type,
+                &draft,
+                2,
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+
+        let found = repository.find(document_type, &published).await.unwrap();
+        assert_eq!(names(&found), ["acme"]);
+        let found = repository.find(document_type, &drafts).await.unwrap();
+        assert_eq!(names(&found), ["acme corp"]);
+    }
+
+    #[tokio::test]
+    async fn test_drafts_saved_after_publication_are_modified() {
+        let document_type = brands(true);
+        let repository = repository(document_type);
+        let mut created = Vec::new();
+        for name in ["acme", "globex"] {
+            let mut instance = repository
+                .insert(
+                    document_type,
+                    &brand(name),
+                    &HashMap::new(),
+                    &ActorContext::anonymous(),
+                )
+                .await
+                .unwrap();
+            instance.publish(None).unwrap();
+            repository
+                .update(
+                    document_type,
+                    &instance,
+                    1,
+                    &HashMap::new(),
+                    &ActorContext::anonymous(),
+                )
+                .await
+                .unwrap();
+            created.push(instance);
+        }
+
+        let mut draft = brand("acme corp");
+        draft.document_id = created[0].document_id;
+        draft.audit.version = 3;
+        repository
+            .update(
+                document_type,
+                &draft,
+                2,
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+
+        let modified = DocumentInstanceQuery::new()
+            .with_status(DocumentStatus::Draft)
+            .with_filter(FilterExpression::Modified);
+        let found = repository.find(document_type, &modified).await.unwrap();
+        assert_eq!(names(&found), ["acme corp"]);
+        assert!(found[0].modified);
+        let published = DocumentInstanceQuery::new().with_filter(FilterExpression::Modified);
+        assert!(
+            repository
+                .find(document_type, &published)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_published_documents_saved_after_publication_are_not_modified() {
+        let document_type = brands(true);
+        let repository = repository(document_type);
+        let mut instance = repository
+            .insert(
+                document_type,
+                &brand("acme"),
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+        instance.publish(None).unwrap();
+        // stamped after the publication, like documents published before
+        // publishing stamped both at once, or touched since
+        instance.audit.updated_at += chrono::TimeDelta::seconds(1);
+        repository
+            .update(
+                document_type,
+                &instance,
+                1,
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+
+        let modified = DocumentInstanceQuery::new()
+            .with_status(DocumentStatus::Draft)
+            .with_filter(FilterExpression::Modified);
+        assert!(
+            repository
+                .find(document_type, &modified)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_before_hooks_change_or_reject_writes() {
+        struct Hooks;
+
+        impl LifecycleHooks for Hooks {
+            fn before_create(
+                &self,
+                _document_type: &DocumentType,
+                _actor: &ActorContext,
+                instance: &mut DocumentInstance,
+            ) -> Result<(), HookError> {
+                if value_of(instance, "name") == Some(DomainValue::Text("spam".to_string())) {
+                    return Err(HookError("no spam".to_string()));
+                }
+                instance.content.fields.insert(
+                    AttributeId::try_new("name").unwrap(),
+                    ContentValue::Scalar(DomainValue::Text("hooked".to_string())),
+                );
+                Ok(())
+            }
+        }
+
+        let document_type = brands(false);
+        let repository = repository(document_type)
+            .with_hooks(LifecycleHooksRegistry::new().register(document_type.id.clone(), Hooks));
+
+        let created = repository
+            .insert(
+                document_type,
+                &brand("acme"),
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(names(&[created]), ["hooked"]);
+
+        let error = repository
+            .insert(
+                document_type,
+                &brand("spam"),
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, RepositoryError::ValidationFailed(_)));
+        let query = DocumentInstanceQuery::new();
+        assert_eq!(repository.count(document_type, &query).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_delete_handles_links_by_their_on_delete_action() {
+        let brand_type = brands(false);
+        let mut partner =
+            DocumentType::new_bare_collection("partner", "partner", "partners").unwrap();
+        for (id, on_delete) in [
+            ("brands", OnDeleteAction::Restrict),
+            ("featured", OnDeleteAction::Detach),
+        ] {
+            partner.relations.insert(DocumentRelation {
+                id: AttributeId::try_new(id).unwrap(),
+                relation_type: RelationType::HasMany,
+                target: brand_type.id.clone(),
+                on_delete,
+                renamed_from: None,
+                api_name: None,
+                description: None,
+            });
+        }
+        let partner_type: &'static DocumentType = Box::leak(Box::new(partner));
+        let registry = Box::leak(Box::new(MockRegistry {
+            types: vec![brand_type, partner_type],
+        }));
+        let repository = InMemoryDocumentsRepository::new(registry);
+
+        let acme = repository
+            .insert(
+                brand_type,
+                &brand("acme"),
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+        let globex = repository
+            .insert(
+                brand_type,
+                &brand("globex"),
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+        let links = |ids: Vec<DocumentInstanceId>| RelationOps {
+            connect: ids,
+            ..Default::default()
+        };
+        let created = repository
+            .insert(
+                partner_type,
+                &DocumentInstance::new(
+                    DatabaseRowId(0),
+                    DocumentInstanceId::generate(),
+                    crate::domain::document::content::DocumentContent::new(HashMap::new()),
+                    HashMap::new(),
+                ),
+                &HashMap::from([
+                    (
+                        AttributeId::try_new("brands").unwrap(),
+                        links(vec![acme.document_id]),
+                    ),
+                    (
+                        AttributeId::try_new("featured").unwrap(),
+                        links(vec![acme.document_id, globex.document_id]),
+                    ),
+                ]),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+
+        let references = repository
+            .find_references(brand_type, acme.document_id)
+            .await
+            .unwrap();
+        let relations = references
+            .iter()
+            .map(|reference| (reference.relation.as_ref(), reference.on_delete))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            relations,
+            [
+                ("brands", OnDeleteAction::Restrict),
+                ("featured", OnDeleteAction::Detach)
+            ]
+        );
+        assert_eq!(references[0].document_ids, [created.document_id]);
+
+        let error = repository
+            .delete(brand_type, acme.document_id, &ActorContext::anonymous())
+            .await
+            .unwrap_err();
+        let RepositoryError::Referenced(restricting) = error else {
+            panic!("unexpected error {error:?}");
+        };
+        assert_eq!(restricting.len(), 1);
+        assert_eq!(restricting[0].relation.as_ref(), "brands");
+
+        repository
+            .delete(brand_type, globex.document_id, &ActorContext::anonymous())
+            .await
+            .unwrap();
+        let query = DocumentInstanceQuery::new();
+        let detached = repository
+            .find_by_id(partner_type, created.document_id, &query)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(detached.audit.updated_at > created.audit.updated_at);
+        assert!(
+            repository
+                .find_references(brand_type, globex.document_id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subject_is_found_and_erased_in_personal_fields() {
+        let mut document = DocumentType::new_bare_collection("lead", "lead", "leads").unwrap();
+        document.fields.insert(DocumentField {
+            id: AttributeId::try_new("email").unwrap(),
+            field_type: FieldType::Text,
+            constraints: HashSet::new(),
+            required: false,
+            unique: false,
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: true,
+        });
+        let document_type: &'static DocumentType = Box::leak(Box::new(document));
+        let repository = repository(document_type);
+        let lead = DocumentInstance::new(
+            DatabaseRowId(0),
+            DocumentInstanceId::generate(),
+            crate::domain::document::content::DocumentContent::new(HashMap::from([(
+                AttributeId::try_new("email").unwrap(),
+                ContentValue::Scalar(DomainValue::Text("Jane@Example.com".to_string())),
+            )])),
+            HashMap::new(),
+        );
+        let inserted = repository
+            .insert(
+                document_type,
+                &lead,
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+        let id = inserted.document_id;
+
+        let found = repository
+            .find_subject_documents("jane@example.com")
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].document_ids, [id]);
+
+        let erasure = repository
+            .erase_subject("jane@example.com", &ActorContext::anonymous())
+            .await
+            .unwrap();
+        assert_eq!(erasure.subject, subject_hash("Jane@Example.com"));
+        assert_eq!(erasure.documents[0].document_ids, [id]);
+        let erased = repository
+            .find_by_id(document_type, id, &DocumentInstanceQuery::new())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(value_of(&erased, "email"), None);
+        assert_eq!(erased.audit.version, inserted.audit.version + 1);
+        assert!(
+            repository
+                .find_subject_documents("jane@example.com")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_translations_go_stale_when_the_source_locale_changes() {
+        let mut document =
+            DocumentType::new_bare_collection("article", "article", "articles").unwrap();
+        document.fields.insert(DocumentField {
+            id: AttributeId::try_new("title").unwrap(),
+            field_type: FieldType::LocalizedText,
+            constraints: HashSet::new(),
+            required: false,
+            unique: false,
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        });
+        document.options = Some(DocumentTypeOptions {
+            draft_and_publish: false,
+            localizations: vec![
+                LocalizationId::try_new("en").unwrap(),
+                LocalizationId::try_new("ro").unwrap(),
+            ],
+            workflow: None,
+            cache_control: None,
+            feed: None,
+            operations: None,
+            partitioning: None,
+            view: None,
+            retention: None,
+        });
+        let document_type: &'static DocumentType = Box::leak(Box::new(document));
+        let repository = repository(document_type);
+        let article = |texts: &[(&str, &str)]| {
+            let texts = texts
+                .iter()
+                .map(|(locale, text)| (locale.to_string(), text.to_string()))
+                .collect();
+            DocumentInstance::new(
+                DatabaseRowId(0),
+                DocumentInstanceId::generate(),
+                crate::domain::document::content::DocumentContent::new(HashMap::from([(
+                    AttributeId::try_new("title").unwrap(),
+                    ContentValue::LocalizedText(texts),
+                )])),
+                HashMap::new(),
+            )
+        };
+        let translated = repository
+            .insert(
+                document_type,
+                &article(&[("en", "Hello"), ("ro", "Salut")]),
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+        repository
+            .insert(
+                document_type,
+                &article(&[("en", "Bye")]),
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+
+        let mut edited = article(&[("en", "Hello there"), ("ro", "Salut")]);
+        edited.document_id = translated.document_id;
+        edited.audit.version = 2;
+        edited.audit.updated_at = translated.audit.created_at + chrono::TimeDelta::minutes(1);
+        repository
+            .update(
+                document_type,
+                &edited,
+                1,
+                &HashMap::new(),
+                &ActorContext::anonymous(),
+            )
+            .await
+            .unwrap();
+
+        let status = repository
+            .translation_status(document_type, 0, 10)
+            .await
+            .unwrap();
+        let translations = status.documents;
+        assert_eq!(translations.len(), 2);
+        let states = |id: DocumentInstanceId| {
+            let translation = translations
+                .iter()
+                .find(|translation| translation.document_id == id)
+                .unwrap();
+            translation
+                .locales
+                .iter()
+                .map(|locale| translation.state(locale))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            states(translated.document_id),
+            [TranslationState::Current, TranslationState::Stale]
+        );
+        let untranslated = translations
+            .iter()
+            .find(|translation| translation.document_id != translated.document_id)
+            .unwrap();
+        assert_eq!(
+            states(untranslated.document_id),
+            [TranslationState::Current, TranslationState::Missing]
+        );
+        assert_eq!(
+            status.counts[1],
+            LocaleTranslationCounts {
+                locale: "ro".to_string(),
+                translated: 0,
+                stale: 1,
+                missing: 1,
+            }
+        );
+
+        // the counts are of all the drafts, whichever of them are paged
+        let status = repository
+            .translation_status(document_type, 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(status.documents.len(), 1);
+        assert_eq!(status.total(), 2);
+        assert_eq!(status.counts[0].translated, 2);
+    }
+}
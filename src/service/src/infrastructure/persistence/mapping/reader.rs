@@ -5,16 +5,18 @@ use crate::domain::{
         content::{ContentValue, DomainValue},
         lifecycle::{AuditTrail, PublicationState, UserId},
     },
-    repository::RepositoryError,
+    repository::{ArchiveRow, RepositoryError},
 };
 use chrono::{DateTime, Utc};
 use luminair_common::{
     AttributeId, CREATED_BY_FIELD_NAME, CREATED_FIELD_NAME, DOCUMENT_ID_FIELD_NAME, DocumentType,
     PUBLISHED_BY_FIELD_NAME, PUBLISHED_FIELD_NAME, REVISION_FIELD_NAME, SNAPSHOT_ID_FIELD_NAME,
-    UPDATED_BY_FIELD_NAME, UPDATED_FIELD_NAME, VERSION_FIELD_NAME,
-    entities::{DocumentField, FieldType},
+    UPDATED_BY_FIELD_NAME, UPDATED_FIELD_NAME, VERSION_FIELD_NAME, WORKFLOW_STAGE_FIELD_NAME,
+    entities::{DocumentField, FieldType, WorkflowStageId},
 };
 use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
 use sqlx::postgres::PgValueRef;
 use sqlx::{
     Postgres, Row, Type, ValueRef,
@@ -60,6 +62,13 @@ pub fn row_to_document(
 
     let publication_state = parse_publication_state(row, schema, created_at)?;
     let audit = parse_audit_trail(row, created_at)?;
+    // only main rows keep the stage, there is no such column in snapshots and revisions
+    let workflow_stage = match row.try_get::<Option<String>, _>(WORKFLOW_STAGE_FIELD_NAME) {
+        Ok(stage) => workflow_stage(schema, stage),
+        Err(_) => None,
+    };
+    // selected only for drafts of draft-and-publish types
+    let modified = row.try_get::<bool, _>("modified").unwrap_or(false);
 
     let content = DocumentContent {
         fields,
@@ -72,9 +81,122 @@ pub fn row_to_document(
         content,
         audit,
         relations: HashMap::new(),
+        relation_counts: HashMap::new(),
+        workflow_stage,
+        modified,
+    })
+}
+
+/// Map a document row serialized with `to_jsonb()`, as aggregated by the
+/// lateral populate query, to a domain instance.
+pub fn json_to_document(
+    value: &JsonValue,
+    schema: &DocumentType,
+) -> Result<DocumentInstance, RepositoryError> {
+    let object = value.as_object().ok_or_else(|| {
+        RepositoryError::DatabaseError("Failed to parse document: expected an object".to_string())
+    })?;
+    let column = |name: &str| object.get(name).cloned().unwrap_or(JsonValue::Null);
+
+    let id = match column(SNAPSHOT_ID_FIELD_NAME).as_i64() {
+        Some(sid) => DatabaseRowId(sid),
+        None => DatabaseRowId(0),
+    };
+    let document_id = DocumentInstanceId(json_uuid(
+        &column(DOCUMENT_ID_FIELD_NAME),
+        DOCUMENT_ID_FIELD_NAME,
+    )?);
+
+    let mut fields = HashMap::new();
+    for field in schema.fields.iter() {
+        let normalized_name = field.id.normalized();
+        let column_name: &str = normalized_name.as_ref();
+
+        let value = parse_json_field_value(column(column_name), field, column_name)?;
+
+        fields.insert(field.id.clone(), value);
+    }
+
+    let created_at: DateTime<Utc> = json_decode(column(CREATED_FIELD_NAME), CREATED_FIELD_NAME)?;
+    let publication_state = if schema.has_draft_and_publish() {
+        let published_at: Option<DateTime<Utc>> =
+            json_decode(column(PUBLISHED_FIELD_NAME), PUBLISHED_FIELD_NAME)?;
+        let published_by: Option<String> =
+            json_decode(column(PUBLISHED_BY_FIELD_NAME), PUBLISHED_BY_FIELD_NAME)?;
+        let revision: i32 = json_decode(column(REVISION_FIELD_NAME), REVISION_FIELD_NAME)?;
+
+        match published_at {
+            Some(pub_at) => PublicationState::Published {
+                revision,
+                published_at: pub_at,
+                published_by: published_by.and_then(|s| UserId::try_new(s).ok()),
+            },
+            None => PublicationState::Draft { revision },
+        }
+    } else {
+        PublicationState::Published {
+            revision: 1,
+            published_at: created_at,
+            published_by: None,
+        }
+    };
+
+    let created_by: Option<String> =
+        json_decode(column(CREATED_BY_FIELD_NAME), CREATED_BY_FIELD_NAME)?;
+    let updated_by: Option<String> =
+        json_decode(column(UPDATED_BY_FIELD_NAME), UPDATED_BY_FIELD_NAME)?;
+    let audit = AuditTrail {
+        created_at,
+        created_by: created_by.and_then(|s| UserId::try_new(s).ok()),
+        updated_at: json_decode(column(UPDATED_FIELD_NAME), UPDATED_FIELD_NAME)?,
+        updated_by: updated_by.and_then(|s| UserId::try_new(s).ok()),
+        version: json_decode(column(VERSION_FIELD_NAME), VERSION_FIELD_NAME)?,
+    };
+
+    let workflow_stage = match object.get(WORKFLOW_STAGE_FIELD_NAME) {
+        Some(stage) => workflow_stage(
+            schema,
+            json_decode(stage.clone(), WORKFLOW_STAGE_FIELD_NAME)?,
+        ),
+        None => None,
+    };
+    let modified = column("modified").as_bool().unwrap_or(false);
+
+    Ok(DocumentInstance {
+        id,
+        document_id,
+        content: DocumentContent {
+            fields,
+            publication_state,
+        },
+        audit,
+        relations: HashMap::new(),
+        relation_counts: HashMap::new(),
+        workflow_stage,
+        modified,
     })
 }
 
+/// The table and the stored columns of a row of an export.
+pub fn row_to_archive_row(row: &PgRow) -> Result<ArchiveRow, RepositoryError> {
+    let table: String = row
+        .try_get("table")
+        .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+    let Json(row): Json<JsonValue> = row
+        .try_get("row")
+        .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+    Ok(ArchiveRow { table, row })
+}
+
+/// The stored workflow stage of a main row; documents never moved yet are
+/// in the initial stage of the workflow.
+fn workflow_stage(schema: &DocumentType, stored: Option<String>) -> Option<WorkflowStageId> {
+    let workflow = schema.workflow()?;
+    stored
+        .and_then(|stage| WorkflowStageId::try_new(stage).ok())
+        .or_else(|| Some(workflow.initial_stage().id.clone()))
+}
+
 fn decode_value<'r, T>(value: PgValueRef<'r>) -> Result<T, RepositoryError>
 where
     T: Decode<'r, Postgres> + Type<Postgres>,
@@ -128,6 +250,14 @@ pub fn parse_field_value(
             let value: DateTime<Utc> = decode_value(value_ref)?;
             ContentValue::Scalar(DomainValue::DateTime(value))
         }
+        FieldType::LocalDateTime => {
+            let value: chrono::NaiveDateTime = decode_value(value_ref)?;
+            ContentValue::Scalar(DomainValue::LocalDateTime(value))
+        }
+        FieldType::Time => {
+            let value: chrono::NaiveTime = decode_value(value_ref)?;
+            ContentValue::Scalar(DomainValue::Time(value))
+        }
         // Uid is a human-readable slug stored as a text column — not a UUID column.
         // This mirrors the from_json codec: FieldType::Uid → DomainValue::Text.
         FieldType::Uid => {
@@ -139,13 +269,75 @@ pub fn parse_field_value(
             ContentValue::Scalar(DomainValue::Uuid(value))
         }
         FieldType::Json => {
-            let value: Json<HashMap<String, String>> = decode_value(value_ref)?;
+            let value: Json<serde_json::Map<String, JsonValue>> = decode_value(value_ref)?;
             ContentValue::Scalar(DomainValue::Json(value.0))
         }
     };
     Ok(value)
 }
 
+fn json_decode<T: DeserializeOwned>(
+    value: JsonValue,
+    column_name: &str,
+) -> Result<T, RepositoryError> {
+    serde_json::from_value(value).map_err(|e| {
+        RepositoryError::DatabaseError(format!("Failed to parse {}: {}", column_name, e))
+    })
+}
+
+fn json_uuid(value: &JsonValue, column_name: &str) -> Result<Uuid, RepositoryError> {
+    value
+        .as_str()
+        .and_then(|s| Uuid::parse_str(s).ok())
+        .ok_or_else(|| {
+            RepositoryError::DatabaseError(format!("Failed to parse {}: not a UUID", column_name))
+        })
+}
+
+// Mirrors parse_field_value for the JSON representation of a column
+fn parse_json_field_value(
+    value: JsonValue,
+    field: &DocumentField,
+    column_name: &str,
+) -> Result<ContentValue, RepositoryError> {
+    if value.is_null() {
+        return Ok(ContentValue::Null);
+    }
+
+    let value = match field.field_type {
+        FieldType::Text | FieldType::Uid => {
+            ContentValue::Scalar(DomainValue::Text(json_decode(value, column_name)?))
+        }
+        FieldType::LocalizedText => ContentValue::LocalizedText(json_decode(value, column_name)?),
+        FieldType::Integer(_) => {
+            ContentValue::Scalar(DomainValue::Integer(json_decode(value, column_name)?))
+        }
+        FieldType::Decimal { .. } => {
+            ContentValue::Scalar(DomainValue::Decimal(json_decode(value, column_name)?))
+        }
+        FieldType::Boolean => {
+            ContentValue::Scalar(DomainValue::Boolean(json_decode(value, column_name)?))
+        }
+        FieldType::Date => {
+            ContentValue::Scalar(DomainValue::Date(json_decode(value, column_name)?))
+        }
+        FieldType::DateTime => {
+            ContentValue::Scalar(DomainValue::DateTime(json_decode(value, column_name)?))
+        }
+        FieldType::LocalDateTime => {
+            ContentValue::Scalar(DomainValue::LocalDateTime(json_decode(value, column_name)?))
+        }
+        FieldType::Time => {
+            ContentValue::Scalar(DomainValue::Time(json_decode(value, column_name)?))
+        }
+        FieldType::Uuid => ContentValue::Scalar(DomainValue::Uuid(json_uuid(&value, column_name)?)),
+        FieldType::Json => {
+            ContentValue::Scalar(DomainValue::Json(json_decode(value, column_name)?))
+        }
+    };
+    Ok(value)
+}
+
 fn parse_audit_trail(
     row: &PgRow,
     created_at: DateTime<Utc>,
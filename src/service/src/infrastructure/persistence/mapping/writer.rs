@@ -1,6 +1,72 @@
 use crate::domain::document::content::{ContentValue, DomainValue};
-use sea_query::Expr;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use luminair_common::entities::{DocumentField, FieldType};
+use rust_decimal::Decimal;
+use sea_query::{Expr, Value};
 use serde_json::json;
+use uuid::Uuid;
+
+/// Parameter of the column of `field` for `value`; a missing value is a
+/// NULL of the column type, so a statement has the same SQL, and is prepared
+/// once, whichever fields a document has values of.
+pub fn field_expr(field: &DocumentField, value: Option<&ContentValue>) -> Expr {
+    match value {
+        Some(ContentValue::Null) | None => typed_null(field.field_type),
+        Some(value) => value.into(),
+    }
+}
+
+/// NULL parameter of the column type of `field_type`.
+pub fn typed_null(field_type: FieldType) -> Expr {
+    match field_type {
+        FieldType::Uid | FieldType::Text => Expr::val(Option::<String>::None),
+        FieldType::Uuid => Expr::val(Option::<Uuid>::None),
+        FieldType::LocalizedText | FieldType::Json => Expr::val(Option::<serde_json::Value>::None),
+        FieldType::Integer(_) => Expr::val(Option::<i64>::None),
+        FieldType::Decimal { .. } => Expr::val(Option::<Decimal>::None),
+        FieldType::Date => Expr::val(Option::<NaiveDate>::None),
+        FieldType::DateTime => Expr::val(Option::<DateTime<Utc>>::None),
+        FieldType::LocalDateTime => Expr::val(Option::<NaiveDateTime>::None),
+        FieldType::Time => Expr::val(Option::<NaiveTime>::None),
+        FieldType::Boolean => Expr::val(Option::<bool>::None),
+    }
+}
+
+/// `values` as one array parameter, when they are all texts, all integers or
+/// all UUIDs, so that a list is bound as `= ANY($1)` rather than a parameter
+/// per value.
+pub fn array_value(values: &[DomainValue]) -> Option<Value> {
+    let texts: Option<Vec<String>> = values
+        .iter()
+        .map(|value| match value {
+            DomainValue::Text(text) => Some(text.clone()),
+            DomainValue::Email(email) => Some(email.as_ref().to_string()),
+            DomainValue::Url(url) => Some(url.as_ref().to_string()),
+            _ => None,
+        })
+        .collect();
+    if let Some(texts) = texts {
+        return Some(texts.into());
+    }
+    let integers: Option<Vec<i64>> = values
+        .iter()
+        .map(|value| match value {
+            DomainValue::Integer(integer) => Some(*integer),
+            _ => None,
+        })
+        .collect();
+    if let Some(integers) = integers {
+        return Some(integers.into());
+    }
+    let uuids: Option<Vec<Uuid>> = values
+        .iter()
+        .map(|value| match value {
+            DomainValue::Uuid(uuid) => Some(*uuid),
+            _ => None,
+        })
+        .collect();
+    uuids.map(Value::from)
+}
 
 impl From<&ContentValue> for Expr {
     fn from(value: &ContentValue) -> Self {
@@ -21,6 +87,8 @@ impl From<&DomainValue> for Expr {
             DomainValue::Boolean(b) => (*b).into(),
             DomainValue::Date(d) => (*d).into(),
             DomainValue::DateTime(dt) => (*dt).into(),
+            DomainValue::LocalDateTime(dt) => (*dt).into(),
+            DomainValue::Time(t) => (*t).into(),
             DomainValue::Uuid(v) => (*v).into(),
             DomainValue::Json(j) => json!(j).into(),
             DomainValue::Email(email) => email.as_ref().into(),
@@ -28,3 +96,22 @@ impl From<&DomainValue> for Expr {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lists_of_one_type_are_arrays() {
+        let uuid = Uuid::nil();
+        assert!(matches!(
+            array_value(&[DomainValue::Uuid(uuid), DomainValue::Uuid(uuid)]),
+            Some(Value::Array(..))
+        ));
+        assert!(matches!(
+            array_value(&[DomainValue::Text("a".to_string())]),
+            Some(Value::Array(..))
+        ));
+        assert!(array_value(&[DomainValue::Integer(1), DomainValue::Boolean(true)]).is_none());
+    }
+}
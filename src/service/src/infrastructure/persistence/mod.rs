@@ -1,3 +1,10 @@
 pub mod builders;
+pub mod collations;
+pub mod diagnostics;
+pub mod document_cache;
+#[cfg(any(test, feature = "test-helpers"))]
+pub mod in_memory;
 pub mod mapping;
+pub mod pool_metrics;
 pub mod repository;
+pub mod unit_of_work;
@@ -0,0 +1,34 @@
+use std::time::{Duration, Instant};
+
+use axum_prometheus::metrics::gauge;
+use sqlx::PgPool;
+
+/// How often the pool statistics are sampled.
+pub const POOL_METRICS_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Publish the statistics of the connection pool as Prometheus gauges,
+/// sampled every `interval` for as long as the service runs:
+///
+/// - `db_pool_connections`: open connections, idle or in use
+/// - `db_pool_idle_connections`: open connections not in use
+/// - `db_pool_max_connections`: configured upper bound of the pool
+/// - `db_pool_acquire_wait_seconds`: time a sample waited for a connection
+pub async fn record_pool_metrics(pool: PgPool, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+
+        gauge!("db_pool_connections").set(pool.size() as f64);
+        gauge!("db_pool_idle_connections").set(pool.num_idle() as f64);
+        gauge!("db_pool_max_connections").set(pool.options().get_max_connections() as f64);
+
+        let started = Instant::now();
+        match pool.acquire().await {
+            Ok(connection) => {
+                gauge!("db_pool_acquire_wait_seconds").set(started.elapsed().as_secs_f64());
+                drop(connection);
+            }
+            Err(e) => tracing::warn!("failed to acquire a connection for pool metrics: {}", e),
+        }
+    }
+}
@@ -1,41 +1,103 @@
 use crate::{
     domain::{
-        document::{DocumentInstance, DocumentInstanceId, lifecycle::PublicationState},
-        query::{DocumentInstanceQuery, DocumentStatus},
-        repository::{DocumentsRepository, RelationMap, RelationOps, RepositoryError},
+        actor::ActorContext,
+        document::{
+            DocumentInstance, DocumentInstanceId,
+            lifecycle::{PublicationSchedule, PublicationState, UserId},
+        },
+        events::{ContentAction, content_diff},
+        hooks::{HookError, LifecycleHooks, LifecycleHooksRegistry},
+        query::{
+            AggregateGroup, AggregateQuery, DocumentInstanceQuery, DocumentStatus, PopulateOptions,
+            SearchHit,
+        },
+        repository::{
+            ArchiveRow, DocumentsRepository, InboundReference, RelationCounts, RelationMap,
+            RelationOps, RepositoryError,
+        },
+        subject::{Erasure, SubjectDocuments, subject_hash},
+        translation::{
+            DocumentTranslations, LocaleTranslation, LocaleTranslationCounts, TranslationStatus,
+            locales, written_locales,
+        },
     },
     infrastructure::persistence::builders::{
-        find::{query_count_documents, query_find_document_by_criteria, query_find_document_by_id},
+        aggregate::{metric_column, query_aggregate_documents},
+        archive::{
+            content_tables, insert_archive_rows, query_any_row, query_export_rows, reset_identity,
+        },
+        find::{
+            query_count_documents, query_document_exists, query_find_document_by_criteria,
+            query_find_document_by_id,
+        },
+        orphans::{delete_orphaned_links, query_count_orphaned_links},
+        outbox::{insert_outbox_event, query_document_row},
         relations::{
+            PopulatedRelation, delete_all_relation_entries, delete_links_to_document,
             delete_relation_entry, delete_relation_snapshot_entry, insert_relation_entry,
-            insert_relation_snapshot_entry, query_find_related_documents,
-            query_snapshot_relation_target_ids, query_working_relation_target_ids,
+            insert_relation_snapshot_entry, populated_relation_column,
+            query_count_related_documents, query_existing_document_ids,
+            query_find_documents_with_relations, query_find_related_documents,
+            query_linking_document_ids, query_snapshot_relation_target_ids,
+            query_working_relation_target_ids,
         },
+        retention::{archive_expired, delete_expired, query_count_expired},
+        revisions::{delete_revisions, insert_revision, query_find_revision, query_find_revisions},
+        schedule::{query_due_schedules, update_publish_schedule, update_schedule},
+        search::query_search_documents,
+        subject::{
+            erase_archived_personal_fields, erase_outbox_personal_fields, erase_personal_fields,
+            insert_erasure, query_subject_documents,
+        },
+        translation::{query_translation_counts, query_translation_status},
         write::{
             build_copy_relations_to_snapshots, build_snapshot_insert, build_snapshot_update,
-            delete_document, insert_document, update_document,
+            delete_document, delete_snapshot, insert_document, locales_updated_expr, lock_document,
+            query_snapshot_id, touch_documents, update_document,
         },
     },
 };
 
-use crate::infrastructure::persistence::mapping::reader::row_to_document;
-use futures::TryStreamExt;
-use luminair_common::database::Database;
+use crate::infrastructure::persistence::collations::SortCollations;
+use crate::infrastructure::persistence::diagnostics::{QueryDiagnostics, QueryDiagnosticsSettings};
+use crate::infrastructure::persistence::document_cache::{DocumentCache, DocumentCacheSettings};
+use crate::infrastructure::persistence::mapping::reader::{
+    json_to_document, parse_field_value, row_to_archive_row, row_to_document,
+};
+use crate::infrastructure::persistence::mapping::writer::{field_expr, typed_null};
+use crate::infrastructure::persistence::unit_of_work::UnitOfWork;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt, future, stream};
+use luminair_common::database::{Database, StatementKind};
+use luminair_common::entities::{FieldType, OnDeleteAction};
 use luminair_common::{
-    AttributeId, DocumentType, DocumentTypesRegistry, OWNING_DOCUMENT_ID_FIELD_NAME,
-    PUBLISHED_BY_FIELD_NAME, PUBLISHED_FIELD_NAME, REVISION_FIELD_NAME, STATUS_FIELD_NAME,
-    UPDATED_FIELD_NAME, VERSION_FIELD_NAME,
+    AttributeId, DOCUMENT_ID_FIELD_NAME, DocumentType, DocumentTypesRegistry,
+    LOCALES_UPDATED_FIELD_NAME, OWNING_DOCUMENT_ID_FIELD_NAME, PUBLISH_AT_FIELD_NAME,
+    PUBLISHED_BY_FIELD_NAME, PUBLISHED_FIELD_NAME, REVISION_FIELD_NAME, ResolvedRelation,
+    STATUS_FIELD_NAME, UNPUBLISH_AT_FIELD_NAME, UPDATED_FIELD_NAME, VERSION_FIELD_NAME,
+    WORKFLOW_STAGE_FIELD_NAME,
 };
+use rust_decimal::Decimal;
 use sea_query::{DynIden, Expr};
 use sea_query_sqlx::SqlxValues;
-use sqlx::{AssertSqlSafe, Row};
-use std::collections::HashMap;
+use serde_json::{Map, Value};
+use sqlx::postgres::PgRow;
+use sqlx::types::Json;
+use sqlx::{AssertSqlSafe, PgTransaction, Row};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct PostgresDocumentsRepository {
     schema_registry: &'static dyn DocumentTypesRegistry,
     database: &'static Database,
+    diagnostics: QueryDiagnostics,
+    hooks: LifecycleHooksRegistry,
+    outbox: bool,
+    document_cache: Option<DocumentCache>,
+    collations: SortCollations,
 }
 
 impl PostgresDocumentsRepository {
@@ -46,31 +108,194 @@ impl PostgresDocumentsRepository {
         Self {
             schema_registry,
             database,
+            diagnostics: QueryDiagnostics::default(),
+            hooks: LifecycleHooksRegistry::default(),
+            outbox: false,
+            document_cache: None,
+            collations: SortCollations::default(),
         }
     }
+
+    /// Log slow read queries, and their plans, as configured by `settings`.
+    pub fn with_diagnostics(mut self, settings: &QueryDiagnosticsSettings) -> Self {
+        self.diagnostics = QueryDiagnostics::new(settings);
+        self
+    }
+
+    /// Call the lifecycle hooks of `hooks` around the writes of their document types.
+    pub fn with_hooks(mut self, hooks: LifecycleHooksRegistry) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Record a content change event in the outbox with every write, in the
+    /// same transaction, for the event relay to publish once committed.
+    pub fn with_outbox(mut self) -> Self {
+        self.outbox = true;
+        self
+    }
+
+    /// Serve the published documents looked up by id from a [`DocumentCache`]
+    /// of `settings`, invalidated by lifecycle hooks of every document type.
+    ///
+    /// Call it after [`Self::with_hooks`], which replaces the hooks.
+    pub fn with_document_cache(mut self, settings: &DocumentCacheSettings) -> Self {
+        let cache = DocumentCache::new(settings);
+        for document_type in self.schema_registry.iterate() {
+            self.hooks = self.hooks.register(document_type.id.clone(), cache.clone());
+        }
+        self.document_cache = Some(cache);
+        self
+    }
+
+    /// Sort the localized text fields by the `collations` of their locales,
+    /// see [`SortCollations::load`].
+    pub fn with_collations(mut self, collations: SortCollations) -> Self {
+        self.collations = collations;
+        self
+    }
+
+    /// Resolve an owning relation to populate against its target document type.
+    fn owning_relation(
+        &self,
+        document_type: &DocumentType,
+        attr_id: &AttributeId,
+    ) -> Result<ResolvedRelation<'static>, RepositoryError> {
+        let relation = self
+            .schema_registry
+            .relation(&document_type.id, attr_id)
+            .ok_or_else(|| {
+                RepositoryError::ValidationFailed(format!("Relation not found: {}", attr_id))
+            })?;
+
+        if !relation.is_owning() {
+            return Err(RepositoryError::ValidationFailed(format!(
+                "Relation is not owning: {}",
+                attr_id
+            )));
+        }
+        Ok(relation)
+    }
+
+    /// The statements reading the documents linking to `id` by the owning
+    /// relations targeting `document_type`, by their draft and published links.
+    fn reference_statements(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+    ) -> Vec<(ResolvedRelation<'static>, String, SqlxValues)> {
+        inbound_relations(self.schema_registry, document_type)
+            .into_iter()
+            .flat_map(|relation| {
+                link_versions(relation).iter().map(move |published| {
+                    let (sql, values) = query_linking_document_ids(relation, *published, id.0);
+                    (relation, sql, values)
+                })
+            })
+            .collect()
+    }
 }
 
-fn map_db_error(e: sqlx::Error) -> RepositoryError {
-    if let Some(db_err) = e.as_database_error() {
-        match db_err.code().as_deref() {
-            // Postgres error code 23505: unique_violation
-            // Raised when an insert/update violates a unique index constraint (e.g. duplicate UUID or UID).
-            Some("23505") => return RepositoryError::UniqueViolation(db_err.message().to_string()),
+/// The owning relations of every document type which target `document_type`,
+/// by document type and attribute.
+fn inbound_relations(
+    registry: &'static dyn DocumentTypesRegistry,
+    document_type: &DocumentType,
+) -> Vec<ResolvedRelation<'static>> {
+    owning_relations(registry)
+        .into_iter()
+        .filter(|relation| relation.target.id == document_type.id)
+        .collect()
+}
 
-            // Postgres error code 23503: foreign_key_violation
-            // Raised when a referenced key (such as a relation target ID) does not exist or is violated.
-            Some("23503") => {
-                return RepositoryError::ValidationFailed(format!(
-                    "Relation constraint violation: {}",
-                    db_err.message()
-                ));
-            }
-            _ => {}
+/// The owning relations of every document type, which store the links, by
+/// document type and attribute.
+fn owning_relations(
+    registry: &'static dyn DocumentTypesRegistry,
+) -> Vec<ResolvedRelation<'static>> {
+    let mut owners = registry.iterate().collect::<Vec<_>>();
+    owners.sort_by(|a, b| a.id.as_ref().cmp(b.id.as_ref()));
+    owners
+        .into_iter()
+        .flat_map(|owner| registry.relations_of(&owner.id))
+        .filter(|relation| relation.is_owning())
+        .collect()
+}
+
+/// Whether the links of `relation` are kept for drafts only (`false`), or for
+/// published snapshots too (`true`).
+fn link_versions(relation: ResolvedRelation<'_>) -> &'static [bool] {
+    if relation.owner.has_draft_and_publish() {
+        &[false, true]
+    } else {
+        &[false]
+    }
+}
+
+/// The document ids of the rows of a subject lookup.
+fn subject_document_ids(rows: &[PgRow]) -> Vec<DocumentInstanceId> {
+    rows.iter()
+        .map(|row| DocumentInstanceId(row.get::<Uuid, _>(DOCUMENT_ID_FIELD_NAME)))
+        .collect()
+}
+
+/// Add the documents of the `rows` of [`query_linking_document_ids`] to the
+/// references by `relation`, keeping the ids unique and in order.
+fn add_references(
+    references: &mut Vec<InboundReference>,
+    relation: ResolvedRelation<'static>,
+    rows: &[PgRow],
+) {
+    if rows.is_empty() {
+        return;
+    }
+    let position = references.iter().position(|reference| {
+        reference.document_type.id == relation.owner.id && &reference.relation == relation.id()
+    });
+    let reference = match position {
+        Some(position) => &mut references[position],
+        None => {
+            references.push(InboundReference {
+                document_type: relation.owner,
+                relation: relation.id().clone(),
+                on_delete: relation.relation.on_delete,
+                document_ids: Vec::new(),
+            });
+            let last = references.len() - 1;
+            &mut references[last]
+        }
+    };
+    for row in rows {
+        let id = DocumentInstanceId(row.get::<Uuid, _>(OWNING_DOCUMENT_ID_FIELD_NAME));
+        if !reference.document_ids.contains(&id) {
+            reference.document_ids.push(id);
         }
     }
-    RepositoryError::DatabaseError(e.to_string())
+    reference.document_ids.sort_by_key(|id| id.0);
 }
 
+/// Transaction for read queries, on a read replica when there is one available.
+async fn begin_read(database: &Database) -> Result<PgTransaction<'static>, RepositoryError> {
+    database
+        .begin_read()
+        .await
+        .map_err(|e| RepositoryError::DatabaseError(e.to_string()))
+}
+
+/// Transaction for read queries on the primary, for the reads writes are
+/// based on.
+async fn begin_primary_read(
+    database: &Database,
+) -> Result<PgTransaction<'static>, RepositoryError> {
+    database
+        .begin(StatementKind::Read)
+        .await
+        .map_err(|e| RepositoryError::DatabaseError(e.to_string()))
+}
+
+/// Most rows of an archive inserted by one statement.
+const IMPORT_BATCH_SIZE: usize = 500;
+
 fn sqlx_query_with<'q>(
     sql: String,
     values: SqlxValues,
@@ -84,22 +309,33 @@ impl DocumentsRepository for PostgresDocumentsRepository {
         document_type: &DocumentType,
         query: &DocumentInstanceQuery,
     ) -> Result<Vec<DocumentInstance>, RepositoryError> {
-        let (sql, values) = query_find_document_by_criteria(document_type, query);
-        let query_object = sqlx_query_with(sql, values);
+        let (sql, values) = query_find_document_by_criteria(document_type, query, &self.collations);
+        let database = self.database;
 
-        let mut rows = query_object.fetch(self.database.database_pool());
-        let mut documents = Vec::new();
+        self.diagnostics
+            .observe(
+                database.database_pool(),
+                document_type,
+                sql,
+                values,
+                |sql, values| async move {
+                    let mut transaction = begin_read(database).await?;
+                    let mut rows = sqlx_query_with(sql, values).fetch(&mut *transaction);
+                    let mut documents = Vec::new();
+
+                    while let Some(row) = rows
+                        .try_next()
+                        .await
+                        .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?
+                    {
+                        let document = row_to_document(&row, document_type)?;
+                        documents.push(document);
+                    }
 
-        while let Some(row) = rows
-            .try_next()
+                    Ok(documents)
+                },
+            )
             .await
-            .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?
-        {
-            let document = row_to_document(&row, document_type)?;
-            documents.push(document);
-        }
-
-        Ok(documents)
     }
 
     async fn count(
@@ -108,38 +344,116 @@ impl DocumentsRepository for PostgresDocumentsRepository {
         query: &DocumentInstanceQuery,
     ) -> Result<u64, RepositoryError> {
         let (sql, values) = query_count_documents(document_type, query);
-        let row = sqlx_query_with(sql, values)
-            .fetch_one(self.database.database_pool())
-            .await
-            .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+        let database = self.database;
+        let row = self
+            .diagnostics
+            .observe(
+                database.database_pool(),
+                document_type,
+                sql,
+                values,
+                |sql, values| async move {
+                    let mut transaction = begin_read(database).await?;
+                    sqlx_query_with(sql, values)
+                        .fetch_one(&mut *transaction)
+                        .await
+                        .map_err(|e| RepositoryError::DatabaseError(e.to_string()))
+                },
+            )
+            .await?;
         let count: i64 = row
             .try_get(0)
             .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
         Ok(count as u64)
     }
 
+    fn stream(
+        &self,
+        document_type: &'static DocumentType,
+        query: &DocumentInstanceQuery,
+    ) -> BoxStream<'static, Result<DocumentInstance, RepositoryError>> {
+        let (sql, values) = query_find_document_by_criteria(document_type, query, &self.collations);
+        sqlx_query_with(sql, values)
+            .fetch(self.database.read_pool())
+            .map_err(|e| RepositoryError::DatabaseError(e.to_string()))
+            .and_then(move |row| future::ready(row_to_document(&row, document_type)))
+            .boxed()
+    }
+
     async fn find_by_id(
         &self,
         document_type: &DocumentType,
         id: DocumentInstanceId,
         query: &DocumentInstanceQuery,
     ) -> Result<Option<DocumentInstance>, RepositoryError> {
+        let cache = self
+            .document_cache
+            .as_ref()
+            .filter(|_| DocumentCache::caches(query));
+        if let Some(document) = cache.and_then(|cache| cache.get(document_type, id)) {
+            return Ok(Some(document));
+        }
+
         let (sql, values) = query_find_document_by_id(document_type, id.0, query);
-        let query_object = sqlx_query_with(sql, values);
+        let database = self.database;
 
-        let mut rows = query_object.fetch(self.database.database_pool());
-        let mut documents = Vec::new();
+        let document = self
+            .diagnostics
+            .observe(
+                database.database_pool(),
+                document_type,
+                sql,
+                values,
+                |sql, values| async move {
+                    let mut transaction = begin_primary_read(database).await?;
+                    let mut rows = sqlx_query_with(sql, values).fetch(&mut *transaction);
+                    let mut documents = Vec::new();
+
+                    while let Some(row) = rows
+                        .try_next()
+                        .await
+                        .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?
+                    {
+                        let document = row_to_document(&row, document_type)?;
+                        documents.push(document);
+                    }
 
-        while let Some(row) = rows
-            .try_next()
-            .await
-            .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?
-        {
-            let document = row_to_document(&row, document_type)?;
-            documents.push(document);
+                    Ok(documents.into_iter().next())
+                },
+            )
+            .await?;
+
+        if let (Some(cache), Some(document)) = (cache, &document) {
+            cache.insert(document_type, document.clone());
         }
+        Ok(document)
+    }
 
-        Ok(documents.into_iter().next())
+    async fn exists(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+        status: DocumentStatus,
+    ) -> Result<bool, RepositoryError> {
+        let (sql, values) = query_document_exists(document_type, id.0, status);
+        let database = self.database;
+        let row = self
+            .diagnostics
+            .observe(
+                database.database_pool(),
+                document_type,
+                sql,
+                values,
+                |sql, values| async move {
+                    let mut transaction = begin_read(database).await?;
+                    sqlx_query_with(sql, values)
+                        .fetch_optional(&mut *transaction)
+                        .await
+                        .map_err(|e| RepositoryError::DatabaseError(e.to_string()))
+                },
+            )
+            .await?;
+        Ok(row.is_some())
     }
 
     async fn fetch_relations(
@@ -147,111 +461,1083 @@ impl DocumentsRepository for PostgresDocumentsRepository {
         document_type: &DocumentType,
         fields: &[AttributeId],
         filters: &HashMap<AttributeId, crate::domain::query::FilterExpression>,
+        options: &HashMap<AttributeId, PopulateOptions>,
         status: DocumentStatus,
         ids: &[DocumentInstanceId],
     ) -> Result<RelationMap, RepositoryError> {
         let mut result = HashMap::new();
+        let default_options = PopulateOptions::default();
 
         let params: Vec<Uuid> = ids.iter().map(|id| id.0).collect();
 
         for attr_id in fields {
-            let rel_metadata = document_type.relations.get(attr_id).ok_or_else(|| {
-                RepositoryError::ValidationFailed(format!("Relation not found: {}", attr_id))
-            })?;
-
-            if !rel_metadata.relation_type.is_owning() {
-                return Err(RepositoryError::ValidationFailed(format!(
-                    "Relation is not owning: {}",
-                    attr_id
-                )));
-            }
-
-            let related_document_type = self
-                .schema_registry
-                .get(&rel_metadata.target)
-                .ok_or(RepositoryError::DocumentInstanceNotFound)?;
+            let relation = self.owning_relation(document_type, attr_id)?;
+            let related_document_type = relation.target;
 
             let rel_filter = filters
                 .get(attr_id)
                 .unwrap_or(&crate::domain::query::FilterExpression::None);
 
+            let rel_options = options.get(attr_id).unwrap_or(&default_options);
+
             let (sql, values) = query_find_related_documents(
-                document_type,
-                related_document_type,
-                attr_id,
+                relation,
                 rel_filter,
+                rel_options,
                 status,
                 params.clone(),
+                &self.collations,
             );
-            let query_object = sqlx_query_with(sql, values);
+            let database = self.database;
 
             // Group related docs by their owning main document id (UUID)
-            let mut grouped: HashMap<DocumentInstanceId, Vec<DocumentInstance>> = HashMap::new();
+            let grouped = self
+                .diagnostics
+                .observe(
+                    database.database_pool(),
+                    related_document_type,
+                    sql,
+                    values,
+                    |sql, values| async move {
+                        let mut transaction = begin_read(database).await?;
+                        let mut grouped: HashMap<DocumentInstanceId, Vec<DocumentInstance>> =
+                            HashMap::new();
+                        let mut rows = sqlx_query_with(sql, values).fetch(&mut *transaction);
+
+                        while let Some(row) = rows
+                            .try_next()
+                            .await
+                            .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?
+                        {
+                            let document = rel_options
+                                .select_fields(row_to_document(&row, related_document_type)?);
+                            let owning_uuid: Uuid =
+                                row.try_get(OWNING_DOCUMENT_ID_FIELD_NAME).map_err(|e| {
+                                    RepositoryError::DatabaseError(format!(
+                                        "Failed to parse owning_document_id: {}",
+                                        e
+                                    ))
+                                })?;
+
+                            let id = DocumentInstanceId(owning_uuid);
+                            grouped.entry(id).or_default().push(document);
+                        }
+
+                        Ok(grouped)
+                    },
+                )
+                .await?;
 
-            let mut rows = query_object.fetch(self.database.database_pool());
+            result.insert(attr_id.clone(), grouped);
+        }
 
-            while let Some(row) = rows
-                .try_next()
-                .await
-                .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?
-            {
-                let document = row_to_document(&row, related_document_type)?;
-                let owning_uuid: Uuid =
-                    row.try_get(OWNING_DOCUMENT_ID_FIELD_NAME).map_err(|e| {
+        Ok(result)
+    }
+
+    async fn count_relations(
+        &self,
+        document_type: &DocumentType,
+        fields: &[AttributeId],
+        status: DocumentStatus,
+        ids: &[DocumentInstanceId],
+    ) -> Result<RelationCounts, RepositoryError> {
+        let relations = fields
+            .iter()
+            .map(|attr_id| self.owning_relation(document_type, attr_id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let params: Vec<Uuid> = ids.iter().map(|id| id.0).collect();
+        let published = status == DocumentStatus::Published;
+        let Some((sql, values)) = query_count_related_documents(&relations, published, &params)
+        else {
+            return Ok(HashMap::new());
+        };
+
+        let mut transaction = begin_read(self.database).await?;
+        let rows = sqlx_query_with(sql, values)
+            .fetch_all(&mut *transaction)
+            .await
+            .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+
+        let mut counts = RelationCounts::new();
+        for row in rows {
+            let attribute: String = row.get("attribute");
+            let Some(attr_id) = fields.iter().find(|attr_id| attr_id.as_ref() == attribute) else {
+                continue;
+            };
+            let id = DocumentInstanceId(row.get::<Uuid, _>(OWNING_DOCUMENT_ID_FIELD_NAME));
+            counts
+                .entry(attr_id.clone())
+                .or_default()
+                .insert(id, row.get::<i64, _>("count") as u64);
+        }
+        Ok(counts)
+    }
+
+    async fn find_with_relations(
+        &self,
+        document_type: &DocumentType,
+        query: &DocumentInstanceQuery,
+        fields: &[AttributeId],
+        filters: &HashMap<AttributeId, crate::domain::query::FilterExpression>,
+        options: &HashMap<AttributeId, PopulateOptions>,
+    ) -> Result<Vec<DocumentInstance>, RepositoryError> {
+        let default_options = PopulateOptions::default();
+        let mut relations = Vec::with_capacity(fields.len());
+        for attr_id in fields {
+            relations.push(PopulatedRelation {
+                relation: self.owning_relation(document_type, attr_id)?,
+                filter: filters
+                    .get(attr_id)
+                    .unwrap_or(&crate::domain::query::FilterExpression::None),
+                options: options.get(attr_id).unwrap_or(&default_options),
+            });
+        }
+
+        let (sql, values) =
+            query_find_documents_with_relations(document_type, query, &relations, &self.collations);
+        let database = self.database;
+        let rows = self
+            .diagnostics
+            .observe(
+                database.database_pool(),
+                document_type,
+                sql,
+                values,
+                |sql, values| async move {
+                    let mut transaction = begin_read(database).await?;
+                    sqlx_query_with(sql, values)
+                        .fetch_all(&mut *transaction)
+                        .await
+                        .map_err(|e| RepositoryError::DatabaseError(e.to_string()))
+                },
+            )
+            .await?;
+        let mut documents = Vec::with_capacity(rows.len());
+
+        for row in rows {
+            let document = row_to_document(&row, document_type)?;
+
+            let mut populated = HashMap::new();
+            for (index, relation) in relations.iter().enumerate() {
+                let related: Json<Vec<serde_json::Value>> = row
+                    .try_get(populated_relation_column(index).as_str())
+                    .map_err(|e| {
                         RepositoryError::DatabaseError(format!(
-                            "Failed to parse owning_document_id: {}",
+                            "Failed to parse relation {}: {}",
+                            relation.relation.id(),
                             e
                         ))
                     })?;
-
-                let id = DocumentInstanceId(owning_uuid);
-                grouped.entry(id).or_default().push(document);
+                let related = related
+                    .0
+                    .iter()
+                    .map(|value| {
+                        json_to_document(value, relation.relation.target)
+                            .map(|document| relation.options.select_fields(document))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                populated.insert(relation.relation.id().clone(), related);
             }
 
-            result.insert(attr_id.clone(), grouped);
+            documents.push(document.with_relations(populated));
         }
 
-        Ok(result)
+        Ok(documents)
+    }
+
+    async fn aggregate(
+        &self,
+        document_type: &DocumentType,
+        query: &AggregateQuery,
+    ) -> Result<Vec<AggregateGroup>, RepositoryError> {
+        let (sql, values) = query_aggregate_documents(document_type, query);
+        let database = self.database;
+        let rows = self
+            .diagnostics
+            .observe(
+                database.database_pool(),
+                document_type,
+                sql,
+                values,
+                |sql, values| async move {
+                    let mut transaction = begin_read(database).await?;
+                    sqlx_query_with(sql, values)
+                        .fetch_all(&mut *transaction)
+                        .await
+                        .map_err(|e| RepositoryError::DatabaseError(e.to_string()))
+                },
+            )
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                let mut group = HashMap::new();
+                for attr_id in &query.group_by {
+                    let field = document_type
+                        .fields
+                        .iter()
+                        .find(|f| &f.id == attr_id)
+                        .ok_or_else(|| {
+                            RepositoryError::ValidationFailed(format!(
+                                "Field not found: {}",
+                                attr_id
+                            ))
+                        })?;
+                    let value = parse_field_value(row, field, &attr_id.normalized())?;
+                    group.insert(attr_id.clone(), value);
+                }
+
+                let metrics = query
+                    .metrics
+                    .iter()
+                    .map(|metric| {
+                        let value: Option<Decimal> =
+                            row.try_get(metric_column(metric).as_str()).map_err(|e| {
+                                RepositoryError::DatabaseError(format!(
+                                    "Failed to parse metric {}: {}",
+                                    metric_column(metric),
+                                    e
+                                ))
+                            })?;
+                        Ok((metric.clone(), value))
+                    })
+                    .collect::<Result<Vec<_>, RepositoryError>>()?;
+
+                Ok(AggregateGroup { group, metrics })
+            })
+            .collect()
+    }
+
+    async fn search(
+        &self,
+        document_types: &[&DocumentType],
+        text: &str,
+        limit: u64,
+    ) -> Result<Vec<SearchHit>, RepositoryError> {
+        let Some((sql, values)) = query_search_documents(document_types, text, limit) else {
+            return Ok(Vec::new());
+        };
+        let mut transaction = begin_read(self.database).await?;
+        let rows = sqlx_query_with(sql, values)
+            .fetch_all(&mut *transaction)
+            .await
+            .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+
+        rows.iter()
+            .map(|row| {
+                let type_id: String = row
+                    .try_get("document_type")
+                    .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+                let document_type = document_types
+                    .iter()
+                    .find(|document_type| document_type.id.as_ref() == type_id)
+                    .ok_or(RepositoryError::DocumentTypeNotFound)?;
+                let document_id: Uuid = row
+                    .try_get(DOCUMENT_ID_FIELD_NAME)
+                    .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+                let rank: f32 = row
+                    .try_get("rank")
+                    .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+                Ok(SearchHit {
+                    document_type: document_type.id.clone(),
+                    document_id: DocumentInstanceId(document_id),
+                    rank,
+                })
+            })
+            .collect()
+    }
+
+    async fn find_revisions(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+    ) -> Result<Vec<DocumentInstance>, RepositoryError> {
+        let (sql, values) = query_find_revisions(document_type, id.0);
+        let database = self.database;
+        let rows = self
+            .diagnostics
+            .observe(
+                database.database_pool(),
+                document_type,
+                sql,
+                values,
+                |sql, values| async move {
+                    let mut transaction = begin_read(database).await?;
+                    sqlx_query_with(sql, values)
+                        .fetch_all(&mut *transaction)
+                        .await
+                        .map_err(|e| RepositoryError::DatabaseError(e.to_string()))
+                },
+            )
+            .await?;
+
+        rows.iter()
+            .map(|row| row_to_document(row, document_type))
+            .collect()
+    }
+
+    async fn find_references(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+    ) -> Result<Vec<InboundReference>, RepositoryError> {
+        // read from the primary: the references are checked right before a delete
+        let mut transaction = begin_primary_read(self.database).await?;
+        let mut references = Vec::new();
+        for (relation, sql, values) in self.reference_statements(document_type, id) {
+            let rows = sqlx_query_with(sql, values)
+                .fetch_all(&mut *transaction)
+                .await
+                .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+            add_references(&mut references, relation, &rows);
+        }
+        Ok(references)
+    }
+
+    async fn find_revision(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+        version: i32,
+    ) -> Result<Option<DocumentInstance>, RepositoryError> {
+        // read from the primary like find_by_id: the revision is restored
+        // right after, and may have been recorded a moment ago
+        let (sql, values) = query_find_revision(document_type, id.0, version);
+        let database = self.database;
+        let row = self
+            .diagnostics
+            .observe(
+                database.database_pool(),
+                document_type,
+                sql,
+                values,
+                |sql, values| async move {
+                    let mut transaction = begin_primary_read(database).await?;
+                    sqlx_query_with(sql, values)
+                        .fetch_optional(&mut *transaction)
+                        .await
+                        .map_err(|e| RepositoryError::DatabaseError(e.to_string()))
+                },
+            )
+            .await?;
+
+        row.map(|row| row_to_document(&row, document_type))
+            .transpose()
+    }
+
+    async fn find_scheduled(
+        &self,
+        document_type: &DocumentType,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<(DocumentInstanceId, PublicationSchedule)>, RepositoryError> {
+        // from the primary: the scheduler publishes the documents right after
+        let (sql, values) = query_due_schedules(document_type, until);
+        let database = self.database;
+        let rows = self
+            .diagnostics
+            .observe(
+                database.database_pool(),
+                document_type,
+                sql,
+                values,
+                |sql, values| async move {
+                    let mut transaction = begin_primary_read(database).await?;
+                    sqlx_query_with(sql, values)
+                        .fetch_all(&mut *transaction)
+                        .await
+                        .map_err(|e| RepositoryError::DatabaseError(e.to_string()))
+                },
+            )
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                let document_id: Uuid = row
+                    .try_get(DOCUMENT_ID_FIELD_NAME)
+                    .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+                let schedule = PublicationSchedule {
+                    publish_at: row
+                        .try_get(PUBLISH_AT_FIELD_NAME)
+                        .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?,
+                    unpublish_at: row
+                        .try_get(UNPUBLISH_AT_FIELD_NAME)
+                        .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?,
+                };
+                Ok((DocumentInstanceId(document_id), schedule))
+            })
+            .collect()
     }
 
     async fn insert(
         &self,
         document_type: &DocumentType,
         instance: &DocumentInstance,
-    ) -> Result<(), RepositoryError> {
+        relations: &HashMap<AttributeId, RelationOps>,
+        actor: &ActorContext,
+    ) -> Result<DocumentInstance, RepositoryError> {
+        let mut unit = UnitOfWork::begin(self.database).await?;
+        let instance = self.run_before_hooks(document_type, instance, |hooks, dt, instance| {
+            hooks.before_create(dt, actor, instance)
+        })?;
+
         // For both Use Cases (draftAndPublish ON/OFF), the initial record is written to the main table.
         // PublicationState in the instance contains the correct details for status, revision, and dates.
-        self.insert_main_table(document_type, instance).await
+        let (sql, values) = insert_document(
+            document_type,
+            self.main_insert_params(document_type, &instance),
+        );
+        let row = unit.fetch_one(sql, values).await?;
+        let created = row_to_document(&row, document_type)?;
+
+        self.write_relation_ops(&mut unit, document_type, created.document_id, relations)
+            .await?;
+
+        self.record_event(
+            &mut unit,
+            document_type,
+            created.document_id,
+            ContentAction::Create,
+            actor,
+            None,
+        )
+        .await?;
+
+        unit.commit().await?;
+        for hooks in self.hooks.for_type(document_type) {
+            hooks.after_create(document_type, actor, &created);
+        }
+        Ok(created)
     }
 
     async fn update(
         &self,
         document_type: &DocumentType,
         instance: &DocumentInstance,
+        expected_version: i32,
+        relations: &HashMap<AttributeId, RelationOps>,
+        actor: &ActorContext,
     ) -> Result<(), RepositoryError> {
-        let has_draft_publish = document_type.has_draft_and_publish();
-        let is_publishing = matches!(
-            instance.content.publication_state,
-            PublicationState::Published { .. }
-        );
+        self.write_update(
+            document_type,
+            instance,
+            expected_version,
+            relations,
+            actor,
+            None,
+        )
+        .await
+    }
 
-        if has_draft_publish && is_publishing {
-            // Use Case 3: draft-and-publish is ON, publishing
-            // 1. Update main table metadata ONLY (status -> PUBLISHED, revision, published_at, version, updated_at)
-            self.update_main_table_metadata_only(document_type, instance)
+    async fn publish(
+        &self,
+        document_type: &DocumentType,
+        instance: &DocumentInstance,
+        expected_version: i32,
+        unpublish_at: Option<DateTime<Utc>>,
+        actor: &ActorContext,
+    ) -> Result<(), RepositoryError> {
+        let schedule =
+            update_publish_schedule(document_type, instance.document_id.0, None, unpublish_at);
+        self.write_update(
+            document_type,
+            instance,
+            expected_version,
+            &HashMap::new(),
+            actor,
+            Some(schedule),
+        )
+        .await
+    }
+
+    async fn unpublish(
+        &self,
+        document_type: &DocumentType,
+        instance: &DocumentInstance,
+        expected_version: i32,
+        actor: &ActorContext,
+    ) -> Result<(), RepositoryError> {
+        let mut unit = UnitOfWork::begin(self.database).await?;
+        self.lock_document(
+            &mut unit,
+            document_type,
+            instance.document_id,
+            expected_version,
+        )
+        .await?;
+        let instance = self.run_before_hooks(document_type, instance, |hooks, dt, instance| {
+            hooks.before_unpublish(dt, actor, instance)
+        })?;
+        let before = self
+            .document_row(&mut unit, document_type, instance.document_id)
+            .await?;
+
+        let (sql, values) = insert_revision(document_type, instance.document_id.0);
+        unit.execute(sql, values).await?;
+
+        self.update_main_table_metadata_only(&mut unit, document_type, &instance, expected_version)
+            .await?;
+
+        let (sql, values) = delete_snapshot(document_type, instance.document_id.0);
+        unit.execute(sql, values).await?;
+
+        self.record_event(
+            &mut unit,
+            document_type,
+            instance.document_id,
+            ContentAction::Unpublish,
+            actor,
+            before,
+        )
+        .await?;
+
+        unit.commit().await?;
+        for hooks in self.hooks.for_type(document_type) {
+            hooks.after_unpublish(document_type, actor, &instance);
+        }
+        Ok(())
+    }
+
+    async fn move_to_stage(
+        &self,
+        document_type: &DocumentType,
+        instance: &DocumentInstance,
+        expected_version: i32,
+        actor: &ActorContext,
+    ) -> Result<(), RepositoryError> {
+        let mut unit = UnitOfWork::begin(self.database).await?;
+        self.lock_document(
+            &mut unit,
+            document_type,
+            instance.document_id,
+            expected_version,
+        )
+        .await?;
+        let instance = self.run_before_hooks(document_type, instance, |hooks, dt, instance| {
+            hooks.before_update(dt, actor, instance)
+        })?;
+        let before = self
+            .document_row(&mut unit, document_type, instance.document_id)
+            .await?;
+
+        let (sql, values) = insert_revision(document_type, instance.document_id.0);
+        unit.execute(sql, values).await?;
+
+        self.update_main_table_metadata_only(&mut unit, document_type, &instance, expected_version)
+            .await?;
+
+        self.record_event(
+            &mut unit,
+            document_type,
+            instance.document_id,
+            ContentAction::Update,
+            actor,
+            before,
+        )
+        .await?;
+
+        unit.commit().await?;
+        for hooks in self.hooks.for_type(document_type) {
+            hooks.after_update(document_type, actor, &instance);
+        }
+        Ok(())
+    }
+
+    async fn schedule(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+        schedule: &PublicationSchedule,
+    ) -> Result<(), RepositoryError> {
+        let mut unit = UnitOfWork::begin(self.database).await?;
+        let (sql, values) = update_schedule(document_type, id.0, schedule);
+        let result = unit.execute(sql, values).await?;
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::DocumentInstanceNotFound);
+        }
+        unit.commit().await
+    }
+
+    async fn schedule_publish(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+        publish_at: DateTime<Utc>,
+        unpublish_at: Option<DateTime<Utc>>,
+    ) -> Result<(), RepositoryError> {
+        let mut unit = UnitOfWork::begin(self.database).await?;
+        let (sql, values) =
+            update_publish_schedule(document_type, id.0, Some(publish_at), unpublish_at);
+        let result = unit.execute(sql, values).await?;
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::DocumentInstanceNotFound);
+        }
+        unit.commit().await
+    }
+
+    async fn delete(
+        &self,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+        actor: &ActorContext,
+    ) -> Result<(), RepositoryError> {
+        let mut unit = UnitOfWork::begin(self.database).await?;
+        // the hooks are only called for a document which exists
+        let before = self.document_row(&mut unit, document_type, id).await?;
+        if before.is_none() {
+            return Err(RepositoryError::DocumentInstanceNotFound);
+        }
+        for hooks in self.hooks.for_type(document_type) {
+            hooks
+                .before_delete(document_type, actor, id)
+                .map_err(|e| RepositoryError::ValidationFailed(e.to_string()))?;
+        }
+
+        let mut references = Vec::new();
+        for (relation, sql, values) in self.reference_statements(document_type, id) {
+            let rows = unit.fetch_all(sql, values).await?;
+            add_references(&mut references, relation, &rows);
+        }
+        let restricting = references
+            .iter()
+            .filter(|reference| reference.on_delete == OnDeleteAction::Restrict)
+            .cloned()
+            .collect::<Vec<_>>();
+        if !restricting.is_empty() {
+            return Err(RepositoryError::Referenced(restricting));
+        }
+
+        // detached links are removed here, cascading links by their foreign keys
+        references.retain(|reference| reference.on_delete.detaches());
+        for relation in inbound_relations(self.schema_registry, document_type) {
+            if !relation.relation.on_delete.detaches() {
+                continue;
+            }
+            for published in link_versions(relation) {
+                let (sql, values) = delete_links_to_document(relation, *published, id.0);
+                unit.execute(sql, values).await?;
+            }
+        }
+        for reference in &references {
+            let ids = reference.document_ids.iter().map(|id| id.0).collect();
+            let (sql, values) = touch_documents(reference.document_type, ids);
+            unit.execute(sql, values).await?;
+        }
+
+        // no foreign keys reference a partitioned main table to delete by
+        if document_type.partitioning().is_some() {
+            if document_type.has_draft_and_publish() {
+                let (sql, values) = delete_snapshot(document_type, id.0);
+                unit.execute(sql, values).await?;
+            }
+            let (sql, values) = delete_revisions(document_type, id.0);
+            unit.execute(sql, values).await?;
+        }
+        let (sql, values) = delete_document(document_type, id.0);
+        let result = unit.execute(sql, values).await?;
+        // nothing was deleted, so there's no change to record
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::DocumentInstanceNotFound);
+        }
+        self.record_event(
+            &mut unit,
+            document_type,
+            id,
+            ContentAction::Delete,
+            actor,
+            before,
+        )
+        .await?;
+        unit.commit().await?;
+        for hooks in self.hooks.for_type(document_type) {
+            hooks.after_delete(document_type, actor, id);
+        }
+        if let Some(cache) = &self.document_cache {
+            for reference in &references {
+                for linking_id in &reference.document_ids {
+                    cache.invalidate(reference.document_type, *linking_id);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn export_archive(&self) -> BoxStream<'static, Result<ArchiveRow, RepositoryError>> {
+        let Some((sql, values)) = query_export_rows(&content_tables(self.schema_registry)) else {
+            return stream::empty().boxed();
+        };
+        sqlx_query_with(sql, values)
+            .fetch(self.database.read_pool())
+            .map_err(|e| RepositoryError::DatabaseError(e.to_string()))
+            .and_then(|row| future::ready(row_to_archive_row(&row)))
+            .boxed()
+    }
+
+    /// Lifecycle hooks are not called and no events are recorded: the rows
+    /// are restored as they were stored, not written anew.
+    async fn import_archive(
+        &self,
+        rows: Vec<ArchiveRow>,
+    ) -> Result<BTreeMap<String, u64>, RepositoryError> {
+        let tables = content_tables(self.schema_registry);
+        let mut rows_by_table = HashMap::<String, Vec<Value>>::new();
+        for row in rows {
+            if !tables.iter().any(|table| table.name == row.table) {
+                return Err(RepositoryError::ValidationFailed(format!(
+                    "Table '{}' is not part of the schema",
+                    row.table
+                )));
+            }
+            rows_by_table.entry(row.table).or_default().push(row.row);
+        }
+
+        let mut unit = UnitOfWork::begin(self.database).await?;
+        for table in &tables {
+            let (sql, values) = query_any_row(table);
+            if !unit.fetch_all(sql, values).await?.is_empty() {
+                return Err(RepositoryError::NotEmpty);
+            }
+        }
+
+        let mut restored = BTreeMap::new();
+        for table in &tables {
+            let Some(mut rows) = rows_by_table.remove(&table.name) else {
+                continue;
+            };
+            restored.insert(table.name.clone(), rows.len() as u64);
+            while !rows.is_empty() {
+                let batch = rows.drain(..rows.len().min(IMPORT_BATCH_SIZE)).collect();
+                let (sql, values) = insert_archive_rows(table, batch);
+                unit.execute(sql, values).await?;
+            }
+            if let Some(column) = table.identity {
+                let (sql, values) = reset_identity(table, column);
+                unit.execute(sql, values).await?;
+            }
+        }
+        unit.commit().await?;
+        Ok(restored)
+    }
+
+    async fn clean_orphaned_links(
+        &self,
+        remove: bool,
+        batch_size: u64,
+    ) -> Result<BTreeMap<String, u64>, RepositoryError> {
+        let mut orphaned = BTreeMap::new();
+        for relation in owning_relations(self.schema_registry) {
+            for published in link_versions(relation) {
+                let count = if remove {
+                    self.remove_orphaned_links(relation, *published, batch_size)
+                        .await?
+                } else {
+                    let (sql, values) = query_count_orphaned_links(relation, *published);
+                    let mut transaction = begin_read(self.database).await?;
+                    let row = sqlx_query_with(sql, values)
+                        .fetch_one(&mut *transaction)
+                        .await
+                        .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+                    row.get::<i64, _>("count") as u64
+                };
+                if count > 0 {
+                    orphaned.insert(relation.links_table(*published).table_name(), count);
+                }
+            }
+        }
+        Ok(orphaned)
+    }
+
+    /// Lifecycle hooks are not called and no events are recorded, like for
+    /// the cleanup of orphaned links; cached documents are invalidated.
+    async fn apply_retention(
+        &self,
+        now: DateTime<Utc>,
+        dry_run: bool,
+        batch_size: u64,
+    ) -> Result<BTreeMap<String, u64>, RepositoryError> {
+        let mut expired = BTreeMap::new();
+        let document_types = self.schema_registry.iterate().collect::<Vec<_>>();
+        for document_type in document_types {
+            let Some(retention) = document_type.retention() else {
+                continue;
+            };
+            let before = now - chrono::Duration::days(i64::from(retention.days()));
+            let count = if dry_run {
+                let (sql, values) = query_count_expired(document_type, before);
+                let mut transaction = begin_read(self.database).await?;
+                let row = sqlx_query_with(sql, values)
+                    .fetch_one(&mut *transaction)
+                    .await
+                    .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+                row.get::<i64, _>("count") as u64
+            } else {
+                self.remove_expired(document_type, before, batch_size)
+                    .await?
+            };
+            if count > 0 {
+                expired.insert(document_type.id.to_string(), count);
+            }
+        }
+        Ok(expired)
+    }
+
+    async fn find_subject_documents(
+        &self,
+        value: &str,
+    ) -> Result<Vec<SubjectDocuments>, RepositoryError> {
+        let mut transaction = begin_read(self.database).await?;
+        let mut subjects = Vec::new();
+        for document_type in self.subject_types() {
+            let Some((sql, values)) = query_subject_documents(document_type, value) else {
+                continue;
+            };
+            let rows = sqlx_query_with(sql, values)
+                .fetch_all(&mut *transaction)
+                .await
+                .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+            if !rows.is_empty() {
+                subjects.push(SubjectDocuments {
+                    document_type,
+                    document_ids: subject_document_ids(&rows),
+                });
+            }
+        }
+        Ok(subjects)
+    }
+
+    /// Lifecycle hooks are not called; the content change events let the
+    /// search index and the response cache drop the erased values.
+    async fn erase_subject(
+        &self,
+        value: &str,
+        actor: &ActorContext,
+    ) -> Result<Erasure, RepositoryError> {
+        let mut unit = UnitOfWork::begin(self.database).await?;
+        let mut subjects = Vec::new();
+        for document_type in self.subject_types() {
+            let Some((sql, values)) = query_subject_documents(document_type, value) else {
+                continue;
+            };
+            let rows = unit.fetch_all(sql, values).await?;
+            let mut document_ids = subject_document_ids(&rows);
+            let ids = document_ids.iter().map(|id| id.0).collect::<Vec<_>>();
+            if !ids.is_empty() {
+                for (sql, values) in erase_personal_fields(document_type, ids) {
+                    unit.execute(sql, values).await?;
+                }
+            }
+            for id in &document_ids {
+                self.record_event(
+                    &mut unit,
+                    document_type,
+                    *id,
+                    ContentAction::Update,
+                    actor,
+                    None,
+                )
                 .await?;
+            }
+
+            let archives = document_type
+                .retention()
+                .is_some_and(|retention| retention.archives());
+            let archived = if archives {
+                erase_archived_personal_fields(document_type, value)
+            } else {
+                None
+            };
+            if let Some((sql, values)) = archived {
+                let rows = unit.fetch_all(sql, values).await?;
+                document_ids.extend(subject_document_ids(&rows));
+            }
+
+            // the content change events keep the former values of the fields
+            let ids = document_ids.iter().map(|id| id.0).collect::<Vec<_>>();
+            if let Some((sql, values)) = erase_outbox_personal_fields(document_type, ids) {
+                unit.execute(sql, values).await?;
+            }
+
+            if !document_ids.is_empty() {
+                subjects.push(SubjectDocuments {
+                    document_type,
+                    document_ids,
+                });
+            }
+        }
+
+        let documents = subjects
+            .iter()
+            .map(|subject| {
+                let ids = subject
+                    .document_ids
+                    .iter()
+                    .map(|id| Value::from(id.0.to_string()))
+                    .collect();
+                (subject.document_type.id.to_string(), Value::Array(ids))
+            })
+            .collect::<Map<String, Value>>();
+        let subject = subject_hash(value);
+        let erased_by = actor.user_id.as_ref().map(UserId::to_string);
+        let (sql, values) =
+            insert_erasure(subject.clone(), erased_by.clone(), Value::Object(documents));
+        let rows = unit.fetch_all(sql, values).await?;
+        let row = rows.first().ok_or_else(|| {
+            RepositoryError::DatabaseError("the erasure wasn't recorded".to_string())
+        })?;
+        let erasure = Erasure {
+            id: row.get("erasure_id"),
+            subject,
+            documents: subjects,
+            erased_by,
+            erased_at: row.get("erased_at"),
+        };
+        unit.commit().await?;
+
+        // lifecycle hooks aren't run by erasures, the cache is told directly
+        if let Some(cache) = &self.document_cache {
+            for subject in &erasure.documents {
+                for id in &subject.document_ids {
+                    cache.invalidate(subject.document_type, *id);
+                }
+            }
+        }
+        Ok(erasure)
+    }
+
+    async fn translation_status(
+        &self,
+        document_type: &DocumentType,
+        offset: u64,
+        limit: u64,
+    ) -> Result<TranslationStatus, RepositoryError> {
+        let mut transaction = begin_read(self.database).await?;
+        let (sql, values) = query_translation_status(document_type, offset, limit);
+        let rows = sqlx_query_with(sql, values)
+            .fetch_all(&mut *transaction)
+            .await
+            .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+        let (sql, values) = query_translation_counts(document_type);
+        let count_rows = sqlx_query_with(sql, values)
+            .fetch_all(&mut *transaction)
+            .await
+            .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+
+        let locales = locales(document_type).collect::<Vec<_>>();
+        let documents = rows
+            .iter()
+            .map(|row| {
+                let present: Vec<bool> = row.try_get("present")?;
+                let updated_at: Vec<Option<DateTime<Utc>>> = row.try_get("updated_at")?;
+                let locales = locales
+                    .iter()
+                    .zip(present.into_iter().zip(updated_at))
+                    .map(|(locale, (present, updated_at))| LocaleTranslation {
+                        locale: locale.clone(),
+                        present,
+                        updated_at,
+                    })
+                    .collect();
+                Ok(DocumentTranslations {
+                    document_id: DocumentInstanceId(row.try_get(DOCUMENT_ID_FIELD_NAME)?),
+                    locales,
+                })
+            })
+            .collect::<Result<_, sqlx::Error>>()
+            .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+        let mut counted = count_rows
+            .iter()
+            .map(|row| {
+                let counts = LocaleTranslationCounts {
+                    locale: row.try_get("locale")?,
+                    translated: row.try_get::<i64, _>("translated")? as u64,
+                    stale: row.try_get::<i64, _>("stale")? as u64,
+                    missing: row.try_get::<i64, _>("missing")? as u64,
+                };
+                Ok((counts.locale.clone(), counts))
+            })
+            .collect::<Result<HashMap<_, _>, sqlx::Error>>()
+            .map_err(|e| RepositoryError::DatabaseError(e.to_string()))?;
+        // a type without drafts has no counts at all
+        let counts = locales
+            .into_iter()
+            .map(|locale| {
+                counted
+                    .remove(&locale)
+                    .unwrap_or_else(|| LocaleTranslationCounts::new(locale))
+            })
+            .collect();
+        Ok(TranslationStatus { documents, counts })
+    }
+}
 
-            // 2. Insert or Update snapshot row depending on revision
-            let is_update = matches!(
-                &instance.content.publication_state,
-                PublicationState::Published { revision, .. } if *revision > 1
+impl PostgresDocumentsRepository {
+    /// Persist changes to an existing document instance, see
+    /// [`DocumentsRepository::update`], running the `schedule` statement in
+    /// the same unit when given.
+    async fn write_update(
+        &self,
+        document_type: &DocumentType,
+        instance: &DocumentInstance,
+        expected_version: i32,
+        relations: &HashMap<AttributeId, RelationOps>,
+        actor: &ActorContext,
+        schedule: Option<(String, SqlxValues)>,
+    ) -> Result<(), RepositoryError> {
+        // Use Case 3: draft-and-publish is ON, publishing
+        let is_publishing = document_type.has_draft_and_publish()
+            && matches!(
+                instance.content.publication_state,
+                PublicationState::Published { .. }
             );
 
+        let mut unit = UnitOfWork::begin(self.database).await?;
+        self.lock_document(
+            &mut unit,
+            document_type,
+            instance.document_id,
+            expected_version,
+        )
+        .await?;
+        let hooked = self.run_before_hooks(document_type, instance, |hooks, dt, instance| {
+            if is_publishing {
+                hooks.before_publish(dt, actor, instance)
+            } else {
+                hooks.before_update(dt, actor, instance)
+            }
+        })?;
+        let instance = &*hooked;
+        let before = self
+            .document_row(&mut unit, document_type, instance.document_id)
+            .await?;
+
+        // the content being replaced is kept as a revision of the document
+        let (sql, values) = insert_revision(document_type, instance.document_id.0);
+        unit.execute(sql, values).await?;
+
+        // links are written first, so a publish snapshots them too; a version
+        // conflict below rolls them back with the rest of the unit
+        self.write_relation_ops(&mut unit, document_type, instance.document_id, relations)
+            .await?;
+
+        if is_publishing {
+            // 1. Update main table metadata ONLY (status -> PUBLISHED, revision, published_at, version, updated_at)
+            self.update_main_table_metadata_only(
+                &mut unit,
+                document_type,
+                instance,
+                expected_version,
+            )
+            .await?;
+
+            // 2. Insert or Update snapshot row depending on whether there is one:
+            // a document unpublished since its last publish has none left
+            let (sql, values) = query_snapshot_id(document_type, instance.document_id.0);
+            let is_update = !unit.fetch_all(sql, values).await?.is_empty();
+
             let snapshot_id = if is_update {
-                self.update_snapshot_for_published_instance(document_type, instance)
+                self.update_snapshot_for_published_instance(&mut unit, document_type, instance)
                     .await?
             } else {
-                self.store_snapshot_for_published_instance(document_type, instance)
+                self.store_snapshot_for_published_instance(&mut unit, document_type, instance)
                     .await?
             };
 
@@ -268,11 +1554,8 @@ impl DocumentsRepository for PostgresDocumentsRepository {
                         &relation.id,
                         instance.document_id.0,
                     );
-                    let working_rows = sqlx_query_with(working_sql, working_values)
-                        .fetch_all(self.database.database_pool())
-                        .await
-                        .map_err(map_db_error)?;
-                    let current_working_ids: std::collections::HashSet<Uuid> = working_rows
+                    let working_rows = unit.fetch_all(working_sql, working_values).await?;
+                    let current_working_ids: HashSet<Uuid> = working_rows
                         .into_iter()
                         .map(|row| row.get::<Uuid, _>("target_document_id"))
                         .collect();
@@ -283,11 +1566,8 @@ impl DocumentsRepository for PostgresDocumentsRepository {
                         &relation.id,
                         instance.document_id.0,
                     );
-                    let snapshot_rows = sqlx_query_with(snapshot_sql, snapshot_values)
-                        .fetch_all(self.database.database_pool())
-                        .await
-                        .map_err(map_db_error)?;
-                    let existing_snapshot_ids: std::collections::HashSet<Uuid> = snapshot_rows
+                    let snapshot_rows = unit.fetch_all(snapshot_sql, snapshot_values).await?;
+                    let existing_snapshot_ids: HashSet<Uuid> = snapshot_rows
                         .into_iter()
                         .map(|row| row.get::<Uuid, _>("target_document_id"))
                         .collect();
@@ -301,10 +1581,7 @@ impl DocumentsRepository for PostgresDocumentsRepository {
                             snapshot_id,
                             *target_id,
                         );
-                        sqlx_query_with(sql, values)
-                            .execute(self.database.database_pool())
-                            .await
-                            .map_err(map_db_error)?;
+                        unit.execute(sql, values).await?;
                     }
 
                     // Calculate difference: items to insert
@@ -317,10 +1594,7 @@ impl DocumentsRepository for PostgresDocumentsRepository {
                             instance.document_id.0,
                             *target_id,
                         );
-                        sqlx_query_with(sql, values)
-                            .execute(self.database.database_pool())
-                            .await
-                            .map_err(map_db_error)?;
+                        unit.execute(sql, values).await?;
                     }
                 } else {
                     // First publish: copy everything
@@ -330,90 +1604,294 @@ impl DocumentsRepository for PostgresDocumentsRepository {
                         instance.document_id.0,
                         snapshot_id,
                     );
-                    sqlx_query_with(sql, values)
-                        .execute(self.database.database_pool())
-                        .await
-                        .map_err(map_db_error)?;
+                    unit.execute(sql, values).await?;
                 }
             }
         } else {
             // For both remaining use cases, we perform a full content and metadata update on the main table:
             // - Use Case 1: draft-and-publish is OFF, saving an edit (status is always PUBLISHED)
             // - Use Case 2: draft-and-publish is ON, saving a draft (status -> DRAFT/MODIFIED, clears published_at)
-            self.update_main_table_content_and_metadata(document_type, instance)
-                .await?;
+            self.update_main_table_content_and_metadata(
+                &mut unit,
+                document_type,
+                instance,
+                expected_version,
+            )
+            .await?;
         }
 
+        if let Some((sql, values)) = schedule {
+            unit.execute(sql, values).await?;
+        }
+
+        let action = if is_publishing {
+            ContentAction::Publish
+        } else {
+            ContentAction::Update
+        };
+        self.record_event(
+            &mut unit,
+            document_type,
+            instance.document_id,
+            action,
+            actor,
+            before,
+        )
+        .await?;
+
+        unit.commit().await?;
+        for hooks in self.hooks.for_type(document_type) {
+            if is_publishing {
+                hooks.after_publish(document_type, actor, instance);
+            } else {
+                hooks.after_update(document_type, actor, instance);
+            }
+        }
         Ok(())
     }
 
-    async fn delete(
+    /// The document types a data subject is looked up in, by the order of
+    /// their ids; read-only ones are left out, their views are refreshed
+    /// from the tables they select from.
+    fn subject_types(&self) -> Vec<&'static DocumentType> {
+        let mut types = self
+            .schema_registry
+            .iterate()
+            .filter(|document_type| document_type.view().is_none())
+            .collect::<Vec<_>>();
+        types.sort_by(|a, b| a.id.cmp(&b.id));
+        types
+    }
+
+    /// Delete, or archive, the documents of `document_type` created before
+    /// `before` batch by batch, each in a transaction of its own, returning
+    /// how many were removed.
+    async fn remove_expired(
+        &self,
+        document_type: &DocumentType,
+        before: DateTime<Utc>,
+        batch_size: u64,
+    ) -> Result<u64, RepositoryError> {
+        let archives = document_type
+            .retention()
+            .is_some_and(|retention| retention.archives());
+        let mut removed = 0;
+        loop {
+            let (sql, values) = if archives {
+                archive_expired(document_type, before, batch_size)
+            } else {
+                delete_expired(document_type, before, batch_size)
+            };
+            let mut unit = UnitOfWork::begin(self.database).await?;
+            let rows = unit.fetch_all(sql, values).await?;
+            unit.commit().await?;
+            if let Some(cache) = &self.document_cache {
+                for row in &rows {
+                    let id = DocumentInstanceId(row.get::<Uuid, _>(DOCUMENT_ID_FIELD_NAME));
+                    cache.invalidate(document_type, id);
+                }
+            }
+            removed += rows.len() as u64;
+            if (rows.len() as u64) < batch_size {
+                return Ok(removed);
+            }
+        }
+    }
+
+    /// Remove the orphaned links of one relation table batch by batch, each in
+    /// a transaction of its own so the table isn't locked for the whole
+    /// cleanup, returning how many were removed.
+    async fn remove_orphaned_links(
+        &self,
+        relation: ResolvedRelation<'static>,
+        published: bool,
+        batch_size: u64,
+    ) -> Result<u64, RepositoryError> {
+        let mut removed = 0;
+        loop {
+            let (sql, values) = delete_orphaned_links(relation, published, batch_size);
+            let mut unit = UnitOfWork::begin(self.database).await?;
+            let result = unit.execute(sql, values).await?;
+            unit.commit().await?;
+            removed += result.rows_affected();
+            if result.rows_affected() < batch_size {
+                return Ok(removed);
+            }
+        }
+    }
+
+    /// Call `before` on every lifecycle hooks of the document type, with the
+    /// instance about to be written, which they may change.
+    ///
+    /// The instance is only copied when there are hooks to call.
+    fn run_before_hooks<'a>(
         &self,
         document_type: &DocumentType,
+        instance: &'a DocumentInstance,
+        before: impl Fn(
+            &dyn LifecycleHooks,
+            &DocumentType,
+            &mut DocumentInstance,
+        ) -> Result<(), HookError>,
+    ) -> Result<Cow<'a, DocumentInstance>, RepositoryError> {
+        let hooks = self.hooks.for_type(document_type);
+        if hooks.is_empty() {
+            return Ok(Cow::Borrowed(instance));
+        }
+        let mut instance = instance.clone();
+        for hooks in hooks {
+            before(hooks.as_ref(), document_type, &mut instance)
+                .map_err(|e| RepositoryError::ValidationFailed(e.to_string()))?;
+        }
+        Ok(Cow::Owned(instance))
+    }
+
+    /// Read the main row of the document within the unit of work, for the diff
+    /// of the content change event; nothing is read without the outbox.
+    async fn document_row(
+        &self,
+        unit: &mut UnitOfWork,
+        document_type: &DocumentType,
+        id: DocumentInstanceId,
+    ) -> Result<Option<Map<String, Value>>, RepositoryError> {
+        if !self.outbox {
+            return Ok(None);
+        }
+        let (sql, values) = query_document_row(document_type, id.0);
+        let rows = unit.fetch_all(sql, values).await?;
+        Ok(rows
+            .first()
+            .map(|row| row.get::<Json<Map<String, Value>>, _>("document").0))
+    }
+
+    /// Write the content change event of a write into the outbox, within the
+    /// unit of work, so the event is stored if and only if the write commits.
+    ///
+    /// `before` is the main row of the document read before the write.
+    async fn record_event(
+        &self,
+        unit: &mut UnitOfWork,
+        document_type: &DocumentType,
         id: DocumentInstanceId,
+        action: ContentAction,
+        actor: &ActorContext,
+        before: Option<Map<String, Value>>,
     ) -> Result<(), RepositoryError> {
-        let (sql, values) = delete_document(document_type, id.0);
-        sqlx_query_with(sql, values)
-            .execute(self.database.database_pool())
-            .await
-            .map_err(map_db_error)?;
+        if !self.outbox {
+            return Ok(());
+        }
+        let after = self.document_row(unit, document_type, id).await?;
+        let diff = content_diff(document_type, before.as_ref(), after.as_ref());
+        let (sql, values) = insert_outbox_event(
+            document_type,
+            id.0,
+            action,
+            actor.user_id.as_ref().map(UserId::to_string),
+            diff,
+        );
+        unit.execute(sql, values).await?;
         Ok(())
     }
 
-    async fn apply_relation_ops(
+    /// Lock the main row of the document for the unit of work, and check it
+    /// still has the version the update is based on.
+    ///
+    /// A concurrent update or publish of the same document waits for the
+    /// lock, then fails here with a conflict instead of racing on the
+    /// relation and snapshot writes.
+    async fn lock_document(
         &self,
+        unit: &mut UnitOfWork,
         document_type: &DocumentType,
-        document_id: DocumentInstanceId,
-        ops: &HashMap<AttributeId, RelationOps>,
+        id: DocumentInstanceId,
+        expected_version: i32,
     ) -> Result<(), RepositoryError> {
-        if ops.is_empty() {
-            return Ok(());
+        let (sql, values) = lock_document(document_type, id.0);
+        let rows = unit.fetch_all(sql, values).await?;
+        let version: Option<i32> = rows.first().map(|row| row.get(VERSION_FIELD_NAME));
+
+        if version != Some(expected_version) {
+            return Err(RepositoryError::Conflict(expected_version));
         }
+        Ok(())
+    }
 
-        // 1. For each relation attribute apply connect / disconnect using UUIDs directly
+    /// Apply set / connect / disconnect relation operations within the unit of work.
+    ///
+    /// Every linked target is checked for existence first, so a dangling
+    /// link is reported as a validation failure naming the missing documents.
+    async fn write_relation_ops(
+        &self,
+        unit: &mut UnitOfWork,
+        document_type: &DocumentType,
+        document_id: DocumentInstanceId,
+        ops: &HashMap<AttributeId, RelationOps>,
+    ) -> Result<(), RepositoryError> {
         for (attr_id, rel_ops) in ops {
             let rel_meta = document_type.relations.get(attr_id).ok_or_else(|| {
                 RepositoryError::ValidationFailed(format!("Relation not found: {}", attr_id))
             })?;
 
-            let _related_type = self
+            let related_type = self
                 .schema_registry
                 .get(&rel_meta.target)
                 .ok_or(RepositoryError::DocumentTypeNotFound)?;
 
-            if !rel_ops.connect.is_empty() {
-                for target_id in &rel_ops.connect {
-                    let (sql, values) =
-                        insert_relation_entry(document_type, attr_id, document_id.0, target_id.0);
-                    sqlx_query_with(sql, values)
-                        .execute(self.database.database_pool())
-                        .await
-                        .map_err(map_db_error)?;
+            let set = rel_ops.set.as_deref().unwrap_or_default();
+            let targets: Vec<Uuid> = set
+                .iter()
+                .chain(rel_ops.connect.iter())
+                .map(|id| id.0)
+                .collect();
+            if !targets.is_empty() {
+                let (sql, values) = query_existing_document_ids(related_type, targets.clone());
+                let existing: HashSet<Uuid> = unit
+                    .fetch_all(sql, values)
+                    .await?
+                    .into_iter()
+                    .map(|row| row.get::<Uuid, _>(DOCUMENT_ID_FIELD_NAME))
+                    .collect();
+                let missing: Vec<String> = targets
+                    .iter()
+                    .filter(|id| !existing.contains(id))
+                    .map(Uuid::to_string)
+                    .collect();
+                if !missing.is_empty() {
+                    return Err(RepositoryError::ValidationFailed(format!(
+                        "Relation constraint violation: '{}' targets unknown documents: {}",
+                        attr_id,
+                        missing.join(", ")
+                    )));
                 }
             }
 
-            if !rel_ops.disconnect.is_empty() {
-                for target_id in &rel_ops.disconnect {
-                    let (sql, values) =
-                        delete_relation_entry(document_type, attr_id, document_id.0, target_id.0);
-                    sqlx_query_with(sql, values)
-                        .execute(self.database.database_pool())
-                        .await
-                        .map_err(map_db_error)?;
-                }
+            if rel_ops.set.is_some() {
+                let (sql, values) =
+                    delete_all_relation_entries(document_type, attr_id, document_id.0);
+                unit.execute(sql, values).await?;
+            }
+
+            for target_id in set.iter().chain(rel_ops.connect.iter()) {
+                let (sql, values) =
+                    insert_relation_entry(document_type, attr_id, document_id.0, target_id.0);
+                unit.execute(sql, values).await?;
+            }
+
+            for target_id in &rel_ops.disconnect {
+                let (sql, values) =
+                    delete_relation_entry(document_type, attr_id, document_id.0, target_id.0);
+                unit.execute(sql, values).await?;
             }
         }
 
         Ok(())
     }
-}
 
-impl PostgresDocumentsRepository {
-    async fn insert_main_table(
+    fn main_insert_params(
         &self,
         document_type: &DocumentType,
         instance: &DocumentInstance,
-    ) -> Result<(), RepositoryError> {
+    ) -> Vec<Expr> {
         let revision: i32 = match &instance.content.publication_state {
             PublicationState::Published { revision, .. } | PublicationState::Draft { revision } => {
                 *revision
@@ -422,7 +1900,7 @@ impl PostgresDocumentsRepository {
 
         let published_at = match &instance.content.publication_state {
             PublicationState::Published { published_at, .. } => Expr::from(*published_at),
-            _ => Expr::null(),
+            _ => typed_null(FieldType::DateTime),
         };
 
         let published_by = match &instance.content.publication_state {
@@ -430,10 +1908,10 @@ impl PostgresDocumentsRepository {
                 if let Some(user_id) = published_by {
                     Expr::from(user_id.to_string())
                 } else {
-                    Expr::null()
+                    typed_null(FieldType::Text)
                 }
             }
-            _ => Expr::null(),
+            _ => typed_null(FieldType::Text),
         };
 
         let mut params: Vec<Expr> = vec![
@@ -448,25 +1926,26 @@ impl PostgresDocumentsRepository {
         ];
 
         for field in document_type.fields.iter() {
-            match instance.content.fields.get(&field.id) {
-                Some(val) => params.push(val.into()),
-                None => params.push(Expr::null()),
-            }
+            params.push(field_expr(field, instance.content.fields.get(&field.id)));
         }
 
-        let (sql, values) = insert_document(document_type, params);
-        sqlx_query_with(sql, values)
-            .execute(self.database.database_pool())
-            .await
-            .map_err(map_db_error)?;
+        if document_type.has_localization() {
+            let written_at: Map<String, Value> = written_locales(document_type, &instance.content)
+                .into_iter()
+                .map(|locale| (locale, instance.audit.created_at.to_rfc3339().into()))
+                .collect();
+            params.push(Value::Object(written_at).into());
+        }
 
-        Ok(())
+        params
     }
 
     async fn update_main_table_content_and_metadata(
         &self,
+        unit: &mut UnitOfWork,
         document_type: &DocumentType,
         instance: &DocumentInstance,
+        expected_version: i32,
     ) -> Result<(), RepositoryError> {
         let mut column_values: Vec<(DynIden, Expr)> = vec![
             (UPDATED_FIELD_NAME.into(), instance.audit.updated_at.into()),
@@ -488,41 +1967,51 @@ impl PostgresDocumentsRepository {
                 column_values.push((PUBLISHED_FIELD_NAME.into(), (*published_at).into()));
                 let by_expr = match published_by {
                     Some(user_id) => Expr::from(user_id.to_string()),
-                    None => Expr::null(),
+                    None => typed_null(FieldType::Text),
                 };
                 column_values.push((PUBLISHED_BY_FIELD_NAME.into(), by_expr));
             }
             PublicationState::Draft { revision } => {
                 column_values.push((REVISION_FIELD_NAME.into(), (*revision).into()));
-                column_values.push((PUBLISHED_FIELD_NAME.into(), Expr::null()));
-                column_values.push((PUBLISHED_BY_FIELD_NAME.into(), Expr::null()));
+                column_values.push((PUBLISHED_FIELD_NAME.into(), typed_null(FieldType::DateTime)));
+                column_values.push((PUBLISHED_BY_FIELD_NAME.into(), typed_null(FieldType::Text)));
             }
         }
 
         for field in document_type.fields.iter() {
-            let expr = match instance.content.fields.get(&field.id) {
-                Some(val) => val.into(),
-                None => Expr::null(),
-            };
+            let expr = field_expr(field, instance.content.fields.get(&field.id));
             column_values.push((field.id.normalized().into(), expr));
         }
 
-        let (sql, values) = update_document(document_type, instance.document_id.0, column_values);
-        let result = sqlx_query_with(sql, values)
-            .execute(self.database.database_pool())
-            .await
-            .map_err(map_db_error)?;
+        if document_type.has_localization() {
+            column_values.push((
+                LOCALES_UPDATED_FIELD_NAME.into(),
+                locales_updated_expr(document_type, &instance.content, instance.audit.updated_at),
+            ));
+        }
 
+        let (sql, values) = update_document(
+            document_type,
+            instance.document_id.0,
+            expected_version,
+            column_values,
+        );
+        let result = unit.execute(sql, values).await?;
+
+        // the row is either gone or carries another version — both mean the
+        // instance this update was based on is stale
         if result.rows_affected() == 0 {
-            return Err(RepositoryError::DocumentInstanceNotFound);
+            return Err(RepositoryError::Conflict(expected_version));
         }
         Ok(())
     }
 
     async fn update_main_table_metadata_only(
         &self,
+        unit: &mut UnitOfWork,
         document_type: &DocumentType,
         instance: &DocumentInstance,
+        expected_version: i32,
     ) -> Result<(), RepositoryError> {
         let mut column_values: Vec<(DynIden, Expr)> = vec![
             (UPDATED_FIELD_NAME.into(), instance.audit.updated_at.into()),
@@ -544,39 +2033,45 @@ impl PostgresDocumentsRepository {
                 column_values.push((PUBLISHED_FIELD_NAME.into(), (*published_at).into()));
                 let by_expr = match published_by {
                     Some(user_id) => Expr::from(user_id.to_string()),
-                    None => Expr::null(),
+                    None => typed_null(FieldType::Text),
                 };
                 column_values.push((PUBLISHED_BY_FIELD_NAME.into(), by_expr));
             }
             PublicationState::Draft { revision } => {
                 column_values.push((REVISION_FIELD_NAME.into(), (*revision).into()));
-                column_values.push((PUBLISHED_FIELD_NAME.into(), Expr::null()));
-                column_values.push((PUBLISHED_BY_FIELD_NAME.into(), Expr::null()));
+                column_values.push((PUBLISHED_FIELD_NAME.into(), typed_null(FieldType::DateTime)));
+                column_values.push((PUBLISHED_BY_FIELD_NAME.into(), typed_null(FieldType::Text)));
             }
         }
 
-        let (sql, values) = update_document(document_type, instance.document_id.0, column_values);
-        let result = sqlx_query_with(sql, values)
-            .execute(self.database.database_pool())
-            .await
-            .map_err(map_db_error)?;
+        if let (Some(stage), Some(_)) = (&instance.workflow_stage, document_type.workflow()) {
+            column_values.push((WORKFLOW_STAGE_FIELD_NAME.into(), stage.to_string().into()));
+        }
+
+        let (sql, values) = update_document(
+            document_type,
+            instance.document_id.0,
+            expected_version,
+            column_values,
+        );
+        let result = unit.execute(sql, values).await?;
 
+        // the row is either gone or carries another version — both mean the
+        // instance this update was based on is stale
         if result.rows_affected() == 0 {
-            return Err(RepositoryError::DocumentInstanceNotFound);
+            return Err(RepositoryError::Conflict(expected_version));
         }
         Ok(())
     }
 
     async fn store_snapshot_for_published_instance(
         &self,
+        unit: &mut UnitOfWork,
         document_type: &DocumentType,
         instance: &DocumentInstance,
     ) -> Result<i64, RepositoryError> {
         let (sql, values) = build_snapshot_insert(document_type, instance);
-        let row = sqlx_query_with(sql, values)
-            .fetch_one(self.database.database_pool())
-            .await
-            .map_err(map_db_error)?;
+        let row = unit.fetch_one(sql, values).await?;
         let snapshot_id: i64 = row.try_get("snapshot_id").map_err(|e| {
             RepositoryError::DatabaseError(format!("Failed to retrieve snapshot_id: {}", e))
         })?;
@@ -585,14 +2080,12 @@ impl PostgresDocumentsRepository {
 
     async fn update_snapshot_for_published_instance(
         &self,
+        unit: &mut UnitOfWork,
         document_type: &DocumentType,
         instance: &DocumentInstance,
     ) -> Result<i64, RepositoryError> {
         let (sql, values) = build_snapshot_update(document_type, instance);
-        let row = sqlx_query_with(sql, values)
-            .fetch_one(self.database.database_pool())
-            .await
-            .map_err(map_db_error)?;
+        let row = unit.fetch_one(sql, values).await?;
         let snapshot_id: i64 = row.try_get("snapshot_id").map_err(|e| {
             RepositoryError::DatabaseError(format!("Failed to retrieve snapshot_id: {}", e))
         })?;
@@ -0,0 +1,105 @@
+use crate::domain::repository::RepositoryError;
+use crate::infrastructure::error_reporting::note_failed_query;
+use luminair_common::database::{Database, StatementKind};
+use sea_query_sqlx::SqlxValues;
+use sqlx::postgres::{PgQueryResult, PgRow};
+use sqlx::{AssertSqlSafe, Postgres, Transaction};
+
+/// One atomic write against the database.
+///
+/// Every statement of a repository write (main table, snapshot table and
+/// relation tables) runs through the same transaction, so the write either
+/// commits as a whole or leaves no trace. A unit of work dropped without
+/// [`UnitOfWork::commit`] is rolled back.
+pub struct UnitOfWork {
+    transaction: Transaction<'static, Postgres>,
+}
+
+impl UnitOfWork {
+    /// Begin a new transaction on the primary of `database`, whose statements
+    /// have the timeout of the writes.
+    pub async fn begin(database: &Database) -> Result<Self, RepositoryError> {
+        let transaction = database
+            .begin(StatementKind::Write)
+            .await
+            .map_err(map_db_error)?;
+        Ok(Self { transaction })
+    }
+
+    /// Execute a statement, returning the number of affected rows.
+    pub async fn execute(
+        &mut self,
+        sql: String,
+        values: SqlxValues,
+    ) -> Result<PgQueryResult, RepositoryError> {
+        let statement = sql.clone();
+        sqlx::query_with(AssertSqlSafe(sql), values)
+            .execute(&mut *self.transaction)
+            .await
+            .map_err(|e| map_statement_error(e, &statement))
+    }
+
+    /// Execute a statement returning exactly one row.
+    pub async fn fetch_one(
+        &mut self,
+        sql: String,
+        values: SqlxValues,
+    ) -> Result<PgRow, RepositoryError> {
+        let statement = sql.clone();
+        sqlx::query_with(AssertSqlSafe(sql), values)
+            .fetch_one(&mut *self.transaction)
+            .await
+            .map_err(|e| map_statement_error(e, &statement))
+    }
+
+    /// Execute a query returning all of its rows.
+    pub async fn fetch_all(
+        &mut self,
+        sql: String,
+        values: SqlxValues,
+    ) -> Result<Vec<PgRow>, RepositoryError> {
+        let statement = sql.clone();
+        sqlx::query_with(AssertSqlSafe(sql), values)
+            .fetch_all(&mut *self.transaction)
+            .await
+            .map_err(|e| map_statement_error(e, &statement))
+    }
+
+    /// Commit all statements of the unit of work.
+    pub async fn commit(self) -> Result<(), RepositoryError> {
+        self.transaction.commit().await.map_err(map_db_error)
+    }
+}
+
+/// Map sqlx errors of write statements to repository errors, keeping
+/// constraint violations distinguishable from other database failures.
+pub(crate) fn map_db_error(e: sqlx::Error) -> RepositoryError {
+    if let Some(db_err) = e.as_database_error() {
+        match db_err.code().as_deref() {
+            // Postgres error code 23505: unique_violation
+            // Raised when an insert/update violates a unique index constraint (e.g. duplicate UUID or UID).
+            Some("23505") => return RepositoryError::UniqueViolation(db_err.message().to_string()),
+
+            // Postgres error code 23503: foreign_key_violation
+            // Raised when a referenced key (such as a relation target ID) does not exist or is violated.
+            Some("23503") => {
+                return RepositoryError::ValidationFailed(format!(
+                    "Relation constraint violation: {}",
+                    db_err.message()
+                ));
+            }
+            _ => {}
+        }
+    }
+    RepositoryError::DatabaseError(e.to_string())
+}
+
+/// Map the error of `statement` as [`map_db_error`] does, noting the
+/// statement for the error reporting when it is a database failure.
+fn map_statement_error(e: sqlx::Error, statement: &str) -> RepositoryError {
+    let error = map_db_error(e);
+    if matches!(error, RepositoryError::DatabaseError(_)) {
+        note_failed_query(None, statement);
+    }
+    error
+}
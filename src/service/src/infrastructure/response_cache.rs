@@ -0,0 +1,345 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Debug;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::Router;
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum_prometheus::metrics::counter;
+use luminair_common::database::current_tenant;
+use luminair_common::{DocumentTypeApiId, DocumentTypesRegistry};
+use redis::AsyncCommands;
+use redis::aio::ConnectionManager;
+use serde::Deserialize;
+
+use crate::domain::events::ContentChangeEvent;
+use crate::infrastructure::events::EventPublisher;
+use crate::infrastructure::http::api::ApiError;
+use crate::infrastructure::http::handlers::content::response::SURROGATE_KEY;
+
+/// Header telling whether a response was served from the cache: `HIT` or `MISS`.
+pub const CACHE_STATUS_HEADER: &str = "x-cache";
+
+/// Headers of a response which are cached with it.
+static CACHED_HEADERS: [HeaderName; 3] =
+    [header::CONTENT_TYPE, header::CACHE_CONTROL, SURROGATE_KEY];
+
+/// Field of a cached response holding its body, next to its headers.
+const BODY_FIELD: &str = "body";
+
+/// Settings of the cache of the REST API responses
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResponseCacheSettings {
+    /// cache the responses of the document reads in Redis
+    #[serde(default)]
+    pub enabled: bool,
+    /// Redis the responses are stored in
+    #[serde(default = "default_url")]
+    pub url: String,
+    /// how long a response is served from the cache at most
+    #[serde(default = "default_ttl_seconds")]
+    pub ttl_seconds: u64,
+    /// prefix of the Redis keys, for instances sharing a Redis
+    #[serde(default = "default_key_prefix")]
+    pub key_prefix: String,
+}
+
+impl Default for ResponseCacheSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: default_url(),
+            ttl_seconds: default_ttl_seconds(),
+            key_prefix: default_key_prefix(),
+        }
+    }
+}
+
+fn default_url() -> String {
+    "redis://localhost:6379".to_string()
+}
+
+fn default_ttl_seconds() -> u64 {
+    60
+}
+
+fn default_key_prefix() -> String {
+    "luminair".to_string()
+}
+
+/// Cache of the successful JSON responses of the `GET /documents/{api_type}/..`
+/// routes, shared by the replicas of the service through Redis.
+///
+/// Responses are cached per document type under a generation counter, keyed
+/// by their path, query string and `Accept` header, so by the filters,
+/// locale and publication state asked for. A change of a document moves the
+/// generation of its type, and of the types it is related to, forward; the
+/// responses of the former generation are no longer looked up and expire
+/// with their TTL.
+///
+/// The changes are taken from the content change events relayed from the
+/// outbox, see [`ResponseCache::invalidating`], so writes through every API
+/// and the scheduler invalidate alike, shortly after they are committed.
+///
+/// With tenants, the keys of every tenant have a prefix of their own.
+#[derive(Clone)]
+pub struct ResponseCache {
+    connection: ConnectionManager,
+    key_prefix: Arc<str>,
+    ttl_seconds: u64,
+    registry: &'static dyn DocumentTypesRegistry,
+    related_types: Arc<HashMap<String, BTreeSet<String>>>,
+}
+
+impl ResponseCache {
+    /// Connect to the Redis of `settings`.
+    pub async fn connect(
+        settings: &ResponseCacheSettings,
+        registry: &'static dyn DocumentTypesRegistry,
+    ) -> anyhow::Result<Self> {
+        let client = redis::Client::open(settings.url.as_str())
+            .with_context(|| format!("invalid Redis url '{}'", settings.url))?;
+        let connection = ConnectionManager::new(client)
+            .await
+            .with_context(|| format!("failed to connect to Redis at '{}'", settings.url))?;
+        Ok(Self {
+            connection,
+            key_prefix: settings.key_prefix.as_str().into(),
+            ttl_seconds: settings.ttl_seconds,
+            registry,
+            related_types: Arc::new(related_types(registry)),
+        })
+    }
+
+    /// Serve the routes of `router` from the cache where they can be.
+    pub fn cache_responses<S: Clone + Send + Sync + 'static>(
+        &self,
+        router: Router<S>,
+    ) -> Router<S> {
+        let cache = self.clone();
+        router.route_layer(middleware::from_fn(move |request: Request, next: Next| {
+            let cache = cache.clone();
+            async move { cache.serve(request, next).await }
+        }))
+    }
+
+    /// `publisher`, which also invalidates the cached responses of the
+    /// documents of the events it publishes.
+    pub fn invalidating<P: EventPublisher>(&self, publisher: P) -> InvalidatingPublisher<P> {
+        InvalidatingPublisher {
+            publisher,
+            cache: self.clone(),
+        }
+    }
+
+    /// Drop the cached responses of the documents of `document_type`, by its
+    /// id, and of the document types related to it.
+    pub async fn invalidate(&self, document_type: &str) -> anyhow::Result<()> {
+        let mut pipe = redis::pipe();
+        pipe.incr(self.generation_key(document_type), 1).ignore();
+        for related in self.related_types.get(document_type).into_iter().flatten() {
+            pipe.incr(self.generation_key(related), 1).ignore();
+        }
+        pipe.query_async::<()>(&mut self.connection.clone())
+            .await
+            .with_context(|| format!("failed to invalidate the responses of '{document_type}'"))
+    }
+
+    /// Answer `request` from the cache, or run it and cache its response.
+    ///
+    /// The cache never fails a request: when Redis can't be reached, the
+    /// request is run as if nothing was cached.
+    async fn serve(&self, request: Request, next: Next) -> Response {
+        let Some(document_type) = self.cached_type(&request) else {
+            return next.run(request).await;
+        };
+        let generation = match self.generation(&document_type).await {
+            Ok(generation) => generation,
+            Err(e) => {
+                tracing::warn!("Response cache lookup failed: {:#}", e);
+                return next.run(request).await;
+            }
+        };
+        let key = self.response_key(&document_type, generation, &request);
+
+        match self.lookup(&key).await {
+            Ok(Some(response)) => {
+                counter!("response_cache_requests_total", "result" => "hit").increment(1);
+                return response;
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Response cache lookup failed: {:#}", e),
+        }
+        counter!("response_cache_requests_total", "result" => "miss").increment(1);
+
+        let response = next.run(request).await;
+        let is_json = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+        if response.status() != StatusCode::OK || !is_json {
+            return response;
+        }
+
+        let (mut parts, body) = response.into_parts();
+        let body = match axum::body::to_bytes(body, usize::MAX).await {
+            Ok(body) => body,
+            Err(e) => {
+                return ApiError::InternalServerError(format!("Failed to read the response: {e}"))
+                    .into_response();
+            }
+        };
+        if let Err(e) = self.store(&key, &parts.headers, &body).await {
+            tracing::warn!("Response cache store failed: {:#}", e);
+        }
+        parts
+            .headers
+            .insert(CACHE_STATUS_HEADER, HeaderValue::from_static("MISS"));
+        Response::from_parts(parts, Body::from(body))
+    }
+
+    /// Id of the document type whose documents `request` reads, when its
+    /// response can be cached.
+    fn cached_type(&self, request: &Request) -> Option<String> {
+        if request.method() != Method::GET {
+            return None;
+        }
+        let api_type = request
+            .uri()
+            .path()
+            .strip_prefix("/documents/")?
+            .split('/')
+            .next()?;
+        let api_id = DocumentTypeApiId::from_str(api_type).ok()?;
+        let document_type = self.registry.lookup(&api_id)?;
+        Some(document_type.id.to_string())
+    }
+
+    async fn generation(&self, document_type: &str) -> anyhow::Result<u64> {
+        let generation: Option<u64> = self
+            .connection
+            .clone()
+            .get(self.generation_key(document_type))
+            .await?;
+        Ok(generation.unwrap_or_default())
+    }
+
+    async fn lookup(&self, key: &str) -> anyhow::Result<Option<Response>> {
+        let mut entry: HashMap<String, Vec<u8>> = self.connection.clone().hgetall(key).await?;
+        let Some(body) = entry.remove(BODY_FIELD) else {
+            return Ok(None);
+        };
+        let mut response = Body::from(body).into_response();
+        for (name, value) in entry {
+            response.headers_mut().insert(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_bytes(&value)?,
+            );
+        }
+        response
+            .headers_mut()
+            .insert(CACHE_STATUS_HEADER, HeaderValue::from_static("HIT"));
+        Ok(Some(response))
+    }
+
+    /// Store `body` under `key` with its headers among [`CACHED_HEADERS`].
+    async fn store(&self, key: &str, headers: &HeaderMap, body: &[u8]) -> anyhow::Result<()> {
+        let mut fields = CACHED_HEADERS
+            .iter()
+            .filter_map(|name| Some((name.as_str(), headers.get(name)?.as_bytes())))
+            .collect::<Vec<_>>();
+        fields.push((BODY_FIELD, body));
+        redis::pipe()
+            .atomic()
+            .hset_multiple(key, &fields)
+            .ignore()
+            .expire(key, self.ttl_seconds as i64)
+            .ignore()
+            .query_async::<()>(&mut self.connection.clone())
+            .await?;
+        Ok(())
+    }
+
+    fn generation_key(&self, document_type: &str) -> String {
+        format!("{}:generation:{}", self.namespace(), document_type)
+    }
+
+    fn response_key(&self, document_type: &str, generation: u64, request: &Request) -> String {
+        let accept = request
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .unwrap_or_default();
+        let path = request
+            .uri()
+            .path_and_query()
+            .map(|path| path.as_str())
+            .unwrap_or_default();
+        format!(
+            "{}:response:{}:{}:{}:{}",
+            self.namespace(),
+            document_type,
+            generation,
+            accept,
+            path
+        )
+    }
+
+    /// Prefix of the keys of the tenant of the running task, if any.
+    fn namespace(&self) -> String {
+        match current_tenant() {
+            Some(tenant) => format!("{}:{}", self.key_prefix, tenant),
+            None => self.key_prefix.to_string(),
+        }
+    }
+}
+
+impl Debug for ResponseCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseCache")
+            .field("key_prefix", &self.key_prefix)
+            .field("ttl_seconds", &self.ttl_seconds)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Event publisher invalidating the cached responses of the changed
+/// documents, once the events are published.
+pub struct InvalidatingPublisher<P> {
+    publisher: P,
+    cache: ResponseCache,
+}
+
+impl<P: EventPublisher> EventPublisher for InvalidatingPublisher<P> {
+    /// A failed invalidation doesn't fail the publish, which would publish
+    /// the event again; the stale responses expire with their TTL.
+    async fn publish(&self, event: &ContentChangeEvent) -> anyhow::Result<()> {
+        self.publisher.publish(event).await?;
+        if let Err(e) = self.cache.invalidate(&event.document_type).await {
+            tracing::warn!("Response cache invalidation failed: {:#}", e);
+        }
+        Ok(())
+    }
+}
+
+/// Ids of the document types related to each document type, on either side
+/// of a relation: their responses embed each other's documents when populated.
+fn related_types(registry: &dyn DocumentTypesRegistry) -> HashMap<String, BTreeSet<String>> {
+    let mut related = HashMap::<String, BTreeSet<String>>::new();
+    for document_type in registry.iterate() {
+        for relation in &document_type.relations {
+            let (id, target) = (document_type.id.to_string(), relation.target.to_string());
+            related
+                .entry(id.clone())
+                .or_default()
+                .insert(target.clone());
+            related.entry(target).or_default().insert(id);
+        }
+    }
+    related
+}
@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use luminair_common::database::{self, Database};
+use serde::Deserialize;
+use tokio::time::MissedTickBehavior;
+
+use crate::application::AppState;
+use crate::application::commands::ApplyRetentionCommand;
+use crate::application::service::DocumentsService;
+
+/// Settings of the maintenance removing the expired documents of the types
+/// with a retention
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetentionSettings {
+    /// run the maintenance in this instance of the service
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// how often the expired documents are looked up
+    #[serde(default = "default_interval_seconds")]
+    pub interval_seconds: u64,
+    /// documents removed in a transaction
+    #[serde(default = "default_batch_size")]
+    pub batch_size: u64,
+    /// only report the expired documents, without removing them
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_seconds: default_interval_seconds(),
+            batch_size: default_batch_size(),
+            dry_run: false,
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_seconds() -> u64 {
+    60 * 60
+}
+
+fn default_batch_size() -> u64 {
+    1000
+}
+
+/// Delete or archive the expired documents of every tenant, every
+/// `interval_seconds` of `settings`, as long as the service runs.
+///
+/// Replicas running it at the same time share the expired documents out;
+/// a failed run is retried with the next tick.
+pub async fn run_retention<S: AppState>(
+    state: S,
+    database: &'static Database,
+    settings: RetentionSettings,
+) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(settings.interval_seconds));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        for tenant in database.tenant_scopes() {
+            let command = ApplyRetentionCommand {
+                now: Utc::now(),
+                dry_run: settings.dry_run,
+                batch_size: settings.batch_size,
+            };
+            let result =
+                database::with_tenant(tenant, state.documents_service().apply_retention(command))
+                    .await;
+            match result {
+                Ok(documents) if documents.is_empty() => {}
+                Ok(documents) if settings.dry_run => {
+                    tracing::info!("Expired documents to remove: {:?}", documents)
+                }
+                Ok(documents) => tracing::info!("Removed expired documents: {:?}", documents),
+                Err(e) => tracing::warn!("Applying the retention failed: {:#}", e),
+            }
+        }
+    }
+}
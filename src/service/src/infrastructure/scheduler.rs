@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use luminair_common::database::{self, Database};
+use serde::Deserialize;
+use tokio::time::MissedTickBehavior;
+
+use crate::application::AppState;
+use crate::application::commands::ApplyScheduleCommand;
+use crate::application::service::DocumentsService;
+
+/// Key of the Postgres advisory lock held by the replica running the
+/// scheduled publications, so they run on one replica at a time.
+pub const SCHEDULER_LOCK_KEY: i64 = 0x4c75_6d69_6e61_6972;
+
+/// Settings of the scheduled publication worker
+#[derive(Debug, Clone, Deserialize)]
+pub struct SchedulerSettings {
+    /// run the worker in this instance of the service
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// how often due publications are looked up
+    #[serde(default = "default_interval_seconds")]
+    pub interval_seconds: u64,
+}
+
+impl Default for SchedulerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_seconds: default_interval_seconds(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_seconds() -> u64 {
+    30
+}
+
+/// Publish and unpublish the documents whose scheduled time has come, every
+/// `interval`, as long as the service runs.
+pub async fn run_scheduled_publications<S: AppState>(
+    state: S,
+    database: &'static Database,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        // the publications of every tenant in turn
+        for tenant in database.tenant_scopes() {
+            let result =
+                database::with_tenant(tenant, apply_due_publications(&state, database, Utc::now()))
+                    .await;
+            match result {
+                Ok(Some(applied)) if applied > 0 => {
+                    tracing::info!("Applied {} scheduled publications", applied)
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Scheduled publications failed: {:#}", e),
+            }
+        }
+    }
+}
+
+/// Apply the publications of all document types due at `now`, unless
+/// another replica holds the scheduler lock.
+///
+/// Returns the number of documents changed, or `None` when another replica
+/// is running the scheduled publications.
+pub async fn apply_due_publications<S: AppState>(
+    state: &S,
+    database: &Database,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Option<usize>> {
+    // the lock belongs to the session: it is taken and released on the same
+    // connection, which is kept aside meanwhile
+    let mut connection = database.database_pool().acquire().await?;
+    let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+        .bind(SCHEDULER_LOCK_KEY)
+        .fetch_one(&mut *connection)
+        .await?;
+    if !locked {
+        return Ok(None);
+    }
+
+    let result = apply_all(state, now).await;
+
+    let unlocked = sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(SCHEDULER_LOCK_KEY)
+        .execute(&mut *connection)
+        .await;
+    if let Err(e) = unlocked {
+        // closing the session releases the lock, instead of returning the
+        // connection to the pool with it
+        drop(connection.detach());
+        return Err(e.into());
+    }
+
+    result.map(Some)
+}
+
+async fn apply_all<S: AppState>(state: &S, now: DateTime<Utc>) -> anyhow::Result<usize> {
+    let mut applied = 0;
+    let document_types = state.document_types().iterate().collect::<Vec<_>>();
+    for document_type in document_types {
+        if !document_type.has_draft_and_publish() {
+            continue;
+        }
+        let cmd = ApplyScheduleCommand { document_type, now };
+        applied += state.documents_service().apply_schedule(cmd).await?;
+    }
+    Ok(applied)
+}
@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+
+use axum_prometheus::metrics::{counter, gauge};
+use chrono::{DateTime, Utc};
+use luminair_common::DocumentTypesRegistry;
+
+/// Size of the schema loaded from the schema directory.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SchemaStats {
+    pub document_types: usize,
+    pub fields: usize,
+    pub relations: usize,
+    /// distinct locales of all document types
+    pub locales: usize,
+}
+
+impl SchemaStats {
+    pub fn of(registry: &dyn DocumentTypesRegistry) -> Self {
+        let mut stats = Self::default();
+        let mut locales = HashSet::new();
+        for document_type in registry.iterate() {
+            stats.document_types += 1;
+            stats.fields += document_type.fields.len();
+            stats.relations += document_type.relations.len();
+            locales.extend(
+                document_type
+                    .options
+                    .iter()
+                    .flat_map(|options| options.localizations.iter()),
+            );
+        }
+        stats.locales = locales.len();
+        stats
+    }
+}
+
+/// Publish the schema of `registry`, loaded at `loaded_at`, as Prometheus
+/// gauges:
+///
+/// - `schema_document_types`, `schema_fields`, `schema_relations` and
+///   `schema_locales`: what the schema is made of
+/// - `schema_loaded_timestamp`: when it was loaded, in seconds since the epoch
+///
+/// and count the load in `schema_loads_total`. The schema is loaded once, at
+/// startup, and a schema directory failing to load stops the startup, so a
+/// deploy with a broken schema shows as a replica that doesn't come up.
+pub fn record_schema_metrics(registry: &dyn DocumentTypesRegistry, loaded_at: DateTime<Utc>) {
+    let stats = SchemaStats::of(registry);
+    gauge!("schema_document_types").set(stats.document_types as f64);
+    gauge!("schema_fields").set(stats.fields as f64);
+    gauge!("schema_relations").set(stats.relations as f64);
+    gauge!("schema_locales").set(stats.locales as f64);
+    gauge!("schema_loaded_timestamp").set(loaded_at.timestamp() as f64);
+    counter!("schema_loads_total", "result" => "success").increment(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use luminair_common::entities::{
+        DocumentField, DocumentTypeOptions, FieldType, LocalizationId,
+    };
+    use luminair_common::{AttributeId, DocumentType, DocumentTypeApiId, DocumentTypeId};
+
+    #[derive(Debug)]
+    struct MockRegistry {
+        types: Vec<DocumentType>,
+    }
+
+    impl DocumentTypesRegistry for MockRegistry {
+        fn iterate(&self) -> Box<dyn Iterator<Item = &DocumentType> + '_> {
+            Box::new(self.types.iter())
+        }
+        fn get(&self, id: &DocumentTypeId) -> Option<&DocumentType> {
+            self.types.iter().find(|dt| &dt.id == id)
+        }
+        fn lookup(&self, _api_id: &DocumentTypeApiId) -> Option<&DocumentType> {
+            None
+        }
+    }
+
+    fn localized(id: &str, locales: &[&str]) -> DocumentType {
+        let mut document = DocumentType::new_bare_collection(id, id, &format!("{id}s")).unwrap();
+        document.fields.insert(DocumentField {
+            id: AttributeId::try_new("name").unwrap(),
+            field_type: FieldType::Text,
+            constraints: Default::default(),
+            required: false,
+            unique: false,
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        });
+        document.options = Some(DocumentTypeOptions {
+            draft_and_publish: false,
+            localizations: locales
+                .iter()
+                .map(|locale| LocalizationId::try_new(*locale).unwrap())
+                .collect(),
+            workflow: None,
+            cache_control: None,
+            feed: None,
+            operations: None,
+            partitioning: None,
+            view: None,
+            retention: None,
+        });
+        document
+    }
+
+    #[test]
+    fn test_stats_count_distinct_locales() {
+        let registry = MockRegistry {
+            types: vec![
+                localized("brand", &["en", "ro"]),
+                localized("partner", &["en", "ru"]),
+            ],
+        };
+
+        assert_eq!(
+            SchemaStats::of(&registry),
+            SchemaStats {
+                document_types: 2,
+                fields: 2,
+                relations: 0,
+                locales: 3,
+            }
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use anyhow::Context;
+use futures::future::BoxFuture;
+use luminair_common::entities::FieldType;
+use reqwest::{Client, Method, RequestBuilder, StatusCode, Url};
+use serde_json::{Map, Value, json};
+
+use crate::infrastructure::search_index::{
+    IndexSettings, PRIMARY_KEY, SearchEngine, TIMESTAMP_FIELDS,
+};
+
+/// Media type of the bodies of the bulk requests.
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Indexes the published documents in Elasticsearch.
+///
+/// The fields are mapped by their type; the mapping of an existing field
+/// can't change, a changed field type takes a new index.
+pub struct ElasticsearchEngine {
+    client: Client,
+    url: Url,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl ElasticsearchEngine {
+    pub fn new(
+        url: &str,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+            url: Url::parse(url).with_context(|| format!("invalid Elasticsearch url '{url}'"))?,
+            username,
+            password,
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> anyhow::Result<RequestBuilder> {
+        let url = self.url.join(path)?;
+        let request = self.client.request(method, url);
+        Ok(match &self.username {
+            Some(username) => request.basic_auth(username, self.password.as_ref()),
+            None => request,
+        })
+    }
+
+    async fn send(&self, request: RequestBuilder) -> anyhow::Result<reqwest::Response> {
+        request
+            .send()
+            .await
+            .context("failed to reach Elasticsearch")?
+            .error_for_status()
+            .context("Elasticsearch rejected the request")
+    }
+
+    /// Run the bulk `actions`, failing with the first action which failed.
+    async fn bulk(&self, index: &str, actions: String) -> anyhow::Result<()> {
+        let request = self
+            .request(Method::POST, &format!("{index}/_bulk"))?
+            .header(reqwest::header::CONTENT_TYPE, NDJSON_CONTENT_TYPE)
+            .body(actions);
+        let response: Value = self.send(request).await?.json().await?;
+        if response["errors"].as_bool().unwrap_or(false) {
+            let error = response["items"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|item| item.as_object()?.values().next())
+                .find_map(|result| result.get("error"))
+                .cloned()
+                .unwrap_or(Value::Null);
+            anyhow::bail!("Elasticsearch failed a bulk action: {error}");
+        }
+        Ok(())
+    }
+}
+
+impl SearchEngine for ElasticsearchEngine {
+    /// Fields added to the schema are added to the mapping of an existing
+    /// index.
+    fn configure<'a>(
+        &'a self,
+        index: &'a str,
+        settings: &'a IndexSettings,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let properties = mapping(settings);
+            let exists = self
+                .request(Method::HEAD, index)?
+                .send()
+                .await
+                .context("failed to reach Elasticsearch")?
+                .status()
+                != StatusCode::NOT_FOUND;
+            let request = if exists {
+                self.request(Method::PUT, &format!("{index}/_mapping"))?
+                    .json(&json!({ "properties": properties }))
+            } else {
+                self.request(Method::PUT, index)?
+                    .json(&json!({ "mappings": { "properties": properties } }))
+            };
+            self.send(request).await?;
+            Ok(())
+        })
+    }
+
+    fn upsert<'a>(
+        &'a self,
+        index: &'a str,
+        documents: Vec<Value>,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let mut actions = String::new();
+            for document in documents {
+                let id = document[PRIMARY_KEY]
+                    .as_str()
+                    .context("the document has no id")?
+                    .to_string();
+                actions.push_str(&json!({ "index": { "_id": id } }).to_string());
+                actions.push('\n');
+                actions.push_str(&document.to_string());
+                actions.push('\n');
+            }
+            self.bulk(index, actions).await
+        })
+    }
+
+    /// Documents which aren't indexed are no failure.
+    fn delete<'a>(&'a self, index: &'a str, ids: Vec<String>) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let mut actions = String::new();
+            for id in ids {
+                actions.push_str(&json!({ "delete": { "_id": id } }).to_string());
+                actions.push('\n');
+            }
+            self.bulk(index, actions).await
+        })
+    }
+
+    fn clear<'a>(&'a self, index: &'a str) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let request = self
+                .request(Method::POST, &format!("{index}/_delete_by_query"))?
+                .query(&[("conflicts", "proceed")])
+                .json(&json!({ "query": { "match_all": {} } }));
+            self.send(request).await?;
+            Ok(())
+        })
+    }
+}
+
+/// Properties of the mapping of an index with `settings`: the localized
+/// texts and JSON fields are left to the dynamic mapping.
+fn mapping(settings: &IndexSettings) -> Map<String, Value> {
+    let mut properties = Map::new();
+    properties.insert(PRIMARY_KEY.to_string(), json!({ "type": "keyword" }));
+    for field in TIMESTAMP_FIELDS {
+        properties.insert(field.to_string(), json!({ "type": "date" }));
+    }
+    for (name, field_type) in &settings.fields {
+        let property = match field_type {
+            FieldType::Text => json!({
+                "type": "text",
+                "fields": { "keyword": { "type": "keyword", "ignore_above": 256 } }
+            }),
+            // times of day sort as texts
+            FieldType::Uid | FieldType::Uuid | FieldType::Time => json!({ "type": "keyword" }),
+            FieldType::Integer(_) => json!({ "type": "long" }),
+            FieldType::Decimal { .. } => json!({ "type": "double" }),
+            FieldType::Date | FieldType::DateTime | FieldType::LocalDateTime => {
+                json!({ "type": "date" })
+            }
+            FieldType::Boolean => json!({ "type": "boolean" }),
+            FieldType::LocalizedText | FieldType::Json => continue,
+        };
+        properties.insert(name.clone(), property);
+    }
+    properties
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use luminair_common::entities::IntegerSize;
+
+    #[test]
+    fn test_fields_are_mapped_by_their_type() {
+        let settings = IndexSettings {
+            searchable: vec!["title".to_string()],
+            filterable: vec!["rank".to_string()],
+            sortable: vec!["rank".to_string()],
+            fields: vec![
+                ("title".to_string(), FieldType::Text),
+                ("rank".to_string(), FieldType::Integer(IntegerSize::Int32)),
+                ("summary".to_string(), FieldType::LocalizedText),
+            ],
+        };
+
+        let properties = mapping(&settings);
+        assert_eq!(properties["documentId"], json!({ "type": "keyword" }));
+        assert_eq!(properties["publishedAt"], json!({ "type": "date" }));
+        assert_eq!(properties["title"]["type"], "text");
+        assert_eq!(properties["rank"], json!({ "type": "long" }));
+        assert!(!properties.contains_key("summary"));
+    }
+}
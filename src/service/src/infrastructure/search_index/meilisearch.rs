@@ -0,0 +1,104 @@
+use anyhow::Context;
+use futures::future::BoxFuture;
+use reqwest::{Client, Method, RequestBuilder, Url};
+use serde_json::{Value, json};
+
+use crate::infrastructure::search_index::{IndexSettings, PRIMARY_KEY, SearchEngine};
+
+/// Indexes the published documents in Meilisearch.
+///
+/// Meilisearch applies the writes as tasks, after answering them: a write it
+/// fails later is only seen in its task list.
+pub struct MeilisearchEngine {
+    client: Client,
+    url: Url,
+    api_key: Option<String>,
+}
+
+impl MeilisearchEngine {
+    pub fn new(url: &str, api_key: Option<String>) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+            url: Url::parse(url).with_context(|| format!("invalid Meilisearch url '{url}'"))?,
+            api_key,
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> anyhow::Result<RequestBuilder> {
+        let url = self.url.join(path)?;
+        let request = self.client.request(method, url);
+        Ok(match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        })
+    }
+
+    async fn send(&self, request: RequestBuilder) -> anyhow::Result<()> {
+        request
+            .send()
+            .await
+            .context("failed to reach Meilisearch")?
+            .error_for_status()
+            .context("Meilisearch rejected the request")?;
+        Ok(())
+    }
+}
+
+impl SearchEngine for MeilisearchEngine {
+    /// The settings create the index when it doesn't exist.
+    fn configure<'a>(
+        &'a self,
+        index: &'a str,
+        settings: &'a IndexSettings,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            // without searchable fields, every field is searched
+            let searchable = if settings.searchable.is_empty() {
+                vec!["*".to_string()]
+            } else {
+                settings.searchable.clone()
+            };
+            let request = self
+                .request(Method::PATCH, &format!("indexes/{index}/settings"))?
+                .json(&json!({
+                    "searchableAttributes": searchable,
+                    "filterableAttributes": settings.filterable,
+                    "sortableAttributes": settings.sortable,
+                }));
+            self.send(request).await
+        })
+    }
+
+    fn upsert<'a>(
+        &'a self,
+        index: &'a str,
+        documents: Vec<Value>,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let request = self
+                .request(Method::POST, &format!("indexes/{index}/documents"))?
+                .query(&[("primaryKey", PRIMARY_KEY)])
+                .json(&documents);
+            self.send(request).await
+        })
+    }
+
+    fn delete<'a>(&'a self, index: &'a str, ids: Vec<String>) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let request = self
+                .request(
+                    Method::POST,
+                    &format!("indexes/{index}/documents/delete-batch"),
+                )?
+                .json(&ids);
+            self.send(request).await
+        })
+    }
+
+    fn clear<'a>(&'a self, index: &'a str) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let request = self.request(Method::DELETE, &format!("indexes/{index}/documents"))?;
+            self.send(request).await
+        })
+    }
+}
@@ -0,0 +1,452 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use futures::StreamExt;
+use futures::future::BoxFuture;
+use luminair_common::database::{self, Database};
+use luminair_common::entities::FieldType;
+use luminair_common::{DocumentType, DocumentTypeId, DocumentTypesRegistry};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::application::AppState;
+use crate::application::commands::{FindByIdCommand, StreamDocumentsCommand};
+use crate::application::service::DocumentsService;
+use crate::domain::document::{DocumentInstance, DocumentInstanceId};
+use crate::domain::events::ContentChangeEvent;
+use crate::domain::query::{DocumentInstanceQuery, DocumentStatus};
+use crate::infrastructure::events::EventPublisher;
+use crate::infrastructure::http::handlers::content::response::{
+    DocumentInstanceResponse, attribute_key,
+};
+use crate::infrastructure::jobs::JobHandler;
+
+pub mod elasticsearch;
+pub mod meilisearch;
+
+/// Kind of the jobs rebuilding the search indexes of all document types from
+/// their published documents, without payload.
+pub const REINDEX_SEARCH_JOB: &str = "reindex-search";
+
+/// Field of the indexed documents which identifies them: the document id.
+pub const PRIMARY_KEY: &str = "documentId";
+
+/// Timestamps of the indexed documents, which they can be sorted by.
+pub const TIMESTAMP_FIELDS: [&str; 2] = ["publishedAt", "updatedAt"];
+
+/// Settings of the sync of the published documents to a search engine
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchIndexSettings {
+    /// index the published documents with the content change events
+    #[serde(default)]
+    pub enabled: bool,
+    /// prefix of the index names, for instances sharing a search engine
+    #[serde(default = "default_index_prefix")]
+    pub index_prefix: String,
+    /// most documents sent to the search engine per request of a reindex
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// search engine the documents are indexed in
+    #[serde(default)]
+    pub engine: Option<SearchEngineSettings>,
+}
+
+impl Default for SearchIndexSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            index_prefix: default_index_prefix(),
+            batch_size: default_batch_size(),
+            engine: None,
+        }
+    }
+}
+
+/// Search engine the published documents are indexed in
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SearchEngineSettings {
+    /// Meilisearch, with `api_key` as bearer token if any
+    Meilisearch {
+        url: String,
+        #[serde(default)]
+        api_key: Option<String>,
+    },
+    /// Elasticsearch, with basic authentication if `username` is given
+    Elasticsearch {
+        url: String,
+        #[serde(default)]
+        username: Option<String>,
+        #[serde(default)]
+        password: Option<String>,
+    },
+}
+
+fn default_index_prefix() -> String {
+    "luminair".to_string()
+}
+
+fn default_batch_size() -> usize {
+    500
+}
+
+/// How the documents of a type are indexed, derived from its fields.
+///
+/// The names are those of the REST API, the documents being indexed as it
+/// answers them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexSettings {
+    /// fields the search text is matched against
+    pub searchable: Vec<String>,
+    /// fields the results can be filtered by
+    pub filterable: Vec<String>,
+    /// fields the results can be sorted by
+    pub sortable: Vec<String>,
+    /// every field with its type, for engines mapping them explicitly
+    pub fields: Vec<(String, FieldType)>,
+}
+
+impl IndexSettings {
+    /// Settings of the index of `document_type`: its text fields are
+    /// searched, its other scalar fields filter and its text, number and
+    /// date fields sort the results.
+    pub fn of(document_type: &DocumentType) -> Self {
+        let mut fields: Vec<(String, FieldType)> = document_type
+            .fields
+            .iter()
+            .map(|field| (attribute_key(document_type, &field.id), field.field_type))
+            .collect();
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let names = |keep: fn(&FieldType) -> bool| {
+            fields
+                .iter()
+                .filter(|(_, field_type)| keep(field_type))
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>()
+        };
+        let searchable = names(FieldType::is_text);
+        let filterable = names(|field_type| {
+            !matches!(
+                field_type,
+                FieldType::Text | FieldType::LocalizedText | FieldType::Json
+            )
+        });
+        let mut sortable = names(|field_type| {
+            !matches!(
+                field_type,
+                FieldType::LocalizedText | FieldType::Uuid | FieldType::Boolean | FieldType::Json
+            )
+        });
+        sortable.extend(TIMESTAMP_FIELDS.map(String::from));
+
+        Self {
+            searchable,
+            filterable,
+            sortable,
+            fields,
+        }
+    }
+}
+
+/// Adapter of the search engine the published documents are indexed in.
+pub trait SearchEngine: Send + Sync + 'static {
+    /// Create `index` unless it exists, and apply `settings` to it.
+    fn configure<'a>(
+        &'a self,
+        index: &'a str,
+        settings: &'a IndexSettings,
+    ) -> BoxFuture<'a, anyhow::Result<()>>;
+
+    /// Index `documents` by their [`PRIMARY_KEY`], replacing those indexed
+    /// with the same keys.
+    fn upsert<'a>(
+        &'a self,
+        index: &'a str,
+        documents: Vec<Value>,
+    ) -> BoxFuture<'a, anyhow::Result<()>>;
+
+    /// Remove the documents with the keys `ids` from `index`, if indexed.
+    fn delete<'a>(&'a self, index: &'a str, ids: Vec<String>) -> BoxFuture<'a, anyhow::Result<()>>;
+
+    /// Remove all documents from `index`.
+    fn clear<'a>(&'a self, index: &'a str) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+/// Index of the published documents in an external search engine, an index
+/// per document type, kept in sync by the content change events.
+///
+/// Postgres stays the source of truth: the documents of the events are read
+/// back from it, and [`SearchIndex::reindex`] rebuilds the indexes from it,
+/// e.g. after the schema changed or the engine lost its data.
+///
+/// With tenants, every tenant has indexes of its own.
+#[derive(Clone)]
+pub struct SearchIndex<S> {
+    engine: Arc<dyn SearchEngine>,
+    state: S,
+    index_prefix: Arc<str>,
+    batch_size: usize,
+}
+
+impl<S: AppState> SearchIndex<S> {
+    /// Index of the published documents of `state` in the engine configured
+    /// in `settings`.
+    pub fn new(settings: &SearchIndexSettings, state: S) -> anyhow::Result<Self> {
+        let engine: Arc<dyn SearchEngine> = match &settings.engine {
+            Some(SearchEngineSettings::Meilisearch { url, api_key }) => {
+                Arc::new(meilisearch::MeilisearchEngine::new(url, api_key.clone())?)
+            }
+            Some(SearchEngineSettings::Elasticsearch {
+                url,
+                username,
+                password,
+            }) => Arc::new(elasticsearch::ElasticsearchEngine::new(
+                url,
+                username.clone(),
+                password.clone(),
+            )?),
+            None => {
+                anyhow::bail!("the search index is enabled, but no search engine is configured")
+            }
+        };
+        Ok(Self::with_engine(engine, settings, state))
+    }
+
+    /// Index of the published documents of `state` in `engine`.
+    pub fn with_engine(
+        engine: Arc<dyn SearchEngine>,
+        settings: &SearchIndexSettings,
+        state: S,
+    ) -> Self {
+        Self {
+            engine,
+            state,
+            index_prefix: settings.index_prefix.as_str().into(),
+            batch_size: settings.batch_size.max(1),
+        }
+    }
+
+    /// `publisher`, which also syncs the index with the documents of the
+    /// events it publishes.
+    pub fn indexing<P: EventPublisher>(&self, publisher: P) -> IndexingPublisher<P, S> {
+        IndexingPublisher {
+            publisher,
+            index: self.clone(),
+        }
+    }
+
+    /// Handler of the [`REINDEX_SEARCH_JOB`]s.
+    pub fn reindex_job(&self) -> ReindexSearchJob<S> {
+        ReindexSearchJob {
+            index: self.clone(),
+        }
+    }
+
+    /// Name of the index of `document_type`, for the current tenant.
+    pub fn index_name(&self, document_type: &DocumentType) -> String {
+        match database::current_tenant() {
+            Some(tenant) => format!("{}-{}-{}", self.index_prefix, tenant, document_type.id),
+            None => format!("{}-{}", self.index_prefix, document_type.id),
+        }
+        .to_lowercase()
+    }
+
+    /// Create the indexes of all document types of every tenant of
+    /// `database`, with the settings derived from their fields.
+    pub async fn configure_indexes(&self, database: &Database) -> anyhow::Result<()> {
+        for tenant in database.tenant_scopes() {
+            database::with_tenant(tenant, async {
+                for document_type in self.state.document_types().iterate() {
+                    let index = self.index_name(document_type);
+                    self.engine
+                        .configure(&index, &IndexSettings::of(document_type))
+                        .await
+                        .with_context(|| format!("failed to configure the index '{index}'"))?;
+                }
+                Ok::<(), anyhow::Error>(())
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Sync the index with the document of `event`: its published version is
+    /// indexed, or it is removed from the index when it has none.
+    pub async fn sync(&self, event: &ContentChangeEvent) -> anyhow::Result<()> {
+        let document_type = DocumentTypeId::try_new(&event.document_type)
+            .ok()
+            .and_then(|id| self.state.document_types().get(&id))
+            .with_context(|| format!("unknown document type '{}'", event.document_type))?;
+        let document_instance_id = DocumentInstanceId::try_from(&event.document_id)?;
+        let index = self.index_name(document_type);
+
+        let published = self
+            .state
+            .documents_service()
+            .find_by_id(FindByIdCommand {
+                document_type,
+                document_instance_id,
+                populate: None,
+                populate_filters: None,
+                populate_options: Default::default(),
+                query: DocumentInstanceQuery::new().with_status(DocumentStatus::Published),
+            })
+            .await?;
+        match published {
+            Some(document) => {
+                self.engine
+                    .upsert(
+                        &index,
+                        vec![search_document(
+                            document,
+                            document_type,
+                            self.state.document_types(),
+                        )?],
+                    )
+                    .await
+            }
+            None => {
+                self.engine
+                    .delete(&index, vec![event.document_id.clone()])
+                    .await
+            }
+        }
+    }
+
+    /// Rebuild the indexes of all document types of the current tenant from
+    /// their published documents, returning how many were indexed.
+    ///
+    /// An index is emptied before its documents are indexed again, so it
+    /// misses documents until its type is done.
+    pub async fn reindex(&self) -> anyhow::Result<u64> {
+        let mut indexed = 0;
+        let document_types = self.state.document_types().iterate().collect::<Vec<_>>();
+        for document_type in document_types {
+            let index = self.index_name(document_type);
+            self.engine
+                .configure(&index, &IndexSettings::of(document_type))
+                .await?;
+            self.engine.clear(&index).await?;
+
+            let mut batches = self
+                .state
+                .documents_service()
+                .stream(StreamDocumentsCommand {
+                    document_type,
+                    query: DocumentInstanceQuery::new().with_status(DocumentStatus::Published),
+                })
+                .chunks(self.batch_size);
+            while let Some(batch) = batches.next().await {
+                let documents = batch
+                    .into_iter()
+                    .map(|document| {
+                        search_document(document?, document_type, self.state.document_types())
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                indexed += documents.len() as u64;
+                self.engine.upsert(&index, documents).await?;
+            }
+        }
+        Ok(indexed)
+    }
+}
+
+/// Event publisher syncing the search index with the changed documents,
+/// once the events are published.
+pub struct IndexingPublisher<P, S> {
+    publisher: P,
+    index: SearchIndex<S>,
+}
+
+impl<P: EventPublisher, S: AppState> EventPublisher for IndexingPublisher<P, S> {
+    /// A failed sync doesn't fail the publish, which would publish the event
+    /// again; the document is indexed with its next change, or a reindex.
+    async fn publish(&self, event: &ContentChangeEvent) -> anyhow::Result<()> {
+        self.publisher.publish(event).await?;
+        if let Err(e) = self.index.sync(event).await {
+            tracing::warn!(
+                "Indexing document {} of '{}' failed: {:#}",
+                event.document_id,
+                event.document_type,
+                e
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Runs the [`REINDEX_SEARCH_JOB`]s.
+pub struct ReindexSearchJob<S> {
+    index: SearchIndex<S>,
+}
+
+impl<S: AppState> JobHandler for ReindexSearchJob<S> {
+    fn run<'a>(&'a self, _payload: &'a Value) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let indexed = self.index.reindex().await?;
+            tracing::info!("Reindexed {} published documents", indexed);
+            Ok(())
+        })
+    }
+}
+
+/// `document` as indexed: as the REST API answers it.
+fn search_document(
+    document: DocumentInstance,
+    document_type: &DocumentType,
+    registry: &dyn DocumentTypesRegistry,
+) -> anyhow::Result<Value> {
+    Ok(serde_json::to_value(DocumentInstanceResponse::new(
+        document,
+        document_type,
+        registry,
+    ))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use luminair_common::AttributeId;
+    use luminair_common::entities::{DocumentField, IntegerSize};
+    use std::collections::HashSet;
+
+    fn field(id: &str, field_type: FieldType) -> DocumentField {
+        DocumentField {
+            id: AttributeId::try_new(id).unwrap(),
+            field_type,
+            constraints: HashSet::new(),
+            required: false,
+            unique: false,
+            renamed_from: None,
+            api_name: None,
+            description: None,
+            filterable: false,
+            sortable: false,
+            personal: false,
+        }
+    }
+
+    #[test]
+    fn test_index_settings_follow_the_field_types() {
+        let mut document_type =
+            DocumentType::new_bare_collection("articles", "article", "articles").unwrap();
+        document_type.fields = HashSet::from([
+            field("title", FieldType::Text),
+            field("summary", FieldType::LocalizedText),
+            field("slug", FieldType::Uid),
+            field("view_count", FieldType::Integer(IntegerSize::Int32)),
+            field("featured", FieldType::Boolean),
+            field("metadata", FieldType::Json),
+        ]);
+
+        let settings = IndexSettings::of(&document_type);
+        assert_eq!(settings.searchable, vec!["slug", "summary", "title"]);
+        assert_eq!(settings.filterable, vec!["featured", "slug", "viewCount"]);
+        assert_eq!(
+            settings.sortable,
+            vec!["slug", "title", "viewCount", "publishedAt", "updatedAt"]
+        );
+        assert_eq!(settings.fields.len(), 6);
+    }
+}
@@ -1,36 +1,127 @@
 use std::env;
 
-use anyhow::Context;
-use config::{Config, Environment, File};
-use dotenvy::dotenv;
 use luminair_common::database::DatabaseSettings;
+use luminair_common::settings;
 use serde::Deserialize;
 
-use crate::application::PaginationSettings;
+use crate::application::{PaginationSettings, QueryLimits, ResponseFormat};
+use crate::infrastructure::error_reporting::ErrorReportingSettings;
+use crate::infrastructure::events::EventsSettings;
+use crate::infrastructure::grpc::GrpcSettings;
+use crate::infrastructure::http::admin::AdminSettings;
+use crate::infrastructure::http::graphql::GraphqlSettings;
+use crate::infrastructure::http::health::HealthSettings;
+use crate::infrastructure::http::idempotency::IdempotencySettings;
+use crate::infrastructure::http::slow_requests::SlowRequestSettings;
+use crate::infrastructure::http::tenancy::TenancySettings;
+use crate::infrastructure::http::versioning::ApiVersion;
+use crate::infrastructure::http::{InternalListenerSettings, default_host};
+use crate::infrastructure::jobs::JobsSettings;
+use crate::infrastructure::partitions::PartitionsSettings;
+use crate::infrastructure::persistence::collations::CollationSettings;
+use crate::infrastructure::persistence::diagnostics::QueryDiagnosticsSettings;
+use crate::infrastructure::persistence::document_cache::DocumentCacheSettings;
+use crate::infrastructure::response_cache::ResponseCacheSettings;
+use crate::infrastructure::retention::RetentionSettings;
+use crate::infrastructure::scheduler::SchedulerSettings;
+use crate::infrastructure::search_index::SearchIndexSettings;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Settings {
+    /// interface the content API listens on, all by default
+    #[serde(default = "default_host")]
+    pub server_host: String,
     pub server_port: u16,
+    /// listener of `/health`, `/metrics` and the admin API, apart from the
+    /// content API, so they are never exposed on the public edge
+    #[serde(default)]
+    pub internal_listener: Option<InternalListenerSettings>,
     pub schema_config_path: String,
     pub database: DatabaseSettings,
     pub pagination: PaginationSettings,
+    /// guards against costly queries
+    #[serde(default)]
+    pub query_limits: QueryLimits,
+    /// envelope of the documents of list and detail responses
+    #[serde(default)]
+    pub response_format: ResponseFormat,
+    /// version of the content API responses of the requests whose `Accept`
+    /// doesn't ask for one
+    #[serde(default)]
+    pub api_version: ApiVersion,
+    /// collations the localized text fields are sorted by, per locale
+    #[serde(default)]
+    pub collations: CollationSettings,
+    /// slow query log
+    #[serde(default)]
+    pub diagnostics: QueryDiagnosticsSettings,
+    /// slow request log, with thresholds for reads and writes
+    #[serde(default)]
+    pub slow_requests: SlowRequestSettings,
+    /// reporting of the internal errors to Sentry
+    #[serde(default)]
+    pub error_reporting: ErrorReportingSettings,
+    /// worker carrying out scheduled publishes and unpublishes
+    #[serde(default)]
+    pub scheduler: SchedulerSettings,
+    /// workers running the background jobs
+    #[serde(default)]
+    pub jobs: JobsSettings,
+    /// maintenance creating the partitions of the partitioned types ahead
+    #[serde(default)]
+    pub partitions: PartitionsSettings,
+    /// maintenance removing the expired documents of the types with a retention
+    #[serde(default)]
+    pub retention: RetentionSettings,
+    /// content change events published to a message broker
+    #[serde(default)]
+    pub events: EventsSettings,
+    /// cache of the REST API responses in Redis, invalidated by the content
+    /// change events
+    #[serde(default)]
+    pub response_cache: ResponseCacheSettings,
+    /// index of the published documents in Meilisearch or Elasticsearch,
+    /// synced by the content change events
+    #[serde(default)]
+    pub search_index: SearchIndexSettings,
+    /// in-process cache of the published documents looked up by id
+    #[serde(default)]
+    pub document_cache: DocumentCacheSettings,
+    /// replay of the POST requests retried with an `Idempotency-Key`
+    #[serde(default)]
+    pub idempotency: IdempotencySettings,
+    /// gRPC server for machine-to-machine consumers
+    #[serde(default)]
+    pub grpc: GrpcSettings,
+    /// GraphQL endpoint
+    #[serde(default)]
+    pub graphql: GraphqlSettings,
+    /// API of the admin UI
+    #[serde(default)]
+    pub admin: AdminSettings,
+    /// detail of the components on `/health`
+    #[serde(default)]
+    pub health: HealthSettings,
+    /// resolution of the tenant of the requests, when the database has tenants
+    #[serde(default)]
+    pub tenancy: TenancySettings,
+    /// migrate the database schema and apply seeds before starting the API
+    #[serde(default)]
+    pub auto_migrate: bool,
+    /// directory with seed files applied by auto-migration
+    #[serde(default)]
+    pub seeds_path: Option<String>,
 }
 
 impl Settings {
+    /// The settings of the config files and the environment, see
+    /// [`settings::load`].
     pub fn from_env() -> anyhow::Result<Self> {
-        dotenv().ok();
-        let run_mode = load_env("RUN_MODE", "development");
-
-        let s = Config::builder()
-            .add_source(File::with_name("./config/default"))
-            .add_source(File::with_name(&format!("./config/{run_mode}")).required(false))
-            .add_source(Environment::with_prefix("app").separator("_"))
-            .build()?;
-
-        s.try_deserialize().with_context(|| "failed to read config")
+        settings::load(|builder, run_mode| {
+            builder
+                .set_default("graphql.graphiql", run_mode == "development")?
+                .set_override_option("auto_migrate", env::var("AUTO_MIGRATE").ok())?
+                .set_override_option("database.url", env::var("DATABASE_URL").ok())
+        })
     }
 }
-
-fn load_env(key: &str, default_value: &'static str) -> String {
-    env::var(key).unwrap_or_else(|_| default_value.into())
-}
@@ -1,9 +1,31 @@
+use chrono::Utc;
 use luminair_common::{database, load_documents};
 use service::infrastructure::AppStateImpl;
+use service::infrastructure::error_reporting::start_error_reporting;
+use service::infrastructure::events::start_outbox_relay;
+use service::infrastructure::grpc::GrpcServer;
+use service::infrastructure::http::admin::AdminServer;
+use service::infrastructure::http::health::HealthCheck;
+use service::infrastructure::http::idempotency::IdempotencyKeys;
+use service::infrastructure::http::slow_requests::SlowRequestLog;
+use service::infrastructure::http::tenancy::TenantResolver;
+use service::infrastructure::http::versioning::ApiVersioning;
 use service::infrastructure::http::{HttpServer, HttpServerConfig};
+use service::infrastructure::jobs::{JobQueue, JobWorkers};
+use service::infrastructure::partitions::run_partition_maintenance;
+use service::infrastructure::response_cache::ResponseCache;
+use service::infrastructure::retention::run_retention;
 use service::infrastructure::settings::Settings;
 
+use service::infrastructure::persistence::collations::SortCollations;
+use service::infrastructure::persistence::pool_metrics::{
+    POOL_METRICS_INTERVAL, record_pool_metrics,
+};
 use service::infrastructure::persistence::repository::PostgresDocumentsRepository;
+use service::infrastructure::scheduler::run_scheduled_publications;
+use service::infrastructure::schema_metrics::record_schema_metrics;
+use service::infrastructure::search_index::{REINDEX_SEARCH_JOB, SearchIndex};
+use std::time::Duration;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
@@ -17,19 +39,192 @@ async fn main() -> anyhow::Result<()> {
         )
         .with(tracing_subscriber::fmt::layer())
         .init();
+    start_error_reporting(&settings.error_reporting)?;
 
     let registry = load_documents(&settings.schema_config_path)?;
+    let schema_loaded_at = Utc::now();
     tracing::debug!("Configuration loaded");
 
     let database = database::connect(&settings.database).await?;
     tracing::debug!("Connected to DB");
 
-    let repository = PostgresDocumentsRepository::new(registry, database);
-    let state = AppStateImpl::new(registry, repository, settings.pagination);
+    if settings.auto_migrate {
+        migration::migrate_database(registry, database, settings.seeds_path.as_deref()).await?;
+        tracing::info!("Database migrated");
+    }
+
+    let collations = SortCollations::load(&settings.collations, registry, database).await?;
+    let mut repository = PostgresDocumentsRepository::new(registry, database)
+        .with_diagnostics(&settings.diagnostics)
+        .with_collations(collations);
+    if settings.document_cache.enabled {
+        repository = repository.with_document_cache(&settings.document_cache);
+    }
+    if settings.events.enabled {
+        repository = repository.with_outbox();
+    }
+    let state = AppStateImpl::builder(registry, repository)
+        .pagination_settings(settings.pagination)
+        .query_limits(settings.query_limits)
+        .response_format(settings.response_format)
+        .build();
+
+    if settings.scheduler.enabled {
+        tokio::spawn(run_scheduled_publications(
+            state.clone(),
+            database,
+            Duration::from_secs(settings.scheduler.interval_seconds),
+        ));
+    }
+
+    let partitioned = registry
+        .iterate()
+        .any(|document_type| document_type.partitioning().is_some());
+    if settings.partitions.enabled && partitioned {
+        tokio::spawn(run_partition_maintenance(
+            registry,
+            database,
+            Duration::from_secs(settings.partitions.interval_seconds),
+        ));
+    }
+
+    let expiring = registry
+        .iterate()
+        .any(|document_type| document_type.retention().is_some());
+    if settings.retention.enabled && expiring {
+        tokio::spawn(run_retention(
+            state.clone(),
+            database,
+            settings.retention.clone(),
+        ));
+    }
+
+    let search_index = if settings.search_index.enabled {
+        // the indexes are synced with the relayed events
+        anyhow::ensure!(
+            settings.events.enabled,
+            "the search index requires the content change events to be enabled"
+        );
+        let index = SearchIndex::new(&settings.search_index, state.clone())?;
+        // the engine may come up later, the indexes are configured by a reindex too
+        if let Err(e) = index.configure_indexes(database).await {
+            tracing::warn!("Configuring the search indexes failed: {:#}", e);
+        }
+        Some(index)
+    } else {
+        None
+    };
+
+    let jobs = if settings.jobs.enabled {
+        let queue = JobQueue::new(&settings.jobs, database);
+        let mut workers = JobWorkers::new(&settings.jobs, queue.clone(), state.clone());
+        if let Some(index) = &search_index {
+            workers = workers.register(REINDEX_SEARCH_JOB, index.reindex_job());
+        }
+        workers.spawn();
+        tracing::debug!("Running background jobs");
+        Some(queue)
+    } else {
+        None
+    };
+
+    let response_cache = if settings.response_cache.enabled {
+        // the cached responses are invalidated with the relayed events
+        anyhow::ensure!(
+            settings.events.enabled,
+            "the response cache requires the content change events to be enabled"
+        );
+        Some(ResponseCache::connect(&settings.response_cache, registry).await?)
+    } else {
+        None
+    };
+
+    if settings.events.enabled {
+        start_outbox_relay(
+            &settings.events,
+            database,
+            response_cache.clone(),
+            search_index,
+        )
+        .await?;
+        tracing::debug!("Relaying content change events");
+    }
+
+    let tenants = if settings.database.tenants.is_empty() {
+        None
+    } else {
+        // the gRPC requests name no tenant
+        anyhow::ensure!(
+            !settings.grpc.enabled,
+            "the gRPC server can't be enabled with tenants"
+        );
+        Some(TenantResolver::new(&settings.tenancy, database)?)
+    };
+
+    let grpc_server = if settings.grpc.enabled {
+        Some(GrpcServer::new(state.clone(), settings.grpc.port).await?)
+    } else {
+        None
+    };
+
+    let slow_requests = SlowRequestLog::new(&settings.slow_requests);
+    let admin = &settings.admin;
+    let admin_server = match admin.port {
+        Some(port) if admin.enabled => Some(
+            AdminServer::new(
+                state.clone(),
+                port,
+                admin.roles.clone(),
+                tenants.clone(),
+                jobs.clone(),
+                &slow_requests,
+            )
+            .await?,
+        ),
+        _ => None,
+    };
 
     let server_config = HttpServerConfig {
+        host: settings.server_host.clone(),
         port: settings.server_port,
+        internal_listener: settings.internal_listener.clone(),
+        graphiql: settings.graphql.graphiql,
+        admin_roles: (admin.enabled && admin.port.is_none()).then(|| admin.roles.clone()),
+        response_cache,
+        idempotency: settings
+            .idempotency
+            .enabled
+            .then(|| IdempotencyKeys::new(&settings.idempotency, database)),
+        jobs,
+        tenants,
+        health: settings
+            .health
+            .detail
+            .then(|| HealthCheck::new(&settings.health, registry, database, schema_loaded_at)),
+        slow_requests,
+        versioning: ApiVersioning::new(settings.api_version),
     };
     let http_server = HttpServer::new(state, server_config).await?;
-    http_server.run().await
+
+    // the Prometheus recorder is installed with the HTTP server
+    record_schema_metrics(registry, schema_loaded_at);
+    tokio::spawn(record_pool_metrics(
+        database.database_pool().clone(),
+        POOL_METRICS_INTERVAL,
+    ));
+
+    let grpc_server = async {
+        match grpc_server {
+            Some(grpc_server) => grpc_server.run().await,
+            None => Ok(()),
+        }
+    };
+    let admin_server = async {
+        match admin_server {
+            Some(admin_server) => admin_server.run().await,
+            None => Ok(()),
+        }
+    };
+    tokio::try_join!(http_server.run(), grpc_server, admin_server)?;
+    Ok(())
 }
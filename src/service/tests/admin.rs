@@ -0,0 +1,302 @@
+mod common;
+
+use common::*;
+
+const ADMIN_USER: [(&str, &str); 2] = [("x-user-id", "admin-1"), ("x-user-roles", "editor,admin")];
+
+async fn admin_request(
+    router: &TestRouter,
+    method: &str,
+    uri: &str,
+    headers: &[(&str, &str)],
+    body: Option<Value>,
+) -> anyhow::Result<(StatusCode, Value)> {
+    let mut request = Request::builder().method(method).uri(uri);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let body = match body {
+        Some(body) => {
+            request = request.header("content-type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = router.clone().oneshot(request.body(body)?).await?;
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), 1 << 20).await?;
+    Ok((status, serde_json::from_slice(&bytes)?))
+}
+
+/// POST `body` as it is, answering with the raw response.
+async fn admin_send(
+    router: &TestRouter,
+    uri: &str,
+    body: Body,
+) -> anyhow::Result<(StatusCode, axum::http::HeaderMap, axum::body::Bytes)> {
+    let mut request = Request::builder().method("POST").uri(uri);
+    for (name, value) in ADMIN_USER {
+        request = request.header(name, value);
+    }
+    let response = router.clone().oneshot(request.body(body)?).await?;
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    Ok((status, headers, bytes))
+}
+
+// ---------------------------------------------------------------------------
+// Tests — admin API
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn admin_api_requires_a_user_with_an_admin_role() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    for headers in [
+        &[][..],
+        &[("x-user-roles", "admin")][..],
+        &[("x-user-id", "editor-1"), ("x-user-roles", "editor")][..],
+    ] {
+        let (status, json) =
+            admin_request(&router, "GET", "/admin/api/content-types", headers, None).await?;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(json["code"], "FORBIDDEN");
+    }
+
+    let (status, json) = admin_request(
+        &router,
+        "GET",
+        "/admin/api/content-types",
+        &ADMIN_USER,
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json.as_array().map(Vec::len), Some(4));
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_api_describes_content_types_with_display_hints() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let (status, json) = admin_request(
+        &router,
+        "GET",
+        "/admin/api/content-types/brands",
+        &ADMIN_USER,
+        None,
+    )
+    .await?;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["apiId"], "brands");
+    assert_eq!(json["draftAndPublish"], true);
+    let attribute = |id: &str| {
+        json["attributes"]
+            .as_array()
+            .and_then(|attributes| attributes.iter().find(|a| a["id"] == id))
+            .cloned()
+            .unwrap_or_default()
+    };
+    assert_eq!(attribute("uid")["widget"], "uid");
+    assert_eq!(attribute("name")["widget"], "localizedText");
+    assert_eq!(attribute("partners")["widget"], "relation");
+    assert_eq!(attribute("partners")["target"], "partners");
+    assert_eq!(attribute("partners")["multiple"], true);
+    assert_eq!(attribute("partners")["editable"], false);
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_api_lists_drafts_with_relation_counts() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let mut brand_ids = Vec::new();
+    for uid in ["adm-aaa", "adm-bbb"] {
+        let loc = create_brand(&router, uid, uid).await?;
+        brand_ids.push(loc.trim_start_matches("/api/documents/brands/").to_string());
+    }
+    let partner_loc = create_partner(&router, "7100000000001", "Counted Ltd").await?;
+    let (status, _) = put_json(
+        &router,
+        &partner_loc,
+        &format!(
+            r#"{{"data": {{"brands": {{"connect": ["{}", "{}"]}}}}}}"#,
+            brand_ids[0], brand_ids[1]
+        ),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, json) = admin_request(
+        &router,
+        "GET",
+        "/admin/api/content-types/partners/entries",
+        &ADMIN_USER,
+        None,
+    )
+    .await?;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["meta"]["total"], 1);
+    let entry = &json["data"][0];
+    assert_eq!(entry["status"], "draft");
+    assert_eq!(entry["legalEntity"], "Counted Ltd");
+    assert_eq!(entry["relationCounts"]["brands"], 2);
+    assert_eq!(entry["relationCounts"]["category"], 0);
+    assert!(entry.get("brands").is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_api_pages_the_translation_status() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    for (uid, priority) in [("tr-a", 1), ("tr-b", 2), ("tr-c", 3)] {
+        create_partner_category(&router, uid, priority).await?;
+    }
+
+    let (status, json) = admin_request(
+        &router,
+        "GET",
+        "/admin/api/translation-status?contentType=partner-categories\
+            &pagination[page]=2&pagination[pageSize]=2",
+        &ADMIN_USER,
+        None,
+    )
+    .await?;
+
+    assert_eq!(status, StatusCode::OK, "{json}");
+    let translations = &json["data"][0];
+    assert_eq!(translations["contentType"], "partner-categories");
+    assert_eq!(translations["entries"].as_array().unwrap().len(), 1);
+    assert_eq!(translations["pagination"]["total"], 3);
+    // the counts are of all the entries, not of the page only
+    assert_eq!(translations["locales"][0]["locale"], "en");
+    assert_eq!(translations["locales"][0]["translated"], 3);
+    assert_eq!(translations["locales"][1]["missing"], 3);
+
+    let (status, _) = admin_request(
+        &router,
+        "GET",
+        "/admin/api/translation-status?pagination[pageSize]=999",
+        &ADMIN_USER,
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_api_bulk_actions_report_every_document() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let mut brand_ids = Vec::new();
+    for uid in ["blk-aaa", "blk-bbb"] {
+        let loc = create_brand(&router, uid, uid).await?;
+        brand_ids.push(loc.trim_start_matches("/api/documents/brands/").to_string());
+    }
+    let missing_id = uuid::Uuid::new_v4().to_string();
+
+    let (status, json) = admin_request(
+        &router,
+        "POST",
+        "/admin/api/content-types/brands/actions/bulk-publish",
+        &ADMIN_USER,
+        Some(serde_json::json!({
+            "documentIds": [brand_ids[0], brand_ids[1], missing_id, "not-an-id"]
+        })),
+    )
+    .await?;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["succeeded"], serde_json::json!(brand_ids));
+    let failed = json["data"]["failed"].as_array().unwrap();
+    assert_eq!(failed.len(), 2);
+    assert_eq!(failed[0]["documentId"], missing_id.as_str());
+    assert_eq!(failed[0]["code"], "NOT_FOUND");
+    assert_eq!(failed[1]["code"], "UNPROCESSABLE_ENTITY");
+
+    let (status, json) = get_json(&router, "/api/documents/brands").await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["meta"]["total"], 2);
+
+    let (status, json) = admin_request(
+        &router,
+        "POST",
+        "/admin/api/content-types/brands/actions/bulk-delete",
+        &ADMIN_USER,
+        Some(serde_json::json!({ "documentIds": [brand_ids[0]] })),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["succeeded"][0], brand_ids[0].as_str());
+
+    let (_, json) = get_json(&router, "/api/documents/brands?status=draft").await?;
+    assert_eq!(json["meta"]["total"], 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_api_export_restores_into_an_empty_database() -> anyhow::Result<()> {
+    let (source, _c) = build_router().await?;
+
+    let brand_loc = create_brand(&source, "exp-aaa", "Exported").await?;
+    publish_document(&source, &brand_loc).await?;
+    let cat_loc = create_partner_category(&source, "exp-retail", 1).await?;
+    let cat_id = cat_loc.trim_start_matches("/api/documents/partner-categories/");
+    let partner_loc = create_partner(&source, "7200000000001", "Exported Ltd").await?;
+    let (status, _) = put_json(
+        &source,
+        &partner_loc,
+        &format!(r#"{{"data": {{"category": {{"connect": ["{cat_id}"]}}}}}}"#),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, headers, export) = admin_send(&source, "/admin/api/export", Body::empty()).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["content-type"], "application/x-ndjson");
+    let lines = std::str::from_utf8(&export)?
+        .lines()
+        .map(serde_json::from_str::<Value>)
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(lines[0]["format"], "luminair-export");
+    assert_eq!(lines[0]["schema"].as_array().map(Vec::len), Some(4));
+    let tables = lines[1..]
+        .iter()
+        .filter_map(|line| line["table"].as_str())
+        .collect::<Vec<_>>();
+    assert!(tables.contains(&"brands_snapshots"), "{tables:?}");
+    assert!(tables.contains(&"partners_category_relation"), "{tables:?}");
+
+    let (target, _t) = build_router().await?;
+    let (status, headers, imported) =
+        admin_send(&target, "/admin/api/import", Body::from(export.clone())).await?;
+    assert_eq!(status, StatusCode::OK, "{imported:?}");
+    assert_eq!(headers["content-type"], "application/json");
+    let imported = serde_json::from_slice::<Value>(&imported)?;
+    assert_eq!(imported["data"]["rows"]["brands"], 1);
+    assert_eq!(imported["data"]["rows"]["partners_category_relation"], 1);
+
+    let (status, json) = get_json(&target, &brand_loc).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["uid"], "exp-aaa");
+    let (_, json) = get_json(
+        &target,
+        &format!("{partner_loc}?status=draft&populate=category"),
+    )
+    .await?;
+    assert_eq!(json["data"]["category"][0]["uid"], "exp-retail");
+
+    // the identities continue after the restored rows
+    let other_loc = create_brand(&target, "exp-bbb", "Created").await?;
+    publish_document(&target, &other_loc).await?;
+
+    let (status, _, _) = admin_send(&target, "/admin/api/import", Body::from(export)).await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    Ok(())
+}
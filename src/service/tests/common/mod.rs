@@ -1,4 +1,4 @@
-#![allow(dead_code)]
+#![allow(dead_code, unused_imports)]
 
 use std::sync::OnceLock;
 
@@ -9,17 +9,46 @@ pub use axum::{
     routing::get,
 };
 pub use serde_json::Value;
+pub use service::application::ResponseFormat;
+pub use service::domain::events::{ContentAction, ContentChangeEvent};
+pub use service::domain::hooks::{HookError, LifecycleHooks, LifecycleHooksRegistry};
 pub use service::infrastructure::{
-    AppStateImpl,
-    http::{handlers::health_check, routes::api_routes},
-    persistence::repository::PostgresDocumentsRepository,
+    AppStateBuilder, AppStateImpl,
+    events::{EventPublisher, prune_published_events, relay_pending_events},
+    grpc::{
+        GrpcServer,
+        convert::{json_to_struct, struct_to_json},
+        proto,
+    },
+    http::{
+        admin::{ADMIN_PATH, admin_routes},
+        graphql::graphql_routes,
+        handlers::health_check,
+        idempotency::{
+            IDEMPOTENCY_KEY_HEADER, IDEMPOTENT_REPLAYED_HEADER, IdempotencyKeys,
+            IdempotencySettings,
+        },
+        routes::api_routes,
+        tenancy::{TenancySettings, TenantResolver},
+    },
+    jobs::{JobQueue, JobWorkers, JobsSettings},
+    persistence::{document_cache::DocumentCacheSettings, repository::PostgresDocumentsRepository},
+    response_cache::{CACHE_STATUS_HEADER, ResponseCache, ResponseCacheSettings},
+    scheduler::{SCHEDULER_LOCK_KEY, apply_due_publications},
+    search_index::{IndexSettings, SearchEngine, SearchIndex, SearchIndexSettings},
+};
+pub use testcontainers_modules::{
+    postgres::Postgres,
+    redis::{REDIS_PORT, Redis},
+    testcontainers::runners::AsyncRunner,
 };
-pub use testcontainers_modules::{postgres::Postgres, testcontainers::runners::AsyncRunner};
 pub use tower::ServiceExt;
 
 pub use luminair_common::{
     DocumentTypesRegistry,
-    database::{self, DatabaseConnection, DatabaseCredentials, DatabaseSettings},
+    database::{
+        self, DatabaseConnection, DatabaseCredentials, DatabaseSettings, ReadReplicaSettings,
+    },
     load_documents,
 };
 pub use migration::{application::Migration, infrastructure::persistence::PersistenceAdapter};
@@ -43,15 +72,45 @@ pub fn registry() -> &'static dyn DocumentTypesRegistry {
 
 /// Boot a fresh Postgres container, apply migrations, and return the pool + guard.
 pub async fn start_postgres() -> anyhow::Result<(&'static database::Database, impl Drop)> {
+    start_postgres_with_read_replicas(|_| Vec::new()).await
+}
+
+/// Like [`start_postgres`], with the read replicas `read_replicas` returns for
+/// the `host:port` of the container.
+pub async fn start_postgres_with_read_replicas(
+    read_replicas: impl FnOnce(&str) -> Vec<String>,
+) -> anyhow::Result<(&'static database::Database, impl Drop)> {
+    start_postgres_with(read_replicas, Vec::new()).await
+}
+
+/// Like [`start_postgres`], with a migrated schema for each of `tenants`.
+pub async fn start_postgres_with_tenants(
+    tenants: &[&str],
+) -> anyhow::Result<(&'static database::Database, impl Drop)> {
+    let tenants = tenants.iter().map(|tenant| tenant.to_string()).collect();
+    start_postgres_with(|_| Vec::new(), tenants).await
+}
+
+async fn start_postgres_with(
+    read_replicas: impl FnOnce(&str) -> Vec<String>,
+    tenants: Vec<String>,
+) -> anyhow::Result<(&'static database::Database, impl Drop)> {
     let reg = registry();
     let container = Postgres::default().start().await?;
 
     let host = container.get_host().await?;
     let port = container.get_host_port_ipv4(5432).await?;
     let schema_name = format!("test_{}", uuid::Uuid::new_v4().simple());
+    let primary = format!("{host}:{port}");
 
     let settings = DatabaseSettings {
-        host: format!("{host}:{port}"),
+        read_replicas: read_replicas(&primary)
+            .into_iter()
+            .map(|host| ReadReplicaSettings { host })
+            .collect(),
+        url: None,
+        host: host.to_string(),
+        port,
         db: "postgres".to_string(),
         schema: schema_name.clone(),
         credentials: DatabaseCredentials {
@@ -62,7 +121,18 @@ pub async fn start_postgres() -> anyhow::Result<(&'static database::Database, im
             min_connections: 1,
             max_connections: 5,
             acquire_timeout_seconds: 5,
+            connect_timeout_seconds: 30,
+            connect_retry_delay_ms: 250,
+            connect_retry_max_delay_ms: 5_000,
+            warmup: false,
+            lazy: false,
+            idle_timeout_seconds: None,
+            max_lifetime_seconds: None,
+            statement_timeout_ms: None,
+            statement_timeouts: Default::default(),
         },
+        tls: Default::default(),
+        tenants,
     };
 
     let database = database::Database::new(&settings).await?;
@@ -77,25 +147,164 @@ pub async fn start_postgres() -> anyhow::Result<(&'static database::Database, im
 
     let persistence = PersistenceAdapter::new(pool.clone(), &schema_name);
     Migration::new(reg, persistence).migrate(false).await?;
+    if database.tenants().next().is_some() {
+        migration::migrate_database(reg, database, None).await?;
+    }
 
     Ok((database, container))
 }
 
+/// Boot a fresh Redis container and connect a response cache to it.
+pub async fn start_response_cache() -> anyhow::Result<(ResponseCache, impl Drop)> {
+    let container = Redis::default().start().await?;
+    let host = container.get_host().await?;
+    let port = container.get_host_port_ipv4(REDIS_PORT).await?;
+
+    let settings = ResponseCacheSettings {
+        enabled: true,
+        url: format!("redis://{host}:{port}"),
+        ..Default::default()
+    };
+    let cache = ResponseCache::connect(&settings, registry()).await?;
+    Ok((cache, container))
+}
+
 pub type TestRouter = Router;
 
 /// Build a fully wired Axum router backed by a fresh isolated database.
 pub async fn build_router() -> anyhow::Result<(TestRouter, impl Drop)> {
-    let reg = registry();
+    build_router_with_read_replicas(|_| Vec::new()).await
+}
+
+/// Like [`build_router`], reading through the replicas of
+/// [`start_postgres_with_read_replicas`].
+pub async fn build_router_with_read_replicas(
+    read_replicas: impl FnOnce(&str) -> Vec<String>,
+) -> anyhow::Result<(TestRouter, impl Drop)> {
+    let (database, container) = start_postgres_with_read_replicas(read_replicas).await?;
+    let (router, _) = build_app(database, LifecycleHooksRegistry::default());
+    Ok((router, container))
+}
+
+/// Like [`build_router`], calling the lifecycle hooks of `hooks`.
+pub async fn build_router_with_hooks(
+    hooks: LifecycleHooksRegistry,
+) -> anyhow::Result<(TestRouter, impl Drop)> {
+    let (database, container) = start_postgres().await?;
+    let (router, _) = build_app(database, hooks);
+    Ok((router, container))
+}
+
+/// Like [`build_router`], answering in the response format `response_format`.
+pub async fn build_router_with_response_format(
+    response_format: ResponseFormat,
+) -> anyhow::Result<(TestRouter, impl Drop)> {
+    let (database, container) = start_postgres().await?;
+    let (router, _) = build_app_with_state(database, LifecycleHooksRegistry::default(), |state| {
+        state.response_format(response_format)
+    });
+    Ok((router, container))
+}
+
+/// Build a fully wired Axum router with its application state, for the tests
+/// which also drive the state directly, e.g. the scheduled publications.
+pub async fn build_router_with_state() -> anyhow::Result<(
+    TestRouter,
+    AppStateImpl,
+    &'static database::Database,
+    impl Drop,
+)> {
     let (database, container) = start_postgres().await?;
-    let repository = PostgresDocumentsRepository::new(reg, database);
+    let (router, state) = build_app(database, LifecycleHooksRegistry::default());
+    Ok((router, state, database, container))
+}
+
+/// Build a fully wired Axum router on a fresh database with `tenants`,
+/// resolving the tenant of the requests by their `x-tenant-id` header or a
+/// subdomain of `cms.example.com`.
+pub async fn build_router_with_tenants(
+    tenants: &[&str],
+) -> anyhow::Result<(TestRouter, impl Drop)> {
+    let (database, container) = start_postgres_with_tenants(tenants).await?;
+    let settings = TenancySettings {
+        subdomain_of: Some("cms.example.com".to_string()),
+        ..Default::default()
+    };
+    let resolver = TenantResolver::new(&settings, database)?;
+
+    let reg = registry();
+    let repository = PostgresDocumentsRepository::new(reg, database).with_outbox();
     let state = AppStateImpl::new(reg, repository, Default::default());
+    let graphql = graphql_routes(&state, false)?;
     let router = Router::new()
         .route("/health", get(health_check))
-        .nest("/api", api_routes())
+        .nest("/api", resolver.resolve_tenants(api_routes()))
+        .merge(resolver.resolve_tenants(graphql))
+        .nest(
+            ADMIN_PATH,
+            resolver.resolve_tenants(admin_routes(vec!["admin".to_string()], None)),
+        )
         .with_state(state);
     Ok((router, container))
 }
 
+/// Build two routers on one fresh database, like two replicas of the service;
+/// the first one serves the published documents from a document cache.
+pub async fn build_replicas_with_document_cache()
+-> anyhow::Result<(TestRouter, TestRouter, impl Drop)> {
+    let (database, container) = start_postgres().await?;
+    let settings = DocumentCacheSettings {
+        enabled: true,
+        ..Default::default()
+    };
+    let repository = PostgresDocumentsRepository::new(registry(), database).with_outbox();
+    let (cached, _) = build_app_with_repository(repository.clone().with_document_cache(&settings));
+    let (uncached, _) = build_app_with_repository(repository);
+    Ok((cached, uncached, container))
+}
+
+fn build_app(
+    database: &'static database::Database,
+    hooks: LifecycleHooksRegistry,
+) -> (TestRouter, AppStateImpl) {
+    build_app_with_state(database, hooks, |state| state)
+}
+
+fn build_app_with_state(
+    database: &'static database::Database,
+    hooks: LifecycleHooksRegistry,
+    configure: impl FnOnce(
+        AppStateBuilder<PostgresDocumentsRepository>,
+    ) -> AppStateBuilder<PostgresDocumentsRepository>,
+) -> (TestRouter, AppStateImpl) {
+    let reg = registry();
+    let repository = PostgresDocumentsRepository::new(reg, database)
+        .with_hooks(hooks)
+        .with_outbox();
+    let state = configure(AppStateImpl::builder(reg, repository)).build();
+    build_app_from_state(state)
+}
+
+fn build_app_with_repository(
+    repository: PostgresDocumentsRepository,
+) -> (TestRouter, AppStateImpl) {
+    build_app_from_state(AppStateImpl::new(
+        registry(),
+        repository,
+        Default::default(),
+    ))
+}
+
+fn build_app_from_state(state: AppStateImpl) -> (TestRouter, AppStateImpl) {
+    let router = Router::new()
+        .route("/health", get(health_check))
+        .nest("/api", api_routes())
+        .merge(graphql_routes(&state, false).expect("GraphQL schema of the test documents"))
+        .nest(ADMIN_PATH, admin_routes(vec!["admin".to_string()], None))
+        .with_state(state.clone());
+    (router, state)
+}
+
 // ---------------------------------------------------------------------------
 // HTTP primitives
 // ---------------------------------------------------------------------------
@@ -151,10 +360,23 @@ pub async fn put_json(
         .await?;
     let status = response.status();
     let bytes = axum::body::to_bytes(response.into_body(), 1 << 20).await?;
-    let json = serde_json::from_slice(&bytes)?;
+    // successful updates answer 204 without a body
+    let json = if bytes.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&bytes)?
+    };
     Ok((status, json))
 }
 
+/// POST a GraphQL query with its variables; returns the GraphQL response.
+pub async fn graphql(router: &TestRouter, query: &str, variables: Value) -> anyhow::Result<Value> {
+    let body = serde_json::json!({ "query": query, "variables": variables }).to_string();
+    let (status, _, bytes) = post_json(router, "/graphql", &body).await?;
+    assert_eq!(status, StatusCode::OK);
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
 /// POST to create a document; returns the Location URI (without query string).
 pub async fn create_document(
     router: &TestRouter,
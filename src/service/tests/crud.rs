@@ -54,6 +54,7 @@ async fn duplicate_unique_field_returns_409_problem_details() -> anyhow::Result<
     assert_eq!(status, StatusCode::CONFLICT);
     assert_eq!(json["type"], "/errors/conflict");
     assert_eq!(json["status"], 409);
+    assert_eq!(json["code"], "CONFLICT");
     Ok(())
 }
 
@@ -82,24 +83,568 @@ async fn nonexistent_relation_target_returns_422_problem_details() -> anyhow::Re
     Ok(())
 }
 
+#[tokio::test]
+async fn invalid_fields_return_422_problem_details_with_field_errors() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let (status, headers, bytes) = post_json(
+        &router,
+        "/api/documents/brands",
+        r#"{"data": {"uid": "ab", "name": "Short"}}"#,
+    )
+    .await?;
+    let json: Value = serde_json::from_slice(&bytes)?;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(headers["content-type"], "application/problem+json");
+    assert_eq!(json["type"], "/errors/unprocessable-entity");
+    assert_eq!(json["code"], "UNPROCESSABLE_ENTITY");
+    assert_eq!(json["errors"][0]["field"], "uid");
+    assert_eq!(json["errors"][0]["code"], "constraint");
+
+    let (status, _, bytes) = post_json(
+        &router,
+        "/api/documents/brands",
+        r#"{"data": {"name": "No uid"}}"#,
+    )
+    .await?;
+    let json: Value = serde_json::from_slice(&bytes)?;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json["errors"][0]["field"], "uid");
+    assert_eq!(json["errors"][0]["code"], "required");
+
+    let (_, _, bytes) = post_json(
+        &router,
+        "/api/documents/brands",
+        r#"{"data": {"uid": "brand-x", "name": "X", "ghost": 1}}"#,
+    )
+    .await?;
+    let json: Value = serde_json::from_slice(&bytes)?;
+    assert_eq!(json["errors"][0]["field"], "ghost");
+    assert_eq!(json["errors"][0]["code"], "unknown");
+    Ok(())
+}
+
+#[tokio::test]
+async fn update_with_stale_if_match_returns_412_problem_details() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let loc = create_brand(&router, "brand-v", "Versioned").await?;
+    let (_, json) = get_json(&router, &format!("{loc}?status=draft")).await?;
+    let version = json["data"]["version"].as_i64().unwrap();
+
+    let put_if_match = |if_match: String| {
+        Request::builder()
+            .method("PUT")
+            .uri(loc.clone())
+            .header("content-type", "application/json")
+            .header("if-match", if_match)
+            .body(Body::from(r#"{"data": {"name": "Renamed"}}"#))
+    };
+
+    let response = router
+        .clone()
+        .oneshot(put_if_match(format!("\"{}\"", version - 1))?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    let bytes = axum::body::to_bytes(response.into_body(), 1 << 20).await?;
+    let json: Value = serde_json::from_slice(&bytes)?;
+    assert_eq!(json["type"], "/errors/precondition-failed");
+
+    let response = router
+        .clone()
+        .oneshot(put_if_match(format!("\"{version}\""))?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let (_, json) = get_json(&router, &format!("{loc}?status=draft")).await?;
+    assert_eq!(json["data"]["name"], "Renamed");
+    assert_eq!(json["data"]["version"].as_i64().unwrap(), version + 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn update_with_locale_keeps_other_translations() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let loc = create_partner_category(&router, "loc-cat", 1).await?;
+
+    let (status, _) = put_json(
+        &router,
+        &format!("{loc}?locale=ro"),
+        r#"{"data": {"name": "Categorie"}}"#,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (_, json) = get_json(&router, &format!("{loc}?status=draft")).await?;
+    assert_eq!(json["data"]["name"]["en"], "Category loc-cat");
+    assert_eq!(json["data"]["name"]["ro"], "Categorie");
+
+    let (status, _) = put_json(
+        &router,
+        &format!("{loc}?locale=de"),
+        r#"{"data": {"name": "Kategorie"}}"#,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    Ok(())
+}
+
+#[tokio::test]
+async fn clone_locale_copies_localized_fields() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let loc = create_partner_category(&router, "clone-cat", 1).await?;
+
+    // there are no texts to clone from 'ru', nothing is written
+    let (status, _, _) =
+        post_json(&router, &format!("{loc}/localizations/ro?from=ru"), "{}").await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (_, json) = get_json(&router, &format!("{loc}?status=draft")).await?;
+    assert_eq!(json["data"]["version"].as_i64().unwrap(), 1);
+
+    let (status, _, bytes) =
+        post_json(&router, &format!("{loc}/localizations/ro?from=en"), "{}").await?;
+    assert_eq!(
+        status,
+        StatusCode::CREATED,
+        "clone failed: {}",
+        String::from_utf8_lossy(&bytes)
+    );
+
+    let (_, json) = get_json(&router, &format!("{loc}?status=draft")).await?;
+    assert_eq!(json["data"]["name"]["en"], "Category clone-cat");
+    assert_eq!(json["data"]["name"]["ro"], "Category clone-cat");
+
+    // the locale exists now
+    let (status, _, _) = post_json(&router, &format!("{loc}/localizations/ro"), "{}").await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    Ok(())
+}
+
+#[tokio::test]
+async fn revisions_are_recorded_and_restored() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let loc = create_brand(&router, "brand-r", "First").await?;
+    let (status, _) = put_json(&router, &loc, r#"{"data": {"name": "Second"}}"#).await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    publish_document(&router, &loc).await?;
+
+    let (status, json) = get_json(&router, &format!("{loc}/revisions")).await?;
+    assert_eq!(status, StatusCode::OK);
+    let revisions = json["data"].as_array().unwrap();
+    let versions: Vec<i64> = revisions
+        .iter()
+        .map(|revision| revision["version"].as_i64().unwrap())
+        .collect();
+    assert_eq!(versions, vec![2, 1]);
+    assert_eq!(revisions[0]["name"], "Second");
+    assert_eq!(revisions[1]["name"], "First");
+
+    let (status, _, bytes) =
+        post_json(&router, &format!("{loc}/revisions/1/restore"), "{}").await?;
+    assert_eq!(
+        status,
+        StatusCode::NO_CONTENT,
+        "restore failed: {}",
+        String::from_utf8_lossy(&bytes)
+    );
+
+    let (_, json) = get_json(&router, &format!("{loc}?status=draft")).await?;
+    assert_eq!(json["data"]["name"], "First");
+    assert_eq!(json["data"]["version"].as_i64().unwrap(), 4);
+
+    // the restore is a revision of its own
+    let (_, json) = get_json(&router, &format!("{loc}/revisions")).await?;
+    assert_eq!(json["data"][0]["version"].as_i64().unwrap(), 3);
+    assert_eq!(json["data"][0]["name"], "Second");
+
+    let (status, _, _) = post_json(&router, &format!("{loc}/revisions/9/restore"), "{}").await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    Ok(())
+}
+
+#[tokio::test]
+async fn graphql_mutations_write_documents() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let cat_loc = create_partner_category(&router, "gql-mut", 1).await?;
+    let cat_id = cat_loc.trim_start_matches("/api/documents/partner-categories/");
+
+    let json = graphql(
+        &router,
+        r#"mutation($category: ID!) {
+            createPartner(data: {idno: "6200000000001", legalEntity: "Mutated Ltd", category: {connect: [$category]}}) {
+                documentId status version category { uid }
+            }
+        }"#,
+        serde_json::json!({"category": cat_id}),
+    )
+    .await?;
+    assert!(json.get("errors").is_none(), "{json}");
+    let created = &json["data"]["createPartner"];
+    assert_eq!(created["status"], "draft");
+    assert_eq!(created["category"]["uid"], "gql-mut");
+    let partner_id = created["documentId"].as_str().unwrap().to_string();
+    let version = created["version"].as_i64().unwrap();
+
+    let update = r#"mutation($id: ID!, $version: Int) {
+        updatePartner(documentId: $id, expectedVersion: $version, data: {legalEntity: "Renamed Ltd", category: {set: []}}) {
+            legalEntity version category { uid }
+        }
+    }"#;
+    let json = graphql(
+        &router,
+        update,
+        serde_json::json!({"id": partner_id, "version": version - 1}),
+    )
+    .await?;
+    assert_eq!(
+        json["errors"][0]["extensions"]["code"],
+        "PRECONDITION_FAILED"
+    );
+
+    let json = graphql(
+        &router,
+        update,
+        serde_json::json!({"id": partner_id, "version": version}),
+    )
+    .await?;
+    assert!(json.get("errors").is_none(), "{json}");
+    assert_eq!(
+        json["data"]["updatePartner"],
+        serde_json::json!({"legalEntity": "Renamed Ltd", "version": version + 1, "category": null})
+    );
+
+    // the field values go through the validation of the REST API
+    let json = graphql(
+        &router,
+        r#"mutation { createPartner(data: {idno: "not-an-idno", legalEntity: "Invalid Ltd"}) { documentId } }"#,
+        Value::Null,
+    )
+    .await?;
+    assert_eq!(
+        json["errors"][0]["extensions"]["code"],
+        "UNPROCESSABLE_ENTITY"
+    );
+
+    let brand_loc = create_brand(&router, "gql-pub", "Published").await?;
+    let brand_id = brand_loc.trim_start_matches("/api/documents/brands/");
+    let json = graphql(
+        &router,
+        r#"mutation($id: ID!) { publishBrand(documentId: $id) { name status } }"#,
+        serde_json::json!({"id": brand_id}),
+    )
+    .await?;
+    assert!(json.get("errors").is_none(), "{json}");
+    assert_eq!(
+        json["data"]["publishBrand"],
+        serde_json::json!({"name": "Published", "status": "published"})
+    );
+
+    let json = graphql(
+        &router,
+        r#"mutation($id: ID!) { deleteBrand(documentId: $id) }"#,
+        serde_json::json!({"id": brand_id}),
+    )
+    .await?;
+    assert_eq!(json["data"]["deleteBrand"], brand_id);
+    let (status, _) = get_json(&router, &format!("{brand_loc}?status=draft")).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    Ok(())
+}
+
+#[tokio::test]
+async fn published_reads_carry_cache_control_and_surrogate_keys() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let loc = create_brand(&router, "cc-aaa", "Cached").await?;
+    let id = loc.trim_start_matches("/api/documents/brands/");
+    publish_document(&router, &loc).await?;
+
+    for uri in [loc.as_str(), "/api/documents/brands"] {
+        let response = router
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty())?)
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()["cache-control"],
+            "public, max-age=60, s-maxage=300, stale-while-revalidate=30"
+        );
+        assert_eq!(
+            response.headers()["surrogate-key"],
+            format!("brands {id}").as_str()
+        );
+    }
+
+    // drafts are not to be cached
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("{loc}?status=draft"))
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.headers()["cache-control"], "no-store");
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests — gRPC
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn grpc_documents_service_writes_and_reads_documents() -> anyhow::Result<()> {
+    use proto::documents_client::DocumentsClient;
+    use proto::{CreateRequest, FindRequest, GetRequest, PublishRequest, Status, UpdateRequest};
+
+    let (_router, state, _database, _c) = build_router_with_state().await?;
+    let server = GrpcServer::new(state, 0).await?;
+    let port = server.local_addr()?.port();
+    tokio::spawn(server.run());
+    let mut client = DocumentsClient::connect(format!("http://127.0.0.1:{port}")).await?;
+
+    let data = |value: Value| Some(json_to_struct(value.as_object().unwrap().clone()));
+    let created = client
+        .create(CreateRequest {
+            document_type: "brands".to_string(),
+            data: data(serde_json::json!({"uid": "grpc-aaa", "name": "Remote"})),
+            locale: None,
+        })
+        .await?
+        .into_inner();
+    assert_eq!(created.status, "draft");
+    let content = struct_to_json(created.data.unwrap());
+    assert_eq!(content["uid"], "grpc-aaa");
+
+    let error = client
+        .update(UpdateRequest {
+            document_type: "brands".to_string(),
+            document_id: created.document_id.clone(),
+            data: data(serde_json::json!({"name": "Renamed"})),
+            locale: None,
+            expected_version: Some(created.version - 1),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), tonic::Code::FailedPrecondition);
+
+    let updated = client
+        .update(UpdateRequest {
+            document_type: "brands".to_string(),
+            document_id: created.document_id.clone(),
+            data: data(serde_json::json!({"name": "Renamed"})),
+            locale: None,
+            expected_version: Some(created.version),
+        })
+        .await?
+        .into_inner();
+    assert_eq!(updated.version, created.version + 1);
+
+    let published = client
+        .publish(PublishRequest {
+            document_type: "brands".to_string(),
+            document_id: created.document_id.clone(),
+            ..Default::default()
+        })
+        .await?
+        .into_inner();
+    assert_eq!(published.document.unwrap().status, "published");
+
+    let found = client
+        .find(FindRequest {
+            document_type: "brands".to_string(),
+            filters: data(serde_json::json!({"uid": {"$eq": "grpc-aaa"}})),
+            ..Default::default()
+        })
+        .await?
+        .into_inner();
+    assert_eq!(found.meta.unwrap().total, 1);
+    let content = struct_to_json(found.documents[0].data.clone().unwrap());
+    assert_eq!(content["name"], "Renamed");
+
+    let error = client
+        .get(GetRequest {
+            document_type: "brands".to_string(),
+            document_id: uuid::Uuid::now_v7().to_string(),
+            status: Status::Draft.into(),
+            populate: Vec::new(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), tonic::Code::NotFound);
+
+    // the field values go through the validation of the REST API
+    let error = client
+        .create(CreateRequest {
+            document_type: "brands".to_string(),
+            data: data(serde_json::json!({"ghost": 1})),
+            locale: None,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), tonic::Code::InvalidArgument);
+    Ok(())
+}
+
 // ---------------------------------------------------------------------------
-// Tests — pagination cap
+// Tests — pagination limit
 // ---------------------------------------------------------------------------
 
 #[tokio::test]
-async fn page_size_is_capped_at_configured_maximum() -> anyhow::Result<()> {
+async fn page_size_above_configured_maximum_is_rejected() -> anyhow::Result<()> {
     let (router, _c) = build_router().await?;
 
+    let (status, json) =
+        get_json(&router, "/api/documents/brands?pagination[pageSize]=100").await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["meta"]["page_size"], 100);
+
     let (status, json) =
         get_json(&router, "/api/documents/brands?pagination[pageSize]=999").await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json["detail"], "pagination[pageSize] must be at most 100");
+    Ok(())
+}
 
-    assert_eq!(status, StatusCode::OK);
-    let meta = &json["meta"];
-    let page_size = meta
-        .get("pageSize")
-        .or_else(|| meta.get("page_size"))
-        .and_then(|v| v.as_u64())
-        .expect("pageSize must be present in meta");
-    assert_eq!(page_size, 100, "pageSize must be capped at 100");
+// ---------------------------------------------------------------------------
+// Tests — read replicas
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn reads_use_replicas_and_fall_back_to_primary() -> anyhow::Result<()> {
+    // an unreachable replica next to one pointing at the test database itself
+    let (router, _c) = build_router_with_read_replicas(|primary| {
+        vec!["127.0.0.1:1".to_string(), primary.to_string()]
+    })
+    .await?;
+
+    create_brand(&router, "rep-aaa", "Replicated").await?;
+    create_brand(&router, "rep-bbb", "Replicated Too").await?;
+
+    for _ in 0..3 {
+        let (status, json) = get_json(&router, "/api/documents/brands?status=draft").await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["meta"]["total"], 2);
+        assert_eq!(json["data"].as_array().unwrap().len(), 2);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn connections_resolve_tables_in_the_configured_schema() -> anyhow::Result<()> {
+    let (database, _c) = start_postgres().await?;
+
+    for pool in [database.database_pool(), database.read_pool()] {
+        let schema: String = sqlx::query_scalar("SELECT current_schema()")
+            .fetch_one(pool)
+            .await?;
+        assert_eq!(schema, database.database_schema());
+    }
+
+    let public_tables: i64 = sqlx::query_scalar(
+        "SELECT count(*) FROM information_schema.tables WHERE table_schema = 'public'",
+    )
+    .fetch_one(database.database_pool())
+    .await?;
+    assert_eq!(public_tables, 0, "no table may be created in public");
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests — HEAD and OPTIONS
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn head_answers_the_headers_of_get_without_body() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let loc = create_brand(&router, "head-aaa", "Headless").await?;
+
+    let get = router
+        .clone()
+        .oneshot(Request::get(format!("{loc}?status=draft")).body(Body::empty())?)
+        .await?;
+    let head = router
+        .oneshot(Request::head(format!("{loc}?status=draft")).body(Body::empty())?)
+        .await?;
+
+    assert_eq!(head.status(), StatusCode::OK);
+    for name in ["content-type", "cache-control"] {
+        assert_eq!(head.headers().get(name), get.headers().get(name), "{name}");
+    }
+    let bytes = axum::body::to_bytes(head.into_body(), 1000).await?;
+    assert!(bytes.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn options_answers_allowed_methods_and_capabilities() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let response = router
+        .clone()
+        .oneshot(Request::options("/api/documents/brands").body(Body::empty())?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["allow"], "GET, HEAD, POST, OPTIONS");
+    let bytes = axum::body::to_bytes(response.into_body(), 10_000).await?;
+    let json: Value = serde_json::from_slice(&bytes)?;
+    assert_eq!(json["data"]["methods"][2], "POST");
+    assert!(
+        json["data"]["filters"]["name"]
+            .as_array()
+            .unwrap()
+            .contains(&Value::from("$contains"))
+    );
+    assert!(json["data"]["maxPageSize"].is_number());
+
+    let response = router
+        .oneshot(
+            Request::options("/api/documents/brands/9c00b05b-800e-436f-8705-d14bfb2875b4")
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(
+        response.headers()["allow"],
+        "GET, HEAD, PUT, DELETE, OPTIONS"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn exists_answers_without_body_by_status() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let loc = create_brand(&router, "exists-a", "Existing").await?;
+
+    let response = router
+        .clone()
+        .oneshot(Request::get(format!("{loc}/exists?status=draft")).body(Body::empty())?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), 1000).await?;
+    assert!(bytes.is_empty());
+
+    // the brand is only a draft until published
+    let response = router
+        .clone()
+        .oneshot(Request::get(format!("{loc}/exists")).body(Body::empty())?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let missing = format!(
+        "/api/documents/brands/{}/exists?status=draft",
+        uuid::Uuid::new_v4()
+    );
+    let response = router
+        .oneshot(Request::get(missing).body(Body::empty())?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
     Ok(())
 }
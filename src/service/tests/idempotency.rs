@@ -0,0 +1,124 @@
+mod common;
+
+use common::*;
+
+/// POST `body` to `uri`, with `key` as its idempotency key; returns the
+/// status, the `Idempotent-Replayed` header and the body of the response.
+async fn post_with_key(
+    router: &TestRouter,
+    uri: &str,
+    key: &str,
+    body: &str,
+) -> anyhow::Result<(StatusCode, bool, Value)> {
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .header(IDEMPOTENCY_KEY_HEADER, key)
+                .body(Body::from(body.to_string()))?,
+        )
+        .await?;
+    let status = response.status();
+    let replayed = response.headers().contains_key(IDEMPOTENT_REPLAYED_HEADER);
+    let bytes = axum::body::to_bytes(response.into_body(), 1 << 20).await?;
+    let json = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    Ok((status, replayed, json))
+}
+
+// ---------------------------------------------------------------------------
+// Tests — idempotency keys
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn retried_creates_are_replayed() -> anyhow::Result<()> {
+    let (_, state, database, _c) = build_router_with_state().await?;
+    let keys = IdempotencyKeys::new(&IdempotencySettings::default(), database);
+    let router = Router::new()
+        .nest("/api", keys.replay_retries(api_routes()))
+        .with_state(state);
+    let body = r#"{"data": {"uid": "idm-aaa", "name": "Once"}}"#;
+
+    let (status, replayed, created) =
+        post_with_key(&router, "/api/documents/brands", "key-1", body).await?;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(!replayed);
+
+    // the retry is answered with the first response, without a second brand
+    let (status, replayed, retried) =
+        post_with_key(&router, "/api/documents/brands", "key-1", body).await?;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(replayed);
+    assert_eq!(retried, created);
+    let (_, json) = get_json(&router, "/api/documents/brands?status=draft").await?;
+    assert_eq!(json["meta"]["total"], 1);
+
+    // the key of another request
+    let (status, _, problem) =
+        post_with_key(&router, "/api/documents/partners", "key-1", "{}").await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        problem["detail"],
+        "The idempotency key 'key-1' was used for another request: POST /documents/brands"
+    );
+
+    // failed requests are replayed too, unless they failed on the server
+    let (status, _, _) = post_with_key(&router, "/api/documents/brands", "key-2", body).await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, replayed, _) =
+        post_with_key(&router, "/api/documents/brands", "key-2", body).await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(replayed);
+    Ok(())
+}
+
+#[tokio::test]
+async fn keys_are_scoped_by_caller_and_body() -> anyhow::Result<()> {
+    let (_, state, database, _c) = build_router_with_state().await?;
+    let keys = IdempotencyKeys::new(&IdempotencySettings::default(), database);
+    let router = Router::new()
+        .nest("/api", keys.replay_retries(api_routes()))
+        .with_state(state);
+    let post_as = |user: &'static str, body: &'static str| {
+        let router = router.clone();
+        async move {
+            let response = router
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/api/documents/brands")
+                        .header("content-type", "application/json")
+                        .header("x-user-id", user)
+                        .header(IDEMPOTENCY_KEY_HEADER, "1")
+                        .body(Body::from(body))?,
+                )
+                .await?;
+            let replayed = response.headers().contains_key(IDEMPOTENT_REPLAYED_HEADER);
+            let bytes = axum::body::to_bytes(response.into_body(), 1 << 20).await?;
+            let json: Value = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+            Ok::<_, anyhow::Error>((replayed, json))
+        }
+    };
+    let alice = r#"{"data": {"uid": "idm-alice", "name": "Alice"}}"#;
+    let bob = r#"{"data": {"uid": "idm-bob", "name": "Bob"}}"#;
+
+    let (replayed, _) = post_as("alice", alice).await?;
+    assert!(!replayed);
+    // the same key of another user is another key
+    let (replayed, _) = post_as("bob", bob).await?;
+    assert!(!replayed);
+    let (_, json) = get_json(&router, "/api/documents/brands?status=draft").await?;
+    assert_eq!(json["meta"]["total"], 2);
+
+    // a retry with another body is rejected, not replayed
+    let (replayed, problem) = post_as("alice", bob).await?;
+    assert!(!replayed);
+    assert_eq!(problem["status"], 422);
+    assert_eq!(
+        problem["detail"],
+        "The idempotency key '1' was used for the same request with another body"
+    );
+    Ok(())
+}
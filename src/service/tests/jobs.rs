@@ -0,0 +1,114 @@
+mod common;
+
+use common::*;
+
+const ADMIN_USER: [(&str, &str); 2] = [("x-user-id", "admin-1"), ("x-user-roles", "admin")];
+
+async fn admin_request(
+    router: &TestRouter,
+    method: &str,
+    uri: &str,
+    body: Option<Value>,
+) -> anyhow::Result<(StatusCode, Value)> {
+    let mut request = Request::builder().method(method).uri(uri);
+    for (name, value) in ADMIN_USER {
+        request = request.header(name, value);
+    }
+    let body = match body {
+        Some(body) => {
+            request = request.header("content-type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = router.clone().oneshot(request.body(body)?).await?;
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), 1 << 20).await?;
+    Ok((status, serde_json::from_slice(&bytes)?))
+}
+
+// ---------------------------------------------------------------------------
+// Tests — background jobs
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn jobs_run_retry_and_cancel() -> anyhow::Result<()> {
+    let (_, state, database, _c) = build_router_with_state().await?;
+    let settings = JobsSettings {
+        max_attempts: 1,
+        ..Default::default()
+    };
+    let queue = JobQueue::new(&settings, database);
+    let workers = JobWorkers::new(&settings, queue.clone(), state.clone());
+    let router = Router::new()
+        .nest(
+            ADMIN_PATH,
+            admin_routes(vec!["admin".to_string()], Some(queue)),
+        )
+        .with_state(state);
+
+    let (status, json) = admin_request(
+        &router,
+        "POST",
+        "/admin/api/jobs",
+        Some(serde_json::json!({"kind": "clean-orphaned-links"})),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED, "{json}");
+    assert_eq!(json["data"]["status"], "pending");
+    let cleanup = json["data"]["id"].as_i64().unwrap();
+
+    assert!(workers.run_next().await?);
+    assert!(!workers.run_next().await?);
+    let (_, json) =
+        admin_request(&router, "GET", &format!("/admin/api/jobs/{cleanup}"), None).await?;
+    assert_eq!(json["data"]["status"], "succeeded");
+    assert_eq!(json["data"]["attempts"], 1);
+
+    // a kind no handler runs fails, for good after its only attempt
+    let (_, json) = admin_request(
+        &router,
+        "POST",
+        "/admin/api/jobs",
+        Some(serde_json::json!({"kind": "send-webhook"})),
+    )
+    .await?;
+    let unknown = json["data"]["id"].as_i64().unwrap();
+    assert!(workers.run_next().await?);
+    let (_, json) = admin_request(&router, "GET", "/admin/api/jobs?status=failed", None).await?;
+    assert_eq!(json["data"].as_array().unwrap().len(), 1);
+    assert_eq!(json["data"][0]["id"], unknown);
+    assert!(
+        json["data"][0]["lastError"]
+            .as_str()
+            .unwrap()
+            .contains("send-webhook")
+    );
+
+    let retry = format!("/admin/api/jobs/{unknown}/retry");
+    let (status, json) = admin_request(&router, "POST", &retry, None).await?;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["data"]["status"], "pending");
+    assert_eq!(json["data"]["attempts"], 0);
+
+    let cancel = format!("/admin/api/jobs/{unknown}/cancel");
+    let (status, json) = admin_request(&router, "POST", &cancel, None).await?;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    assert_eq!(json["data"]["status"], "cancelled");
+    assert!(!workers.run_next().await?);
+
+    // only pending jobs are cancelled, only finished ones retried
+    let (status, _) = admin_request(&router, "POST", &cancel, None).await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = admin_request(
+        &router,
+        "POST",
+        &format!("/admin/api/jobs/{cleanup}/retry"),
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = admin_request(&router, "GET", "/admin/api/jobs/999999", None).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    Ok(())
+}
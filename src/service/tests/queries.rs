@@ -26,6 +26,122 @@ async fn filter_by_field_value() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn filter_by_related_document_field() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let cat_loc = create_partner_category(&router, "rel-retail", 3).await?;
+    let cat_id = cat_loc.trim_start_matches("/api/documents/partner-categories/");
+    create_partner_category(&router, "rel-other", 4).await?;
+
+    let partner_loc = create_partner(&router, "6000000000001", "Retail Partner Ltd").await?;
+    create_partner(&router, "6000000000002", "Other Partner Ltd").await?;
+
+    let (status, _) = put_json(
+        &router,
+        &partner_loc,
+        &format!(r#"{{"data": {{"category": {{"connect": ["{cat_id}"]}}}}}}"#),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    for filter in [
+        "filters[category][uid][$eq]=rel-retail",
+        "filters[category.uid][$eq]=rel-retail",
+    ] {
+        let (status, json) = get_json(
+            &router,
+            &format!("/api/documents/partners?status=draft&{filter}"),
+        )
+        .await?;
+        assert_eq!(status, StatusCode::OK);
+        let data = json["data"].as_array().unwrap();
+        assert_eq!(data.len(), 1, "{filter} should match one partner");
+        assert_eq!(data[0]["idno"], "6000000000001");
+        assert_eq!(json["meta"]["total"], 1);
+    }
+
+    let (status, json) = get_json(
+        &router,
+        "/api/documents/partners?status=draft&filters[category][uid][$eq]=rel-other",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert!(json["data"].as_array().unwrap().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn filter_by_inverse_relation_is_rejected() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    for filter in [
+        "filters[partners][idno][$eq]=6000000000001",
+        "filters[partners.idno][$eq]=6000000000001",
+    ] {
+        let (status, json) = get_json(
+            &router,
+            &format!("/api/documents/brands?status=draft&{filter}"),
+        )
+        .await?;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{filter}: {json}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn filter_by_range_and_literal_patterns() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    create_partner_category(&router, "rng-low", 1).await?;
+    create_partner_category(&router, "rng-mid", 5).await?;
+    create_partner_category(&router, "rng-high", 9).await?;
+
+    let (status, json) = get_json(
+        &router,
+        "/api/documents/partner-categories?status=draft&sort=priority:asc\
+            &filters[priority][$between][]=2&filters[priority][$between][]=9",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    let uids: Vec<&str> = json["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| d["uid"].as_str().unwrap())
+        .collect();
+    assert_eq!(uids, ["rng-mid", "rng-high"], "bounds are inclusive");
+
+    let (status, _) = get_json(
+        &router,
+        "/api/documents/partner-categories?status=draft&filters[priority][$between][]=2",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    create_brand(&router, "pat-pct", "50% Off").await?;
+    create_brand(&router, "pat-num", "500 Off").await?;
+    create_brand(&router, "pat-und", "Acme_Co").await?;
+    create_brand(&router, "pat-any", "AcmeXCo").await?;
+
+    for (filter, expected) in [
+        ("filters[name][$contains]=50%25", "pat-pct"),
+        ("filters[name][$startsWith]=Acme_", "pat-und"),
+        ("filters[name][$endsWith]=_Co", "pat-und"),
+    ] {
+        let (status, json) = get_json(
+            &router,
+            &format!("/api/documents/brands?status=draft&{filter}"),
+        )
+        .await?;
+        assert_eq!(status, StatusCode::OK);
+        let data = json["data"].as_array().unwrap();
+        assert_eq!(data.len(), 1, "{filter} must match wildcards literally");
+        assert_eq!(data[0]["uid"], expected);
+    }
+    Ok(())
+}
+
 // ---------------------------------------------------------------------------
 // Tests — sort / order
 // ---------------------------------------------------------------------------
@@ -54,3 +170,377 @@ async fn sort_documents_by_field_ascending() -> anyhow::Result<()> {
     assert_eq!(uids, sorted, "brands should be in ascending uid order");
     Ok(())
 }
+
+#[tokio::test]
+async fn sort_documents_by_localized_field() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    for (uid, en, ro) in [
+        ("loc-1", "Apple", "Zmeură"),
+        ("loc-2", "Zucchini", "Afine"),
+        ("loc-3", "Mango", "Mango"),
+    ] {
+        create_document(
+            &router,
+            "partner-categories",
+            &format!(
+                r#"{{"data": {{"uid": "{uid}", "name": {{"en": "{en}", "ro": "{ro}"}}, "priority": 1}}}}"#
+            ),
+        )
+        .await?;
+    }
+
+    for (sort, expected) in [
+        ("name.en:asc", ["loc-1", "loc-3", "loc-2"]),
+        ("name.ro:asc", ["loc-2", "loc-3", "loc-1"]),
+    ] {
+        let (status, json) = get_json(
+            &router,
+            &format!("/api/documents/partner-categories?status=draft&sort={sort}"),
+        )
+        .await?;
+        assert_eq!(status, StatusCode::OK, "{sort}");
+        let uids: Vec<&str> = json["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| d["uid"].as_str().unwrap())
+            .collect();
+        assert_eq!(uids, expected, "{sort}");
+    }
+
+    // a locale the type doesn't have, or a field which isn't localized
+    for sort in ["name.de:asc", "priority.en:asc"] {
+        let (status, _) = get_json(
+            &router,
+            &format!("/api/documents/partner-categories?status=draft&sort={sort}"),
+        )
+        .await?;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{sort}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_fields_by_their_key_in_the_responses() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    create_partner(&router, "6100000000001", "Alpha").await?;
+    create_partner(&router, "6100000000002", "Beta").await?;
+
+    let (status, json) = get_json(
+        &router,
+        "/api/documents/partners?status=draft&sort=legalEntity:desc",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK, "{json}");
+    let entities: Vec<&str> = json["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|partner| partner["legalEntity"].as_str().unwrap())
+        .collect();
+    assert_eq!(entities, ["Beta", "Alpha"]);
+
+    // the key in the responses and the id name the same field
+    for filter in [
+        "filters[legalEntity][$eq]=Alpha",
+        "filters[legal_entity][$eq]=Alpha",
+    ] {
+        let (status, json) = get_json(
+            &router,
+            &format!("/api/documents/partners?status=draft&{filter}"),
+        )
+        .await?;
+        assert_eq!(status, StatusCode::OK, "{json}");
+        let data = json["data"].as_array().unwrap();
+        assert_eq!(data.len(), 1, "{filter} should match one partner");
+        assert_eq!(data[0]["idno"], "6100000000001");
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests — NDJSON export
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn ndjson_export_streams_all_matching_documents() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    create_brand(&router, "exp-ccc", "Gamma").await?;
+    create_brand(&router, "exp-aaa", "Alpha").await?;
+    create_brand(&router, "exp-bbb", "Beta").await?;
+    create_brand(&router, "oth-aaa", "Other").await?;
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(
+                    "/api/documents/brands?status=draft&filters[uid][$startsWith]=exp-\
+                    &sort=uid:asc&pagination[pageSize]=1",
+                )
+                .header("accept", "application/x-ndjson")
+                .body(Body::empty())?,
+        )
+        .await?;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/x-ndjson",
+        "export must be newline-delimited JSON"
+    );
+    let bytes = axum::body::to_bytes(response.into_body(), 1 << 20).await?;
+    let uids = std::str::from_utf8(&bytes)?
+        .lines()
+        .map(|line| {
+            let json: Value = serde_json::from_str(line)?;
+            Ok(json["uid"].as_str().unwrap_or_default().to_string())
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    assert_eq!(
+        uids,
+        ["exp-aaa", "exp-bbb", "exp-ccc"],
+        "export must ignore pagination and keep the sort order"
+    );
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests — aggregate
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn aggregate_groups_documents_with_metrics() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    for (title, latitude) in [("North", "47.5"), ("North", "46.5"), ("South", "45.25")] {
+        create_document(
+            &router,
+            "points-of-sale",
+            &format!(
+                r#"{{"data": {{"title": "{title}", "location": "Somewhere", "latitude": "{latitude}", "longitude": "28.8"}}}}"#
+            ),
+        )
+        .await?;
+    }
+
+    let (status, json) = get_json(
+        &router,
+        "/api/documents/points-of-sale/aggregate?status=draft&groupBy=title&count&avg=latitude",
+    )
+    .await?;
+
+    assert_eq!(status, StatusCode::OK);
+    let groups = json["data"].as_array().expect("data must be an array");
+    assert_eq!(groups.len(), 2, "one group per title, got: {json}");
+    assert_eq!(groups[0]["group"]["title"], "North");
+    assert_eq!(groups[0]["metrics"]["count"], 2);
+    assert_eq!(groups[0]["metrics"]["avg"]["latitude"], 47.0);
+    assert_eq!(groups[1]["group"]["title"], "South");
+    assert_eq!(groups[1]["metrics"]["count"], 1);
+
+    let (status, _) =
+        get_json(&router, "/api/documents/points-of-sale/aggregate?sum=title").await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    Ok(())
+}
+
+#[tokio::test]
+async fn distinct_values_of_field_are_sorted_and_counted() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    for title in ["South", "North", "North"] {
+        create_document(
+            &router,
+            "points-of-sale",
+            &format!(
+                r#"{{"data": {{"title": "{title}", "location": "Somewhere", "latitude": "47.0", "longitude": "28.8"}}}}"#
+            ),
+        )
+        .await?;
+    }
+    // documents without a value are not listed
+    create_document(
+        &router,
+        "points-of-sale",
+        r#"{"data": {"location": "Nowhere", "latitude": "47.0", "longitude": "28.8"}}"#,
+    )
+    .await?;
+
+    let uri = "/api/documents/points-of-sale/distinct/title?status=draft";
+    let (status, json) = get_json(&router, uri).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"], serde_json::json!(["North", "South"]));
+
+    let (status, json) = get_json(&router, &format!("{uri}&count")).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        json["data"],
+        serde_json::json!([
+            {"value": "North", "count": 2},
+            {"value": "South", "count": 1},
+        ])
+    );
+
+    let (status, _) = get_json(&router, "/api/documents/points-of-sale/distinct/ghost").await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    Ok(())
+}
+
+#[tokio::test]
+async fn strapi_response_format_wraps_documents_in_attributes() -> anyhow::Result<()> {
+    let (router, _c) = build_router_with_response_format(ResponseFormat::Strapi).await?;
+
+    let cat_loc = create_partner_category(&router, "str-retail", 3).await?;
+    let cat_id = cat_loc.trim_start_matches("/api/documents/partner-categories/");
+    let partner_loc = create_partner(&router, "6300000000001", "Strapi Partner Ltd").await?;
+    let partner_id = partner_loc.trim_start_matches("/api/documents/partners/");
+    put_json(
+        &router,
+        &partner_loc,
+        &format!(r#"{{"data": {{"category": {{"connect": ["{cat_id}"]}}}}}}"#),
+    )
+    .await?;
+
+    let (status, json) = get_json(
+        &router,
+        "/api/documents/partners?status=draft&populate=category&pagination[pageSize]=10",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        json["meta"],
+        serde_json::json!({"pagination": {"page": 1, "pageSize": 10, "pageCount": 1, "total": 1}})
+    );
+    let partner = &json["data"][0];
+    assert_eq!(partner["id"], partner_id);
+    assert_eq!(partner["attributes"]["legalEntity"], "Strapi Partner Ltd");
+    assert!(partner["attributes"].get("documentId").is_none());
+    assert_eq!(partner["attributes"]["category"]["data"]["id"], cat_id);
+    assert_eq!(
+        partner["attributes"]["category"]["data"]["attributes"]["uid"],
+        "str-retail"
+    );
+
+    let (status, json) = get_json(&router, &format!("{cat_loc}?status=draft")).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["id"], cat_id);
+    assert_eq!(json["data"]["attributes"]["priority"], 3);
+    assert_eq!(json["meta"], serde_json::json!({}));
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests — GraphQL
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn graphql_queries_documents_with_their_relations() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    create_brand(&router, "gql-aaa", "Acme").await?;
+    create_brand(&router, "gql-bbb", "Beta").await?;
+    let cat_loc = create_partner_category(&router, "gql-retail", 3).await?;
+    let cat_id = cat_loc.trim_start_matches("/api/documents/partner-categories/");
+    let partner_loc = create_partner(&router, "6100000000001", "GraphQL Partner Ltd").await?;
+    let partner_id = partner_loc.trim_start_matches("/api/documents/partners/");
+    put_json(
+        &router,
+        &partner_loc,
+        &format!(r#"{{"data": {{"category": {{"connect": ["{cat_id}"]}}}}}}"#),
+    )
+    .await?;
+
+    let json = graphql(
+        &router,
+        r#"{ brands(status: DRAFT, sort: ["uid:desc"], pagination: {pageSize: 1}) {
+            data { uid name status }
+            meta { page pageSize total }
+        } }"#,
+        Value::Null,
+    )
+    .await?;
+    assert!(json.get("errors").is_none(), "{json}");
+    assert_eq!(
+        json["data"]["brands"],
+        serde_json::json!({
+            "data": [{"uid": "gql-bbb", "name": "Beta", "status": "draft"}],
+            "meta": {"page": 1, "pageSize": 1, "total": 2},
+        })
+    );
+
+    let json = graphql(
+        &router,
+        &format!(
+            r#"{{ partner(documentId: "{partner_id}", status: DRAFT) {{
+                documentId idno category {{ uid priority }} brands {{ uid }}
+            }} }}"#
+        ),
+        Value::Null,
+    )
+    .await?;
+    assert!(json.get("errors").is_none(), "{json}");
+    assert_eq!(
+        json["data"]["partner"],
+        serde_json::json!({
+            "documentId": partner_id,
+            "idno": "6100000000001",
+            "category": {"uid": "gql-retail", "priority": 3},
+            "brands": [],
+        })
+    );
+
+    let json = graphql(
+        &router,
+        r#"query($filters: JSON) { partners(status: DRAFT, filters: $filters) {
+            data { legalEntity }
+        } }"#,
+        serde_json::json!({"filters": {"category": {"uid": {"$eq": "gql-retail"}}}}),
+    )
+    .await?;
+    assert!(json.get("errors").is_none(), "{json}");
+    assert_eq!(
+        json["data"]["partners"]["data"],
+        serde_json::json!([{"legalEntity": "GraphQL Partner Ltd"}])
+    );
+
+    let json = graphql(
+        &router,
+        r#"query($filters: JSON) { brands(filters: $filters) { data { uid } } }"#,
+        serde_json::json!({"filters": {"ghost": {"$eq": 1}}}),
+    )
+    .await?;
+    assert_eq!(
+        json["errors"][0]["extensions"]["code"],
+        "UNPROCESSABLE_ENTITY"
+    );
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests — search
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn search_finds_published_documents_across_types() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let loc = create_brand(&router, "srch-pub", "Zephyrine Motors").await?;
+    publish_document(&router, &loc).await?;
+    create_brand(&router, "srch-draft", "Zephyrine Drafts").await?;
+
+    let (status, json) = get_json(&router, "/api/search?q=zephyrine").await?;
+    assert_eq!(status, StatusCode::OK);
+    let data = json["data"].as_array().expect("data must be an array");
+    assert_eq!(data.len(), 1, "only the published brand is found: {json}");
+    assert_eq!(data[0]["type"], "brands");
+    assert_eq!(data[0]["document"]["uid"], "srch-pub");
+    assert!(data[0]["rank"].as_f64().unwrap() > 0.0);
+
+    let (status, _) = get_json(&router, "/api/search?q=%20").await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    Ok(())
+}
@@ -1,6 +1,10 @@
 mod common;
 
 use common::*;
+use luminair_common::{AttributeId, DocumentType, DocumentTypeId};
+use service::domain::actor::ActorContext;
+use service::domain::document::content::{ContentValue, DomainValue};
+use service::domain::document::{DocumentInstance, DocumentInstanceId};
 
 // ---------------------------------------------------------------------------
 // Tests — populate (relation loading)
@@ -41,6 +45,120 @@ async fn populate_loads_related_documents() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn lateral_populate_matches_batched_populate() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let cat_loc = create_partner_category(&router, "lat-retail", 2).await?;
+    let cat_id = cat_loc.trim_start_matches("/api/documents/partner-categories/");
+
+    let partner_loc = create_partner(&router, "5000000000002", "Lateral Partner Ltd").await?;
+    create_partner(&router, "5000000000003", "Lonely Partner Ltd").await?;
+
+    let (status, _) = put_json(
+        &router,
+        &partner_loc,
+        &format!(r#"{{"data": {{"category": {{"connect": ["{cat_id}"]}}}}}}"#),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let list = "/api/documents/partners?status=draft&populate=category&sort=idno:asc";
+    let (status, batched) = get_json(&router, list).await?;
+    assert_eq!(status, StatusCode::OK);
+    let (status, lateral) = get_json(&router, &format!("{list}&populateStrategy=lateral")).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    assert_eq!(lateral["meta"], batched["meta"]);
+    assert_eq!(
+        lateral["data"], batched["data"],
+        "both strategies must return the same documents"
+    );
+    assert_eq!(lateral["data"][0]["category"][0]["uid"], "lat-retail");
+    assert!(
+        lateral["data"][1]["category"].is_null(),
+        "a document without links has no populated relation"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn populate_options_select_fields_of_related_documents() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let cat_loc = create_partner_category(&router, "opt-retail", 3).await?;
+    let cat_id = cat_loc.trim_start_matches("/api/documents/partner-categories/");
+
+    let partner_loc = create_partner(&router, "5000000000006", "Optioned Partner Ltd").await?;
+    let (status, _) = put_json(
+        &router,
+        &partner_loc,
+        &format!(r#"{{"data": {{"category": {{"connect": ["{cat_id}"]}}}}}}"#),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let list = "/api/documents/partners?status=draft\
+        &populate[category][fields]=uid&populate[category][limit]=1";
+    for strategy in ["batched", "lateral"] {
+        let (status, json) =
+            get_json(&router, &format!("{list}&populateStrategy={strategy}")).await?;
+        assert_eq!(status, StatusCode::OK, "{strategy}");
+        let category = &json["data"][0]["category"][0];
+        assert_eq!(category["uid"], "opt-retail", "{strategy}");
+        assert!(category["name"].is_null(), "{strategy}: {category}");
+        assert!(category["priority"].is_null(), "{strategy}: {category}");
+    }
+
+    let (status, _) = get_json(
+        &router,
+        "/api/documents/partners?status=draft&populate[category][fields]=unknown",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    Ok(())
+}
+
+#[tokio::test]
+async fn with_count_counts_related_documents_without_populating() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let cat_loc = create_partner_category(&router, "cnt-retail", 2).await?;
+    let cat_id = cat_loc.trim_start_matches("/api/documents/partner-categories/");
+
+    let partner_loc = create_partner(&router, "5000000000004", "Counted Partner Ltd").await?;
+    create_partner(&router, "5000000000005", "Uncounted Partner Ltd").await?;
+
+    let (status, _) = put_json(
+        &router,
+        &partner_loc,
+        &format!(r#"{{"data": {{"category": {{"connect": ["{cat_id}"]}}}}}}"#),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, json) = get_json(
+        &router,
+        "/api/documents/partners?status=draft&withCount=category&sort=idno:asc",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"][0]["relationCounts"]["category"], 1);
+    assert_eq!(json["data"][1]["relationCounts"]["category"], 0);
+    assert!(
+        json["data"][0]["category"].is_null(),
+        "a counted relation is not populated"
+    );
+
+    let (status, _) = get_json(
+        &router,
+        "/api/documents/partners?status=draft&withCount=unknown",
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    Ok(())
+}
+
 // ---------------------------------------------------------------------------
 // Tests — connect / disconnect
 // ---------------------------------------------------------------------------
@@ -84,7 +202,11 @@ async fn connect_and_disconnect_relation() -> anyhow::Result<()> {
         &format!(r#"{{"data": {{"category": {{"disconnect": ["{cat_id}"]}}}}}}"#),
     )
     .await?;
-    assert_eq!(status, StatusCode::NO_CONTENT, "disconnect should return 204");
+    assert_eq!(
+        status,
+        StatusCode::NO_CONTENT,
+        "disconnect should return 204"
+    );
 
     let (_, json) = get_json(
         &router,
@@ -101,6 +223,80 @@ async fn connect_and_disconnect_relation() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn set_replaces_relation_links() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let first_loc = create_partner_category(&router, "set-first", 3).await?;
+    let first_id = first_loc.trim_start_matches("/api/documents/partner-categories/");
+    let second_loc = create_partner_category(&router, "set-second", 4).await?;
+    let second_id = second_loc.trim_start_matches("/api/documents/partner-categories/");
+
+    // relations in the create payload are linked together with the document
+    let partner_loc = create_document(
+        &router,
+        "partners",
+        &format!(
+            r#"{{"data": {{"idno": "6100000000001", "legal_entity": "Set Test Ltd", "category": {{"connect": ["{first_id}"]}}}}}}"#
+        ),
+    )
+    .await?;
+
+    let (status, _) = put_json(
+        &router,
+        &partner_loc,
+        &format!(r#"{{"data": {{"category": {{"set": ["{second_id}"]}}}}}}"#),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT, "set should return 204");
+
+    let (_, json) = get_json(
+        &router,
+        &format!("{partner_loc}?status=draft&populate=category"),
+    )
+    .await?;
+    let category = json["data"]["category"].as_array().unwrap();
+    assert_eq!(category.len(), 1, "set should replace the links: {json}");
+    assert_eq!(category[0]["documentId"], second_id);
+    Ok(())
+}
+
+#[tokio::test]
+async fn set_with_unknown_target_keeps_existing_links() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let cat_loc = create_partner_category(&router, "set-keep", 5).await?;
+    let cat_id = cat_loc.trim_start_matches("/api/documents/partner-categories/");
+    let partner_loc = create_document(
+        &router,
+        "partners",
+        &format!(
+            r#"{{"data": {{"idno": "6200000000001", "legal_entity": "Keep Ltd", "category": {{"connect": ["{cat_id}"]}}}}}}"#
+        ),
+    )
+    .await?;
+
+    let phantom_id = uuid::Uuid::new_v4().to_string();
+    let (status, json) = put_json(
+        &router,
+        &partner_loc,
+        &format!(r#"{{"data": {{"legal_entity": "Renamed Ltd", "category": {{"set": ["{phantom_id}"]}}}}}}"#),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(json["detail"].as_str().unwrap().contains(&phantom_id));
+
+    // neither the links nor the fields of the rejected request were written
+    let (_, json) = get_json(
+        &router,
+        &format!("{partner_loc}?status=draft&populate=category"),
+    )
+    .await?;
+    assert_eq!(json["data"]["legal_entity"], "Keep Ltd");
+    assert_eq!(json["data"]["category"][0]["documentId"], cat_id);
+    Ok(())
+}
+
 // ---------------------------------------------------------------------------
 // Tests — publish
 // ---------------------------------------------------------------------------
@@ -149,3 +345,689 @@ async fn draft_copy_still_accessible_after_publish() -> anyhow::Result<()> {
     assert_eq!(status, StatusCode::OK, "published copy must be accessible");
     Ok(())
 }
+
+#[tokio::test]
+async fn draft_edits_do_not_change_published_content_until_publish() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let cat_loc = create_partner_category(&router, "live-cat", 1).await?;
+    let cat_id = cat_loc.trim_start_matches("/api/documents/partner-categories/");
+    publish_document(&router, &cat_loc).await?;
+
+    let loc = create_partner(&router, "6000000000001", "Live Partner Ltd").await?;
+    let (status, _) = put_json(
+        &router,
+        &loc,
+        &format!(r#"{{"data": {{"category": {{"connect": ["{cat_id}"]}}}}}}"#),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    publish_document(&router, &loc).await?;
+
+    // edit the draft: a field and the relation links
+    let (status, _) = put_json(
+        &router,
+        &loc,
+        &format!(
+            r#"{{"data": {{"legal_entity": "Edited Partner Ltd", "category": {{"disconnect": ["{cat_id}"]}}}}}}"#
+        ),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (_, json) = get_json(&router, &format!("{loc}?status=draft&populate=category")).await?;
+    assert_eq!(json["data"]["legal_entity"], "Edited Partner Ltd");
+    assert!(
+        json["data"]["category"]
+            .as_array()
+            .map(|a| a.is_empty())
+            .unwrap_or(true),
+        "category should be disconnected: {json}"
+    );
+
+    // the live content is still the published one
+    let (status, json) = get_json(&router, &format!("{loc}?populate=category")).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["legal_entity"], "Live Partner Ltd");
+    assert_eq!(json["data"]["category"][0]["documentId"], cat_id);
+
+    // until the draft is published over it
+    publish_document(&router, &loc).await?;
+    let (_, json) = get_json(&router, &format!("{loc}?populate=category")).await?;
+    assert_eq!(json["data"]["legal_entity"], "Edited Partner Ltd");
+    assert!(
+        json["data"]["category"]
+            .as_array()
+            .map(|a| a.is_empty())
+            .unwrap_or(true),
+        "category should be disconnected: {json}"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn concurrent_publishes_of_a_document_are_serialized() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let loc = create_brand(&router, "pub-race", "Raced Brand").await?;
+    let publish_uri = format!("{loc}/publish");
+
+    let (first, second) = tokio::join!(
+        post_json(&router, &publish_uri, "{}"),
+        post_json(&router, &publish_uri, "{}"),
+    );
+    let statuses = [first?.0, second?.0];
+
+    let published = statuses
+        .iter()
+        .filter(|status| **status == StatusCode::NO_CONTENT)
+        .count();
+    assert_eq!(published, 1, "exactly one publish must win: {statuses:?}");
+    assert!(
+        statuses
+            .iter()
+            .all(|status| *status == StatusCode::NO_CONTENT || status.is_client_error()),
+        "the other publish must be rejected, got {statuses:?}"
+    );
+
+    let (status, json) = get_json(&router, &loc).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["revision"], 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn scheduled_publish_and_unpublish_are_applied_when_due() -> anyhow::Result<()> {
+    let (router, state, database, _c) = build_router_with_state().await?;
+
+    let loc = create_brand(&router, "pub-sched", "Scheduled Brand").await?;
+    let now = chrono::Utc::now();
+    let body = serde_json::json!({
+        "publishAt": (now + chrono::Duration::hours(1)).to_rfc3339(),
+        "unpublishAt": (now + chrono::Duration::hours(2)).to_rfc3339(),
+    });
+    let (status, _, bytes) =
+        post_json(&router, &format!("{loc}/publish"), &body.to_string()).await?;
+    assert_eq!(
+        status,
+        StatusCode::NO_CONTENT,
+        "schedule failed: {}",
+        String::from_utf8_lossy(&bytes)
+    );
+
+    let (status, _) = get_json(&router, &loc).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND, "must not be published yet");
+
+    // nothing is due yet
+    let applied = apply_due_publications(&state, database, now).await?;
+    assert_eq!(applied, Some(0));
+
+    // another replica running the scheduled publications
+    let mut other = database.database_pool().acquire().await?;
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(SCHEDULER_LOCK_KEY)
+        .execute(&mut *other)
+        .await?;
+    let applied =
+        apply_due_publications(&state, database, now + chrono::Duration::minutes(90)).await?;
+    assert_eq!(applied, None, "the lock holder must run the publications");
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(SCHEDULER_LOCK_KEY)
+        .execute(&mut *other)
+        .await?;
+
+    let applied =
+        apply_due_publications(&state, database, now + chrono::Duration::minutes(90)).await?;
+    assert_eq!(applied, Some(1));
+    let (status, json) = get_json(&router, &loc).await?;
+    assert_eq!(status, StatusCode::OK, "must be published when due");
+    assert_eq!(json["data"]["uid"], "pub-sched");
+
+    let applied =
+        apply_due_publications(&state, database, now + chrono::Duration::hours(3)).await?;
+    assert_eq!(applied, Some(1));
+    let (status, _) = get_json(&router, &loc).await?;
+    assert_eq!(
+        status,
+        StatusCode::NOT_FOUND,
+        "must be unpublished when due"
+    );
+
+    // the draft is kept and can be published again
+    let (status, _) = get_json(&router, &format!("{loc}?status=draft")).await?;
+    assert_eq!(status, StatusCode::OK);
+    publish_document(&router, &loc).await?;
+    let (status, _) = get_json(&router, &loc).await?;
+    assert_eq!(status, StatusCode::OK);
+    Ok(())
+}
+
+#[tokio::test]
+async fn publishing_again_keeps_the_scheduled_unpublish() -> anyhow::Result<()> {
+    let (router, state, database, _c) = build_router_with_state().await?;
+
+    let loc = create_brand(&router, "pub-keep", "Kept Schedule").await?;
+    let now = chrono::Utc::now();
+    let body = serde_json::json!({
+        "unpublishAt": (now + chrono::Duration::hours(2)).to_rfc3339(),
+    });
+    let (status, _, _) = post_json(&router, &format!("{loc}/publish"), &body.to_string()).await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // an edit published again without a schedule
+    let (status, _) = put_json(&router, &loc, r#"{"data": {"name": "Kept Schedule 2"}}"#).await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    publish_document(&router, &loc).await?;
+
+    let applied =
+        apply_due_publications(&state, database, now + chrono::Duration::hours(3)).await?;
+    assert_eq!(applied, Some(1));
+    let (status, _) = get_json(&router, &loc).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND, "the unpublish must be kept");
+    Ok(())
+}
+
+#[tokio::test]
+async fn schedule_with_unpublish_before_publish_is_rejected() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let loc = create_brand(&router, "pub-inv", "Invalid Schedule").await?;
+    let body = r#"{"publishAt": "2030-01-02T00:00:00Z", "unpublishAt": "2030-01-01T00:00:00Z"}"#;
+    let (status, _, _) = post_json(&router, &format!("{loc}/publish"), body).await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, _, _) = post_json(
+        &router,
+        &format!("{loc}/publish"),
+        r#"{"publishAt": "tomorrow"}"#,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests — editorial workflow
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn documents_move_through_workflow_stages() -> anyhow::Result<()> {
+    let (router, _c) = build_router().await?;
+
+    let loc = create_partner(&router, "7000000000001", "Workflow Partner Ltd").await?;
+    let (_, json) = get_json(&router, &format!("{loc}?status=draft")).await?;
+    assert_eq!(json["data"]["workflowStage"], "draft");
+    let version = json["data"]["version"].as_i64().unwrap();
+
+    let (status, _, bytes) = post_json(&router, &format!("{loc}/workflow/review"), "{}").await?;
+    assert_eq!(
+        status,
+        StatusCode::NO_CONTENT,
+        "move failed: {}",
+        String::from_utf8_lossy(&bytes)
+    );
+    let (_, json) = get_json(&router, &format!("{loc}?status=draft")).await?;
+    assert_eq!(json["data"]["workflowStage"], "review");
+    assert_eq!(json["data"]["version"].as_i64().unwrap(), version + 1);
+
+    // approving takes the editor role
+    let move_to_approved = |roles: &str| {
+        Request::builder()
+            .method("POST")
+            .uri(format!("{loc}/workflow/approved"))
+            .header("x-user-id", "alice")
+            .header("x-user-roles", roles)
+            .body(Body::empty())
+    };
+    let response = router.clone().oneshot(move_to_approved("author")?).await?;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = router
+        .clone()
+        .oneshot(move_to_approved("author,editor")?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let (_, json) = get_json(&router, &format!("{loc}?status=draft")).await?;
+    assert_eq!(json["data"]["workflowStage"], "approved");
+
+    let (status, _, _) = post_json(&router, &format!("{loc}/workflow/approved"), "{}").await?;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = post_json(&router, &format!("{loc}/workflow/archived"), "{}").await?;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // brands have no workflow
+    let brand = create_brand(&router, "wf-brand", "No Workflow").await?;
+    let (status, _, _) = post_json(&router, &format!("{brand}/workflow/review"), "{}").await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests — lifecycle hooks
+// ---------------------------------------------------------------------------
+
+/// Records the hooks it is called for, writes legal entities in upper case
+/// and rejects every delete.
+#[derive(Clone, Default)]
+struct RecordingHooks {
+    calls: std::sync::Arc<std::sync::Mutex<Vec<&'static str>>>,
+}
+
+impl RecordingHooks {
+    fn record(&self, call: &'static str) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+impl LifecycleHooks for RecordingHooks {
+    fn before_create(
+        &self,
+        _document_type: &DocumentType,
+        _actor: &ActorContext,
+        instance: &mut DocumentInstance,
+    ) -> Result<(), HookError> {
+        self.record("before_create");
+        let legal_entity = AttributeId::try_new("legal_entity").unwrap();
+        if let Some(ContentValue::Scalar(DomainValue::Text(text))) =
+            instance.content.fields.get_mut(&legal_entity)
+        {
+            *text = text.to_uppercase();
+        }
+        Ok(())
+    }
+
+    fn after_create(
+        &self,
+        _document_type: &DocumentType,
+        _actor: &ActorContext,
+        _instance: &DocumentInstance,
+    ) {
+        self.record("after_create");
+    }
+
+    fn after_update(
+        &self,
+        _document_type: &DocumentType,
+        _actor: &ActorContext,
+        _instance: &DocumentInstance,
+    ) {
+        self.record("after_update");
+    }
+
+    fn after_publish(
+        &self,
+        _document_type: &DocumentType,
+        _actor: &ActorContext,
+        _instance: &DocumentInstance,
+    ) {
+        self.record("after_publish");
+    }
+
+    fn before_delete(
+        &self,
+        _document_type: &DocumentType,
+        _actor: &ActorContext,
+        _id: DocumentInstanceId,
+    ) -> Result<(), HookError> {
+        self.record("before_delete");
+        Err(HookError("partners are never deleted".to_string()))
+    }
+
+    fn after_delete(
+        &self,
+        _document_type: &DocumentType,
+        _actor: &ActorContext,
+        _id: DocumentInstanceId,
+    ) {
+        self.record("after_delete");
+    }
+}
+
+#[tokio::test]
+async fn lifecycle_hooks_are_called_around_writes() -> anyhow::Result<()> {
+    let recording = RecordingHooks::default();
+    let hooks = LifecycleHooksRegistry::new()
+        .register(DocumentTypeId::try_new("partners")?, recording.clone());
+    let (router, _c) = build_router_with_hooks(hooks).await?;
+
+    let loc = create_partner(&router, "8000000000001", "Hooked Ltd").await?;
+    let (_, json) = get_json(&router, &format!("{loc}?status=draft")).await?;
+    assert_eq!(json["data"]["legal_entity"], "HOOKED LTD");
+
+    let (status, _) =
+        put_json(&router, &loc, r#"{"data": {"legal_entity": "Edited Ltd"}}"#).await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    publish_document(&router, &loc).await?;
+
+    // a rejecting hook rolls the delete back
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(&loc)
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, _) = get_json(&router, &format!("{loc}?status=draft")).await?;
+    assert_eq!(status, StatusCode::OK);
+
+    // nor are they for a document which doesn't exist
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/api/documents/partners/{}", uuid::Uuid::new_v4()))
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // hooks of other document types are not called
+    create_brand(&router, "hook-brd", "Not Hooked").await?;
+
+    assert_eq!(
+        *recording.calls.lock().unwrap(),
+        vec![
+            "before_create",
+            "after_create",
+            "after_update",
+            "after_publish",
+            "before_delete"
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn published_documents_are_cached_until_written_by_the_replica() -> anyhow::Result<()> {
+    let (cached, other, _c) = build_replicas_with_document_cache().await?;
+
+    let loc = create_partner(&cached, "8100000000001", "Cached Ltd").await?;
+    publish_document(&cached, &loc).await?;
+    let (_, json) = get_json(&cached, &loc).await?;
+    assert_eq!(json["data"]["legal_entity"], "Cached Ltd");
+
+    // the writes of another replica are only seen once the document expires
+    let (status, _) = put_json(
+        &other,
+        &loc,
+        r#"{"data": {"legal_entity": "Elsewhere Ltd"}}"#,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    publish_document(&other, &loc).await?;
+    let (_, json) = get_json(&cached, &loc).await?;
+    assert_eq!(json["data"]["legal_entity"], "Cached Ltd");
+    // drafts are never cached
+    let (_, json) = get_json(&cached, &format!("{loc}?status=draft")).await?;
+    assert_eq!(json["data"]["legal_entity"], "Elsewhere Ltd");
+
+    // the writes of the replica itself invalidate the document
+    let (status, _) =
+        put_json(&cached, &loc, r#"{"data": {"legal_entity": "Edited Ltd"}}"#).await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    publish_document(&cached, &loc).await?;
+    let (_, json) = get_json(&cached, &loc).await?;
+    assert_eq!(json["data"]["legal_entity"], "Edited Ltd");
+
+    let response = cached
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(&loc)
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let (status, _) = get_json(&cached, &loc).await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests — content change events
+// ---------------------------------------------------------------------------
+
+#[derive(Clone, Default)]
+struct RecordingPublisher {
+    events: std::sync::Arc<std::sync::Mutex<Vec<ContentChangeEvent>>>,
+}
+
+impl EventPublisher for RecordingPublisher {
+    async fn publish(&self, event: &ContentChangeEvent) -> anyhow::Result<()> {
+        self.events.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn content_changes_are_relayed_from_the_outbox_once() -> anyhow::Result<()> {
+    let (router, _state, database, _c) = build_router_with_state().await?;
+
+    let loc = create_brand(&router, "evt-brand", "Evented").await?;
+    let id = loc.trim_start_matches("/api/documents/brands/");
+    let (status, _) = put_json(&router, &loc, r#"{"data": {"name": "Renamed"}}"#).await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    publish_document(&router, &loc).await?;
+
+    // a write which is rolled back records no event
+    let (status, _, _) = post_json(
+        &router,
+        "/api/documents/brands",
+        r#"{"data": {"uid": "evt-brand", "name": "Duplicate"}}"#,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(&loc)
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    // a document deleted already records no second event
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(&loc)
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let publisher = RecordingPublisher::default();
+    assert_eq!(
+        relay_pending_events(&publisher, database, 100).await?,
+        Some(4)
+    );
+    // published events are not published again
+    assert_eq!(
+        relay_pending_events(&publisher, database, 100).await?,
+        Some(0)
+    );
+
+    let events = publisher.events.lock().unwrap().clone();
+    let actions: Vec<ContentAction> = events.iter().map(|event| event.action).collect();
+    assert_eq!(
+        actions,
+        vec![
+            ContentAction::Create,
+            ContentAction::Update,
+            ContentAction::Publish,
+            ContentAction::Delete
+        ]
+    );
+    assert!(events.windows(2).all(|w| w[0].event_id < w[1].event_id));
+    assert!(
+        events
+            .iter()
+            .all(|event| event.document_type == "brands" && event.document_id == id)
+    );
+    assert_eq!(events[0].diff["uid"]["new"], "evt-brand");
+    assert_eq!(
+        events[1].diff,
+        serde_json::json!({"name": {"old": "Evented", "new": "Renamed"}})
+    );
+    assert_eq!(events[2].diff, serde_json::json!({}));
+    assert_eq!(events[3].diff["name"]["old"], "Renamed");
+    assert!(events[3].diff["name"]["new"].is_null());
+    Ok(())
+}
+
+/// Publisher rejecting the events of one document.
+struct RejectingPublisher {
+    document_id: String,
+    inner: RecordingPublisher,
+}
+
+impl EventPublisher for RejectingPublisher {
+    async fn publish(&self, event: &ContentChangeEvent) -> anyhow::Result<()> {
+        anyhow::ensure!(event.document_id != self.document_id, "broker unavailable");
+        self.inner.publish(event).await
+    }
+}
+
+#[tokio::test]
+async fn failed_events_hold_back_only_their_document() -> anyhow::Result<()> {
+    let (router, _state, database, _c) = build_router_with_state().await?;
+
+    let held = create_brand(&router, "evt-held", "Held").await?;
+    let held_id = held
+        .trim_start_matches("/api/documents/brands/")
+        .to_string();
+    create_brand(&router, "evt-other", "Other").await?;
+    let (status, _) = put_json(&router, &held, r#"{"data": {"name": "Renamed"}}"#).await?;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let rejecting = RejectingPublisher {
+        document_id: held_id.clone(),
+        inner: RecordingPublisher::default(),
+    };
+    assert!(
+        relay_pending_events(&rejecting, database, 100)
+            .await
+            .is_err()
+    );
+    let events = rejecting.inner.events.lock().unwrap().clone();
+    assert_eq!(events.len(), 1);
+    assert_ne!(events[0].document_id, held_id);
+
+    // the events of the held document follow, in their order
+    let publisher = RecordingPublisher::default();
+    assert_eq!(
+        relay_pending_events(&publisher, database, 100).await?,
+        Some(2)
+    );
+    let actions: Vec<ContentAction> = publisher
+        .events
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|event| event.action)
+        .collect();
+    assert_eq!(actions, vec![ContentAction::Create, ContentAction::Update]);
+
+    // published events are removed past their retention only
+    assert_eq!(
+        prune_published_events(database, std::time::Duration::from_secs(3600)).await?,
+        0
+    );
+    assert_eq!(
+        prune_published_events(database, std::time::Duration::ZERO).await?,
+        3
+    );
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests — search index
+// ---------------------------------------------------------------------------
+
+/// Search engine recording the ids of the documents indexed and removed.
+#[derive(Default)]
+struct RecordingEngine {
+    upserted: std::sync::Mutex<Vec<(String, String)>>,
+    deleted: std::sync::Mutex<Vec<(String, String)>>,
+}
+
+impl SearchEngine for RecordingEngine {
+    fn configure<'a>(
+        &'a self,
+        _index: &'a str,
+        _settings: &'a IndexSettings,
+    ) -> futures::future::BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn upsert<'a>(
+        &'a self,
+        index: &'a str,
+        documents: Vec<Value>,
+    ) -> futures::future::BoxFuture<'a, anyhow::Result<()>> {
+        let mut upserted = self.upserted.lock().unwrap();
+        for document in documents {
+            let uid = document["uid"].as_str().unwrap_or_default().to_string();
+            upserted.push((index.to_string(), uid));
+        }
+        Box::pin(async { Ok(()) })
+    }
+
+    fn delete<'a>(
+        &'a self,
+        index: &'a str,
+        ids: Vec<String>,
+    ) -> futures::future::BoxFuture<'a, anyhow::Result<()>> {
+        let mut deleted = self.deleted.lock().unwrap();
+        deleted.extend(ids.into_iter().map(|id| (index.to_string(), id)));
+        Box::pin(async { Ok(()) })
+    }
+
+    fn clear<'a>(&'a self, _index: &'a str) -> futures::future::BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[tokio::test]
+async fn published_documents_are_synced_to_the_search_index() -> anyhow::Result<()> {
+    let (router, state, database, _c) = build_router_with_state().await?;
+    let engine = std::sync::Arc::new(RecordingEngine::default());
+    let index = SearchIndex::with_engine(engine.clone(), &SearchIndexSettings::default(), state);
+
+    let published = create_brand(&router, "idx-pub", "Indexed").await?;
+    publish_document(&router, &published).await?;
+    let draft = create_brand(&router, "idx-draft", "Draft").await?;
+    let draft_id = draft.trim_start_matches("/api/documents/brands/");
+
+    let publisher = RecordingPublisher::default();
+    assert_eq!(
+        relay_pending_events(&index.indexing(publisher), database, 100).await?,
+        Some(3)
+    );
+    // the published version is indexed, documents without one are removed
+    let upserted = engine.upserted.lock().unwrap().clone();
+    assert!(!upserted.is_empty());
+    assert!(
+        upserted
+            .iter()
+            .all(|(index, uid)| index == "luminair-brands" && uid == "idx-pub")
+    );
+    assert_eq!(
+        engine.deleted.lock().unwrap().clone(),
+        vec![("luminair-brands".to_string(), draft_id.to_string())]
+    );
+
+    engine.upserted.lock().unwrap().clear();
+    assert_eq!(index.reindex().await?, 1);
+    assert_eq!(engine.upserted.lock().unwrap().len(), 1);
+    Ok(())
+}
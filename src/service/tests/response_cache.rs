@@ -0,0 +1,74 @@
+mod common;
+
+use common::*;
+
+/// Publisher of the relay which drops the events, for the tests of the
+/// invalidation only.
+struct DiscardingPublisher;
+
+impl EventPublisher for DiscardingPublisher {
+    async fn publish(&self, _event: &ContentChangeEvent) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// GET `uri`; returns the `X-Cache` header of the response and its JSON body.
+async fn get_cached(router: &TestRouter, uri: &str) -> anyhow::Result<(String, Value)> {
+    let response = router
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty())?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let cache_status = response
+        .headers()
+        .get(CACHE_STATUS_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let bytes = axum::body::to_bytes(response.into_body(), 1 << 20).await?;
+    Ok((cache_status, serde_json::from_slice(&bytes)?))
+}
+
+// ---------------------------------------------------------------------------
+// Tests — response cache
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn cached_responses_are_invalidated_by_relayed_events() -> anyhow::Result<()> {
+    let (_, state, database, _c) = build_router_with_state().await?;
+    let (cache, _r) = start_response_cache().await?;
+    let router = Router::new()
+        .nest("/api", cache.cache_responses(api_routes()))
+        .with_state(state);
+    let publisher = cache.invalidating(DiscardingPublisher);
+
+    create_brand(&router, "cch-aaa", "Cached").await?;
+    relay_pending_events(&publisher, database, 100).await?;
+
+    let (cache_status, json) = get_cached(&router, "/api/documents/brands?status=draft").await?;
+    assert_eq!(cache_status, "MISS");
+    assert_eq!(json["meta"]["total"], 1);
+    let (cache_status, json) = get_cached(&router, "/api/documents/brands?status=draft").await?;
+    assert_eq!(cache_status, "HIT");
+    assert_eq!(json["meta"]["total"], 1);
+
+    // another query string is another response
+    let (cache_status, _) = get_cached(&router, "/api/documents/brands?status=published").await?;
+    assert_eq!(cache_status, "MISS");
+    get_cached(&router, "/api/documents/partners").await?;
+
+    // the responses are stale until the event of the write is relayed
+    create_brand(&router, "cch-bbb", "Cached too").await?;
+    let (cache_status, json) = get_cached(&router, "/api/documents/brands?status=draft").await?;
+    assert_eq!(cache_status, "HIT");
+    assert_eq!(json["meta"]["total"], 1);
+
+    relay_pending_events(&publisher, database, 100).await?;
+    let (cache_status, json) = get_cached(&router, "/api/documents/brands?status=draft").await?;
+    assert_eq!(cache_status, "MISS");
+    assert_eq!(json["meta"]["total"], 2);
+    // partners relate to brands, so their responses are invalidated as well
+    let (cache_status, _) = get_cached(&router, "/api/documents/partners").await?;
+    assert_eq!(cache_status, "MISS");
+    Ok(())
+}
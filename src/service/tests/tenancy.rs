@@ -0,0 +1,97 @@
+mod common;
+
+use common::*;
+
+/// Send `method` `uri` with `headers`; returns the status and JSON body.
+async fn tenant_request(
+    router: &TestRouter,
+    method: &str,
+    uri: &str,
+    headers: &[(&str, &str)],
+    body: Option<Value>,
+) -> anyhow::Result<(StatusCode, Value)> {
+    let mut request = Request::builder().method(method).uri(uri);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let body = match body {
+        Some(body) => {
+            request = request.header("content-type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = router.clone().oneshot(request.body(body)?).await?;
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), 1 << 20).await?;
+    let json = if bytes.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&bytes)?
+    };
+    Ok((status, json))
+}
+
+// ---------------------------------------------------------------------------
+// Tests — multi-tenancy
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn tenants_see_only_their_own_documents() -> anyhow::Result<()> {
+    let (router, _c) = build_router_with_tenants(&["acme", "globex"]).await?;
+
+    let brand = serde_json::json!({ "data": { "uid": "b-1", "name": "Acme brand" } });
+    let (status, _) = tenant_request(
+        &router,
+        "POST",
+        "/api/documents/brands",
+        &[("x-tenant-id", "acme")],
+        Some(brand),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, json) = tenant_request(
+        &router,
+        "GET",
+        "/api/documents/brands?status=draft",
+        &[("x-tenant-id", "acme")],
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["meta"]["total"], 1);
+
+    // by subdomain rather than by header
+    let (status, json) = tenant_request(
+        &router,
+        "GET",
+        "/api/documents/brands?status=draft",
+        &[("host", "globex.cms.example.com")],
+        None,
+    )
+    .await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["meta"]["total"], 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn requests_of_no_or_an_unknown_tenant_are_not_found() -> anyhow::Result<()> {
+    let (router, _c) = build_router_with_tenants(&["acme"]).await?;
+
+    for headers in [
+        &[][..],
+        &[("x-tenant-id", "initech")][..],
+        &[("host", "initech.cms.example.com")][..],
+    ] {
+        let (status, json) =
+            tenant_request(&router, "GET", "/api/documents/brands", headers, None).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(json["code"], "NOT_FOUND");
+    }
+
+    let (status, _) = tenant_request(&router, "GET", "/health", &[], None).await?;
+    assert_eq!(status, StatusCode::OK);
+    Ok(())
+}